@@ -14,6 +14,7 @@ use futures::StreamExt;
 use tokio::sync::Mutex;
 use uuid::Uuid;
 
+use crate::agent::approval_policy::SharedApprovalPolicies;
 use crate::agent::compaction::ContextCompactor;
 use crate::agent::context_monitor::ContextMonitor;
 use crate::agent::router::Router;
@@ -28,7 +29,7 @@ use crate::config::AgentConfig;
 use crate::error::Error;
 use crate::extensions::ExtensionManager;
 use crate::llm::{ChatMessage, LlmProvider};
-use crate::safety::SafetyLayer;
+use crate::safety::{PolicyAction, SafetyLayer};
 use crate::store::Database;
 use crate::tools::registry::ToolRegistry;
 use crate::workspace::Workspace;
@@ -62,6 +63,8 @@ pub fn truncate_for_preview(output: &str, max_chars: usize) -> String {
 pub struct AgentDeps {
     pub store: Option<Arc<dyn Database>>,
     pub llm: Arc<dyn LlmProvider>,
+    /// Model for context compaction summaries; falls back to `llm`.
+    pub compaction_llm: Option<Arc<dyn LlmProvider>>,
     pub safety: Arc<SafetyLayer>,
     pub tools: Arc<ToolRegistry>,
     pub workspace: Option<Arc<Workspace>>,
@@ -69,6 +72,8 @@ pub struct AgentDeps {
     pub reply_drafter: Option<Arc<ReplyDrafter>>,
     pub card_queue: Option<Arc<CardQueue>>,
     pub routine_engine: Option<Arc<crate::agent::routine_engine::RoutineEngine>>,
+    /// Persistent per-tool approval rules (shared with the REST API).
+    pub approval_policies: SharedApprovalPolicies,
 }
 
 /// The main agent that coordinates all components.
@@ -86,18 +91,19 @@ impl Agent {
     pub fn new(
         config: AgentConfig,
         deps: AgentDeps,
-        channels: ChannelManager,
+        channels: Arc<ChannelManager>,
         session_manager: Option<Arc<SessionManager>>,
     ) -> Self {
         let session_manager = session_manager.unwrap_or_else(|| Arc::new(SessionManager::new()));
+        let context_monitor = ContextMonitor::new().with_limit(config.max_context_tokens);
 
         Self {
             config,
             deps,
-            channels: Arc::new(channels),
+            channels,
             router: Router::new(),
             session_manager,
-            context_monitor: ContextMonitor::new(),
+            context_monitor,
         }
     }
 
@@ -111,6 +117,10 @@ impl Agent {
         &self.deps.llm
     }
 
+    pub(crate) fn compaction_llm(&self) -> &Arc<dyn LlmProvider> {
+        self.deps.compaction_llm.as_ref().unwrap_or(&self.deps.llm)
+    }
+
     pub(crate) fn safety(&self) -> &Arc<SafetyLayer> {
         &self.deps.safety
     }
@@ -119,6 +129,10 @@ impl Agent {
         &self.deps.tools
     }
 
+    pub(crate) fn approval_policies(&self) -> &SharedApprovalPolicies {
+        &self.deps.approval_policies
+    }
+
     pub(crate) fn workspace(&self) -> Option<&Arc<Workspace>> {
         self.deps.workspace.as_ref()
     }
@@ -238,6 +252,7 @@ impl Agent {
         );
 
         // Process based on submission type
+        let persist_session = Arc::clone(&session);
         let result = match submission {
             Submission::UserInput { content } => {
                 self.process_user_input(message, session, thread_id, &content)
@@ -283,6 +298,11 @@ impl Agent {
             }
         };
 
+        // Write the thread through so a restart resumes it (pending approval, undo)
+        self.session_manager
+            .persist_thread(&persist_session, thread_id)
+            .await;
+
         // Convert SubmissionResult to response string
         match result? {
             SubmissionResult::Response { content } => Ok(Some(content)),
@@ -339,6 +359,16 @@ impl Agent {
             }
         }
 
+        // Prefer the persisted thread: it keeps state, pending approval and undo.
+        if self
+            .session_manager
+            .restore_thread(&message.user_id, &message.channel, Some(external_thread_id))
+            .await
+            .is_some()
+        {
+            return;
+        }
+
         // Load history from DB (may be empty for a newly created thread).
         let mut chat_messages: Vec<ChatMessage> = Vec::new();
         let msg_count;
@@ -467,11 +497,23 @@ impl Agent {
         }
 
         let violations = self.safety().check_policy(content);
-        if violations
-            .iter()
-            .any(|rule| rule.action == crate::safety::PolicyAction::Block)
-        {
-            return Ok(SubmissionResult::error("Input rejected by safety policy."));
+        if !violations.is_empty() {
+            let blocked = violations
+                .iter()
+                .any(|rule| rule.action == PolicyAction::Block);
+            let reasons = violations
+                .iter()
+                .map(|rule| format!("Policy '{}': {}", rule.name, rule.reason))
+                .collect();
+            let action = if blocked {
+                PolicyAction::Block
+            } else {
+                PolicyAction::Warn
+            };
+            self.report_safety_event(message, None, action, reasons).await;
+            if blocked {
+                return Ok(SubmissionResult::error("Input rejected by safety policy."));
+            }
         }
 
         // Fire-and-forget card generation (non-blocking)
@@ -487,6 +529,10 @@ impl Agent {
                 .unwrap_or_else(|| message.user_id.clone());
             let chat_id = thread_id.to_string();
             let channel = message.channel.clone();
+            // Where an approved reply goes: the channel's own routing (e.g.
+            // Telegram chat/message IDs) plus the user to send it to.
+            let mut reply_metadata = message.metadata.as_object().cloned().unwrap_or_default();
+            reply_metadata.insert("user_id".into(), message.user_id.clone().into());
             let expire = drafter.expire_minutes();
             tokio::spawn(async move {
                 match drafter.draft(&msg_content, &sender, &chat_id).await {
@@ -501,7 +547,7 @@ impl Agent {
                                 conversation_id: chat_id,
                                 thread: Vec::new(),
                                 email_thread: Vec::new(),
-                                reply_metadata: None,
+                                reply_metadata: Some(reply_metadata.into()),
                                 message_id: None,
                             },
                             CardSilo::Messages,
@@ -541,7 +587,7 @@ impl Agent {
                     )
                     .await;
 
-                let compactor = ContextCompactor::new(self.llm().clone());
+                let compactor = ContextCompactor::new(self.compaction_llm().clone());
                 if let Err(e) = compactor
                     .compact(thread, strategy, self.workspace().map(|w| w.as_ref()))
                     .await
@@ -634,6 +680,30 @@ impl Agent {
         .await
     }
 
+    /// Surface a safety warning or block on the originating channel.
+    ///
+    /// For todo agents this lands in the activity stream.
+    pub(crate) async fn report_safety_event(
+        &self,
+        message: &IncomingMessage,
+        tool_name: Option<&str>,
+        action: PolicyAction,
+        reasons: Vec<String>,
+    ) {
+        let _ = self
+            .channels
+            .send_status(
+                &message.channel,
+                StatusUpdate::SafetyEvent {
+                    tool_name: tool_name.map(String::from),
+                    action,
+                    reasons,
+                },
+                &message.metadata,
+            )
+            .await;
+    }
+
     // ── Persistence helpers ─────────────────────────────────────────
 
     /// Fire-and-forget: persist a turn (user message + optional assistant response) to the DB.
@@ -894,4 +964,203 @@ mod tests {
             assert!(!should_nudge, "Should NOT nudge after 3 iterations");
         }
     }
+
+    // ── Restart persistence ─────────────────────────────────────────
+
+    mod restart {
+        use std::collections::VecDeque;
+        use std::sync::Arc;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::time::Duration;
+
+        use async_trait::async_trait;
+        use rust_decimal::Decimal;
+        use tokio::sync::{Mutex, RwLock};
+
+        use crate::agent::approval_policy::ApprovalPolicies;
+        use crate::agent::session::ThreadState;
+        use crate::agent::session_manager::SessionManager;
+        use crate::agent::{Agent, AgentDeps};
+        use crate::channels::{ChannelManager, IncomingMessage};
+        use crate::config::AgentConfig;
+        use crate::context::JobContext;
+        use crate::error::LlmError;
+        use crate::llm::provider::{
+            CompletionRequest, CompletionResponse, FinishReason, LlmProvider, ToolCall,
+            ToolCompletionRequest, ToolCompletionResponse,
+        };
+        use crate::safety::SafetyLayer;
+        use crate::store::{Database, LibSqlBackend};
+        use crate::tools::registry::ToolRegistry;
+        use crate::tools::tool::{Tool, ToolError, ToolOutput};
+
+        /// Replays canned tool-completion responses in order.
+        struct ScriptedLlm(Mutex<VecDeque<ToolCompletionResponse>>);
+
+        impl ScriptedLlm {
+            fn new(responses: Vec<ToolCompletionResponse>) -> Self {
+                Self(Mutex::new(responses.into()))
+            }
+        }
+
+        fn response(content: Option<&str>, tool_calls: Vec<ToolCall>) -> ToolCompletionResponse {
+            ToolCompletionResponse {
+                content: content.map(String::from),
+                finish_reason: if tool_calls.is_empty() {
+                    FinishReason::Stop
+                } else {
+                    FinishReason::ToolUse
+                },
+                tool_calls,
+                input_tokens: 10,
+                output_tokens: 5,
+                cache_read_tokens: 0,
+                cache_write_tokens: 0,
+                response_id: None,
+                provider: "mock".into(),
+                model: "scripted".into(),
+            }
+        }
+
+        #[async_trait]
+        impl LlmProvider for ScriptedLlm {
+            fn model_name(&self) -> &str {
+                "scripted"
+            }
+
+            fn cost_per_token(&self) -> (Decimal, Decimal) {
+                (Decimal::ZERO, Decimal::ZERO)
+            }
+
+            async fn complete(
+                &self,
+                _request: CompletionRequest,
+            ) -> Result<CompletionResponse, LlmError> {
+                unimplemented!()
+            }
+
+            async fn complete_with_tools(
+                &self,
+                _request: ToolCompletionRequest,
+            ) -> Result<ToolCompletionResponse, LlmError> {
+                self.0.lock().await.pop_front().ok_or(LlmError::RequestFailed {
+                    provider: "scripted".into(),
+                    reason: "script exhausted".into(),
+                })
+            }
+        }
+
+        /// A tool that needs approval and counts its runs.
+        struct StampTool(Arc<AtomicUsize>);
+
+        #[async_trait]
+        impl Tool for StampTool {
+            fn name(&self) -> &str {
+                "stamp"
+            }
+            fn description(&self) -> &str {
+                "Stamp a file"
+            }
+            fn parameters_schema(&self) -> serde_json::Value {
+                serde_json::json!({"type": "object", "properties": {"path": {"type": "string"}}})
+            }
+            async fn execute(
+                &self,
+                _params: serde_json::Value,
+                _ctx: &JobContext,
+            ) -> Result<ToolOutput, ToolError> {
+                self.0.fetch_add(1, Ordering::Relaxed);
+                Ok(ToolOutput::text("stamped", Duration::from_millis(1)))
+            }
+            fn requires_approval(&self) -> bool {
+                true
+            }
+        }
+
+        /// A freshly started agent over `db`, as after a restart.
+        async fn start_agent(
+            db: &Arc<dyn Database>,
+            llm: ScriptedLlm,
+            runs: &Arc<AtomicUsize>,
+        ) -> (Agent, Arc<SessionManager>) {
+            let tools = Arc::new(ToolRegistry::new());
+            tools.register(Arc::new(StampTool(Arc::clone(runs)))).await;
+            let deps = AgentDeps {
+                store: Some(Arc::clone(db)),
+                llm: Arc::new(llm),
+                compaction_llm: None,
+                safety: Arc::new(SafetyLayer::new()),
+                tools,
+                workspace: None,
+                extension_manager: None,
+                reply_drafter: None,
+                card_queue: None,
+                routine_engine: None,
+                approval_policies: Arc::new(RwLock::new(ApprovalPolicies::empty())),
+            };
+            let sessions = Arc::new(SessionManager::new().with_store(Arc::clone(db)));
+            let agent = Agent::new(
+                AgentConfig::default(),
+                deps,
+                Arc::new(ChannelManager::new()),
+                Some(Arc::clone(&sessions)),
+            );
+            (agent, sessions)
+        }
+
+        #[tokio::test]
+        async fn approval_survives_restart() {
+            let db: Arc<dyn Database> = Arc::new(LibSqlBackend::new_memory().await.unwrap());
+            let runs = Arc::new(AtomicUsize::new(0));
+
+            // Before the restart: the model asks for an approval-gated tool
+            let (agent, sessions) = start_agent(
+                &db,
+                ScriptedLlm::new(vec![response(
+                    None,
+                    vec![ToolCall {
+                        id: "call_1".into(),
+                        name: "stamp".into(),
+                        arguments: serde_json::json!({"path": "notes.txt"}),
+                    }],
+                )]),
+                &runs,
+            )
+            .await;
+            let reply = agent
+                .handle_message(&IncomingMessage::new("telegram", "user-1", "stamp notes.txt"))
+                .await
+                .unwrap();
+            assert_eq!(reply.as_deref(), Some(""), "approval prompt goes out via status");
+            let (session, thread_id) = sessions.resolve_thread("user-1", "telegram", None).await;
+            assert_eq!(
+                session.lock().await.threads[&thread_id].state,
+                ThreadState::AwaitingApproval
+            );
+            drop((agent, sessions, session));
+
+            // After the restart: approving resumes the same turn
+            let (agent, sessions) = start_agent(
+                &db,
+                ScriptedLlm::new(vec![response(Some("Stamped notes.txt."), vec![])]),
+                &runs,
+            )
+            .await;
+            let reply = agent
+                .handle_message(&IncomingMessage::new("telegram", "user-1", "yes"))
+                .await
+                .unwrap();
+            assert_eq!(reply.as_deref(), Some("Stamped notes.txt."));
+            assert_eq!(runs.load(Ordering::Relaxed), 1);
+
+            let (session, resolved) = sessions.resolve_thread("user-1", "telegram", None).await;
+            assert_eq!(resolved, thread_id);
+            let sess = session.lock().await;
+            let thread = &sess.threads[&thread_id];
+            assert_eq!(thread.state, ThreadState::Idle);
+            assert!(thread.pending_approval.is_none());
+            assert_eq!(thread.turns.len(), 1);
+            assert_eq!(thread.turns[0].response.as_deref(), Some("Stamped notes.txt."));
+        }
+    }
 }
@@ -6,6 +6,9 @@
 //!
 //! The DB stores `AgentQueued` status for persistence/crash recovery,
 //! but the hot path uses the in-memory channel.
+//!
+//! Todos whose spend budget is at its hard limit are parked as
+//! `BudgetPaused` instead of dispatched, and requeued by `resume_paused()`.
 
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -50,6 +53,18 @@ impl AgentQueue {
         let queue_clone = Arc::clone(&queue);
         tokio::spawn(Self::dispatch_loop(queue_clone, rx));
 
+        // Requeue paused todos as soon as a budget is raised
+        if let Some(budgets) = &queue.deps.budgets {
+            let mut changes = budgets.changes();
+            let weak = Arc::downgrade(&queue);
+            tokio::spawn(async move {
+                while changes.changed().await.is_ok() {
+                    let Some(queue) = weak.upgrade() else { break };
+                    queue.resume_paused().await;
+                }
+            });
+        }
+
         queue
     }
 
@@ -172,6 +187,43 @@ impl AgentQueue {
         }
     }
 
+    /// Requeue `BudgetPaused` todos whose budget no longer holds them —
+    /// the limit was raised or the period rolled over.
+    pub async fn resume_paused(&self) {
+        let Ok(paused) = self.deps.db.list_todos_by_status("default", TodoStatus::BudgetPaused).await else {
+            return;
+        };
+        for todo in paused {
+            if self.budget_paused(todo.id).await {
+                continue;
+            }
+            info!(todo_id = %todo.id, "Budget no longer paused, requeuing todo");
+            if let Err(e) = self.enqueue(todo.id).await {
+                warn!(todo_id = %todo.id, error = %e, "Failed to requeue paused todo");
+            }
+        }
+    }
+
+    /// Whether a hard budget limit holds this todo's agent.
+    async fn budget_paused(&self, todo_id: Uuid) -> bool {
+        match &self.deps.budgets {
+            Some(budgets) => budgets.todo_paused(todo_id).await,
+            None => false,
+        }
+    }
+
+    /// Park a todo until its budget allows more work.
+    async fn park(&self, todo_id: Uuid) {
+        if let Err(e) = self.deps.db.update_todo_status(todo_id, TodoStatus::BudgetPaused).await {
+            warn!(todo_id = %todo_id, error = %e, "Failed to set BudgetPaused");
+            return;
+        }
+        if let Ok(Some(updated)) = self.deps.db.get_todo(todo_id).await {
+            let _ = self.deps.todo_tx.send(TodoWsMessage::TodoUpdated { todo: updated });
+        }
+        info!(todo_id = %todo_id, "Budget exhausted, todo paused");
+    }
+
     /// Broadcast current agent status to iOS.
     fn broadcast_status(&self) {
         let _ = self.deps.todo_tx.send(TodoWsMessage::AgentStatus {
@@ -185,6 +237,15 @@ impl AgentQueue {
         info!("Agent dispatch loop started");
 
         while let Some(todo_id) = rx.recv().await {
+            // Paused work doesn't take a slot
+            if queue.budget_paused(todo_id).await {
+                match queue.deps.db.get_todo(todo_id).await {
+                    Ok(Some(t)) if t.status == TodoStatus::AgentQueued => queue.park(todo_id).await,
+                    _ => debug!(todo_id = %todo_id, "Paused todo no longer AgentQueued, skipping"),
+                }
+                continue;
+            }
+
             // Acquire a permit (blocks until a slot is free)
             let permit = match queue.semaphore.clone().acquire_owned().await {
                 Ok(p) => p,
@@ -263,6 +324,110 @@ impl AgentQueue {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use async_trait::async_trait;
+    use rust_decimal::Decimal;
+    use rust_decimal_macros::dec;
+    use tokio::sync::{RwLock, broadcast};
+
+    use crate::agent::approval_policy::ApprovalPolicies;
+    use crate::cards::queue::CardQueue;
+    use crate::error::LlmError;
+    use crate::llm::budget::{Budget, BudgetGuard, BudgetPeriod, BudgetScope};
+    use crate::llm::{
+        CompletionRequest, CompletionResponse, LlmProvider, ToolCompletionRequest, ToolCompletionResponse,
+    };
+    use crate::safety::SafetyLayer;
+    use crate::store::traits::LlmCallRecord;
+    use crate::store::{Database, LibSqlBackend};
+    use crate::todos::approval_registry::TodoApprovalRegistry;
+    use crate::todos::model::{TodoItem, TodoType};
+    use crate::tools::registry::ToolRegistry;
+    use crate::workspace::Workspace;
+
+    struct NoLlm;
+
+    #[async_trait]
+    impl LlmProvider for NoLlm {
+        fn model_name(&self) -> &str {
+            "none"
+        }
+        fn cost_per_token(&self) -> (Decimal, Decimal) {
+            (Decimal::ZERO, Decimal::ZERO)
+        }
+        async fn complete(&self, _req: CompletionRequest) -> Result<CompletionResponse, LlmError> {
+            unimplemented!("no agent is spawned in these tests")
+        }
+        async fn complete_with_tools(
+            &self,
+            _req: ToolCompletionRequest,
+        ) -> Result<ToolCompletionResponse, LlmError> {
+            unimplemented!("no agent is spawned in these tests")
+        }
+    }
+
+    fn deps(db: &Arc<dyn Database>, budgets: &Arc<BudgetGuard>, workspace: &std::path::Path) -> TodoAgentDeps {
+        TodoAgentDeps {
+            db: Arc::clone(db),
+            llm: Arc::new(NoLlm),
+            compaction_llm: Arc::new(NoLlm),
+            safety: Arc::new(SafetyLayer::new()),
+            tools: Arc::new(ToolRegistry::new()),
+            workspace: Arc::new(Workspace::new(workspace.to_path_buf())),
+            activity_tx: broadcast::channel(16).0,
+            todo_tx: broadcast::channel(16).0,
+            card_queue: CardQueue::new(),
+            approval_registry: TodoApprovalRegistry::new(),
+            approval_policies: Arc::new(RwLock::new(ApprovalPolicies::empty())),
+            budgets: Some(Arc::clone(budgets)),
+        }
+    }
+
+    async fn wait_for_status(db: &Arc<dyn Database>, todo_id: Uuid, status: TodoStatus) -> bool {
+        for _ in 0..100 {
+            if db.get_todo(todo_id).await.unwrap().is_some_and(|t| t.status == status) {
+                return true;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        false
+    }
+
+    #[tokio::test]
+    async fn budget_pause_then_raise_requeues_todo() {
+        let db: Arc<dyn Database> = Arc::new(LibSqlBackend::new_memory().await.unwrap());
+        let cards = CardQueue::new();
+        let budget = Budget::new(BudgetScope::Global, None, BudgetPeriod::Daily, None, Some(dec!(1)));
+        db.create_budget(&budget).await.unwrap();
+        db.record_llm_call(&LlmCallRecord {
+            conversation_id: None,
+            routine_run_id: None,
+            todo_id: None,
+            provider: "m",
+            model: "m",
+            input_tokens: 1,
+            output_tokens: 1,
+            cost: dec!(1.2),
+            purpose: None,
+            role: Some("agent"),
+            price_source: None,
+        })
+        .await
+        .unwrap();
+        let guard = Arc::new(BudgetGuard::load(Arc::clone(&db), Some(Arc::clone(&cards))).await.unwrap());
+        let workspace = tempfile::tempdir().unwrap();
+        // No slots: a dispatched todo waits for one instead of spawning an agent
+        let queue = AgentQueue::new(0, deps(&db, &guard, workspace.path()));
+        let todo = TodoItem::new("default", "Compare flights", TodoType::Research, TodoBucket::AgentStartable);
+        db.create_todo(&todo).await.unwrap();
+
+        queue.enqueue(todo.id).await.unwrap();
+        assert!(wait_for_status(&db, todo.id, TodoStatus::BudgetPaused).await);
+
+        // Approving the raise card lifts the limit and requeues the todo
+        let card = cards.pending().await.remove(0);
+        assert!(guard.resolve(card.id, true).await.unwrap());
+        assert!(wait_for_status(&db, todo.id, TodoStatus::AgentQueued).await);
+    }
 
     #[test]
     fn semaphore_raii_releases_on_drop() {
@@ -5,12 +5,13 @@ use std::sync::Arc;
 use tokio::sync::Mutex;
 use uuid::Uuid;
 
+use crate::agent::approval_policy::{reload_approval_policies, similar_rule};
 use crate::agent::session::{Session, ThreadState};
 use crate::agent::submission::SubmissionResult;
-use crate::agent::tool_executor::AgenticLoopResult;
+use crate::agent::tool_executor::{AgenticLoopResult, chat_job_context};
 use crate::channels::{IncomingMessage, StatusUpdate};
-use crate::context::JobContext;
-use crate::error::Error;
+use crate::error::{Error, LlmError};
+use crate::safety::PolicyAction;
 
 use super::agent_loop::Agent;
 
@@ -71,6 +72,20 @@ impl Agent {
                 })
             }
             Err(e) => {
+                // Only background agents hit a hard budget; their channel
+                // parks the work instead of failing it.
+                if let Error::Llm(LlmError::BudgetExceeded { reason }) = &e {
+                    let _ = self
+                        .channels
+                        .send_status(
+                            channel,
+                            StatusUpdate::BudgetPaused {
+                                reason: reason.clone(),
+                            },
+                            metadata,
+                        )
+                        .await;
+                }
                 thread.fail_turn(e.to_string());
 
                 // Persist the user message even on failure
@@ -83,6 +98,35 @@ impl Agent {
         }
     }
 
+    /// Persist an "always allow similar" rule for an approved tool call.
+    ///
+    /// Without a store (or if the write fails) the whole tool is auto-approved
+    /// for this session only.
+    async fn remember_approval(
+        &self,
+        session: &Arc<Mutex<Session>>,
+        tool_name: &str,
+        parameters: &serde_json::Value,
+    ) {
+        if let Some(db) = self.store() {
+            let rule = similar_rule(tool_name, parameters);
+            match db.create_approval_rule(&rule).await {
+                Ok(()) => {
+                    if let Err(e) = reload_approval_policies(db.as_ref(), self.approval_policies()).await {
+                        tracing::warn!(error = %e, "Failed to reload approval policies");
+                    }
+                    tracing::info!(rule = %rule.name, "Saved approval rule");
+                    return;
+                }
+                Err(e) => tracing::warn!(error = %e, "Failed to save approval rule"),
+            }
+        }
+
+        let mut sess = session.lock().await;
+        sess.auto_approve_tool(tool_name);
+        tracing::info!("Auto-approved tool '{}' for session {}", tool_name, sess.id);
+    }
+
     /// Process an approval or rejection of a pending tool execution.
     pub(crate) async fn process_approval(
         &self,
@@ -129,15 +173,10 @@ impl Agent {
         }
 
         if approved {
-            // If always, add to auto-approved set
+            // If always, remember a rule for similar calls
             if always {
-                let mut sess = session.lock().await;
-                sess.auto_approve_tool(&pending.tool_name);
-                tracing::info!(
-                    "Auto-approved tool '{}' for session {}",
-                    pending.tool_name,
-                    sess.id
-                );
+                self.remember_approval(&session, &pending.tool_name, &pending.parameters)
+                    .await;
             }
 
             // Reset thread state to processing
@@ -149,8 +188,7 @@ impl Agent {
             }
 
             // Execute the approved tool and continue the loop
-            let job_ctx =
-                JobContext::with_user(&message.user_id, "chat", "Interactive chat session");
+            let job_ctx = chat_job_context(message);
 
             let _ = self
                 .channels
@@ -188,7 +226,7 @@ impl Agent {
                         &message.channel,
                         StatusUpdate::ToolResult {
                             name: pending.tool_name.clone(),
-                            preview: output.clone(),
+                            preview: self.safety().scrub_secrets(output),
                         },
                         &message.metadata,
                     )
@@ -221,6 +259,15 @@ impl Agent {
                     let sanitized = self
                         .safety()
                         .sanitize_tool_output(&pending.tool_name, &output);
+                    if sanitized.action() != PolicyAction::Allow {
+                        self.report_safety_event(
+                            message,
+                            Some(&pending.tool_name),
+                            sanitized.action(),
+                            sanitized.warnings.clone(),
+                        )
+                        .await;
+                    }
                     self.safety().wrap_for_llm(
                         &pending.tool_name,
                         &sanitized.content,
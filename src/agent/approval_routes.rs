@@ -0,0 +1,280 @@
+//! REST API routes for persistent tool approval rules.
+//!
+//! Endpoints:
+//! - `GET    /api/approval-rules`        — list rules
+//! - `POST   /api/approval-rules`        — create a rule
+//! - `GET    /api/approval-rules/:id`    — get single rule
+//! - `PUT    /api/approval-rules/:id`    — update a rule
+//! - `DELETE /api/approval-rules/:id`    — delete a rule
+//! - `POST   /api/approval-rules/check`  — which rule would decide a tool call
+//!
+//! Every successful mutation reloads the policies into running agents.
+
+use std::sync::Arc;
+
+use axum::{
+    Json, Router,
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+};
+use serde::Deserialize;
+use tracing::warn;
+use uuid::Uuid;
+
+use super::approval_policy::{
+    ApprovalDecision, ApprovalRule, SharedApprovalPolicies, reload_approval_policies,
+};
+use crate::pipeline::rules::PatternKind;
+use crate::store::Database;
+
+/// Shared state for approval rule routes.
+#[derive(Clone)]
+pub struct ApprovalRulesState {
+    pub db: Arc<dyn Database>,
+    /// The policies consulted by running agents.
+    pub policies: SharedApprovalPolicies,
+}
+
+/// Request body for creating a rule.
+#[derive(Debug, Deserialize)]
+pub struct CreateApprovalRuleRequest {
+    pub name: String,
+    pub tool: String,
+    #[serde(default)]
+    pub argument: Option<String>,
+    #[serde(default = "default_kind")]
+    pub kind: PatternKind,
+    #[serde(default)]
+    pub pattern: String,
+    #[serde(default)]
+    pub negate: bool,
+    pub decision: ApprovalDecision,
+    #[serde(default)]
+    pub note: String,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_kind() -> PatternKind {
+    PatternKind::Glob
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl CreateApprovalRuleRequest {
+    fn into_rule(self) -> ApprovalRule {
+        let argument = self.argument.filter(|a| !a.is_empty());
+        let mut rule = ApprovalRule::new(
+            self.name,
+            self.tool,
+            argument,
+            self.kind,
+            self.pattern,
+            self.decision,
+        );
+        rule.negate = self.negate;
+        rule.note = self.note;
+        rule.enabled = self.enabled;
+        rule
+    }
+}
+
+/// Request body for updating a rule. An empty `argument` clears it (the rule
+/// then matches every invocation of the tool).
+#[derive(Debug, Deserialize)]
+pub struct UpdateApprovalRuleRequest {
+    pub name: Option<String>,
+    pub tool: Option<String>,
+    pub argument: Option<String>,
+    pub kind: Option<PatternKind>,
+    pub pattern: Option<String>,
+    pub negate: Option<bool>,
+    pub decision: Option<ApprovalDecision>,
+    pub note: Option<String>,
+    pub enabled: Option<bool>,
+}
+
+/// Request body for a policy check.
+#[derive(Debug, Deserialize)]
+pub struct CheckRequest {
+    pub tool: String,
+    #[serde(default)]
+    pub params: serde_json::Value,
+}
+
+/// Build the Axum router for `/api/approval-rules`.
+pub fn approval_rule_routes(state: ApprovalRulesState) -> Router {
+    Router::new()
+        .route("/api/approval-rules", get(list_rules).post(create_rule))
+        .route("/api/approval-rules/check", post(check))
+        .route(
+            "/api/approval-rules/{id}",
+            get(get_rule).put(update_rule).delete(delete_rule),
+        )
+        .with_state(state)
+}
+
+fn error_response(status: StatusCode, message: impl Into<String>) -> Response {
+    (status, Json(serde_json::json!({"error": message.into()}))).into_response()
+}
+
+/// Reload the shared policies; failures are logged — the DB write already succeeded.
+async fn reload(state: &ApprovalRulesState) {
+    if let Err(e) = reload_approval_policies(state.db.as_ref(), &state.policies).await {
+        warn!(error = %e, "Failed to reload approval policies");
+    }
+}
+
+/// GET /api/approval-rules
+async fn list_rules(State(state): State<ApprovalRulesState>) -> Response {
+    match state.db.list_approval_rules().await {
+        Ok(rules) => Json(serde_json::json!({"rules": rules})).into_response(),
+        Err(e) => error_response(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+/// GET /api/approval-rules/:id
+async fn get_rule(State(state): State<ApprovalRulesState>, Path(id): Path<String>) -> Response {
+    let Ok(rule_id) = Uuid::parse_str(&id) else {
+        return error_response(StatusCode::BAD_REQUEST, "Invalid rule ID");
+    };
+
+    match state.db.get_approval_rule(rule_id).await {
+        Ok(Some(rule)) => Json(rule).into_response(),
+        Ok(None) => error_response(StatusCode::NOT_FOUND, "Rule not found"),
+        Err(e) => error_response(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+/// POST /api/approval-rules
+async fn create_rule(
+    State(state): State<ApprovalRulesState>,
+    Json(req): Json<CreateApprovalRuleRequest>,
+) -> Response {
+    let rule = req.into_rule();
+    if let Err(e) = rule.compile() {
+        return error_response(StatusCode::BAD_REQUEST, e);
+    }
+
+    match state.db.create_approval_rule(&rule).await {
+        Ok(()) => {
+            reload(&state).await;
+            (StatusCode::CREATED, Json(serde_json::json!({"rule": rule}))).into_response()
+        }
+        Err(e) => error_response(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+/// PUT /api/approval-rules/:id
+async fn update_rule(
+    State(state): State<ApprovalRulesState>,
+    Path(id): Path<String>,
+    Json(req): Json<UpdateApprovalRuleRequest>,
+) -> Response {
+    let Ok(rule_id) = Uuid::parse_str(&id) else {
+        return error_response(StatusCode::BAD_REQUEST, "Invalid rule ID");
+    };
+
+    let existing = match state.db.get_approval_rule(rule_id).await {
+        Ok(Some(rule)) => rule,
+        Ok(None) => return error_response(StatusCode::NOT_FOUND, "Rule not found"),
+        Err(e) => return error_response(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    };
+
+    // Merge updates
+    let argument = match req.argument {
+        Some(arg) if arg.is_empty() => None,
+        Some(arg) => Some(arg),
+        None => existing.argument.clone(),
+    };
+    let updated = ApprovalRule {
+        name: req.name.unwrap_or(existing.name),
+        tool: req.tool.unwrap_or(existing.tool),
+        argument,
+        kind: req.kind.unwrap_or(existing.kind),
+        pattern: req.pattern.unwrap_or(existing.pattern),
+        negate: req.negate.unwrap_or(existing.negate),
+        decision: req.decision.unwrap_or(existing.decision),
+        note: req.note.unwrap_or(existing.note),
+        enabled: req.enabled.unwrap_or(existing.enabled),
+        updated_at: chrono::Utc::now(),
+        ..existing
+    };
+    if let Err(e) = updated.compile() {
+        return error_response(StatusCode::BAD_REQUEST, e);
+    }
+
+    match state.db.update_approval_rule(&updated).await {
+        Ok(()) => {
+            reload(&state).await;
+            Json(serde_json::json!({"rule": updated})).into_response()
+        }
+        Err(e) => error_response(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+/// DELETE /api/approval-rules/:id
+async fn delete_rule(State(state): State<ApprovalRulesState>, Path(id): Path<String>) -> Response {
+    let Ok(rule_id) = Uuid::parse_str(&id) else {
+        return error_response(StatusCode::BAD_REQUEST, "Invalid rule ID");
+    };
+
+    match state.db.delete_approval_rule(rule_id).await {
+        Ok(true) => {
+            reload(&state).await;
+            Json(serde_json::json!({"deleted": true})).into_response()
+        }
+        Ok(false) => error_response(StatusCode::NOT_FOUND, "Rule not found"),
+        Err(e) => error_response(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+/// POST /api/approval-rules/check
+///
+/// Reports which live rule (if any) would decide the given tool call.
+/// `decision: null` means the tool's own default applies.
+async fn check(State(state): State<ApprovalRulesState>, Json(req): Json<CheckRequest>) -> Response {
+    let policies = state.policies.read().await;
+    match policies.decide(&req.tool, &req.params) {
+        Some(hit) => Json(serde_json::json!({
+            "decision": hit.decision,
+            "rule_id": hit.rule_id,
+            "rule_name": hit.rule_name,
+        }))
+        .into_response(),
+        None => Json(serde_json::json!({"decision": null})).into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn create_request_defaults_to_enabled_glob() {
+        let req: CreateApprovalRuleRequest = serde_json::from_str(
+            r#"{"name": "Notes", "tool": "write_file", "argument": "path", "pattern": "~/notes/*", "decision": "auto_approve"}"#,
+        )
+        .unwrap();
+        let rule = req.into_rule();
+        assert_eq!(rule.kind, PatternKind::Glob);
+        assert!(rule.enabled);
+        assert!(!rule.negate);
+        assert!(rule.compile().is_ok());
+    }
+
+    #[test]
+    fn empty_argument_means_whole_tool() {
+        let req: CreateApprovalRuleRequest = serde_json::from_str(
+            r#"{"name": "Ask for mail", "tool": "create_message", "argument": "", "decision": "always_ask"}"#,
+        )
+        .unwrap();
+        let rule = req.into_rule();
+        assert!(rule.argument.is_none());
+        assert_eq!(rule.decision, ApprovalDecision::AlwaysAsk);
+    }
+}
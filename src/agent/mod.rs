@@ -3,6 +3,8 @@
 pub mod agent_loop;
 pub mod agent_queue;
 pub mod approval;
+pub mod approval_policy;
+pub mod approval_routes;
 pub mod commands;
 pub mod compaction;
 pub mod context_monitor;
@@ -15,6 +17,7 @@ pub mod submission;
 pub mod todo_agent;
 pub mod tool_executor;
 pub mod undo;
+pub mod webhook;
 
 // Re-exports
 pub use agent_loop::{Agent, AgentDeps, truncate_for_preview};
@@ -13,6 +13,7 @@ use std::time::Duration;
 
 use chrono::Utc;
 use regex::Regex;
+use thiserror::Error;
 use tokio::sync::{RwLock, mpsc};
 use uuid::Uuid;
 
@@ -21,10 +22,25 @@ use crate::agent::routine::{
 };
 use crate::channels::{IncomingMessage, OutgoingResponse};
 use crate::config::RoutineConfig;
+use crate::llm::budget::BudgetGuard;
 use crate::llm::{ChatMessage, CompletionRequest, FinishReason, LlmProvider};
 use crate::store::Database;
 use crate::workspace::Workspace;
 
+/// Why a routine could not be fired on demand.
+#[derive(Debug, Error)]
+pub enum FireError {
+    /// The routine can't start right now: disabled, at its concurrency
+    /// limit, or paused by its budget.
+    #[error("{0}")]
+    Conflict(String),
+    #[error("routine {0} not found")]
+    NotFound(Uuid),
+    /// The store failed.
+    #[error("{0}")]
+    Internal(String),
+}
+
 /// The routine execution engine.
 pub struct RoutineEngine {
     config: RoutineConfig,
@@ -39,6 +55,8 @@ pub struct RoutineEngine {
     event_cache: Arc<RwLock<Vec<(Uuid, Routine, Regex)>>>,
     /// Optional scheduler for FullJob execution.
     scheduler: Option<Arc<crate::worker::Scheduler>>,
+    /// Spend budgets; routines over a hard limit don't fire.
+    budgets: Option<Arc<BudgetGuard>>,
 }
 
 impl RoutineEngine {
@@ -59,6 +77,21 @@ impl RoutineEngine {
             running_count: Arc::new(AtomicUsize::new(0)),
             event_cache: Arc::new(RwLock::new(Vec::new())),
             scheduler,
+            budgets: None,
+        }
+    }
+
+    /// Hold routines to the spend budgets.
+    pub fn with_budgets(mut self, budgets: Arc<BudgetGuard>) -> Self {
+        self.budgets = Some(budgets);
+        self
+    }
+
+    /// Whether a hard budget limit has paused this routine.
+    async fn budget_paused(&self, routine: &Routine) -> bool {
+        match &self.budgets {
+            Some(budgets) => budgets.routine_paused(routine.id).await,
+            None => false,
         }
     }
 
@@ -129,6 +162,11 @@ impl RoutineEngine {
                 continue;
             }
 
+            if self.budget_paused(routine).await {
+                tracing::info!(routine = %routine.name, "Skipped: budget exceeded");
+                continue;
+            }
+
             let detail = truncate(&message.content, 200);
             self.spawn_fire(routine.clone(), "event", Some(detail));
             fired += 1;
@@ -161,6 +199,11 @@ impl RoutineEngine {
                 continue;
             }
 
+            if self.budget_paused(&routine).await {
+                tracing::info!(routine = %routine.name, "Skipped: budget exceeded");
+                continue;
+            }
+
             let detail = if let Trigger::Cron { ref schedule } = routine.trigger {
                 Some(schedule.clone())
             } else {
@@ -172,31 +215,82 @@ impl RoutineEngine {
     }
 
     /// Fire a routine manually (from tool call or CLI).
-    pub async fn fire_manual(&self, routine_id: Uuid) -> Result<Uuid, String> {
+    pub async fn fire_manual(&self, routine_id: Uuid) -> Result<Uuid, FireError> {
         let routine = self
             .store
             .get_routine(routine_id)
             .await
-            .map_err(|e| format!("DB error: {e}"))?
-            .ok_or_else(|| format!("routine {routine_id} not found"))?;
+            .map_err(|e| FireError::Internal(format!("DB error: {e}")))?
+            .ok_or(FireError::NotFound(routine_id))?;
 
+        self.fire_now(routine, "manual", None).await
+    }
+
+    /// Find the enabled webhook routine registered under `path`.
+    ///
+    /// Leading/trailing slashes are ignored. A webhook routine without an
+    /// explicit path is addressable by its routine ID.
+    pub async fn find_webhook_routine(&self, path: &str) -> Result<Option<Routine>, String> {
+        let wanted = normalize_webhook_path(path);
+        let routines = self
+            .store
+            .list_webhook_routines()
+            .await
+            .map_err(|e| format!("DB error: {e}"))?;
+
+        Ok(routines.into_iter().find(|r| match &r.trigger {
+            Trigger::Webhook { path: Some(p), .. } => normalize_webhook_path(p) == wanted,
+            Trigger::Webhook { path: None, .. } => r.id.to_string() == wanted,
+            _ => false,
+        }))
+    }
+
+    /// Fire a webhook routine with the request body as trigger detail.
+    ///
+    /// The caller is responsible for verifying the request signature.
+    pub async fn fire_webhook(&self, routine: Routine, payload: &str) -> Result<Uuid, FireError> {
+        let detail = (!payload.is_empty()).then(|| truncate(payload, MAX_WEBHOOK_DETAIL));
+        self.fire_now(routine, "webhook", detail).await
+    }
+
+    /// Record a run and execute it in the background, bypassing cooldown.
+    ///
+    /// The run record is written before returning so callers can poll it.
+    async fn fire_now(
+        &self,
+        routine: Routine,
+        trigger_type: &str,
+        trigger_detail: Option<String>,
+    ) -> Result<Uuid, FireError> {
         if !routine.enabled {
-            return Err(format!("routine '{}' is disabled", routine.name));
+            return Err(FireError::Conflict(format!("routine '{}' is disabled", routine.name)));
         }
 
-        if !self.check_concurrent(&routine).await {
-            return Err(format!(
+        let running = self
+            .store
+            .count_running_routine_runs(routine.id)
+            .await
+            .map_err(|e| FireError::Internal(format!("DB error: {e}")))?;
+        if running >= routine.guardrails.max_concurrent as i64 {
+            return Err(FireError::Conflict(format!(
                 "routine '{}' already at max concurrent runs",
                 routine.name
-            ));
+            )));
+        }
+
+        if self.budget_paused(&routine).await {
+            return Err(FireError::Conflict(format!(
+                "routine '{}' is paused: its LLM budget is exhausted",
+                routine.name
+            )));
         }
 
         let run_id = Uuid::new_v4();
         let run = RoutineRun {
             id: run_id,
             routine_id: routine.id,
-            trigger_type: "manual".to_string(),
-            trigger_detail: None,
+            trigger_type: trigger_type.to_string(),
+            trigger_detail,
             started_at: Utc::now(),
             completed_at: None,
             status: RunStatus::Running,
@@ -207,7 +301,7 @@ impl RoutineEngine {
         };
 
         if let Err(e) = self.store.create_routine_run(&run).await {
-            return Err(format!("failed to create run record: {e}"));
+            return Err(FireError::Internal(format!("failed to create run record: {e}")));
         }
 
         let ctx = EngineContext {
@@ -304,14 +398,24 @@ struct EngineContext {
 async fn execute_routine(ctx: EngineContext, routine: Routine, run: RoutineRun) {
     ctx.running_count.fetch_add(1, Ordering::Relaxed);
 
+    // Webhook payloads are handed to the LLM alongside the routine prompt.
+    let webhook_payload = match run.trigger_type.as_str() {
+        "webhook" => run.trigger_detail.as_deref(),
+        _ => None,
+    };
+
     let result = match &routine.action {
         RoutineAction::Lightweight {
             prompt,
             context_paths,
             max_tokens,
-        } => execute_lightweight(&ctx, &routine, run.id, prompt, context_paths, *max_tokens).await,
+        } => {
+            let prompt = with_webhook_payload(prompt, webhook_payload);
+            execute_lightweight(&ctx, &routine, run.id, &prompt, context_paths, *max_tokens).await
+        }
         RoutineAction::FullJob { title, description, .. } => {
-            execute_full_job(&ctx, &routine, run.id, title, description).await
+            let description = with_webhook_payload(description, webhook_payload);
+            execute_full_job(&ctx, &routine, run.id, title, &description).await
         }
     };
 
@@ -425,9 +529,20 @@ async fn execute_lightweight(
         ]
     };
 
-    let request = CompletionRequest::new(messages)
+    let mut request = CompletionRequest::new(messages)
         .with_max_tokens(max_tokens)
         .with_temperature(0.3);
+    // The model router records the call (and its cost) against this run,
+    // and checks the routine's budget first.
+    request
+        .metadata
+        .insert("routine_run_id".to_string(), routine_run_id.to_string());
+    request
+        .metadata
+        .insert("purpose".to_string(), "routine".to_string());
+    request
+        .metadata
+        .insert("routine_id".to_string(), routine.id.to_string());
 
     let response = ctx
         .llm
@@ -438,30 +553,6 @@ async fn execute_lightweight(
     let content = response.content.trim();
     let tokens_used = Some((response.input_tokens + response.output_tokens) as i32);
 
-    // Record LLM call for cost tracking
-    {
-        let (input_cost, output_cost) = ctx.llm.cost_per_token();
-        let cost = input_cost * rust_decimal::Decimal::from(response.input_tokens)
-            + output_cost * rust_decimal::Decimal::from(response.output_tokens);
-        let model_name = ctx.llm.model_name().to_string();
-        let record = crate::store::traits::LlmCallRecord {
-            conversation_id: None,
-            routine_run_id: Some(routine_run_id),
-            provider: &model_name,
-            model: &model_name,
-            input_tokens: response.input_tokens,
-            output_tokens: response.output_tokens,
-            cost,
-            purpose: Some("routine"),
-        };
-        if let Err(e) = ctx.store.record_llm_call(&record).await {
-            tracing::warn!(
-                routine = %routine.name,
-                "Failed to record LLM call cost: {}", e
-            );
-        }
-    }
-
     if content.is_empty() {
         return if response.finish_reason == FinishReason::Length {
             Err(
@@ -621,6 +712,22 @@ async fn execute_full_job(
     ))
 }
 
+/// Maximum bytes of a webhook body kept as trigger detail.
+const MAX_WEBHOOK_DETAIL: usize = 8192;
+
+/// Strip surrounding slashes so `/deploy`, `deploy` and `deploy/` match.
+pub fn normalize_webhook_path(path: &str) -> &str {
+    path.trim_matches('/')
+}
+
+/// Append a webhook payload section to a routine prompt (if any).
+fn with_webhook_payload(prompt: &str, payload: Option<&str>) -> String {
+    match payload {
+        Some(body) => format!("{prompt}\n\n---\n\n# Webhook payload\n\n{body}"),
+        None => prompt.to_string(),
+    }
+}
+
 fn truncate(s: &str, max: usize) -> String {
     if s.len() <= max {
         s.to_string()
@@ -664,6 +771,23 @@ mod tests {
         }
     }
 
+    #[test]
+    fn webhook_path_normalization() {
+        assert_eq!(super::normalize_webhook_path("/deploy"), "deploy");
+        assert_eq!(super::normalize_webhook_path("deploy/"), "deploy");
+        assert_eq!(super::normalize_webhook_path("ci/build"), "ci/build");
+    }
+
+    #[test]
+    fn webhook_payload_appended_to_prompt() {
+        let prompt = super::with_webhook_payload("Summarize the build", Some("{\"ok\":false}"));
+        assert!(prompt.starts_with("Summarize the build"));
+        assert!(prompt.contains("# Webhook payload"));
+        assert!(prompt.ends_with("{\"ok\":false}"));
+
+        assert_eq!(super::with_webhook_payload("plain", None), "plain");
+    }
+
     #[test]
     fn truncate_short() {
         assert_eq!(super::truncate("hello", 10), "hello");
@@ -2,6 +2,11 @@
 //!
 //! Maps external channel thread IDs to internal UUIDs and manages undo state
 //! for each thread.
+//!
+//! With a store attached, sessions and bound threads (turns, state, pending
+//! approval, undo history) are written through after each message and
+//! rehydrated lazily the first time `resolve_thread` misses in memory, so a
+//! restart mid-approval picks up where it left off.
 
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -9,22 +14,21 @@ use std::sync::Arc;
 use tokio::sync::{Mutex, RwLock};
 use uuid::Uuid;
 
-use crate::agent::session::Session;
+use crate::agent::session::{Session, ThreadState};
 use crate::agent::undo::UndoManager;
+use crate::store::Database;
+use crate::store::traits::ThreadBinding;
 
 /// Key for mapping external thread IDs to internal ones.
-#[derive(Clone, Hash, Eq, PartialEq)]
-struct ThreadKey {
-    user_id: String,
-    channel: String,
-    external_thread_id: Option<String>,
-}
+type ThreadKey = ThreadBinding;
 
 /// Manages sessions, threads, and undo state for all users.
 pub struct SessionManager {
     sessions: RwLock<HashMap<String, Arc<Mutex<Session>>>>,
     thread_map: RwLock<HashMap<ThreadKey, Uuid>>,
     undo_managers: RwLock<HashMap<Uuid, Arc<Mutex<UndoManager>>>>,
+    /// Where sessions and threads are persisted; `None` keeps them in memory only.
+    store: Option<Arc<dyn Database>>,
 }
 
 impl SessionManager {
@@ -34,9 +38,16 @@ impl SessionManager {
             sessions: RwLock::new(HashMap::new()),
             thread_map: RwLock::new(HashMap::new()),
             undo_managers: RwLock::new(HashMap::new()),
+            store: None,
         }
     }
 
+    /// Persist sessions and threads to `store` and rehydrate them on demand.
+    pub fn with_store(mut self, store: Arc<dyn Database>) -> Self {
+        self.store = Some(store);
+        self
+    }
+
     /// Get or create a session for a user.
     pub async fn get_or_create_session(&self, user_id: &str) -> Arc<Mutex<Session>> {
         // Fast path: check if session exists
@@ -54,11 +65,23 @@ impl SessionManager {
             return Arc::clone(session);
         }
 
-        let session = Arc::new(Mutex::new(Session::new(user_id)));
+        let session = Arc::new(Mutex::new(self.load_session(user_id).await));
         sessions.insert(user_id.to_string(), Arc::clone(&session));
         session
     }
 
+    /// The user's persisted session (without threads), or a fresh one.
+    async fn load_session(&self, user_id: &str) -> Session {
+        if let Some(store) = &self.store {
+            match store.get_agent_session(user_id).await {
+                Ok(Some(session)) => return session,
+                Ok(None) => {}
+                Err(e) => tracing::warn!(user_id, "Failed to load persisted session: {}", e),
+            }
+        }
+        Session::new(user_id)
+    }
+
     /// Resolve an external thread ID to an internal thread.
     ///
     /// Returns the session and thread ID. Creates both if they don't exist.
@@ -88,6 +111,11 @@ impl SessionManager {
             }
         }
 
+        // Rehydrate a persisted thread for this key
+        if let Some(thread_id) = self.rehydrate_thread(&session, &key).await {
+            return (session, thread_id);
+        }
+
         // Create new thread (always create a new one for a new key)
         let thread_id = {
             let mut sess = session.lock().await;
@@ -110,6 +138,99 @@ impl SessionManager {
         (session, thread_id)
     }
 
+    /// Load the persisted thread bound to `key` into `session`, with its undo
+    /// history. A turn the restart cut off is marked interrupted.
+    async fn rehydrate_thread(&self, session: &Arc<Mutex<Session>>, key: &ThreadKey) -> Option<Uuid> {
+        let store = self.store.as_ref()?;
+        let stored = match store.find_agent_thread(key).await {
+            Ok(stored) => stored?,
+            Err(e) => {
+                tracing::warn!(user_id = %key.user_id, "Failed to load persisted thread: {}", e);
+                return None;
+            }
+        };
+
+        let mut thread = stored.thread;
+        let thread_id = thread.id;
+        if thread.state == ThreadState::Processing {
+            thread.interrupt();
+        }
+        {
+            let mut sess = session.lock().await;
+            thread.session_id = sess.id;
+            sess.threads.entry(thread_id).or_insert(thread);
+            sess.active_thread = Some(thread_id);
+            sess.last_active_at = chrono::Utc::now();
+        }
+
+        self.thread_map.write().await.insert(key.clone(), thread_id);
+        self.undo_managers
+            .write()
+            .await
+            .insert(thread_id, Arc::new(Mutex::new(stored.undo.unwrap_or_default())));
+
+        tracing::debug!("Rehydrated thread {} for {}", thread_id, key.user_id);
+        Some(thread_id)
+    }
+
+    /// Rehydrate the persisted thread bound to this key, if there is one.
+    ///
+    /// Returns `None` when nothing is persisted (or no store is attached).
+    pub async fn restore_thread(
+        &self,
+        user_id: &str,
+        channel: &str,
+        external_thread_id: Option<&str>,
+    ) -> Option<(Arc<Mutex<Session>>, Uuid)> {
+        self.store.as_ref()?;
+        let session = self.get_or_create_session(user_id).await;
+        let key = ThreadKey {
+            user_id: user_id.to_string(),
+            channel: channel.to_string(),
+            external_thread_id: external_thread_id.map(String::from),
+        };
+        let thread_id = self.rehydrate_thread(&session, &key).await?;
+        Some((session, thread_id))
+    }
+
+    /// Write a thread (and its session and undo history) to the store.
+    ///
+    /// Only threads resolved through a channel key are persisted — others
+    /// couldn't be found again. Failures are logged; the in-memory state
+    /// stays authoritative.
+    pub async fn persist_thread(&self, session: &Arc<Mutex<Session>>, thread_id: Uuid) {
+        let Some(store) = &self.store else {
+            return;
+        };
+        let key = {
+            let thread_map = self.thread_map.read().await;
+            thread_map
+                .iter()
+                .find(|(_, id)| **id == thread_id)
+                .map(|(key, _)| key.clone())
+        };
+        let Some(key) = key else {
+            return;
+        };
+
+        let thread = {
+            let sess = session.lock().await;
+            if let Err(e) = store.upsert_agent_session(&sess).await {
+                tracing::warn!(user_id = %sess.user_id, "Failed to persist session: {}", e);
+            }
+            sess.threads.get(&thread_id).cloned()
+        };
+        let Some(thread) = thread else {
+            return;
+        };
+
+        let undo_mgr = self.get_undo_manager(thread_id).await;
+        let undo = undo_mgr.lock().await;
+        if let Err(e) = store.upsert_agent_thread(&key, &thread, Some(&undo)).await {
+            tracing::warn!("Failed to persist thread {}: {}", thread_id, e);
+        }
+    }
+
     /// Register a hydrated thread so subsequent `resolve_thread` calls find it.
     ///
     /// Inserts into the thread_map and creates an undo manager for the thread.
@@ -169,7 +290,8 @@ impl SessionManager {
 
     /// Remove sessions that have been idle for longer than the given duration.
     ///
-    /// Returns the number of sessions pruned.
+    /// Only the in-memory copies are dropped; persisted sessions are
+    /// rehydrated on their next message. Returns the number of sessions pruned.
     pub async fn prune_stale_sessions(&self, max_idle: std::time::Duration) -> usize {
         let cutoff = chrono::Utc::now() - chrono::TimeDelta::seconds(max_idle.as_secs() as i64);
 
@@ -645,6 +767,144 @@ mod tests {
         assert_eq!(sess.active_thread, Some(thread_id));
     }
 
+    fn persistent_manager(db: &Arc<dyn Database>) -> SessionManager {
+        SessionManager::new().with_store(Arc::clone(db))
+    }
+
+    fn pending_approval() -> crate::agent::session::PendingApproval {
+        crate::agent::session::PendingApproval {
+            request_id: Uuid::new_v4(),
+            tool_name: "shell".into(),
+            parameters: serde_json::json!({"command": "ls"}),
+            description: "Run ls".into(),
+            tool_call_id: "call_1".into(),
+            context_messages: vec![crate::llm::ChatMessage::user("list files")],
+            summary: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_restart_rehydrates_pending_approval_and_undo() {
+        let db: Arc<dyn Database> =
+            Arc::new(crate::store::LibSqlBackend::new_memory().await.unwrap());
+        let pending = pending_approval();
+
+        let (session_id, thread_id) = {
+            let manager = persistent_manager(&db);
+            let (session, thread_id) = manager.resolve_thread("user-1", "telegram", None).await;
+            {
+                let mut sess = session.lock().await;
+                sess.auto_approve_tool("echo");
+                let thread = sess.threads.get_mut(&thread_id).unwrap();
+                thread.start_turn("list files");
+                thread.await_approval(pending.clone());
+            }
+            manager
+                .get_undo_manager(thread_id)
+                .await
+                .lock()
+                .await
+                .checkpoint(0, vec![], "Before turn 1");
+            manager.persist_thread(&session, thread_id).await;
+            let session_id = session.lock().await.id;
+            (session_id, thread_id)
+        };
+
+        // "Restart": a fresh manager over the same database
+        let manager = persistent_manager(&db);
+        let (session, resolved) = manager.resolve_thread("user-1", "telegram", None).await;
+        assert_eq!(resolved, thread_id);
+
+        let sess = session.lock().await;
+        assert_eq!(sess.id, session_id);
+        assert_eq!(sess.active_thread, Some(thread_id));
+        assert!(sess.is_tool_auto_approved("echo"));
+        let thread = &sess.threads[&thread_id];
+        assert_eq!(thread.state, ThreadState::AwaitingApproval);
+        assert_eq!(thread.turns.len(), 1);
+        let restored = thread.pending_approval.as_ref().unwrap();
+        assert_eq!(restored.request_id, pending.request_id);
+        assert_eq!(restored.tool_call_id, "call_1");
+        drop(sess);
+
+        let undo = manager.get_undo_manager(thread_id).await;
+        assert_eq!(undo.lock().await.undo_count(), 1);
+
+        // Other keys still get fresh threads
+        let (_, other) = manager.resolve_thread("user-1", "telegram", Some("ext-1")).await;
+        assert_ne!(other, thread_id);
+    }
+
+    #[tokio::test]
+    async fn test_rehydrate_interrupts_turn_cut_off_by_restart() {
+        let db: Arc<dyn Database> =
+            Arc::new(crate::store::LibSqlBackend::new_memory().await.unwrap());
+        let thread_id = {
+            let manager = persistent_manager(&db);
+            let (session, thread_id) = manager.resolve_thread("user-1", "cli", None).await;
+            session
+                .lock()
+                .await
+                .threads
+                .get_mut(&thread_id)
+                .unwrap()
+                .start_turn("long job");
+            manager.persist_thread(&session, thread_id).await;
+            thread_id
+        };
+
+        let manager = persistent_manager(&db);
+        let (session, resolved) = manager.resolve_thread("user-1", "cli", None).await;
+        assert_eq!(resolved, thread_id);
+        let sess = session.lock().await;
+        assert_eq!(sess.threads[&thread_id].state, ThreadState::Interrupted);
+    }
+
+    #[tokio::test]
+    async fn test_pruned_session_rehydrates_from_store() {
+        let db: Arc<dyn Database> =
+            Arc::new(crate::store::LibSqlBackend::new_memory().await.unwrap());
+        let manager = persistent_manager(&db);
+        let (session, thread_id) = manager.resolve_thread("user-1", "cli", None).await;
+        session
+            .lock()
+            .await
+            .threads
+            .get_mut(&thread_id)
+            .unwrap()
+            .start_turn("hello");
+        manager.persist_thread(&session, thread_id).await;
+        session.lock().await.last_active_at =
+            chrono::Utc::now() - chrono::TimeDelta::seconds(86400 * 30);
+        drop(session);
+
+        let pruned = manager
+            .prune_stale_sessions(std::time::Duration::from_secs(86400 * 7))
+            .await;
+        assert_eq!(pruned, 1);
+
+        let (session, resolved) = manager.resolve_thread("user-1", "cli", None).await;
+        assert_eq!(resolved, thread_id);
+        assert_eq!(session.lock().await.threads[&thread_id].turns.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_unbound_thread_not_persisted() {
+        let db: Arc<dyn Database> =
+            Arc::new(crate::store::LibSqlBackend::new_memory().await.unwrap());
+        let manager = persistent_manager(&db);
+        let session = manager.get_or_create_session("user-1").await;
+        let thread_id = session.lock().await.create_thread().id;
+        manager.persist_thread(&session, thread_id).await;
+
+        let binding = ThreadBinding {
+            user_id: "user-1".into(),
+            channel: "cli".into(),
+            external_thread_id: None,
+        };
+        assert!(db.find_agent_thread(&binding).await.unwrap().is_none());
+    }
+
     #[tokio::test]
     async fn test_register_then_resolve_different_channel_creates_new() {
         use crate::agent::session::{Session, Thread};
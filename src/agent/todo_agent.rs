@@ -13,11 +13,13 @@ use uuid::Uuid;
 use tracing::Instrument;
 
 use crate::agent::agent_loop::{Agent, AgentDeps};
+use crate::agent::approval_policy::SharedApprovalPolicies;
 use crate::cards::queue::CardQueue;
 use crate::channels::todo_channel::TodoChannel;
 use crate::channels::ChannelManager;
 use crate::config::AgentConfig;
 use crate::llm::LlmProvider;
+use crate::llm::budget::BudgetGuard;
 use crate::safety::SafetyLayer;
 use crate::store::Database;
 use crate::todos::activity::TodoActivityMessage;
@@ -35,6 +37,8 @@ use crate::workspace::Workspace;
 pub struct TodoAgentDeps {
     pub db: Arc<dyn Database>,
     pub llm: Arc<dyn LlmProvider>,
+    /// Model used to summarize the agent's context when it fills up.
+    pub compaction_llm: Arc<dyn LlmProvider>,
     pub safety: Arc<SafetyLayer>,
     pub tools: Arc<ToolRegistry>,
     pub workspace: Arc<Workspace>,
@@ -42,6 +46,9 @@ pub struct TodoAgentDeps {
     pub todo_tx: broadcast::Sender<TodoWsMessage>,
     pub card_queue: Arc<CardQueue>,
     pub approval_registry: TodoApprovalRegistry,
+    pub approval_policies: SharedApprovalPolicies,
+    /// Spend budgets; todos over a hard limit aren't dispatched.
+    pub budgets: Option<Arc<BudgetGuard>>,
 }
 
 /// Spawn a new Agent wired to a TodoChannel for the given todo.
@@ -112,6 +119,7 @@ pub async fn spawn_todo_agent(
     let agent_deps = AgentDeps {
         store: Some(Arc::clone(&deps.db)),
         llm: Arc::clone(&deps.llm),
+        compaction_llm: Some(Arc::clone(&deps.compaction_llm)),
         safety: Arc::clone(&deps.safety),
         tools: Arc::clone(&deps.tools),
         workspace: Some(Arc::clone(&deps.workspace)),
@@ -119,6 +127,7 @@ pub async fn spawn_todo_agent(
         reply_drafter: None,
         card_queue: None,
         routine_engine: None,
+        approval_policies: Arc::clone(&deps.approval_policies),
     };
 
     // Emit Started activity
@@ -128,7 +137,7 @@ pub async fn spawn_todo_agent(
     });
 
     // Create and spawn the Agent
-    let agent = Agent::new(config, agent_deps, channel_manager, None);
+    let agent = Agent::new(config, agent_deps, Arc::new(channel_manager), None);
 
     let todo_id = todo.id;
     let title = todo.title.clone();
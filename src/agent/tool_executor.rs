@@ -5,12 +5,15 @@ use std::sync::Arc;
 use tokio::sync::Mutex;
 use uuid::Uuid;
 
+use crate::agent::approval_policy::ApprovalDecision;
 use crate::agent::session::{PendingApproval, Session, ThreadState};
 use crate::channels::{IncomingMessage, StatusUpdate};
 use crate::context::JobContext;
 use crate::error::Error;
-use crate::llm::{ChatMessage, Reasoning, ReasoningContext, RespondResult};
-use crate::store::traits::LlmCallRecord;
+use crate::llm::{
+    ChatMessage, Reasoning, ReasoningContext, RespondResult, StreamDelta, StreamTagStripper,
+};
+use crate::safety::PolicyAction;
 
 use super::agent_loop::Agent;
 
@@ -25,6 +28,16 @@ pub(crate) enum AgenticLoopResult {
     },
 }
 
+/// JobContext for chat-driven tool calls. Todo agents carry their todo id
+/// in message metadata so Container tools get a per-todo scratch directory.
+pub(crate) fn chat_job_context(message: &IncomingMessage) -> JobContext {
+    let mut ctx = JobContext::with_user(&message.user_id, "chat", "Interactive chat session");
+    if let Some(todo_id) = message.metadata.get("todo_id") {
+        ctx.metadata = serde_json::json!({ "todo_id": todo_id });
+    }
+    ctx
+}
+
 impl Agent {
     /// Run the agentic loop: call LLM, execute tools, repeat until text response.
     ///
@@ -65,7 +78,7 @@ impl Agent {
         let mut context_messages = initial_messages;
 
         // Create a JobContext for tool execution (chat doesn't have a real job)
-        let job_ctx = JobContext::with_user(&message.user_id, "chat", "Interactive chat session");
+        let job_ctx = chat_job_context(message);
 
         const MAX_TOOL_ITERATIONS: usize = 10;
         let mut iteration = 0;
@@ -114,10 +127,48 @@ impl Agent {
                 .with_metadata({
                     let mut m = std::collections::HashMap::new();
                     m.insert("thread_id".to_string(), thread_id.to_string());
+                    // Read by the model router when it records the call.
+                    m.insert("purpose".to_string(), "chat".to_string());
+                    // Todo agents are held to their todo's budget.
+                    if let Some(todo_id) = message.metadata.get("todo_id").and_then(|v| v.as_str()) {
+                        m.insert("todo_id".to_string(), todo_id.to_string());
+                    }
                     m
                 });
 
-            let output = reasoning.respond_with_tools(&context).await;
+            // Stream text to the channel as it's generated; the final answer
+            // still arrives through `respond`. Reasoning tags are stripped on
+            // the fly, as `respond` strips them from the final text.
+            let (delta_tx, mut delta_rx) = tokio::sync::mpsc::unbounded_channel();
+            let forward_text = async {
+                let mut stripper = StreamTagStripper::new();
+                let mut done = false;
+                while !done {
+                    let visible = match delta_rx.recv().await {
+                        Some(StreamDelta::Text(text)) => stripper.push(&text),
+                        Some(_) => continue,
+                        None => {
+                            done = true;
+                            stripper.finish()
+                        }
+                    };
+                    if visible.is_empty() {
+                        continue;
+                    }
+                    let _ = self
+                        .channels
+                        .send_status(
+                            &message.channel,
+                            StatusUpdate::StreamChunk(visible),
+                            &message.metadata,
+                        )
+                        .await;
+                }
+            };
+            let (output, ()) = tokio::join!(
+                reasoning.respond_with_tools_streaming(&context, delta_tx),
+                forward_text
+            );
             if let Err(ref e) = output {
                 let _ = self
                     .channels
@@ -137,27 +188,6 @@ impl Agent {
                 output.usage.output_tokens
             );
 
-            // Record LLM call for cost tracking
-            if let Some(store) = self.store() {
-                let (input_cost, output_cost) = self.llm().cost_per_token();
-                let cost = input_cost * rust_decimal::Decimal::from(output.usage.input_tokens)
-                    + output_cost * rust_decimal::Decimal::from(output.usage.output_tokens);
-                let model_name = self.llm().model_name().to_string();
-                let record = LlmCallRecord {
-                    conversation_id: Some(thread_id),
-                    routine_run_id: None,
-                    provider: &model_name,
-                    model: &model_name,
-                    input_tokens: output.usage.input_tokens,
-                    output_tokens: output.usage.output_tokens,
-                    cost,
-                    purpose: Some("chat"),
-                };
-                if let Err(e) = store.record_llm_call(&record).await {
-                    tracing::warn!("Failed to record LLM call cost: {}", e);
-                }
-            }
-
             // Log what the LLM returned before acting on it
             match &output.result {
                 RespondResult::Text(text) => {
@@ -265,42 +295,34 @@ impl Agent {
 
                     // Execute each tool (with approval checking)
                     for tc in tool_calls {
+                        // Persistent approval rules refine the tool's own default
+                        let policy = self.approval_policies().read().await.decide(&tc.name, &tc.arguments);
+
                         // Check if tool requires approval
                         if let Some(tool) = self.tools().get(&tc.name).await
-                            && tool.requires_approval()
+                            && (tool.requires_approval()
+                                || policy.as_ref().is_some_and(|p| p.decision == ApprovalDecision::AlwaysAsk))
                         {
-                            // Check if auto-approved for this session
-                            let mut is_auto_approved = {
-                                let sess = session.lock().await;
-                                sess.is_tool_auto_approved(&tc.name)
+                            let mut is_auto_approved = match &policy {
+                                Some(hit) => {
+                                    tracing::debug!(tool = %tc.name, rule = %hit.rule_name, "Approval rule matched");
+                                    hit.decision == ApprovalDecision::AutoApprove
+                                }
+                                // Check if auto-approved for this session
+                                None => {
+                                    let sess = session.lock().await;
+                                    sess.is_tool_auto_approved(&tc.name)
+                                }
                             };
 
-                            // Override auto-approval for destructive shell commands
-                            if is_auto_approved && tc.name == "shell" {
-                                let cmd = tc
-                                    .arguments
-                                    .get("command")
-                                    .and_then(|c| c.as_str().map(String::from))
-                                    .or_else(|| {
-                                        tc.arguments
-                                            .as_str()
-                                            .and_then(|s| {
-                                                serde_json::from_str::<serde_json::Value>(s).ok()
-                                            })
-                                            .and_then(|v| {
-                                                v.get("command")
-                                                    .and_then(|c| c.as_str().map(String::from))
-                                            })
-                                    });
-                                if let Some(ref cmd) = cmd
-                                    && crate::tools::builtin::shell::requires_explicit_approval(cmd)
-                                {
-                                    tracing::info!(
-                                        "Shell command requires explicit approval despite auto-approve: {}",
-                                        &cmd[..cmd.len().min(80)]
-                                    );
-                                    is_auto_approved = false;
-                                }
+                            // Some invocations (e.g. destructive shell commands)
+                            // need approval even when the tool is auto-approved
+                            if is_auto_approved && tool.requires_explicit_approval(&tc.arguments) {
+                                tracing::info!(
+                                    tool = %tc.name,
+                                    "Invocation requires explicit approval despite auto-approve"
+                                );
+                                is_auto_approved = false;
                             }
 
                             if !is_auto_approved {
@@ -356,7 +378,7 @@ impl Agent {
                                     &message.channel,
                                     StatusUpdate::ToolResult {
                                         name: tc.name.clone(),
-                                        preview: output.clone(),
+                                        preview: self.safety().scrub_secrets(output),
                                     },
                                     &message.metadata,
                                 )
@@ -386,6 +408,15 @@ impl Agent {
                                 // Sanitize output before showing to LLM
                                 let sanitized =
                                     self.safety().sanitize_tool_output(&tc.name, &output);
+                                if sanitized.action() != PolicyAction::Allow {
+                                    self.report_safety_event(
+                                        message,
+                                        Some(&tc.name),
+                                        sanitized.action(),
+                                        sanitized.warnings.clone(),
+                                    )
+                                    .await;
+                                }
                                 self.safety().wrap_for_llm(
                                     &tc.name,
                                     &sanitized.content,
@@ -43,6 +43,9 @@ impl Checkpoint {
 }
 
 /// Manager for undo/redo functionality.
+///
+/// Serializable so a thread's undo history survives restarts.
+#[derive(Debug, Serialize, Deserialize)]
 pub struct UndoManager {
     /// Stack of past checkpoints (for undo).
     undo_stack: VecDeque<Checkpoint>,
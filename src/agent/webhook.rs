@@ -0,0 +1,428 @@
+//! HTTP endpoints for webhook-triggered routines.
+//!
+//! Endpoints:
+//! - `POST /hooks/{path}`                — fire the webhook routine registered at `path`
+//! - `GET  /api/routines/runs/{id}`      — poll the status of a routine run
+//!
+//! Every webhook request must carry an HMAC-SHA256 signature of the raw body,
+//! keyed with the routine's `secret`, in `X-Signature-256: sha256=<hex>`
+//! (GitHub's `X-Hub-Signature-256` is accepted too). Routines without a secret
+//! cannot be fired over HTTP.
+
+use std::sync::Arc;
+
+use axum::{
+    Json, Router,
+    body::Bytes,
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    routing::{get, post},
+};
+use ring::hmac;
+use uuid::Uuid;
+
+use crate::agent::routine::Trigger;
+use crate::agent::routine_engine::{FireError, RoutineEngine};
+use crate::store::Database;
+
+/// Signature headers checked in order.
+const SIGNATURE_HEADERS: [&str; 2] = ["x-signature-256", "x-hub-signature-256"];
+
+/// Shared state for webhook routes.
+#[derive(Clone)]
+pub struct WebhookState {
+    pub engine: Arc<RoutineEngine>,
+    pub db: Arc<dyn Database>,
+}
+
+/// Build the Axum router for `/hooks` and run polling.
+pub fn webhook_routes(state: WebhookState) -> Router {
+    Router::new()
+        .route("/hooks/{*path}", post(fire_webhook))
+        .route("/api/routines/runs/{id}", get(get_run))
+        .with_state(state)
+}
+
+/// POST /hooks/{path}
+async fn fire_webhook(
+    State(state): State<WebhookState>,
+    Path(path): Path<String>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> impl IntoResponse {
+    let routine = match state.engine.find_webhook_routine(&path).await {
+        Ok(Some(r)) => r,
+        Ok(None) => {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(serde_json::json!({"error": "No webhook routine at this path"})),
+            )
+                .into_response()
+        }
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({"error": e})),
+            )
+                .into_response()
+        }
+    };
+
+    let secret = match &routine.trigger {
+        Trigger::Webhook {
+            secret: Some(s), ..
+        } if !s.is_empty() => s.clone(),
+        _ => {
+            tracing::warn!(routine = %routine.name, "Webhook rejected: no secret configured");
+            return (
+                StatusCode::FORBIDDEN,
+                Json(serde_json::json!({"error": "Webhook secret not configured"})),
+            )
+                .into_response();
+        }
+    };
+
+    let signature = SIGNATURE_HEADERS
+        .iter()
+        .find_map(|h| headers.get(*h))
+        .and_then(|v| v.to_str().ok());
+
+    if !signature.is_some_and(|sig| verify_signature(&secret, &body, sig)) {
+        tracing::warn!(routine = %routine.name, "Webhook rejected: bad signature");
+        return (
+            StatusCode::UNAUTHORIZED,
+            Json(serde_json::json!({"error": "Invalid or missing signature"})),
+        )
+            .into_response();
+    }
+
+    let payload = String::from_utf8_lossy(&body);
+    let routine_id = routine.id;
+    let routine_name = routine.name.clone();
+
+    match state.engine.fire_webhook(routine, &payload).await {
+        Ok(run_id) => {
+            tracing::info!(routine = %routine_name, run_id = %run_id, "Webhook routine fired");
+            (
+                StatusCode::ACCEPTED,
+                Json(serde_json::json!({
+                    "run_id": run_id.to_string(),
+                    "routine_id": routine_id.to_string(),
+                    "status": "running",
+                })),
+            )
+                .into_response()
+        }
+        Err(e) => {
+            let status = match e {
+                FireError::Conflict(_) => StatusCode::CONFLICT,
+                FireError::NotFound(_) => StatusCode::NOT_FOUND,
+                FireError::Internal(_) => {
+                    tracing::error!(routine = %routine_name, error = %e, "Webhook routine failed to fire");
+                    StatusCode::INTERNAL_SERVER_ERROR
+                }
+            };
+            (status, Json(serde_json::json!({"error": e.to_string()}))).into_response()
+        }
+    }
+}
+
+/// GET /api/routines/runs/:id
+async fn get_run(
+    State(state): State<WebhookState>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    let run_id = match Uuid::parse_str(&id) {
+        Ok(id) => id,
+        Err(_) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({"error": "Invalid run ID"})),
+            )
+                .into_response()
+        }
+    };
+
+    match state.db.get_routine_run(run_id).await {
+        Ok(Some(run)) => Json(run).into_response(),
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({"error": "Run not found"})),
+        )
+            .into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({"error": e.to_string()})),
+        )
+            .into_response(),
+    }
+}
+
+/// Verify a `sha256=<hex>` HMAC signature of `body` (constant-time compare).
+pub fn verify_signature(secret: &str, body: &[u8], header: &str) -> bool {
+    let Some(hex_sig) = header.trim().strip_prefix("sha256=") else {
+        return false;
+    };
+    let Some(expected) = decode_hex(hex_sig) else {
+        return false;
+    };
+    let key = hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes());
+    hmac::verify(&key, body, &expected).is_ok()
+}
+
+/// Compute the `sha256=<hex>` signature header value for `body`.
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    let key = hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes());
+    let tag = hmac::sign(&key, body);
+    let hex: String = tag.as_ref().iter().map(|b| format!("{b:02x}")).collect();
+    format!("sha256={hex}")
+}
+
+fn decode_hex(s: &str) -> Option<Vec<u8>> {
+    if !s.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(s.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    use async_trait::async_trait;
+    use chrono::Utc;
+    use rust_decimal::Decimal;
+
+    use crate::agent::routine::{
+        NotifyConfig, Routine, RoutineAction, RoutineGuardrails, RunStatus,
+    };
+    use crate::config::RoutineConfig;
+    use crate::error::LlmError;
+    use crate::llm::{
+        CompletionRequest, CompletionResponse, FinishReason, LlmProvider, ToolCompletionRequest,
+        ToolCompletionResponse,
+    };
+    use crate::store::LibSqlBackend;
+
+    struct StubLlm;
+
+    #[async_trait]
+    impl LlmProvider for StubLlm {
+        fn model_name(&self) -> &str {
+            "stub"
+        }
+        fn cost_per_token(&self) -> (Decimal, Decimal) {
+            (Decimal::ZERO, Decimal::ZERO)
+        }
+        async fn complete(&self, _req: CompletionRequest) -> Result<CompletionResponse, LlmError> {
+            Ok(CompletionResponse {
+                content: "ROUTINE_OK".to_string(),
+                input_tokens: 1,
+                output_tokens: 1,
+                cache_read_tokens: 0,
+                cache_write_tokens: 0,
+                finish_reason: FinishReason::Stop,
+                response_id: None,
+                provider: "mock".into(),
+                model: self.model_name().into(),
+            })
+        }
+        async fn complete_with_tools(
+            &self,
+            _req: ToolCompletionRequest,
+        ) -> Result<ToolCompletionResponse, LlmError> {
+            unimplemented!("not used in webhook tests")
+        }
+    }
+
+    fn webhook_routine(path: &str, secret: Option<&str>) -> Routine {
+        Routine {
+            id: Uuid::new_v4(),
+            name: format!("hook-{path}"),
+            description: String::new(),
+            user_id: "default".to_string(),
+            enabled: true,
+            trigger: Trigger::Webhook {
+                path: Some(format!("/{path}")),
+                secret: secret.map(String::from),
+            },
+            action: RoutineAction::Lightweight {
+                prompt: "Summarize the payload".to_string(),
+                context_paths: vec![],
+                max_tokens: 256,
+            },
+            guardrails: RoutineGuardrails::default(),
+            notify: NotifyConfig::default(),
+            last_run_at: None,
+            next_fire_at: None,
+            run_count: 0,
+            consecutive_failures: 0,
+            state: serde_json::json!({}),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    async fn start_server(routines: &[Routine]) -> (String, Arc<dyn Database>) {
+        let db: Arc<dyn Database> = Arc::new(LibSqlBackend::new_memory().await.unwrap());
+        for r in routines {
+            db.create_routine(r).await.unwrap();
+        }
+        let (notify_tx, _notify_rx) = tokio::sync::mpsc::channel(16);
+        let engine = Arc::new(RoutineEngine::new(
+            RoutineConfig::default(),
+            Arc::clone(&db),
+            Arc::new(StubLlm),
+            None,
+            notify_tx,
+            None,
+        ));
+        let app = webhook_routes(WebhookState {
+            engine,
+            db: Arc::clone(&db),
+        });
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        (format!("http://127.0.0.1:{port}"), db)
+    }
+
+    #[test]
+    fn sign_and_verify_roundtrip() {
+        let sig = sign("s3cret", b"{\"ref\":\"main\"}");
+        assert!(sig.starts_with("sha256="));
+        assert!(verify_signature("s3cret", b"{\"ref\":\"main\"}", &sig));
+        assert!(!verify_signature("other", b"{\"ref\":\"main\"}", &sig));
+        assert!(!verify_signature("s3cret", b"{\"ref\":\"dev\"}", &sig));
+    }
+
+    #[test]
+    fn verify_rejects_malformed_headers() {
+        assert!(!verify_signature("s", b"body", ""));
+        assert!(!verify_signature("s", b"body", "sha1=abcd"));
+        assert!(!verify_signature("s", b"body", "sha256=zz"));
+        assert!(!verify_signature("s", b"body", "sha256=abc"));
+    }
+
+    #[tokio::test]
+    async fn valid_signature_fires_and_run_is_pollable() {
+        let routine = webhook_routine("deploy", Some("s3cret"));
+        let (base, db) = start_server(std::slice::from_ref(&routine)).await;
+        let body = r#"{"status":"failed"}"#;
+
+        let resp = reqwest::Client::new()
+            .post(format!("{base}/hooks/deploy"))
+            .header("X-Signature-256", sign("s3cret", body.as_bytes()))
+            .body(body)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), 202);
+        let json: serde_json::Value = resp.json().await.unwrap();
+        let run_id: Uuid = json["run_id"].as_str().unwrap().parse().unwrap();
+        assert_eq!(json["routine_id"], routine.id.to_string());
+
+        let run = db.get_routine_run(run_id).await.unwrap().unwrap();
+        assert_eq!(run.trigger_type, "webhook");
+        assert_eq!(run.trigger_detail.as_deref(), Some(body));
+
+        // Poll until the stub LLM run completes.
+        let mut status = String::new();
+        for _ in 0..50 {
+            let polled: serde_json::Value = reqwest::get(format!("{base}/api/routines/runs/{run_id}"))
+                .await
+                .unwrap()
+                .json()
+                .await
+                .unwrap();
+            status = polled["status"].as_str().unwrap_or_default().to_string();
+            if status != RunStatus::Running.to_string() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert_eq!(status, "ok");
+    }
+
+    #[tokio::test]
+    async fn bad_signature_is_rejected() {
+        let routine = webhook_routine("deploy", Some("s3cret"));
+        let (base, db) = start_server(std::slice::from_ref(&routine)).await;
+
+        let resp = reqwest::Client::new()
+            .post(format!("{base}/hooks/deploy"))
+            .header("X-Signature-256", sign("wrong", b"{}"))
+            .body("{}")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), 401);
+
+        let missing = reqwest::Client::new()
+            .post(format!("{base}/hooks/deploy"))
+            .body("{}")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(missing.status(), 401);
+
+        let runs = db.list_routine_runs(routine.id, 10).await.unwrap();
+        assert!(runs.is_empty());
+    }
+
+    #[tokio::test]
+    async fn unknown_path_and_missing_secret() {
+        let routine = webhook_routine("open", None);
+        let (base, _db) = start_server(&[routine]).await;
+
+        let unknown = reqwest::Client::new()
+            .post(format!("{base}/hooks/nope"))
+            .body("{}")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(unknown.status(), 404);
+
+        let no_secret = reqwest::Client::new()
+            .post(format!("{base}/hooks/open"))
+            .header("X-Signature-256", sign("", b"{}"))
+            .body("{}")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(no_secret.status(), 403);
+    }
+
+    #[tokio::test]
+    async fn routine_at_its_run_limit_is_a_conflict() {
+        let mut routine = webhook_routine("busy", Some("s3cret"));
+        routine.guardrails.max_concurrent = 0;
+        let (base, db) = start_server(std::slice::from_ref(&routine)).await;
+
+        let resp = reqwest::Client::new()
+            .post(format!("{base}/hooks/busy"))
+            .header("X-Signature-256", sign("s3cret", b"{}"))
+            .body("{}")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), 409);
+        assert!(db.list_routine_runs(routine.id, 10).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn poll_unknown_run() {
+        let (base, _db) = start_server(&[]).await;
+        let resp = reqwest::get(format!("{base}/api/routines/runs/{}", Uuid::new_v4()))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), 404);
+    }
+}
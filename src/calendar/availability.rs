@@ -0,0 +1,222 @@
+//! Free/busy over the mirrored calendar.
+//!
+//! Busy time is every stored event that blocks the owner's time (declined,
+//! cancelled and free-time events don't). Free slots are the gaps between
+//! busy intervals inside working hours, in the caller's time zone.
+
+use chrono::{DateTime, Datelike, Duration, NaiveTime, TimeZone, Utc, Weekday};
+use tracing::warn;
+
+use super::invite::describe;
+use super::model::{CalendarEvent, StoredEvent};
+use crate::store::Database;
+
+/// A half-open `[start, end)` span of time.
+pub type Span = (DateTime<Utc>, DateTime<Utc>);
+
+/// The part of each day slots may be offered in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WorkingHours {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+    /// Offer Saturdays and Sundays too.
+    pub weekends: bool,
+}
+
+impl Default for WorkingHours {
+    fn default() -> Self {
+        Self {
+            start: NaiveTime::from_hms_opt(9, 0, 0).expect("valid time"),
+            end: NaiveTime::from_hms_opt(17, 0, 0).expect("valid time"),
+            weekends: false,
+        }
+    }
+}
+
+/// Merged busy spans within `[start, end)`.
+///
+/// All-day events cover their dates in `tz` rather than in UTC, so a
+/// day off blocks the local working day.
+pub fn busy<Tz: TimeZone>(
+    tz: &Tz,
+    events: &[StoredEvent],
+    me: Option<&str>,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Vec<Span> {
+    let mut spans: Vec<Span> = events
+        .iter()
+        .map(|stored| &stored.event)
+        .filter(|e| e.blocks_time(me))
+        .filter_map(|e| {
+            let (from, to) = if e.all_day {
+                (
+                    local_midnight(tz, e.start)?,
+                    local_midnight(tz, e.end)?,
+                )
+            } else {
+                (e.start, e.end)
+            };
+            let span = (from.max(start), to.min(end));
+            (span.0 < span.1).then_some(span)
+        })
+        .collect();
+    spans.sort();
+
+    let mut merged: Vec<Span> = Vec::with_capacity(spans.len());
+    for (from, to) in spans {
+        match merged.last_mut() {
+            Some(last) if from <= last.1 => last.1 = last.1.max(to),
+            _ => merged.push((from, to)),
+        }
+    }
+    merged
+}
+
+/// Gaps of at least `min` between `busy` spans, inside working hours.
+///
+/// `busy` must be sorted and non-overlapping, as [`busy`] returns it.
+pub fn free_slots<Tz: TimeZone>(
+    tz: &Tz,
+    busy: &[Span],
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    hours: &WorkingHours,
+    min: Duration,
+) -> Vec<Span> {
+    let mut slots = Vec::new();
+    let mut day = start.with_timezone(tz).date_naive();
+    let last_day = end.with_timezone(tz).date_naive();
+
+    while day <= last_day {
+        let weekend = matches!(day.weekday(), Weekday::Sat | Weekday::Sun);
+        let window = (
+            tz.from_local_datetime(&day.and_time(hours.start)).earliest(),
+            tz.from_local_datetime(&day.and_time(hours.end)).earliest(),
+        );
+        if let (false, Some(open), Some(close)) = (weekend && !hours.weekends, window.0, window.1) {
+            let mut cursor = open.with_timezone(&Utc).max(start);
+            let close = close.with_timezone(&Utc).min(end);
+            for &(from, to) in busy {
+                if from >= close {
+                    break;
+                }
+                if to <= cursor {
+                    continue;
+                }
+                if from - cursor >= min {
+                    slots.push((cursor, from));
+                }
+                cursor = cursor.max(to);
+            }
+            if close - cursor >= min {
+                slots.push((cursor, close));
+            }
+        }
+        let Some(next) = day.succ_opt() else { break };
+        day = next;
+    }
+    slots
+}
+
+/// Stored events that clash with `event`, described for display.
+/// Declined, cancelled and free-time events don't count.
+pub async fn conflicts(db: &dyn Database, event: &CalendarEvent, me: Option<&str>) -> Vec<String> {
+    let existing = match db.list_calendar_events(event.start, event.end).await {
+        Ok(events) => events,
+        Err(e) => {
+            warn!(error = %e, "Failed to load calendar for conflict check");
+            return Vec::new();
+        }
+    };
+    existing
+        .iter()
+        .map(|stored| &stored.event)
+        .filter(|e| e.uid != event.uid && e.overlaps(event.start, event.end) && e.blocks_time(me))
+        .map(describe)
+        .collect()
+}
+
+/// Midnight in `tz` on the (UTC-stored) date of an all-day boundary.
+fn local_midnight<Tz: TimeZone>(tz: &Tz, at: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    tz.from_local_datetime(&at.date_naive().and_time(NaiveTime::MIN))
+        .earliest()
+        .map(|t| t.with_timezone(&Utc))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::FixedOffset;
+
+    use super::*;
+    use crate::calendar::model::{Attendee, EventSource, PartStat};
+
+    // Monday
+    fn at(day: u32, h: u32, m: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 3, day, h, m, 0).unwrap()
+    }
+
+    fn stored(uid: &str, start: DateTime<Utc>, end: DateTime<Utc>) -> StoredEvent {
+        StoredEvent::new(CalendarEvent::new(uid, uid, start, end), EventSource::Caldav)
+    }
+
+    #[test]
+    fn busy_merges_overlaps_and_skips_declined() {
+        let mut declined = stored("declined", at(2, 8, 0), at(2, 18, 0));
+        declined.event.attendees.push(Attendee {
+            partstat: PartStat::Declined,
+            ..Attendee::new("me@x.com")
+        });
+        let events = [
+            stored("b", at(2, 10, 30), at(2, 11, 30)),
+            stored("a", at(2, 10, 0), at(2, 11, 0)),
+            stored("c", at(2, 14, 0), at(2, 15, 0)),
+            declined,
+        ];
+        let spans = busy(&Utc, &events, Some("me@x.com"), at(2, 0, 0), at(3, 0, 0));
+        assert_eq!(spans, vec![(at(2, 10, 0), at(2, 11, 30)), (at(2, 14, 0), at(2, 15, 0))]);
+    }
+
+    #[test]
+    fn free_slots_fill_working_hours_around_busy_time() {
+        let busy = [(at(2, 10, 0), at(2, 11, 30)), (at(2, 14, 0), at(2, 16, 45))];
+        let slots = free_slots(
+            &Utc,
+            &busy,
+            at(2, 0, 0),
+            at(3, 12, 0),
+            &WorkingHours::default(),
+            Duration::minutes(30),
+        );
+        assert_eq!(
+            slots,
+            vec![
+                (at(2, 9, 0), at(2, 10, 0)),
+                (at(2, 11, 30), at(2, 14, 0)),
+                // 16:45–17:00 is too short; Tuesday stops at the range end
+                (at(3, 9, 0), at(3, 12, 0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn weekends_and_time_zones() {
+        // Saturday 7 March, with the working day in UTC+2
+        let tz = FixedOffset::east_opt(2 * 3600).unwrap();
+        let hours = WorkingHours::default();
+        let (start, end) = (at(7, 0, 0), at(8, 0, 0));
+        assert!(free_slots(&tz, &[], start, end, &hours, Duration::hours(1)).is_empty());
+
+        let hours = WorkingHours { weekends: true, ..hours };
+        let slots = free_slots(&tz, &[], start, end, &hours, Duration::hours(1));
+        assert_eq!(slots, vec![(at(7, 7, 0), at(7, 15, 0))]);
+
+        // An all-day event blocks the local day, not the UTC one
+        let mut off = stored("off", at(7, 0, 0), at(8, 0, 0));
+        off.event.all_day = true;
+        assert_eq!(
+            busy(&tz, &[off], None, at(6, 0, 0), at(9, 0, 0)),
+            vec![(at(6, 22, 0), at(7, 22, 0))]
+        );
+    }
+}
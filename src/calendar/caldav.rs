@@ -0,0 +1,407 @@
+//! CalDAV client (RFC 4791) — the calendar-query REPORT used by sync, and
+//! PUT for events created here.
+//!
+//! Servers disagree on namespace prefixes and on whether calendar data is
+//! entity-escaped or wrapped in CDATA, so the multistatus response is
+//! picked apart by element local name rather than with a full XML parser.
+
+use std::sync::LazyLock;
+
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use reqwest::Method;
+
+use super::ics;
+use super::model::CalendarEvent;
+use crate::config::CalendarConfig;
+use crate::error::CalendarError;
+
+/// One calendar object resource returned by the server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteEvent {
+    pub href: String,
+    pub etag: Option<String>,
+    /// The resource's iCalendar text.
+    pub ics: String,
+}
+
+/// Client for a single CalDAV calendar collection.
+pub struct CalDavClient {
+    url: String,
+    username: String,
+    password: String,
+    http: reqwest::Client,
+}
+
+impl CalDavClient {
+    pub fn new(url: impl Into<String>, username: impl Into<String>, password: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            username: username.into(),
+            password: password.into(),
+            http: reqwest::Client::new(),
+        }
+    }
+
+    /// Build a client from config; `None` when no `CALDAV_URL` is set.
+    pub fn from_config(config: &CalendarConfig) -> Option<Self> {
+        let url = config.caldav_url.as_ref()?;
+        Some(Self::new(url, &config.username, &config.password))
+    }
+
+    fn request(&self, method: Method, url: &str) -> reqwest::RequestBuilder {
+        let request = self.http.request(method, url);
+        if self.username.is_empty() {
+            request
+        } else {
+            request.basic_auth(&self.username, Some(&self.password))
+        }
+    }
+
+    /// Fetch events overlapping `[start, end)`, with recurring events
+    /// expanded into instances by the server.
+    pub async fn events_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<RemoteEvent>, CalendarError> {
+        let method = Method::from_bytes(b"REPORT").expect("valid method");
+        let response = self
+            .request(method, &self.url)
+            .header("Depth", "1")
+            .header("Content-Type", "application/xml; charset=utf-8")
+            .body(calendar_query(start, end))
+            .send()
+            .await
+            .map_err(|e| CalendarError::Request(e.to_string()))?;
+
+        let status = response.status();
+        let body = response
+            .text()
+            .await
+            .map_err(|e| CalendarError::Request(e.to_string()))?;
+        if !status.is_success() {
+            return Err(CalendarError::Server {
+                status: status.as_u16(),
+                reason: body.chars().take(200).collect(),
+            });
+        }
+        Ok(parse_multistatus(&body))
+    }
+
+    /// Create `event` as a new resource `<uid>.ics` in the collection.
+    /// Fails rather than overwrite an existing resource.
+    pub async fn put_event(&self, event: &CalendarEvent) -> Result<RemoteEvent, CalendarError> {
+        let base = if self.url.ends_with('/') {
+            self.url.clone()
+        } else {
+            format!("{}/", self.url)
+        };
+        let url = reqwest::Url::parse(&base)
+            .and_then(|base| base.join(&format!("{}.ics", resource_name(&event.uid))))
+            .map_err(|e| CalendarError::Request(format!("Invalid calendar URL: {e}")))?;
+        let ics = ics::write(None, std::slice::from_ref(event));
+
+        let response = self
+            .request(Method::PUT, url.as_str())
+            .header("If-None-Match", "*")
+            .header("Content-Type", "text/calendar; charset=utf-8")
+            .body(ics.clone())
+            .send()
+            .await
+            .map_err(|e| CalendarError::Request(e.to_string()))?;
+
+        let status = response.status();
+        let etag = response
+            .headers()
+            .get("etag")
+            .and_then(|v| v.to_str().ok())
+            .map(String::from);
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(CalendarError::Server {
+                status: status.as_u16(),
+                reason: body.chars().take(200).collect(),
+            });
+        }
+        Ok(RemoteEvent {
+            href: url.path().to_string(),
+            etag,
+            ics,
+        })
+    }
+}
+
+/// A UID made safe to use as a path segment.
+fn resource_name(uid: &str) -> String {
+    uid.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || "-_.@".contains(c) { c } else { '_' })
+        .collect()
+}
+
+fn calendar_query(start: DateTime<Utc>, end: DateTime<Utc>) -> String {
+    let start = start.format("%Y%m%dT%H%M%SZ");
+    let end = end.format("%Y%m%dT%H%M%SZ");
+    format!(
+        r#"<?xml version="1.0" encoding="utf-8"?>
+<C:calendar-query xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:caldav">
+  <D:prop>
+    <D:getetag/>
+    <C:calendar-data>
+      <C:expand start="{start}" end="{end}"/>
+    </C:calendar-data>
+  </D:prop>
+  <C:filter>
+    <C:comp-filter name="VCALENDAR">
+      <C:comp-filter name="VEVENT">
+        <C:time-range start="{start}" end="{end}"/>
+      </C:comp-filter>
+    </C:comp-filter>
+  </C:filter>
+</C:calendar-query>"#
+    )
+}
+
+/// Regex for an element by local name, any (or no) namespace prefix.
+fn element(local: &str) -> Regex {
+    Regex::new(&format!(
+        r"(?s)<(?:[A-Za-z][\w.-]*:)?{local}(?:\s[^>]*)?>(.*?)</(?:[A-Za-z][\w.-]*:)?{local}\s*>"
+    ))
+    .expect("valid element regex")
+}
+
+static RESPONSE: LazyLock<Regex> = LazyLock::new(|| element("response"));
+static HREF: LazyLock<Regex> = LazyLock::new(|| element("href"));
+static ETAG: LazyLock<Regex> = LazyLock::new(|| element("getetag"));
+static CALENDAR_DATA: LazyLock<Regex> = LazyLock::new(|| element("calendar-data"));
+
+/// Extract `(href, etag, calendar-data)` from a multistatus body. Entries
+/// without calendar data (e.g. 404 propstats) are skipped.
+pub fn parse_multistatus(xml: &str) -> Vec<RemoteEvent> {
+    RESPONSE
+        .captures_iter(xml)
+        .filter_map(|response| {
+            let body = response.get(1)?.as_str();
+            let href = text_of(&HREF, body)?;
+            let ics = text_of(&CALENDAR_DATA, body).filter(|ics| ics.contains("BEGIN:VCALENDAR"))?;
+            Some(RemoteEvent {
+                href,
+                etag: text_of(&ETAG, body),
+                ics,
+            })
+        })
+        .collect()
+}
+
+fn text_of(re: &Regex, body: &str) -> Option<String> {
+    let raw = re.captures(body)?.get(1)?.as_str().trim();
+    let text = match raw
+        .strip_prefix("<![CDATA[")
+        .and_then(|s| s.strip_suffix("]]>"))
+    {
+        Some(cdata) => cdata.trim().to_string(),
+        None => unescape_xml(raw).trim().to_string(),
+    };
+    (!text.is_empty()).then_some(text)
+}
+
+fn unescape_xml(s: &str) -> String {
+    s.replace("&#13;", "\r")
+        .replace("&#xD;", "\r")
+        .replace("&#xd;", "\r")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// A local CalDAV stand-in for tests: serves a mutable set of resources
+/// from `REPORT` on any path and stores `PUT`s, behind basic auth `me` / `pw`.
+#[cfg(test)]
+pub(crate) mod stand_in {
+    use std::sync::{Arc, Mutex};
+
+    use axum::Router;
+    use axum::body::Bytes;
+    use axum::extract::State;
+    use axum::http::{HeaderMap, Method, StatusCode, Uri};
+    use axum::response::{IntoResponse, Response};
+    use axum::routing::any;
+
+    /// Resources served, as `(href, etag, ics)`, and the request bodies seen.
+    #[derive(Default)]
+    pub(crate) struct Server {
+        pub resources: Mutex<Vec<(String, String, String)>>,
+        pub requests: Mutex<Vec<(Method, String)>>,
+    }
+
+    fn escape(s: &str) -> String {
+        s.replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+            .replace('\r', "&#13;")
+    }
+
+    async fn handle(
+        State(server): State<Arc<Server>>,
+        method: Method,
+        uri: Uri,
+        headers: HeaderMap,
+        body: Bytes,
+    ) -> Response {
+        // "me:pw"
+        let authorized = headers
+            .get("authorization")
+            .is_some_and(|v| v == "Basic bWU6cHc=");
+        if !authorized {
+            return StatusCode::UNAUTHORIZED.into_response();
+        }
+        server
+            .requests
+            .lock()
+            .unwrap()
+            .push((method.clone(), String::from_utf8_lossy(&body).into_owned()));
+        if method == Method::PUT {
+            let mut resources = server.resources.lock().unwrap();
+            let href = uri.path().to_string();
+            if resources.iter().any(|(h, _, _)| *h == href) {
+                return StatusCode::PRECONDITION_FAILED.into_response();
+            }
+            let etag = format!("\"{}\"", resources.len() + 1);
+            let ics = String::from_utf8_lossy(&body).into_owned();
+            resources.push((href, etag.clone(), ics));
+            return (StatusCode::CREATED, [("etag", etag)]).into_response();
+        }
+        if method.as_str() != "REPORT" {
+            return StatusCode::METHOD_NOT_ALLOWED.into_response();
+        }
+
+        let mut xml = String::from(
+            r#"<?xml version="1.0"?><d:multistatus xmlns:d="DAV:" xmlns:cal="urn:ietf:params:xml:ns:caldav">"#,
+        );
+        for (href, etag, ics) in server.resources.lock().unwrap().iter() {
+            xml.push_str(&format!(
+                "<d:response><d:href>{href}</d:href><d:propstat><d:prop>\
+                 <d:getetag>{}</d:getetag><cal:calendar-data>{}</cal:calendar-data>\
+                 </d:prop><d:status>HTTP/1.1 200 OK</d:status></d:propstat></d:response>",
+                escape(etag),
+                escape(ics)
+            ));
+        }
+        xml.push_str("</d:multistatus>");
+        (StatusCode::MULTI_STATUS, xml).into_response()
+    }
+
+    /// Start the stand-in; returns its calendar URL and state.
+    pub(crate) async fn start() -> (String, Arc<Server>) {
+        let server = Arc::new(Server::default());
+        let app = Router::new()
+            .route("/{*path}", any(handle))
+            .with_state(Arc::clone(&server));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        (format!("http://127.0.0.1:{port}/calendars/me/personal/"), server)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_prefixed_escaped_multistatus() {
+        let xml = r#"<?xml version="1.0"?>
+<d:multistatus xmlns:d="DAV:" xmlns:cal="urn:ietf:params:xml:ns:caldav">
+  <d:response>
+    <d:href>/remote.php/dav/calendars/me/personal/abc.ics</d:href>
+    <d:propstat>
+      <d:prop>
+        <d:getetag>&quot;5f1c&quot;</d:getetag>
+        <cal:calendar-data>BEGIN:VCALENDAR&#13;
+SUMMARY:R&amp;D &lt;sync&gt;&#13;
+END:VCALENDAR</cal:calendar-data>
+      </d:prop>
+      <d:status>HTTP/1.1 200 OK</d:status>
+    </d:propstat>
+  </d:response>
+  <d:response>
+    <d:href>/remote.php/dav/calendars/me/personal/</d:href>
+    <d:propstat><d:prop><d:getetag/></d:prop><d:status>HTTP/1.1 404 Not Found</d:status></d:propstat>
+  </d:response>
+</d:multistatus>"#;
+        let events = parse_multistatus(xml);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].href, "/remote.php/dav/calendars/me/personal/abc.ics");
+        assert_eq!(events[0].etag.as_deref(), Some("\"5f1c\""));
+        assert_eq!(
+            events[0].ics,
+            "BEGIN:VCALENDAR\r\nSUMMARY:R&D <sync>\r\nEND:VCALENDAR"
+        );
+    }
+
+    #[test]
+    fn parses_default_namespace_and_cdata() {
+        let xml = r#"<multistatus xmlns="DAV:">
+<response><href>/cal/x.ics</href><propstat><prop>
+<calendar-data xmlns="urn:ietf:params:xml:ns:caldav"><![CDATA[BEGIN:VCALENDAR
+SUMMARY:a < b
+END:VCALENDAR]]></calendar-data>
+</prop></propstat></response></multistatus>"#;
+        let events = parse_multistatus(xml);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].etag, None);
+        assert!(events[0].ics.contains("SUMMARY:a < b"));
+    }
+
+    #[tokio::test]
+    async fn report_sends_time_range_and_credentials() {
+        let (url, server) = stand_in::start().await;
+        server.resources.lock().unwrap().push((
+            "/calendars/me/personal/a.ics".into(),
+            "\"e1\"".into(),
+            "BEGIN:VCALENDAR\r\nEND:VCALENDAR\r\n".into(),
+        ));
+        let start = chrono::TimeZone::with_ymd_and_hms(&Utc, 2026, 3, 1, 0, 0, 0).unwrap();
+        let end = start + chrono::Duration::days(7);
+
+        let client = CalDavClient::new(&url, "me", "pw");
+        let events = client.events_between(start, end).await.unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].etag.as_deref(), Some("\"e1\""));
+        assert_eq!(events[0].ics, "BEGIN:VCALENDAR\r\nEND:VCALENDAR");
+
+        {
+            let requests = server.requests.lock().unwrap();
+            assert_eq!(requests[0].0.as_str(), "REPORT");
+            assert!(requests[0].1.contains(r#"<C:time-range start="20260301T000000Z" end="20260308T000000Z"/>"#));
+        }
+
+        let denied = CalDavClient::new(&url, "me", "wrong").events_between(start, end).await;
+        assert!(matches!(denied, Err(CalendarError::Server { status: 401, .. })));
+    }
+
+    #[tokio::test]
+    async fn put_creates_resource_once() {
+        let (url, server) = stand_in::start().await;
+        let start = chrono::TimeZone::with_ymd_and_hms(&Utc, 2026, 3, 2, 14, 0, 0).unwrap();
+        let event = CalendarEvent::new("a/b@ai-assist", "Call with Alice", start, start + chrono::Duration::minutes(30));
+
+        let client = CalDavClient::new(url.trim_end_matches('/'), "me", "pw");
+        let created = client.put_event(&event).await.unwrap();
+        assert_eq!(created.href, "/calendars/me/personal/a_b@ai-assist.ics");
+        assert_eq!(created.etag.as_deref(), Some("\"1\""));
+        {
+            let resources = server.resources.lock().unwrap();
+            assert_eq!(resources[0].0, created.href);
+            assert!(resources[0].2.contains("SUMMARY:Call with Alice"));
+            assert!(!resources[0].2.contains("METHOD:"));
+        }
+
+        let again = client.put_event(&event).await;
+        assert!(matches!(again, Err(CalendarError::Server { status: 412, .. })));
+    }
+}
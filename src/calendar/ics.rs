@@ -0,0 +1,789 @@
+//! Minimal iCalendar (RFC 5545) reader and writer.
+//!
+//! Covers what invitations and CalDAV servers actually send: folded lines,
+//! escaped text, `VEVENT`s with organizer/attendees, and `TZID`-qualified
+//! times resolved through the calendar's own `VTIMEZONE` blocks (yearly
+//! `BYMONTH`/`BYDAY` rules, the form every major server emits). Times with
+//! an unknown `TZID`, and floating times, are read as UTC.
+//!
+//! The writer produces iTIP (RFC 5546) responses — `REPLY` with our
+//! `PARTSTAT`, or `COUNTER` to propose a new time.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc, Weekday};
+
+use super::model::{Attendee, CalendarEvent, EventStatus, MeetingResponse, PartStat};
+use crate::error::CalendarError;
+
+/// `PRODID` written on everything we generate.
+const PRODID: &str = "-//ai-assist//calendar//EN";
+
+/// Content lines are folded at this many octets.
+const FOLD_WIDTH: usize = 75;
+
+/// A parsed `VCALENDAR`.
+#[derive(Debug, Clone, Default)]
+pub struct Calendar {
+    /// iTIP method (`REQUEST`, `CANCEL`, `REPLY`, …), uppercased.
+    pub method: Option<String>,
+    pub events: Vec<CalendarEvent>,
+}
+
+// ── Reading ─────────────────────────────────────────────────────────
+
+/// One content line: `NAME;PARAM=value:value`.
+#[derive(Debug, Clone)]
+struct Property {
+    name: String,
+    params: Vec<(String, String)>,
+    value: String,
+}
+
+impl Property {
+    fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(k, _)| k == name)
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+#[derive(Debug, Default)]
+struct Component {
+    name: String,
+    props: Vec<Property>,
+    children: Vec<Component>,
+}
+
+impl Component {
+    fn prop(&self, name: &str) -> Option<&Property> {
+        self.props.iter().find(|p| p.name == name)
+    }
+
+    fn text(&self, name: &str) -> Option<String> {
+        self.prop(name).map(|p| unescape_text(&p.value))
+    }
+}
+
+/// Parse iCalendar text. Events without a `UID` or `DTSTART` are skipped.
+pub fn parse(text: &str) -> Result<Calendar, CalendarError> {
+    let root = parse_components(text)?;
+    let Some(vcal) = root.children.into_iter().find(|c| c.name == "VCALENDAR") else {
+        return Err(CalendarError::Parse("no VCALENDAR component".into()));
+    };
+
+    let timezones: HashMap<String, Vec<Observance>> = vcal
+        .children
+        .iter()
+        .filter(|c| c.name == "VTIMEZONE")
+        .filter_map(|tz| {
+            let id = tz.prop("TZID")?.value.clone();
+            let observances = tz.children.iter().filter_map(Observance::parse).collect();
+            Some((id, observances))
+        })
+        .collect();
+
+    let events = vcal
+        .children
+        .iter()
+        .filter(|c| c.name == "VEVENT")
+        .filter_map(|c| parse_event(c, &timezones))
+        .collect();
+
+    Ok(Calendar {
+        method: vcal.prop("METHOD").map(|p| p.value.trim().to_ascii_uppercase()),
+        events,
+    })
+}
+
+/// Unfold lines and build the component tree.
+fn parse_components(text: &str) -> Result<Component, CalendarError> {
+    let mut lines: Vec<String> = Vec::new();
+    for raw in text.split('\n') {
+        let line = raw.strip_suffix('\r').unwrap_or(raw);
+        if let Some(rest) = line.strip_prefix([' ', '\t']) {
+            if let Some(last) = lines.last_mut() {
+                last.push_str(rest);
+            }
+        } else if !line.trim().is_empty() {
+            lines.push(line.to_string());
+        }
+    }
+
+    let mut stack = vec![Component::default()];
+    for line in &lines {
+        let Some(prop) = parse_property(line) else {
+            continue;
+        };
+        match prop.name.as_str() {
+            "BEGIN" => stack.push(Component {
+                name: prop.value.trim().to_ascii_uppercase(),
+                ..Component::default()
+            }),
+            "END" => {
+                if stack.len() < 2 {
+                    return Err(CalendarError::Parse(format!("unbalanced END:{}", prop.value)));
+                }
+                let done = stack.pop().expect("checked above");
+                stack.last_mut().expect("checked above").children.push(done);
+            }
+            _ => stack.last_mut().expect("root is never popped").props.push(prop),
+        }
+    }
+    if stack.len() != 1 {
+        return Err(CalendarError::Parse("unterminated component".into()));
+    }
+    Ok(stack.pop().expect("root"))
+}
+
+/// Split a content line into name, parameters and value. Parameter values
+/// may be quoted (and contain `:` or `;` when they are).
+fn parse_property(line: &str) -> Option<Property> {
+    let mut name = String::new();
+    let mut params = Vec::new();
+    let mut chars = line.char_indices().peekable();
+
+    // Name
+    while let Some(&(_, c)) = chars.peek() {
+        if c == ';' || c == ':' {
+            break;
+        }
+        name.push(c);
+        chars.next();
+    }
+
+    // Parameters
+    while let Some((_, c)) = chars.next() {
+        if c == ':' {
+            break;
+        }
+        // c == ';' — read NAME=value
+        let mut key = String::new();
+        while let Some(&(_, c)) = chars.peek() {
+            if c == '=' || c == ';' || c == ':' {
+                break;
+            }
+            key.push(c);
+            chars.next();
+        }
+        let mut value = String::new();
+        if chars.peek().is_some_and(|&(_, c)| c == '=') {
+            chars.next();
+            let mut quoted = false;
+            while let Some(&(_, c)) = chars.peek() {
+                if c == '"' {
+                    quoted = !quoted;
+                } else if !quoted && (c == ';' || c == ':') {
+                    break;
+                } else {
+                    value.push(c);
+                }
+                chars.next();
+            }
+        }
+        params.push((key.to_ascii_uppercase(), value));
+    }
+
+    let value = match chars.peek() {
+        Some(&(i, _)) => line[i..].to_string(),
+        None => String::new(),
+    };
+    if name.is_empty() {
+        return None;
+    }
+    Some(Property {
+        name: name.to_ascii_uppercase(),
+        params,
+        value,
+    })
+}
+
+fn unescape_text(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n' | 'N') => out.push('\n'),
+            Some(other) => out.push(other),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+fn parse_event(c: &Component, timezones: &HashMap<String, Vec<Observance>>) -> Option<CalendarEvent> {
+    let uid = c.prop("UID")?.value.trim().to_string();
+    let (start, all_day) = parse_time(c.prop("DTSTART")?, timezones)?;
+    let end = match (c.prop("DTEND"), c.prop("DURATION")) {
+        (Some(dtend), _) => parse_time(dtend, timezones).map(|(t, _)| t)?,
+        (None, Some(duration)) => start.checked_add_signed(parse_duration(&duration.value)?)?,
+        (None, None) if all_day => start.checked_add_signed(Duration::days(1))?,
+        (None, None) => start,
+    };
+
+    let mut event = CalendarEvent::new(
+        uid,
+        c.text("SUMMARY").unwrap_or_else(|| "(no title)".into()),
+        start,
+        end.max(start),
+    );
+    event.all_day = all_day;
+    event.recurrence_id = c
+        .prop("RECURRENCE-ID")
+        .and_then(|p| parse_time(p, timezones))
+        .map(|(t, _)| t);
+    event.description = c.text("DESCRIPTION").filter(|s| !s.trim().is_empty());
+    event.location = c.text("LOCATION").filter(|s| !s.trim().is_empty());
+    event.organizer = c.prop("ORGANIZER").map(parse_attendee);
+    event.attendees = c
+        .props
+        .iter()
+        .filter(|p| p.name == "ATTENDEE")
+        .map(parse_attendee)
+        .collect();
+    event.sequence = c
+        .prop("SEQUENCE")
+        .and_then(|p| p.value.trim().parse().ok())
+        .unwrap_or(0);
+    event.status = match c.prop("STATUS").map(|p| p.value.trim().to_ascii_uppercase()) {
+        Some(s) if s == "CANCELLED" => EventStatus::Cancelled,
+        Some(s) if s == "TENTATIVE" => EventStatus::Tentative,
+        _ => EventStatus::Confirmed,
+    };
+    event.transparent = c
+        .prop("TRANSP")
+        .is_some_and(|p| p.value.trim().eq_ignore_ascii_case("TRANSPARENT"));
+    event.rrule = c.prop("RRULE").map(|p| p.value.trim().to_string());
+    Some(event)
+}
+
+fn parse_attendee(p: &Property) -> Attendee {
+    let value = p.value.trim();
+    let email = value
+        .get(..7)
+        .filter(|scheme| scheme.eq_ignore_ascii_case("mailto:"))
+        .map_or(value, |_| &value[7..]);
+    Attendee {
+        email: email.to_string(),
+        name: p.param("CN").map(str::to_string).filter(|n| !n.is_empty()),
+        partstat: p.param("PARTSTAT").map(PartStat::from_ics).unwrap_or_default(),
+    }
+}
+
+/// Resolve a `DTSTART`-style property to UTC. Returns `(time, is_date)`.
+fn parse_time(
+    p: &Property,
+    timezones: &HashMap<String, Vec<Observance>>,
+) -> Option<(DateTime<Utc>, bool)> {
+    let value = p.value.trim();
+    if p.param("VALUE").is_some_and(|v| v.eq_ignore_ascii_case("DATE")) || value.len() == 8 {
+        let date = NaiveDate::parse_from_str(value, "%Y%m%d").ok()?;
+        return Some((date.and_time(NaiveTime::MIN).and_utc(), true));
+    }
+    if let Some(utc) = value.strip_suffix(['Z', 'z']) {
+        let naive = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").ok()?;
+        return Some((naive.and_utc(), false));
+    }
+
+    let local = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()?;
+    let offset = p
+        .param("TZID")
+        .and_then(|tzid| timezones.get(tzid))
+        .map_or(0, |observances| offset_at(observances, local));
+    Some((local.and_utc().checked_sub_signed(Duration::seconds(offset.into()))?, false))
+}
+
+/// Parse an RFC 5545 duration (`P1D`, `PT1H30M`, `P2W`, `-PT15M`).
+/// Out-of-range values are rejected rather than overflowing.
+fn parse_duration(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    let (negative, rest) = match value.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, value.strip_prefix('+').unwrap_or(value)),
+    };
+    let rest = rest.strip_prefix('P')?;
+
+    let mut total = Duration::zero();
+    let mut number = String::new();
+    for c in rest.chars() {
+        match c {
+            '0'..='9' => number.push(c),
+            'T' => {}
+            unit => {
+                let n: i64 = number.parse().ok()?;
+                number.clear();
+                let part = match unit {
+                    'W' => Duration::try_weeks(n),
+                    'D' => Duration::try_days(n),
+                    'H' => Duration::try_hours(n),
+                    'M' => Duration::try_minutes(n),
+                    'S' => Duration::try_seconds(n),
+                    _ => return None,
+                }?;
+                total = total.checked_add(&part)?;
+            }
+        }
+    }
+    Some(if negative { -total } else { total })
+}
+
+// ── VTIMEZONE ───────────────────────────────────────────────────────
+
+/// One `STANDARD` / `DAYLIGHT` block of a `VTIMEZONE`.
+#[derive(Debug, Clone)]
+struct Observance {
+    /// First onset, in local time.
+    dtstart: NaiveDateTime,
+    /// UTC offset in effect from each onset, in seconds.
+    offset_to: i32,
+    rule: Option<YearlyRule>,
+}
+
+/// `FREQ=YEARLY;BYMONTH=m;BYDAY=nDD` — "the nth weekday of month m".
+#[derive(Debug, Clone, Copy)]
+struct YearlyRule {
+    month: u32,
+    /// 1..=5 from the start of the month, -1..=-5 from the end.
+    week: i32,
+    weekday: Weekday,
+    until: Option<NaiveDateTime>,
+}
+
+impl Observance {
+    fn parse(c: &Component) -> Option<Self> {
+        if c.name != "STANDARD" && c.name != "DAYLIGHT" {
+            return None;
+        }
+        let dtstart =
+            NaiveDateTime::parse_from_str(c.prop("DTSTART")?.value.trim(), "%Y%m%dT%H%M%S").ok()?;
+        Some(Self {
+            dtstart,
+            offset_to: parse_utc_offset(&c.prop("TZOFFSETTO")?.value)?,
+            rule: c.prop("RRULE").and_then(|p| YearlyRule::parse(&p.value)),
+        })
+    }
+
+    /// The latest onset at or before `local`, if any.
+    fn last_onset(&self, local: NaiveDateTime) -> Option<NaiveDateTime> {
+        let Some(rule) = self.rule else {
+            return (self.dtstart <= local).then_some(self.dtstart);
+        };
+        [local.year(), local.year() - 1].into_iter().find_map(|year| {
+            let onset = rule.onset(year, self.dtstart.time())?;
+            let in_range = onset <= local
+                && onset >= self.dtstart
+                && rule.until.is_none_or(|until| onset <= until);
+            in_range.then_some(onset)
+        })
+    }
+}
+
+impl YearlyRule {
+    fn parse(rrule: &str) -> Option<Self> {
+        let mut freq_yearly = false;
+        let mut month = None;
+        let mut byday = None;
+        let mut until = None;
+        for part in rrule.trim().split(';') {
+            let (key, value) = part.split_once('=')?;
+            match key.to_ascii_uppercase().as_str() {
+                "FREQ" => freq_yearly = value.eq_ignore_ascii_case("YEARLY"),
+                "BYMONTH" => month = value.parse().ok(),
+                "BYDAY" => byday = Some(value.to_ascii_uppercase()),
+                "UNTIL" => {
+                    until = NaiveDateTime::parse_from_str(value.trim_end_matches('Z'), "%Y%m%dT%H%M%S").ok()
+                }
+                _ => {}
+            }
+        }
+        if !freq_yearly {
+            return None;
+        }
+        let byday = byday?;
+        let split = byday.len().checked_sub(2)?;
+        let (week, day) = byday.split_at(split);
+        Some(Self {
+            month: month?,
+            week: if week.is_empty() {
+                1
+            } else {
+                // Nth weekday of a month: ±1..=5
+                week.parse().ok().filter(|n: &i32| (1..=5).contains(&n.unsigned_abs()))?
+            },
+            weekday: match day {
+                "MO" => Weekday::Mon,
+                "TU" => Weekday::Tue,
+                "WE" => Weekday::Wed,
+                "TH" => Weekday::Thu,
+                "FR" => Weekday::Fri,
+                "SA" => Weekday::Sat,
+                "SU" => Weekday::Sun,
+                _ => return None,
+            },
+            until,
+        })
+    }
+
+    fn onset(&self, year: i32, time: NaiveTime) -> Option<NaiveDateTime> {
+        let date = if self.week > 0 {
+            let first = NaiveDate::from_ymd_opt(year, self.month, 1)?;
+            let shift = (7 + self.weekday.num_days_from_monday() as i64
+                - first.weekday().num_days_from_monday() as i64)
+                % 7;
+            first.checked_add_signed(Duration::try_days(shift + 7 * (self.week as i64 - 1))?)?
+        } else {
+            let next_month = if self.month == 12 {
+                NaiveDate::from_ymd_opt(year + 1, 1, 1)?
+            } else {
+                NaiveDate::from_ymd_opt(year, self.month + 1, 1)?
+            };
+            let last = next_month.pred_opt()?;
+            let shift = (7 + last.weekday().num_days_from_monday() as i64
+                - self.weekday.num_days_from_monday() as i64)
+                % 7;
+            last.checked_sub_signed(Duration::try_days(shift + 7 * (-(self.week as i64) - 1))?)?
+        };
+        (date.month() == self.month).then(|| date.and_time(time))
+    }
+}
+
+/// Offset of `local` time under the given observances, in seconds.
+fn offset_at(observances: &[Observance], local: NaiveDateTime) -> i32 {
+    observances
+        .iter()
+        .filter_map(|o| o.last_onset(local).map(|onset| (onset, o.offset_to)))
+        .max_by_key(|(onset, _)| *onset)
+        .or_else(|| observances.first().map(|o| (o.dtstart, o.offset_to)))
+        .map_or(0, |(_, offset)| offset)
+}
+
+/// `+0100`, `-0500`, `+053000` → seconds.
+fn parse_utc_offset(value: &str) -> Option<i32> {
+    let value = value.trim();
+    let (sign, digits) = match value.split_at_checked(1)? {
+        ("+", d) => (1, d),
+        ("-", d) => (-1, d),
+        _ => return None,
+    };
+    if digits.len() != 4 && digits.len() != 6 {
+        return None;
+    }
+    let hours: i32 = digits.get(0..2)?.parse().ok()?;
+    let minutes: i32 = digits.get(2..4)?.parse().ok()?;
+    let seconds: i32 = digits.get(4..6).map_or(Some(0), |s| s.parse().ok())?;
+    Some(sign * (hours * 3600 + minutes * 60 + seconds))
+}
+
+// ── Writing ─────────────────────────────────────────────────────────
+
+/// Serialize events into a `VCALENDAR`, with `METHOD` when given.
+pub fn write(method: Option<&str>, events: &[CalendarEvent]) -> String {
+    let mut out = String::new();
+    push_line(&mut out, "BEGIN:VCALENDAR");
+    push_line(&mut out, "VERSION:2.0");
+    push_line(&mut out, &format!("PRODID:{PRODID}"));
+    push_line(&mut out, "CALSCALE:GREGORIAN");
+    if let Some(method) = method {
+        push_line(&mut out, &format!("METHOD:{method}"));
+    }
+    let now = Utc::now();
+    for event in events {
+        write_event(&mut out, event, now);
+    }
+    push_line(&mut out, "END:VCALENDAR");
+    out
+}
+
+fn write_event(out: &mut String, event: &CalendarEvent, now: DateTime<Utc>) {
+    push_line(out, "BEGIN:VEVENT");
+    push_line(out, &format!("UID:{}", event.uid));
+    push_line(out, &format!("DTSTAMP:{}", format_utc(now)));
+    if let Some(rid) = event.recurrence_id {
+        push_line(out, &format!("RECURRENCE-ID:{}", format_utc(rid)));
+    }
+    if event.all_day {
+        push_line(out, &format!("DTSTART;VALUE=DATE:{}", event.start.format("%Y%m%d")));
+        push_line(out, &format!("DTEND;VALUE=DATE:{}", event.end.format("%Y%m%d")));
+    } else {
+        push_line(out, &format!("DTSTART:{}", format_utc(event.start)));
+        push_line(out, &format!("DTEND:{}", format_utc(event.end)));
+    }
+    push_line(out, &format!("SUMMARY:{}", escape_text(&event.summary)));
+    if let Some(ref description) = event.description {
+        push_line(out, &format!("DESCRIPTION:{}", escape_text(description)));
+    }
+    if let Some(ref location) = event.location {
+        push_line(out, &format!("LOCATION:{}", escape_text(location)));
+    }
+    if let Some(ref organizer) = event.organizer {
+        push_line(out, &format!("ORGANIZER{}", person_params(organizer, false)));
+    }
+    for attendee in &event.attendees {
+        push_line(out, &format!("ATTENDEE{}", person_params(attendee, true)));
+    }
+    if let Some(ref rrule) = event.rrule {
+        push_line(out, &format!("RRULE:{rrule}"));
+    }
+    push_line(out, &format!("SEQUENCE:{}", event.sequence));
+    push_line(
+        out,
+        match event.status {
+            EventStatus::Confirmed => "STATUS:CONFIRMED",
+            EventStatus::Tentative => "STATUS:TENTATIVE",
+            EventStatus::Cancelled => "STATUS:CANCELLED",
+        },
+    );
+    if event.transparent {
+        push_line(out, "TRANSP:TRANSPARENT");
+    }
+    push_line(out, "END:VEVENT");
+}
+
+/// `;CN="Name";PARTSTAT=…:mailto:addr` for ORGANIZER / ATTENDEE.
+fn person_params(person: &Attendee, with_partstat: bool) -> String {
+    let mut s = String::new();
+    if let Some(ref name) = person.name {
+        s.push_str(&format!(";CN=\"{}\"", name.replace('"', "")));
+    }
+    if with_partstat {
+        s.push_str(&format!(";PARTSTAT={}", person.partstat.as_ics()));
+    }
+    s.push_str(&format!(":mailto:{}", person.email));
+    s
+}
+
+fn format_utc(t: DateTime<Utc>) -> String {
+    t.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+fn escape_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+/// Append a content line, folded at 75 octets, with CRLF.
+fn push_line(out: &mut String, line: &str) {
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > FOLD_WIDTH {
+            out.push_str("\r\n ");
+            width = 1;
+        }
+        out.push(c);
+        width += c.len_utf8();
+    }
+    out.push_str("\r\n");
+}
+
+// ── iTIP ────────────────────────────────────────────────────────────
+
+/// Build the iTIP message answering `invite` as `me`. Returns the method
+/// (`REPLY`, or `COUNTER` for a proposed time) and the calendar text.
+pub fn itip_response(
+    invite: &CalendarEvent,
+    me: &Attendee,
+    response: &MeetingResponse,
+) -> (&'static str, String) {
+    let mut event = invite.clone();
+    event.description = None;
+    event.rrule = None;
+    event.attendees = vec![Attendee {
+        partstat: response.partstat(),
+        ..me.clone()
+    }];
+    let method = match *response {
+        MeetingResponse::Propose { start, end } => {
+            event.start = start;
+            event.end = end;
+            event.all_day = false;
+            "COUNTER"
+        }
+        _ => "REPLY",
+    };
+    (method, write(Some(method), &[event]))
+}
+
+/// Midnight UTC on `date` — handy for all-day ranges.
+pub fn date_start(date: NaiveDate) -> DateTime<Utc> {
+    Utc.from_utc_datetime(&date.and_time(NaiveTime::MIN))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GOOGLE_INVITE: &str = include_str!("../../tests/fixtures/calendar/invite_google.ics");
+    const OUTLOOK_INVITE: &str = include_str!("../../tests/fixtures/calendar/invite_outlook.ics");
+    const ALL_DAY: &str = include_str!("../../tests/fixtures/calendar/all_day.ics");
+
+    fn utc(y: i32, m: u32, d: u32, h: u32, min: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, m, d, h, min, 0).unwrap()
+    }
+
+    #[test]
+    fn parses_google_invitation() {
+        let cal = parse(GOOGLE_INVITE).unwrap();
+        assert_eq!(cal.method.as_deref(), Some("REQUEST"));
+        assert_eq!(cal.events.len(), 1);
+
+        let event = &cal.events[0];
+        assert_eq!(event.uid, "7kukuqrfedlm2f9t5mhaoa1lvn@google.com");
+        assert_eq!(event.summary, "Q2 planning");
+        assert_eq!(event.location.as_deref(), Some("Room 4, 2nd floor"));
+        // 10:00 New York in July is EDT (UTC-4)
+        assert_eq!(event.start, utc(2026, 7, 14, 14, 0));
+        assert_eq!(event.end, utc(2026, 7, 14, 15, 0));
+        assert_eq!(event.sequence, 1);
+
+        let organizer = event.organizer.as_ref().unwrap();
+        assert_eq!(organizer.email, "alice@example.com");
+        assert_eq!(organizer.name.as_deref(), Some("Alice Smith"));
+        assert_eq!(event.attendees.len(), 2);
+        let me = event.attendee("ME@example.com").unwrap();
+        assert_eq!(me.partstat, PartStat::NeedsAction);
+        assert_eq!(me.name.as_deref(), Some("Me, Myself"));
+    }
+
+    #[test]
+    fn unfolds_lines_and_unescapes_text() {
+        let cal = parse(OUTLOOK_INVITE).unwrap();
+        let event = &cal.events[0];
+        assert_eq!(
+            event.description.as_deref(),
+            Some("Agenda:\n1. Budget, headcount; timeline\n2. Anything else")
+        );
+        // Folded UID is rejoined
+        assert!(event.uid.starts_with("040000008200E00074C5B7101A82E008"));
+        assert!(event.uid.ends_with("DEADBEEF"));
+        // 09:30 Berlin in January is CET (UTC+1); DURATION gives the end
+        assert_eq!(event.start, utc(2026, 1, 20, 8, 30));
+        assert_eq!(event.end, utc(2026, 1, 20, 9, 15));
+    }
+
+    #[test]
+    fn winter_and_summer_offsets_follow_vtimezone_rules() {
+        let mut summer = GOOGLE_INVITE.replace("20260714T100000", "20261201T100000");
+        summer = summer.replace("20260714T110000", "20261201T110000");
+        let event = &parse(&summer).unwrap().events[0];
+        // December in New York is EST (UTC-5)
+        assert_eq!(event.start, utc(2026, 12, 1, 15, 0));
+
+        // Just after the 2nd Sunday of March switch (2026-03-08 02:00)
+        let dst = GOOGLE_INVITE
+            .replace("20260714T100000", "20260308T030000")
+            .replace("20260714T110000", "20260308T040000");
+        assert_eq!(parse(&dst).unwrap().events[0].start, utc(2026, 3, 8, 7, 0));
+    }
+
+    #[test]
+    fn all_day_and_cancelled_events() {
+        let cal = parse(ALL_DAY).unwrap();
+        assert_eq!(cal.method, None);
+        assert_eq!(cal.events.len(), 2);
+
+        let offsite = &cal.events[0];
+        assert!(offsite.all_day);
+        assert_eq!(offsite.start, utc(2026, 5, 4, 0, 0));
+        assert_eq!(offsite.end, utc(2026, 5, 6, 0, 0));
+        assert!(offsite.transparent);
+
+        let cancelled = &cal.events[1];
+        assert_eq!(cancelled.status, EventStatus::Cancelled);
+        assert_eq!(cancelled.recurrence_id, Some(utc(2026, 5, 5, 9, 0)));
+        assert_eq!(cancelled.rrule, None);
+    }
+
+    #[test]
+    fn rejects_garbage() {
+        assert!(parse("hello").is_err());
+        assert!(parse("BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\n").is_err());
+    }
+
+    #[test]
+    fn durations() {
+        assert_eq!(parse_duration("PT1H30M"), Some(Duration::minutes(90)));
+        assert_eq!(parse_duration("P1W"), Some(Duration::weeks(1)));
+        assert_eq!(parse_duration("P1DT2H"), Some(Duration::hours(26)));
+        assert_eq!(parse_duration("-PT15M"), Some(Duration::minutes(-15)));
+        assert_eq!(parse_duration("1H"), None);
+        assert_eq!(parse_duration("P99999999999999W"), None);
+        assert_eq!(parse_duration("P9223372036854775807D"), None);
+        assert_eq!(parse_duration("PT9223372036854775807S"), None);
+    }
+
+    #[test]
+    fn hostile_values_are_rejected_not_panicking() {
+        assert_eq!(parse_utc_offset("+0é0"), None);
+        assert_eq!(parse_utc_offset("+é000"), None);
+        assert!(YearlyRule::parse("FREQ=YEARLY;BYMONTH=3;BYDAY=2000000000SU").is_none());
+        assert!(YearlyRule::parse("FREQ=YEARLY;BYMONTH=3;BYDAY=-2147483648SU").is_none());
+
+        // A whole invitation built from them parses without panicking
+        let ics = "BEGIN:VCALENDAR\r\nMETHOD:REQUEST\r\n\
+            BEGIN:VTIMEZONE\r\nTZID:Evil\r\n\
+            BEGIN:STANDARD\r\nDTSTART:19701025T030000\r\nTZOFFSETFROM:+0é0\r\nTZOFFSETTO:+0é0\r\n\
+            RRULE:FREQ=YEARLY;BYMONTH=10;BYDAY=2000000000SU\r\nEND:STANDARD\r\nEND:VTIMEZONE\r\n\
+            BEGIN:VEVENT\r\nUID:evil\r\nDTSTART;TZID=Evil:20260310T100000\r\n\
+            DURATION:P99999999999999W\r\nSUMMARY:Boom\r\nEND:VEVENT\r\n\
+            BEGIN:VEVENT\r\nUID:far\r\nDTSTART:20260310T100000Z\r\n\
+            DURATION:P99999999W\r\nSUMMARY:Far\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n";
+        let _ = parse(ics);
+    }
+
+    #[test]
+    fn written_calendar_round_trips_and_folds() {
+        let mut event = CalendarEvent::new("abc-123", "Lunch; then a walk, maybe", utc(2026, 2, 3, 12, 0), utc(2026, 2, 3, 13, 0));
+        event.description = Some(format!("Line one\n{}", "x".repeat(200)));
+        event.organizer = Some(Attendee {
+            email: "bob@example.com".into(),
+            name: Some("Bob \"B\" Jones".into()),
+            partstat: PartStat::Accepted,
+        });
+
+        let text = write(Some("REQUEST"), &[event.clone()]);
+        assert!(text.lines().all(|l| l.trim_end_matches('\r').len() <= FOLD_WIDTH));
+        assert!(text.contains("\r\n "));
+
+        let back = &parse(&text).unwrap().events[0];
+        assert_eq!(back.summary, event.summary);
+        assert_eq!(back.description, event.description);
+        assert_eq!(back.start, event.start);
+        assert_eq!(back.organizer.as_ref().unwrap().name.as_deref(), Some("Bob B Jones"));
+    }
+
+    #[test]
+    fn itip_reply_and_counter() {
+        let invite = parse(GOOGLE_INVITE).unwrap().events.remove(0);
+        let me = invite.attendee("me@example.com").unwrap().clone();
+
+        let (method, text) = itip_response(&invite, &me, &MeetingResponse::Decline);
+        assert_eq!(method, "REPLY");
+        let reply = parse(&text).unwrap();
+        assert_eq!(reply.method.as_deref(), Some("REPLY"));
+        let event = &reply.events[0];
+        assert_eq!(event.uid, invite.uid);
+        assert_eq!(event.sequence, invite.sequence);
+        assert_eq!(event.attendees.len(), 1);
+        assert_eq!(event.attendees[0].email, "me@example.com");
+        assert_eq!(event.attendees[0].partstat, PartStat::Declined);
+        assert_eq!(event.organizer, invite.organizer);
+
+        let proposed = MeetingResponse::Propose {
+            start: utc(2026, 7, 15, 14, 0),
+            end: utc(2026, 7, 15, 15, 0),
+        };
+        let (method, text) = itip_response(&invite, &me, &proposed);
+        assert_eq!(method, "COUNTER");
+        let counter = &parse(&text).unwrap().events[0];
+        assert_eq!(counter.start, utc(2026, 7, 15, 14, 0));
+        assert_eq!(counter.attendees[0].partstat, PartStat::Tentative);
+    }
+}
@@ -0,0 +1,193 @@
+//! Meeting invitations arriving by email.
+//!
+//! The email poller keeps any iCalendar part under
+//! `reply_metadata["calendar"]` (`ics` plus the `attendee` address the
+//! mail was delivered to). A `METHOD:REQUEST` there is an invitation: the
+//! pipeline turns it into a Calendar-silo Meeting card instead of a reply.
+
+use chrono::{DateTime, Local, Utc};
+use tracing::warn;
+
+use super::model::{Attendee, CalendarEvent, EventSource, EventStatus, MeetingResponse, StoredEvent};
+use super::{availability, ics};
+use crate::pipeline::types::InboundMessage;
+use crate::store::Database;
+
+/// An invitation found in an inbound message.
+#[derive(Debug, Clone)]
+pub struct Invitation {
+    pub event: CalendarEvent,
+    /// Our address, as invited.
+    pub attendee: String,
+}
+
+impl Invitation {
+    /// The invitation carried by `msg`, if it has a `METHOD:REQUEST`
+    /// calendar with a live event and we know which address was invited.
+    pub fn from_message(msg: &InboundMessage) -> Option<Self> {
+        let calendar = msg.reply_metadata.get("calendar")?;
+        let attendee = calendar.get("attendee")?.as_str()?.trim();
+        if attendee.is_empty() {
+            return None;
+        }
+        let parsed = match ics::parse(calendar.get("ics")?.as_str()?) {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                warn!(message_id = %msg.id, error = %e, "Ignoring unparseable calendar attachment");
+                return None;
+            }
+        };
+        if parsed.method.as_deref() != Some("REQUEST") {
+            return None;
+        }
+        let event = parsed
+            .events
+            .into_iter()
+            .find(|e| e.status != EventStatus::Cancelled)?;
+        Some(Self {
+            event,
+            attendee: attendee.to_string(),
+        })
+    }
+
+    /// Events already on the calendar that clash with this one, described
+    /// for display. Declined, cancelled and free-time events don't count.
+    pub async fn conflicts(&self, db: &dyn Database) -> Vec<String> {
+        availability::conflicts(db, &self.event, Some(&self.attendee)).await
+    }
+}
+
+/// Mark our answer to an invitation on the stored copy of the event, so
+/// conflict checks see declined meetings as free time. Called once the
+/// RSVP has actually been sent.
+pub async fn record_response(
+    db: &dyn Database,
+    attendee: &str,
+    event: &CalendarEvent,
+    response: &MeetingResponse,
+) {
+    let mut stored = match db.get_calendar_event(&event.key()).await {
+        Ok(Some(stored)) => stored,
+        Ok(None) => StoredEvent::new(event.clone(), EventSource::Invite),
+        Err(e) => {
+            warn!(uid = %event.uid, error = %e, "Failed to load invited event");
+            return;
+        }
+    };
+    let partstat = response.partstat();
+    match stored
+        .event
+        .attendees
+        .iter_mut()
+        .find(|a| a.email.eq_ignore_ascii_case(attendee))
+    {
+        Some(me) => me.partstat = partstat,
+        None => stored.event.attendees.push(Attendee {
+            partstat,
+            ..Attendee::new(attendee)
+        }),
+    }
+    stored.updated_at = Utc::now();
+    if let Err(e) = db.upsert_calendar_event(&stored).await {
+        warn!(uid = %event.uid, error = %e, "Failed to record meeting response");
+    }
+}
+
+/// `"Standup (Tue 3 Mar 09:00–09:15)"` in local time; all-day events show
+/// just the date.
+pub fn describe(event: &CalendarEvent) -> String {
+    format!("{} ({})", event.summary, when(event))
+}
+
+/// When an event happens, in local time.
+pub fn when(event: &CalendarEvent) -> String {
+    if event.all_day {
+        return format!("{}, all day", event.start.date_naive().format("%a %-d %b"));
+    }
+    span(event.start, event.end)
+}
+
+/// A time span in local time, e.g. `"Tue 3 Mar 09:00–09:15"`.
+pub fn span(start: DateTime<Utc>, end: DateTime<Utc>) -> String {
+    let (start, end) = (start.with_timezone(&Local), end.with_timezone(&Local));
+    if start.date_naive() == end.date_naive() {
+        format!("{}–{}", start.format("%a %-d %b %H:%M"), end.format("%H:%M"))
+    } else {
+        format!("{} – {}", start.format("%a %-d %b %H:%M"), end.format("%a %-d %b %H:%M"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::calendar::model::{Attendee, EventSource, PartStat, StoredEvent};
+    use crate::pipeline::types::PriorityHints;
+    use crate::store::LibSqlBackend;
+
+    const GOOGLE_INVITE: &str = include_str!("../../tests/fixtures/calendar/invite_google.ics");
+
+    fn message(reply_metadata: serde_json::Value) -> InboundMessage {
+        InboundMessage {
+            id: "m1".into(),
+            channel: "email".into(),
+            sender: "alice@example.com".into(),
+            sender_name: None,
+            content: "Invitation: Q2 planning".into(),
+            subject: Some("Invitation: Q2 planning".into()),
+            thread_context: Vec::new(),
+            reply_metadata,
+            received_at: Utc::now(),
+            priority_hints: PriorityHints::default(),
+        }
+    }
+
+    #[test]
+    fn only_requests_are_invitations() {
+        let meta = |ics: &str| {
+            serde_json::json!({
+                "reply_to": "alice@example.com",
+                "calendar": { "ics": ics, "attendee": "me@example.com" },
+            })
+        };
+        let invitation = Invitation::from_message(&message(meta(GOOGLE_INVITE))).unwrap();
+        assert_eq!(invitation.event.summary, "Q2 planning");
+        assert_eq!(invitation.attendee, "me@example.com");
+
+        let reply = GOOGLE_INVITE.replace("METHOD:REQUEST", "METHOD:REPLY");
+        assert!(Invitation::from_message(&message(meta(&reply))).is_none());
+        assert!(Invitation::from_message(&message(meta("garbage"))).is_none());
+        assert!(Invitation::from_message(&message(serde_json::json!({"reply_to": "a@b"}))).is_none());
+    }
+
+    #[tokio::test]
+    async fn conflicts_ignore_free_and_declined_events() {
+        let db: Arc<dyn Database> = Arc::new(LibSqlBackend::new_memory().await.unwrap());
+        let invitation = Invitation::from_message(&message(serde_json::json!({
+            "calendar": { "ics": GOOGLE_INVITE, "attendee": "me@example.com" },
+        })))
+        .unwrap();
+        let (start, end) = (invitation.event.start, invitation.event.end);
+        let half = chrono::Duration::minutes(30);
+
+        let clash = CalendarEvent::new("c1", "Dentist", start - half, start + half);
+        let mut free = CalendarEvent::new("c2", "Focus time", start, end);
+        free.transparent = true;
+        let mut declined = CalendarEvent::new("c3", "All hands", start, end);
+        declined.attendees.push(Attendee {
+            partstat: PartStat::Declined,
+            ..Attendee::new("me@example.com")
+        });
+        let later = CalendarEvent::new("c4", "Gym", end, end + half);
+        for event in [clash, free, declined, later, invitation.event.clone()] {
+            db.upsert_calendar_event(&StoredEvent::new(event, EventSource::Caldav))
+                .await
+                .unwrap();
+        }
+
+        let conflicts = invitation.conflicts(db.as_ref()).await;
+        assert_eq!(conflicts.len(), 1);
+        assert!(conflicts[0].starts_with("Dentist ("));
+    }
+}
@@ -0,0 +1,19 @@
+//! Calendar integration — backs the Calendar card silo.
+//!
+//! - [`caldav`] talks to the user's CalDAV server; [`sync`] mirrors the
+//!   upcoming window into the `calendar_events` table on a timer.
+//! - [`ics`] reads and writes iCalendar, including iTIP replies.
+//! - [`invite`] picks meeting invitations out of inbound email and turns
+//!   them into accept/decline/propose cards, flagging conflicts against
+//!   the stored calendar.
+//! - [`availability`] computes busy time and free slots for the agent's
+//!   calendar tools.
+
+pub mod availability;
+pub mod caldav;
+pub mod ics;
+pub mod invite;
+pub mod model;
+pub mod sync;
+
+pub use model::{Attendee, CalendarEvent, EventSource, MeetingResponse, PartStat, StoredEvent};
@@ -0,0 +1,305 @@
+//! Calendar data model — events, attendees, and RSVP responses.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// An attendee's participation status (`PARTSTAT`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PartStat {
+    #[default]
+    NeedsAction,
+    Accepted,
+    Declined,
+    Tentative,
+}
+
+impl PartStat {
+    /// The iCalendar spelling, e.g. `NEEDS-ACTION`.
+    pub fn as_ics(&self) -> &'static str {
+        match self {
+            Self::NeedsAction => "NEEDS-ACTION",
+            Self::Accepted => "ACCEPTED",
+            Self::Declined => "DECLINED",
+            Self::Tentative => "TENTATIVE",
+        }
+    }
+
+    /// Parse an iCalendar `PARTSTAT`; unknown values (e.g. `DELEGATED`)
+    /// count as not yet answered.
+    pub fn from_ics(s: &str) -> Self {
+        match s.to_ascii_uppercase().as_str() {
+            "ACCEPTED" => Self::Accepted,
+            "DECLINED" => Self::Declined,
+            "TENTATIVE" => Self::Tentative,
+            _ => Self::NeedsAction,
+        }
+    }
+}
+
+/// An organizer or attendee of an event.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Attendee {
+    /// Email address (the `mailto:` prefix stripped).
+    pub email: String,
+    /// Display name (`CN`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub partstat: PartStat,
+}
+
+impl Attendee {
+    pub fn new(email: impl Into<String>) -> Self {
+        Self {
+            email: email.into(),
+            name: None,
+            partstat: PartStat::NeedsAction,
+        }
+    }
+
+    /// Name if known, otherwise the address.
+    pub fn display(&self) -> &str {
+        self.name.as_deref().unwrap_or(&self.email)
+    }
+}
+
+/// `STATUS` of an event.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventStatus {
+    #[default]
+    Confirmed,
+    Tentative,
+    Cancelled,
+}
+
+/// One calendar event (or one instance of a recurring event).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CalendarEvent {
+    pub uid: String,
+    /// Set on instances of a recurring event.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recurrence_id: Option<DateTime<Utc>>,
+    pub summary: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub location: Option<String>,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    /// Date-only event; `start`/`end` are midnight UTC.
+    #[serde(default)]
+    pub all_day: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub organizer: Option<Attendee>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attendees: Vec<Attendee>,
+    #[serde(default)]
+    pub sequence: u32,
+    #[serde(default)]
+    pub status: EventStatus,
+    /// `TRANSP:TRANSPARENT` — shown, but doesn't block time.
+    #[serde(default)]
+    pub transparent: bool,
+    /// Recurrence rule, kept verbatim (instances come pre-expanded from
+    /// CalDAV).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rrule: Option<String>,
+}
+
+impl CalendarEvent {
+    /// A confirmed event with no attendees.
+    pub fn new(
+        uid: impl Into<String>,
+        summary: impl Into<String>,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Self {
+        Self {
+            uid: uid.into(),
+            recurrence_id: None,
+            summary: summary.into(),
+            description: None,
+            location: None,
+            start,
+            end,
+            all_day: false,
+            organizer: None,
+            attendees: Vec::new(),
+            sequence: 0,
+            status: EventStatus::Confirmed,
+            transparent: false,
+            rrule: None,
+        }
+    }
+
+    /// Storage key — the UID, plus the instance for recurring events.
+    pub fn key(&self) -> String {
+        match self.recurrence_id {
+            Some(rid) => format!("{}@{}", self.uid, rid.format("%Y%m%dT%H%M%SZ")),
+            None => self.uid.clone(),
+        }
+    }
+
+    /// The attendee entry for `email`, if invited.
+    pub fn attendee(&self, email: &str) -> Option<&Attendee> {
+        self.attendees
+            .iter()
+            .find(|a| a.email.eq_ignore_ascii_case(email))
+    }
+
+    /// Whether this event makes `me` busy: not cancelled, not transparent,
+    /// and not declined by `me`.
+    pub fn blocks_time(&self, me: Option<&str>) -> bool {
+        if self.status == EventStatus::Cancelled || self.transparent {
+            return false;
+        }
+        !me.and_then(|me| self.attendee(me))
+            .is_some_and(|a| a.partstat == PartStat::Declined)
+    }
+
+    /// Whether the event overlaps `[start, end)`.
+    pub fn overlaps(&self, start: DateTime<Utc>, end: DateTime<Utc>) -> bool {
+        self.start < end && self.end > start
+    }
+}
+
+/// Where a stored event came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventSource {
+    /// Synced from the CalDAV server.
+    Caldav,
+    /// Taken from an emailed invitation (not yet on the server).
+    Invite,
+    /// Created here with no CalDAV server to hold it.
+    Local,
+}
+
+impl EventSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Caldav => "caldav",
+            Self::Invite => "invite",
+            Self::Local => "local",
+        }
+    }
+}
+
+impl std::str::FromStr for EventSource {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "caldav" => Ok(Self::Caldav),
+            "invite" => Ok(Self::Invite),
+            "local" => Ok(Self::Local),
+            _ => Err(format!("Unknown event source: {s}")),
+        }
+    }
+}
+
+/// An event as persisted in `calendar_events`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StoredEvent {
+    pub event: CalendarEvent,
+    pub source: EventSource,
+    /// CalDAV resource path and ETag (synced events only).
+    pub href: Option<String>,
+    pub etag: Option<String>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl StoredEvent {
+    pub fn new(event: CalendarEvent, source: EventSource) -> Self {
+        Self {
+            event,
+            source,
+            href: None,
+            etag: None,
+            updated_at: Utc::now(),
+        }
+    }
+}
+
+/// The user's answer to a meeting invitation.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "response", rename_all = "snake_case")]
+pub enum MeetingResponse {
+    #[default]
+    Accept,
+    Tentative,
+    Decline,
+    /// Ask the organizer to move the meeting (sent as an iTIP `COUNTER`).
+    Propose {
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    },
+}
+
+impl MeetingResponse {
+    /// The participation status this response reports.
+    pub fn partstat(&self) -> PartStat {
+        match self {
+            Self::Accept => PartStat::Accepted,
+            Self::Tentative | Self::Propose { .. } => PartStat::Tentative,
+            Self::Decline => PartStat::Declined,
+        }
+    }
+
+    /// Subject-line verb, e.g. "Accepted".
+    pub fn verb(&self) -> &'static str {
+        match self {
+            Self::Accept => "Accepted",
+            Self::Tentative => "Tentative",
+            Self::Decline => "Declined",
+            Self::Propose { .. } => "New time proposed",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(h: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 3, 2, h, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn declined_cancelled_and_transparent_events_dont_block() {
+        let mut event = CalendarEvent::new("e1", "Sync", at(9), at(10));
+        let mut me = Attendee::new("me@x.com");
+        event.attendees.push(me.clone());
+        assert!(event.blocks_time(Some("ME@x.com")));
+
+        me.partstat = PartStat::Declined;
+        event.attendees = vec![me];
+        assert!(!event.blocks_time(Some("me@x.com")));
+        assert!(event.blocks_time(None));
+
+        let mut cancelled = CalendarEvent::new("e2", "Gone", at(9), at(10));
+        cancelled.status = EventStatus::Cancelled;
+        assert!(!cancelled.blocks_time(None));
+    }
+
+    #[test]
+    fn recurring_instances_have_distinct_keys() {
+        let mut event = CalendarEvent::new("series", "Standup", at(9), at(10));
+        assert_eq!(event.key(), "series");
+        event.recurrence_id = Some(at(9));
+        assert_eq!(event.key(), "series@20260302T090000Z");
+    }
+
+    #[test]
+    fn meeting_response_serde() {
+        let json = serde_json::to_string(&MeetingResponse::Decline).unwrap();
+        assert_eq!(json, r#"{"response":"decline"}"#);
+        let propose: MeetingResponse = serde_json::from_str(
+            r#"{"response":"propose","start":"2026-03-02T14:00:00Z","end":"2026-03-02T15:00:00Z"}"#,
+        )
+        .unwrap();
+        assert_eq!(propose.partstat(), PartStat::Tentative);
+    }
+}
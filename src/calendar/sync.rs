@@ -0,0 +1,174 @@
+//! Background CalDAV sync — mirrors the upcoming window of the user's
+//! calendar into `calendar_events`.
+//!
+//! Each run fetches events from yesterday through `window_days` ahead,
+//! upserts them, and removes synced events in that range the server no
+//! longer has. Invitation copies (source `invite`) are left alone until the
+//! server's own copy replaces them.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use chrono::Utc;
+use tokio::task::JoinHandle;
+use tracing::{debug, error, info, warn};
+
+use super::caldav::CalDavClient;
+use super::ics;
+use super::model::{EventSource, StoredEvent};
+use crate::error::Error;
+use crate::store::Database;
+
+/// What one sync run did.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SyncReport {
+    /// Events stored or refreshed.
+    pub synced: usize,
+    /// Previously synced events that disappeared from the server.
+    pub removed: usize,
+}
+
+/// Mirrors one CalDAV calendar into the database.
+pub struct CalendarSync {
+    db: Arc<dyn Database>,
+    client: Arc<CalDavClient>,
+    window_days: i64,
+}
+
+impl CalendarSync {
+    pub fn new(db: Arc<dyn Database>, client: Arc<CalDavClient>, window_days: i64) -> Self {
+        Self {
+            db,
+            client,
+            window_days,
+        }
+    }
+
+    /// Run one sync.
+    pub async fn sync_once(&self) -> Result<SyncReport, Error> {
+        let start = Utc::now() - chrono::Duration::days(1);
+        let end = Utc::now() + chrono::Duration::days(self.window_days);
+        let remote = self.client.events_between(start, end).await?;
+
+        let mut seen = Vec::new();
+        for resource in remote {
+            let calendar = match ics::parse(&resource.ics) {
+                Ok(calendar) => calendar,
+                Err(e) => {
+                    warn!(href = %resource.href, error = %e, "Skipping unparseable calendar object");
+                    continue;
+                }
+            };
+            for event in calendar.events {
+                let stored = StoredEvent {
+                    href: Some(resource.href.clone()),
+                    etag: resource.etag.clone(),
+                    ..StoredEvent::new(event, EventSource::Caldav)
+                };
+                self.db.upsert_calendar_event(&stored).await?;
+                seen.push(stored.event.key());
+            }
+        }
+
+        let removed = self
+            .db
+            .prune_calendar_events(EventSource::Caldav, start, end, &seen)
+            .await?;
+        Ok(SyncReport {
+            synced: seen.len(),
+            removed,
+        })
+    }
+}
+
+/// Spawn the background calendar sync loop. The first sync runs right away.
+///
+/// Returns a `JoinHandle` and shutdown flag.
+pub fn spawn_calendar_sync(
+    sync: Arc<CalendarSync>,
+    interval_secs: u64,
+) -> (JoinHandle<()>, Arc<AtomicBool>) {
+    let shutdown = Arc::new(AtomicBool::new(false));
+    let shutdown_flag = Arc::clone(&shutdown);
+
+    let handle = tokio::spawn(async move {
+        info!("Calendar sync started — syncing every {interval_secs}s");
+        let mut tick = tokio::time::interval(Duration::from_secs(interval_secs.max(1)));
+
+        loop {
+            tick.tick().await;
+
+            if shutdown.load(Ordering::Relaxed) {
+                info!("Calendar sync shutting down");
+                return;
+            }
+
+            match sync.sync_once().await {
+                Ok(report) => debug!(
+                    synced = report.synced,
+                    removed = report.removed,
+                    "Calendar synced"
+                ),
+                Err(e) => error!(error = %e, "Calendar sync failed"),
+            }
+        }
+    });
+
+    (handle, shutdown_flag)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calendar::CalendarEvent;
+    use crate::calendar::caldav::stand_in;
+    use crate::store::LibSqlBackend;
+
+    fn resource(uid: &str, summary: &str, start: chrono::DateTime<Utc>) -> String {
+        let event = CalendarEvent::new(uid, summary, start, start + chrono::Duration::hours(1));
+        ics::write(None, &[event])
+    }
+
+    #[tokio::test]
+    async fn sync_mirrors_server_and_drops_deleted_events() {
+        let (url, server) = stand_in::start().await;
+        let tomorrow = Utc::now() + chrono::Duration::days(1);
+        server.resources.lock().unwrap().extend([
+            ("/c/a.ics".to_string(), "\"1\"".to_string(), resource("a", "Design review", tomorrow)),
+            ("/c/b.ics".to_string(), "\"1\"".to_string(), resource("b", "1:1", tomorrow)),
+            ("/c/bad.ics".to_string(), "\"1\"".to_string(), "BEGIN:VCALENDAR\r\nBEGIN:VEVENT".to_string()),
+        ]);
+
+        let db: Arc<dyn Database> = Arc::new(LibSqlBackend::new_memory().await.unwrap());
+        // An invitation we haven't answered yet must survive the prune
+        let invite = CalendarEvent::new("inv", "Offsite", tomorrow, tomorrow + chrono::Duration::hours(2));
+        db.upsert_calendar_event(&StoredEvent::new(invite, EventSource::Invite))
+            .await
+            .unwrap();
+
+        let sync = CalendarSync::new(Arc::clone(&db), Arc::new(CalDavClient::new(&url, "me", "pw")), 30);
+        let report = sync.sync_once().await.unwrap();
+        assert_eq!(report, SyncReport { synced: 2, removed: 0 });
+
+        let a = db.get_calendar_event("a").await.unwrap().unwrap();
+        assert_eq!(a.event.summary, "Design review");
+        assert_eq!(a.source, EventSource::Caldav);
+        assert_eq!(a.href.as_deref(), Some("/c/a.ics"));
+
+        // "b" is deleted on the server
+        server.resources.lock().unwrap().retain(|(href, _, _)| href != "/c/b.ics");
+        let report = sync.sync_once().await.unwrap();
+        assert_eq!(report, SyncReport { synced: 1, removed: 1 });
+        assert!(db.get_calendar_event("b").await.unwrap().is_none());
+        assert!(db.get_calendar_event("inv").await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn server_errors_surface() {
+        let (url, _server) = stand_in::start().await;
+        let db: Arc<dyn Database> = Arc::new(LibSqlBackend::new_memory().await.unwrap());
+        let sync = CalendarSync::new(db, Arc::new(CalDavClient::new(&url, "me", "nope")), 30);
+        assert!(matches!(sync.sync_once().await, Err(Error::Calendar(_))));
+    }
+}
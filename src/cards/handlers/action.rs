@@ -27,110 +27,112 @@ pub struct ActionHandler {
 #[async_trait]
 impl ApprovalHandler for ActionHandler {
     async fn on_approve(&self, card: &ApprovalCard, _ctx: &CardActionContext) {
-        resolve_approval(card, true, &self.approval_registry, &self.activity_tx, &self.db, &self.todo_tx, &self.agent_queue).await;
+        self.resolve_approval(card, true, false).await;
     }
 
     async fn on_dismiss(&self, card: &ApprovalCard, _ctx: &CardActionContext) {
-        resolve_approval(card, false, &self.approval_registry, &self.activity_tx, &self.db, &self.todo_tx, &self.agent_queue).await;
+        self.resolve_approval(card, false, false).await;
     }
 
     async fn on_edit(&self, card: &ApprovalCard, _new_text: &str, _ctx: &CardActionContext) {
         // Edit on an Action card = approve with (potentially modified) details
-        resolve_approval(card, true, &self.approval_registry, &self.activity_tx, &self.db, &self.todo_tx, &self.agent_queue).await;
+        self.resolve_approval(card, true, false).await;
     }
 }
 
-/// Resolve a pending todo agent tool approval by sending a message back into
-/// the agent's mpsc stream. The agent's `process_approval()` handles the rest.
-///
-/// If the card is not in the approval registry, check if it's a todo queue
-/// approval card (has `todo_id` set). If approved, transition to `AgentQueued`.
-async fn resolve_approval(
-    card: &ApprovalCard,
-    approved: bool,
-    registry: &TodoApprovalRegistry,
-    activity_tx: &broadcast::Sender<TodoActivityMessage>,
-    db: &Arc<dyn Database>,
-    todo_tx: &broadcast::Sender<TodoWsMessage>,
-    agent_queue: &Option<Arc<AgentQueue>>,
-) {
-    // First check if this is a tool approval (agent waiting for response)
-    if let Some(pending) = registry.take(card.id).await {
-        // Re-acquire a concurrency permit before resuming the agent
-        match pending.semaphore.clone().acquire_owned().await {
-            Ok(permit) => {
-                *pending.permit_slot.lock().await = Some(permit);
-            }
-            Err(_) => {
-                warn!(card_id = %card.id, "Semaphore closed — cannot re-acquire permit");
+impl ActionHandler {
+    /// Approve and ask the agent to save an approval rule for similar calls
+    /// ("always allow similar" swipe).
+    pub async fn on_always_allow(&self, card: &ApprovalCard) {
+        self.resolve_approval(card, true, true).await;
+    }
+
+    /// Resolve a pending todo agent tool approval by sending a message back into
+    /// the agent's mpsc stream. The agent's `process_approval()` handles the rest
+    /// (including persisting a rule when `always` is set).
+    ///
+    /// If the card is not in the approval registry, check if it's a todo queue
+    /// approval card (has `todo_id` set). If approved, transition to `AgentQueued`.
+    async fn resolve_approval(&self, card: &ApprovalCard, approved: bool, always: bool) {
+        // First check if this is a tool approval (agent waiting for response)
+        if let Some(pending) = self.approval_registry.take(card.id).await {
+            // Re-acquire a concurrency permit before resuming the agent
+            match pending.semaphore.clone().acquire_owned().await {
+                Ok(permit) => {
+                    *pending.permit_slot.lock().await = Some(permit);
+                }
+                Err(_) => {
+                    warn!(card_id = %card.id, "Semaphore closed — cannot re-acquire permit");
+                }
             }
-        }
 
-        let content = format!(
-            "{{\"ExecApproval\":{{\"request_id\":\"{}\",\"approved\":{},\"always\":false}}}}",
-            pending.request_id, approved,
-        );
+            let content = format!(
+                "{{\"ExecApproval\":{{\"request_id\":\"{}\",\"approved\":{},\"always\":{}}}}}",
+                pending.request_id, approved, always,
+            );
 
-        let msg = IncomingMessage::new("todo", "todo-agent", content);
+            let msg = IncomingMessage::new("todo", "todo-agent", content)
+                .with_metadata(serde_json::json!({ "todo_id": pending.todo_id }));
+
+            match pending.tx.send(msg).await {
+                Ok(()) => {
+                    info!(
+                        card_id = %card.id,
+                        todo_id = %pending.todo_id,
+                        approved,
+                        "Sent approval response to todo agent"
+                    );
+
+                    // Broadcast ApprovalResolved to activity stream
+                    let _ = self.activity_tx.send(TodoActivityMessage::ApprovalResolved {
+                        job_id: pending.todo_id, // Use todo_id as job_id proxy for routing
+                        card_id: card.id,
+                        approved,
+                    });
+                }
+                Err(e) => {
+                    warn!(
+                        card_id = %card.id,
+                        error = %e,
+                        "Failed to send approval response — agent may have exited"
+                    );
+                }
+            }
+            return;
+        }
 
-        match pending.tx.send(msg).await {
-            Ok(()) => {
+        // Not a tool approval — check if it's a todo queue approval card (US-003)
+        if let Some(todo_id) = card.todo_id {
+            if approved {
+                // Enqueue via AgentQueue (sets DB status + sends to dispatch channel)
+                if let Some(queue) = &self.agent_queue {
+                    if let Err(e) = queue.enqueue(todo_id).await {
+                        warn!(todo_id = %todo_id, error = %e, "Failed to enqueue todo");
+                        return;
+                    }
+                } else {
+                    // Fallback: just set DB status (no queue available)
+                    if let Err(e) = self.db.update_todo_status(todo_id, TodoStatus::AgentQueued).await {
+                        warn!(todo_id = %todo_id, error = %e, "Failed to update todo to AgentQueued");
+                        return;
+                    }
+                    if let Ok(Some(updated)) = self.db.get_todo(todo_id).await {
+                        let _ = self.todo_tx.send(TodoWsMessage::TodoUpdated { todo: updated });
+                    }
+                }
                 info!(
                     card_id = %card.id,
-                    todo_id = %pending.todo_id,
-                    approved,
-                    "Sent approval response to todo agent"
+                    todo_id = %todo_id,
+                    "Todo approved for agent queue → AgentQueued"
                 );
-
-                // Broadcast ApprovalResolved to activity stream
-                let _ = activity_tx.send(TodoActivityMessage::ApprovalResolved {
-                    job_id: pending.todo_id, // Use todo_id as job_id proxy for routing
-                    card_id: card.id,
-                    approved,
-                });
-            }
-            Err(e) => {
-                warn!(
+            } else {
+                info!(
                     card_id = %card.id,
-                    error = %e,
-                    "Failed to send approval response — agent may have exited"
+                    todo_id = %todo_id,
+                    "Todo queue approval dismissed — stays Created"
                 );
             }
         }
-        return;
-    }
-
-    // Not a tool approval — check if it's a todo queue approval card (US-003)
-    if let Some(todo_id) = card.todo_id {
-        if approved {
-            // Enqueue via AgentQueue (sets DB status + sends to dispatch channel)
-            if let Some(queue) = agent_queue {
-                if let Err(e) = queue.enqueue(todo_id).await {
-                    warn!(todo_id = %todo_id, error = %e, "Failed to enqueue todo");
-                    return;
-                }
-            } else {
-                // Fallback: just set DB status (no queue available)
-                if let Err(e) = db.update_todo_status(todo_id, TodoStatus::AgentQueued).await {
-                    warn!(todo_id = %todo_id, error = %e, "Failed to update todo to AgentQueued");
-                    return;
-                }
-                if let Ok(Some(updated)) = db.get_todo(todo_id).await {
-                    let _ = todo_tx.send(TodoWsMessage::TodoUpdated { todo: updated });
-                }
-            }
-            info!(
-                card_id = %card.id,
-                todo_id = %todo_id,
-                "Todo approved for agent queue → AgentQueued"
-            );
-        } else {
-            info!(
-                card_id = %card.id,
-                todo_id = %todo_id,
-                "Todo queue approval dismissed — stays Created"
-            );
-        }
     }
 }
 
@@ -210,6 +212,27 @@ mod tests {
         assert!(msg.content.contains("\"always\":false"));
     }
 
+    #[tokio::test]
+    async fn always_allow_sends_exec_approval_always_true() {
+        let registry = TodoApprovalRegistry::new();
+        let card = make_action_card();
+        let (tx, mut rx) = mpsc::channel(8);
+        registry.register(card.id, make_approval_pending(tx, uuid::Uuid::new_v4())).await;
+
+        let handler = ActionHandler {
+            approval_registry: registry,
+            activity_tx: make_activity_tx(),
+            db: make_db().await,
+            todo_tx: make_todo_tx(),
+            agent_queue: None,
+        };
+        handler.on_always_allow(&card).await;
+
+        let msg = rx.recv().await.expect("should receive approval message");
+        assert!(msg.content.contains("\"approved\":true"));
+        assert!(msg.content.contains("\"always\":true"));
+    }
+
     #[tokio::test]
     async fn dismiss_sends_exec_approval_false() {
         let registry = TodoApprovalRegistry::new();
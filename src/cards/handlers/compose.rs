@@ -1,102 +1,77 @@
-//! ComposeHandler — sends new outbound messages via the appropriate channel.
+//! ComposeHandler — hands approved new outbound messages to the outbox.
+
+use std::sync::Arc;
 
 use async_trait::async_trait;
-use tracing::{info, warn};
 
-use super::{ApprovalHandler, CardActionContext};
-use crate::cards::model::{ApprovalCard, CardPayload};
-use crate::channels::email::{EmailConfig, send_new_email};
+use super::{ApprovalHandler, CardActionContext, enqueue_outbound};
+use crate::cards::model::ApprovalCard;
+use crate::cards::outbox::Outbox;
 
 pub struct ComposeHandler {
-    pub email_config: Option<EmailConfig>,
+    pub outbox: Option<Arc<Outbox>>,
 }
 
 #[async_trait]
 impl ApprovalHandler for ComposeHandler {
-    async fn on_approve(&self, card: &ApprovalCard, ctx: &CardActionContext) {
-        send_compose(card, self.email_config.as_ref(), ctx).await;
+    async fn on_approve(&self, card: &ApprovalCard, _ctx: &CardActionContext) {
+        enqueue_outbound(card, self.outbox.as_deref()).await;
     }
 
     async fn on_dismiss(&self, _card: &ApprovalCard, _ctx: &CardActionContext) {
         // No additional action on dismiss
     }
 
-    async fn on_edit(&self, card: &ApprovalCard, _new_text: &str, ctx: &CardActionContext) {
+    async fn on_edit(&self, card: &ApprovalCard, _new_text: &str, _ctx: &CardActionContext) {
         // Card already has edited text by the time handler runs
-        send_compose(card, self.email_config.as_ref(), ctx).await;
-    }
-}
-
-/// Send the composed message for an approved/edited compose card via the appropriate channel.
-async fn send_compose(card: &ApprovalCard, email_config: Option<&EmailConfig>, ctx: &CardActionContext) {
-    if let CardPayload::Compose {
-        ref channel,
-        ref recipient,
-        ref subject,
-        ref draft_body,
-        ..
-    } = card.payload
-    {
-        if channel == "email" {
-            if let Some(config) = email_config {
-                let subj = subject.as_deref().unwrap_or("AI Assist");
-                match send_new_email(config, recipient, subj, draft_body) {
-                    Ok(()) => {
-                        ctx.queue.mark_sent(card.id).await;
-                        info!(card_id = %card.id, "Compose email sent successfully");
-                    }
-                    Err(e) => {
-                        tracing::error!(
-                            card_id = %card.id,
-                            error = %e,
-                            "Failed to send compose email"
-                        );
-                    }
-                }
-            } else {
-                warn!(
-                    card_id = %card.id,
-                    "Cannot send compose email — missing email config"
-                );
-            }
-        } else {
-            info!(
-                card_id = %card.id,
-                channel = %channel,
-                "Compose card approved on non-email channel — send not yet wired"
-            );
-        }
+        enqueue_outbound(card, self.outbox.as_deref()).await;
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::cards::model::CardStatus;
+    use crate::cards::outbox::ChannelDelivery;
+    use crate::cards::queue::CardQueue;
+    use crate::channels::email::EmailAccounts;
+    use crate::store::{Database, LibSqlBackend};
 
-    #[test]
-    fn compose_handler_can_be_constructed_with_email_config() {
-        let config = EmailConfig {
-            imap_host: "imap.example.com".into(),
-            imap_port: 993,
-            smtp_host: "smtp.example.com".into(),
-            smtp_port: 587,
-            username: "user@example.com".into(),
-            password: "password".into(),
-            from_address: "user@example.com".into(),
-            poll_interval_secs: 60,
-            allowed_senders: vec![],
-        };
+    #[tokio::test]
+    async fn approved_compose_is_queued_for_delivery() {
+        let db: Arc<dyn Database> = Arc::new(LibSqlBackend::new_memory().await.unwrap());
+        let queue = CardQueue::with_db(Arc::clone(&db)).await;
+        let delivery = Arc::new(ChannelDelivery::new(EmailAccounts::default(), None));
         let handler = ComposeHandler {
-            email_config: Some(config),
+            outbox: Some(Arc::new(Outbox::new(db, Arc::clone(&queue), delivery))),
         };
-        assert!(handler.email_config.is_some());
+
+        let card = ApprovalCard::new_compose("email", "bob@x.com", None, "Hi Bob", 0.8, 15);
+        let card_id = card.id;
+        queue.push(card).await;
+        let card = queue.approve(card_id).await.unwrap();
+        let ctx = CardActionContext {
+            queue: Arc::clone(&queue),
+        };
+        handler.on_approve(&card, &ctx).await;
+
+        let cards = queue.all_cards().await;
+        assert_eq!(cards[0].status, CardStatus::Sending);
     }
 
-    #[test]
-    fn compose_handler_can_be_constructed_without_email_config() {
-        let handler = ComposeHandler {
-            email_config: None,
+    #[tokio::test]
+    async fn compose_without_outbox_is_left_approved() {
+        let queue = CardQueue::new();
+        let handler = ComposeHandler { outbox: None };
+        let card = ApprovalCard::new_compose("email", "bob@x.com", None, "Hi Bob", 0.8, 15);
+        let card_id = card.id;
+        queue.push(card).await;
+        let card = queue.approve(card_id).await.unwrap();
+        let ctx = CardActionContext {
+            queue: Arc::clone(&queue),
         };
-        assert!(handler.email_config.is_none());
+        handler.on_approve(&card, &ctx).await;
+
+        assert_eq!(queue.all_cards().await[0].status, CardStatus::Approved);
     }
 }
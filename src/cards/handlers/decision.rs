@@ -1,20 +1,90 @@
-//! DecisionHandler — stub for decision/judgment cards.
+//! DecisionHandler — decision/judgment cards.
+//!
+//! Learned ignore-rule suggestions are Decision cards: approving one stores
+//! the rule, dismissing it rejects the suggestion. Budget raise requests are
+//! too: approving one raises the hard limit, dismissing it keeps background
+//! work paused until the period resets. So are failed deliveries from the
+//! outbox: approve retries, edit retries with the new text, dismiss discards.
+
+use std::sync::Arc;
 
 use async_trait::async_trait;
-use tracing::info;
+use tracing::{error, info};
 
 use super::{ApprovalHandler, CardActionContext};
 use crate::cards::model::ApprovalCard;
+use crate::cards::outbox::{Outbox, Resolution};
+use crate::llm::budget::BudgetGuard;
+use crate::pipeline::feedback::TriageFeedback;
 
-pub struct DecisionHandler;
+pub struct DecisionHandler {
+    pub feedback: Option<Arc<TriageFeedback>>,
+    pub budgets: Option<Arc<BudgetGuard>>,
+    pub outbox: Option<Arc<Outbox>>,
+}
+
+impl DecisionHandler {
+    async fn resolve_suggestion(&self, card: &ApprovalCard, accepted: bool) {
+        let resolution = if accepted {
+            Resolution::Retry
+        } else {
+            Resolution::Discard
+        };
+        if self.resolve_delivery(card, resolution).await {
+            return;
+        }
+        if let Some(feedback) = &self.feedback {
+            match feedback.resolve(card.id, accepted).await {
+                Ok(true) => return,
+                Ok(false) => {}
+                Err(e) => {
+                    error!(card_id = %card.id, error = %e, "Failed to resolve rule suggestion");
+                    return;
+                }
+            }
+        }
+        if let Some(budgets) = &self.budgets
+            && let Err(e) = budgets.resolve(card.id, accepted).await
+        {
+            error!(card_id = %card.id, error = %e, "Failed to resolve budget raise");
+        }
+    }
+
+    /// Resolve a failed-delivery card. Returns `false` if it isn't one.
+    async fn resolve_delivery(&self, card: &ApprovalCard, resolution: Resolution) -> bool {
+        let Some(outbox) = &self.outbox else {
+            return false;
+        };
+        match outbox.resolve(card.id, resolution).await {
+            Ok(resolved) => resolved,
+            Err(e) => {
+                error!(card_id = %card.id, error = %e, "Failed to resolve failed delivery");
+                true
+            }
+        }
+    }
+}
 
 #[async_trait]
 impl ApprovalHandler for DecisionHandler {
     async fn on_approve(&self, card: &ApprovalCard, _ctx: &CardActionContext) {
         info!(card_id = %card.id, "Decision card approved");
+        self.resolve_suggestion(card, true).await;
     }
 
     async fn on_dismiss(&self, card: &ApprovalCard, _ctx: &CardActionContext) {
         info!(card_id = %card.id, "Decision card dismissed");
+        self.resolve_suggestion(card, false).await;
+    }
+
+    async fn on_edit(&self, card: &ApprovalCard, new_text: &str, _ctx: &CardActionContext) {
+        // Only a failed delivery takes text; anything else treats it as approval.
+        info!(card_id = %card.id, "Decision card edited");
+        if !self
+            .resolve_delivery(card, Resolution::Edit(new_text.to_string()))
+            .await
+        {
+            self.resolve_suggestion(card, true).await;
+        }
     }
 }
@@ -0,0 +1,24 @@
+//! DigestHandler — digest cards are informational; items are promoted individually.
+
+use async_trait::async_trait;
+use tracing::info;
+
+use super::{ApprovalHandler, CardActionContext};
+use crate::cards::model::{ApprovalCard, CardPayload};
+
+pub struct DigestHandler;
+
+#[async_trait]
+impl ApprovalHandler for DigestHandler {
+    async fn on_approve(&self, card: &ApprovalCard, _ctx: &CardActionContext) {
+        let items = match &card.payload {
+            CardPayload::Digest { groups, .. } => groups.iter().map(|g| g.items.len()).sum(),
+            _ => 0,
+        };
+        info!(card_id = %card.id, items, "Digest card acknowledged");
+    }
+
+    async fn on_dismiss(&self, card: &ApprovalCard, _ctx: &CardActionContext) {
+        info!(card_id = %card.id, "Digest card dismissed");
+    }
+}
@@ -0,0 +1,77 @@
+//! MeetingHandler — sends the RSVP for an approved invitation through the
+//! outbox. Our answer is recorded on the stored event once the outbox has
+//! delivered it, so an undone or failed RSVP leaves the calendar alone.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use tracing::info;
+
+use super::{ApprovalHandler, CardActionContext, enqueue_outbound};
+use crate::cards::model::ApprovalCard;
+use crate::cards::outbox::Outbox;
+
+pub struct MeetingHandler {
+    pub outbox: Option<Arc<Outbox>>,
+}
+
+#[async_trait]
+impl ApprovalHandler for MeetingHandler {
+    async fn on_approve(&self, card: &ApprovalCard, _ctx: &CardActionContext) {
+        enqueue_outbound(card, self.outbox.as_deref()).await;
+    }
+
+    async fn on_dismiss(&self, card: &ApprovalCard, _ctx: &CardActionContext) {
+        // Dismissing leaves the invitation unanswered
+        info!(card_id = %card.id, "Meeting invitation dismissed without a response");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calendar::{Attendee, CalendarEvent, MeetingResponse};
+    use crate::cards::model::CardStatus;
+    use crate::cards::outbox::ChannelDelivery;
+    use crate::cards::queue::CardQueue;
+    use crate::channels::email::EmailAccounts;
+    use crate::store::{Database, LibSqlBackend};
+
+    #[tokio::test]
+    async fn declining_queues_the_reply_without_touching_the_calendar() {
+        let db: Arc<dyn Database> = Arc::new(LibSqlBackend::new_memory().await.unwrap());
+        let queue = CardQueue::with_db(Arc::clone(&db)).await;
+        let delivery = Arc::new(ChannelDelivery::new(EmailAccounts::default(), None));
+        let outbox = Arc::new(Outbox::new(Arc::clone(&db), Arc::clone(&queue), delivery));
+        let handler = MeetingHandler {
+            outbox: Some(Arc::clone(&outbox)),
+        };
+
+        let start = chrono::Utc::now() + chrono::Duration::days(1);
+        let mut event = CalendarEvent::new("m1", "Planning", start, start + chrono::Duration::hours(1));
+        event.organizer = Some(Attendee::new("alice@x.com"));
+        event.attendees.push(Attendee::new("ME@x.com"));
+        let card = ApprovalCard::new_meeting("email", "me@x.com", event.clone(), vec![]);
+        let card_id = card.id;
+        queue.push(card).await;
+
+        let card = queue.respond(card_id, MeetingResponse::Decline).await.unwrap();
+        let ctx = CardActionContext {
+            queue: Arc::clone(&queue),
+        };
+        handler.on_approve(&card, &ctx).await;
+
+        // Nothing is recorded until the RSVP has gone out
+        let status = |id| {
+            let queue = Arc::clone(&queue);
+            async move { queue.all_cards().await.into_iter().find(|c| c.id == id).unwrap().status }
+        };
+        assert_eq!(status(card_id).await, CardStatus::Sending);
+        assert!(db.get_calendar_event("m1").await.unwrap().is_none());
+
+        // With no email account the send fails, and the slot stays as it was
+        outbox.process_due().await.unwrap();
+        assert_eq!(status(card_id).await, CardStatus::Failed);
+        assert!(db.get_calendar_event("m1").await.unwrap().is_none());
+    }
+}
@@ -1,68 +1,60 @@
-//! MessageHandler — sends approved reply via the originating channel.
+//! MessageHandler — hands approved replies to the outbox for delivery.
+//!
+//! The outbox sends through the originating channel (SMTP for email, the
+//! `ChannelManager` for chat) with retries; see `cards::outbox`.
+//!
+//! For email, the decision is mirrored back onto the original message:
+//! a sent reply marks it `\Answered`, a dismissal marks it `\Seen`.
+
+use std::sync::Arc;
 
 use async_trait::async_trait;
-use tracing::{info, warn};
+use tracing::warn;
 
-use super::{ApprovalHandler, CardActionContext};
+use super::{ApprovalHandler, CardActionContext, enqueue_outbound};
 use crate::cards::model::{ApprovalCard, CardPayload};
-use crate::channels::email::{EmailConfig, send_reply_email};
+use crate::cards::outbox::Outbox;
+use crate::channels::email::{DISMISSED_FLAGS, EmailAccounts, flag_original_message};
 
 pub struct MessageHandler {
-    pub email_config: Option<EmailConfig>,
+    pub email_accounts: EmailAccounts,
+    pub outbox: Option<Arc<Outbox>>,
 }
 
 #[async_trait]
 impl ApprovalHandler for MessageHandler {
-    async fn on_approve(&self, card: &ApprovalCard, ctx: &CardActionContext) {
-        send_reply(card, self.email_config.as_ref(), ctx).await;
+    async fn on_approve(&self, card: &ApprovalCard, _ctx: &CardActionContext) {
+        enqueue_outbound(card, self.outbox.as_deref()).await;
     }
 
-    async fn on_dismiss(&self, _card: &ApprovalCard, _ctx: &CardActionContext) {
-        // Card already dismissed by queue — no additional action needed
+    async fn on_dismiss(&self, card: &ApprovalCard, _ctx: &CardActionContext) {
+        // Card already dismissed by queue — only the mailbox needs updating.
+        if let CardPayload::Reply {
+            ref channel,
+            reply_metadata: Some(ref meta),
+            ..
+        } = card.payload
+            && channel == "email"
+        {
+            flag_original(card, &self.email_accounts, meta, DISMISSED_FLAGS).await;
+        }
     }
 
-    async fn on_edit(&self, card: &ApprovalCard, _new_text: &str, ctx: &CardActionContext) {
+    async fn on_edit(&self, card: &ApprovalCard, _new_text: &str, _ctx: &CardActionContext) {
         // Card already has edited text by the time handler runs
-        send_reply(card, self.email_config.as_ref(), ctx).await;
+        enqueue_outbound(card, self.outbox.as_deref()).await;
     }
 }
 
-/// Send the reply for an approved/edited reply card via the originating channel.
-async fn send_reply(card: &ApprovalCard, email_config: Option<&EmailConfig>, ctx: &CardActionContext) {
-    if let CardPayload::Reply {
-        ref channel,
-        ref reply_metadata,
-        ref suggested_reply,
-        ..
-    } = card.payload
-    {
-        if channel == "email" {
-            if let (Some(config), Some(meta)) = (email_config, reply_metadata) {
-                match send_reply_email(config, meta, suggested_reply) {
-                    Ok(()) => {
-                        ctx.queue.mark_sent(card.id).await;
-                        info!(card_id = %card.id, "Reply email sent successfully");
-                    }
-                    Err(e) => {
-                        tracing::error!(
-                            card_id = %card.id,
-                            error = %e,
-                            "Failed to send reply email"
-                        );
-                    }
-                }
-            } else {
-                warn!(
-                    card_id = %card.id,
-                    "Cannot send email reply — missing email config or reply_metadata"
-                );
-            }
-        } else {
-            info!(
-                card_id = %card.id,
-                channel = %channel,
-                "Card approved on non-email channel — reply not sent (not yet wired)"
-            );
-        }
+/// Set IMAP flags on the email a reply card came from. Failures only warn —
+/// the card decision itself already stands.
+async fn flag_original(
+    card: &ApprovalCard,
+    email_accounts: &EmailAccounts,
+    meta: &serde_json::Value,
+    flags: &'static [&'static str],
+) {
+    if let Err(e) = flag_original_message(email_accounts, meta, flags).await {
+        warn!(card_id = %card.id, error = %e, "Failed to update IMAP flags");
     }
 }
@@ -6,21 +6,29 @@
 mod action;
 mod compose;
 mod decision;
+mod digest;
+mod meeting;
 mod message;
 mod multiple_choice;
+mod new_event;
 
 use std::sync::Arc;
 
 use async_trait::async_trait;
+use tracing::{error, warn};
 
 use super::model::ApprovalCard;
+use super::outbox::Outbox;
 use super::queue::CardQueue;
 
 pub use action::ActionHandler;
 pub use compose::ComposeHandler;
 pub use decision::DecisionHandler;
+pub use digest::DigestHandler;
+pub use meeting::MeetingHandler;
 pub use message::MessageHandler;
 pub use multiple_choice::MultipleChoiceHandler;
+pub use new_event::NewEventHandler;
 
 /// Shared dependencies available to all approval handlers.
 pub struct CardActionContext {
@@ -42,3 +50,15 @@ pub trait ApprovalHandler: Send + Sync {
         self.on_approve(card, ctx).await;
     }
 }
+
+/// Hand an approved outbound card (Reply / Compose / Meeting) to the outbox, which
+/// delivers it and moves the card to `Sending`, then `Sent` or `Failed`.
+async fn enqueue_outbound(card: &ApprovalCard, outbox: Option<&Outbox>) {
+    let Some(outbox) = outbox else {
+        warn!(card_id = %card.id, "No outbox configured — approved message not sent");
+        return;
+    };
+    if let Err(e) = outbox.enqueue(card).await {
+        error!(card_id = %card.id, error = %e, "Failed to queue approved message");
+    }
+}
@@ -0,0 +1,136 @@
+//! NewEventHandler — puts an approved agent-proposed event on the calendar.
+//!
+//! With a CalDAV server the event goes through the outbox, which creates
+//! it there (mirroring it locally ahead of the next sync) and retries or
+//! raises a Retry/Discard card on failure; without one it is kept in the
+//! local store only.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use tracing::{info, warn};
+
+use super::{ApprovalHandler, CardActionContext, enqueue_outbound};
+use crate::calendar::{EventSource, StoredEvent};
+use crate::cards::model::{ApprovalCard, CardPayload};
+use crate::cards::outbox::Outbox;
+use crate::store::Database;
+
+pub struct NewEventHandler {
+    /// A CalDAV server is configured; the outbox delivers to it.
+    pub caldav: bool,
+    pub outbox: Option<Arc<Outbox>>,
+    pub db: Arc<dyn Database>,
+}
+
+#[async_trait]
+impl ApprovalHandler for NewEventHandler {
+    async fn on_approve(&self, card: &ApprovalCard, ctx: &CardActionContext) {
+        let CardPayload::NewEvent { event, .. } = &card.payload else {
+            return;
+        };
+        if self.caldav {
+            enqueue_outbound(card, self.outbox.as_deref()).await;
+            return;
+        }
+        let stored = StoredEvent::new(event.clone(), EventSource::Local);
+        if let Err(e) = self.db.upsert_calendar_event(&stored).await {
+            warn!(card_id = %card.id, error = %e, "Failed to store created event");
+        }
+        info!(card_id = %card.id, uid = %event.uid, "Calendar event created locally");
+        ctx.queue.mark_sent(card.id).await;
+    }
+
+    async fn on_dismiss(&self, _card: &ApprovalCard, _ctx: &CardActionContext) {
+        // Nothing was created yet
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calendar::CalendarEvent;
+    use crate::calendar::caldav::{CalDavClient, stand_in};
+    use crate::cards::model::{CardSilo, CardStatus};
+    use crate::cards::outbox::ChannelDelivery;
+    use crate::cards::queue::CardQueue;
+    use crate::channels::email::EmailAccounts;
+    use crate::store::LibSqlBackend;
+
+    async fn approve(handler: &NewEventHandler, queue: &Arc<CardQueue>, uid: &str) -> CardStatus {
+        let start = chrono::Utc::now() + chrono::Duration::days(2);
+        let event = CalendarEvent::new(uid, "Call with Alice", start, start + chrono::Duration::minutes(30));
+        let card = ApprovalCard::new_calendar_event(event, vec![]);
+        let card_id = card.id;
+        queue.push(card).await;
+        let card = queue.approve(card_id).await.unwrap();
+        let ctx = CardActionContext {
+            queue: Arc::clone(queue),
+        };
+        handler.on_approve(&card, &ctx).await;
+        if let Some(outbox) = &handler.outbox {
+            outbox.process_due().await.unwrap();
+        }
+        queue.all_cards().await.into_iter().find(|c| c.id == card_id).unwrap().status
+    }
+
+    fn caldav_handler(db: &Arc<dyn Database>, queue: &Arc<CardQueue>, client: CalDavClient) -> NewEventHandler {
+        let delivery = ChannelDelivery::new(EmailAccounts::default(), None)
+            .with_calendar(Arc::new(client), Arc::clone(db));
+        NewEventHandler {
+            caldav: true,
+            outbox: Some(Arc::new(Outbox::new(Arc::clone(db), Arc::clone(queue), Arc::new(delivery)))),
+            db: Arc::clone(db),
+        }
+    }
+
+    #[tokio::test]
+    async fn approved_event_is_created_on_the_server() {
+        let (url, server) = stand_in::start().await;
+        let db: Arc<dyn Database> = Arc::new(LibSqlBackend::new_memory().await.unwrap());
+        let queue = CardQueue::with_db(Arc::clone(&db)).await;
+        let handler = caldav_handler(&db, &queue, CalDavClient::new(&url, "me", "pw"));
+
+        assert_eq!(approve(&handler, &queue, "call-1").await, CardStatus::Sent);
+        assert_eq!(server.resources.lock().unwrap().len(), 1);
+        let stored = db.get_calendar_event("call-1").await.unwrap().unwrap();
+        assert_eq!(stored.source, EventSource::Caldav);
+        assert_eq!(stored.href.as_deref(), Some("/calendars/me/personal/call-1.ics"));
+    }
+
+    #[tokio::test]
+    async fn refused_event_asks_whether_to_retry() {
+        let (url, _server) = stand_in::start().await;
+        let db: Arc<dyn Database> = Arc::new(LibSqlBackend::new_memory().await.unwrap());
+        let queue = CardQueue::with_db(Arc::clone(&db)).await;
+        let handler = caldav_handler(&db, &queue, CalDavClient::new(&url, "me", "wrong"));
+
+        // The server refuses — nothing is stored, and a Retry/Discard card
+        // takes the event's place
+        assert_eq!(approve(&handler, &queue, "call-2").await, CardStatus::Failed);
+        assert!(db.get_calendar_event("call-2").await.unwrap().is_none());
+        let pending = queue.pending().await;
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].silo, CardSilo::Calendar);
+        let CardPayload::Decision { ref question, ref options, .. } = pending[0].payload else {
+            panic!("Expected a Decision card");
+        };
+        assert!(question.starts_with("Couldn't add Call with Alice"));
+        assert_eq!(options, &["Retry", "Discard"]);
+    }
+
+    #[tokio::test]
+    async fn without_caldav_the_event_is_kept_locally() {
+        let db: Arc<dyn Database> = Arc::new(LibSqlBackend::new_memory().await.unwrap());
+        let queue = CardQueue::with_db(Arc::clone(&db)).await;
+        let handler = NewEventHandler {
+            caldav: false,
+            outbox: None,
+            db: Arc::clone(&db),
+        };
+
+        assert_eq!(approve(&handler, &queue, "call-1").await, CardStatus::Sent);
+        let stored = db.get_calendar_event("call-1").await.unwrap().unwrap();
+        assert_eq!(stored.source, EventSource::Local);
+    }
+}
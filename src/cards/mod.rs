@@ -4,5 +4,6 @@ pub mod choice_registry;
 pub mod handlers;
 pub mod reply_drafter;
 pub mod model;
+pub mod outbox;
 pub mod queue;
 pub mod ws;
@@ -5,7 +5,9 @@ use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
 use uuid::Uuid;
 
+use crate::calendar::{CalendarEvent, MeetingResponse};
 use crate::channels::EmailMessage;
+use crate::safety::LeakDetector;
 
 /// A message in an email thread — provides context for reply cards.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -20,6 +22,28 @@ pub struct ThreadMessage {
     pub is_outgoing: bool,
 }
 
+/// A single low-priority message rolled into a digest card.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DigestEntry {
+    /// ID of the row in `digest_items` — used by the "promote to reply" action.
+    pub item_id: String,
+    /// Subject line (email) or thread title, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subject: Option<String>,
+    /// One-line triage summary.
+    pub summary: String,
+    /// When the original message was received.
+    pub received_at: DateTime<Utc>,
+}
+
+/// Digest entries from one sender on one channel.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DigestGroup {
+    pub channel: String,
+    pub sender: String,
+    pub items: Vec<DigestEntry>,
+}
+
 /// Status of an approval card in the queue.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -34,6 +58,12 @@ pub enum CardStatus {
     Expired,
     /// Reply was sent successfully.
     Sent,
+    /// Approved, held in the outbox until its send time — can still be undone.
+    Scheduled,
+    /// Reply is in the outbox, being delivered (or waiting to retry).
+    Sending,
+    /// Delivery gave up; a Decision card asks the user what to do.
+    Failed,
 }
 
 /// Which tab/silo this card belongs to in the iOS UI.
@@ -112,6 +142,10 @@ pub enum CardPayload {
         description: String,
         #[serde(default, skip_serializing_if = "Option::is_none")]
         action_detail: Option<String>,
+        /// Label of the approval rule an "always allow similar" swipe would
+        /// save (tool-approval cards only).
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        always_allow: Option<String>,
     },
     /// Agent needs the user's judgment.
     Decision {
@@ -126,6 +160,36 @@ pub enum CardPayload {
         question: String,
         options: Vec<String>,
     },
+    /// Periodic roll-up of low-priority messages, grouped per channel/sender.
+    /// Individual items can be promoted to a Reply card.
+    Digest {
+        summary: String,
+        groups: Vec<DigestGroup>,
+    },
+    /// A meeting invitation awaiting an answer. Approving sends `response`
+    /// to the organizer as an iTIP email.
+    Meeting {
+        channel: String,
+        /// Our address, as invited.
+        attendee: String,
+        event: CalendarEvent,
+        /// Existing calendar events that clash, ready to display.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        conflicts: Vec<String>,
+        #[serde(default)]
+        response: MeetingResponse,
+        /// Threading headers and account of the invitation email.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        reply_metadata: Option<serde_json::Value>,
+    },
+    /// An event the agent wants to put on the calendar. Approving creates
+    /// it on the CalDAV server.
+    NewEvent {
+        event: CalendarEvent,
+        /// Existing calendar events that clash, ready to display.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        conflicts: Vec<String>,
+    },
 }
 
 impl CardPayload {
@@ -137,13 +201,18 @@ impl CardPayload {
             Self::Action { .. } => "action",
             Self::Decision { .. } => "decision",
             Self::MultipleChoice { .. } => "multiple_choice",
+            Self::Digest { .. } => "digest",
+            Self::Meeting { .. } => "meeting",
+            Self::NewEvent { .. } => "new_event",
         }
     }
 
     /// Extract the channel name if this payload type has one.
     pub fn channel(&self) -> Option<&str> {
         match self {
-            Self::Reply { channel, .. } | Self::Compose { channel, .. } => Some(channel.as_str()),
+            Self::Reply { channel, .. }
+            | Self::Compose { channel, .. }
+            | Self::Meeting { channel, .. } => Some(channel.as_str()),
             _ => None,
         }
     }
@@ -158,6 +227,28 @@ impl CardPayload {
         }
     }
 
+    /// The outbound text (Reply / Compose only).
+    pub fn draft(&self) -> Option<&str> {
+        match self {
+            Self::Reply {
+                suggested_reply, ..
+            } => Some(suggested_reply.as_str()),
+            Self::Compose { draft_body, .. } => Some(draft_body.as_str()),
+            _ => None,
+        }
+    }
+
+    /// Replace the outbound text (Reply / Compose only).
+    pub fn set_draft(&mut self, text: String) {
+        match self {
+            Self::Reply {
+                suggested_reply, ..
+            } => *suggested_reply = text,
+            Self::Compose { draft_body, .. } => *draft_body = text,
+            _ => {}
+        }
+    }
+
     /// Extract the reply metadata (Reply variant only).
     pub fn reply_metadata(&self) -> Option<&serde_json::Value> {
         match self {
@@ -239,6 +330,9 @@ pub struct ApprovalCard {
     /// Associated todo ID (for Action cards created by todo agents).
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub todo_id: Option<Uuid>,
+    /// Warnings shown to the user before approving (e.g. secrets were redacted).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
 }
 
 impl ApprovalCard {
@@ -257,6 +351,7 @@ impl ApprovalCard {
             expires_at: Some(now + chrono::Duration::minutes(expire_minutes as i64)),
             updated_at: now,
             todo_id: None,
+            warnings: Vec::new(),
         }
     }
 
@@ -321,6 +416,7 @@ impl ApprovalCard {
             CardPayload::Action {
                 description: description.into(),
                 action_detail,
+                always_allow: None,
             },
             silo,
             expire_minutes,
@@ -346,6 +442,44 @@ impl ApprovalCard {
         )
     }
 
+    /// Create a Meeting card for an invitation. It stays answerable until
+    /// the meeting starts.
+    pub fn new_meeting(
+        channel: impl Into<String>,
+        attendee: impl Into<String>,
+        event: CalendarEvent,
+        conflicts: Vec<String>,
+    ) -> Self {
+        let starts = event.start;
+        let mut card = Self::new(
+            CardPayload::Meeting {
+                channel: channel.into(),
+                attendee: attendee.into(),
+                event,
+                conflicts,
+                response: MeetingResponse::default(),
+                reply_metadata: None,
+            },
+            CardSilo::Calendar,
+            0,
+        );
+        card.expires_at = Some(starts.max(card.created_at));
+        card
+    }
+
+    /// Create a NewEvent card for an event the agent proposes. Like a
+    /// Meeting card, it expires when the event would have started.
+    pub fn new_calendar_event(event: CalendarEvent, conflicts: Vec<String>) -> Self {
+        let starts = event.start;
+        let mut card = Self::new(
+            CardPayload::NewEvent { event, conflicts },
+            CardSilo::Calendar,
+            0,
+        );
+        card.expires_at = Some(starts.max(card.created_at));
+        card
+    }
+
     /// Create a new MultipleChoice card (no expiry — waits for user response).
     pub fn new_multiple_choice(
         question: impl Into<String>,
@@ -365,6 +499,7 @@ impl ApprovalCard {
             expires_at: None,
             updated_at: now,
             todo_id: None,
+            warnings: Vec::new(),
         }
     }
 
@@ -386,6 +521,36 @@ impl ApprovalCard {
         self
     }
 
+    /// Redact secrets from outbound text (Reply / Compose drafts).
+    ///
+    /// When anything was redacted, a warning is attached so the user reviews
+    /// the draft before approving. Returns `true` if the card changed.
+    pub fn redact_secrets(&mut self, detector: &LeakDetector) -> bool {
+        let text = match self.payload {
+            CardPayload::Reply {
+                ref mut suggested_reply,
+                ..
+            } => suggested_reply,
+            CardPayload::Compose {
+                ref mut draft_body, ..
+            } => draft_body,
+            _ => return false,
+        };
+        let scan = detector.scan(text);
+        if scan.is_clean() {
+            return false;
+        }
+        *text = scan.content;
+        let warning = format!(
+            "Secrets were redacted from this draft ({}). Review before approving.",
+            scan.findings.join(", ")
+        );
+        if !self.warnings.contains(&warning) {
+            self.warnings.push(warning);
+        }
+        true
+    }
+
     /// Set the email thread (Reply variant only).
     pub fn with_email_thread(mut self, email_thread: Vec<EmailMessage>) -> Self {
         if let CardPayload::Reply {
@@ -409,11 +574,15 @@ impl ApprovalCard {
         self
     }
 
-    /// Set reply metadata (Reply variant only).
+    /// Set reply metadata (Reply and Meeting variants).
     pub fn with_reply_metadata(mut self, metadata: serde_json::Value) -> Self {
         if let CardPayload::Reply {
             reply_metadata: ref mut rm,
             ..
+        }
+        | CardPayload::Meeting {
+            reply_metadata: ref mut rm,
+            ..
         } = self.payload
         {
             *rm = Some(metadata);
@@ -461,6 +630,27 @@ pub enum CardAction {
     Refine { card_id: Uuid, instruction: String },
     /// Select an option from a multiple-choice card.
     SelectOption { card_id: Uuid, selected_index: usize },
+    /// Turn one item of a digest card into its own Reply card.
+    PromoteDigestItem { card_id: Uuid, item_id: String },
+    /// Approve a tool-approval Action card and save an approval rule so
+    /// similar calls run without asking.
+    AlwaysAllow { card_id: Uuid },
+    /// Take back an approved message while it is still `Scheduled`; the
+    /// card returns to `Pending`.
+    Undo { card_id: Uuid },
+    /// Approve a Reply/Compose card, sending it at `send_at` instead of
+    /// after the usual undo window.
+    Schedule {
+        card_id: Uuid,
+        send_at: DateTime<Utc>,
+    },
+    /// Answer a Meeting card — accept, tentative, decline, or propose a
+    /// new time — and send it. Plain `Approve` accepts.
+    RespondToMeeting {
+        card_id: Uuid,
+        #[serde(flatten)]
+        response: MeetingResponse,
+    },
 }
 
 /// Messages sent over WebSocket (server → client and internal events).
@@ -475,8 +665,12 @@ pub enum WsMessage {
     CardExpired { id: Uuid },
     /// Full queue sync (sent on connect).
     CardsSync { cards: Vec<ApprovalCard> },
-    /// A card was refined — full updated card for the client to replace in-place.
+    /// A card was refined (or restored by undo) — full updated card for the
+    /// client to replace in-place.
     CardRefreshed { card: ApprovalCard },
+    /// An approved message is held until `send_at`; the client shows a
+    /// countdown with an undo button until then.
+    CardScheduled { id: Uuid, send_at: DateTime<Utc> },
     /// Badge counts per silo — broadcast on every card state change.
     SiloCounts {
         counts: SiloCounts,
@@ -583,6 +777,40 @@ mod tests {
         assert_eq!(parsed.card_type_str(), "decision");
     }
 
+    #[test]
+    fn meeting_card_lives_in_calendar_silo_until_the_meeting() {
+        let start = Utc::now() + chrono::Duration::days(2);
+        let event = CalendarEvent::new("uid-1", "Planning", start, start + chrono::Duration::hours(1));
+        let card = ApprovalCard::new_meeting("email", "me@x.com", event, vec![]);
+        assert_eq!(card.silo, CardSilo::Calendar);
+        assert_eq!(card.card_type_str(), "meeting");
+        assert_eq!(card.expires_at, Some(start));
+
+        let json = serde_json::to_string(&card).unwrap();
+        assert!(json.contains("\"card_type\":\"meeting\""));
+        let parsed: ApprovalCard = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.payload.channel(), Some("email"));
+    }
+
+    #[test]
+    fn respond_to_meeting_action_is_flat() {
+        let card_id = Uuid::new_v4();
+        let json = format!(
+            r#"{{"action":"respond_to_meeting","card_id":"{card_id}","response":"propose","start":"2026-03-02T14:00:00Z","end":"2026-03-02T15:00:00Z"}}"#
+        );
+        match serde_json::from_str::<CardAction>(&json).unwrap() {
+            CardAction::RespondToMeeting { response: MeetingResponse::Propose { start, .. }, .. } => {
+                assert_eq!(start.to_rfc3339(), "2026-03-02T14:00:00+00:00");
+            }
+            other => panic!("Expected RespondToMeeting, got {other:?}"),
+        }
+        let decline = format!(r#"{{"action":"respond_to_meeting","card_id":"{card_id}","response":"decline"}}"#);
+        assert!(matches!(
+            serde_json::from_str::<CardAction>(&decline).unwrap(),
+            CardAction::RespondToMeeting { response: MeetingResponse::Decline, .. }
+        ));
+    }
+
     // ── CardSilo tests ──────────────────────────────────────────────
 
     #[test]
@@ -842,7 +1070,7 @@ mod tests {
         let card = ApprovalCard::new_action("run deploy", Some("kubectl apply -f".into()), CardSilo::Todos, 60);
         assert_eq!(card.card_type_str(), "action");
         assert_eq!(card.silo, CardSilo::Todos);
-        if let CardPayload::Action { description, action_detail } = &card.payload {
+        if let CardPayload::Action { description, action_detail, .. } = &card.payload {
             assert_eq!(description, "run deploy");
             assert_eq!(action_detail.as_deref(), Some("kubectl apply -f"));
         } else {
@@ -1051,6 +1279,42 @@ mod tests {
         assert!(card.todo_id.is_none());
     }
 
+    #[test]
+    fn redact_secrets_flags_reply_card() {
+        let mut card = ApprovalCard::new_reply(
+            "email",
+            "alice",
+            "what's the key?",
+            "Here it is: sk-ant-REDACTED",
+            0.9,
+            "c",
+            15,
+        );
+        assert!(card.redact_secrets(&LeakDetector::new()));
+        assert_eq!(
+            card.payload.suggested_reply().unwrap(),
+            "Here it is: [REDACTED:anthropic_api_key]"
+        );
+        assert_eq!(card.warnings.len(), 1);
+        // Second pass is a no-op.
+        assert!(!card.redact_secrets(&LeakDetector::new()));
+        assert_eq!(card.warnings.len(), 1);
+    }
+
+    #[test]
+    fn redact_secrets_compose_and_clean_cards() {
+        let detector = LeakDetector::new().with_known_secrets(["imap-pass-123"]);
+        let mut compose = ApprovalCard::new_compose("email", "bob", None, "pw is imap-pass-123", 0.8, 15);
+        assert!(compose.redact_secrets(&detector));
+        assert!(compose.warnings[0].contains("configured_secret"));
+
+        let mut clean = ApprovalCard::new_reply("email", "a", "m", "See you Tuesday!", 0.9, "c", 15);
+        assert!(!clean.redact_secrets(&detector));
+        assert!(clean.warnings.is_empty());
+        let json = serde_json::to_string(&clean).unwrap();
+        assert!(!json.contains("warnings"));
+    }
+
     #[test]
     fn with_todo_id_sets_id() {
         let todo_id = Uuid::new_v4();
@@ -0,0 +1,1188 @@
+//! Outbox — durable delivery of approved outbound messages.
+//!
+//! Flow:
+//! 1. Approving a Reply, Compose or Meeting card — or a NewEvent card when
+//!    a CalDAV server is configured — enqueues an `OutboxJob` holding a
+//!    snapshot of the card. The job is held `Scheduled` for the undo window
+//!    (or until an explicit send time), during which `undo` puts the card
+//!    back to `Pending`; with no window the card goes straight to `Sending`
+//! 2. A background worker delivers due jobs — SMTP on a blocking thread,
+//!    chat replies through the `ChannelManager`, new events with a CalDAV
+//!    PUT — retrying transient failures with exponential backoff
+//! 3. Success marks the card `Sent` (and, for a meeting RSVP, records our
+//!    answer on the stored event). When retries run out (or the failure
+//!    can't be retried) the card becomes `Failed` and a Decision card asks
+//!    the user to retry (approve), edit the text and retry (edit), or
+//!    discard (dismiss)
+//!
+//! Jobs caught mid-send by a restart are queued again on startup, so a
+//! message may — rarely — be delivered twice, but never silently dropped.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Notify;
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use super::model::{ApprovalCard, CardPayload, CardSilo};
+use super::queue::CardQueue;
+use crate::calendar::caldav::CalDavClient;
+use crate::calendar::{
+    Attendee, CalendarEvent, EventSource, MeetingResponse, StoredEvent, ics, invite,
+};
+use crate::channels::email::{
+    APPROVED_FLAGS, EmailAccounts, flag_original_message, send_calendar_reply, send_new_email,
+    send_reply_email,
+};
+use crate::channels::{ChannelManager, OutgoingResponse};
+use crate::error::{CalendarError, DatabaseError};
+use crate::store::Database;
+
+/// Failed-delivery cards stay up for a week.
+const FAILED_CARD_EXPIRE_MINUTES: u32 = 7 * 24 * 60;
+
+/// How often the worker looks for due retries (it also wakes on enqueue).
+pub const POLL_INTERVAL_SECS: u64 = 10;
+
+/// Jobs picked up per worker pass.
+const BATCH_SIZE: i64 = 20;
+
+/// Lifecycle of an outbox job.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutboxStatus {
+    /// Held until `next_attempt_at` (undo window or send-at time); not yet
+    /// attempted, so it can still be cancelled.
+    Scheduled,
+    /// Waiting for `next_attempt_at`.
+    Queued,
+    /// A delivery attempt is in flight.
+    Sending,
+    Sent,
+    /// Out of retries; waiting on the user's Decision card.
+    Failed,
+    /// The user gave up on it.
+    Discarded,
+}
+
+impl OutboxStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Scheduled => "scheduled",
+            Self::Queued => "queued",
+            Self::Sending => "sending",
+            Self::Sent => "sent",
+            Self::Failed => "failed",
+            Self::Discarded => "discarded",
+        }
+    }
+}
+
+impl std::str::FromStr for OutboxStatus {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "scheduled" => Ok(Self::Scheduled),
+            "queued" => Ok(Self::Queued),
+            "sending" => Ok(Self::Sending),
+            "sent" => Ok(Self::Sent),
+            "failed" => Ok(Self::Failed),
+            "discarded" => Ok(Self::Discarded),
+            _ => Err(format!("Unknown outbox status: {s}")),
+        }
+    }
+}
+
+/// One outbound message, persisted in `outbox`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutboxJob {
+    pub id: Uuid,
+    /// The Reply/Compose card being delivered.
+    pub card_id: Uuid,
+    /// Snapshot of the card at approval — what actually gets sent.
+    pub card: ApprovalCard,
+    pub status: OutboxStatus,
+    /// Delivery attempts so far (reset by a retry from the Decision card).
+    pub attempts: u32,
+    pub next_attempt_at: DateTime<Utc>,
+    pub last_error: Option<String>,
+    /// Decision card raised when the job failed.
+    pub decision_card_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl OutboxJob {
+    /// A job for `card`, due immediately.
+    pub fn new(card: ApprovalCard) -> Self {
+        let now = Utc::now();
+        Self {
+            id: Uuid::new_v4(),
+            card_id: card.id,
+            card,
+            status: OutboxStatus::Queued,
+            attempts: 0,
+            next_attempt_at: now,
+            last_error: None,
+            decision_card_id: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+}
+
+/// How many times to try, and how long to wait between tries.
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    /// Delay after the first failure; doubled after each one after that.
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            base_delay: Duration::from_secs(30),
+            max_delay: Duration::from_secs(30 * 60),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Wait before the attempt after `attempts` failed ones.
+    pub fn backoff(&self, attempts: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempts.saturating_sub(1));
+        self.base_delay.saturating_mul(factor).min(self.max_delay)
+    }
+}
+
+/// Why a delivery attempt failed.
+#[derive(Debug, Clone)]
+pub struct DeliveryError {
+    pub reason: String,
+    /// Retrying can't help (e.g. no account configured) — fail right away.
+    pub permanent: bool,
+}
+
+impl DeliveryError {
+    pub fn transient(reason: impl Into<String>) -> Self {
+        Self {
+            reason: reason.into(),
+            permanent: false,
+        }
+    }
+
+    pub fn permanent(reason: impl Into<String>) -> Self {
+        Self {
+            reason: reason.into(),
+            permanent: true,
+        }
+    }
+}
+
+/// Sends one approved card's message.
+#[async_trait]
+pub trait Deliver: Send + Sync {
+    async fn deliver(&self, card: &ApprovalCard) -> Result<(), DeliveryError>;
+}
+
+/// Production delivery: email over SMTP, new events to the CalDAV server,
+/// everything else through the originating chat channel.
+pub struct ChannelDelivery {
+    email_accounts: EmailAccounts,
+    channels: Option<Arc<ChannelManager>>,
+    /// Where NewEvent cards are created, and the store they're mirrored to.
+    calendar: Option<(Arc<CalDavClient>, Arc<dyn Database>)>,
+}
+
+impl ChannelDelivery {
+    pub fn new(email_accounts: EmailAccounts, channels: Option<Arc<ChannelManager>>) -> Self {
+        Self {
+            email_accounts,
+            channels,
+            calendar: None,
+        }
+    }
+
+    /// Create approved events on `client`, mirroring them into `db` ahead
+    /// of the next sync (builder pattern).
+    pub fn with_calendar(mut self, client: Arc<CalDavClient>, db: Arc<dyn Database>) -> Self {
+        self.calendar = Some((client, db));
+        self
+    }
+
+    async fn email_reply(
+        &self,
+        card: &ApprovalCard,
+        meta: &serde_json::Value,
+        body: String,
+    ) -> Result<(), DeliveryError> {
+        if self.email_accounts.is_empty() {
+            return Err(DeliveryError::permanent("No email accounts configured"));
+        }
+        let accounts = self.email_accounts.clone();
+        let owned_meta = meta.clone();
+        run_blocking(move || send_reply_email(&accounts, &owned_meta, &body)).await?;
+
+        // The reply went out; flag failures only warn.
+        if let Err(e) = flag_original_message(&self.email_accounts, meta, APPROVED_FLAGS).await {
+            warn!(card_id = %card.id, error = %e, "Failed to update IMAP flags");
+        }
+        Ok(())
+    }
+
+    async fn chat_reply(
+        &self,
+        channel: &str,
+        meta: &serde_json::Value,
+        conversation_id: &str,
+        body: String,
+    ) -> Result<(), DeliveryError> {
+        let Some(channels) = &self.channels else {
+            return Err(DeliveryError::permanent("No chat channels configured"));
+        };
+        let Some(user_id) = meta.get("user_id").and_then(|v| v.as_str()) else {
+            return Err(DeliveryError::permanent("No user_id in reply_metadata"));
+        };
+        channels
+            .broadcast(channel, user_id, chat_response(meta, conversation_id, body))
+            .await
+            .map_err(|e| DeliveryError::transient(e.to_string()))
+    }
+}
+
+#[async_trait]
+impl Deliver for ChannelDelivery {
+    async fn deliver(&self, card: &ApprovalCard) -> Result<(), DeliveryError> {
+        match &card.payload {
+            CardPayload::Reply {
+                channel,
+                reply_metadata,
+                suggested_reply,
+                conversation_id,
+                ..
+            } => {
+                let meta = reply_metadata.as_ref().unwrap_or(&serde_json::Value::Null);
+                let body = suggested_reply.clone();
+                if channel == "email" {
+                    if meta.is_null() {
+                        return Err(DeliveryError::permanent("Missing reply_metadata"));
+                    }
+                    self.email_reply(card, meta, body).await
+                } else {
+                    self.chat_reply(channel, meta, conversation_id, body).await
+                }
+            }
+            CardPayload::Compose {
+                channel,
+                recipient,
+                subject,
+                draft_body,
+                ..
+            } => {
+                if channel != "email" {
+                    return Err(DeliveryError::permanent(format!(
+                        "Composing new messages on {channel} is not supported"
+                    )));
+                }
+                let Some(config) = self.email_accounts.default_account().cloned() else {
+                    return Err(DeliveryError::permanent("No email accounts configured"));
+                };
+                let subject = subject.clone().unwrap_or_else(|| "AI Assist".into());
+                let recipient = recipient.clone();
+                let body = draft_body.clone();
+                run_blocking(move || send_new_email(&config, &recipient, &subject, &body)).await
+            }
+            CardPayload::Meeting {
+                attendee,
+                event,
+                response,
+                reply_metadata,
+                ..
+            } => {
+                let Some(organizer) = event.organizer.as_ref().map(|o| o.email.clone()) else {
+                    return Err(DeliveryError::permanent("Invitation has no organizer to reply to"));
+                };
+                if self.email_accounts.is_empty() {
+                    return Err(DeliveryError::permanent("No email accounts configured"));
+                }
+                let me = event
+                    .attendee(attendee)
+                    .cloned()
+                    .unwrap_or_else(|| Attendee::new(attendee.as_str()));
+                let (method, ics) = ics::itip_response(event, &me, response);
+                let subject = format!("{}: {}", response.verb(), event.summary);
+                let body = match response {
+                    MeetingResponse::Propose { start, end } => format!(
+                        "{} proposed a new time for \"{}\": {}",
+                        me.display(),
+                        event.summary,
+                        invite::when(&CalendarEvent {
+                            start: *start,
+                            end: *end,
+                            all_day: false,
+                            ..event.clone()
+                        })
+                    ),
+                    _ => format!("{} — {}: {}", me.display(), response.verb(), event.summary),
+                };
+                let accounts = self.email_accounts.clone();
+                let meta = reply_metadata.clone().unwrap_or(serde_json::Value::Null);
+                run_blocking(move || {
+                    send_calendar_reply(&accounts, &meta, &organizer, &subject, &body, method, &ics)
+                })
+                .await
+            }
+            CardPayload::NewEvent { event, .. } => {
+                let Some((client, db)) = &self.calendar else {
+                    return Err(DeliveryError::permanent("No CalDAV server configured"));
+                };
+                let remote = client.put_event(event).await.map_err(calendar_error)?;
+                let stored = StoredEvent {
+                    href: Some(remote.href),
+                    etag: remote.etag,
+                    ..StoredEvent::new(event.clone(), EventSource::Caldav)
+                };
+                // The event is on the server now; the next sync mirrors it if this fails.
+                if let Err(e) = db.upsert_calendar_event(&stored).await {
+                    warn!(card_id = %card.id, error = %e, "Failed to store created event");
+                }
+                Ok(())
+            }
+            _ => Err(DeliveryError::permanent(format!(
+                "{} cards have nothing to send",
+                card.payload.card_type_str()
+            ))),
+        }
+    }
+}
+
+/// Server errors other than timeouts and rate limits won't go away by
+/// retrying; network errors might.
+fn calendar_error(e: CalendarError) -> DeliveryError {
+    match e {
+        CalendarError::Server { status, .. }
+            if (400..500).contains(&status) && status != 408 && status != 429 =>
+        {
+            DeliveryError::permanent(e.to_string())
+        }
+        CalendarError::Parse(_) => DeliveryError::permanent(e.to_string()),
+        _ => DeliveryError::transient(e.to_string()),
+    }
+}
+
+/// Run a synchronous SMTP send off the async runtime.
+async fn run_blocking<E: std::fmt::Display + Send + 'static>(
+    send: impl FnOnce() -> Result<(), E> + Send + 'static,
+) -> Result<(), DeliveryError> {
+    match tokio::task::spawn_blocking(move || send().map_err(|e| e.to_string())).await {
+        Ok(result) => result.map_err(DeliveryError::transient),
+        Err(e) => Err(DeliveryError::transient(format!("Send task failed: {e}"))),
+    }
+}
+
+/// The outgoing chat reply: in the card's conversation, addressed to the
+/// original chat and — where the channel threads replies — to the original
+/// message.
+fn chat_response(
+    meta: &serde_json::Value,
+    conversation_id: &str,
+    body: String,
+) -> OutgoingResponse {
+    let mut metadata = serde_json::json!({});
+    if let Some(chat_id) = meta.get("chat_id").filter(|v| !v.is_null()) {
+        metadata["chat_id"] = chat_id.clone();
+    }
+    if let Some(message_id) = meta.get("message_id").filter(|v| !v.is_null()) {
+        metadata["reply_to_message_id"] = message_id.clone();
+    }
+    OutgoingResponse {
+        metadata,
+        ..OutgoingResponse::text(body).in_thread(conversation_id)
+    }
+}
+
+/// What the user chose on a failed-delivery Decision card.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Resolution {
+    Retry,
+    /// Replace the message text, then retry.
+    Edit(String),
+    Discard,
+}
+
+/// Durable queue of outbound messages and the logic that delivers them.
+pub struct Outbox {
+    db: Arc<dyn Database>,
+    card_queue: Arc<CardQueue>,
+    delivery: Arc<dyn Deliver>,
+    policy: RetryPolicy,
+    /// How long an approved message is held before it is sent.
+    undo_window: Duration,
+    /// Wakes the worker as soon as a job is due.
+    wake: Arc<Notify>,
+}
+
+impl Outbox {
+    pub fn new(
+        db: Arc<dyn Database>,
+        card_queue: Arc<CardQueue>,
+        delivery: Arc<dyn Deliver>,
+    ) -> Self {
+        Self {
+            db,
+            card_queue,
+            delivery,
+            policy: RetryPolicy::default(),
+            undo_window: Duration::ZERO,
+            wake: Arc::new(Notify::new()),
+        }
+    }
+
+    /// Override the retry policy (builder pattern).
+    pub fn with_policy(mut self, policy: RetryPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Hold approved messages for `window` before sending (builder pattern).
+    pub fn with_undo_window(mut self, window: Duration) -> Self {
+        self.undo_window = window;
+        self
+    }
+
+    /// Queue an approved card's message for delivery after the undo window.
+    pub async fn enqueue(&self, card: &ApprovalCard) -> Result<OutboxJob, DatabaseError> {
+        let send_at =
+            Utc::now() + chrono::Duration::from_std(self.undo_window).unwrap_or_default();
+        self.schedule(card, send_at).await
+    }
+
+    /// Queue an approved card's message to be sent at `send_at`. A time in
+    /// the past sends right away.
+    pub async fn schedule(
+        &self,
+        card: &ApprovalCard,
+        send_at: DateTime<Utc>,
+    ) -> Result<OutboxJob, DatabaseError> {
+        let mut job = OutboxJob::new(card.clone());
+        let delay = (send_at - Utc::now()).to_std().unwrap_or_default();
+        if delay.is_zero() {
+            self.db.insert_outbox_job(&job).await?;
+            self.card_queue.mark_sending(card.id).await;
+            info!(card_id = %card.id, job_id = %job.id, "Outbound message queued");
+            self.wake.notify_one();
+            return Ok(job);
+        }
+
+        job.status = OutboxStatus::Scheduled;
+        job.next_attempt_at = send_at;
+        self.db.insert_outbox_job(&job).await?;
+        self.card_queue.mark_scheduled(card.id, send_at).await;
+        info!(card_id = %card.id, job_id = %job.id, %send_at, "Outbound message scheduled");
+
+        // Wake the worker right on time rather than at the next poll.
+        let wake = Arc::clone(&self.wake);
+        tokio::spawn(async move {
+            tokio::time::sleep(delay).await;
+            wake.notify_one();
+        });
+        Ok(job)
+    }
+
+    /// Take back a scheduled message. Returns the card (now `Pending`
+    /// again), or `None` if it is no longer scheduled — already sending,
+    /// sent, or never queued.
+    pub async fn undo(&self, card_id: Uuid) -> Result<Option<ApprovalCard>, DatabaseError> {
+        if !self.db.cancel_scheduled_outbox_job(card_id).await? {
+            return Ok(None);
+        }
+        info!(card_id = %card_id, "Scheduled outbound message undone");
+        Ok(self.card_queue.undo(card_id).await)
+    }
+
+    /// Put jobs interrupted mid-send (by a crash or restart) back in the queue.
+    pub async fn recover(&self) -> Result<usize, DatabaseError> {
+        let recovered = self.db.requeue_sending_outbox_jobs().await?;
+        if recovered > 0 {
+            warn!(count = recovered, "Requeued outbound messages interrupted mid-send");
+        }
+        Ok(recovered)
+    }
+
+    /// Attempt every job that is due. Returns how many were attempted.
+    pub async fn process_due(&self) -> Result<usize, DatabaseError> {
+        let jobs = self.db.due_outbox_jobs(Utc::now(), BATCH_SIZE).await?;
+        let count = jobs.len();
+        for job in jobs {
+            self.attempt(job).await?;
+        }
+        Ok(count)
+    }
+
+    async fn attempt(&self, mut job: OutboxJob) -> Result<(), DatabaseError> {
+        let was_scheduled = job.status == OutboxStatus::Scheduled;
+        job.status = OutboxStatus::Sending;
+        job.attempts += 1;
+        job.updated_at = Utc::now();
+        if !self.db.claim_outbox_job(&job).await? {
+            // Undone (or picked up elsewhere) since it was read.
+            return Ok(());
+        }
+        if was_scheduled {
+            self.card_queue.mark_sending(job.card_id).await;
+        }
+
+        match self.delivery.deliver(&job.card).await {
+            Ok(()) => {
+                job.status = OutboxStatus::Sent;
+                job.last_error = None;
+                job.updated_at = Utc::now();
+                self.db.update_outbox_job(&job).await?;
+                self.card_queue.mark_sent(job.card_id).await;
+                if let CardPayload::Meeting {
+                    attendee,
+                    event,
+                    response,
+                    ..
+                } = &job.card.payload
+                {
+                    invite::record_response(self.db.as_ref(), attendee, event, response).await;
+                }
+                info!(card_id = %job.card_id, attempts = job.attempts, "Outbound message delivered");
+            }
+            Err(e) if !e.permanent && job.attempts < self.policy.max_attempts => {
+                let delay = self.policy.backoff(job.attempts);
+                job.status = OutboxStatus::Queued;
+                job.next_attempt_at = Utc::now()
+                    + chrono::Duration::from_std(delay).unwrap_or_else(|_| chrono::Duration::zero());
+                job.last_error = Some(e.reason.clone());
+                job.updated_at = Utc::now();
+                self.db.update_outbox_job(&job).await?;
+                warn!(
+                    card_id = %job.card_id,
+                    attempts = job.attempts,
+                    retry_in_secs = delay.as_secs(),
+                    error = %e.reason,
+                    "Outbound message failed; will retry"
+                );
+            }
+            Err(e) => {
+                let card = failed_card(&job, &e.reason);
+                job.status = OutboxStatus::Failed;
+                job.last_error = Some(e.reason.clone());
+                job.decision_card_id = Some(card.id);
+                job.updated_at = Utc::now();
+                self.db.update_outbox_job(&job).await?;
+                self.card_queue.mark_failed(job.card_id).await;
+                self.card_queue.push(card).await;
+                error!(
+                    card_id = %job.card_id,
+                    attempts = job.attempts,
+                    error = %e.reason,
+                    "Outbound message failed"
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Apply the user's choice on a failed-delivery Decision card.
+    ///
+    /// Returns `false` when the card isn't one of ours.
+    pub async fn resolve(
+        &self,
+        decision_card_id: Uuid,
+        resolution: Resolution,
+    ) -> Result<bool, DatabaseError> {
+        let Some(mut job) = self.db.get_outbox_job_by_decision_card(decision_card_id).await? else {
+            return Ok(false);
+        };
+        if job.status != OutboxStatus::Failed {
+            return Ok(true);
+        }
+
+        match resolution {
+            Resolution::Discard => {
+                job.status = OutboxStatus::Discarded;
+                info!(card_id = %job.card_id, "Failed outbound message discarded");
+            }
+            Resolution::Retry | Resolution::Edit(_) => {
+                if let Resolution::Edit(text) = resolution
+                    && self.card_queue.set_edited_draft(&mut job.card, text).await
+                {
+                    // Offer the choice again so the redacted text is only
+                    // sent once the user has seen it.
+                    let mut card = failed_card(&job, "secrets were redacted from your edit");
+                    card.warnings = job.card.warnings.clone();
+                    job.decision_card_id = Some(card.id);
+                    job.updated_at = Utc::now();
+                    self.db.update_outbox_job(&job).await?;
+                    self.card_queue.push(card).await;
+                    warn!(card_id = %job.card_id, "Edited outbound message redacted; not retrying");
+                    return Ok(true);
+                }
+                job.status = OutboxStatus::Queued;
+                job.attempts = 0;
+                job.next_attempt_at = Utc::now();
+                info!(card_id = %job.card_id, "Failed outbound message queued for retry");
+            }
+        }
+        job.updated_at = Utc::now();
+        self.db.update_outbox_job(&job).await?;
+        if job.status == OutboxStatus::Queued {
+            self.card_queue.mark_sending(job.card_id).await;
+            self.wake.notify_one();
+        }
+        Ok(true)
+    }
+}
+
+/// Build the Decision card offering to retry, edit or discard a failed message.
+fn failed_card(job: &OutboxJob, reason: &str) -> ApprovalCard {
+    if let CardPayload::NewEvent { event, .. } = &job.card.payload {
+        return ApprovalCard::new_decision(
+            format!("Couldn't add {} to your calendar", invite::describe(event)),
+            format!(
+                "The calendar server refused it after {} attempt(s): {reason}. Approve to \
+                 retry, or dismiss to discard it.",
+                job.attempts
+            ),
+            vec!["Retry".into(), "Discard".into()],
+            CardSilo::Calendar,
+            FAILED_CARD_EXPIRE_MINUTES,
+        );
+    }
+    let (what, to) = match &job.card.payload {
+        CardPayload::Reply {
+            channel,
+            source_sender,
+            ..
+        } => ("reply", format!("{source_sender} ({channel})")),
+        CardPayload::Compose {
+            channel, recipient, ..
+        } => ("message", format!("{recipient} ({channel})")),
+        CardPayload::Meeting { event, .. } => (
+            "meeting response",
+            event
+                .organizer
+                .as_ref()
+                .map_or_else(|| "the organizer".to_string(), |o| o.display().to_string()),
+        ),
+        _ => ("message", "its recipient".to_string()),
+    };
+    ApprovalCard::new_decision(
+        format!("Couldn't send your {what} to {to}"),
+        format!(
+            "Delivery failed after {} attempt(s): {reason}. Approve to retry, edit to change \
+             the text and retry, or dismiss to discard it.",
+            job.attempts
+        ),
+        vec!["Retry".into(), "Edit".into(), "Discard".into()],
+        CardSilo::Messages,
+        FAILED_CARD_EXPIRE_MINUTES,
+    )
+}
+
+/// Spawn the background worker that delivers due outbox jobs.
+///
+/// Recovers interrupted jobs first, then runs every `interval_secs` and
+/// whenever a job is enqueued. Returns a `JoinHandle` and shutdown flag.
+pub fn spawn_outbox_worker(
+    outbox: Arc<Outbox>,
+    interval_secs: u64,
+) -> (JoinHandle<()>, Arc<AtomicBool>) {
+    let shutdown = Arc::new(AtomicBool::new(false));
+    let shutdown_flag = Arc::clone(&shutdown);
+
+    let handle = tokio::spawn(async move {
+        info!("Outbox worker started — polling every {interval_secs}s");
+        if let Err(e) = outbox.recover().await {
+            error!(error = %e, "Outbox recovery failed");
+        }
+        let mut tick = tokio::time::interval(Duration::from_secs(interval_secs.max(1)));
+
+        loop {
+            tokio::select! {
+                _ = tick.tick() => {}
+                _ = outbox.wake.notified() => {}
+            }
+
+            if shutdown.load(Ordering::Relaxed) {
+                info!("Outbox worker shutting down");
+                return;
+            }
+
+            if let Err(e) = outbox.process_due().await {
+                error!(error = %e, "Outbox pass failed");
+            }
+        }
+    });
+
+    (handle, shutdown_flag)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    use crate::cards::model::{CardStatus, WsMessage};
+    use crate::channels::{Channel, IncomingMessage, MessageStream};
+    use crate::error::ChannelError;
+    use crate::store::LibSqlBackend;
+
+    /// Fails the first `failures` deliveries, then succeeds.
+    struct ScriptedDelivery {
+        failures: Mutex<u32>,
+        permanent: bool,
+        sent: Mutex<Vec<String>>,
+    }
+
+    impl ScriptedDelivery {
+        fn new(failures: u32, permanent: bool) -> Arc<Self> {
+            Arc::new(Self {
+                failures: Mutex::new(failures),
+                permanent,
+                sent: Mutex::new(Vec::new()),
+            })
+        }
+    }
+
+    #[async_trait]
+    impl Deliver for ScriptedDelivery {
+        async fn deliver(&self, card: &ApprovalCard) -> Result<(), DeliveryError> {
+            let mut failures = self.failures.lock().unwrap();
+            if *failures > 0 {
+                *failures -= 1;
+                return Err(DeliveryError {
+                    reason: "SMTP 451 try again later".into(),
+                    permanent: self.permanent,
+                });
+            }
+            let text = card.payload.suggested_reply().unwrap_or_default().to_string();
+            self.sent.lock().unwrap().push(text);
+            Ok(())
+        }
+    }
+
+    /// No waiting between attempts.
+    fn instant(max_attempts: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts,
+            base_delay: Duration::ZERO,
+            max_delay: Duration::ZERO,
+        }
+    }
+
+    async fn setup(
+        delivery: Arc<ScriptedDelivery>,
+        policy: RetryPolicy,
+    ) -> (Arc<CardQueue>, Outbox, ApprovalCard) {
+        let db: Arc<dyn Database> = Arc::new(LibSqlBackend::new_memory().await.unwrap());
+        let queue = CardQueue::with_db(Arc::clone(&db)).await;
+        let outbox = Outbox::new(db, Arc::clone(&queue), delivery).with_policy(policy);
+
+        let card = ApprovalCard::new_reply("email", "alice@x.com", "lunch?", "Sure", 0.9, "c1", 15);
+        let card_id = card.id;
+        queue.push(card).await;
+        let card = queue.approve(card_id).await.unwrap();
+        (queue, outbox, card)
+    }
+
+    async fn status(queue: &CardQueue, card_id: Uuid) -> CardStatus {
+        queue
+            .all_cards()
+            .await
+            .into_iter()
+            .find(|c| c.id == card_id)
+            .unwrap()
+            .status
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_the_cap() {
+        let policy = RetryPolicy::default();
+        assert_eq!(policy.backoff(1), Duration::from_secs(30));
+        assert_eq!(policy.backoff(2), Duration::from_secs(60));
+        assert_eq!(policy.backoff(4), Duration::from_secs(240));
+        assert_eq!(policy.backoff(20), Duration::from_secs(30 * 60));
+    }
+
+    #[tokio::test]
+    async fn transient_failure_is_retried_until_sent() {
+        let delivery = ScriptedDelivery::new(2, false);
+        let (queue, outbox, card) = setup(Arc::clone(&delivery), instant(5)).await;
+
+        outbox.enqueue(&card).await.unwrap();
+        assert_eq!(status(&queue, card.id).await, CardStatus::Sending);
+
+        for _ in 0..3 {
+            assert_eq!(outbox.process_due().await.unwrap(), 1);
+        }
+        assert_eq!(status(&queue, card.id).await, CardStatus::Sent);
+        assert_eq!(*delivery.sent.lock().unwrap(), vec!["Sure"]);
+        assert_eq!(outbox.process_due().await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn undo_window_holds_the_message() {
+        let delivery = ScriptedDelivery::new(0, false);
+        let (queue, outbox, card) = setup(Arc::clone(&delivery), instant(5)).await;
+        let outbox = outbox.with_undo_window(Duration::from_secs(60));
+        let mut rx = queue.subscribe();
+
+        outbox.enqueue(&card).await.unwrap();
+        assert_eq!(status(&queue, card.id).await, CardStatus::Scheduled);
+        assert_eq!(outbox.process_due().await.unwrap(), 0);
+        assert!(delivery.sent.lock().unwrap().is_empty());
+
+        let mut countdown = None;
+        while let Ok(msg) = rx.try_recv() {
+            if let WsMessage::CardScheduled { id, send_at } = msg {
+                countdown = Some((id, send_at));
+            }
+        }
+        let (id, send_at) = countdown.expect("clients are told when it sends");
+        assert_eq!(id, card.id);
+        assert!(send_at > Utc::now() + chrono::Duration::seconds(50));
+    }
+
+    #[tokio::test]
+    async fn undo_returns_the_card_to_pending() {
+        let delivery = ScriptedDelivery::new(0, false);
+        let (queue, outbox, card) = setup(Arc::clone(&delivery), instant(5)).await;
+        let outbox = outbox.with_undo_window(Duration::from_secs(60));
+
+        outbox.enqueue(&card).await.unwrap();
+        let restored = outbox.undo(card.id).await.unwrap().unwrap();
+        assert_eq!(restored.status, CardStatus::Pending);
+        assert_eq!(queue.pending().await.len(), 1);
+
+        // Nothing left to send, and a second undo has nothing to take back
+        let later = Utc::now() + chrono::Duration::minutes(5);
+        assert!(outbox.db.due_outbox_jobs(later, 10).await.unwrap().is_empty());
+        assert!(outbox.undo(card.id).await.unwrap().is_none());
+
+        // The card can be approved again
+        let card = queue.approve(card.id).await.unwrap();
+        outbox.schedule(&card, Utc::now()).await.unwrap();
+        outbox.process_due().await.unwrap();
+        assert_eq!(status(&queue, card.id).await, CardStatus::Sent);
+    }
+
+    #[tokio::test]
+    async fn meeting_response_is_recorded_only_once_sent() {
+        use crate::calendar::PartStat;
+
+        let delivery = ScriptedDelivery::new(0, false);
+        let (queue, outbox, _) = setup(delivery, instant(5)).await;
+        let outbox = outbox.with_undo_window(Duration::from_secs(60));
+
+        let start = Utc::now() + chrono::Duration::days(1);
+        let mut event = CalendarEvent::new("m1", "Planning", start, start + chrono::Duration::hours(1));
+        event.organizer = Some(Attendee::new("alice@x.com"));
+        let card = ApprovalCard::new_meeting("email", "me@x.com", event, vec![]);
+        let card_id = card.id;
+        queue.push(card).await;
+        let card = queue.respond(card_id, MeetingResponse::Decline).await.unwrap();
+
+        // Undone inside the window: the calendar never hears of it
+        outbox.enqueue(&card).await.unwrap();
+        outbox.undo(card_id).await.unwrap().unwrap();
+        assert!(outbox.db.get_calendar_event("m1").await.unwrap().is_none());
+
+        let card = queue.respond(card_id, MeetingResponse::Decline).await.unwrap();
+        outbox.schedule(&card, Utc::now()).await.unwrap();
+        outbox.process_due().await.unwrap();
+        assert_eq!(status(&queue, card_id).await, CardStatus::Sent);
+        let stored = outbox.db.get_calendar_event("m1").await.unwrap().unwrap();
+        assert_eq!(stored.event.attendee("me@x.com").unwrap().partstat, PartStat::Declined);
+        assert!(!stored.event.blocks_time(Some("me@x.com")));
+    }
+
+    #[tokio::test]
+    async fn undo_is_too_late_once_sending() {
+        let delivery = ScriptedDelivery::new(1, false);
+        let (queue, outbox, card) = setup(delivery, RetryPolicy::default()).await;
+
+        outbox.enqueue(&card).await.unwrap();
+        outbox.process_due().await.unwrap();
+        assert!(outbox.undo(card.id).await.unwrap().is_none());
+        assert_eq!(status(&queue, card.id).await, CardStatus::Sending);
+    }
+
+    #[tokio::test]
+    async fn send_at_delivers_at_the_requested_time() {
+        let delivery = ScriptedDelivery::new(0, false);
+        let (queue, outbox, card) = setup(Arc::clone(&delivery), instant(5)).await;
+
+        let send_at = Utc::now() + chrono::Duration::hours(3);
+        let job = outbox.schedule(&card, send_at).await.unwrap();
+        assert_eq!(job.status, OutboxStatus::Scheduled);
+        assert_eq!(status(&queue, card.id).await, CardStatus::Scheduled);
+        assert_eq!(outbox.process_due().await.unwrap(), 0);
+
+        // Once the time comes it goes out like any other job
+        let due = outbox
+            .db
+            .due_outbox_jobs(send_at + chrono::Duration::seconds(1), 10)
+            .await
+            .unwrap();
+        outbox.attempt(due.into_iter().next().unwrap()).await.unwrap();
+        assert_eq!(status(&queue, card.id).await, CardStatus::Sent);
+        assert_eq!(*delivery.sent.lock().unwrap(), vec!["Sure"]);
+    }
+
+    #[tokio::test]
+    async fn backoff_delays_the_next_attempt() {
+        let delivery = ScriptedDelivery::new(1, false);
+        let (_queue, outbox, card) = setup(delivery, RetryPolicy::default()).await;
+
+        outbox.enqueue(&card).await.unwrap();
+        assert_eq!(outbox.process_due().await.unwrap(), 1);
+        // Next attempt is 30s out
+        assert_eq!(outbox.process_due().await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn exhausted_retries_raise_a_decision_card() {
+        let delivery = ScriptedDelivery::new(10, false);
+        let (queue, outbox, card) = setup(Arc::clone(&delivery), instant(2)).await;
+
+        outbox.enqueue(&card).await.unwrap();
+        outbox.process_due().await.unwrap();
+        outbox.process_due().await.unwrap();
+        assert_eq!(status(&queue, card.id).await, CardStatus::Failed);
+
+        let pending = queue.pending().await;
+        assert_eq!(pending.len(), 1);
+        let CardPayload::Decision { ref question, ref options, .. } = pending[0].payload else {
+            panic!("expected a Decision card");
+        };
+        assert!(question.contains("alice@x.com"));
+        assert_eq!(options, &["Retry", "Edit", "Discard"]);
+        assert_eq!(outbox.process_due().await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn permanent_failure_skips_retries() {
+        let delivery = ScriptedDelivery::new(1, true);
+        let (queue, outbox, card) = setup(delivery, instant(5)).await;
+
+        outbox.enqueue(&card).await.unwrap();
+        outbox.process_due().await.unwrap();
+        assert_eq!(status(&queue, card.id).await, CardStatus::Failed);
+        assert_eq!(queue.pending().await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn edit_from_decision_card_resends_new_text() {
+        let delivery = ScriptedDelivery::new(1, true);
+        let (queue, outbox, card) = setup(Arc::clone(&delivery), instant(5)).await;
+
+        outbox.enqueue(&card).await.unwrap();
+        outbox.process_due().await.unwrap();
+        let decision = queue.pending().await.remove(0);
+
+        let resolved = outbox
+            .resolve(decision.id, Resolution::Edit("Sure, 1pm?".into()))
+            .await
+            .unwrap();
+        assert!(resolved);
+        assert_eq!(status(&queue, card.id).await, CardStatus::Sending);
+        outbox.process_due().await.unwrap();
+        assert_eq!(status(&queue, card.id).await, CardStatus::Sent);
+        assert_eq!(*delivery.sent.lock().unwrap(), vec!["Sure, 1pm?"]);
+
+        // Already resolved; unrelated cards aren't ours
+        assert!(outbox.resolve(decision.id, Resolution::Discard).await.unwrap());
+        assert!(!outbox.resolve(Uuid::new_v4(), Resolution::Retry).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn edited_text_is_scrubbed_with_a_warning() {
+        let delivery = ScriptedDelivery::new(1, true);
+        let (queue, outbox, card) = setup(Arc::clone(&delivery), instant(5)).await;
+
+        outbox.enqueue(&card).await.unwrap();
+        outbox.process_due().await.unwrap();
+        let decision = queue.pending().await.remove(0);
+
+        let text = "Key: sk-ant-REDACTED";
+        outbox.resolve(decision.id, Resolution::Edit(text.into())).await.unwrap();
+
+        // Nothing is sent; the message stays failed with the warning and a
+        // fresh Decision card lets the user retry the redacted text.
+        assert_eq!(outbox.process_due().await.unwrap(), 0);
+        assert!(delivery.sent.lock().unwrap().is_empty());
+        let failed = queue.all_cards().await.into_iter().find(|c| c.id == card.id).unwrap();
+        assert_eq!(failed.status, CardStatus::Failed);
+        assert_eq!(failed.payload.draft(), Some("Key: [REDACTED:anthropic_api_key]"));
+        assert_eq!(failed.warnings.len(), 1);
+        let retry = queue.pending().await.into_iter().find(|c| c.id != decision.id).unwrap();
+        assert_eq!(retry.warnings, failed.warnings);
+
+        outbox.resolve(retry.id, Resolution::Retry).await.unwrap();
+        outbox.process_due().await.unwrap();
+        assert_eq!(*delivery.sent.lock().unwrap(), vec!["Key: [REDACTED:anthropic_api_key]"]);
+        assert_eq!(status(&queue, card.id).await, CardStatus::Sent);
+    }
+
+    #[tokio::test]
+    async fn discard_leaves_the_job_alone() {
+        let delivery = ScriptedDelivery::new(1, true);
+        let (queue, outbox, card) = setup(Arc::clone(&delivery), instant(5)).await;
+
+        outbox.enqueue(&card).await.unwrap();
+        outbox.process_due().await.unwrap();
+        let decision = queue.pending().await.remove(0);
+
+        outbox.resolve(decision.id, Resolution::Discard).await.unwrap();
+        assert_eq!(outbox.process_due().await.unwrap(), 0);
+        assert_eq!(status(&queue, card.id).await, CardStatus::Failed);
+        assert!(delivery.sent.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn interrupted_jobs_are_recovered() {
+        let delivery = ScriptedDelivery::new(0, false);
+        let (_queue, outbox, card) = setup(Arc::clone(&delivery), instant(5)).await;
+
+        let mut job = outbox.enqueue(&card).await.unwrap();
+        job.status = OutboxStatus::Sending;
+        outbox.db.update_outbox_job(&job).await.unwrap();
+        assert_eq!(outbox.process_due().await.unwrap(), 0);
+
+        assert_eq!(outbox.recover().await.unwrap(), 1);
+        assert_eq!(outbox.process_due().await.unwrap(), 1);
+        assert_eq!(delivery.sent.lock().unwrap().len(), 1);
+    }
+
+    /// Records proactive sends; fails them when `fail` is set.
+    struct RecordingChannel {
+        sent: Sent,
+        fail: bool,
+    }
+
+    #[async_trait]
+    impl Channel for RecordingChannel {
+        fn name(&self) -> &str {
+            "telegram"
+        }
+
+        async fn start(&self) -> Result<MessageStream, ChannelError> {
+            Ok(Box::pin(futures::stream::empty()))
+        }
+
+        async fn respond(
+            &self,
+            _msg: &IncomingMessage,
+            _response: OutgoingResponse,
+        ) -> Result<(), ChannelError> {
+            Ok(())
+        }
+
+        async fn broadcast(
+            &self,
+            user_id: &str,
+            response: OutgoingResponse,
+        ) -> Result<(), ChannelError> {
+            if self.fail {
+                return Err(ChannelError::SendFailed {
+                    name: "telegram".into(),
+                    reason: "chat not found".into(),
+                });
+            }
+            self.sent.lock().unwrap().push((user_id.to_string(), response));
+            Ok(())
+        }
+
+        async fn health_check(&self) -> Result<(), ChannelError> {
+            Ok(())
+        }
+    }
+
+    fn telegram_reply() -> ApprovalCard {
+        let mut card = ApprovalCard::new_reply(
+            "telegram",
+            "Alice",
+            "lunch?",
+            "Sure, 12:30",
+            0.9,
+            "thread-1",
+            15,
+        );
+        if let CardPayload::Reply {
+            ref mut reply_metadata,
+            ..
+        } = card.payload
+        {
+            *reply_metadata = Some(serde_json::json!({
+                "chat_id": "-100200",
+                "message_id": 77,
+                "user_id": "4242",
+            }));
+        }
+        card
+    }
+
+    type Sent = Arc<Mutex<Vec<(String, OutgoingResponse)>>>;
+
+    fn recording_delivery(fail: bool) -> (ChannelDelivery, Sent) {
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        let mut channels = ChannelManager::new();
+        channels.add(Box::new(RecordingChannel {
+            sent: Arc::clone(&sent),
+            fail,
+        }));
+        let delivery = ChannelDelivery::new(EmailAccounts::default(), Some(Arc::new(channels)));
+        (delivery, sent)
+    }
+
+    #[tokio::test]
+    async fn chat_reply_goes_to_the_originating_chat() {
+        let (delivery, sent) = recording_delivery(false);
+        delivery.deliver(&telegram_reply()).await.unwrap();
+
+        let sent = sent.lock().unwrap();
+        assert_eq!(sent.len(), 1);
+        let (user_id, response) = &sent[0];
+        assert_eq!(user_id, "4242");
+        assert_eq!(response.content, "Sure, 12:30");
+        assert_eq!(response.metadata["reply_to_message_id"], 77);
+    }
+
+    #[tokio::test]
+    async fn channel_errors_are_retried_but_missing_config_is_not() {
+        let (delivery, _) = recording_delivery(true);
+        let err = delivery.deliver(&telegram_reply()).await.unwrap_err();
+        assert!(!err.permanent);
+        assert!(err.reason.contains("chat not found"));
+
+        let compose = ApprovalCard::new_compose("email", "bob@x.com", None, "Hi", 0.8, 15);
+        let err = delivery.deliver(&compose).await.unwrap_err();
+        assert!(err.permanent);
+    }
+
+    #[test]
+    fn chat_response_threads_under_original_message() {
+        let meta = serde_json::json!({"chat_id": "-100200", "message_id": 77, "user_id": "4242"});
+        let response = chat_response(&meta, "thread-1", "Sure".into());
+        assert_eq!(response.content, "Sure");
+        assert_eq!(response.thread_id.as_deref(), Some("thread-1"));
+        assert_eq!(response.metadata["chat_id"], "-100200");
+        assert_eq!(response.metadata["reply_to_message_id"], 77);
+
+        let response = chat_response(&serde_json::json!({"message_id": null}), "t", "x".into());
+        assert_eq!(response.metadata, serde_json::json!({}));
+    }
+}
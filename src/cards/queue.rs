@@ -5,12 +5,15 @@
 use std::collections::VecDeque;
 use std::sync::Arc;
 
+use chrono::{DateTime, Utc};
 use tokio::sync::{RwLock, broadcast};
 use tracing::{debug, error, info, warn};
 use uuid::Uuid;
 
 use super::reply_drafter::ReplyDrafter;
 use super::model::{ApprovalCard, CardPayload, CardStatus, SiloCounts, WsMessage};
+use crate::calendar::MeetingResponse;
+use crate::safety::LeakDetector;
 use crate::store::{Database, MessageStatus};
 
 /// Default broadcast channel capacity.
@@ -21,10 +24,16 @@ const DEFAULT_BROADCAST_CAPACITY: usize = 256;
 /// When constructed with `with_db()`, all mutations are written through to the database.
 /// If a DB write fails, we log the error and continue with the in-memory operation
 /// (graceful degradation).
+///
+/// Outbound drafts (Reply / Compose) are scrubbed for secrets on the way in,
+/// and again whenever the user's own text replaces them (see
+/// [`CardQueue::set_edited_draft`]); a card that had something redacted
+/// carries a warning for the user.
 pub struct CardQueue {
     cards: RwLock<VecDeque<ApprovalCard>>,
     tx: broadcast::Sender<WsMessage>,
     db: Option<Arc<dyn Database>>,
+    leak_detector: LeakDetector,
 }
 
 impl CardQueue {
@@ -35,6 +44,7 @@ impl CardQueue {
             cards: RwLock::new(VecDeque::new()),
             tx,
             db: None,
+            leak_detector: LeakDetector::new(),
         })
     }
 
@@ -42,6 +52,15 @@ impl CardQueue {
     ///
     /// Loads pending cards from the database on creation.
     pub async fn with_db(db: Arc<dyn Database>) -> Arc<Self> {
+        Self::with_db_and_leak_detector(db, LeakDetector::new()).await
+    }
+
+    /// Create a card queue backed by a `Database`, scrubbing drafts with
+    /// the given leak detector (e.g. one that knows the email password).
+    pub async fn with_db_and_leak_detector(
+        db: Arc<dyn Database>,
+        leak_detector: LeakDetector,
+    ) -> Arc<Self> {
         let (tx, _rx) = broadcast::channel(DEFAULT_BROADCAST_CAPACITY);
 
         // Load pending cards from DB
@@ -60,6 +79,7 @@ impl CardQueue {
             cards: RwLock::new(cards),
             tx,
             db: Some(db),
+            leak_detector,
         })
     }
 
@@ -78,7 +98,11 @@ impl CardQueue {
     }
 
     /// Push a new card into the queue and broadcast to all subscribers.
-    pub async fn push(&self, card: ApprovalCard) {
+    pub async fn push(&self, mut card: ApprovalCard) {
+        if card.redact_secrets(&self.leak_detector) {
+            warn!(card_id = %card.id, "Redacted secrets from card draft");
+        }
+
         info!(
             card_id = %card.id,
             card_type = card.payload.card_type_str(),
@@ -142,6 +166,34 @@ impl CardQueue {
         Some(approved)
     }
 
+    /// Set a pending Meeting card's response, then approve it.
+    pub async fn respond(&self, card_id: Uuid, response: MeetingResponse) -> Option<ApprovalCard> {
+        {
+            let mut cards = self.cards.write().await;
+            let card = cards.iter_mut().find(|c| c.id == card_id)?;
+            if card.status != CardStatus::Pending {
+                warn!(card_id = %card_id, status = ?card.status, "Cannot respond to non-pending card");
+                return None;
+            }
+            let CardPayload::Meeting {
+                response: ref mut current,
+                ..
+            } = card.payload
+            else {
+                warn!(card_id = %card_id, "Cannot respond to a card that isn't a meeting");
+                return None;
+            };
+            *current = response;
+
+            if let Some(ref db) = self.db
+                && let Err(e) = db.update_card_payload(card_id, &card.payload).await
+            {
+                error!(card_id = %card_id, error = %e, "Failed to persist meeting response to DB");
+            }
+        }
+        self.approve(card_id).await
+    }
+
     /// Dismiss a card.
     pub async fn dismiss(&self, card_id: Uuid) -> Option<ApprovalCard> {
         let mut cards = self.cards.write().await;
@@ -185,33 +237,40 @@ impl CardQueue {
     }
 
     /// Edit a card's reply text. Returns the updated card if successful.
+    ///
+    /// If secrets had to be redacted from the new text, the card stays
+    /// pending with a warning so the user can review what would be sent;
+    /// check the returned card's status before acting on the edit.
     pub async fn edit(&self, card_id: Uuid, new_text: String) -> Option<ApprovalCard> {
-        let mut cards = self.cards.write().await;
-
-        let card = cards.iter_mut().find(|c| c.id == card_id)?;
+        let (edited, redacted) = {
+            let mut cards = self.cards.write().await;
+            let card = cards.iter_mut().find(|c| c.id == card_id)?;
 
-        if card.status != CardStatus::Pending {
-            warn!(card_id = %card_id, "Cannot edit non-pending card");
-            return None;
-        }
+            if card.status != CardStatus::Pending {
+                warn!(card_id = %card_id, "Cannot edit non-pending card");
+                return None;
+            }
+            card.payload.set_draft(new_text);
+            let redacted = card.redact_secrets(&self.leak_detector);
+            if !redacted {
+                card.status = CardStatus::Approved;
+            }
+            card.updated_at = chrono::Utc::now();
+            (card.clone(), redacted)
+        };
 
         // Persist to DB
         if let Some(ref db) = self.db {
-            if let Err(e) = db
-                .update_card_reply(card_id, &new_text, CardStatus::Approved)
-                .await
-            {
+            let text = edited.payload.draft().unwrap_or_default();
+            if let Err(e) = db.update_card_reply(card_id, text, edited.status.clone()).await {
                 error!(card_id = %card_id, error = %e, "Failed to persist edit to DB");
             }
         }
-
-        // Update the suggested_reply inside the payload
-        if let CardPayload::Reply { ref mut suggested_reply, .. } = card.payload {
-            *suggested_reply = new_text;
+        if redacted {
+            self.flag_redacted(&edited).await;
+            info!(card_id = %card_id, "Card edited; left pending for review of redactions");
+            return Some(edited);
         }
-        card.status = CardStatus::Approved;
-        card.updated_at = chrono::Utc::now();
-        let edited = card.clone();
 
         info!(card_id = %card_id, "Card edited and approved");
 
@@ -219,11 +278,45 @@ impl CardQueue {
             id: card_id,
             status: CardStatus::Approved,
         });
-        self.broadcast_silo_counts_from(&cards);
+        self.broadcast_silo_counts_from(&*self.cards.read().await);
 
         Some(edited)
     }
 
+    /// Replace `card`'s draft with text the user wrote, scrubbed for secrets.
+    ///
+    /// User text never went through `push`, so this is where it gets the
+    /// same scrub. When anything is redacted the card gets a warning, which
+    /// is persisted and re-broadcast so the user can see their message was
+    /// changed. The queued copy of the card is updated to match. Returns
+    /// `true` if something was redacted.
+    pub async fn set_edited_draft(&self, card: &mut ApprovalCard, text: String) -> bool {
+        card.payload.set_draft(text);
+        let redacted = card.redact_secrets(&self.leak_detector);
+        {
+            let mut cards = self.cards.write().await;
+            if let Some(queued) = cards.iter_mut().find(|c| c.id == card.id) {
+                queued.payload = card.payload.clone();
+                queued.warnings = card.warnings.clone();
+            }
+        }
+        if redacted {
+            self.flag_redacted(card).await;
+        }
+        redacted
+    }
+
+    /// Persist and broadcast the warning left by redacting an edited draft.
+    async fn flag_redacted(&self, card: &ApprovalCard) {
+        warn!(card_id = %card.id, "Redacted secrets from edited draft");
+        if let Some(ref db) = self.db
+            && let Err(e) = db.update_card_warnings(card.id, &card.warnings).await
+        {
+            error!(card_id = %card.id, error = %e, "Failed to persist card warnings to DB");
+        }
+        let _ = self.tx.send(WsMessage::CardRefreshed { card: card.clone() });
+    }
+
     /// Refine a card's draft via LLM. Returns the updated card if successful.
     pub async fn refine(
         &self,
@@ -270,6 +363,9 @@ impl CardQueue {
                 *suggested_reply = new_text;
                 *confidence = new_confidence;
             }
+            if card.redact_secrets(&self.leak_detector) {
+                warn!(card_id = %card_id, "Redacted secrets from refined draft");
+            }
             card.updated_at = chrono::Utc::now();
             card.clone()
         };
@@ -283,6 +379,11 @@ impl CardQueue {
             {
                 error!(card_id = %card_id, error = %e, "Failed to persist refine to DB");
             }
+            if !updated.warnings.is_empty()
+                && let Err(e) = db.update_card_warnings(card_id, &updated.warnings).await
+            {
+                error!(card_id = %card_id, error = %e, "Failed to persist card warnings to DB");
+            }
         }
 
         info!(card_id = %card_id, "Card refined");
@@ -379,35 +480,145 @@ impl CardQueue {
 
     /// Mark a card as sent (after the reply was delivered to the channel).
     pub async fn mark_sent(&self, card_id: Uuid) -> bool {
-        let mut cards = self.cards.write().await;
+        let Some(card) = self.set_delivery_status(card_id, CardStatus::Sent).await else {
+            return false;
+        };
 
-        if let Some(card) = cards.iter_mut().find(|c| c.id == card_id) {
-            // Persist to DB
-            if let Some(ref db) = self.db {
-                if let Err(e) = db.update_card_status(card_id, CardStatus::Sent).await {
-                    error!(card_id = %card_id, error = %e, "Failed to persist mark_sent to DB");
+        // Update linked message status → replied
+        if let Some(msg_id) = card.payload.message_id() {
+            self.update_message_status(msg_id, MessageStatus::Replied)
+                .await;
+        }
+        true
+    }
+
+    /// Mark a card as held in the outbox until `send_at`, and tell clients
+    /// so they can show the undo countdown.
+    pub async fn mark_scheduled(&self, card_id: Uuid, send_at: DateTime<Utc>) -> bool {
+        if self
+            .set_delivery_status(card_id, CardStatus::Scheduled)
+            .await
+            .is_none()
+        {
+            return false;
+        }
+        let _ = self.tx.send(WsMessage::CardScheduled {
+            id: card_id,
+            send_at,
+        });
+        true
+    }
+
+    /// Return a `Scheduled` card to `Pending` after its send was undone.
+    ///
+    /// A card whose expiry passed while it was scheduled gets its original
+    /// lifetime again, so it doesn't expire the moment it comes back.
+    pub async fn undo(&self, card_id: Uuid) -> Option<ApprovalCard> {
+        let mut cards = self.cards.write().await;
+        let idx = match cards.iter().position(|c| c.id == card_id) {
+            Some(idx) => idx,
+            // Scheduled cards aren't reloaded on restart — fetch it back.
+            None => {
+                let card = self.db.as_ref()?.get_card(card_id).await.ok().flatten()?;
+                if card.status != CardStatus::Scheduled {
+                    warn!(card_id = %card_id, status = ?card.status, "Cannot undo unscheduled card");
+                    return None;
                 }
+                cards.push_back(card);
+                cards.len() - 1
             }
+        };
 
-            card.status = CardStatus::Sent;
-            card.updated_at = chrono::Utc::now();
+        let card = &mut cards[idx];
+        if card.status != CardStatus::Scheduled {
+            warn!(card_id = %card_id, status = ?card.status, "Cannot undo unscheduled card");
+            return None;
+        }
 
-            // Update linked message status → replied
-            if let Some(msg_id) = card.payload.message_id() {
-                self.update_message_status(msg_id, MessageStatus::Replied)
-                    .await;
+        let now = Utc::now();
+        card.status = CardStatus::Pending;
+        card.updated_at = now;
+        let mut expiry_moved = false;
+        if let Some(expires_at) = card.expires_at
+            && expires_at <= now
+        {
+            card.expires_at = Some(now + (expires_at - card.created_at));
+            expiry_moved = true;
+        }
+        let restored = card.clone();
+
+        if let Some(ref db) = self.db {
+            if let Err(e) = db.update_card_status(card_id, CardStatus::Pending).await {
+                error!(card_id = %card_id, error = %e, "Failed to persist undo to DB");
+            }
+            if expiry_moved
+                && let Err(e) = db.update_card_expiry(card_id, restored.expires_at).await
+            {
+                error!(card_id = %card_id, error = %e, "Failed to persist card expiry to DB");
             }
+        }
 
-            let _ = self.tx.send(WsMessage::CardUpdate {
-                id: card_id,
-                status: CardStatus::Sent,
-            });
-            self.broadcast_silo_counts_from(&cards);
+        // The linked message is unanswered again
+        if let Some(msg_id) = restored.payload.message_id() {
+            self.update_message_status(msg_id, MessageStatus::Pending)
+                .await;
+        }
 
-            true
-        } else {
-            false
+        info!(card_id = %card_id, "Card send undone");
+
+        let _ = self.tx.send(WsMessage::CardRefreshed {
+            card: restored.clone(),
+        });
+        self.broadcast_silo_counts_from(&cards);
+
+        Some(restored)
+    }
+
+    /// Mark a card as handed to the outbox for delivery.
+    pub async fn mark_sending(&self, card_id: Uuid) -> bool {
+        self.set_delivery_status(card_id, CardStatus::Sending)
+            .await
+            .is_some()
+    }
+
+    /// Mark a card whose delivery gave up.
+    pub async fn mark_failed(&self, card_id: Uuid) -> bool {
+        self.set_delivery_status(card_id, CardStatus::Failed)
+            .await
+            .is_some()
+    }
+
+    /// Move an approved card through delivery and broadcast the change.
+    ///
+    /// Delivery can outlive the in-memory copy (only pending cards are
+    /// reloaded on restart), so the DB is updated either way. Returns the
+    /// card if it was found in memory or in the DB.
+    async fn set_delivery_status(&self, card_id: Uuid, status: CardStatus) -> Option<ApprovalCard> {
+        if let Some(ref db) = self.db {
+            if let Err(e) = db.update_card_status(card_id, status.clone()).await {
+                error!(card_id = %card_id, error = %e, "Failed to persist card status to DB");
+            }
         }
+
+        let mut cards = self.cards.write().await;
+        let card = match cards.iter_mut().find(|c| c.id == card_id) {
+            Some(card) => {
+                card.status = status.clone();
+                card.updated_at = chrono::Utc::now();
+                card.clone()
+            }
+            None => match self.db {
+                Some(ref db) => db.get_card(card_id).await.ok().flatten()?,
+                None => return None,
+            },
+        };
+
+        let _ = self.tx.send(WsMessage::CardUpdate {
+            id: card_id,
+            status,
+        });
+        self.broadcast_silo_counts_from(&cards);
+        Some(card)
     }
 
     /// Compute silo counts from a cards slice and broadcast to all WS clients.
@@ -533,6 +744,34 @@ mod tests {
         assert_eq!(edited.status, CardStatus::Approved);
     }
 
+    #[tokio::test]
+    async fn undo_revives_an_expired_scheduled_card() {
+        let db = make_db().await;
+        let mut card = make_card(15);
+        card.created_at -= chrono::Duration::minutes(30);
+        card.expires_at = Some(card.created_at + chrono::Duration::minutes(15));
+        let card_id = card.id;
+
+        let queue = CardQueue::with_db(Arc::clone(&db)).await;
+        queue.push(card).await;
+        queue.approve(card_id).await.unwrap();
+        assert!(queue.mark_scheduled(card_id, Utc::now()).await);
+        assert!(queue.undo(card_id).await.is_some());
+        assert!(queue.undo(card_id).await.is_none(), "only scheduled cards can be undone");
+
+        // After a restart the scheduled card is only in the DB
+        queue.approve(card_id).await.unwrap();
+        queue.mark_scheduled(card_id, Utc::now()).await;
+        let queue = CardQueue::with_db(Arc::clone(&db)).await;
+        assert!(queue.pending().await.is_empty());
+
+        let restored = queue.undo(card_id).await.unwrap();
+        assert_eq!(restored.status, CardStatus::Pending);
+        assert!(restored.expires_at.unwrap() > Utc::now() + chrono::Duration::minutes(14));
+        assert_eq!(queue.pending().await.len(), 1);
+        assert_eq!(db.get_pending_cards().await.unwrap().len(), 1);
+    }
+
     #[tokio::test]
     async fn broadcast_works() {
         let queue = CardQueue::new();
@@ -562,6 +801,70 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn push_redacts_secrets_and_flags_card() {
+        let db = make_db().await;
+        let detector = LeakDetector::new().with_known_secrets(["smtp-password-1"]);
+        let queue = CardQueue::with_db_and_leak_detector(Arc::clone(&db), detector).await;
+
+        let card = ApprovalCard::new_reply(
+            "email", "Alice", "creds?", "Use smtp-password-1 to log in", 0.9, "c", 15,
+        );
+        let card_id = card.id;
+        queue.push(card).await;
+
+        let pending = queue.pending().await;
+        assert_eq!(
+            pending[0].payload.suggested_reply().unwrap(),
+            "Use [REDACTED:configured_secret] to log in"
+        );
+        assert_eq!(pending[0].warnings.len(), 1);
+
+        let db_card = db.get_card(card_id).await.unwrap().unwrap();
+        assert!(!db_card.payload.suggested_reply().unwrap().contains("smtp-password-1"));
+        assert_eq!(db_card.warnings, pending[0].warnings);
+    }
+
+    #[tokio::test]
+    async fn edit_redacts_secrets_and_flags_card() {
+        let db = make_db().await;
+        let detector = LeakDetector::new().with_known_secrets(["smtp-password-1"]);
+        let queue = CardQueue::with_db_and_leak_detector(Arc::clone(&db), detector).await;
+        let card = ApprovalCard::new_reply("email", "Alice", "creds?", "Ask IT", 0.9, "c", 15);
+        let card_id = card.id;
+        queue.push(card).await;
+        let mut rx = queue.subscribe();
+
+        let edited = queue
+            .edit(card_id, "It's smtp-password-1".into())
+            .await
+            .unwrap();
+        assert_eq!(
+            edited.payload.suggested_reply().unwrap(),
+            "It's [REDACTED:configured_secret]"
+        );
+        assert_eq!(edited.warnings.len(), 1);
+        // Nothing is sent until the user approves the redacted text
+        assert_eq!(edited.status, CardStatus::Pending);
+        assert_eq!(queue.pending().await.len(), 1);
+
+        // Clients are shown the warning, and it survives a reload
+        let mut refreshed = None;
+        while let Ok(msg) = rx.try_recv() {
+            if let WsMessage::CardRefreshed { card } = msg {
+                refreshed = Some(card);
+            }
+        }
+        assert_eq!(refreshed.unwrap().warnings, edited.warnings);
+        let db_card = db.get_card(card_id).await.unwrap().unwrap();
+        assert_eq!(db_card.payload.suggested_reply(), edited.payload.suggested_reply());
+        assert_eq!(db_card.warnings, edited.warnings);
+        assert_eq!(db_card.status, CardStatus::Pending);
+
+        let approved = queue.approve(card_id).await.unwrap();
+        assert_eq!(approved.payload.suggested_reply(), edited.payload.suggested_reply());
+    }
+
     #[tokio::test]
     async fn mark_sent() {
         let queue = CardQueue::new();
@@ -812,4 +1115,30 @@ mod tests {
             _ => panic!("Expected SiloCounts"),
         }
     }
+
+    #[tokio::test]
+    async fn respond_sets_meeting_response_and_approves() {
+        use crate::calendar::CalendarEvent;
+        let db: Arc<dyn Database> = Arc::new(LibSqlBackend::new_memory().await.unwrap());
+        let queue = CardQueue::with_db(Arc::clone(&db)).await;
+        let start = Utc::now() + chrono::Duration::days(1);
+        let event = CalendarEvent::new("m1", "Planning", start, start + chrono::Duration::hours(1));
+        let card = ApprovalCard::new_meeting("email", "me@x.com", event, vec![]);
+        let card_id = card.id;
+        queue.push(card).await;
+
+        let reply = make_card(15);
+        let reply_id = reply.id;
+        queue.push(reply).await;
+        assert!(queue.respond(reply_id, MeetingResponse::Decline).await.is_none());
+
+        let approved = queue.respond(card_id, MeetingResponse::Decline).await.unwrap();
+        assert_eq!(approved.status, CardStatus::Approved);
+        let stored = db.get_card(card_id).await.unwrap().unwrap();
+        assert!(matches!(
+            stored.payload,
+            CardPayload::Meeting { response: MeetingResponse::Decline, .. }
+        ));
+        assert!(queue.respond(card_id, MeetingResponse::Accept).await.is_none());
+    }
 }
@@ -12,17 +12,22 @@ use axum::{
     response::IntoResponse,
     routing::{get, post},
 };
+use chrono::{DateTime, Utc};
 use serde::Deserialize;
 use tracing::{debug, info, warn};
 use uuid::Uuid;
 
 use super::reply_drafter::ReplyDrafter;
 use super::handlers::{ApprovalHandler, CardActionContext};
-use super::model::{ApprovalCard, CardAction, CardPayload, CardSilo, WsMessage};
+use super::model::{ApprovalCard, CardAction, CardPayload, CardSilo, CardStatus, WsMessage};
+use super::outbox::Outbox;
 use super::queue::CardQueue;
 use crate::agent::agent_queue::AgentQueue;
+use crate::calendar::MeetingResponse;
 use crate::cards::choice_registry::ChoiceRegistry;
-use crate::channels::email::EmailConfig;
+use crate::channels::email::EmailAccounts;
+use crate::llm::budget::BudgetGuard;
+use crate::pipeline::feedback::TriageFeedback;
 use crate::store::Database;
 use crate::todos::activity::TodoActivityMessage;
 use crate::todos::approval_registry::TodoApprovalRegistry;
@@ -32,7 +37,7 @@ use crate::todos::model::TodoWsMessage;
 #[derive(Clone)]
 pub struct AppState {
     pub queue: Arc<CardQueue>,
-    pub email_config: Option<EmailConfig>,
+    pub email_accounts: EmailAccounts,
     pub reply_drafter: Arc<ReplyDrafter>,
     pub approval_registry: TodoApprovalRegistry,
     pub activity_tx: tokio::sync::broadcast::Sender<TodoActivityMessage>,
@@ -40,6 +45,14 @@ pub struct AppState {
     pub db: Arc<dyn Database>,
     pub todo_tx: tokio::sync::broadcast::Sender<TodoWsMessage>,
     pub agent_queue: Option<Arc<AgentQueue>>,
+    pub feedback: Option<Arc<TriageFeedback>>,
+    pub budgets: Option<Arc<BudgetGuard>>,
+    /// Delivers approved Reply / Compose / Meeting cards, and NewEvent
+    /// cards when `caldav` is set.
+    pub outbox: Option<Arc<Outbox>>,
+    /// A CalDAV server is configured; without one approved events are
+    /// only stored locally.
+    pub caldav: bool,
 }
 
 impl AppState {
@@ -55,22 +68,28 @@ impl AppState {
         match &card.payload {
             CardPayload::Reply { .. } => {
                 Box::new(super::handlers::MessageHandler {
-                    email_config: self.email_config.clone(),
-                })
-            }
-            CardPayload::Action { .. } => {
-                Box::new(super::handlers::ActionHandler {
-                    approval_registry: self.approval_registry.clone(),
-                    activity_tx: self.activity_tx.clone(),
-                    db: Arc::clone(&self.db),
-                    todo_tx: self.todo_tx.clone(),
-                    agent_queue: self.agent_queue.clone(),
+                    email_accounts: self.email_accounts.clone(),
+                    outbox: self.outbox.clone(),
                 })
             }
+            CardPayload::Action { .. } => Box::new(self.action_handler()),
             CardPayload::Compose { .. } => Box::new(super::handlers::ComposeHandler {
-                email_config: self.email_config.clone(),
+                outbox: self.outbox.clone(),
+            }),
+            CardPayload::Decision { .. } => Box::new(super::handlers::DecisionHandler {
+                feedback: self.feedback.clone(),
+                budgets: self.budgets.clone(),
+                outbox: self.outbox.clone(),
+            }),
+            CardPayload::Digest { .. } => Box::new(super::handlers::DigestHandler),
+            CardPayload::Meeting { .. } => Box::new(super::handlers::MeetingHandler {
+                outbox: self.outbox.clone(),
+            }),
+            CardPayload::NewEvent { .. } => Box::new(super::handlers::NewEventHandler {
+                caldav: self.caldav,
+                outbox: self.outbox.clone(),
+                db: Arc::clone(&self.db),
             }),
-            CardPayload::Decision { .. } => Box::new(super::handlers::DecisionHandler),
             CardPayload::MultipleChoice { .. } => {
                 Box::new(super::handlers::MultipleChoiceHandler {
                     choice_registry: self.choice_registry.clone(),
@@ -78,12 +97,71 @@ impl AppState {
             }
         }
     }
+
+    fn action_handler(&self) -> super::handlers::ActionHandler {
+        super::handlers::ActionHandler {
+            approval_registry: self.approval_registry.clone(),
+            activity_tx: self.activity_tx.clone(),
+            db: Arc::clone(&self.db),
+            todo_tx: self.todo_tx.clone(),
+            agent_queue: self.agent_queue.clone(),
+        }
+    }
+
+    /// Resolve an approved card with "always allow similar". Only tool-approval
+    /// Action cards save a rule; anything else is a plain approve.
+    async fn always_allow(&self, card: &ApprovalCard) {
+        match &card.payload {
+            CardPayload::Action { .. } => self.action_handler().on_always_allow(card).await,
+            _ => self.handler_for(card).on_approve(card, &self.action_context()).await,
+        }
+    }
+
+    /// Undo a scheduled send. Returns the card, back to `Pending`.
+    async fn undo(&self, card_id: Uuid) -> Option<ApprovalCard> {
+        let outbox = self.outbox.as_ref()?;
+        match outbox.undo(card_id).await {
+            Ok(card) => card,
+            Err(e) => {
+                warn!(card_id = %card_id, error = %e, "Undo failed");
+                None
+            }
+        }
+    }
+
+    /// Approve a pending Reply/Compose card to be sent at `send_at`.
+    async fn schedule(
+        &self,
+        card_id: Uuid,
+        send_at: DateTime<Utc>,
+    ) -> Result<ApprovalCard, &'static str> {
+        let outbox = self.outbox.as_ref().ok_or("Sending is not configured")?;
+        let schedulable = self.queue.all_cards().await.into_iter().any(|c| {
+            c.id == card_id
+                && matches!(c.payload, CardPayload::Reply { .. } | CardPayload::Compose { .. })
+        });
+        if !schedulable {
+            return Err("Only pending Reply and Compose cards can be scheduled");
+        }
+        let card = self
+            .queue
+            .approve(card_id)
+            .await
+            .ok_or("Card not found or not pending")?;
+        outbox
+            .schedule(&card, send_at)
+            .await
+            .map_err(|_| "Failed to schedule message")?;
+        // Pick up the Scheduled/Sending status the outbox just set.
+        let cards = self.queue.all_cards().await;
+        Ok(cards.into_iter().find(|c| c.id == card_id).unwrap_or(card))
+    }
 }
 
 /// Build the Axum router with card WebSocket and REST routes.
 pub fn card_routes(
     queue: Arc<CardQueue>,
-    email_config: Option<EmailConfig>,
+    email_accounts: EmailAccounts,
     reply_drafter: Arc<ReplyDrafter>,
     approval_registry: TodoApprovalRegistry,
     activity_tx: tokio::sync::broadcast::Sender<TodoActivityMessage>,
@@ -91,10 +169,14 @@ pub fn card_routes(
     db: Arc<dyn Database>,
     todo_tx: tokio::sync::broadcast::Sender<TodoWsMessage>,
     agent_queue: Option<Arc<AgentQueue>>,
+    feedback: Option<Arc<TriageFeedback>>,
+    budgets: Option<Arc<BudgetGuard>>,
+    outbox: Option<Arc<Outbox>>,
+    caldav: bool,
 ) -> Router {
     let state = AppState {
         queue,
-        email_config,
+        email_accounts,
         reply_drafter,
         approval_registry,
         activity_tx,
@@ -102,6 +184,10 @@ pub fn card_routes(
         db,
         todo_tx,
         agent_queue,
+        feedback,
+        budgets,
+        outbox,
+        caldav,
     };
 
     Router::new()
@@ -110,9 +196,17 @@ pub fn card_routes(
         .route("/api/cards", get(list_cards))
         .route("/api/cards/{id}", get(get_card))
         .route("/api/cards/{id}/approve", post(approve_card))
+        .route("/api/cards/{id}/always-allow", post(always_allow_card))
         .route("/api/cards/{id}/dismiss", post(dismiss_card))
         .route("/api/cards/{id}/edit", post(edit_card))
         .route("/api/cards/{id}/refine", post(refine_card))
+        .route("/api/cards/{id}/undo", post(undo_card))
+        .route("/api/cards/{id}/schedule", post(schedule_card))
+        .route("/api/cards/{id}/respond", post(respond_card))
+        .route(
+            "/api/cards/{id}/digest/{item_id}/promote",
+            post(promote_digest_item),
+        )
         .route("/api/cards/test", post(create_test_card))
         .with_state(state)
 }
@@ -230,8 +324,12 @@ async fn handle_client_message(text: &str, state: &AppState) {
             }
             CardAction::Edit { card_id, new_text } => {
                 if let Some(card) = state.queue.edit(card_id, new_text.clone()).await {
-                    info!(card_id = %card_id, "Card edited and approved via WS");
-                    state.handler_for(&card).on_edit(&card, &new_text, &ctx).await;
+                    if card.status == CardStatus::Approved {
+                        info!(card_id = %card_id, "Card edited and approved via WS");
+                        state.handler_for(&card).on_edit(&card, &new_text, &ctx).await;
+                    } else {
+                        info!(card_id = %card_id, "Card edited via WS; redactions need review");
+                    }
                 } else {
                     warn!(card_id = %card_id, "Edit failed — card not found or not pending");
                 }
@@ -259,6 +357,47 @@ async fn handle_client_message(text: &str, state: &AppState) {
                     warn!(card_id = %card_id, "SelectOption failed — card not found or not pending");
                 }
             }
+            CardAction::AlwaysAllow { card_id } => {
+                if let Some(card) = state.queue.approve(card_id).await {
+                    info!(card_id = %card_id, "Card approved (always allow similar) via WS");
+                    state.always_allow(&card).await;
+                } else {
+                    warn!(card_id = %card_id, "AlwaysAllow failed — card not found or not pending");
+                }
+            }
+            CardAction::Undo { card_id } => {
+                if state.undo(card_id).await.is_some() {
+                    info!(card_id = %card_id, "Card send undone via WS");
+                } else {
+                    warn!(card_id = %card_id, "Undo failed — card not scheduled");
+                }
+            }
+            CardAction::RespondToMeeting { card_id, response } => {
+                if let Some(card) = state.queue.respond(card_id, response).await {
+                    info!(card_id = %card_id, "Meeting response sent via WS");
+                    state.handler_for(&card).on_approve(&card, &ctx).await;
+                } else {
+                    warn!(card_id = %card_id, "Respond failed — not a pending meeting card");
+                }
+            }
+            CardAction::Schedule { card_id, send_at } => match state.schedule(card_id, send_at).await {
+                Ok(_card) => info!(card_id = %card_id, %send_at, "Card scheduled via WS"),
+                Err(e) => warn!(card_id = %card_id, error = e, "Schedule failed via WS"),
+            },
+            CardAction::PromoteDigestItem { card_id, item_id } => {
+                match crate::pipeline::digest::promote_item(
+                    &state.db,
+                    &state.queue,
+                    &state.reply_drafter,
+                    card_id,
+                    &item_id,
+                )
+                .await
+                {
+                    Ok(card) => info!(card_id = %card_id, reply_card_id = %card.id, "Digest item promoted via WS"),
+                    Err(e) => warn!(card_id = %card_id, item_id = %item_id, error = %e, "Promote failed via WS"),
+                }
+            }
         },
         Err(e) => {
             debug!(error = %e, text = text, "Unrecognized WS message from client");
@@ -319,6 +458,29 @@ async fn approve_card(State(state): State<AppState>, Path(id): Path<String>) ->
     }
 }
 
+async fn always_allow_card(State(state): State<AppState>, Path(id): Path<String>) -> impl IntoResponse {
+    let card_id = match Uuid::parse_str(&id) {
+        Ok(id) => id,
+        Err(_) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({"error": "Invalid card ID"})),
+            );
+        }
+    };
+
+    match state.queue.approve(card_id).await {
+        Some(card) => {
+            state.always_allow(&card).await;
+            (StatusCode::OK, Json(serde_json::json!(card)))
+        }
+        None => (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({"error": "Card not found or not pending"})),
+        ),
+    }
+}
+
 async fn dismiss_card(State(state): State<AppState>, Path(id): Path<String>) -> impl IntoResponse {
     let card_id = match Uuid::parse_str(&id) {
         Ok(id) => id,
@@ -368,8 +530,10 @@ async fn edit_card(
 
     match state.queue.edit(card_id, body.text.clone()).await {
         Some(card) => {
-            let ctx = state.action_context();
-            state.handler_for(&card).on_edit(&card, &body.text, &ctx).await;
+            if card.status == CardStatus::Approved {
+                let ctx = state.action_context();
+                state.handler_for(&card).on_edit(&card, &body.text, &ctx).await;
+            }
             (StatusCode::OK, Json(serde_json::json!(card)))
         }
         None => (
@@ -379,6 +543,83 @@ async fn edit_card(
     }
 }
 
+async fn undo_card(State(state): State<AppState>, Path(id): Path<String>) -> impl IntoResponse {
+    let card_id = match Uuid::parse_str(&id) {
+        Ok(id) => id,
+        Err(_) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({"error": "Invalid card ID"})),
+            );
+        }
+    };
+
+    match state.undo(card_id).await {
+        Some(card) => (StatusCode::OK, Json(serde_json::json!(card))),
+        None => (
+            StatusCode::CONFLICT,
+            Json(serde_json::json!({"error": "Card is not scheduled — too late to undo"})),
+        ),
+    }
+}
+
+#[derive(Deserialize)]
+struct ScheduleRequest {
+    send_at: DateTime<Utc>,
+}
+
+async fn schedule_card(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(body): Json<ScheduleRequest>,
+) -> impl IntoResponse {
+    let card_id = match Uuid::parse_str(&id) {
+        Ok(id) => id,
+        Err(_) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({"error": "Invalid card ID"})),
+            );
+        }
+    };
+
+    match state.schedule(card_id, body.send_at).await {
+        Ok(card) => (
+            StatusCode::OK,
+            Json(serde_json::json!({"card": card, "send_at": body.send_at})),
+        ),
+        Err(e) => (StatusCode::CONFLICT, Json(serde_json::json!({"error": e}))),
+    }
+}
+
+async fn respond_card(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(response): Json<MeetingResponse>,
+) -> impl IntoResponse {
+    let card_id = match Uuid::parse_str(&id) {
+        Ok(id) => id,
+        Err(_) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({"error": "Invalid card ID"})),
+            );
+        }
+    };
+
+    match state.queue.respond(card_id, response).await {
+        Some(card) => {
+            let ctx = state.action_context();
+            state.handler_for(&card).on_approve(&card, &ctx).await;
+            (StatusCode::OK, Json(serde_json::json!(card)))
+        }
+        None => (
+            StatusCode::CONFLICT,
+            Json(serde_json::json!({"error": "Not a pending meeting card"})),
+        ),
+    }
+}
+
 #[derive(Deserialize)]
 struct RefineRequest {
     instruction: String,
@@ -418,6 +659,37 @@ async fn refine_card(
     }
 }
 
+async fn promote_digest_item(
+    State(state): State<AppState>,
+    Path((id, item_id)): Path<(String, String)>,
+) -> impl IntoResponse {
+    let card_id = match Uuid::parse_str(&id) {
+        Ok(id) => id,
+        Err(_) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({"error": "Invalid card ID"})),
+            );
+        }
+    };
+
+    match crate::pipeline::digest::promote_item(
+        &state.db,
+        &state.queue,
+        &state.reply_drafter,
+        card_id,
+        &item_id,
+    )
+    .await
+    {
+        Ok(card) => (StatusCode::CREATED, Json(serde_json::json!(card))),
+        Err(e) => (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({"error": e})),
+        ),
+    }
+}
+
 // ── Debug / Test ────────────────────────────────────────────────────────
 
 #[derive(Deserialize)]
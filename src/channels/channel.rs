@@ -142,6 +142,16 @@ pub enum StatusUpdate {
         success: bool,
         message: String,
     },
+    /// The safety layer warned about or blocked content.
+    SafetyEvent {
+        /// Tool whose output was flagged; `None` for user input.
+        tool_name: Option<String>,
+        action: crate::safety::PolicyAction,
+        reasons: Vec<String>,
+    },
+    /// A hard spend budget stopped background work; it resumes once the
+    /// budget is raised or its period rolls over.
+    BudgetPaused { reason: String },
 }
 
 /// Trait for message channels.
@@ -170,12 +180,18 @@ pub trait Channel: Send + Sync {
     }
 
     /// Send a proactive message without a prior incoming message.
+    ///
+    /// Channels that can't reach a user unprompted keep this default, which
+    /// fails so callers never mistake it for a delivery.
     async fn broadcast(
         &self,
         _user_id: &str,
         _response: OutgoingResponse,
     ) -> Result<(), ChannelError> {
-        Ok(())
+        Err(ChannelError::SendFailed {
+            name: self.name().to_string(),
+            reason: "Channel does not support proactive messages".to_string(),
+        })
     }
 
     /// Check if the channel is healthy.
@@ -100,6 +100,9 @@ impl Channel for CliChannel {
                 eprintln!("   Type 'yes' to approve, 'no' to reject");
             }
             StatusUpdate::Status(msg) => eprintln!("ℹ️  {}", msg),
+            StatusUpdate::SafetyEvent {
+                action, reasons, ..
+            } => eprintln!("🛡️  Safety {:?}: {}", action, reasons.join("; ")),
             _ => {}
         }
         Ok(())
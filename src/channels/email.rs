@@ -7,24 +7,36 @@
 //! This module retains: `EmailConfig`, IMAP fetch/mark helpers,
 //! `send_reply_email`, `build_reply_metadata`, and parsing utilities.
 
+use std::collections::HashSet;
 use std::io::Write as IoWrite;
 use std::net::TcpStream;
+use std::path::Path;
+use std::sync::Arc;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
+use lettre::message::header::ContentType;
+use lettre::message::{MultiPart, SinglePart};
 use lettre::transport::smtp::authentication::Credentials;
 use lettre::{Message, SmtpTransport, Transport};
 use mail_parser::{MessageParser, MimeHeaders};
+use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use crate::cards::model::ThreadMessage;
 use crate::channels::email_types::{self, EmailMessage};
-use crate::error::ChannelError;
+use crate::channels::imap_session::{ImapSession, TlsConnector};
+use crate::error::{ChannelError, ConfigError};
 
 // ── Configuration ───────────────────────────────────────────────────
 
+/// Account id used when only the single-account `EMAIL_*` variables are set.
+pub const DEFAULT_ACCOUNT_ID: &str = "default";
+
 /// Email channel configuration, built from environment variables.
 #[derive(Debug, Clone)]
 pub struct EmailConfig {
+    /// Stable account id, carried on stored messages and reply metadata.
+    pub account_id: String,
     pub imap_host: String,
     pub imap_port: u16,
     pub smtp_host: String,
@@ -34,6 +46,13 @@ pub struct EmailConfig {
     pub from_address: String,
     pub poll_interval_secs: u64,
     pub allowed_senders: Vec<String>,
+    /// Folders to sync, e.g. `INBOX` plus a "Needs reply" label. The first
+    /// one is watched with IDLE.
+    pub folders: Vec<String>,
+    /// Use IMAP IDLE push when the server supports it (falls back to polling).
+    pub imap_idle: bool,
+    /// Re-issue IDLE after this many seconds (RFC 2177 recommends < 29 min).
+    pub idle_timeout_secs: u64,
 }
 
 impl EmailConfig {
@@ -71,7 +90,22 @@ impl EmailConfig {
             .filter(|s| !s.is_empty())
             .collect();
 
+        let folders = parse_folders(&std::env::var("EMAIL_IMAP_FOLDERS").unwrap_or_default());
+
+        let imap_idle = std::env::var("EMAIL_IMAP_IDLE")
+            .map(|v| v != "false" && v != "0")
+            .unwrap_or(true);
+
+        let idle_timeout_secs: u64 = std::env::var("EMAIL_IDLE_TIMEOUT_SECS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(1500);
+
+        let account_id =
+            std::env::var("EMAIL_ACCOUNT_ID").unwrap_or_else(|_| DEFAULT_ACCOUNT_ID.to_string());
+
         Some(Self {
+            account_id,
             imap_host,
             imap_port,
             smtp_host,
@@ -81,10 +115,210 @@ impl EmailConfig {
             from_address,
             poll_interval_secs,
             allowed_senders,
+            folders,
+            imap_idle,
+            idle_timeout_secs,
         })
     }
 }
 
+/// One entry in `EMAIL_ACCOUNTS_FILE`. Omitted fields default like the env vars.
+#[derive(Debug, Deserialize)]
+struct AccountEntry {
+    id: String,
+    imap_host: String,
+    #[serde(default = "default_imap_port")]
+    imap_port: u16,
+    smtp_host: Option<String>,
+    #[serde(default = "default_smtp_port")]
+    smtp_port: u16,
+    username: String,
+    /// Inline password. Prefer `password_env` to keep secrets out of the file.
+    password: Option<String>,
+    /// Name of an environment variable holding the password.
+    password_env: Option<String>,
+    from_address: Option<String>,
+    #[serde(default = "default_poll_interval")]
+    poll_interval_secs: u64,
+    #[serde(default)]
+    allowed_senders: Vec<String>,
+    #[serde(default)]
+    folders: Vec<String>,
+    #[serde(default = "default_true")]
+    imap_idle: bool,
+    #[serde(default = "default_idle_timeout")]
+    idle_timeout_secs: u64,
+}
+
+/// Comma-separated folder list; empty means just `INBOX`.
+fn parse_folders(raw: &str) -> Vec<String> {
+    let folders: Vec<String> = raw
+        .split(',')
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect();
+    if folders.is_empty() {
+        vec!["INBOX".to_string()]
+    } else {
+        folders
+    }
+}
+
+fn default_imap_port() -> u16 {
+    993
+}
+
+fn default_smtp_port() -> u16 {
+    587
+}
+
+fn default_poll_interval() -> u64 {
+    60
+}
+
+fn default_idle_timeout() -> u64 {
+    1500
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Debug, Deserialize)]
+struct AccountsFile {
+    accounts: Vec<AccountEntry>,
+}
+
+impl AccountEntry {
+    fn into_config(self) -> Result<EmailConfig, ConfigError> {
+        let password = match (self.password, &self.password_env) {
+            (_, Some(var)) => std::env::var(var).map_err(|_| ConfigError::InvalidValue {
+                key: format!("accounts.{}.password_env", self.id),
+                message: format!("environment variable {var} is not set"),
+            })?,
+            (Some(password), None) => password,
+            (None, None) => String::new(),
+        };
+        Ok(EmailConfig {
+            smtp_host: self
+                .smtp_host
+                .unwrap_or_else(|| self.imap_host.replace("imap", "smtp")),
+            from_address: self.from_address.unwrap_or_else(|| self.username.clone()),
+            account_id: self.id,
+            imap_host: self.imap_host,
+            imap_port: self.imap_port,
+            smtp_port: self.smtp_port,
+            username: self.username,
+            password,
+            poll_interval_secs: self.poll_interval_secs,
+            allowed_senders: self.allowed_senders,
+            folders: if self.folders.is_empty() {
+                vec!["INBOX".to_string()]
+            } else {
+                self.folders
+            },
+            imap_idle: self.imap_idle,
+            idle_timeout_secs: self.idle_timeout_secs,
+        })
+    }
+}
+
+/// All configured email accounts. The first account is the default.
+///
+/// Loaded from the JSON file named by `EMAIL_ACCOUNTS_FILE`, or — when that
+/// isn't set — from the single-account `EMAIL_*` variables.
+#[derive(Debug, Clone, Default)]
+pub struct EmailAccounts {
+    accounts: Arc<Vec<EmailConfig>>,
+}
+
+impl EmailAccounts {
+    /// Build from explicit configs. Ids must be non-empty and unique.
+    pub fn new(accounts: Vec<EmailConfig>) -> Result<Self, ConfigError> {
+        let mut seen = HashSet::new();
+        for account in &accounts {
+            if account.account_id.trim().is_empty() {
+                return Err(ConfigError::InvalidValue {
+                    key: "account_id".into(),
+                    message: "email account id must not be empty".into(),
+                });
+            }
+            if !seen.insert(account.account_id.as_str()) {
+                return Err(ConfigError::InvalidValue {
+                    key: "account_id".into(),
+                    message: format!("duplicate email account id '{}'", account.account_id),
+                });
+            }
+        }
+        Ok(Self {
+            accounts: Arc::new(accounts),
+        })
+    }
+
+    /// Load accounts from `EMAIL_ACCOUNTS_FILE`, falling back to `EmailConfig::from_env()`.
+    ///
+    /// Returns an empty set when email isn't configured.
+    pub fn from_env() -> Result<Self, ConfigError> {
+        match std::env::var("EMAIL_ACCOUNTS_FILE") {
+            Ok(path) => Self::from_file(Path::new(&path)),
+            Err(_) => Self::new(EmailConfig::from_env().into_iter().collect()),
+        }
+    }
+
+    /// Parse a JSON accounts file: `{"accounts": [{"id": "work", "imap_host": ..., ...}]}`.
+    pub fn from_file(path: &Path) -> Result<Self, ConfigError> {
+        let raw = std::fs::read_to_string(path).map_err(|e| ConfigError::InvalidValue {
+            key: "EMAIL_ACCOUNTS_FILE".to_string(),
+            message: format!("{}: {e}", path.display()),
+        })?;
+        let file: AccountsFile = serde_json::from_str(&raw)
+            .map_err(|e| ConfigError::ParseError(format!("{}: {e}", path.display())))?;
+        let accounts = file
+            .accounts
+            .into_iter()
+            .map(AccountEntry::into_config)
+            .collect::<Result<Vec<_>, _>>()?;
+        Self::new(accounts)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.accounts.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.accounts.len()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &EmailConfig> {
+        self.accounts.iter()
+    }
+
+    /// Look up an account by id.
+    pub fn get(&self, account_id: &str) -> Option<&EmailConfig> {
+        self.accounts.iter().find(|a| a.account_id == account_id)
+    }
+
+    /// The first configured account — used for new (non-reply) mail.
+    pub fn default_account(&self) -> Option<&EmailConfig> {
+        self.accounts.first()
+    }
+
+    /// The account a reply should go out on: the one named by
+    /// `reply_metadata["account_id"]`, or the default for metadata that
+    /// predates multi-account support.
+    pub fn for_reply(&self, reply_metadata: &serde_json::Value) -> Option<&EmailConfig> {
+        match reply_metadata["account_id"].as_str() {
+            Some(id) => self.get(id),
+            None => self.default_account(),
+        }
+    }
+
+    /// Every account password (for leak scrubbing).
+    pub fn passwords(&self) -> impl Iterator<Item = &str> {
+        self.accounts.iter().map(|a| a.password.as_str())
+    }
+}
+
 // NOTE: EmailChannel struct + Channel trait impl removed in PR #75.
 // Email now uses the standalone pipeline: email_poller → DB → email_processor → pipeline → cards.
 // See src/channels/email_poller.rs and src/pipeline/email_processor.rs.
@@ -176,6 +410,22 @@ fn extract_text(parsed: &mail_parser::Message) -> String {
     "(no readable content)".to_string()
 }
 
+/// The message's iCalendar data — an inline `text/calendar` part or an
+/// `.ics` attachment — if it has any.
+fn extract_calendar(parsed: &mail_parser::Message) -> Option<String> {
+    parsed.parts.iter().find_map(|part| {
+        let is_calendar = MimeHeaders::content_type(part)
+            .is_some_and(|ct| ct.subtype().is_some_and(|sub| sub.eq_ignore_ascii_case("calendar")))
+            || MimeHeaders::attachment_name(part)
+                .is_some_and(|name| name.to_ascii_lowercase().ends_with(".ics"));
+        if !is_calendar {
+            return None;
+        }
+        let text = std::str::from_utf8(part.contents()).ok()?;
+        text.contains("BEGIN:VCALENDAR").then(|| text.to_string())
+    })
+}
+
 /// A fetched email: (uid, message_id, sender, content, subject, timestamp, reply_metadata).
 pub(crate) type FetchedEmail = (
     String,
@@ -190,252 +440,131 @@ pub(crate) type FetchedEmail = (
 /// Error type for IMAP fetch operations.
 pub(crate) type ImapError = Box<dyn std::error::Error + Send + Sync>;
 
-/// Fetch unseen emails via raw IMAP over TLS (blocking — run in spawn_blocking).
-pub(crate) fn fetch_unseen_imap(config: &EmailConfig) -> Result<Vec<FetchedEmail>, ImapError> {
-    use std::sync::Arc as StdArc;
-
-    // Connect TCP
-    let tcp = TcpStream::connect((&*config.imap_host, config.imap_port))?;
-    tcp.set_read_timeout(Some(Duration::from_secs(30)))?;
-
-    // TLS via rustls
-    let mut root_store = rustls::RootCertStore::empty();
-    root_store.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
-    let tls_config = StdArc::new(
-        rustls::ClientConfig::builder()
-            .with_root_certificates(root_store)
-            .with_no_client_auth(),
-    );
-    let server_name: rustls::pki_types::ServerName<'_> =
-        rustls::pki_types::ServerName::try_from(config.imap_host.clone())?;
-    let conn = rustls::ClientConnection::new(tls_config, server_name)?;
-    let mut tls = rustls::StreamOwned::new(conn, tcp);
-
-    // ── IMAP helpers ────────────────────────────────────────────────
-    let read_line =
-        |tls: &mut rustls::StreamOwned<rustls::ClientConnection, TcpStream>| -> Result<String, ImapError> {
-            let mut buf = Vec::new();
-            loop {
-                let mut byte = [0u8; 1];
-                match std::io::Read::read(tls, &mut byte) {
-                    Ok(0) => return Err("IMAP connection closed".into()),
-                    Ok(_) => {
-                        buf.push(byte[0]);
-                        if buf.ends_with(b"\r\n") {
-                            return Ok(String::from_utf8_lossy(&buf).to_string());
-                        }
-                    }
-                    Err(e) => return Err(e.into()),
-                }
-            }
-        };
-
-    let send_cmd = |tls: &mut rustls::StreamOwned<rustls::ClientConnection, TcpStream>,
-                    tag: &str,
-                    cmd: &str|
-     -> Result<Vec<String>, ImapError> {
-        let full = format!("{tag} {cmd}\r\n");
-        IoWrite::write_all(tls, full.as_bytes())?;
-        IoWrite::flush(tls)?;
-        let mut lines = Vec::new();
-        loop {
-            let line = read_line(tls)?;
-            let done = line.starts_with(tag);
-            lines.push(line);
-            if done {
-                break;
-            }
-        }
-        Ok(lines)
-    };
-
-    // Read greeting
-    let _greeting = read_line(&mut tls)?;
-
-    // Login
-    let login_resp = send_cmd(
-        &mut tls,
-        "A1",
-        &format!("LOGIN \"{}\" \"{}\"", config.username, config.password),
-    )?;
-    if !login_resp.last().is_some_and(|l| l.contains("OK")) {
-        return Err("IMAP login failed".into());
-    }
-
-    // Select INBOX
-    let _select = send_cmd(&mut tls, "A2", "SELECT \"INBOX\"")?;
-
-    // Search unseen
-    let search_resp = send_cmd(&mut tls, "A3", "SEARCH UNSEEN")?;
-    let mut uids: Vec<&str> = Vec::new();
-    for line in &search_resp {
-        if line.starts_with("* SEARCH") {
-            let parts: Vec<&str> = line.split_whitespace().collect();
-            if parts.len() > 2 {
-                uids.extend_from_slice(&parts[2..]);
-            }
-        }
+/// Parse a raw RFC 822 message into the tuple persisted by the poller.
+pub(crate) fn parse_fetched_email(uid: &str, raw: &[u8], config: &EmailConfig) -> Option<FetchedEmail> {
+    let parsed = MessageParser::default().parse(raw)?;
+    let sender = extract_sender(&parsed);
+    let subject = parsed.subject().unwrap_or("(no subject)").to_string();
+    let body = extract_text(&parsed);
+    let cleaned_body = email_types::strip_quoted_text(&body);
+    let content = format!("Subject: {subject}\n\n{cleaned_body}");
+    let msg_id = parsed
+        .message_id()
+        .map(|s| s.to_string())
+        .unwrap_or_else(|| format!("gen-{}", Uuid::new_v4()));
+
+    // Build reply_metadata for reply-all send
+    let mut reply_metadata =
+        build_reply_metadata(&parsed, &sender, &subject, &msg_id, &config.from_address);
+
+    // Keep any attached invitation so triage can turn it into a meeting card
+    if let Some(ics) = extract_calendar(&parsed) {
+        reply_metadata["calendar"] = serde_json::json!({
+            "ics": ics,
+            "attendee": config.from_address,
+        });
     }
 
-    let mut results = Vec::new();
-    let mut tag_counter = 4_u32;
+    #[allow(clippy::cast_sign_loss)]
+    let ts = parsed
+        .date()
+        .map(|d| {
+            let naive = chrono::NaiveDate::from_ymd_opt(
+                d.year as i32,
+                u32::from(d.month),
+                u32::from(d.day),
+            )
+            .and_then(|date| {
+                date.and_hms_opt(
+                    u32::from(d.hour),
+                    u32::from(d.minute),
+                    u32::from(d.second),
+                )
+            });
+            naive.map_or(0, |n| n.and_utc().timestamp() as u64)
+        })
+        .unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0)
+        });
+
+    Some((
+        uid.to_string(),
+        msg_id,
+        sender,
+        content,
+        subject,
+        ts,
+        reply_metadata,
+    ))
+}
 
-    for uid in &uids {
-        let fetch_tag = format!("A{tag_counter}");
-        tag_counter += 1;
-        let fetch_resp = send_cmd(&mut tls, &fetch_tag, &format!("FETCH {uid} RFC822"))?;
+/// Flags set on the original message when its reply card is approved.
+pub(crate) const APPROVED_FLAGS: &[&str] = &["\\Answered", "\\Seen"];
 
-        let raw: String = fetch_resp
-            .iter()
-            .skip(1)
-            .take(fetch_resp.len().saturating_sub(2))
-            .cloned()
-            .collect();
-
-        if let Some(parsed) = MessageParser::default().parse(raw.as_bytes()) {
-            let sender = extract_sender(&parsed);
-            let subject = parsed.subject().unwrap_or("(no subject)").to_string();
-            let body = extract_text(&parsed);
-            let cleaned_body = email_types::strip_quoted_text(&body);
-            let content = format!("Subject: {subject}\n\n{cleaned_body}");
-            let msg_id = parsed
-                .message_id()
-                .map(|s| s.to_string())
-                .unwrap_or_else(|| format!("gen-{}", Uuid::new_v4()));
+/// Flags set on the original message when its reply card is dismissed.
+pub(crate) const DISMISSED_FLAGS: &[&str] = &["\\Seen"];
 
-            // Build reply_metadata for reply-all send
-            let reply_metadata =
-                build_reply_metadata(&parsed, &sender, &subject, &msg_id, &config.from_address);
-
-            #[allow(clippy::cast_sign_loss)]
-            let ts = parsed
-                .date()
-                .map(|d| {
-                    let naive = chrono::NaiveDate::from_ymd_opt(
-                        d.year as i32,
-                        u32::from(d.month),
-                        u32::from(d.day),
-                    )
-                    .and_then(|date| {
-                        date.and_hms_opt(
-                            u32::from(d.hour),
-                            u32::from(d.minute),
-                            u32::from(d.second),
-                        )
-                    });
-                    naive.map_or(0, |n| n.and_utc().timestamp() as u64)
-                })
-                .unwrap_or_else(|| {
-                    SystemTime::now()
-                        .duration_since(UNIX_EPOCH)
-                        .map(|d| d.as_secs())
-                        .unwrap_or(0)
-                });
+/// Where a fetched message lives on the IMAP server — enough to flag it later.
+///
+/// Stored under `reply_metadata["imap"]`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImapLocation {
+    pub folder: String,
+    pub uid: u32,
+    pub uid_validity: u32,
+}
 
-            results.push((
-                uid.to_string(),
-                msg_id,
-                sender,
-                content,
-                subject,
-                ts,
-                reply_metadata,
-            ));
-        }
-        // NOTE: \Seen is NOT marked here — caller marks after persisting to DB.
+impl ImapLocation {
+    pub fn from_reply_metadata(reply_metadata: &serde_json::Value) -> Option<Self> {
+        serde_json::from_value(reply_metadata.get("imap")?.clone()).ok()
     }
-
-    // Logout
-    let logout_tag = format!("A{tag_counter}");
-    let _ = send_cmd(&mut tls, &logout_tag, "LOGOUT");
-
-    Ok(results)
 }
 
-/// Mark specific UIDs as \Seen on IMAP (blocking — run in spawn_blocking).
-pub(crate) fn mark_seen_imap(config: &EmailConfig, uids: &[String]) -> Result<(), ImapError> {
-    use std::sync::Arc as StdArc;
-
-    if uids.is_empty() {
-        return Ok(());
+/// Add flags to one message by UID (blocking — run in spawn_blocking).
+///
+/// Refuses to touch the mailbox if its UIDVALIDITY changed since the
+/// message was fetched, since the UID may now name a different message.
+pub(crate) fn store_flags_imap(
+    config: &EmailConfig,
+    location: &ImapLocation,
+    flags: &[&str],
+) -> Result<(), ImapError> {
+    let mut session = ImapSession::open(&TlsConnector::new(config))?;
+    session.login(&config.username, &config.password)?;
+    let status = session.select(&location.folder)?;
+    if status.uid_validity != location.uid_validity {
+        session.logout();
+        return Err(format!(
+            "UIDVALIDITY of {} changed ({} → {}); not flagging UID {}",
+            location.folder, location.uid_validity, status.uid_validity, location.uid
+        )
+        .into());
     }
+    session.uid_add_flags(&[location.uid], flags)?;
+    session.logout();
+    Ok(())
+}
 
-    let tcp = TcpStream::connect((&*config.imap_host, config.imap_port))?;
-    tcp.set_read_timeout(Some(Duration::from_secs(30)))?;
-
-    let mut root_store = rustls::RootCertStore::empty();
-    root_store.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
-    let tls_config = StdArc::new(
-        rustls::ClientConfig::builder()
-            .with_root_certificates(root_store)
-            .with_no_client_auth(),
-    );
-    let server_name: rustls::pki_types::ServerName<'_> =
-        rustls::pki_types::ServerName::try_from(config.imap_host.clone())?;
-    let conn = rustls::ClientConnection::new(tls_config, server_name)?;
-    let mut tls = rustls::StreamOwned::new(conn, tcp);
-
-    let read_line =
-        |tls: &mut rustls::StreamOwned<rustls::ClientConnection, TcpStream>| -> Result<String, ImapError> {
-            let mut buf = Vec::new();
-            loop {
-                let mut byte = [0u8; 1];
-                match std::io::Read::read(tls, &mut byte) {
-                    Ok(0) => return Err("IMAP connection closed".into()),
-                    Ok(_) => {
-                        buf.push(byte[0]);
-                        if buf.ends_with(b"\r\n") {
-                            return Ok(String::from_utf8_lossy(&buf).to_string());
-                        }
-                    }
-                    Err(e) => return Err(e.into()),
-                }
-            }
-        };
-
-    let send_cmd = |tls: &mut rustls::StreamOwned<rustls::ClientConnection, TcpStream>,
-                    tag: &str,
-                    cmd: &str|
-     -> Result<Vec<String>, ImapError> {
-        let full = format!("{tag} {cmd}\r\n");
-        IoWrite::write_all(tls, full.as_bytes())?;
-        IoWrite::flush(tls)?;
-        let mut lines = Vec::new();
-        loop {
-            let line = read_line(tls)?;
-            let done = line.starts_with(tag);
-            lines.push(line);
-            if done {
-                break;
-            }
-        }
-        Ok(lines)
+/// Mirror a card decision onto the original email's IMAP flags.
+///
+/// No-op for messages without an IMAP location (e.g. fetched before UID sync).
+pub(crate) async fn flag_original_message(
+    accounts: &EmailAccounts,
+    reply_metadata: &serde_json::Value,
+    flags: &'static [&'static str],
+) -> Result<(), ImapError> {
+    let Some(location) = ImapLocation::from_reply_metadata(reply_metadata) else {
+        return Ok(());
     };
-
-    let _greeting = read_line(&mut tls)?;
-
-    let login_resp = send_cmd(
-        &mut tls,
-        "A1",
-        &format!("LOGIN \"{}\" \"{}\"", config.username, config.password),
-    )?;
-    if !login_resp.last().is_some_and(|l| l.contains("OK")) {
-        return Err("IMAP login failed".into());
-    }
-
-    let _select = send_cmd(&mut tls, "A2", "SELECT \"INBOX\"")?;
-
-    let mut tag_counter = 3_u32;
-    for uid in uids {
-        let tag = format!("A{tag_counter}");
-        tag_counter += 1;
-        let _ = send_cmd(&mut tls, &tag, &format!("STORE {uid} +FLAGS (\\Seen)"));
-    }
-
-    let logout_tag = format!("A{tag_counter}");
-    let _ = send_cmd(&mut tls, &logout_tag, "LOGOUT");
-
-    Ok(())
+    let Some(config) = accounts.for_reply(reply_metadata).cloned() else {
+        return Err("no email account for reply metadata".into());
+    };
+    tokio::task::spawn_blocking(move || store_flags_imap(&config, &location, flags))
+        .await
+        .map_err(|e| -> ImapError { e.to_string().into() })?
 }
 
 // ── Reply sending ───────────────────────────────────────────────
@@ -443,7 +572,8 @@ pub(crate) fn mark_seen_imap(config: &EmailConfig, uids: &[String]) -> Result<()
 /// Send a reply email using reply_metadata from the card.
 ///
 /// This is a standalone function (not tied to Channel trait) so it can be called
-/// from the WS/REST handlers with just an EmailConfig reference.
+/// from the WS/REST handlers. SMTP credentials come from the account the
+/// original message arrived on (`reply_metadata["account_id"]`).
 ///
 /// Sends a reply-all email with:
 /// - To: the original sender (reply_to)
@@ -451,10 +581,19 @@ pub(crate) fn mark_seen_imap(config: &EmailConfig, uids: &[String]) -> Result<()
 /// - Subject: with "Re: " prefix
 /// - In-Reply-To / References headers for Gmail/Outlook threading
 pub fn send_reply_email(
-    config: &EmailConfig,
+    accounts: &EmailAccounts,
     reply_metadata: &serde_json::Value,
     body: &str,
 ) -> Result<(), ChannelError> {
+    let config = accounts
+        .for_reply(reply_metadata)
+        .ok_or_else(|| ChannelError::SendFailed {
+            name: "email".into(),
+            reason: match reply_metadata["account_id"].as_str() {
+                Some(id) => format!("Unknown email account '{id}'"),
+                None => "No email account configured".into(),
+            },
+        })?;
     let reply_to = reply_metadata["reply_to"]
         .as_str()
         .ok_or_else(|| ChannelError::SendFailed {
@@ -592,6 +731,93 @@ pub fn send_new_email(
     Ok(())
 }
 
+/// Send an iTIP response (`REPLY` or `COUNTER`) to a meeting organizer.
+///
+/// The calendar goes out as a `text/calendar; method=…` alternative next
+/// to a plain-text note, which is what calendar clients look for. The
+/// account and threading headers come from the invitation's reply_metadata.
+pub fn send_calendar_reply(
+    accounts: &EmailAccounts,
+    reply_metadata: &serde_json::Value,
+    organizer: &str,
+    subject: &str,
+    body: &str,
+    method: &str,
+    ics: &str,
+) -> Result<(), ChannelError> {
+    let config = accounts
+        .for_reply(reply_metadata)
+        .ok_or_else(|| ChannelError::SendFailed {
+            name: "email".into(),
+            reason: "No email account configured".into(),
+        })?;
+
+    let creds = Credentials::new(config.username.clone(), config.password.clone());
+
+    let transport = SmtpTransport::starttls_relay(&config.smtp_host)
+        .map_err(|e| ChannelError::SendFailed {
+            name: "email".into(),
+            reason: format!("SMTP STARTTLS error: {e}"),
+        })?
+        .port(config.smtp_port)
+        .credentials(creds)
+        .build();
+
+    let mut builder = Message::builder()
+        .from(
+            config
+                .from_address
+                .parse()
+                .map_err(|e| ChannelError::SendFailed {
+                    name: "email".into(),
+                    reason: format!("Invalid from address: {e}"),
+                })?,
+        )
+        .to(organizer.parse().map_err(|e| ChannelError::SendFailed {
+            name: "email".into(),
+            reason: format!("Invalid organizer address: {e}"),
+        })?)
+        .subject(subject);
+
+    if let Some(irt) = reply_metadata["in_reply_to"].as_str() {
+        builder = builder.in_reply_to(irt.to_string());
+    }
+    if let Some(refs) = reply_metadata["references"].as_str() {
+        builder = builder.references(refs.to_string());
+    }
+
+    let calendar_type = ContentType::parse(&format!("text/calendar; method={method}; charset=UTF-8"))
+        .map_err(|e| ChannelError::SendFailed {
+            name: "email".into(),
+            reason: format!("Invalid calendar content type: {e}"),
+        })?;
+    let email = builder
+        .multipart(
+            MultiPart::alternative()
+                .singlepart(SinglePart::plain(body.to_string()))
+                .singlepart(SinglePart::builder().header(calendar_type).body(ics.to_string())),
+        )
+        .map_err(|e| ChannelError::SendFailed {
+            name: "email".into(),
+            reason: format!("Failed to build email: {e}"),
+        })?;
+
+    transport
+        .send(&email)
+        .map_err(|e| ChannelError::SendFailed {
+            name: "email".into(),
+            reason: format!("SMTP send failed: {e}"),
+        })?;
+
+    tracing::info!(
+        to = organizer,
+        method = method,
+        subject = subject,
+        "Calendar reply sent"
+    );
+    Ok(())
+}
+
 // ── Reply metadata ──────────────────────────────────────────────
 
 /// Build reply metadata from a parsed email for reply-all sending.
@@ -712,7 +938,7 @@ fn fetch_thread_by_subject(
     let conn = rustls::ClientConnection::new(tls_config, server_name)?;
     let mut tls = rustls::StreamOwned::new(conn, tcp);
 
-    // IMAP helpers (same pattern as fetch_unseen_imap)
+    // IMAP helpers
     let read_line =
         |tls: &mut rustls::StreamOwned<rustls::ClientConnection, TcpStream>| -> Result<String, ImapError> {
             let mut buf = Vec::new();
@@ -1,10 +1,19 @@
-//! Standalone IMAP poller — fetches unseen emails and persists to DB.
+//! Standalone IMAP poller — fetches new emails and persists to DB.
 //!
 //! Unlike the old `EmailChannel::start()`, this does NOT create
 //! `IncomingMessage` or push to any stream. It only:
-//! 1. Fetches unseen emails via IMAP
+//! 1. Fetches messages above the last-seen UID in each configured folder
 //! 2. Persists new ones to the `messages` table (status = "pending")
-//! 3. Marks them \Seen in IMAP
+//! 3. Saves the UIDVALIDITY/UID cursor in `settings`
+//!
+//! Flags are left alone here; the card handlers mirror approve/dismiss
+//! decisions back as `\Answered`/`\Seen`.
+//!
+//! By default a persistent session waits in IMAP IDLE (RFC 2177) and syncs
+//! as soon as the server reports new mail, reconnecting with exponential
+//! backoff when the connection drops. Servers without the IDLE capability
+//! (or `EMAIL_IMAP_IDLE=false`) fall back to polling every
+//! `EMAIL_POLL_INTERVAL_SECS`.
 //!
 //! The `email_processor` timer loop picks up pending emails from the DB
 //! and runs them through the pipeline.
@@ -13,10 +22,15 @@ use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::Duration;
 
+use serde::{Deserialize, Serialize};
+use tokio::runtime::Handle;
 use tokio::task::JoinHandle;
 use tracing::{debug, error, info, warn};
 
-use crate::channels::email::{EmailConfig, is_sender_allowed};
+use crate::channels::email::{
+    EmailConfig, FetchedEmail, ImapError, ImapLocation, is_sender_allowed, parse_fetched_email,
+};
+use crate::channels::imap_session::{IdleEvent, ImapConnector, ImapSession, TlsConnector};
 use crate::store::Database;
 
 /// Spawn a background task that polls IMAP and persists new emails to DB.
@@ -30,6 +44,24 @@ pub fn spawn_email_poller(
     let shutdown_flag = Arc::clone(&shutdown);
 
     let handle = tokio::spawn(async move {
+        if config.imap_idle {
+            info!("Email poller started — IMAP IDLE on {}", config.imap_host);
+            let connector: Arc<dyn ImapConnector> = Arc::new(TlsConnector::new(&config));
+            let settings = IdleSettings::from_config(&config);
+            match run_idle(connector, config.clone(), Arc::clone(&db), Arc::clone(&shutdown), settings)
+                .await
+            {
+                IdleExit::Shutdown => {
+                    info!("Email poller shutting down");
+                    return;
+                }
+                IdleExit::Unsupported => warn!(
+                    "IMAP server {} lacks IDLE — falling back to polling",
+                    config.imap_host
+                ),
+            }
+        }
+
         info!(
             "Email poller started — polling every {}s on {}",
             config.poll_interval_secs, config.imap_host
@@ -52,47 +84,57 @@ pub fn spawn_email_poller(
     (handle, shutdown_flag)
 }
 
-/// Run a single poll cycle: fetch unseen → persist → mark \Seen.
+/// Run a single poll cycle on a fresh session: sync every folder, then log out.
 async fn poll_once(config: &EmailConfig, db: &Arc<dyn Database>) {
     let cfg = config.clone();
-    let fetch_result = tokio::task::spawn_blocking(move || {
-        super::email::fetch_unseen_imap(&cfg)
+    let db = Arc::clone(db);
+    let handle = Handle::current();
+    let result = tokio::task::spawn_blocking(move || -> Result<(), ImapError> {
+        let mut session = ImapSession::open(&TlsConnector::new(&cfg))?;
+        session.login(&cfg.username, &cfg.password)?;
+        sync_account(&mut session, &cfg, &db, &handle)?;
+        session.logout();
+        Ok(())
     })
     .await;
 
-    let messages = match fetch_result {
-        Ok(Ok(msgs)) => msgs,
-        Ok(Err(e)) => {
-            error!("Email poll failed: {e}");
-            return;
-        }
-        Err(e) => {
-            error!("Email poll task panicked: {e}");
-            return;
-        }
-    };
-
-    if messages.is_empty() {
-        return;
+    match result {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => error!("Email poll failed: {e}"),
+        Err(e) => error!("Email poll task panicked: {e}"),
     }
+}
 
-    debug!("Fetched {} unseen emails", messages.len());
+/// What [`persist_fetched`] did with a batch.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Persisted {
+    /// Newly stored messages.
+    stored: usize,
+    /// UIDs whose insert failed; they must be fetched again.
+    failed: Vec<u32>,
+}
 
-    let mut uids_to_mark: Vec<String> = Vec::new();
+/// Persist fetched emails to the `messages` table.
+///
+/// Skips self-sent, blocked, and already-stored messages.
+async fn persist_fetched(
+    config: &EmailConfig,
+    db: &Arc<dyn Database>,
+    messages: &[FetchedEmail],
+) -> Persisted {
+    let mut persisted = Persisted::default();
     let from_addr = &config.from_address;
 
-    for (uid, msg_id, sender, content, _subject, ts, reply_meta) in &messages {
+    for (uid, msg_id, sender, content, _subject, ts, reply_meta) in messages {
         // Self-loop prevention
         if sender.eq_ignore_ascii_case(from_addr) {
             debug!(sender = %sender, "Skipping self-sent email");
-            uids_to_mark.push(uid.clone());
             continue;
         }
 
         // Allowlist check
         if !is_sender_allowed(&config.allowed_senders, sender) {
             warn!("Blocked email from {sender}");
-            uids_to_mark.push(uid.clone());
             continue;
         }
 
@@ -104,7 +146,6 @@ async fn poll_once(config: &EmailConfig, db: &Arc<dyn Database>) {
             .flatten()
             .is_some()
         {
-            uids_to_mark.push(uid.clone());
             continue;
         }
 
@@ -116,41 +157,667 @@ async fn poll_once(config: &EmailConfig, db: &Arc<dyn Database>) {
         let metadata = serde_json::json!({ "reply_metadata": reply_meta }).to_string();
 
         match db
-            .insert_message(msg_id, "email", sender, Some(_subject.as_str()), content, received_at, Some(&metadata))
+            .insert_message(
+                msg_id,
+                "email",
+                sender,
+                Some(_subject.as_str()),
+                content,
+                received_at,
+                Some(&metadata),
+                Some(&config.account_id),
+            )
             .await
         {
             Ok(id) => {
                 debug!(id = %id, msg_id = %msg_id, "Persisted email to DB");
+                persisted.stored += 1;
             }
             Err(e) => {
                 error!("Failed to persist email to DB: {e}");
+                if let Ok(uid) = uid.parse() {
+                    persisted.failed.push(uid);
+                }
             }
         }
+    }
+
+    persisted
+}
+
+// ── UID sync ────────────────────────────────────────────────────────
 
-        uids_to_mark.push(uid.clone());
+/// Per-folder sync cursor, kept in `settings` under [`sync_state_key`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct SyncState {
+    pub uid_validity: u32,
+    /// Highest UID already fetched. Only ever moves forward.
+    pub last_uid: u32,
+    /// UIDs at or below `last_uid` that still need fetching: their insert
+    /// failed, or the sync stopped before reaching them.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub retry: Vec<u32>,
+}
+
+pub(crate) fn sync_state_key(account_id: &str, folder: &str) -> String {
+    format!("email_sync:{account_id}:{folder}")
+}
+
+/// Sync every configured folder on an open, logged-in session.
+fn sync_account(
+    session: &mut ImapSession,
+    config: &EmailConfig,
+    db: &Arc<dyn Database>,
+    handle: &Handle,
+) -> Result<(), ImapError> {
+    for folder in &config.folders {
+        sync_folder(session, config, folder, db, handle)?;
     }
+    Ok(())
+}
 
-    // Mark all processed emails as \Seen
-    if !uids_to_mark.is_empty() {
-        let cfg = config.clone();
-        let uids = uids_to_mark;
-        if let Err(e) = tokio::task::spawn_blocking(move || {
-            super::email::mark_seen_imap(&cfg, &uids)
-        })
-        .await
-        .unwrap_or_else(|e| Err(e.to_string().into()))
-        {
-            warn!("Failed to mark emails as seen: {e}");
+/// Fetch messages with UIDs above the stored cursor (plus any left to
+/// retry) and persist them.
+///
+/// Fetching uses `BODY.PEEK[]`, so reading mail elsewhere first doesn't hide
+/// it and syncing doesn't mark it read. On first sync — or when the server
+/// resets UIDVALIDITY — only currently unseen messages are picked up and the
+/// cursor jumps to the end of the folder. If a fetch fails partway, what was
+/// fetched is still persisted and the cursor saved before the error is
+/// returned, so a reconnect doesn't fetch it again.
+fn sync_folder(
+    session: &mut ImapSession,
+    config: &EmailConfig,
+    folder: &str,
+    db: &Arc<dyn Database>,
+    handle: &Handle,
+) -> Result<(), ImapError> {
+    let status = session.select(folder)?;
+    let key = sync_state_key(&config.account_id, folder);
+    let saved: Option<SyncState> = handle
+        .block_on(db.get_setting("default", &key))?
+        .and_then(|v| serde_json::from_value(v).ok());
+
+    let (mut uids, mut last_uid) = match &saved {
+        Some(state) if state.uid_validity == status.uid_validity => {
+            // "n:*" always matches the highest UID, even when it's below n.
+            let mut uids: Vec<u32> = session
+                .uid_search(&format!("UID {}:*", state.last_uid + 1))?
+                .into_iter()
+                .filter(|uid| *uid > state.last_uid)
+                .collect();
+            if !state.retry.is_empty() {
+                // Searching first skips retries that were expunged since.
+                let set: Vec<String> = state.retry.iter().map(u32::to_string).collect();
+                uids.extend(session.uid_search(&format!("UID {}", set.join(",")))?);
+            }
+            (uids, state.last_uid)
+        }
+        previous => {
+            if previous.is_some() {
+                warn!(folder, account = %config.account_id, "UIDVALIDITY changed — resyncing unseen mail");
+            }
+            let floor = status.uid_next.map_or(0, |next| next.saturating_sub(1));
+            (session.uid_search("UNSEEN")?, floor)
+        }
+    };
+    uids.sort_unstable();
+    uids.dedup();
+
+    let mut messages = Vec::new();
+    let mut fetched = Ok(());
+    let mut unfetched = Vec::new();
+    for (i, &uid) in uids.iter().enumerate() {
+        let raw = match session.uid_fetch(uid) {
+            Ok(raw) => raw,
+            Err(e) => {
+                fetched = Err(e);
+                unfetched = uids[i..].to_vec();
+                break;
+            }
+        };
+        if let Some(mut email) = parse_fetched_email(&uid.to_string(), &raw, config) {
+            let location = ImapLocation {
+                folder: folder.to_string(),
+                uid,
+                uid_validity: status.uid_validity,
+            };
+            if let Some(meta) = email.6.as_object_mut() {
+                meta.insert("imap".into(), serde_json::json!(location));
+            }
+            messages.push(email);
+        }
+        last_uid = last_uid.max(uid);
+    }
+
+    // UIDs above the cursor are found again by the next search; the rest
+    // are kept for an explicit retry.
+    let mut retry: Vec<u32> = unfetched.into_iter().filter(|uid| *uid <= last_uid).collect();
+    if !messages.is_empty() {
+        let persisted = handle.block_on(persist_fetched(config, db, &messages));
+        debug!(folder, fetched = messages.len(), persisted = persisted.stored, "Synced IMAP folder");
+        if !persisted.failed.is_empty() {
+            warn!(folder, failed = ?persisted.failed, "Some emails weren't stored — they'll be fetched again");
+        }
+        retry.extend(persisted.failed);
+    }
+    retry.sort_unstable();
+    retry.dedup();
+
+    let state = SyncState {
+        uid_validity: status.uid_validity,
+        last_uid,
+        retry,
+    };
+    if saved.as_ref() != Some(&state) {
+        handle.block_on(db.set_setting("default", &key, &serde_json::json!(state)))?;
+    }
+    fetched
+}
+
+// ── IMAP IDLE ───────────────────────────────────────────────────────
+
+/// Timing knobs for the IDLE worker.
+#[derive(Debug, Clone)]
+pub(crate) struct IdleSettings {
+    /// Re-issue IDLE after this long without news.
+    pub refresh: Duration,
+    /// First reconnect delay after a dropped connection.
+    pub backoff_initial: Duration,
+    /// Upper bound for the reconnect delay.
+    pub backoff_max: Duration,
+}
+
+impl IdleSettings {
+    fn from_config(config: &EmailConfig) -> Self {
+        // IDLE only watches the selected folder, so with several folders
+        // wake up at the poll interval to sync the others.
+        let mut refresh = config.idle_timeout_secs;
+        if config.folders.len() > 1 {
+            refresh = refresh.min(config.poll_interval_secs);
+        }
+        Self {
+            refresh: Duration::from_secs(refresh.max(1)),
+            backoff_initial: Duration::from_secs(1),
+            backoff_max: Duration::from_secs(300),
+        }
+    }
+}
+
+/// Why the IDLE worker stopped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum IdleExit {
+    /// The shutdown flag was set.
+    Shutdown,
+    /// The server doesn't advertise IDLE — poll instead.
+    Unsupported,
+}
+
+/// Exponential reconnect delay: doubles per failure, capped, reset on success.
+#[derive(Debug)]
+pub(crate) struct Backoff {
+    initial: Duration,
+    max: Duration,
+    next: Duration,
+}
+
+impl Backoff {
+    pub(crate) fn new(initial: Duration, max: Duration) -> Self {
+        Self {
+            initial,
+            max,
+            next: initial,
+        }
+    }
+
+    /// Delay before the next attempt.
+    pub(crate) fn next_delay(&mut self) -> Duration {
+        let delay = self.next;
+        self.next = (self.next * 2).min(self.max);
+        delay
+    }
+
+    pub(crate) fn reset(&mut self) {
+        self.next = self.initial;
+    }
+}
+
+/// Run the IDLE worker on a blocking thread until shutdown or fallback.
+pub(crate) async fn run_idle(
+    connector: Arc<dyn ImapConnector>,
+    config: EmailConfig,
+    db: Arc<dyn Database>,
+    shutdown: Arc<AtomicBool>,
+    settings: IdleSettings,
+) -> IdleExit {
+    let handle = Handle::current();
+    let worker = tokio::task::spawn_blocking(move || {
+        idle_worker(connector.as_ref(), &config, &db, &handle, &shutdown, &settings)
+    });
+    match worker.await {
+        Ok(exit) => exit,
+        Err(e) => {
+            error!("IMAP IDLE worker panicked: {e}");
+            IdleExit::Unsupported
         }
     }
 }
 
+/// Keep an IDLE session alive, reconnecting with backoff on failure.
+fn idle_worker(
+    connector: &dyn ImapConnector,
+    config: &EmailConfig,
+    db: &Arc<dyn Database>,
+    handle: &Handle,
+    shutdown: &AtomicBool,
+    settings: &IdleSettings,
+) -> IdleExit {
+    let mut backoff = Backoff::new(settings.backoff_initial, settings.backoff_max);
+
+    while !shutdown.load(Ordering::Relaxed) {
+        match idle_session(connector, config, db, handle, shutdown, settings, &mut backoff) {
+            Ok(exit) => return exit,
+            Err(e) => {
+                let delay = backoff.next_delay();
+                warn!("IMAP IDLE session failed: {e} — reconnecting in {delay:?}");
+                sleep_unless_shutdown(delay, shutdown);
+            }
+        }
+    }
+    IdleExit::Shutdown
+}
+
+/// One connection's lifetime: login, sync, then IDLE → sync until it breaks.
+fn idle_session(
+    connector: &dyn ImapConnector,
+    config: &EmailConfig,
+    db: &Arc<dyn Database>,
+    handle: &Handle,
+    shutdown: &AtomicBool,
+    settings: &IdleSettings,
+    backoff: &mut Backoff,
+) -> Result<IdleExit, ImapError> {
+    let mut session = ImapSession::open(connector)?;
+    session.login(&config.username, &config.password)?;
+    if !session.capabilities()?.iter().any(|c| c == "IDLE") {
+        session.logout();
+        return Ok(IdleExit::Unsupported);
+    }
+    backoff.reset();
+    info!("IMAP IDLE session established on {}", config.imap_host);
+
+    // IDLE watches the first folder; the others are synced on each wake-up.
+    let watched = config.folders.first().map_or("INBOX", String::as_str);
+    loop {
+        sync_account(&mut session, config, db, handle)?;
+        if config.folders.len() > 1 {
+            session.select(watched)?;
+        }
+        loop {
+            match session.idle(settings.refresh, shutdown)? {
+                IdleEvent::NewMail => break,
+                IdleEvent::Timeout if config.folders.len() > 1 => break,
+                IdleEvent::Timeout => debug!("Re-issuing IMAP IDLE"),
+                IdleEvent::Shutdown => {
+                    session.logout();
+                    return Ok(IdleExit::Shutdown);
+                }
+            }
+        }
+    }
+}
+
+fn sleep_unless_shutdown(delay: Duration, shutdown: &AtomicBool) {
+    let step = Duration::from_millis(100);
+    let mut slept = Duration::ZERO;
+    while slept < delay && !shutdown.load(Ordering::Relaxed) {
+        std::thread::sleep(step.min(delay - slept));
+        slept += step;
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::channels::imap_session::test_server::{Mailbox, Script, TestServer};
+    use crate::store::LibSqlBackend;
+
+    fn test_config() -> EmailConfig {
+        EmailConfig {
+            account_id: "work".into(),
+            imap_host: "127.0.0.1".into(),
+            imap_port: 0,
+            smtp_host: "127.0.0.1".into(),
+            smtp_port: 0,
+            username: "me@example.com".into(),
+            password: "secret".into(),
+            from_address: "me@example.com".into(),
+            poll_interval_secs: 60,
+            allowed_senders: vec!["*".into()],
+            folders: vec!["INBOX".into()],
+            imap_idle: true,
+            idle_timeout_secs: 1500,
+        }
+    }
+
+    fn test_settings() -> IdleSettings {
+        IdleSettings {
+            refresh: Duration::from_secs(30),
+            backoff_initial: Duration::from_millis(10),
+            backoff_max: Duration::from_millis(50),
+        }
+    }
+
+    fn raw_email(n: u32) -> String {
+        format!(
+            "From: alice@example.com\r\nTo: me@example.com\r\nSubject: Note {n}\r\n\
+             Message-ID: <note-{n}@example.com>\r\n\r\nBody {n}\r\n"
+        )
+    }
+
+    async fn sync_state(db: &Arc<dyn Database>, folder: &str) -> Option<SyncState> {
+        db.get_setting("default", &sync_state_key("work", folder))
+            .await
+            .unwrap()
+            .map(|v| serde_json::from_value(v).unwrap())
+    }
+
+    /// Run the IDLE worker until `msg_id` is persisted, then shut it down.
+    async fn run_until(server: &TestServer, config: EmailConfig, db: &Arc<dyn Database>, msg_id: &str) {
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let worker = tokio::spawn(run_idle(
+            Arc::new(server.connector()),
+            config,
+            Arc::clone(db),
+            Arc::clone(&shutdown),
+            test_settings(),
+        ));
+        assert!(wait_for_message(db, msg_id).await);
+        shutdown.store(true, Ordering::Relaxed);
+        assert_eq!(worker.await.unwrap(), IdleExit::Shutdown);
+    }
+
+    fn stored_location(stored: &crate::store::StoredMessage) -> Option<ImapLocation> {
+        let metadata: serde_json::Value = serde_json::from_str(stored.metadata.as_deref()?).ok()?;
+        ImapLocation::from_reply_metadata(&metadata["reply_metadata"])
+    }
+
+    async fn wait_for_message(db: &Arc<dyn Database>, msg_id: &str) -> bool {
+        for _ in 0..100 {
+            if db.get_message_by_external_id(msg_id).await.ok().flatten().is_some() {
+                return true;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        false
+    }
 
     #[test]
-    fn poller_compiles() {
-        // Verified by compilation — needs real IMAP for integration tests.
+    fn backoff_doubles_caps_and_resets() {
+        let mut b = Backoff::new(Duration::from_secs(1), Duration::from_secs(5));
+        assert_eq!(b.next_delay(), Duration::from_secs(1));
+        assert_eq!(b.next_delay(), Duration::from_secs(2));
+        assert_eq!(b.next_delay(), Duration::from_secs(4));
+        assert_eq!(b.next_delay(), Duration::from_secs(5));
+        assert_eq!(b.next_delay(), Duration::from_secs(5));
+        b.reset();
+        assert_eq!(b.next_delay(), Duration::from_secs(1));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn idle_syncs_existing_and_pushed_mail() {
+        let server = TestServer::start(
+            "IMAP4rev1 IDLE",
+            vec![Script::Serve],
+            Mailbox {
+                arriving: vec![raw_email(2)],
+                ..Mailbox::with_inbox(vec![raw_email(1)])
+            },
+        );
+        let db: Arc<dyn Database> = Arc::new(LibSqlBackend::new_memory().await.unwrap());
+        let shutdown = Arc::new(AtomicBool::new(false));
+
+        let worker = tokio::spawn(run_idle(
+            Arc::new(server.connector()),
+            test_config(),
+            Arc::clone(&db),
+            Arc::clone(&shutdown),
+            test_settings(),
+        ));
+
+        assert!(wait_for_message(&db, "note-1@example.com").await);
+        assert!(wait_for_message(&db, "note-2@example.com").await);
+
+        shutdown.store(true, Ordering::Relaxed);
+        assert_eq!(worker.await.unwrap(), IdleExit::Shutdown);
+
+        assert_eq!(
+            sync_state(&db, "INBOX").await,
+            Some(SyncState {
+                uid_validity: 1,
+                last_uid: 2,
+                retry: Vec::new()
+            })
+        );
+        let mailbox = server.mailbox.lock().unwrap();
+        // Fetching peeks, so nothing is flagged until a card is resolved.
+        assert!(mailbox.flags("INBOX", 1).is_empty());
+        // One session: a single LOGIN despite two syncs.
+        let logins = mailbox.commands.iter().filter(|c| c.starts_with("LOGIN")).count();
+        assert_eq!(logins, 1);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn idle_reconnects_after_dropped_connection() {
+        let server = TestServer::start(
+            "IMAP4rev1 IDLE",
+            vec![Script::Drop, Script::Drop, Script::Serve],
+            Mailbox::with_inbox(vec![raw_email(1)]),
+        );
+        let db: Arc<dyn Database> = Arc::new(LibSqlBackend::new_memory().await.unwrap());
+        let shutdown = Arc::new(AtomicBool::new(false));
+
+        let worker = tokio::spawn(run_idle(
+            Arc::new(server.connector()),
+            test_config(),
+            Arc::clone(&db),
+            Arc::clone(&shutdown),
+            test_settings(),
+        ));
+
+        assert!(wait_for_message(&db, "note-1@example.com").await);
+        shutdown.store(true, Ordering::Relaxed);
+        assert_eq!(worker.await.unwrap(), IdleExit::Shutdown);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn resumes_from_last_uid_even_if_read_elsewhere() {
+        let mut mailbox = Mailbox::with_inbox(vec![raw_email(1), raw_email(2)]);
+        mailbox.flag("INBOX", 2, "\\Seen");
+        let server = TestServer::start("IMAP4rev1 IDLE", vec![Script::Serve], mailbox);
+        let db: Arc<dyn Database> = Arc::new(LibSqlBackend::new_memory().await.unwrap());
+        let cursor = SyncState {
+            uid_validity: 1,
+            last_uid: 1,
+            retry: Vec::new(),
+        };
+        db.set_setting("default", &sync_state_key("work", "INBOX"), &serde_json::json!(cursor))
+            .await
+            .unwrap();
+
+        run_until(&server, test_config(), &db, "note-2@example.com").await;
+
+        // UID 1 is below the cursor; UID 2 was read on another device but is new to us.
+        assert!(db.get_message_by_external_id("note-1@example.com").await.unwrap().is_none());
+        let stored = db.get_message_by_external_id("note-2@example.com").await.unwrap().unwrap();
+        let location = stored_location(&stored);
+        assert_eq!(
+            location,
+            Some(ImapLocation {
+                folder: "INBOX".into(),
+                uid: 2,
+                uid_validity: 1
+            })
+        );
+        assert_eq!(sync_state(&db, "INBOX").await.unwrap().last_uid, 2);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn uidvalidity_change_resyncs_unseen() {
+        let mut mailbox = Mailbox::with_inbox(vec![raw_email(1), raw_email(2)]);
+        mailbox.flag("INBOX", 1, "\\Seen");
+        let server = TestServer::start("IMAP4rev1 IDLE", vec![Script::Serve], mailbox);
+        let db: Arc<dyn Database> = Arc::new(LibSqlBackend::new_memory().await.unwrap());
+        let stale = SyncState {
+            uid_validity: 7,
+            last_uid: 40,
+            retry: Vec::new(),
+        };
+        db.set_setting("default", &sync_state_key("work", "INBOX"), &serde_json::json!(stale))
+            .await
+            .unwrap();
+
+        run_until(&server, test_config(), &db, "note-2@example.com").await;
+
+        assert!(db.get_message_by_external_id("note-1@example.com").await.unwrap().is_none());
+        assert_eq!(
+            sync_state(&db, "INBOX").await,
+            Some(SyncState {
+                uid_validity: 1,
+                last_uid: 2,
+                retry: Vec::new()
+            })
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn syncs_every_configured_folder() {
+        let mut mailbox = Mailbox::with_inbox(vec![raw_email(1)]);
+        mailbox.deliver("Needs reply", raw_email(2));
+        let server = TestServer::start("IMAP4rev1 IDLE", vec![Script::Serve], mailbox);
+        let db: Arc<dyn Database> = Arc::new(LibSqlBackend::new_memory().await.unwrap());
+        let config = EmailConfig {
+            folders: vec!["INBOX".into(), "Needs reply".into()],
+            ..test_config()
+        };
+
+        run_until(&server, config, &db, "note-2@example.com").await;
+
+        assert!(wait_for_message(&db, "note-1@example.com").await);
+        assert_eq!(sync_state(&db, "Needs reply").await.unwrap().last_uid, 1);
+        let stored = db.get_message_by_external_id("note-2@example.com").await.unwrap().unwrap();
+        let location = stored_location(&stored);
+        assert_eq!(location.unwrap().folder, "Needs reply");
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn server_without_idle_falls_back() {
+        let server = TestServer::start("IMAP4rev1", vec![Script::Serve], Mailbox::default());
+        let db: Arc<dyn Database> = Arc::new(LibSqlBackend::new_memory().await.unwrap());
+
+        let exit = run_idle(
+            Arc::new(server.connector()),
+            test_config(),
+            db,
+            Arc::new(AtomicBool::new(false)),
+            test_settings(),
+        )
+        .await;
+        assert_eq!(exit, IdleExit::Unsupported);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn restart_after_partial_batch_keeps_what_was_fetched() {
+        let mut mailbox = Mailbox::with_inbox(vec![raw_email(1), raw_email(2), raw_email(3)]);
+        mailbox.drop_on_fetch = Some(2);
+        let server = TestServer::start("IMAP4rev1 IDLE", vec![Script::Serve], mailbox);
+        let db: Arc<dyn Database> = Arc::new(LibSqlBackend::new_memory().await.unwrap());
+        let cursor = SyncState {
+            uid_validity: 1,
+            last_uid: 0,
+            retry: Vec::new(),
+        };
+        db.set_setting("default", &sync_state_key("work", "INBOX"), &serde_json::json!(cursor))
+            .await
+            .unwrap();
+
+        run_until(&server, test_config(), &db, "note-3@example.com").await;
+
+        // UID 1 was stored before the connection dropped and isn't fetched again.
+        assert!(wait_for_message(&db, "note-1@example.com").await);
+        let fetches: Vec<usize> = {
+            let mailbox = server.mailbox.lock().unwrap();
+            (1..=3)
+                .map(|uid| {
+                    let cmd = format!("UID FETCH {uid} BODY.PEEK[]");
+                    mailbox.commands.iter().filter(|c| **c == cmd).count()
+                })
+                .collect()
+        };
+        assert_eq!(fetches, [1, 2, 1]);
+        assert_eq!(sync_state(&db, "INBOX").await.unwrap().last_uid, 3);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn retries_are_fetched_without_moving_the_cursor_back() {
+        let server = TestServer::start(
+            "IMAP4rev1 IDLE",
+            vec![Script::Serve],
+            Mailbox::with_inbox(vec![raw_email(1), raw_email(2), raw_email(3)]),
+        );
+        let db: Arc<dyn Database> = Arc::new(LibSqlBackend::new_memory().await.unwrap());
+        // UID 1 failed to store last time; UID 5 has since been expunged.
+        let cursor = SyncState {
+            uid_validity: 1,
+            last_uid: 4,
+            retry: vec![1, 5],
+        };
+        db.set_setting("default", &sync_state_key("work", "INBOX"), &serde_json::json!(cursor))
+            .await
+            .unwrap();
+
+        run_until(&server, test_config(), &db, "note-1@example.com").await;
+
+        assert!(db.get_message_by_external_id("note-2@example.com").await.unwrap().is_none());
+        assert_eq!(
+            sync_state(&db, "INBOX").await,
+            Some(SyncState {
+                uid_validity: 1,
+                last_uid: 4,
+                retry: Vec::new()
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn persist_skips_self_sent() {
+        let db: Arc<dyn Database> = Arc::new(LibSqlBackend::new_memory().await.unwrap());
+        let config = test_config();
+        let messages: Vec<FetchedEmail> = vec![
+            (
+                "1".into(),
+                "<self@example.com>".into(),
+                "me@example.com".into(),
+                "Subject: x\n\nbody".into(),
+                "x".into(),
+                0,
+                serde_json::Value::Null,
+            ),
+            (
+                "2".into(),
+                "<other@example.com>".into(),
+                "alice@example.com".into(),
+                "Subject: y\n\nbody".into(),
+                "y".into(),
+                0,
+                serde_json::Value::Null,
+            ),
+        ];
+
+        let persisted = persist_fetched(&config, &db, &messages).await;
+        assert_eq!(persisted, Persisted { stored: 1, failed: vec![] });
+        assert!(db.get_message_by_external_id("<self@example.com>").await.unwrap().is_none());
+        let stored = db.get_message_by_external_id("<other@example.com>").await.unwrap().unwrap();
+        assert_eq!(stored.account_id.as_deref(), Some("work"));
     }
 }
@@ -6,7 +6,7 @@ use crate::channels::email_types::strip_quoted_text;
 
 #[test]
 fn source_message_body_strips_quoted_text() {
-    // Simulates what fetch_unseen_imap does: body → strip_quoted_text → format
+    // Simulates what parse_fetched_email does: body → strip_quoted_text → format
     let body = "Hey, can you review this PR?\n\n\
                 On Mon, Feb 16, 2026 at 10:00 AM Alice <alice@test.com> wrote:\n\
                 > Sure, I'll take a look at it.\n\
@@ -263,6 +263,93 @@ fn config_from_env_returns_none_when_no_host() {
     assert!(EmailConfig::from_env().is_none());
 }
 
+// ── Multi-account tests ─────────────────────────────────────────
+
+fn account(id: &str, user: &str) -> EmailConfig {
+    EmailConfig {
+        account_id: id.into(),
+        imap_host: "imap.example.com".into(),
+        imap_port: 993,
+        smtp_host: "smtp.example.com".into(),
+        smtp_port: 587,
+        username: user.into(),
+        password: format!("{id}-secret"),
+        from_address: user.into(),
+        poll_interval_secs: 60,
+        allowed_senders: vec![],
+        folders: vec!["INBOX".into()],
+        imap_idle: true,
+        idle_timeout_secs: 1500,
+    }
+}
+
+#[test]
+fn accounts_file_applies_defaults() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("accounts.json");
+    // SAFETY: Unique variable name; no other test reads it.
+    unsafe { std::env::set_var("EMAIL_TEST_WORK_PASSWORD", "from-env") };
+    std::fs::write(
+        &path,
+        r#"{"accounts": [
+            {"id": "work", "imap_host": "imap.work.com", "username": "me@work.com",
+             "password_env": "EMAIL_TEST_WORK_PASSWORD", "allowed_senders": ["*"]},
+            {"id": "personal", "imap_host": "imap.home.net", "imap_port": 1993,
+             "username": "me@home.net", "password": "pw", "imap_idle": false}
+        ]}"#,
+    )
+    .unwrap();
+
+    let accounts = EmailAccounts::from_file(&path).unwrap();
+    assert_eq!(accounts.len(), 2);
+
+    let work = accounts.get("work").unwrap();
+    assert_eq!(work.password, "from-env");
+    assert_eq!(work.smtp_host, "smtp.work.com");
+    assert_eq!(work.smtp_port, 587);
+    assert_eq!(work.from_address, "me@work.com");
+    assert!(work.imap_idle);
+
+    let personal = accounts.get("personal").unwrap();
+    assert_eq!(personal.imap_port, 1993);
+    assert!(!personal.imap_idle);
+    assert!(personal.allowed_senders.is_empty());
+
+    assert_eq!(accounts.default_account().unwrap().account_id, "work");
+}
+
+#[test]
+fn accounts_reject_duplicate_ids() {
+    let err = EmailAccounts::new(vec![account("work", "a@x.com"), account("work", "b@x.com")])
+        .unwrap_err();
+    assert!(err.to_string().contains("duplicate"));
+}
+
+#[test]
+fn reply_account_follows_metadata() {
+    let accounts =
+        EmailAccounts::new(vec![account("work", "me@work.com"), account("personal", "me@home.net")])
+            .unwrap();
+
+    let meta = serde_json::json!({"reply_to": "a@x.com", "account_id": "personal"});
+    assert_eq!(accounts.for_reply(&meta).unwrap().username, "me@home.net");
+
+    // Metadata from before multi-account support uses the default account.
+    let legacy = serde_json::json!({"reply_to": "a@x.com"});
+    assert_eq!(accounts.for_reply(&legacy).unwrap().account_id, "work");
+
+    let unknown = serde_json::json!({"reply_to": "a@x.com", "account_id": "gone"});
+    assert!(accounts.for_reply(&unknown).is_none());
+}
+
+#[test]
+fn send_reply_rejects_unknown_account() {
+    let accounts = EmailAccounts::new(vec![account("work", "me@work.com")]).unwrap();
+    let meta = serde_json::json!({"reply_to": "a@x.com", "account_id": "gone"});
+    let err = send_reply_email(&accounts, &meta, "hi").unwrap_err();
+    assert!(err.to_string().contains("Unknown email account 'gone'"));
+}
+
 // ── EmailChannel construction tests removed ─────────────────────
 // EmailChannel struct deleted — email uses standalone pipeline now.
 // Sender allowlist is tested via standalone `is_sender_allowed()` above.
@@ -286,3 +373,42 @@ fn incoming_message_missing_reply_to() {
     let reply_to = msg.metadata.get("reply_to").and_then(|v| v.as_str());
     assert_eq!(reply_to, None);
 }
+
+// ── Calendar invitations ────────────────────────────────────────
+
+#[test]
+fn fetched_invitation_keeps_calendar_part() {
+    let raw = "From: Alice <alice@example.com>\r\n\
+To: me@example.com\r\n\
+Subject: Invitation: Q2 planning\r\n\
+Message-ID: <inv-1@example.com>\r\n\
+MIME-Version: 1.0\r\n\
+Content-Type: multipart/alternative; boundary=\"b1\"\r\n\
+\r\n\
+--b1\r\n\
+Content-Type: text/plain; charset=UTF-8\r\n\
+\r\n\
+You have been invited to Q2 planning.\r\n\
+--b1\r\n\
+Content-Type: text/calendar; charset=UTF-8; method=REQUEST\r\n\
+\r\n\
+BEGIN:VCALENDAR\r\n\
+METHOD:REQUEST\r\n\
+BEGIN:VEVENT\r\n\
+UID:q2@example.com\r\n\
+DTSTART:20260714T140000Z\r\n\
+SUMMARY:Q2 planning\r\n\
+END:VEVENT\r\n\
+END:VCALENDAR\r\n\
+--b1--\r\n";
+    let config = account("work", "me@example.com");
+    let (_, _, sender, content, _, _, meta) = parse_fetched_email("7", raw.as_bytes(), &config).unwrap();
+    assert_eq!(sender, "alice@example.com");
+    assert!(content.contains("You have been invited"));
+    assert!(meta["calendar"]["ics"].as_str().unwrap().contains("UID:q2@example.com"));
+    assert_eq!(meta["calendar"]["attendee"], "me@example.com");
+
+    let plain = "From: bob@example.com\r\nSubject: Hi\r\n\r\nNo invite here.\r\n";
+    let (.., meta) = parse_fetched_email("8", plain.as_bytes(), &config).unwrap();
+    assert!(meta.get("calendar").is_none());
+}
@@ -0,0 +1,747 @@
+//! Persistent IMAP session — one connection, many commands.
+//!
+//! `ImapSession` speaks just enough IMAP4rev1 for the email pipeline:
+//! LOGIN, CAPABILITY, SELECT, UID SEARCH/FETCH/STORE, LOGOUT, and IDLE
+//! (RFC 2177). Connections come from an `ImapConnector` so the TLS
+//! transport used in production can be swapped for plain TCP in tests.
+
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::channels::email::{EmailConfig, ImapError};
+
+/// Read timeout for ordinary command responses.
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How often an IDLE wait wakes up to check the shutdown flag.
+const IDLE_TICK: Duration = Duration::from_secs(1);
+
+/// A bidirectional byte stream an IMAP session can run over.
+pub(crate) trait ImapStream: Read + Write + Send {
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> std::io::Result<()>;
+}
+
+impl ImapStream for TcpStream {
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> std::io::Result<()> {
+        TcpStream::set_read_timeout(self, timeout)
+    }
+}
+
+impl ImapStream for rustls::StreamOwned<rustls::ClientConnection, TcpStream> {
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> std::io::Result<()> {
+        self.sock.set_read_timeout(timeout)
+    }
+}
+
+/// Opens new IMAP connections.
+pub(crate) trait ImapConnector: Send + Sync {
+    fn connect(&self) -> Result<Box<dyn ImapStream>, ImapError>;
+}
+
+/// Connects over TLS (rustls + webpki roots) — the production transport.
+pub(crate) struct TlsConnector {
+    host: String,
+    port: u16,
+}
+
+impl TlsConnector {
+    pub(crate) fn new(config: &EmailConfig) -> Self {
+        Self {
+            host: config.imap_host.clone(),
+            port: config.imap_port,
+        }
+    }
+}
+
+impl ImapConnector for TlsConnector {
+    fn connect(&self) -> Result<Box<dyn ImapStream>, ImapError> {
+        let tcp = TcpStream::connect((&*self.host, self.port))?;
+
+        let mut root_store = rustls::RootCertStore::empty();
+        root_store.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+        let tls_config = Arc::new(
+            rustls::ClientConfig::builder()
+                .with_root_certificates(root_store)
+                .with_no_client_auth(),
+        );
+        let server_name: rustls::pki_types::ServerName<'_> =
+            rustls::pki_types::ServerName::try_from(self.host.clone())?;
+        let conn = rustls::ClientConnection::new(tls_config, server_name)?;
+        Ok(Box::new(rustls::StreamOwned::new(conn, tcp)))
+    }
+}
+
+/// Responses to one tagged command.
+#[derive(Debug, Default)]
+pub(crate) struct ImapResponse {
+    /// Untagged lines followed by the tagged completion line.
+    pub lines: Vec<String>,
+    /// Literal payloads (`{N}` blocks) in the order they arrived.
+    pub literals: Vec<Vec<u8>>,
+}
+
+/// UID state of a selected mailbox.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct MailboxStatus {
+    /// Changes when previously issued UIDs are no longer valid.
+    pub uid_validity: u32,
+    /// UID the next arriving message will get, if the server says.
+    pub uid_next: Option<u32>,
+}
+
+/// Why an IDLE wait ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum IdleEvent {
+    /// The server reported new messages (`* n EXISTS`).
+    NewMail,
+    /// The refresh interval elapsed — IDLE should be re-issued.
+    Timeout,
+    /// The shutdown flag was set.
+    Shutdown,
+}
+
+/// A logged-in-or-not IMAP connection with its own tag counter.
+pub(crate) struct ImapSession {
+    stream: Box<dyn ImapStream>,
+    /// Bytes read past the last complete line (kept across read timeouts).
+    buf: Vec<u8>,
+    tag: u32,
+}
+
+impl ImapSession {
+    /// Connect and consume the server greeting.
+    pub(crate) fn open(connector: &dyn ImapConnector) -> Result<Self, ImapError> {
+        let stream = connector.connect()?;
+        stream.set_read_timeout(Some(COMMAND_TIMEOUT))?;
+        let mut session = Self {
+            stream,
+            buf: Vec::new(),
+            tag: 0,
+        };
+        let greeting = session.read_line()?;
+        if !greeting.starts_with("* OK") && !greeting.starts_with("* PREAUTH") {
+            return Err(format!("Unexpected IMAP greeting: {}", greeting.trim_end()).into());
+        }
+        Ok(session)
+    }
+
+    fn next_tag(&mut self) -> String {
+        self.tag += 1;
+        format!("A{}", self.tag)
+    }
+
+    /// Read one CRLF-terminated line. Partial lines survive a read timeout.
+    fn read_line(&mut self) -> std::io::Result<String> {
+        loop {
+            if let Some(pos) = self.buf.windows(2).position(|w| w == b"\r\n") {
+                let line: Vec<u8> = self.buf.drain(..pos + 2).collect();
+                return Ok(String::from_utf8_lossy(&line).to_string());
+            }
+            let mut chunk = [0u8; 4096];
+            match self.stream.read(&mut chunk)? {
+                0 => {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::UnexpectedEof,
+                        "IMAP connection closed",
+                    ));
+                }
+                n => self.buf.extend_from_slice(&chunk[..n]),
+            }
+        }
+    }
+
+    /// Read exactly `len` bytes of literal data.
+    fn read_literal(&mut self, len: usize) -> std::io::Result<Vec<u8>> {
+        while self.buf.len() < len {
+            let mut chunk = [0u8; 4096];
+            match self.stream.read(&mut chunk)? {
+                0 => {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::UnexpectedEof,
+                        "IMAP connection closed mid-literal",
+                    ));
+                }
+                n => self.buf.extend_from_slice(&chunk[..n]),
+            }
+        }
+        Ok(self.buf.drain(..len).collect())
+    }
+
+    fn write_line(&mut self, line: &str) -> Result<(), ImapError> {
+        self.stream.write_all(line.as_bytes())?;
+        self.stream.write_all(b"\r\n")?;
+        self.stream.flush()?;
+        Ok(())
+    }
+
+    /// Read responses until the tagged completion line for `tag`.
+    fn read_until_tagged(&mut self, tag: &str) -> Result<ImapResponse, ImapError> {
+        let mut response = ImapResponse::default();
+        loop {
+            let line = self.read_line()?;
+            if let Some(len) = literal_len(&line) {
+                response.literals.push(self.read_literal(len)?);
+            }
+            let done = line
+                .strip_prefix(tag)
+                .is_some_and(|rest| rest.starts_with(' '));
+            response.lines.push(line);
+            if done {
+                return Ok(response);
+            }
+        }
+    }
+
+    /// Send a command and collect its responses. Fails on a tagged NO/BAD.
+    pub(crate) fn command(&mut self, cmd: &str) -> Result<ImapResponse, ImapError> {
+        let tag = self.next_tag();
+        self.write_line(&format!("{tag} {cmd}"))?;
+        let response = self.read_until_tagged(&tag)?;
+        let status = response
+            .lines
+            .last()
+            .and_then(|l| l.split_whitespace().nth(1))
+            .unwrap_or("");
+        if !status.eq_ignore_ascii_case("OK") {
+            let verb = cmd.split_whitespace().next().unwrap_or(cmd);
+            let reply = response.lines.last().map(|l| l.trim_end()).unwrap_or("");
+            return Err(format!("IMAP {verb} failed: {reply}").into());
+        }
+        Ok(response)
+    }
+
+    pub(crate) fn login(&mut self, username: &str, password: &str) -> Result<(), ImapError> {
+        self.command(&format!("LOGIN \"{username}\" \"{password}\""))
+            .map_err(|_| "IMAP login failed")?;
+        Ok(())
+    }
+
+    /// Server capabilities, uppercased (ask after LOGIN — they may change).
+    pub(crate) fn capabilities(&mut self) -> Result<Vec<String>, ImapError> {
+        let response = self.command("CAPABILITY")?;
+        Ok(response
+            .lines
+            .iter()
+            .filter_map(|l| l.strip_prefix("* CAPABILITY "))
+            .flat_map(|l| l.split_whitespace())
+            .map(|c| c.to_uppercase())
+            .collect())
+    }
+
+    /// Select a mailbox and report its UID state.
+    pub(crate) fn select(&mut self, mailbox: &str) -> Result<MailboxStatus, ImapError> {
+        let response = self.command(&format!("SELECT \"{mailbox}\""))?;
+        let mut status = MailboxStatus::default();
+        for line in &response.lines {
+            if let Some(v) = response_code(line, "UIDVALIDITY") {
+                status.uid_validity = v;
+            } else if let Some(v) = response_code(line, "UIDNEXT") {
+                status.uid_next = Some(v);
+            }
+        }
+        if status.uid_validity == 0 {
+            return Err(format!("IMAP SELECT {mailbox}: server sent no UIDVALIDITY").into());
+        }
+        Ok(status)
+    }
+
+    /// `UID SEARCH` with the given criteria, e.g. `UNSEEN` or `UID 42:*`.
+    pub(crate) fn uid_search(&mut self, criteria: &str) -> Result<Vec<u32>, ImapError> {
+        let response = self.command(&format!("UID SEARCH {criteria}"))?;
+        Ok(response
+            .lines
+            .iter()
+            .filter_map(|l| l.strip_prefix("* SEARCH"))
+            .flat_map(|l| l.split_whitespace())
+            .filter_map(|n| n.parse().ok())
+            .collect())
+    }
+
+    /// Raw RFC 822 source of one message. Uses `BODY.PEEK[]` so the fetch
+    /// itself leaves the message's flags alone.
+    pub(crate) fn uid_fetch(&mut self, uid: u32) -> Result<Vec<u8>, ImapError> {
+        let mut response = self.command(&format!("UID FETCH {uid} BODY.PEEK[]"))?;
+        if response.literals.is_empty() {
+            return Err(format!("IMAP UID FETCH {uid} returned no message body").into());
+        }
+        Ok(response.literals.swap_remove(0))
+    }
+
+    /// Add flags (e.g. `\Seen`, `\Answered`) to messages by UID.
+    pub(crate) fn uid_add_flags(&mut self, uids: &[u32], flags: &[&str]) -> Result<(), ImapError> {
+        if uids.is_empty() || flags.is_empty() {
+            return Ok(());
+        }
+        let set: Vec<String> = uids.iter().map(u32::to_string).collect();
+        self.command(&format!(
+            "UID STORE {} +FLAGS.SILENT ({})",
+            set.join(","),
+            flags.join(" ")
+        ))?;
+        Ok(())
+    }
+
+    /// Enter IDLE and wait for new mail, the refresh interval, or shutdown.
+    ///
+    /// Always leaves IDLE (sends `DONE`) before returning `Ok`, so the session
+    /// is ready for the next command.
+    pub(crate) fn idle(
+        &mut self,
+        refresh: Duration,
+        shutdown: &AtomicBool,
+    ) -> Result<IdleEvent, ImapError> {
+        let tag = self.next_tag();
+        self.write_line(&format!("{tag} IDLE"))?;
+        let cont = self.read_line()?;
+        if !cont.starts_with('+') {
+            return Err(format!("IMAP IDLE rejected: {}", cont.trim_end()).into());
+        }
+
+        self.stream.set_read_timeout(Some(IDLE_TICK))?;
+        let started = Instant::now();
+        let waited = loop {
+            if shutdown.load(Ordering::Relaxed) {
+                break Ok(IdleEvent::Shutdown);
+            }
+            if started.elapsed() >= refresh {
+                break Ok(IdleEvent::Timeout);
+            }
+            match self.read_line() {
+                Ok(line) if is_new_mail(&line) => break Ok(IdleEvent::NewMail),
+                // "* OK Still here", EXPUNGE, FETCH flag updates — keep waiting.
+                Ok(_) => {}
+                Err(e) if is_timeout(&e) => {}
+                Err(e) => break Err(e),
+            }
+        };
+        self.stream.set_read_timeout(Some(COMMAND_TIMEOUT))?;
+        let event = waited?;
+
+        self.write_line("DONE")?;
+        self.read_until_tagged(&tag)?;
+        Ok(event)
+    }
+
+    /// Best-effort LOGOUT; the connection is dropped either way.
+    pub(crate) fn logout(mut self) {
+        let _ = self.command("LOGOUT");
+    }
+}
+
+/// Value of a bracketed response code, e.g. `[UIDVALIDITY 42]`.
+fn response_code(line: &str, name: &str) -> Option<u32> {
+    let start = line.find(&format!("[{name} "))? + name.len() + 2;
+    let rest = &line[start..];
+    rest[..rest.find(']')?].trim().parse().ok()
+}
+
+/// `{N}` at the end of a line announces an N-byte literal.
+fn literal_len(line: &str) -> Option<usize> {
+    let body = line.trim_end().strip_suffix('}')?;
+    let open = body.rfind('{')?;
+    body[open + 1..].trim_end_matches('+').parse().ok()
+}
+
+fn is_new_mail(line: &str) -> bool {
+    line.starts_with("* ") && line.trim_end().to_uppercase().ends_with(" EXISTS")
+}
+
+fn is_timeout(e: &std::io::Error) -> bool {
+    matches!(
+        e.kind(),
+        std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+    )
+}
+
+/// A scripted IMAP stand-in for tests: plain TCP on localhost.
+#[cfg(test)]
+pub(crate) mod test_server {
+    use std::collections::BTreeMap;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::{SocketAddr, TcpListener, TcpStream};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use super::{ImapConnector, ImapError, ImapStream};
+
+    /// Connects to a test server without TLS.
+    pub(crate) struct PlainConnector(pub SocketAddr);
+
+    impl ImapConnector for PlainConnector {
+        fn connect(&self) -> Result<Box<dyn ImapStream>, ImapError> {
+            Ok(Box::new(TcpStream::connect(self.0)?))
+        }
+    }
+
+    /// What the stand-in server does with each accepted connection.
+    #[derive(Clone, Copy, PartialEq, Eq)]
+    pub(crate) enum Script {
+        /// Close immediately without a greeting.
+        Drop,
+        /// Behave like a normal mailbox.
+        Serve,
+    }
+
+    /// One stored message. UIDs are assigned in arrival order from 1.
+    #[derive(Clone)]
+    pub(crate) struct StoredMail {
+        pub uid: u32,
+        pub raw: String,
+        pub flags: Vec<String>,
+    }
+
+    /// Shared mailbox state, inspectable from the test.
+    pub(crate) struct Mailbox {
+        pub uid_validity: u32,
+        pub folders: BTreeMap<String, Vec<StoredMail>>,
+        /// Messages delivered to INBOX while the client is IDLE.
+        pub arriving: Vec<String>,
+        /// Every command received, tag stripped.
+        pub commands: Vec<String>,
+        /// Drop the connection on the next FETCH of this UID (once).
+        pub drop_on_fetch: Option<u32>,
+    }
+
+    impl Default for Mailbox {
+        fn default() -> Self {
+            Self {
+                uid_validity: 1,
+                folders: BTreeMap::from([("INBOX".to_string(), Vec::new())]),
+                arriving: Vec::new(),
+                commands: Vec::new(),
+                drop_on_fetch: None,
+            }
+        }
+    }
+
+    impl Mailbox {
+        pub(crate) fn with_inbox(messages: Vec<String>) -> Self {
+            let mut mailbox = Self::default();
+            for raw in messages {
+                mailbox.deliver("INBOX", raw);
+            }
+            mailbox
+        }
+
+        /// Append a message to a folder (created on demand) and return its UID.
+        pub(crate) fn deliver(&mut self, folder: &str, raw: String) -> u32 {
+            let mails = self.folders.entry(folder.to_string()).or_default();
+            let uid = mails.last().map_or(1, |m| m.uid + 1);
+            mails.push(StoredMail {
+                uid,
+                raw,
+                flags: Vec::new(),
+            });
+            uid
+        }
+
+        /// Set a flag directly, as another mail client would.
+        pub(crate) fn flag(&mut self, folder: &str, uid: u32, flag: &str) {
+            if let Some(m) = self.mail_mut(folder, uid) {
+                m.flags.push(flag.to_string());
+            }
+        }
+
+        pub(crate) fn flags(&self, folder: &str, uid: u32) -> Vec<String> {
+            self.folders
+                .get(folder)
+                .and_then(|mails| mails.iter().find(|m| m.uid == uid))
+                .map(|m| m.flags.clone())
+                .unwrap_or_default()
+        }
+
+        fn mail_mut(&mut self, folder: &str, uid: u32) -> Option<&mut StoredMail> {
+            self.folders.get_mut(folder)?.iter_mut().find(|m| m.uid == uid)
+        }
+    }
+
+    pub(crate) struct TestServer {
+        pub addr: SocketAddr,
+        pub mailbox: Arc<Mutex<Mailbox>>,
+    }
+
+    impl TestServer {
+        /// Start a server that follows `scripts` for successive connections
+        /// (the last script repeats).
+        pub(crate) fn start(
+            capabilities: &'static str,
+            scripts: Vec<Script>,
+            mailbox: Mailbox,
+        ) -> Self {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let addr = listener.local_addr().unwrap();
+            let mailbox = Arc::new(Mutex::new(mailbox));
+            let shared = Arc::clone(&mailbox);
+
+            std::thread::spawn(move || {
+                for (i, stream) in listener.incoming().enumerate() {
+                    let Ok(stream) = stream else { return };
+                    let script = scripts.get(i).or(scripts.last()).copied().unwrap_or(Script::Serve);
+                    if script == Script::Drop {
+                        drop(stream);
+                        continue;
+                    }
+                    let mailbox = Arc::clone(&shared);
+                    std::thread::spawn(move || {
+                        let _ = serve(stream, capabilities, &mailbox);
+                    });
+                }
+            });
+
+            Self { addr, mailbox }
+        }
+
+        pub(crate) fn connector(&self) -> PlainConnector {
+            PlainConnector(self.addr)
+        }
+    }
+
+    /// Parse a UID set like `3`, `1,4`, or `5:*` against the folder's UIDs.
+    fn uid_set(spec: &str, uids: &[u32]) -> Vec<u32> {
+        let max = uids.iter().copied().max().unwrap_or(0);
+        let mut out = Vec::new();
+        for part in spec.split(',') {
+            let (lo, hi) = match part.split_once(':') {
+                Some((lo, "*")) => (lo.parse().unwrap_or(1), max),
+                Some((lo, hi)) => (lo.parse().unwrap_or(1), hi.parse().unwrap_or(0)),
+                None => {
+                    let n = part.parse().unwrap_or(0);
+                    (n, n)
+                }
+            };
+            // RFC 3501: "n:*" always includes the highest UID, even if < n.
+            let (lo, hi) = if part.ends_with(":*") && lo > hi { (hi, hi) } else { (lo, hi) };
+            out.extend(uids.iter().copied().filter(|u| *u >= lo && *u <= hi));
+        }
+        out
+    }
+
+    fn serve(stream: TcpStream, capabilities: &str, mailbox: &Mutex<Mailbox>) -> std::io::Result<()> {
+        let mut out = stream.try_clone()?;
+        let mut reader = BufReader::new(stream);
+        out.write_all(b"* OK test IMAP ready\r\n")?;
+
+        let mut selected = String::new();
+        let mut line = String::new();
+        loop {
+            line.clear();
+            if reader.read_line(&mut line)? == 0 {
+                return Ok(());
+            }
+            let mut parts = line.trim_end().splitn(2, ' ');
+            let tag = parts.next().unwrap_or("*").to_string();
+            let cmd = parts.next().unwrap_or("").to_string();
+            let words: Vec<String> = cmd.split_whitespace().map(str::to_string).collect();
+            let verb = words.first().map(|w| w.to_uppercase()).unwrap_or_default();
+            let mut mb = mailbox.lock().unwrap();
+            mb.commands.push(cmd.clone());
+
+            match verb.as_str() {
+                "LOGIN" if cmd.contains("\"wrong\"") => {
+                    write!(out, "{tag} NO [AUTHENTICATIONFAILED] bad credentials\r\n")?;
+                }
+                "CAPABILITY" => {
+                    write!(out, "* CAPABILITY {capabilities}\r\n{tag} OK done\r\n")?;
+                }
+                "SELECT" => {
+                    let folder = cmd["SELECT ".len()..].trim_matches('"').to_string();
+                    match mb.folders.get(&folder) {
+                        Some(mails) => {
+                            let next = mails.last().map_or(1, |m| m.uid + 1);
+                            write!(
+                                out,
+                                "* {} EXISTS\r\n* OK [UIDVALIDITY {}] ok\r\n* OK [UIDNEXT {next}] ok\r\n\
+                                 {tag} OK [READ-WRITE] selected\r\n",
+                                mails.len(),
+                                mb.uid_validity
+                            )?;
+                            selected = folder;
+                        }
+                        None => write!(out, "{tag} NO no such folder\r\n")?,
+                    }
+                }
+                "UID" => {
+                    let sub = words.get(1).map(|w| w.to_uppercase()).unwrap_or_default();
+                    let mails = mb.folders.get(&selected).cloned().unwrap_or_default();
+                    let uids: Vec<u32> = mails.iter().map(|m| m.uid).collect();
+                    match sub.as_str() {
+                        "SEARCH" => {
+                            let hits: Vec<u32> = if words.get(2).is_some_and(|w| w == "UNSEEN") {
+                                mails
+                                    .iter()
+                                    .filter(|m| !m.flags.iter().any(|f| f == "\\Seen"))
+                                    .map(|m| m.uid)
+                                    .collect()
+                            } else {
+                                uid_set(words.get(3).map(String::as_str).unwrap_or(""), &uids)
+                            };
+                            let ids: String = hits.iter().map(|u| format!(" {u}")).collect();
+                            write!(out, "* SEARCH{ids}\r\n{tag} OK done\r\n")?;
+                        }
+                        "FETCH" => {
+                            let uid: u32 = words.get(2).and_then(|w| w.parse().ok()).unwrap_or(0);
+                            if mb.drop_on_fetch == Some(uid) {
+                                mb.drop_on_fetch = None;
+                                return Ok(());
+                            }
+                            match mails.iter().position(|m| m.uid == uid) {
+                                Some(i) => write!(
+                                    out,
+                                    "* {} FETCH (UID {uid} BODY[] {{{}}}\r\n{})\r\n{tag} OK done\r\n",
+                                    i + 1,
+                                    mails[i].raw.len(),
+                                    mails[i].raw
+                                )?,
+                                None => write!(out, "{tag} OK nothing\r\n")?,
+                            }
+                        }
+                        "STORE" => {
+                            let targets = uid_set(words.get(2).map(String::as_str).unwrap_or(""), &uids);
+                            let flags = cmd
+                                .split_once('(')
+                                .and_then(|(_, f)| f.split_once(')'))
+                                .map(|(f, _)| f.to_string())
+                                .unwrap_or_default();
+                            let folder = selected.clone();
+                            for uid in targets {
+                                for flag in flags.split_whitespace() {
+                                    mb.flag(&folder, uid, flag);
+                                }
+                            }
+                            write!(out, "{tag} OK stored\r\n")?;
+                        }
+                        _ => write!(out, "{tag} BAD unknown UID command\r\n")?,
+                    }
+                }
+                "IDLE" => {
+                    write!(out, "+ idling\r\n")?;
+                    drop(mb);
+                    std::thread::sleep(Duration::from_millis(50));
+                    let arrived = {
+                        let mut mb = mailbox.lock().unwrap();
+                        let arriving = std::mem::take(&mut mb.arriving);
+                        let any = !arriving.is_empty();
+                        for raw in arriving {
+                            mb.deliver("INBOX", raw);
+                        }
+                        any.then(|| mb.folders["INBOX"].len())
+                    };
+                    if let Some(n) = arrived {
+                        write!(out, "* {n} EXISTS\r\n")?;
+                    }
+                    line.clear();
+                    reader.read_line(&mut line)?; // DONE
+                    write!(out, "{tag} OK IDLE terminated\r\n")?;
+                }
+                "LOGOUT" => {
+                    write!(out, "* BYE\r\n{tag} OK bye\r\n")?;
+                    return Ok(());
+                }
+                _ => write!(out, "{tag} OK done\r\n")?,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_server::{Mailbox, Script, TestServer};
+    use super::*;
+
+    const RAW: &str = "From: alice@example.com\r\nSubject: Hi\r\nMessage-ID: <m1@example.com>\r\n\r\nHello there\r\n";
+
+    #[test]
+    fn literal_len_parses_announcements() {
+        assert_eq!(literal_len("* 1 FETCH (RFC822 {342}\r\n"), Some(342));
+        assert_eq!(literal_len("* 1 FETCH (RFC822 {12+}\r\n"), Some(12));
+        assert_eq!(literal_len("* OK done\r\n"), None);
+    }
+
+    #[test]
+    fn response_code_parses_uid_state() {
+        assert_eq!(response_code("* OK [UIDVALIDITY 3857529045] UIDs valid\r\n", "UIDVALIDITY"), Some(3857529045));
+        assert_eq!(response_code("* OK [UIDNEXT 4392] Predicted\r\n", "UIDNEXT"), Some(4392));
+        assert_eq!(response_code("* OK [UIDNEXT 4392] Predicted\r\n", "UIDVALIDITY"), None);
+    }
+
+    #[test]
+    fn new_mail_detection() {
+        assert!(is_new_mail("* 4 EXISTS\r\n"));
+        assert!(!is_new_mail("* 4 EXPUNGE\r\n"));
+        assert!(!is_new_mail("* OK Still here\r\n"));
+    }
+
+    #[test]
+    fn session_fetches_by_uid_and_sets_flags() {
+        let server = TestServer::start(
+            "IMAP4rev1 IDLE",
+            vec![Script::Serve],
+            Mailbox::with_inbox(vec![RAW.to_string()]),
+        );
+        let mut session = ImapSession::open(&server.connector()).unwrap();
+        session.login("user", "pass").unwrap();
+        assert!(session.capabilities().unwrap().contains(&"IDLE".to_string()));
+        let status = session.select("INBOX").unwrap();
+        assert_eq!(status.uid_validity, 1);
+        assert_eq!(status.uid_next, Some(2));
+
+        let uids = session.uid_search("UID 1:*").unwrap();
+        assert_eq!(uids, vec![1]);
+        let raw = session.uid_fetch(1).unwrap();
+        assert_eq!(String::from_utf8(raw).unwrap(), RAW);
+        // BODY.PEEK leaves the message unread.
+        assert_eq!(session.uid_search("UNSEEN").unwrap(), vec![1]);
+
+        session.uid_add_flags(&uids, &["\\Seen", "\\Answered"]).unwrap();
+        assert!(session.uid_search("UNSEEN").unwrap().is_empty());
+        assert_eq!(
+            server.mailbox.lock().unwrap().flags("INBOX", 1),
+            vec!["\\Seen", "\\Answered"]
+        );
+        session.logout();
+    }
+
+    #[test]
+    fn rejected_login_is_an_error() {
+        let server = TestServer::start("IMAP4rev1", vec![Script::Serve], Mailbox::default());
+        let mut session = ImapSession::open(&server.connector()).unwrap();
+        let err = session.login("user", "wrong").unwrap_err();
+        assert!(err.to_string().contains("login failed"));
+    }
+
+    #[test]
+    fn idle_reports_new_mail_and_leaves_idle() {
+        let server = TestServer::start(
+            "IMAP4rev1 IDLE",
+            vec![Script::Serve],
+            Mailbox {
+                arriving: vec![RAW.to_string()],
+                ..Default::default()
+            },
+        );
+        let mut session = ImapSession::open(&server.connector()).unwrap();
+        session.login("user", "pass").unwrap();
+        session.select("INBOX").unwrap();
+
+        let shutdown = AtomicBool::new(false);
+        let event = session.idle(Duration::from_secs(10), &shutdown).unwrap();
+        assert_eq!(event, IdleEvent::NewMail);
+        // Session is usable again after DONE.
+        assert_eq!(session.uid_search("UNSEEN").unwrap(), vec![1]);
+    }
+
+    #[test]
+    fn idle_stops_on_shutdown() {
+        let server = TestServer::start("IMAP4rev1 IDLE", vec![Script::Serve], Mailbox::default());
+        let mut session = ImapSession::open(&server.connector()).unwrap();
+        session.login("user", "pass").unwrap();
+        session.select("INBOX").unwrap();
+
+        let shutdown = AtomicBool::new(true);
+        let event = session.idle(Duration::from_secs(10), &shutdown).unwrap();
+        assert_eq!(event, IdleEvent::Shutdown);
+    }
+}
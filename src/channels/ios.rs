@@ -203,10 +203,30 @@ impl Channel for IosChannel {
         Ok(())
     }
 
+    async fn broadcast(
+        &self,
+        _user_id: &str,
+        response: OutgoingResponse,
+    ) -> Result<(), ChannelError> {
+        let server_msg = ServerMessage::Response {
+            content: response.content,
+            thread_id: response.thread_id,
+        };
+        // Unlike a reply, nobody may be listening — that's not a delivery.
+        self.inner
+            .outgoing_tx
+            .send(server_msg)
+            .map(|_| ())
+            .map_err(|_| ChannelError::SendFailed {
+                name: "ios".into(),
+                reason: "No connected clients".into(),
+            })
+    }
+
     async fn send_status(
         &self,
         status: StatusUpdate,
-        _metadata: &serde_json::Value,
+        metadata: &serde_json::Value,
     ) -> Result<(), ChannelError> {
         let server_msg = match status {
             StatusUpdate::Thinking(msg) => ServerMessage::Thinking { message: msg },
@@ -219,7 +239,10 @@ impl Channel for IosChannel {
             }
             StatusUpdate::StreamChunk(text) => ServerMessage::StreamChunk {
                 content: text,
-                thread_id: None,
+                thread_id: metadata
+                    .get("thread_id")
+                    .and_then(|v| v.as_str())
+                    .map(String::from),
             },
             StatusUpdate::Status(msg) => ServerMessage::Status { message: msg },
             StatusUpdate::JobStarted { title, .. } => ServerMessage::Status { message: title },
@@ -248,6 +271,19 @@ impl Channel for IosChannel {
                     if success { &message } else { "auth failed" }
                 ),
             },
+            StatusUpdate::SafetyEvent {
+                tool_name,
+                action,
+                reasons,
+            } => ServerMessage::Status {
+                message: format!(
+                    "Safety {:?} ({}): {}",
+                    action,
+                    tool_name.as_deref().unwrap_or("input"),
+                    reasons.join("; ")
+                ),
+            },
+            StatusUpdate::BudgetPaused { reason } => ServerMessage::Status { message: reason },
         };
 
         let _ = self.inner.outgoing_tx.send(server_msg);
@@ -314,7 +350,10 @@ async fn handle_chat_socket(mut socket: WebSocket, inner: Arc<IosChannelInner>)
                                 }
                                 let mut msg = IncomingMessage::new("ios", "ios-user", &content);
                                 if let Some(ref tid) = thread_id {
-                                    msg = msg.with_thread(tid);
+                                    // Echoed on stream chunks, which only see metadata
+                                    msg = msg
+                                        .with_thread(tid)
+                                        .with_metadata(serde_json::json!({ "thread_id": tid }));
                                 }
                                 if inner.incoming_tx.send(msg).is_err() {
                                     warn!("iOS incoming channel closed");
@@ -5,6 +5,7 @@ pub mod cli;
 pub mod email;
 pub mod email_poller;
 pub mod email_types;
+pub(crate) mod imap_session;
 pub mod ios;
 pub mod manager;
 pub mod telegram;
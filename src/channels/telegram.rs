@@ -2,8 +2,19 @@
 //!
 //! Native Rust Telegram Bot API implementation, adapted to
 //! ai-assist's Channel trait (MessageStream, respond, send_status).
+//!
+//! Streamed responses (`StatusUpdate::StreamChunk`) post a placeholder
+//! message and progressively `editMessageText` it; `respond` then replaces
+//! the placeholder with the final answer. A turn that stops for tool
+//! approval settles the placeholder with what was streamed so far.
+//!
+//! `broadcast` sends proactively (e.g. an approved reply card) to the
+//! `chat_id` in the response metadata, threaded under
+//! `reply_to_message_id` when one is given.
 
+use std::collections::HashMap;
 use std::path::Path;
+use std::time::{Duration, Instant};
 
 use async_trait::async_trait;
 use reqwest::multipart::{Form, Part};
@@ -14,24 +25,69 @@ use crate::error::ChannelError;
 /// Maximum message length for Telegram's sendMessage API.
 const TELEGRAM_MAX_MESSAGE_LENGTH: usize = 4096;
 
+/// Minimum time between edits of a streaming message. Telegram throttles
+/// bots that edit the same chat more than about once per second.
+const STREAM_EDIT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Text of the placeholder posted when a response starts streaming.
+const STREAM_PLACEHOLDER: &str = "…";
+
+/// A placeholder message being edited as a response streams in.
+struct StreamDraft {
+    /// `None` while the placeholder is still being posted.
+    message_id: Option<i64>,
+    text: String,
+    last_edit: Instant,
+}
+
+impl StreamDraft {
+    /// The edit to make now, if the placeholder is up and the last edit
+    /// was long enough ago.
+    fn due_edit(&mut self) -> Option<(i64, String)> {
+        let message_id = self.message_id?;
+        if self.last_edit.elapsed() < STREAM_EDIT_INTERVAL || self.text.trim().is_empty() {
+            return None;
+        }
+        self.last_edit = Instant::now();
+        Some((message_id, draft_preview(&self.text, TELEGRAM_MAX_MESSAGE_LENGTH)))
+    }
+}
+
+/// Default Bot API endpoint.
+const TELEGRAM_API_BASE: &str = "https://api.telegram.org";
+
 /// Telegram channel — connects to the Bot API via long-polling.
 pub struct TelegramChannel {
     bot_token: String,
+    /// Bot API endpoint, without a trailing slash.
+    api_base: String,
     allowed_users: Vec<String>,
     client: reqwest::Client,
+    /// In-progress streamed responses, keyed by chat ID. Never held across
+    /// a Bot API call.
+    drafts: tokio::sync::Mutex<HashMap<String, StreamDraft>>,
 }
 
 impl TelegramChannel {
     pub fn new(bot_token: String, allowed_users: Vec<String>) -> Self {
         Self {
             bot_token,
+            api_base: TELEGRAM_API_BASE.to_string(),
             allowed_users,
             client: reqwest::Client::new(),
+            drafts: tokio::sync::Mutex::new(HashMap::new()),
         }
     }
 
+    /// Use a different Bot API endpoint (a local Bot API server, or a test
+    /// stand-in).
+    pub fn with_api_base(mut self, api_base: impl Into<String>) -> Self {
+        self.api_base = api_base.into().trim_end_matches('/').to_string();
+        self
+    }
+
     fn api_url(&self, method: &str) -> String {
-        format!("https://api.telegram.org/bot{}/{method}", self.bot_token)
+        format!("{}/bot{}/{method}", self.api_base, self.bot_token)
     }
 
     /// Check if a username is in the allowed list.
@@ -48,25 +104,34 @@ impl TelegramChannel {
     }
 
     /// Send a text message, trying Markdown first with plain text fallback.
-    /// Splits long messages that exceed Telegram's 4096 char limit.
-    async fn send_message(&self, chat_id: &str, text: &str) -> Result<(), ChannelError> {
+    /// Splits long messages that exceed Telegram's 4096 char limit; only the
+    /// first chunk is threaded under `reply_to`.
+    async fn send_message(
+        &self,
+        chat_id: &str,
+        text: &str,
+        reply_to: Option<i64>,
+    ) -> Result<(), ChannelError> {
         // Split long messages
         let chunks = split_message(text, TELEGRAM_MAX_MESSAGE_LENGTH);
 
-        for chunk in &chunks {
-            self.send_message_chunk(chat_id, chunk).await?;
+        for (i, chunk) in chunks.iter().enumerate() {
+            let reply_to = if i == 0 { reply_to } else { None };
+            self.send_message_chunk(chat_id, chunk, reply_to).await?;
         }
         Ok(())
     }
 
     /// Send a single message chunk (≤4096 chars), Markdown-first with fallback.
-    async fn send_message_chunk(&self, chat_id: &str, text: &str) -> Result<(), ChannelError> {
+    async fn send_message_chunk(
+        &self,
+        chat_id: &str,
+        text: &str,
+        reply_to: Option<i64>,
+    ) -> Result<(), ChannelError> {
         // Try Markdown first
-        let markdown_body = serde_json::json!({
-            "chat_id": chat_id,
-            "text": text,
-            "parse_mode": "Markdown"
-        });
+        let mut markdown_body = message_body(chat_id, text, reply_to);
+        markdown_body["parse_mode"] = "Markdown".into();
 
         let markdown_resp = self
             .client
@@ -91,10 +156,7 @@ impl TelegramChannel {
         );
 
         // Retry without parse_mode
-        let plain_body = serde_json::json!({
-            "chat_id": chat_id,
-            "text": text,
-        });
+        let plain_body = message_body(chat_id, text, reply_to);
         let plain_resp = self
             .client
             .post(self.api_url("sendMessage"))
@@ -120,6 +182,194 @@ impl TelegramChannel {
         Ok(())
     }
 
+    // ── Streaming ──────────────────────────────────────────────────
+
+    /// Call a Bot API method and return its `result`.
+    async fn call_api(
+        &self,
+        method: &str,
+        body: &serde_json::Value,
+    ) -> Result<serde_json::Value, ChannelError> {
+        let resp = self
+            .client
+            .post(self.api_url(method))
+            .json(body)
+            .send()
+            .await
+            .map_err(|e| ChannelError::SendFailed {
+                name: "telegram".into(),
+                reason: e.to_string(),
+            })?;
+
+        let status = resp.status();
+        let mut json: serde_json::Value = resp.json().await.unwrap_or_default();
+        if !status.is_success() || json["ok"] != serde_json::Value::Bool(true) {
+            return Err(ChannelError::SendFailed {
+                name: "telegram".into(),
+                reason: format!(
+                    "{method} failed ({status}): {}",
+                    json["description"].as_str().unwrap_or("no description")
+                ),
+            });
+        }
+        Ok(json["result"].take())
+    }
+
+    /// Replace the text of a message the bot sent earlier.
+    async fn edit_message(
+        &self,
+        chat_id: &str,
+        message_id: i64,
+        text: &str,
+        markdown: bool,
+    ) -> Result<(), ChannelError> {
+        let mut body = serde_json::json!({
+            "chat_id": chat_id,
+            "message_id": message_id,
+            "text": text,
+        });
+        if markdown {
+            body["parse_mode"] = "Markdown".into();
+        }
+        match self.call_api("editMessageText", &body).await {
+            Err(e) if !is_not_modified(&e) => Err(e),
+            _ => Ok(()),
+        }
+    }
+
+    /// Delete a message the bot sent earlier.
+    async fn delete_message(&self, chat_id: &str, message_id: i64) -> Result<(), ChannelError> {
+        self.call_api(
+            "deleteMessage",
+            &serde_json::json!({"chat_id": chat_id, "message_id": message_id}),
+        )
+        .await
+        .map(|_| ())
+    }
+
+    /// Append a streamed chunk to the chat's draft, posting the placeholder
+    /// on the first chunk and editing at most once per `STREAM_EDIT_INTERVAL`.
+    /// A failed request drops the draft, so the final answer is sent fresh.
+    async fn stream_chunk(&self, chat_id: &str, chunk: &str) -> Result<(), ChannelError> {
+        let edit = {
+            let mut drafts = self.drafts.lock().await;
+            match drafts.get_mut(chat_id) {
+                Some(draft) => {
+                    draft.text.push_str(chunk);
+                    let Some(edit) = draft.due_edit() else {
+                        return Ok(());
+                    };
+                    Some(edit)
+                }
+                None => {
+                    drafts.insert(
+                        chat_id.to_string(),
+                        StreamDraft {
+                            message_id: None,
+                            text: chunk.to_string(),
+                            last_edit: Instant::now(),
+                        },
+                    );
+                    None
+                }
+            }
+        };
+        let result = match edit {
+            // Plain text: a half-streamed Markdown entity would be rejected.
+            Some((message_id, preview)) => {
+                self.edit_message(chat_id, message_id, &preview, false).await
+            }
+            None => self.post_placeholder(chat_id).await,
+        };
+        if result.is_err() {
+            self.drafts.lock().await.remove(chat_id);
+        }
+        result
+    }
+
+    /// Post the placeholder for a freshly started draft.
+    async fn post_placeholder(&self, chat_id: &str) -> Result<(), ChannelError> {
+        let sent = self
+            .call_api(
+                "sendMessage",
+                &serde_json::json!({"chat_id": chat_id, "text": STREAM_PLACEHOLDER}),
+            )
+            .await?;
+        let message_id = sent["message_id"]
+            .as_i64()
+            .ok_or_else(|| ChannelError::SendFailed {
+                name: "telegram".into(),
+                reason: "sendMessage returned no message_id".into(),
+            })?;
+        let orphaned = match self.drafts.lock().await.get_mut(chat_id) {
+            Some(draft) if draft.message_id.is_none() => {
+                draft.message_id = Some(message_id);
+                false
+            }
+            _ => true,
+        };
+        // The turn finished while the placeholder was in flight.
+        if orphaned {
+            let _ = self.delete_message(chat_id, message_id).await;
+        }
+        Ok(())
+    }
+
+    /// Settle a draft whose turn ended without a `respond` — keep what was
+    /// streamed, or delete a placeholder that never got any text.
+    async fn settle_draft(&self, chat_id: &str) {
+        let Some(draft) = self.drafts.lock().await.remove(chat_id) else {
+            return;
+        };
+        let result = match draft.message_id {
+            Some(message_id) if draft.text.trim().is_empty() => {
+                self.delete_message(chat_id, message_id).await
+            }
+            _ if draft.text.trim().is_empty() => Ok(()),
+            _ => {
+                let text = draft.text.clone();
+                self.finish_draft(chat_id, draft, &text).await
+            }
+        };
+        if let Err(e) = result {
+            tracing::debug!(error = %e, "Failed to settle streamed Telegram message");
+        }
+    }
+
+    /// Replace a streaming placeholder with the final text, sending any
+    /// overflow as follow-up messages. Falls back to a fresh message if the
+    /// placeholder can't be edited.
+    async fn finish_draft(
+        &self,
+        chat_id: &str,
+        draft: StreamDraft,
+        text: &str,
+    ) -> Result<(), ChannelError> {
+        let Some(message_id) = draft.message_id else {
+            return self.send_message(chat_id, text, None).await;
+        };
+        let chunks = split_message(text, TELEGRAM_MAX_MESSAGE_LENGTH);
+        let first = chunks.first().map(String::as_str).unwrap_or_default();
+
+        let edited = match self.edit_message(chat_id, message_id, first, true).await {
+            Ok(()) => true,
+            Err(_) => self
+                .edit_message(chat_id, message_id, first, false)
+                .await
+                .inspect_err(|e| {
+                    tracing::warn!(error = %e, "Failed to finalize streamed Telegram message")
+                })
+                .is_ok(),
+        };
+        if !edited {
+            return self.send_message(chat_id, text, None).await;
+        }
+        for chunk in chunks.iter().skip(1) {
+            self.send_message_chunk(chat_id, chunk, None).await?;
+        }
+        Ok(())
+    }
+
     // ── Rich media methods ─────────────────────────────────────────
 
     /// Send a document/file to a Telegram chat.
@@ -460,7 +710,7 @@ impl Channel for TelegramChannel {
 
     async fn start(&self) -> Result<MessageStream, ChannelError> {
         let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
-        let bot_token = self.bot_token.clone();
+        let url = self.api_url("getUpdates");
         let allowed_users = self.allowed_users.clone();
         let client = self.client.clone();
 
@@ -470,7 +720,6 @@ impl Channel for TelegramChannel {
             tracing::info!("Telegram channel listening for messages...");
 
             loop {
-                let url = format!("https://api.telegram.org/bot{}/getUpdates", bot_token);
                 let body = serde_json::json!({
                     "offset": offset,
                     "timeout": 30,
@@ -559,6 +808,10 @@ impl Channel for TelegramChannel {
                             .and_then(|n| n.as_str())
                             .map(String::from);
 
+                        let message_id = message
+                            .get("message_id")
+                            .and_then(serde_json::Value::as_i64);
+
                         // Build IncomingMessage with chat_id in metadata
                         let mut incoming = IncomingMessage::new(
                             "telegram",
@@ -567,6 +820,7 @@ impl Channel for TelegramChannel {
                         );
                         incoming = incoming.with_metadata(serde_json::json!({
                             "chat_id": chat_id,
+                            "message_id": message_id,
                             "username": username,
                         }));
                         if let Some(name) = first_name.as_deref().or(Some(username)) {
@@ -605,7 +859,29 @@ impl Channel for TelegramChannel {
                 reason: "No chat_id in message metadata".into(),
             })?;
 
-        self.send_message(chat_id, &response.content).await
+        let draft = self.drafts.lock().await.remove(chat_id);
+        match draft {
+            Some(draft) => self.finish_draft(chat_id, draft, &response.content).await,
+            None => self.send_message(chat_id, &response.content, None).await,
+        }
+    }
+
+    async fn broadcast(
+        &self,
+        user_id: &str,
+        response: OutgoingResponse,
+    ) -> Result<(), ChannelError> {
+        // Private chats share the user's ID; groups need the chat_id.
+        let chat_id = response
+            .metadata
+            .get("chat_id")
+            .and_then(|v| v.as_str())
+            .unwrap_or(user_id);
+        let reply_to = response
+            .metadata
+            .get("reply_to_message_id")
+            .and_then(serde_json::Value::as_i64);
+        self.send_message(chat_id, &response.content, reply_to).await
     }
 
     async fn send_status(
@@ -629,7 +905,16 @@ impl Channel for TelegramChannel {
                 }
                 StatusUpdate::Status(ref msg) if !msg.is_empty() => {
                     // Send important status messages as actual messages
-                    let _ = self.send_message(chat_id, &format!("ℹ️ {msg}")).await;
+                    let _ = self.send_message(chat_id, &format!("ℹ️ {msg}"), None).await;
+                }
+                StatusUpdate::StreamChunk(ref chunk) => {
+                    if let Err(e) = self.stream_chunk(chat_id, chunk).await {
+                        tracing::debug!(error = %e, "Telegram stream edit failed");
+                    }
+                }
+                StatusUpdate::ApprovalNeeded { .. } => {
+                    // The turn waits here without a respond()
+                    self.settle_draft(chat_id).await;
                 }
                 _ => {
                     // Other statuses are silent on Telegram
@@ -679,6 +964,39 @@ fn check_user_allowed<'a>(
         .any(|u| u == "*" || ids.contains(&u.as_str()))
 }
 
+/// `sendMessage` body, threaded under `reply_to` when given. A reply to a
+/// message that has since been deleted is still sent, just unthreaded.
+fn message_body(chat_id: &str, text: &str, reply_to: Option<i64>) -> serde_json::Value {
+    let mut body = serde_json::json!({
+        "chat_id": chat_id,
+        "text": text,
+    });
+    if let Some(message_id) = reply_to {
+        body["reply_parameters"] = serde_json::json!({
+            "message_id": message_id,
+            "allow_sending_without_reply": true,
+        });
+    }
+    body
+}
+
+/// Telegram rejects edits that don't change the text; that's not a failure.
+fn is_not_modified(err: &ChannelError) -> bool {
+    matches!(err, ChannelError::SendFailed { reason, .. } if reason.contains("message is not modified"))
+}
+
+/// The streamed text so far, cut to fit one message.
+fn draft_preview(text: &str, max_len: usize) -> String {
+    if text.len() <= max_len {
+        return text.to_string();
+    }
+    let mut end = max_len - STREAM_PLACEHOLDER.len();
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}{STREAM_PLACEHOLDER}", &text[..end])
+}
+
 /// Split a message into chunks that fit Telegram's character limit.
 /// Tries to split on newlines, then spaces, then hard-cuts.
 fn split_message(text: &str, max_len: usize) -> Vec<String> {
@@ -714,6 +1032,62 @@ fn split_message(text: &str, max_len: usize) -> Vec<String> {
 
 // ── Tests ───────────────────────────────────────────────────────────
 
+/// Local stand-in for the Bot API, recording the calls it receives.
+#[cfg(test)]
+pub(crate) mod stand_in {
+    use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
+    use std::sync::{Arc, Mutex};
+
+    use axum::Json;
+    use axum::Router;
+    use axum::extract::{Path, State};
+    use axum::http::StatusCode;
+    use axum::response::{IntoResponse, Response};
+    use axum::routing::post;
+
+    /// Calls seen as `(method, body)`; set `fail` to reject every call.
+    #[derive(Default)]
+    pub(crate) struct Server {
+        pub requests: Mutex<Vec<(String, serde_json::Value)>>,
+        pub fail: AtomicBool,
+        next_message_id: AtomicI64,
+    }
+
+    impl Server {
+        pub(crate) fn methods(&self) -> Vec<String> {
+            self.requests.lock().unwrap().iter().map(|(m, _)| m.clone()).collect()
+        }
+    }
+
+    async fn handle(
+        State(server): State<Arc<Server>>,
+        Path((_bot, method)): Path<(String, String)>,
+        Json(body): Json<serde_json::Value>,
+    ) -> Response {
+        server.requests.lock().unwrap().push((method, body));
+        if server.fail.load(Ordering::SeqCst) {
+            let error = serde_json::json!({"ok": false, "description": "Bad Gateway"});
+            return (StatusCode::BAD_GATEWAY, Json(error)).into_response();
+        }
+        let message_id = server.next_message_id.fetch_add(1, Ordering::SeqCst) + 100;
+        Json(serde_json::json!({"ok": true, "result": {"message_id": message_id}})).into_response()
+    }
+
+    /// Start the stand-in; returns its API base URL and state.
+    pub(crate) async fn start() -> (String, Arc<Server>) {
+        let server = Arc::new(Server::default());
+        let app = Router::new()
+            .route("/{bot}/{method}", post(handle))
+            .with_state(Arc::clone(&server));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        (format!("http://127.0.0.1:{port}"), server)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -735,6 +1109,90 @@ mod tests {
         );
     }
 
+    // ── Streaming tests ─────────────────────────────────────────────
+
+    #[test]
+    fn draft_preview_fits_one_message() {
+        assert_eq!(draft_preview("short", 4096), "short");
+
+        let long = "é".repeat(3000); // 6000 bytes
+        let preview = draft_preview(&long, 4096);
+        assert!(preview.len() <= 4096);
+        assert!(preview.ends_with(STREAM_PLACEHOLDER));
+        assert!(preview.starts_with("éé"));
+    }
+
+    #[tokio::test]
+    async fn failed_stream_request_drops_the_draft() {
+        let (base, server) = stand_in::start().await;
+        server.fail.store(true, std::sync::atomic::Ordering::SeqCst);
+        let ch = TelegramChannel::new("fake-token".into(), vec!["*".into()]).with_api_base(base);
+        assert!(ch.stream_chunk("42", "Hel").await.is_err());
+        assert!(ch.drafts.lock().await.is_empty());
+        assert_eq!(server.methods(), ["sendMessage"]);
+    }
+
+    #[tokio::test]
+    async fn approval_wait_settles_the_draft() {
+        let (base, server) = stand_in::start().await;
+        let ch = TelegramChannel::new("fake-token".into(), vec!["*".into()]).with_api_base(base);
+        ch.drafts.lock().await.insert(
+            "42".into(),
+            StreamDraft {
+                message_id: Some(7),
+                text: "Let me check".into(),
+                last_edit: Instant::now(),
+            },
+        );
+        let approval = StatusUpdate::ApprovalNeeded {
+            request_id: "r1".into(),
+            tool_name: "shell".into(),
+            description: "run ls".into(),
+            parameters: serde_json::json!({}),
+            summary: None,
+        };
+        ch.send_status(approval, &serde_json::json!({"chat_id": "42"}))
+            .await
+            .unwrap();
+        assert!(ch.drafts.lock().await.is_empty());
+
+        // The streamed text is kept in place while the turn waits.
+        let requests = server.requests.lock().unwrap();
+        let [(method, body)] = &requests[..] else {
+            panic!("expected one call, got {:?}", *requests);
+        };
+        assert_eq!(method, "editMessageText");
+        assert_eq!(body["message_id"], 7);
+        assert_eq!(body["text"], "Let me check");
+    }
+
+    #[test]
+    fn unchanged_edit_is_not_an_error() {
+        let err = ChannelError::SendFailed {
+            name: "telegram".into(),
+            reason: "editMessageText failed (400 Bad Request): Bad Request: message is not modified"
+                .into(),
+        };
+        assert!(is_not_modified(&err));
+        let err = ChannelError::SendFailed {
+            name: "telegram".into(),
+            reason: "editMessageText failed (400 Bad Request): message to edit not found".into(),
+        };
+        assert!(!is_not_modified(&err));
+    }
+
+    // ── Reply threading tests ───────────────────────────────────────
+
+    #[test]
+    fn message_body_threads_replies() {
+        let body = message_body("42", "hi", None);
+        assert_eq!(body, serde_json::json!({"chat_id": "42", "text": "hi"}));
+
+        let body = message_body("42", "hi", Some(7));
+        assert_eq!(body["reply_parameters"]["message_id"], 7);
+        assert_eq!(body["reply_parameters"]["allow_sending_without_reply"], true);
+    }
+
     // ── User allowlist tests ────────────────────────────────────────
 
     #[test]
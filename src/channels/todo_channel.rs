@@ -13,6 +13,7 @@ use std::sync::Arc;
 use async_trait::async_trait;
 use tokio::sync::{Mutex, OwnedSemaphorePermit, Semaphore, broadcast, mpsc};
 use uuid::Uuid;
+use crate::agent::approval_policy::similar_rule;
 use crate::cards::model::{ApprovalCard, CardPayload, CardSilo};
 use crate::cards::queue::CardQueue;
 use crate::channels::channel::{
@@ -44,6 +45,8 @@ pub struct TodoChannel {
     semaphore: Arc<Semaphore>,
     /// Set to true when respond() is called successfully.
     responded: AtomicBool,
+    /// Set when a hard budget stopped the agent; the todo waits to be requeued.
+    paused: AtomicBool,
     /// Structured per-run logger.
     logger: AgentLogger,
     /// Buffered ToolCompleted message waiting to be merged with a ToolResult.
@@ -105,6 +108,7 @@ impl TodoChannel {
             permit: permit_slot,
             semaphore,
             responded: AtomicBool::new(false),
+            paused: AtomicBool::new(false),
             logger,
             pending_tool_completed: Mutex::new(None),
             msg_tx: Mutex::new(Some(tx)),
@@ -188,7 +192,8 @@ impl Channel for TodoChannel {
         // Record the task prompt in logger
         self.logger.user_message(&content).await;
 
-        let msg = IncomingMessage::new("todo", "todo-agent", content);
+        let msg = IncomingMessage::new("todo", "todo-agent", content)
+            .with_metadata(serde_json::json!({ "todo_id": self.todo_id }));
 
         // Take the receiver (only called once)
         let rx = self
@@ -216,6 +221,11 @@ impl Channel for TodoChannel {
         _msg: &IncomingMessage,
         response: OutgoingResponse,
     ) -> Result<(), ChannelError> {
+        // The agent's error reply for a budget pause isn't a result
+        if self.paused.load(Ordering::SeqCst) {
+            self.close_stream().await;
+            return Ok(());
+        }
         self.responded.store(true, Ordering::SeqCst);
 
         // Record final response in logger
@@ -334,13 +344,16 @@ impl Channel for TodoChannel {
 
                 let action_detail = summary
                     .as_ref()
-                    .map(|s| s.raw_params.clone())
+                    .map(|s| s.action_detail())
                     .or_else(|| serde_json::to_string_pretty(parameters).ok());
 
+                let always_allow = Some(similar_rule(tool_name, parameters).name);
+
                 let card = ApprovalCard::new(
                     CardPayload::Action {
                         description: headline.clone(),
                         action_detail,
+                        always_allow,
                     },
                     CardSilo::Todos,
                     60,
@@ -406,6 +419,38 @@ impl Channel for TodoChannel {
                     description: headline,
                 });
             }
+            StatusUpdate::SafetyEvent {
+                tool_name,
+                action,
+                reasons,
+            } => {
+                self.flush_pending_tool().await;
+                self.logger
+                    .system(&format!("🛡️ Safety {:?}: {}", action, reasons.join("; ")))
+                    .await;
+                self.emit(TodoActivityMessage::SafetyEvent {
+                    job_id: self.job_id,
+                    tool_name,
+                    action,
+                    reasons,
+                });
+            }
+            StatusUpdate::BudgetPaused { reason } => {
+                self.flush_pending_tool().await;
+                self.paused.store(true, Ordering::SeqCst);
+                if let Err(e) = self
+                    .db
+                    .update_todo_status(self.todo_id, TodoStatus::BudgetPaused)
+                    .await
+                {
+                    tracing::warn!(error = %e, "Failed to update todo status to budget_paused");
+                }
+                self.broadcast_todo_update().await;
+                self.emit(TodoActivityMessage::Paused {
+                    job_id: self.job_id,
+                    reason,
+                });
+            }
             // StreamChunk and other variants — ignore for now
             _ => return Ok(()),
         };
@@ -421,8 +466,8 @@ impl Channel for TodoChannel {
         // Flush any buffered ToolCompleted before emitting terminal events
         self.flush_pending_tool().await;
 
-        // If respond() was never called, the agent errored out
-        if !self.responded.load(Ordering::SeqCst) {
+        // If respond() was never called (and no budget paused it), the agent errored out
+        if !self.responded.load(Ordering::SeqCst) && !self.paused.load(Ordering::SeqCst) {
             self.emit(TodoActivityMessage::Failed {
                 job_id: self.job_id,
                 error: "Agent exited without producing a response".to_string(),
@@ -540,4 +585,46 @@ mod tests {
     fn condense_summary_empty_input() {
         assert_eq!(condense_summary(""), "");
     }
+    #[tokio::test]
+    async fn budget_pause_parks_the_todo_instead_of_failing_it() {
+        use crate::store::LibSqlBackend;
+        use crate::todos::model::{TodoBucket, TodoItem, TodoType};
+
+        let db: Arc<dyn Database> = Arc::new(LibSqlBackend::new_memory().await.unwrap());
+        let todo = TodoItem::new("default", "Compare flights", TodoType::Research, TodoBucket::AgentStartable);
+        db.create_todo(&todo).await.unwrap();
+        db.update_todo_status(todo.id, TodoStatus::AgentWorking).await.unwrap();
+        let semaphore = Arc::new(Semaphore::new(1));
+        let permit = Arc::clone(&semaphore).acquire_owned().await.unwrap();
+        let channel = TodoChannel::new(
+            todo.id,
+            Uuid::new_v4(),
+            todo.title.clone(),
+            String::new(),
+            broadcast::channel(16).0,
+            Arc::clone(&db),
+            broadcast::channel(16).0,
+            CardQueue::new(),
+            TodoApprovalRegistry::new(),
+            permit,
+            Arc::clone(&semaphore),
+        );
+
+        let reason = "daily global budget of $1.00 reached".to_string();
+        channel
+            .send_status(StatusUpdate::BudgetPaused { reason: reason.clone() }, &serde_json::Value::Null)
+            .await
+            .unwrap();
+        // The agent loop still replies with the error; it must not complete the todo
+        let message = IncomingMessage::new("todo", "default", "Compare flights");
+        channel
+            .respond(&message, OutgoingResponse::text(format!("Error: {reason}")))
+            .await
+            .unwrap();
+        channel.shutdown().await.unwrap();
+
+        let stored = db.get_todo(todo.id).await.unwrap().unwrap();
+        assert_eq!(stored.status, TodoStatus::BudgetPaused);
+        assert_eq!(semaphore.available_permits(), 1);
+    }
 }
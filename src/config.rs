@@ -1,7 +1,10 @@
 //! Configuration types.
 
+use std::path::PathBuf;
 use std::time::Duration;
 
+use chrono::{NaiveDateTime, NaiveTime};
+
 /// Default system prompt when none is configured.
 pub const DEFAULT_SYSTEM_PROMPT: &str = "You are AI Assist, a helpful and conversational AI assistant. \
      Respond naturally, concisely, and directly. \
@@ -144,6 +147,406 @@ impl RoutineConfig {
     }
 }
 
+/// Configuration for the safety layer.
+#[derive(Debug, Clone)]
+pub struct SafetyConfig {
+    /// Maximum user input length in bytes.
+    pub max_input_length: usize,
+    /// Tool output longer than this (bytes) is truncated.
+    pub max_output_length: usize,
+    /// Whether to scan tool output for prompt-injection patterns.
+    pub injection_check_enabled: bool,
+    /// Optional JSON file with extra policy rules.
+    pub policy_file: Option<PathBuf>,
+    /// Optional JSON file with extra shell command rules.
+    pub shell_policy_file: Option<PathBuf>,
+}
+
+impl Default for SafetyConfig {
+    fn default() -> Self {
+        Self {
+            max_input_length: 100_000,
+            max_output_length: 100_000,
+            injection_check_enabled: true,
+            policy_file: None,
+            shell_policy_file: None,
+        }
+    }
+}
+
+impl SafetyConfig {
+    /// Build SafetyConfig from environment variables.
+    ///
+    /// | Env Var | Field | Default |
+    /// |---------|-------|---------|
+    /// | `SAFETY_MAX_INPUT_LENGTH` | max_input_length | 100000 |
+    /// | `SAFETY_MAX_OUTPUT_LENGTH` | max_output_length | 100000 |
+    /// | `SAFETY_INJECTION_CHECK` | injection_check_enabled | true |
+    /// | `SAFETY_POLICY_FILE` | policy_file | none |
+    /// | `SHELL_POLICY_FILE` | shell_policy_file | none |
+    pub fn from_env() -> Self {
+        Self {
+            max_input_length: std::env::var("SAFETY_MAX_INPUT_LENGTH")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(100_000),
+            max_output_length: std::env::var("SAFETY_MAX_OUTPUT_LENGTH")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(100_000),
+            injection_check_enabled: std::env::var("SAFETY_INJECTION_CHECK")
+                .map(|v| v != "false" && v != "0")
+                .unwrap_or(true),
+            policy_file: std::env::var("SAFETY_POLICY_FILE").ok().map(PathBuf::from),
+            shell_policy_file: std::env::var("SHELL_POLICY_FILE").ok().map(PathBuf::from),
+        }
+    }
+}
+
+/// Configuration for the scheduled message digest.
+#[derive(Debug, Clone)]
+pub struct DigestConfig {
+    /// Whether digest cards are rolled up on a schedule.
+    pub enabled: bool,
+    /// Local times of day at which pending digest items are rolled up.
+    pub times: Vec<NaiveTime>,
+}
+
+impl Default for DigestConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            times: default_digest_times(),
+        }
+    }
+}
+
+impl DigestConfig {
+    /// Build DigestConfig from environment variables.
+    ///
+    /// | Env Var | Field | Default |
+    /// |---------|-------|---------|
+    /// | `DIGEST_ENABLED` | enabled | true |
+    /// | `DIGEST_TIMES` | times (comma-separated `HH:MM`, local time) | 08:00,17:00 |
+    pub fn from_env() -> Self {
+        let times = match std::env::var("DIGEST_TIMES") {
+            Ok(raw) => parse_digest_times(&raw).unwrap_or_else(|| {
+                tracing::warn!(value = %raw, "Invalid DIGEST_TIMES, using 08:00,17:00");
+                default_digest_times()
+            }),
+            Err(_) => default_digest_times(),
+        };
+        Self {
+            enabled: std::env::var("DIGEST_ENABLED")
+                .map(|v| v != "false" && v != "0")
+                .unwrap_or(true),
+            times,
+        }
+    }
+
+    /// Next scheduled roll-up strictly after `now` (local wall-clock time).
+    ///
+    /// Returns `None` when no times are configured.
+    pub fn next_fire_after(&self, now: NaiveDateTime) -> Option<NaiveDateTime> {
+        let today = now.date();
+        let later_today = self
+            .times
+            .iter()
+            .map(|t| today.and_time(*t))
+            .filter(|at| *at > now)
+            .min();
+        later_today.or_else(|| {
+            let tomorrow = today.succ_opt()?;
+            self.times.iter().min().map(|t| tomorrow.and_time(*t))
+        })
+    }
+}
+
+fn default_digest_times() -> Vec<NaiveTime> {
+    vec![
+        NaiveTime::from_hms_opt(8, 0, 0).expect("valid time"),
+        NaiveTime::from_hms_opt(17, 0, 0).expect("valid time"),
+    ]
+}
+
+/// Parse a comma-separated list of `HH:MM` times. Returns `None` if any entry
+/// is malformed or the list is empty.
+pub fn parse_digest_times(raw: &str) -> Option<Vec<NaiveTime>> {
+    let mut times = raw
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|s| NaiveTime::parse_from_str(s, "%H:%M").ok())
+        .collect::<Option<Vec<_>>>()?;
+    if times.is_empty() {
+        return None;
+    }
+    times.sort();
+    times.dedup();
+    Some(times)
+}
+
+/// Configuration for learning ignore rules from card history.
+#[derive(Debug, Clone)]
+pub struct FeedbackConfig {
+    /// Whether rule suggestions are generated.
+    pub enabled: bool,
+    /// Dismissals (with zero approvals) before an ignore rule is proposed.
+    pub min_dismissals: u32,
+    /// How often card history is scanned, in seconds.
+    pub interval_secs: u64,
+}
+
+impl Default for FeedbackConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            min_dismissals: 5,
+            interval_secs: 900,
+        }
+    }
+}
+
+impl FeedbackConfig {
+    /// Build FeedbackConfig from environment variables.
+    ///
+    /// | Env Var | Field | Default |
+    /// |---------|-------|---------|
+    /// | `TRIAGE_FEEDBACK_ENABLED` | enabled | true |
+    /// | `TRIAGE_FEEDBACK_MIN_DISMISSALS` | min_dismissals | 5 |
+    /// | `TRIAGE_FEEDBACK_INTERVAL_SECS` | interval_secs | 900 |
+    pub fn from_env() -> Self {
+        Self {
+            enabled: std::env::var("TRIAGE_FEEDBACK_ENABLED")
+                .map(|v| v != "false" && v != "0")
+                .unwrap_or(true),
+            min_dismissals: std::env::var("TRIAGE_FEEDBACK_MIN_DISMISSALS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|v| *v > 0)
+                .unwrap_or(5),
+            interval_secs: std::env::var("TRIAGE_FEEDBACK_INTERVAL_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(900),
+        }
+    }
+}
+
+/// Configuration for the outbox that delivers approved messages.
+#[derive(Debug, Clone)]
+pub struct OutboxConfig {
+    /// Seconds an approved message is held (and can be undone) before it
+    /// is sent. `0` sends immediately.
+    pub undo_window_secs: u64,
+}
+
+impl Default for OutboxConfig {
+    fn default() -> Self {
+        Self {
+            undo_window_secs: 10,
+        }
+    }
+}
+
+impl OutboxConfig {
+    /// Build OutboxConfig from environment variables.
+    ///
+    /// | Env Var | Field | Default |
+    /// |---------|-------|---------|
+    /// | `SEND_UNDO_SECS` | undo_window_secs | 10 |
+    pub fn from_env() -> Self {
+        Self {
+            undo_window_secs: std::env::var("SEND_UNDO_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(10),
+        }
+    }
+}
+
+/// Configuration for the CalDAV calendar sync.
+#[derive(Debug, Clone)]
+pub struct CalendarConfig {
+    /// Calendar collection URL. Sync is off when unset.
+    pub caldav_url: Option<String>,
+    pub username: String,
+    pub password: String,
+    /// The calendar owner's address — used to skip events they declined
+    /// and as organizer of events created by the agent.
+    pub owner_email: Option<String>,
+    /// Seconds between syncs.
+    pub sync_interval_secs: u64,
+    /// How many days ahead to mirror.
+    pub sync_days: i64,
+}
+
+impl Default for CalendarConfig {
+    fn default() -> Self {
+        Self {
+            caldav_url: None,
+            username: String::new(),
+            password: String::new(),
+            owner_email: None,
+            sync_interval_secs: 900,
+            sync_days: 30,
+        }
+    }
+}
+
+impl CalendarConfig {
+    /// Build CalendarConfig from environment variables.
+    ///
+    /// | Env Var | Field | Default |
+    /// |---------|-------|---------|
+    /// | `CALDAV_URL` | caldav_url | unset (sync off) |
+    /// | `CALDAV_USERNAME` | username | empty |
+    /// | `CALDAV_PASSWORD` | password | empty |
+    /// | `CALENDAR_OWNER_EMAIL` | owner_email | `CALDAV_USERNAME` if it is an address |
+    /// | `CALDAV_SYNC_INTERVAL_SECS` | sync_interval_secs | 900 |
+    /// | `CALDAV_SYNC_DAYS` | sync_days | 30 |
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let username = std::env::var("CALDAV_USERNAME").unwrap_or_default();
+        let owner_email = std::env::var("CALENDAR_OWNER_EMAIL")
+            .ok()
+            .filter(|v| !v.trim().is_empty())
+            .or_else(|| username.contains('@').then(|| username.clone()));
+        Self {
+            caldav_url: std::env::var("CALDAV_URL").ok().filter(|v| !v.trim().is_empty()),
+            username,
+            password: std::env::var("CALDAV_PASSWORD").unwrap_or_default(),
+            owner_email,
+            sync_interval_secs: std::env::var("CALDAV_SYNC_INTERVAL_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.sync_interval_secs),
+            sync_days: std::env::var("CALDAV_SYNC_DAYS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.sync_days),
+        }
+    }
+}
+
+/// How `ToolDomain::Container` tools are isolated from the host.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SandboxMode {
+    /// Run commands directly on the host.
+    Off,
+    /// Sandbox when a backend is available, otherwise warn and run on the host.
+    Auto,
+    /// Refuse to start without a working sandbox backend.
+    Required,
+}
+
+impl std::str::FromStr for SandboxMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "off" | "false" | "0" => Ok(Self::Off),
+            "auto" => Ok(Self::Auto),
+            "required" | "on" | "true" | "1" => Ok(Self::Required),
+            other => Err(format!("unknown sandbox mode '{other}'")),
+        }
+    }
+}
+
+/// Configuration for the Container-domain tool sandbox.
+#[derive(Debug, Clone)]
+pub struct SandboxConfig {
+    pub mode: SandboxMode,
+    /// Parent of the per-todo scratch directories (the only writable host path).
+    pub scratch_root: PathBuf,
+    /// Host paths mounted read-only inside the sandbox.
+    pub read_only_paths: Vec<PathBuf>,
+    /// Whether sandboxed commands get network access.
+    pub network: bool,
+    /// CPU-time limit per command, in seconds.
+    pub cpu_secs: u64,
+    /// Address-space limit per command, in megabytes.
+    pub memory_mb: u64,
+}
+
+impl Default for SandboxConfig {
+    fn default() -> Self {
+        Self {
+            mode: SandboxMode::Auto,
+            scratch_root: default_scratch_root(),
+            read_only_paths: default_read_only_paths(),
+            network: false,
+            cpu_secs: 60,
+            memory_mb: 1024,
+        }
+    }
+}
+
+impl SandboxConfig {
+    /// Build SandboxConfig from environment variables.
+    ///
+    /// | Env Var | Field | Default |
+    /// |---------|-------|---------|
+    /// | `SANDBOX_MODE` | mode (`off`, `auto`, `required`) | auto |
+    /// | `SANDBOX_SCRATCH_DIR` | scratch_root | ~/.ai-assist/sandbox |
+    /// | `SANDBOX_RO_PATHS` | read_only_paths (comma-separated) | /usr,/bin,/sbin,/lib,/lib64,/etc |
+    /// | `SANDBOX_NETWORK` | network | false |
+    /// | `SANDBOX_CPU_SECS` | cpu_secs | 60 |
+    /// | `SANDBOX_MEMORY_MB` | memory_mb | 1024 |
+    pub fn from_env() -> Self {
+        let mode = match std::env::var("SANDBOX_MODE") {
+            Ok(raw) => raw.parse().unwrap_or_else(|e| {
+                tracing::warn!(value = %raw, "Invalid SANDBOX_MODE ({e}), using auto");
+                SandboxMode::Auto
+            }),
+            Err(_) => SandboxMode::Auto,
+        };
+        let read_only_paths = std::env::var("SANDBOX_RO_PATHS")
+            .ok()
+            .map(|raw| {
+                raw.split(',')
+                    .map(str::trim)
+                    .filter(|s| !s.is_empty())
+                    .map(PathBuf::from)
+                    .collect::<Vec<_>>()
+            })
+            .filter(|paths| !paths.is_empty())
+            .unwrap_or_else(default_read_only_paths);
+        Self {
+            mode,
+            scratch_root: std::env::var("SANDBOX_SCRATCH_DIR")
+                .map(PathBuf::from)
+                .unwrap_or_else(|_| default_scratch_root()),
+            read_only_paths,
+            network: std::env::var("SANDBOX_NETWORK")
+                .map(|v| v == "true" || v == "1")
+                .unwrap_or(false),
+            cpu_secs: std::env::var("SANDBOX_CPU_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|v| *v > 0)
+                .unwrap_or(60),
+            memory_mb: std::env::var("SANDBOX_MEMORY_MB")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|v| *v > 0)
+                .unwrap_or(1024),
+        }
+    }
+}
+
+fn default_scratch_root() -> PathBuf {
+    let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
+    PathBuf::from(home).join(".ai-assist/sandbox")
+}
+
+fn default_read_only_paths() -> Vec<PathBuf> {
+    ["/usr", "/bin", "/sbin", "/lib", "/lib64", "/etc"]
+        .into_iter()
+        .map(PathBuf::from)
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -179,4 +582,48 @@ mod tests {
         assert!(DEFAULT_SYSTEM_PROMPT.contains("AI Assist"));
         assert!(DEFAULT_SYSTEM_PROMPT.contains("conversational"));
     }
+
+    #[test]
+    fn parse_digest_times_sorts_and_validates() {
+        let times = parse_digest_times("17:00, 08:30,17:00").unwrap();
+        assert_eq!(
+            times,
+            vec![
+                NaiveTime::from_hms_opt(8, 30, 0).unwrap(),
+                NaiveTime::from_hms_opt(17, 0, 0).unwrap(),
+            ]
+        );
+        assert!(parse_digest_times("8am").is_none());
+        assert!(parse_digest_times(" , ").is_none());
+    }
+
+    #[test]
+    fn digest_next_fire_rolls_over_to_tomorrow() {
+        let config = DigestConfig::default();
+        let day = chrono::NaiveDate::from_ymd_opt(2026, 3, 2).unwrap();
+
+        let morning = day.and_hms_opt(7, 0, 0).unwrap();
+        assert_eq!(config.next_fire_after(morning), day.and_hms_opt(8, 0, 0));
+
+        let exactly_eight = day.and_hms_opt(8, 0, 0).unwrap();
+        assert_eq!(config.next_fire_after(exactly_eight), day.and_hms_opt(17, 0, 0));
+
+        let evening = day.and_hms_opt(18, 0, 0).unwrap();
+        assert_eq!(
+            config.next_fire_after(evening),
+            day.succ_opt().unwrap().and_hms_opt(8, 0, 0)
+        );
+
+        let empty = DigestConfig { enabled: true, times: Vec::new() };
+        assert!(empty.next_fire_after(evening).is_none());
+    }
+
+    #[test]
+    fn sandbox_mode_parses_aliases() {
+        assert_eq!("off".parse::<SandboxMode>(), Ok(SandboxMode::Off));
+        assert_eq!(" Auto ".parse::<SandboxMode>(), Ok(SandboxMode::Auto));
+        assert_eq!("required".parse::<SandboxMode>(), Ok(SandboxMode::Required));
+        assert_eq!("true".parse::<SandboxMode>(), Ok(SandboxMode::Required));
+        assert!("strict".parse::<SandboxMode>().is_err());
+    }
 }
@@ -84,4 +84,9 @@ impl JobContext {
             ..Default::default()
         }
     }
+
+    /// Todo this job works on, if any (`metadata["todo_id"]`).
+    pub fn todo_id(&self) -> Option<Uuid> {
+        self.metadata.get("todo_id")?.as_str()?.parse().ok()
+    }
 }
@@ -36,6 +36,9 @@ pub enum Error {
 
     #[error("Pipeline error: {0}")]
     Pipeline(#[from] PipelineError),
+
+    #[error("Calendar error: {0}")]
+    Calendar(#[from] CalendarError),
 }
 
 /// Configuration-related errors.
@@ -137,6 +140,15 @@ pub enum LlmError {
     #[error("Session renewal failed for provider {provider}: {reason}")]
     SessionRenewalFailed { provider: String, reason: String },
 
+    #[error("Budget exceeded: {reason}")]
+    BudgetExceeded { reason: String },
+
+    #[error("Circuit open for {provider}, retry after {retry_after:?}")]
+    CircuitOpen {
+        provider: String,
+        retry_after: Duration,
+    },
+
     #[error("JSON error: {0}")]
     Json(#[from] serde_json::Error),
 
@@ -289,5 +301,18 @@ pub enum PipelineError {
     Llm(#[from] LlmError),
 }
 
+/// Calendar (iCalendar parsing / CalDAV) errors.
+#[derive(Debug, thiserror::Error)]
+pub enum CalendarError {
+    #[error("Invalid iCalendar data: {0}")]
+    Parse(String),
+
+    #[error("CalDAV request failed: {0}")]
+    Request(String),
+
+    #[error("CalDAV server returned {status}: {reason}")]
+    Server { status: u16, reason: String },
+}
+
 /// Result type alias for the agent.
 pub type Result<T> = std::result::Result<T, Error>;
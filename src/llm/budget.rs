@@ -0,0 +1,669 @@
+//! LLM spend budgets, enforced against the `llm_calls` cost ledger.
+//!
+//! A budget caps spend per UTC day or month, either globally or for one
+//! routine or todo. Every routed LLM call is checked first:
+//! - over a **soft** limit, the call is downgraded to the cheap (triage) model
+//! - over a **hard** limit, background work — routines and todo agents — is
+//!   paused and a `Decision` card asks whether to raise the limit; interactive
+//!   chat is only downgraded
+//!
+//! Approving the card raises the hard limit by half; dismissing it keeps the
+//! scope paused until the period rolls over. Todos parked by a pause are
+//! requeued once [`BudgetGuard::changes`] fires or the period rolls over.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, TimeZone, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{Mutex, RwLock, watch};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::cards::model::{ApprovalCard, CardSilo};
+use crate::cards::queue::CardQueue;
+use crate::error::DatabaseError;
+use crate::llm::router::{META_ROUTINE_ID, META_TODO_ID};
+use crate::llm::LlmRole;
+use crate::store::Database;
+
+/// Raise-limit cards stay up for a day.
+const RAISE_CARD_EXPIRE_MINUTES: u32 = 24 * 60;
+
+/// What a budget caps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BudgetScope {
+    /// All LLM calls.
+    Global,
+    /// Calls made by one routine's runs.
+    Routine,
+    /// Calls made by the agent working one todo.
+    Todo,
+}
+
+impl BudgetScope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Global => "global",
+            Self::Routine => "routine",
+            Self::Todo => "todo",
+        }
+    }
+}
+
+impl std::str::FromStr for BudgetScope {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "global" => Ok(Self::Global),
+            "routine" => Ok(Self::Routine),
+            "todo" => Ok(Self::Todo),
+            _ => Err(format!("Unknown budget scope: {s}")),
+        }
+    }
+}
+
+/// How often a budget resets (UTC).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BudgetPeriod {
+    Daily,
+    Monthly,
+}
+
+impl BudgetPeriod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Daily => "daily",
+            Self::Monthly => "monthly",
+        }
+    }
+
+    /// The `[start, end)` window containing `now`.
+    pub fn window(&self, now: DateTime<Utc>) -> (DateTime<Utc>, DateTime<Utc>) {
+        let today = now.date_naive();
+        let (start, end) = match self {
+            Self::Daily => (today, today + Duration::days(1)),
+            Self::Monthly => {
+                let first = NaiveDate::from_ymd_opt(today.year(), today.month(), 1)
+                    .expect("first of month is valid");
+                let next = if today.month() == 12 {
+                    NaiveDate::from_ymd_opt(today.year() + 1, 1, 1)
+                } else {
+                    NaiveDate::from_ymd_opt(today.year(), today.month() + 1, 1)
+                }
+                .expect("first of month is valid");
+                (first, next)
+            }
+        };
+        let midnight = |d: NaiveDate| Utc.from_utc_datetime(&d.and_hms_opt(0, 0, 0).expect("midnight"));
+        (midnight(start), midnight(end))
+    }
+}
+
+impl std::str::FromStr for BudgetPeriod {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "daily" => Ok(Self::Daily),
+            "monthly" => Ok(Self::Monthly),
+            _ => Err(format!("Unknown budget period: {s}")),
+        }
+    }
+}
+
+/// A spend cap, persisted in `budgets`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Budget {
+    pub id: Uuid,
+    pub scope: BudgetScope,
+    /// Routine or todo ID; `None` for global budgets.
+    pub scope_id: Option<Uuid>,
+    pub period: BudgetPeriod,
+    /// Above this, calls use the cheaper model.
+    pub soft_limit: Option<Decimal>,
+    /// At or above this, background work pauses.
+    pub hard_limit: Option<Decimal>,
+    pub enabled: bool,
+    /// Decision card asking to raise the hard limit, if one was raised.
+    pub raise_card_id: Option<Uuid>,
+    /// When that card was raised; a card from an earlier period is stale.
+    pub raise_requested_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Budget {
+    pub fn new(
+        scope: BudgetScope,
+        scope_id: Option<Uuid>,
+        period: BudgetPeriod,
+        soft_limit: Option<Decimal>,
+        hard_limit: Option<Decimal>,
+    ) -> Self {
+        let now = Utc::now();
+        Self {
+            id: Uuid::new_v4(),
+            scope,
+            scope_id,
+            period,
+            soft_limit,
+            hard_limit,
+            enabled: true,
+            raise_card_id: None,
+            raise_requested_at: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    /// Check the budget is well-formed.
+    pub fn validate(&self) -> Result<(), String> {
+        match (self.scope, self.scope_id) {
+            (BudgetScope::Global, Some(_)) => return Err("global budgets take no scope_id".into()),
+            (BudgetScope::Routine | BudgetScope::Todo, None) => {
+                return Err(format!("{} budgets need a scope_id", self.scope.as_str()));
+            }
+            _ => {}
+        }
+        if self.soft_limit.is_none() && self.hard_limit.is_none() {
+            return Err("set a soft_limit, a hard_limit, or both".into());
+        }
+        if [self.soft_limit, self.hard_limit]
+            .into_iter()
+            .flatten()
+            .any(|limit| limit <= Decimal::ZERO)
+        {
+            return Err("limits must be positive".into());
+        }
+        if let (Some(soft), Some(hard)) = (self.soft_limit, self.hard_limit)
+            && soft > hard
+        {
+            return Err("soft_limit must not exceed hard_limit".into());
+        }
+        Ok(())
+    }
+
+    /// Does this budget cover a call?
+    pub fn applies_to(&self, call: &CallScope) -> bool {
+        self.enabled
+            && match self.scope {
+                BudgetScope::Global => true,
+                BudgetScope::Routine => self.scope_id.is_some() && self.scope_id == call.routine_id,
+                BudgetScope::Todo => self.scope_id.is_some() && self.scope_id == call.todo_id,
+            }
+    }
+
+    /// Where `spent` falls against the limits.
+    pub fn state(&self, spent: Decimal) -> BudgetState {
+        if self.hard_limit.is_some_and(|hard| spent >= hard) {
+            BudgetState::HardExceeded
+        } else if self.soft_limit.is_some_and(|soft| spent >= soft) {
+            BudgetState::SoftExceeded
+        } else {
+            BudgetState::Ok
+        }
+    }
+
+    /// Whether a raise card was already requested in the current period.
+    fn raise_pending(&self, now: DateTime<Utc>) -> bool {
+        let (start, _) = self.period.window(now);
+        self.raise_card_id.is_some() && self.raise_requested_at.is_some_and(|at| at >= start)
+    }
+}
+
+/// Where spend stands against a budget.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BudgetState {
+    Ok,
+    SoftExceeded,
+    HardExceeded,
+}
+
+/// A budget with its spend in the current period (for the REST report).
+#[derive(Debug, Clone, Serialize)]
+pub struct BudgetStatus {
+    #[serde(flatten)]
+    pub budget: Budget,
+    pub spent: Decimal,
+    pub window_start: DateTime<Utc>,
+    pub window_end: DateTime<Utc>,
+    pub state: BudgetState,
+}
+
+/// Who is making an LLM call, from the request metadata.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CallScope {
+    pub routine_id: Option<Uuid>,
+    pub todo_id: Option<Uuid>,
+    /// Routines and todo agents; hard limits pause these.
+    pub background: bool,
+}
+
+impl CallScope {
+    pub fn from_metadata(metadata: &HashMap<String, String>, role: LlmRole) -> Self {
+        let uuid = |key: &str| metadata.get(key).and_then(|v| Uuid::parse_str(v).ok());
+        let routine_id = uuid(META_ROUTINE_ID);
+        let todo_id = uuid(META_TODO_ID);
+        Self {
+            routine_id,
+            todo_id,
+            background: routine_id.is_some() || todo_id.is_some() || role == LlmRole::Routine,
+        }
+    }
+}
+
+/// Outcome of a budget check.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BudgetCheck {
+    Within,
+    /// A soft limit (or, for interactive calls, a hard limit) is exceeded.
+    Downgrade,
+    /// A hard limit is reached; background work must not proceed.
+    Paused(String),
+}
+
+/// Live budgets plus the ledger they're checked against.
+pub struct BudgetGuard {
+    db: Arc<dyn Database>,
+    card_queue: Option<Arc<CardQueue>>,
+    budgets: RwLock<Vec<Budget>>,
+    /// Serializes raise-card creation so concurrent calls ask only once.
+    raise_lock: Mutex<()>,
+    /// Bumped whenever the budgets are reloaded.
+    changed: watch::Sender<()>,
+}
+
+impl BudgetGuard {
+    /// Load budgets from the database.
+    pub async fn load(
+        db: Arc<dyn Database>,
+        card_queue: Option<Arc<CardQueue>>,
+    ) -> Result<Self, DatabaseError> {
+        let budgets = db.list_budgets().await?;
+        Ok(Self {
+            db,
+            card_queue,
+            budgets: RwLock::new(budgets),
+            raise_lock: Mutex::new(()),
+            changed: watch::channel(()).0,
+        })
+    }
+
+    /// Re-read budgets after a change through the API.
+    pub async fn reload(&self) -> Result<(), DatabaseError> {
+        let budgets = self.db.list_budgets().await?;
+        *self.budgets.write().await = budgets;
+        self.changed.send_replace(());
+        Ok(())
+    }
+
+    /// Notified after every reload — a raised or removed limit may have
+    /// unpaused work.
+    pub fn changes(&self) -> watch::Receiver<()> {
+        self.changed.subscribe()
+    }
+
+    /// Spend in the budget's current period.
+    async fn spent(&self, budget: &Budget, now: DateTime<Utc>) -> Result<Decimal, DatabaseError> {
+        let (start, end) = budget.period.window(now);
+        self.db
+            .get_scope_spend(budget.scope, budget.scope_id, start, end)
+            .await
+    }
+
+    /// Every budget with its current spend.
+    pub async fn status(&self) -> Result<Vec<BudgetStatus>, DatabaseError> {
+        let now = Utc::now();
+        let budgets = self.budgets.read().await.clone();
+        let mut statuses = Vec::with_capacity(budgets.len());
+        for budget in budgets {
+            let spent = self.spent(&budget, now).await?;
+            let (window_start, window_end) = budget.period.window(now);
+            statuses.push(BudgetStatus {
+                state: budget.state(spent),
+                budget,
+                spent,
+                window_start,
+                window_end,
+            });
+        }
+        Ok(statuses)
+    }
+
+    /// Check the budgets covering a call before it's made.
+    ///
+    /// Ledger errors fail open: a broken spend query shouldn't stop the agent.
+    pub async fn check(&self, call: &CallScope) -> BudgetCheck {
+        let now = Utc::now();
+        let budgets: Vec<Budget> = self
+            .budgets
+            .read()
+            .await
+            .iter()
+            .filter(|b| b.applies_to(call))
+            .cloned()
+            .collect();
+
+        let mut verdict = BudgetCheck::Within;
+        for budget in budgets {
+            let spent = match self.spent(&budget, now).await {
+                Ok(spent) => spent,
+                Err(e) => {
+                    warn!(budget_id = %budget.id, error = %e, "Failed to read budget spend");
+                    continue;
+                }
+            };
+            match budget.state(spent) {
+                BudgetState::HardExceeded if call.background => {
+                    self.request_raise(&budget, spent).await;
+                    return BudgetCheck::Paused(format!(
+                        "{} {} budget of {} reached ({} spent); background work is paused",
+                        budget.period.as_str(),
+                        budget.scope.as_str(),
+                        usd(budget.hard_limit.unwrap_or_default()),
+                        usd(spent),
+                    ));
+                }
+                BudgetState::HardExceeded | BudgetState::SoftExceeded => {
+                    verdict = BudgetCheck::Downgrade;
+                }
+                BudgetState::Ok => {}
+            }
+        }
+        verdict
+    }
+
+    /// Raise a Decision card asking to lift the hard limit, once per period.
+    async fn request_raise(&self, budget: &Budget, spent: Decimal) {
+        let _guard = self.raise_lock.lock().await;
+        let now = Utc::now();
+        // Another call may have raised the card while we waited.
+        let current = self
+            .budgets
+            .read()
+            .await
+            .iter()
+            .find(|b| b.id == budget.id)
+            .cloned();
+        let Some(mut budget) = current else {
+            return;
+        };
+        if budget.raise_pending(now) {
+            return;
+        }
+        let Some(queue) = &self.card_queue else {
+            return;
+        };
+
+        let card = self.raise_card(&budget, spent).await;
+        budget.raise_card_id = Some(card.id);
+        budget.raise_requested_at = Some(now);
+        budget.updated_at = now;
+        if let Err(e) = self.db.update_budget(&budget).await {
+            warn!(budget_id = %budget.id, error = %e, "Failed to record budget raise card");
+            return;
+        }
+        queue.push(card).await;
+        info!(budget_id = %budget.id, spent = %spent, "Budget hard limit reached, asked to raise");
+        if let Err(e) = self.reload().await {
+            warn!(error = %e, "Failed to reload budgets");
+        }
+    }
+
+    /// Build the Decision card for a budget at its hard limit.
+    async fn raise_card(&self, budget: &Budget, spent: Decimal) -> ApprovalCard {
+        let hard = budget.hard_limit.unwrap_or_default();
+        let subject = match (budget.scope, budget.scope_id) {
+            (BudgetScope::Routine, Some(id)) => match self.db.get_routine(id).await {
+                Ok(Some(routine)) => format!("routine \"{}\"", routine.name),
+                _ => format!("routine {id}"),
+            },
+            (BudgetScope::Todo, Some(id)) => match self.db.get_todo(id).await {
+                Ok(Some(todo)) => format!("todo \"{}\"", todo.title),
+                _ => format!("todo {id}"),
+            },
+            _ => "all LLM calls".to_string(),
+        };
+        let reset = match budget.period {
+            BudgetPeriod::Daily => "tomorrow",
+            BudgetPeriod::Monthly => "next month",
+        };
+        ApprovalCard::new_decision(
+            format!(
+                "Raise the {} budget for {subject} to {}?",
+                budget.period.as_str(),
+                usd(raised_limit(hard))
+            ),
+            format!(
+                "{} of the {} limit is spent. Background agents and routines are paused. \
+                 Approve to raise the limit; dismiss to stay paused until {reset}.",
+                usd(spent),
+                usd(hard),
+            ),
+            Vec::new(),
+            CardSilo::Todos,
+            RAISE_CARD_EXPIRE_MINUTES,
+        )
+    }
+
+    /// Resolve the budget behind a Decision card, if there is one.
+    ///
+    /// Approving raises the hard limit; the soft limit stays where it was.
+    /// Returns `false` when the card isn't a raise request.
+    pub async fn resolve(&self, card_id: Uuid, accepted: bool) -> Result<bool, DatabaseError> {
+        let budget = self
+            .budgets
+            .read()
+            .await
+            .iter()
+            .find(|b| b.raise_card_id == Some(card_id))
+            .cloned();
+        let Some(mut budget) = budget else {
+            return Ok(false);
+        };
+        if !accepted {
+            // Keep the card ID so the scope stays paused without re-asking.
+            info!(budget_id = %budget.id, "Budget raise declined");
+            return Ok(true);
+        }
+
+        budget.hard_limit = budget.hard_limit.map(raised_limit);
+        budget.raise_card_id = None;
+        budget.raise_requested_at = None;
+        budget.updated_at = Utc::now();
+        self.db.update_budget(&budget).await?;
+        self.reload().await?;
+        info!(budget_id = %budget.id, hard_limit = ?budget.hard_limit, "Budget raised");
+        Ok(true)
+    }
+
+    /// Whether background work for a routine is paused right now.
+    pub async fn routine_paused(&self, routine_id: Uuid) -> bool {
+        let call = CallScope {
+            routine_id: Some(routine_id),
+            todo_id: None,
+            background: true,
+        };
+        matches!(self.check(&call).await, BudgetCheck::Paused(_))
+    }
+
+    /// Whether the agent for a todo is paused right now.
+    pub async fn todo_paused(&self, todo_id: Uuid) -> bool {
+        let call = CallScope {
+            routine_id: None,
+            todo_id: Some(todo_id),
+            background: true,
+        };
+        matches!(self.check(&call).await, BudgetCheck::Paused(_))
+    }
+}
+
+/// The limit offered on a raise card: half again, rounded to cents.
+fn raised_limit(limit: Decimal) -> Decimal {
+    (limit * Decimal::new(15, 1)).round_dp(2)
+}
+
+fn usd(amount: Decimal) -> String {
+    format!("${:.2}", amount)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::LibSqlBackend;
+    use crate::store::traits::LlmCallRecord;
+    use rust_decimal_macros::dec;
+
+    fn at(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn windows_are_utc_days_and_months() {
+        let now = at("2026-12-31T18:30:00Z");
+        assert_eq!(
+            BudgetPeriod::Daily.window(now),
+            (at("2026-12-31T00:00:00Z"), at("2027-01-01T00:00:00Z"))
+        );
+        assert_eq!(
+            BudgetPeriod::Monthly.window(now),
+            (at("2026-12-01T00:00:00Z"), at("2027-01-01T00:00:00Z"))
+        );
+    }
+
+    #[test]
+    fn validation_checks_scope_and_limits() {
+        let ok = Budget::new(BudgetScope::Global, None, BudgetPeriod::Daily, Some(dec!(1)), Some(dec!(2)));
+        assert!(ok.validate().is_ok());
+
+        let mut b = ok.clone();
+        b.scope = BudgetScope::Routine;
+        assert!(b.validate().is_err());
+        b.scope_id = Some(Uuid::new_v4());
+        assert!(b.validate().is_ok());
+
+        let inverted = Budget::new(BudgetScope::Global, None, BudgetPeriod::Daily, Some(dec!(3)), Some(dec!(2)));
+        assert!(inverted.validate().is_err());
+        let empty = Budget::new(BudgetScope::Global, None, BudgetPeriod::Daily, None, None);
+        assert!(empty.validate().is_err());
+        let zero = Budget::new(BudgetScope::Global, None, BudgetPeriod::Daily, None, Some(dec!(0)));
+        assert!(zero.validate().is_err());
+    }
+
+    #[test]
+    fn call_scope_reads_metadata() {
+        let todo = Uuid::new_v4();
+        let meta = HashMap::from([(META_TODO_ID.to_string(), todo.to_string())]);
+        let scope = CallScope::from_metadata(&meta, LlmRole::Agent);
+        assert_eq!(scope.todo_id, Some(todo));
+        assert!(scope.background);
+
+        let chat = CallScope::from_metadata(&HashMap::new(), LlmRole::Agent);
+        assert!(!chat.background);
+        assert!(CallScope::from_metadata(&HashMap::new(), LlmRole::Routine).background);
+    }
+
+    async fn spend(db: &Arc<dyn Database>, cost: Decimal) {
+        db.record_llm_call(&LlmCallRecord {
+            conversation_id: None,
+            routine_run_id: None,
+            todo_id: None,
+            provider: "m",
+            model: "m",
+            input_tokens: 1,
+            output_tokens: 1,
+            cost,
+            purpose: None,
+            role: Some("agent"),
+            price_source: None,
+        })
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn soft_downgrades_hard_pauses_background_and_asks_once() {
+        let db: Arc<dyn Database> = Arc::new(LibSqlBackend::new_memory().await.unwrap());
+        let queue = CardQueue::new();
+        let budget = Budget::new(BudgetScope::Global, None, BudgetPeriod::Daily, Some(dec!(1)), Some(dec!(2)));
+        db.create_budget(&budget).await.unwrap();
+        let guard = BudgetGuard::load(Arc::clone(&db), Some(Arc::clone(&queue))).await.unwrap();
+
+        let chat = CallScope::default();
+        let background = CallScope {
+            background: true,
+            ..CallScope::default()
+        };
+        assert_eq!(guard.check(&background).await, BudgetCheck::Within);
+
+        spend(&db, dec!(1.5)).await;
+        assert_eq!(guard.check(&background).await, BudgetCheck::Downgrade);
+
+        spend(&db, dec!(1)).await;
+        assert_eq!(guard.check(&chat).await, BudgetCheck::Downgrade);
+        assert!(matches!(guard.check(&background).await, BudgetCheck::Paused(_)));
+        assert!(matches!(guard.check(&background).await, BudgetCheck::Paused(_)));
+        assert_eq!(queue.len().await, 1, "one raise card per period");
+
+        let card = queue.pending().await.remove(0);
+        let status = guard.status().await.unwrap();
+        assert_eq!(status[0].spent, dec!(2.5));
+        assert_eq!(status[0].state, BudgetState::HardExceeded);
+        assert_eq!(status[0].budget.raise_card_id, Some(card.id));
+
+        // Declining keeps the scope paused
+        assert!(guard.resolve(card.id, false).await.unwrap());
+        assert!(matches!(guard.check(&background).await, BudgetCheck::Paused(_)));
+
+        // Approving raises the hard limit by half
+        assert!(guard.resolve(card.id, true).await.unwrap());
+        assert_eq!(guard.check(&background).await, BudgetCheck::Downgrade);
+        let raised = db.get_budget(budget.id).await.unwrap().unwrap();
+        assert_eq!(raised.hard_limit, Some(dec!(3)));
+        assert!(raised.raise_card_id.is_none());
+
+        assert!(!guard.resolve(Uuid::new_v4(), true).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn scoped_budgets_only_cover_their_scope() {
+        let db: Arc<dyn Database> = Arc::new(LibSqlBackend::new_memory().await.unwrap());
+        let todo_id = Uuid::new_v4();
+        let budget = Budget::new(BudgetScope::Todo, Some(todo_id), BudgetPeriod::Monthly, None, Some(dec!(0.5)));
+        db.create_budget(&budget).await.unwrap();
+        let guard = BudgetGuard::load(Arc::clone(&db), None).await.unwrap();
+
+        db.record_llm_call(&LlmCallRecord {
+            conversation_id: None,
+            routine_run_id: None,
+            todo_id: Some(todo_id),
+            provider: "m",
+            model: "m",
+            input_tokens: 1,
+            output_tokens: 1,
+            cost: dec!(0.75),
+            purpose: None,
+            role: Some("agent"),
+            price_source: None,
+        })
+        .await
+        .unwrap();
+
+        let this_todo = CallScope {
+            todo_id: Some(todo_id),
+            background: true,
+            ..CallScope::default()
+        };
+        let other_todo = CallScope {
+            todo_id: Some(Uuid::new_v4()),
+            background: true,
+            ..CallScope::default()
+        };
+        assert!(matches!(guard.check(&this_todo).await, BudgetCheck::Paused(_)));
+        assert_eq!(guard.check(&other_todo).await, BudgetCheck::Within);
+    }
+}
@@ -0,0 +1,325 @@
+//! Per-provider health tracking and circuit breaking.
+//!
+//! Each provider in a failover chain gets a [`ProviderHealth`] that keeps a
+//! rolling window of recent calls (error rate, p95 latency). Too many
+//! failures open the circuit: the provider is skipped for a cooldown, then
+//! half-opened so a single probe call decides whether it closes again.
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+/// When to open a provider's circuit, and for how long.
+#[derive(Debug, Clone, Copy)]
+pub struct BreakerConfig {
+    /// Calls kept in the rolling window.
+    pub window: usize,
+    /// Open after this many failures in a row.
+    pub consecutive_failures: u32,
+    /// Open when the window's error rate reaches this...
+    pub error_rate: f64,
+    /// ...once the window holds at least this many calls.
+    pub min_calls: usize,
+    /// How long an open circuit skips the provider before a probe.
+    pub cooldown: Duration,
+}
+
+impl Default for BreakerConfig {
+    fn default() -> Self {
+        Self {
+            window: 20,
+            consecutive_failures: 3,
+            error_rate: 0.5,
+            min_calls: 10,
+            cooldown: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Circuit state as reported by `/api/llm/health`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    /// Calls go through.
+    Closed,
+    /// Calls skip the provider until the cooldown ends.
+    Open,
+    /// Cooldown over; the next call is a probe.
+    HalfOpen,
+}
+
+/// Point-in-time health of one provider.
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthSnapshot {
+    pub state: CircuitState,
+    /// Failed share of the calls in the window (0.0–1.0).
+    pub error_rate: f64,
+    /// 95th-percentile latency over the window, in milliseconds.
+    pub p95_latency_ms: Option<u64>,
+    pub calls: usize,
+    pub consecutive_failures: u32,
+    /// Seconds until an open circuit admits a probe.
+    pub retry_in_secs: Option<u64>,
+}
+
+/// A provider's health, labelled with its model.
+#[derive(Debug, Clone, Serialize)]
+pub struct ProviderHealthReport {
+    pub model: String,
+    #[serde(flatten)]
+    pub health: HealthSnapshot,
+}
+
+#[derive(Debug)]
+struct Inner {
+    /// `(succeeded, latency)` for recent calls, oldest first.
+    calls: VecDeque<(bool, Duration)>,
+    consecutive_failures: u32,
+    /// Set while open; the circuit half-opens once this passes.
+    open_until: Option<Instant>,
+    /// A half-open probe is in flight.
+    probing: bool,
+}
+
+/// Rolling health and circuit breaker for one provider.
+#[derive(Debug)]
+pub struct ProviderHealth {
+    config: BreakerConfig,
+    inner: Mutex<Inner>,
+}
+
+impl ProviderHealth {
+    pub fn new(config: BreakerConfig) -> Self {
+        Self {
+            config,
+            inner: Mutex::new(Inner {
+                calls: VecDeque::with_capacity(config.window),
+                consecutive_failures: 0,
+                open_until: None,
+                probing: false,
+            }),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Ask to call the provider. `None` while the circuit is open, or while
+    /// another caller's half-open probe is in flight.
+    pub fn try_acquire(&self) -> Option<Permit<'_>> {
+        let mut inner = self.lock();
+        match inner.open_until {
+            None => Some(Permit::new(self, false)),
+            Some(until) if Instant::now() < until => None,
+            Some(_) if inner.probing => None,
+            Some(_) => {
+                inner.probing = true;
+                Some(Permit::new(self, true))
+            }
+        }
+    }
+
+    /// Time until an open circuit admits a probe (`None` when closed).
+    pub fn retry_in(&self) -> Option<Duration> {
+        self.lock()
+            .open_until
+            .map(|until| until.saturating_duration_since(Instant::now()))
+    }
+
+    fn record(&self, succeeded: bool, latency: Duration, probe: bool) {
+        let mut inner = self.lock();
+        if inner.calls.len() == self.config.window {
+            inner.calls.pop_front();
+        }
+        inner.calls.push_back((succeeded, latency));
+        if probe {
+            inner.probing = false;
+        }
+
+        if succeeded {
+            inner.consecutive_failures = 0;
+            if probe {
+                // Recovered: start the window afresh so old failures don't reopen it.
+                inner.open_until = None;
+                inner.calls.clear();
+            }
+            return;
+        }
+
+        inner.consecutive_failures += 1;
+        let failures = inner.calls.iter().filter(|(ok, _)| !ok).count();
+        let rate = failures as f64 / inner.calls.len() as f64;
+        let trip = probe
+            || inner.consecutive_failures >= self.config.consecutive_failures
+            || (inner.calls.len() >= self.config.min_calls && rate >= self.config.error_rate);
+        if trip {
+            inner.open_until = Some(Instant::now() + self.config.cooldown);
+        }
+    }
+
+    /// Current health.
+    pub fn snapshot(&self) -> HealthSnapshot {
+        let inner = self.lock();
+        let now = Instant::now();
+        let state = match inner.open_until {
+            None => CircuitState::Closed,
+            Some(until) if now < until => CircuitState::Open,
+            Some(_) => CircuitState::HalfOpen,
+        };
+        let failures = inner.calls.iter().filter(|(ok, _)| !ok).count();
+        let error_rate = if inner.calls.is_empty() {
+            0.0
+        } else {
+            failures as f64 / inner.calls.len() as f64
+        };
+        let mut latencies: Vec<Duration> = inner.calls.iter().map(|(_, l)| *l).collect();
+        latencies.sort();
+        let p95 = (!latencies.is_empty()).then(|| {
+            let idx = ((latencies.len() as f64 * 0.95).ceil() as usize).saturating_sub(1);
+            latencies[idx].as_millis() as u64
+        });
+        HealthSnapshot {
+            state,
+            error_rate,
+            p95_latency_ms: p95,
+            calls: inner.calls.len(),
+            consecutive_failures: inner.consecutive_failures,
+            retry_in_secs: inner
+                .open_until
+                .filter(|until| now < *until)
+                .map(|until| until.duration_since(now).as_secs()),
+        }
+    }
+}
+
+/// Admission to call a provider. Report the outcome with `success` or
+/// `failure`; dropping it unreported (e.g. a cancelled call) frees a
+/// half-open probe slot without recording anything.
+pub struct Permit<'a> {
+    health: &'a ProviderHealth,
+    probe: bool,
+    started: Instant,
+    reported: bool,
+}
+
+impl<'a> Permit<'a> {
+    fn new(health: &'a ProviderHealth, probe: bool) -> Self {
+        Self {
+            health,
+            probe,
+            started: Instant::now(),
+            reported: false,
+        }
+    }
+
+    /// Whether this call is a half-open probe.
+    pub fn is_probe(&self) -> bool {
+        self.probe
+    }
+
+    pub fn success(mut self) {
+        self.reported = true;
+        self.health.record(true, self.started.elapsed(), self.probe);
+    }
+
+    pub fn failure(mut self) {
+        self.reported = true;
+        self.health.record(false, self.started.elapsed(), self.probe);
+    }
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        if self.probe && !self.reported {
+            self.health.lock().probing = false;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(cooldown_ms: u64) -> BreakerConfig {
+        BreakerConfig {
+            cooldown: Duration::from_millis(cooldown_ms),
+            ..BreakerConfig::default()
+        }
+    }
+
+    #[test]
+    fn consecutive_failures_open_the_circuit() {
+        let health = ProviderHealth::new(config(60_000));
+        for _ in 0..2 {
+            health.try_acquire().unwrap().failure();
+        }
+        assert_eq!(health.snapshot().state, CircuitState::Closed);
+        health.try_acquire().unwrap().failure();
+
+        let snap = health.snapshot();
+        assert_eq!(snap.state, CircuitState::Open);
+        assert_eq!(snap.consecutive_failures, 3);
+        assert_eq!(snap.error_rate, 1.0);
+        assert!(health.try_acquire().is_none());
+        assert!(health.retry_in().unwrap() > Duration::from_secs(50));
+    }
+
+    #[test]
+    fn error_rate_opens_the_circuit() {
+        let health = ProviderHealth::new(config(60_000));
+        // Alternate so there are never three failures in a row; the tenth
+        // call (a failure) fills the minimum window at a 50% error rate
+        for i in 0..10 {
+            let permit = health.try_acquire().unwrap();
+            if i % 2 == 1 {
+                permit.failure();
+            } else {
+                permit.success();
+            }
+        }
+        assert_eq!(health.snapshot().state, CircuitState::Open);
+    }
+
+    #[test]
+    fn half_open_admits_one_probe() {
+        let health = ProviderHealth::new(config(10));
+        for _ in 0..3 {
+            health.try_acquire().unwrap().failure();
+        }
+        std::thread::sleep(Duration::from_millis(20));
+        assert_eq!(health.snapshot().state, CircuitState::HalfOpen);
+
+        let probe = health.try_acquire().unwrap();
+        assert!(probe.is_probe());
+        assert!(health.try_acquire().is_none(), "only one probe at a time");
+
+        // A failed probe reopens the circuit
+        probe.failure();
+        assert_eq!(health.snapshot().state, CircuitState::Open);
+
+        std::thread::sleep(Duration::from_millis(20));
+        // A dropped probe frees the slot
+        drop(health.try_acquire().unwrap());
+        let probe = health.try_acquire().unwrap();
+        probe.success();
+        let snap = health.snapshot();
+        assert_eq!(snap.state, CircuitState::Closed);
+        assert_eq!(snap.calls, 0);
+        assert!(health.try_acquire().is_some());
+    }
+
+    #[test]
+    fn p95_latency_over_window() {
+        let health = ProviderHealth::new(BreakerConfig::default());
+        for ms in 1..=20u64 {
+            health.record(true, Duration::from_millis(ms * 10), false);
+        }
+        let snap = health.snapshot();
+        assert_eq!(snap.calls, 20);
+        assert_eq!(snap.p95_latency_ms, Some(190));
+        assert_eq!(snap.error_rate, 0.0);
+    }
+}
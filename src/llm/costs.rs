@@ -1,57 +1,306 @@
-//! Per-model cost lookup table for multi-provider LLM support.
+//! Model pricing, loaded from a versioned data file.
 //!
-//! Returns (input_cost_per_token, output_cost_per_token) as Decimal pairs.
-//! Ollama and other local models return zero cost.
+//! Prices ship in `pricing.json` (USD per million tokens) with prompt-cache
+//! read/write rates and batch discounts, and can be overridden per model
+//! through the `llm_pricing` setting. Local models (Ollama, llama.cpp, ...)
+//! are free. Anything else is priced at the file's fallback rate and tagged
+//! [`PriceSource::Fallback`] so the cost API can warn about it.
+
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock, RwLock};
 
 use rust_decimal::Decimal;
-use rust_decimal_macros::dec;
-
-/// Look up known per-token costs for a model by its identifier.
-///
-/// Returns `Some((input_cost, output_cost))` for known models, `None` otherwise.
-pub fn model_cost(model_id: &str) -> Option<(Decimal, Decimal)> {
-    // Normalize: strip provider prefixes (e.g., "openai/gpt-4o" -> "gpt-4o")
-    let id = model_id
-        .rsplit_once('/')
-        .map(|(_, name)| name)
-        .unwrap_or(model_id);
-
-    match id {
-        // OpenAI models -- prices per token (USD)
-        "gpt-4o" | "gpt-4o-2024-11-20" | "gpt-4o-2024-08-06" => {
-            Some((dec!(0.0000025), dec!(0.00001)))
+use serde::{Deserialize, Serialize};
+
+use crate::error::DatabaseError;
+use crate::store::Database;
+
+/// The pricing table bundled with this build.
+const BUNDLED_PRICING: &str = include_str!("pricing.json");
+
+/// Settings key (user `default`) holding per-model price overrides, in the
+/// same shape as `pricing.json`.
+pub const PRICING_SETTING: &str = "llm_pricing";
+
+/// Prices in the data file are per million tokens.
+const TOKENS_PER_UNIT: i64 = 1_000_000;
+
+/// The on-disk pricing format (also used for overrides).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PricingFile {
+    #[serde(default)]
+    pub version: String,
+    /// Rate for models missing from the table.
+    #[serde(default)]
+    pub fallback: Option<PriceEntry>,
+    #[serde(default)]
+    pub models: Vec<PriceEntry>,
+}
+
+/// One model's prices, in USD per million tokens.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PriceEntry {
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
+    pub input: Decimal,
+    pub output: Decimal,
+    /// Prompt-cache reads; defaults to the input rate.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_read: Option<Decimal>,
+    /// Prompt-cache writes; defaults to the input rate.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_write: Option<Decimal>,
+    /// Fraction knocked off batch API calls (`0.5` = half price).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub batch_discount: Option<Decimal>,
+}
+
+impl PriceEntry {
+    fn validate(&self) -> Result<(), String> {
+        let rates = [Some(self.input), Some(self.output), self.cache_read, self.cache_write];
+        if rates.into_iter().flatten().any(|r| r < Decimal::ZERO) {
+            return Err(format!("{}: prices must not be negative", self.id));
         }
-        "gpt-4o-mini" | "gpt-4o-mini-2024-07-18" => Some((dec!(0.00000015), dec!(0.0000006))),
-        "gpt-4-turbo" | "gpt-4-turbo-2024-04-09" => Some((dec!(0.00001), dec!(0.00003))),
-        "gpt-4" | "gpt-4-0613" => Some((dec!(0.00003), dec!(0.00006))),
-        "gpt-3.5-turbo" | "gpt-3.5-turbo-0125" => Some((dec!(0.0000005), dec!(0.0000015))),
-        "o1" | "o1-2024-12-17" => Some((dec!(0.000015), dec!(0.00006))),
-        "o1-mini" | "o1-mini-2024-09-12" => Some((dec!(0.000003), dec!(0.000012))),
-        "o3-mini" | "o3-mini-2025-01-31" => Some((dec!(0.0000011), dec!(0.0000044))),
-
-        // Anthropic models
-        "claude-3-5-sonnet-20241022" | "claude-3-5-sonnet-latest" | "claude-sonnet-4-20250514" => {
-            Some((dec!(0.000003), dec!(0.000015)))
+        if self
+            .batch_discount
+            .is_some_and(|d| d < Decimal::ZERO || d > Decimal::ONE)
+        {
+            return Err(format!("{}: batch_discount must be between 0 and 1", self.id));
         }
-        "claude-3-5-haiku-20241022" | "claude-3-5-haiku-latest" => {
-            Some((dec!(0.0000008), dec!(0.000004)))
+        Ok(())
+    }
+
+    fn pricing(&self) -> ModelPricing {
+        let per_token = |rate: Decimal| rate / Decimal::from(TOKENS_PER_UNIT);
+        ModelPricing {
+            input: per_token(self.input),
+            output: per_token(self.output),
+            cache_read: per_token(self.cache_read.unwrap_or(self.input)),
+            cache_write: per_token(self.cache_write.unwrap_or(self.input)),
+            batch_discount: self.batch_discount.unwrap_or(Decimal::ZERO),
+        }
+    }
+}
+
+/// Per-token prices for one model.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ModelPricing {
+    pub input: Decimal,
+    pub output: Decimal,
+    pub cache_read: Decimal,
+    pub cache_write: Decimal,
+    pub batch_discount: Decimal,
+}
+
+impl ModelPricing {
+    /// Plain input/output pricing with no cache or batch rates.
+    pub fn flat((input, output): (Decimal, Decimal)) -> Self {
+        Self {
+            input,
+            output,
+            cache_read: input,
+            cache_write: input,
+            batch_discount: Decimal::ZERO,
+        }
+    }
+
+    /// Cost of one call.
+    pub fn cost(&self, usage: &UsageCounts) -> Decimal {
+        let uncached = usage
+            .input_tokens
+            .saturating_sub(usage.cache_read_tokens)
+            .saturating_sub(usage.cache_write_tokens);
+        let cost = self.input * Decimal::from(uncached)
+            + self.cache_read * Decimal::from(usage.cache_read_tokens)
+            + self.cache_write * Decimal::from(usage.cache_write_tokens)
+            + self.output * Decimal::from(usage.output_tokens);
+        if usage.batch {
+            cost * (Decimal::ONE - self.batch_discount)
+        } else {
+            cost
         }
-        "claude-3-opus-20240229" | "claude-3-opus-latest" | "claude-opus-4-20250514" => {
-            Some((dec!(0.000015), dec!(0.000075)))
+    }
+}
+
+/// Token counts for one call. Cache reads and writes are part of
+/// `input_tokens`, not in addition to it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct UsageCounts {
+    pub input_tokens: u32,
+    pub output_tokens: u32,
+    pub cache_read_tokens: u32,
+    pub cache_write_tokens: u32,
+    /// Submitted through a provider batch API.
+    pub batch: bool,
+}
+
+impl UsageCounts {
+    pub fn new(input_tokens: u32, output_tokens: u32) -> Self {
+        Self {
+            input_tokens,
+            output_tokens,
+            ..Self::default()
         }
-        "claude-3-haiku-20240307" => Some((dec!(0.00000025), dec!(0.00000125))),
+    }
+}
 
-        // Ollama / local models -- free
-        _ if is_local_model(id) => Some((Decimal::ZERO, Decimal::ZERO)),
+/// Where a model's price came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PriceSource {
+    /// The bundled pricing table.
+    Table,
+    /// The `llm_pricing` setting.
+    Override,
+    /// A local model; free.
+    Local,
+    /// Unknown model, priced at the fallback rate.
+    Fallback,
+    /// Supplied by the provider itself.
+    Provider,
+}
 
-        _ => None,
+impl PriceSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Table => "table",
+            Self::Override => "override",
+            Self::Local => "local",
+            Self::Fallback => "fallback",
+            Self::Provider => "provider",
+        }
     }
 }
 
-/// Default cost for unknown models.
-pub fn default_cost() -> (Decimal, Decimal) {
-    // Conservative estimate: roughly GPT-4o pricing
-    (dec!(0.0000025), dec!(0.00001))
+/// A model's pricing and its source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PriceQuote {
+    pub pricing: ModelPricing,
+    pub source: PriceSource,
+}
+
+/// Bundled prices plus any overrides, keyed by model ID and alias.
+#[derive(Debug, Clone)]
+pub struct PricingTable {
+    version: String,
+    models: HashMap<String, PriceQuote>,
+    fallback: ModelPricing,
+    overrides: usize,
+}
+
+impl PricingTable {
+    /// Build a table from a pricing file.
+    pub fn from_file(file: &PricingFile) -> Result<Self, String> {
+        let fallback = file
+            .fallback
+            .as_ref()
+            .ok_or("pricing file has no fallback rate")?;
+        fallback.validate()?;
+        let mut table = Self {
+            version: file.version.clone(),
+            models: HashMap::new(),
+            fallback: fallback.pricing(),
+            overrides: 0,
+        };
+        table.insert_all(&file.models, PriceSource::Table)?;
+        Ok(table)
+    }
+
+    /// The table bundled with this build.
+    pub fn bundled() -> Self {
+        let file: PricingFile =
+            serde_json::from_str(BUNDLED_PRICING).expect("bundled pricing.json is valid");
+        Self::from_file(&file).expect("bundled pricing.json is valid")
+    }
+
+    /// Layer overrides on top; a new fallback rate replaces the bundled one.
+    pub fn with_overrides(mut self, overrides: &PricingFile) -> Result<Self, String> {
+        if let Some(fallback) = &overrides.fallback {
+            fallback.validate()?;
+            self.fallback = fallback.pricing();
+        }
+        self.insert_all(&overrides.models, PriceSource::Override)?;
+        self.overrides = overrides.models.len();
+        Ok(self)
+    }
+
+    fn insert_all(&mut self, entries: &[PriceEntry], source: PriceSource) -> Result<(), String> {
+        for entry in entries {
+            entry.validate()?;
+            let quote = PriceQuote {
+                pricing: entry.pricing(),
+                source,
+            };
+            for id in std::iter::once(&entry.id).chain(&entry.aliases) {
+                self.models.insert(id.to_lowercase(), quote);
+            }
+        }
+        Ok(())
+    }
+
+    /// Version of the bundled data file.
+    pub fn version(&self) -> &str {
+        &self.version
+    }
+
+    /// Number of models overridden from settings.
+    pub fn override_count(&self) -> usize {
+        self.overrides
+    }
+
+    /// Price a model by its identifier.
+    ///
+    /// Provider prefixes (`openai/gpt-4o`) and date or `-latest` suffixes
+    /// (`claude-sonnet-4-20250514`, `gpt-4o-2024-08-06`) are ignored.
+    pub fn lookup(&self, model_id: &str) -> PriceQuote {
+        let id = model_id
+            .rsplit_once('/')
+            .map(|(_, name)| name)
+            .unwrap_or(model_id)
+            .to_lowercase();
+
+        let base = strip_version_suffix(&id);
+        if let Some(quote) = self.models.get(&id).or_else(|| self.models.get(base)) {
+            return *quote;
+        }
+        if is_local_model(&id) {
+            return PriceQuote {
+                pricing: ModelPricing::flat((Decimal::ZERO, Decimal::ZERO)),
+                source: PriceSource::Local,
+            };
+        }
+        PriceQuote {
+            pricing: self.fallback,
+            source: PriceSource::Fallback,
+        }
+    }
+}
+
+/// Strip a trailing `-YYYYMMDD`, `-YYYY-MM-DD` or `-latest`.
+fn strip_version_suffix(id: &str) -> &str {
+    if let Some(base) = id.strip_suffix("-latest") {
+        return base;
+    }
+    let is_date = |s: &str| {
+        let digits: String = s.chars().filter(|c| *c != '-').collect();
+        digits.len() == 8 && digits.chars().all(|c| c.is_ascii_digit())
+    };
+    // -YYYY-MM-DD
+    if id.len() > 11 && id.is_char_boundary(id.len() - 11) {
+        let (base, suffix) = id.split_at(id.len() - 11);
+        if suffix.starts_with('-') && suffix.matches('-').count() == 3 && is_date(suffix) {
+            return base;
+        }
+    }
+    // -YYYYMMDD
+    if let Some((base, suffix)) = id.rsplit_once('-')
+        && suffix.len() == 8
+        && is_date(suffix)
+    {
+        return base;
+    }
+    id
 }
 
 /// Heuristic to detect local/self-hosted models (Ollama, llama.cpp, etc.).
@@ -72,53 +321,157 @@ fn is_local_model(model_id: &str) -> bool {
         || lower.contains(":instruct")
 }
 
+/// The live table: bundled prices plus settings overrides.
+static PRICING: LazyLock<RwLock<Arc<PricingTable>>> =
+    LazyLock::new(|| RwLock::new(Arc::new(PricingTable::bundled())));
+
+/// The current pricing table.
+pub fn pricing_table() -> Arc<PricingTable> {
+    Arc::clone(&PRICING.read().unwrap_or_else(|e| e.into_inner()))
+}
+
+/// Price a model against the current table.
+pub fn lookup(model_id: &str) -> PriceQuote {
+    pricing_table().lookup(model_id)
+}
+
+/// Replace the overrides layered on the bundled table (`None` clears them).
+pub fn install_overrides(overrides: Option<&PricingFile>) -> Result<(), String> {
+    let table = match overrides {
+        Some(overrides) => PricingTable::bundled().with_overrides(overrides)?,
+        None => PricingTable::bundled(),
+    };
+    *PRICING.write().unwrap_or_else(|e| e.into_inner()) = Arc::new(table);
+    Ok(())
+}
+
+/// Load overrides from the `llm_pricing` setting. Returns how many models
+/// were overridden.
+pub async fn load_overrides(db: &dyn Database) -> Result<usize, DatabaseError> {
+    let Some(value) = db.get_setting("default", PRICING_SETTING).await? else {
+        return Ok(0);
+    };
+    let overrides: PricingFile = serde_json::from_value(value)
+        .map_err(|e| DatabaseError::Serialization(format!("{PRICING_SETTING}: {e}")))?;
+    install_overrides(Some(&overrides)).map_err(DatabaseError::Serialization)?;
+    Ok(overrides.models.len())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rust_decimal_macros::dec;
+
+    fn per_million(rate: Decimal) -> Decimal {
+        rate / Decimal::from(TOKENS_PER_UNIT)
+    }
+
+    #[test]
+    fn test_bundled_table_loads() {
+        let table = PricingTable::bundled();
+        assert!(!table.version().is_empty());
+        assert_eq!(table.override_count(), 0);
+    }
 
     #[test]
     fn test_known_model_costs() {
-        let (input, output) = model_cost("gpt-4o").unwrap();
-        assert!(input > Decimal::ZERO);
-        assert!(output > input);
+        let quote = PricingTable::bundled().lookup("gpt-4o");
+        assert_eq!(quote.source, PriceSource::Table);
+        assert!(quote.pricing.input > Decimal::ZERO);
+        assert!(quote.pricing.output > quote.pricing.input);
     }
 
     #[test]
-    fn test_claude_costs() {
-        let (input, output) = model_cost("claude-3-5-sonnet-20241022").unwrap();
-        assert!(input > Decimal::ZERO);
-        assert!(output > input);
+    fn test_dated_and_latest_ids_resolve() {
+        let table = PricingTable::bundled();
+        let sonnet = table.lookup("claude-sonnet-4-5");
+        assert_eq!(table.lookup("claude-sonnet-4-20250514"), sonnet);
+        assert_eq!(table.lookup("claude-3-5-sonnet-20241022"), sonnet);
+        assert_eq!(table.lookup("gpt-4o-2024-08-06"), table.lookup("gpt-4o"));
+        assert_eq!(table.lookup("claude-haiku-4-5-latest").source, PriceSource::Table);
+        assert_eq!(sonnet.pricing.input, per_million(dec!(3)));
+        assert_eq!(sonnet.pricing.cache_read, per_million(dec!(0.30)));
     }
 
     #[test]
     fn test_local_model_free() {
-        let (input, output) = model_cost("llama3").unwrap();
-        assert_eq!(input, Decimal::ZERO);
-        assert_eq!(output, Decimal::ZERO);
+        let table = PricingTable::bundled();
+        for id in ["llama3", "mistral:latest"] {
+            let quote = table.lookup(id);
+            assert_eq!(quote.source, PriceSource::Local);
+            assert_eq!(quote.pricing.cost(&UsageCounts::new(1000, 1000)), Decimal::ZERO);
+        }
     }
 
     #[test]
-    fn test_ollama_tagged_model_free() {
-        let (input, output) = model_cost("mistral:latest").unwrap();
-        assert_eq!(input, Decimal::ZERO);
-        assert_eq!(output, Decimal::ZERO);
+    fn test_unknown_model_uses_flagged_fallback() {
+        let quote = PricingTable::bundled().lookup("some-totally-unknown-model-xyz");
+        assert_eq!(quote.source, PriceSource::Fallback);
+        assert!(quote.pricing.input > Decimal::ZERO);
     }
 
     #[test]
-    fn test_unknown_model_returns_none() {
-        assert!(model_cost("some-totally-unknown-model-xyz").is_none());
+    fn test_provider_prefix_stripped() {
+        let table = PricingTable::bundled();
+        assert_eq!(table.lookup("openai/gpt-4o"), table.lookup("gpt-4o"));
     }
 
     #[test]
-    fn test_default_cost_nonzero() {
-        let (input, output) = default_cost();
-        assert!(input > Decimal::ZERO);
-        assert!(output > Decimal::ZERO);
+    fn test_cache_and_batch_rates() {
+        let pricing = PriceEntry {
+            id: "m".into(),
+            aliases: Vec::new(),
+            input: dec!(3),
+            output: dec!(15),
+            cache_read: Some(dec!(0.30)),
+            cache_write: Some(dec!(3.75)),
+            batch_discount: Some(dec!(0.5)),
+        }
+        .pricing();
+
+        let usage = UsageCounts {
+            input_tokens: 1_000_000,
+            output_tokens: 100_000,
+            cache_read_tokens: 600_000,
+            cache_write_tokens: 200_000,
+            batch: false,
+        };
+        // 200k uncached × 3 + 600k × 0.30 + 200k × 3.75 + 100k × 15
+        assert_eq!(pricing.cost(&usage), dec!(0.6) + dec!(0.18) + dec!(0.75) + dec!(1.5));
+        let batched = UsageCounts { batch: true, ..usage };
+        assert_eq!(pricing.cost(&batched), pricing.cost(&usage) / dec!(2));
     }
 
     #[test]
-    fn test_provider_prefix_stripped() {
-        // "openai/gpt-4o" should resolve to same as "gpt-4o"
-        assert_eq!(model_cost("openai/gpt-4o"), model_cost("gpt-4o"));
+    fn test_overrides_replace_and_add_models() {
+        let overrides: PricingFile = serde_json::from_value(serde_json::json!({
+            "models": [
+                {"id": "gpt-4o", "input": "1.00", "output": "2.00"},
+                {"id": "my-hosted-model", "input": "0.10", "output": "0.20"}
+            ]
+        }))
+        .unwrap();
+        let table = PricingTable::bundled().with_overrides(&overrides).unwrap();
+        assert_eq!(table.override_count(), 2);
+
+        let gpt = table.lookup("gpt-4o-2024-11-20");
+        assert_eq!(gpt.source, PriceSource::Override);
+        assert_eq!(gpt.pricing.input, per_million(dec!(1)));
+        assert_eq!(gpt.pricing.cache_read, gpt.pricing.input);
+        assert_eq!(table.lookup("my-hosted-model").source, PriceSource::Override);
+
+        let negative: PricingFile = serde_json::from_value(serde_json::json!({
+            "models": [{"id": "x", "input": "-1", "output": "1"}]
+        }))
+        .unwrap();
+        assert!(PricingTable::bundled().with_overrides(&negative).is_err());
+    }
+
+    #[test]
+    fn test_version_suffixes() {
+        assert_eq!(strip_version_suffix("o3-mini-2025-01-31"), "o3-mini");
+        assert_eq!(strip_version_suffix("claude-opus-4-1-20250805"), "claude-opus-4-1");
+        assert_eq!(strip_version_suffix("gpt-4-0613"), "gpt-4-0613");
+        assert_eq!(strip_version_suffix("gpt-4o"), "gpt-4o");
     }
 }
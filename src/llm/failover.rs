@@ -2,25 +2,37 @@
 //!
 //! Wraps multiple LlmProvider instances and tries each in sequence
 //! until one succeeds. Transparent to callers --- same LlmProvider trait.
+//!
+//! Streaming requests fail over only until the first delta reaches the
+//! caller; after that, switching providers would splice two answers together.
+//!
+//! Each provider has a circuit breaker (see [`crate::llm::circuit`]): one
+//! that keeps failing is skipped until its cooldown ends. When the whole
+//! chain fails, it can be retried with backoff that waits out open circuits
+//! rather than retrying into them.
 
 use std::future::Future;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
 
 use async_trait::async_trait;
 use rust_decimal::Decimal;
 
 use crate::error::LlmError;
+use crate::llm::circuit::{BreakerConfig, ProviderHealth, ProviderHealthReport};
+use crate::llm::costs::PriceQuote;
 use crate::llm::provider::{
-    CompletionRequest, CompletionResponse, LlmProvider, ToolCompletionRequest,
-    ToolCompletionResponse,
+    CompletionRequest, CompletionResponse, LlmProvider, ModelMetadata, StreamSender,
+    ToolCompletionRequest, ToolCompletionResponse,
 };
+use crate::llm::retry::{MAX_RETRY_DELAY, next_retry_delay};
 
 /// Returns `true` if the error is transient and the request should be retried
 /// on the next provider in the failover chain.
 ///
 /// Retryable: `RequestFailed`, `RateLimited`, `InvalidResponse`,
-/// `SessionRenewalFailed`, `ModelNotAvailable`, `Http`, `Io`.
+/// `SessionRenewalFailed`, `ModelNotAvailable`, `CircuitOpen`, `Io`.
 ///
 /// `ModelNotAvailable` is retryable because the next provider in the chain may
 /// offer a different model, so it's worth trying.
@@ -36,6 +48,7 @@ fn is_retryable(err: &LlmError) -> bool {
             | LlmError::SessionRenewalFailed { .. }
             // ModelNotAvailable is retryable: the next provider may offer a different model.
             | LlmError::ModelNotAvailable { .. }
+            | LlmError::CircuitOpen { .. }
             | LlmError::Io(_)
     )
 }
@@ -46,8 +59,16 @@ fn is_retryable(err: &LlmError) -> bool {
 /// The first provider in the list is the primary. If it fails with a retryable
 /// error, the next provider is tried, and so on. Non-retryable errors
 /// (e.g. `AuthFailed`, `ContextLengthExceeded`) propagate immediately.
+/// Providers whose circuit is open are skipped.
 pub struct FailoverProvider {
     providers: Vec<Arc<dyn LlmProvider>>,
+    /// Circuit breaker per provider, same order as `providers`.
+    health: Vec<ProviderHealth>,
+    /// Times to retry the whole chain after every provider failed.
+    max_retries: u32,
+    /// Longest wait before a chain retry; never shorter than the breaker
+    /// cooldown, so a retry can wait out an open circuit.
+    max_retry_delay: Duration,
     /// Index of the provider that last handled a request successfully.
     /// Used by `model_name()` and `cost_per_token()` so downstream cost
     /// tracking reflects the provider that actually served the request.
@@ -65,14 +86,99 @@ impl FailoverProvider {
                 reason: "FailoverProvider requires at least one provider".to_string(),
             });
         }
+        let config = BreakerConfig::default();
+        let health = providers
+            .iter()
+            .map(|_| ProviderHealth::new(config))
+            .collect();
         Ok(Self {
             providers,
+            health,
+            max_retries: 0,
+            max_retry_delay: MAX_RETRY_DELAY.max(config.cooldown),
             last_used: AtomicUsize::new(0),
         })
     }
 
-    /// Try each provider in sequence until one succeeds or all fail.
+    /// Use `config` for every provider's circuit breaker (resets their health).
+    pub fn with_breaker(mut self, config: BreakerConfig) -> Self {
+        self.health = self
+            .providers
+            .iter()
+            .map(|_| ProviderHealth::new(config))
+            .collect();
+        self.max_retry_delay = MAX_RETRY_DELAY.max(config.cooldown);
+        self
+    }
+
+    /// Retry the whole chain up to `max_retries` times after every provider
+    /// failed with a retryable error.
+    pub fn with_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Time until some provider's circuit admits a call; `None` if one is
+    /// closed (or half-open) now.
+    fn breaker_wait(&self) -> Option<Duration> {
+        self.health
+            .iter()
+            .map(ProviderHealth::retry_in)
+            .collect::<Option<Vec<_>>>()
+            .and_then(|waits| waits.into_iter().min())
+            .filter(|wait| !wait.is_zero())
+    }
+
+    /// The error for a chain whose providers were all skipped.
+    fn circuit_open(&self) -> LlmError {
+        LlmError::CircuitOpen {
+            provider: self.providers[0].model_name().to_string(),
+            retry_after: self.breaker_wait().unwrap_or(Duration::ZERO),
+        }
+    }
+
+    /// Delay before retrying the chain after `err`, or `None` to give up.
+    fn retry_delay(&self, attempt: u32, err: &LlmError) -> Option<Duration> {
+        if !is_retryable(err) {
+            return None;
+        }
+        let delay = next_retry_delay(
+            attempt,
+            self.max_retries,
+            err,
+            self.breaker_wait(),
+            self.max_retry_delay,
+        )?;
+        tracing::warn!(
+            error = %err,
+            attempt = attempt + 1,
+            delay_ms = delay.as_millis() as u64,
+            "All providers failed, retrying chain"
+        );
+        Some(delay)
+    }
+
+    /// Run the chain, retrying it with backoff while that's worthwhile.
     async fn try_providers<T, F, Fut>(&self, mut call: F) -> Result<T, LlmError>
+    where
+        F: FnMut(Arc<dyn LlmProvider>) -> Fut,
+        Fut: Future<Output = Result<T, LlmError>>,
+    {
+        let mut attempt = 0;
+        loop {
+            match self.try_chain(&mut call).await {
+                Ok(response) => return Ok(response),
+                Err(err) => match self.retry_delay(attempt, &err) {
+                    Some(delay) => tokio::time::sleep(delay).await,
+                    None => return Err(err),
+                },
+            }
+            attempt += 1;
+        }
+    }
+
+    /// Try each available provider in sequence until one succeeds or all fail.
+    async fn try_chain<T, F, Fut>(&self, call: &mut F) -> Result<T, LlmError>
     where
         F: FnMut(Arc<dyn LlmProvider>) -> Fut,
         Fut: Future<Output = Result<T, LlmError>>,
@@ -80,16 +186,24 @@ impl FailoverProvider {
         let mut last_error: Option<LlmError> = None;
 
         for (i, provider) in self.providers.iter().enumerate() {
+            let Some(permit) = self.health[i].try_acquire() else {
+                tracing::debug!(provider = %provider.model_name(), "Circuit open, skipping provider");
+                continue;
+            };
             let result = call(Arc::clone(provider)).await;
             match result {
                 Ok(response) => {
+                    permit.success();
                     self.last_used.store(i, Ordering::Relaxed);
                     return Ok(response);
                 }
                 Err(err) => {
+                    // Non-retryable errors (bad auth, oversized context) say
+                    // nothing about the provider's health.
                     if !is_retryable(&err) {
                         return Err(err);
                     }
+                    permit.failure();
                     if i + 1 < self.providers.len() {
                         tracing::warn!(
                             provider = %provider.model_name(),
@@ -103,9 +217,68 @@ impl FailoverProvider {
             }
         }
 
-        // SAFETY: providers is non-empty (checked in `new`), so at least one
-        // iteration ran and `last_error` is `Some`.
-        Err(last_error.expect("providers list is non-empty"))
+        // No error means every provider was skipped.
+        Err(last_error.unwrap_or_else(|| self.circuit_open()))
+    }
+
+    /// One streaming pass over the chain. Sets `streamed` once a delta has
+    /// reached the caller, after which the error is final.
+    async fn stream_chain(
+        &self,
+        request: &ToolCompletionRequest,
+        deltas: &StreamSender,
+        streamed: &mut bool,
+    ) -> Result<ToolCompletionResponse, LlmError> {
+        let mut last_error: Option<LlmError> = None;
+
+        for (i, provider) in self.providers.iter().enumerate() {
+            let Some(permit) = self.health[i].try_acquire() else {
+                tracing::debug!(provider = %provider.model_name(), "Circuit open, skipping provider");
+                continue;
+            };
+            // Relay through a per-attempt channel to learn whether anything
+            // reached the caller before a failure.
+            let (attempt_tx, mut attempt_rx) = tokio::sync::mpsc::unbounded_channel();
+            let relay = async {
+                let mut streamed = false;
+                while let Some(delta) = attempt_rx.recv().await {
+                    streamed = true;
+                    let _ = deltas.send(delta);
+                }
+                streamed
+            };
+            let (result, attempt_streamed) =
+                tokio::join!(provider.stream_with_tools(request.clone(), attempt_tx), relay);
+
+            match result {
+                Ok(response) => {
+                    permit.success();
+                    self.last_used.store(i, Ordering::Relaxed);
+                    return Ok(response);
+                }
+                Err(err) => {
+                    if !is_retryable(&err) {
+                        return Err(err);
+                    }
+                    permit.failure();
+                    if attempt_streamed {
+                        *streamed = true;
+                        return Err(err);
+                    }
+                    if i + 1 < self.providers.len() {
+                        tracing::warn!(
+                            provider = %provider.model_name(),
+                            error = %err,
+                            next_provider = %self.providers[i + 1].model_name(),
+                            "Provider failed before streaming, trying next provider"
+                        );
+                    }
+                    last_error = Some(err);
+                }
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| self.circuit_open()))
     }
 }
 
@@ -119,6 +292,17 @@ impl LlmProvider for FailoverProvider {
         self.providers[self.last_used.load(Ordering::Relaxed)].cost_per_token()
     }
 
+    fn pricing(&self) -> PriceQuote {
+        self.providers[self.last_used.load(Ordering::Relaxed)].pricing()
+    }
+
+    fn pricing_for(&self, model: &str) -> PriceQuote {
+        self.providers
+            .iter()
+            .find(|p| p.model_name() == model)
+            .map_or_else(|| self.pricing(), |p| p.pricing_for(model))
+    }
+
     async fn complete(&self, request: CompletionRequest) -> Result<CompletionResponse, LlmError> {
         self.try_providers(|provider| {
             let req = request.clone();
@@ -138,6 +322,26 @@ impl LlmProvider for FailoverProvider {
         .await
     }
 
+    async fn stream_with_tools(
+        &self,
+        request: ToolCompletionRequest,
+        deltas: StreamSender,
+    ) -> Result<ToolCompletionResponse, LlmError> {
+        let mut attempt = 0;
+        loop {
+            let mut streamed = false;
+            match self.stream_chain(&request, &deltas, &mut streamed).await {
+                Ok(response) => return Ok(response),
+                Err(err) if streamed => return Err(err),
+                Err(err) => match self.retry_delay(attempt, &err) {
+                    Some(delay) => tokio::time::sleep(delay).await,
+                    None => return Err(err),
+                },
+            }
+            attempt += 1;
+        }
+    }
+
     async fn list_models(&self) -> Result<Vec<String>, LlmError> {
         let mut all_models = Vec::new();
 
@@ -158,6 +362,22 @@ impl LlmProvider for FailoverProvider {
         all_models.dedup();
         Ok(all_models)
     }
+
+    async fn model_metadata(&self) -> Result<ModelMetadata, LlmError> {
+        // The primary provider's model sizes the context window.
+        self.providers[0].model_metadata().await
+    }
+
+    fn health(&self) -> Vec<ProviderHealthReport> {
+        self.providers
+            .iter()
+            .zip(&self.health)
+            .map(|(provider, health)| ProviderHealthReport {
+                model: provider.model_name().to_string(),
+                health: health.snapshot(),
+            })
+            .collect()
+    }
 }
 
 #[cfg(test)]
@@ -167,7 +387,10 @@ mod tests {
     use std::sync::Mutex;
     use std::time::Duration;
 
-    use crate::llm::provider::{CompletionResponse, FinishReason, ToolCompletionResponse};
+    use crate::llm::circuit::CircuitState;
+    use crate::llm::provider::{
+        CompletionResponse, FinishReason, StreamDelta, ToolCompletionResponse,
+    };
 
     /// A mock LLM provider that returns a predetermined result.
     struct MockProvider {
@@ -188,16 +411,24 @@ mod tests {
                     content: content.to_string(),
                     input_tokens: 10,
                     output_tokens: 5,
+                    cache_read_tokens: 0,
+                    cache_write_tokens: 0,
                     finish_reason: FinishReason::Stop,
                     response_id: None,
+                    provider: "mock".into(),
+                    model: name.to_string(),
                 }))),
                 tool_complete_result: Mutex::new(Some(Ok(ToolCompletionResponse {
                     content: Some(content.to_string()),
                     tool_calls: vec![],
                     input_tokens: 10,
                     output_tokens: 5,
+                    cache_read_tokens: 0,
+                    cache_write_tokens: 0,
                     finish_reason: FinishReason::Stop,
                     response_id: None,
+                    provider: "mock".into(),
+                    model: name.to_string(),
                 }))),
             }
         }
@@ -299,6 +530,135 @@ mod tests {
         }
     }
 
+    /// Streams a partial answer, then fails.
+    struct FailsMidStream;
+
+    #[async_trait]
+    impl LlmProvider for FailsMidStream {
+        fn model_name(&self) -> &str {
+            "mid-stream"
+        }
+
+        fn cost_per_token(&self) -> (Decimal, Decimal) {
+            (Decimal::ZERO, Decimal::ZERO)
+        }
+
+        async fn complete(
+            &self,
+            _request: CompletionRequest,
+        ) -> Result<CompletionResponse, LlmError> {
+            unimplemented!()
+        }
+
+        async fn complete_with_tools(
+            &self,
+            _request: ToolCompletionRequest,
+        ) -> Result<ToolCompletionResponse, LlmError> {
+            unimplemented!()
+        }
+
+        async fn stream_with_tools(
+            &self,
+            _request: ToolCompletionRequest,
+            deltas: StreamSender,
+        ) -> Result<ToolCompletionResponse, LlmError> {
+            let _ = deltas.send(StreamDelta::Text("Half an ans".into()));
+            Err(LlmError::RequestFailed {
+                provider: "mid-stream".into(),
+                reason: "connection reset".into(),
+            })
+        }
+    }
+
+    /// Replays a script of failures (`Some`) and successes (`None`), then
+    /// succeeds; counts calls.
+    struct ScriptedProvider {
+        name: String,
+        script: Mutex<std::collections::VecDeque<Option<LlmError>>>,
+        calls: AtomicUsize,
+    }
+
+    impl ScriptedProvider {
+        fn new(name: &str, script: Vec<Option<LlmError>>) -> Self {
+            Self {
+                name: name.to_string(),
+                script: Mutex::new(script.into()),
+                calls: AtomicUsize::new(0),
+            }
+        }
+
+        fn down(name: &str, failures: usize) -> Self {
+            let script = (0..failures)
+                .map(|_| {
+                    Some(LlmError::RequestFailed {
+                        provider: name.to_string(),
+                        reason: "503".to_string(),
+                    })
+                })
+                .collect();
+            Self::new(name, script)
+        }
+
+        fn calls(&self) -> usize {
+            self.calls.load(Ordering::Relaxed)
+        }
+    }
+
+    #[async_trait]
+    impl LlmProvider for ScriptedProvider {
+        fn model_name(&self) -> &str {
+            &self.name
+        }
+
+        fn cost_per_token(&self) -> (Decimal, Decimal) {
+            (Decimal::ZERO, Decimal::ZERO)
+        }
+
+        async fn complete(
+            &self,
+            _request: CompletionRequest,
+        ) -> Result<CompletionResponse, LlmError> {
+            self.calls.fetch_add(1, Ordering::Relaxed);
+            if let Some(Some(err)) = self.script.lock().unwrap().pop_front() {
+                return Err(err);
+            }
+            Ok(CompletionResponse {
+                content: self.name.clone(),
+                input_tokens: 10,
+                output_tokens: 5,
+                cache_read_tokens: 0,
+                cache_write_tokens: 0,
+                finish_reason: FinishReason::Stop,
+                response_id: None,
+                provider: "mock".into(),
+                model: self.name.clone(),
+            })
+        }
+
+        async fn complete_with_tools(
+            &self,
+            _request: ToolCompletionRequest,
+        ) -> Result<ToolCompletionResponse, LlmError> {
+            unimplemented!()
+        }
+    }
+
+    fn breaker(cooldown: Duration) -> BreakerConfig {
+        BreakerConfig {
+            consecutive_failures: 2,
+            cooldown,
+            ..BreakerConfig::default()
+        }
+    }
+
+    fn collect(mut rx: tokio::sync::mpsc::UnboundedReceiver<StreamDelta>) -> Vec<StreamDelta> {
+        let mut out = Vec::new();
+        while let Ok(delta) = rx.try_recv() {
+            out.push(delta);
+        }
+        out
+    }
+
     fn make_request() -> CompletionRequest {
         CompletionRequest::new(vec![crate::llm::ChatMessage::user("hello")])
     }
@@ -393,6 +753,47 @@ mod tests {
         assert_eq!(response.content.as_deref(), Some("tools fallback"));
     }
 
+    // Test: streaming fails over while nothing has been streamed yet.
+    #[tokio::test]
+    async fn stream_fails_over_before_first_delta() {
+        let primary = Arc::new(MockProvider::failing_retryable("primary"));
+        let fallback = Arc::new(MockProvider::succeeding("fallback", "streamed fallback"));
+
+        let failover = FailoverProvider::new(vec![primary, fallback]).unwrap();
+
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        let response = failover
+            .stream_with_tools(make_tool_request(), tx)
+            .await
+            .unwrap();
+        assert_eq!(response.content.as_deref(), Some("streamed fallback"));
+        assert_eq!(
+            collect(rx),
+            vec![StreamDelta::Text("streamed fallback".into())]
+        );
+        assert_eq!(failover.model_name(), "fallback");
+    }
+
+    // Test: once a delta has been streamed, errors propagate instead of failing over.
+    #[tokio::test]
+    async fn stream_does_not_fail_over_after_first_delta() {
+        let primary = Arc::new(FailsMidStream);
+        let fallback = Arc::new(MockProvider::succeeding("fallback", "other answer"));
+
+        let failover = FailoverProvider::new(vec![primary, fallback]).unwrap();
+
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        let err = failover
+            .stream_with_tools(make_tool_request(), tx)
+            .await
+            .unwrap_err();
+        match err {
+            LlmError::RequestFailed { provider, .. } => assert_eq!(provider, "mid-stream"),
+            other => panic!("expected RequestFailed, got: {other:?}"),
+        }
+        assert_eq!(collect(rx), vec![StreamDelta::Text("Half an ans".into())]);
+    }
+
     // Test: model_name and cost_per_token reflect the last-used provider.
     #[tokio::test]
     async fn model_name_and_cost_track_last_used_provider() {
@@ -413,11 +814,107 @@ mod tests {
         assert_eq!(failover.cost_per_token(), (Decimal::ZERO, Decimal::ZERO));
 
         // After failover, should reflect the fallback provider.
-        let _ = failover.complete(make_request()).await.unwrap();
+        let response = failover.complete(make_request()).await.unwrap();
+        assert_eq!(response.model, "fallback-model");
         assert_eq!(failover.model_name(), "fallback-model");
+        assert_eq!(failover.pricing_for("fallback-model").pricing.input, fallback_cost);
         assert_eq!(failover.cost_per_token(), (fallback_cost, fallback_cost));
     }
 
+    // Test: an open circuit skips the provider until its cooldown ends,
+    // then a half-open probe closes it again.
+    #[tokio::test]
+    async fn open_circuit_skips_provider_until_probe() {
+        let primary = Arc::new(ScriptedProvider::down("primary", 2));
+        let fallback = Arc::new(ScriptedProvider::new("fallback", vec![]));
+        let failover = FailoverProvider::new(vec![primary.clone(), fallback.clone()])
+            .unwrap()
+            .with_breaker(breaker(Duration::from_millis(50)));
+
+        for _ in 0..4 {
+            let response = failover.complete(make_request()).await.unwrap();
+            assert_eq!(response.content, "fallback");
+        }
+        // Two failures opened the circuit; later calls skipped the primary
+        assert_eq!(primary.calls(), 2);
+        let health = failover.health();
+        assert_eq!(health[0].model, "primary");
+        assert_eq!(health[0].health.state, CircuitState::Open);
+        assert_eq!(health[1].health.state, CircuitState::Closed);
+        assert_eq!(health[1].health.calls, 4);
+
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        let response = failover.complete(make_request()).await.unwrap();
+        assert_eq!(response.content, "primary");
+        assert_eq!(failover.health()[0].health.state, CircuitState::Closed);
+    }
+
+    // Test: once every circuit is open, a chain retry waits out the
+    // cooldown instead of giving up on it.
+    #[tokio::test]
+    async fn all_circuits_open_waits_out_the_cooldown() {
+        let primary = Arc::new(ScriptedProvider::down("primary", 10));
+        let failover = FailoverProvider::new(vec![primary.clone() as Arc<dyn LlmProvider>])
+            .unwrap()
+            .with_breaker(breaker(Duration::from_secs(60)))
+            .with_retries(1);
+
+        // Fails, backs off and retries; the second failure opens the circuit
+        let err = failover.complete(make_request()).await.unwrap_err();
+        assert!(matches!(err, LlmError::RequestFailed { .. }), "got {err:?}");
+        assert_eq!(primary.calls(), 2);
+
+        let open = failover.circuit_open();
+        match &open {
+            LlmError::CircuitOpen { provider, retry_after } => {
+                assert_eq!(provider, "primary");
+                assert!(*retry_after > Duration::from_secs(50));
+            }
+            other => panic!("expected CircuitOpen, got: {other:?}"),
+        }
+        let delay = failover.retry_delay(0, &open).expect("waits for the probe");
+        assert!(delay > Duration::from_secs(50));
+        assert_eq!(failover.retry_delay(1, &open), None);
+    }
+
+    // Test: the chain is retried after a rate limit once the wait is over.
+    #[tokio::test]
+    async fn chain_retried_after_rate_limit() {
+        let primary = Arc::new(ScriptedProvider::new(
+            "primary",
+            vec![Some(LlmError::RateLimited {
+                provider: "primary".into(),
+                retry_after: Some(Duration::from_millis(10)),
+            })],
+        ));
+        let failover = FailoverProvider::new(vec![primary.clone() as Arc<dyn LlmProvider>])
+            .unwrap()
+            .with_retries(2);
+
+        let response = failover.complete(make_request()).await.unwrap();
+        assert_eq!(response.content, "primary");
+        assert_eq!(primary.calls(), 2);
+        let health = &failover.health()[0].health;
+        assert_eq!(health.calls, 2);
+        assert_eq!(health.error_rate, 0.5);
+        assert!(health.p95_latency_ms.is_some());
+    }
+
+    // Test: non-retryable errors don't count against a provider's health.
+    #[tokio::test]
+    async fn non_retryable_errors_not_recorded() {
+        let primary = Arc::new(ScriptedProvider::new(
+            "primary",
+            vec![Some(LlmError::ContextLengthExceeded {
+                used: 10,
+                limit: 5,
+            })],
+        ));
+        let failover = FailoverProvider::new(vec![primary as Arc<dyn LlmProvider>]).unwrap();
+        assert!(failover.complete(make_request()).await.is_err());
+        assert_eq!(failover.health()[0].health.calls, 0);
+    }
+
     // Test: list_models aggregates from all providers.
     #[tokio::test]
     async fn list_models_aggregates_all() {
@@ -459,6 +956,10 @@ mod tests {
             provider: "p".into(),
             model: "m".into(),
         }));
+        assert!(is_retryable(&LlmError::CircuitOpen {
+            provider: "p".into(),
+            retry_after: Duration::from_secs(1),
+        }));
 
         // Non-retryable
         assert!(!is_retryable(&LlmError::AuthFailed {
@@ -0,0 +1,288 @@
+//! Local / self-hosted LLM backends: Ollama and OpenAI-compatible servers
+//! (llama.cpp server, vLLM, LM Studio).
+//!
+//! Completions (including tool calls) go through rig-core — Ollama's native
+//! chat API, or the Chat Completions API at a custom base URL. `list_models`
+//! and `model_metadata` query the server's own model endpoints so the agent
+//! can size its context window to the model actually loaded. Local inference
+//! is free, so costs are always zero.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use rust_decimal::Decimal;
+
+use crate::error::LlmError;
+use crate::llm::costs::{ModelPricing, PriceQuote, PriceSource};
+use crate::llm::provider::{
+    CompletionRequest, CompletionResponse, LlmProvider, ModelMetadata, StreamSender,
+    ToolCompletionRequest, ToolCompletionResponse,
+};
+
+/// Default Ollama server URL.
+pub const OLLAMA_DEFAULT_BASE_URL: &str = "http://localhost:11434";
+
+/// Default OpenAI-compatible server URL (llama.cpp server / vLLM).
+pub const OPENAI_COMPATIBLE_DEFAULT_BASE_URL: &str = "http://localhost:8000/v1";
+
+/// Which model-listing API the server speaks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LocalFlavor {
+    /// `GET /api/tags`, `POST /api/show`.
+    Ollama,
+    /// `GET {base}/models`.
+    OpenAiCompatible,
+}
+
+impl LocalFlavor {
+    fn provider_name(&self) -> &'static str {
+        match self {
+            Self::Ollama => "ollama",
+            Self::OpenAiCompatible => "openai_compatible",
+        }
+    }
+}
+
+/// A completion provider backed by a local server, with model discovery.
+pub struct LocalProvider {
+    inner: Arc<dyn LlmProvider>,
+    flavor: LocalFlavor,
+    base_url: String,
+    api_key: Option<String>,
+    http: reqwest::Client,
+}
+
+impl LocalProvider {
+    /// Wrap a completion provider talking to the server at `base_url`.
+    pub fn new(
+        inner: Arc<dyn LlmProvider>,
+        flavor: LocalFlavor,
+        base_url: impl Into<String>,
+        api_key: Option<String>,
+    ) -> Self {
+        Self {
+            inner,
+            flavor,
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            api_key,
+            http: reqwest::Client::new(),
+        }
+    }
+
+    fn error(&self, reason: impl std::fmt::Display) -> LlmError {
+        LlmError::RequestFailed {
+            provider: self.flavor.provider_name().to_string(),
+            reason: reason.to_string(),
+        }
+    }
+
+    /// Send a request and decode the JSON body.
+    async fn fetch_json(&self, request: reqwest::RequestBuilder) -> Result<serde_json::Value, LlmError> {
+        let request = match &self.api_key {
+            Some(key) => request.bearer_auth(key),
+            None => request,
+        };
+        let response = request.send().await.map_err(|e| self.error(e))?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(self.error(format!("HTTP {status}: {body}")));
+        }
+        response.json().await.map_err(|e| LlmError::InvalidResponse {
+            provider: self.flavor.provider_name().to_string(),
+            reason: e.to_string(),
+        })
+    }
+
+    async fn openai_models(&self) -> Result<Vec<(String, Option<u32>)>, LlmError> {
+        let body = self
+            .fetch_json(self.http.get(format!("{}/models", self.base_url)))
+            .await?;
+        Ok(parse_openai_models(&body))
+    }
+}
+
+#[async_trait]
+impl LlmProvider for LocalProvider {
+    fn model_name(&self) -> &str {
+        self.inner.model_name()
+    }
+
+    fn cost_per_token(&self) -> (Decimal, Decimal) {
+        (Decimal::ZERO, Decimal::ZERO)
+    }
+
+    fn pricing(&self) -> PriceQuote {
+        PriceQuote {
+            pricing: ModelPricing::flat(self.cost_per_token()),
+            source: PriceSource::Local,
+        }
+    }
+
+    async fn complete(&self, request: CompletionRequest) -> Result<CompletionResponse, LlmError> {
+        self.inner.complete(request).await
+    }
+
+    async fn complete_with_tools(
+        &self,
+        request: ToolCompletionRequest,
+    ) -> Result<ToolCompletionResponse, LlmError> {
+        self.inner.complete_with_tools(request).await
+    }
+
+    async fn stream_with_tools(
+        &self,
+        request: ToolCompletionRequest,
+        deltas: StreamSender,
+    ) -> Result<ToolCompletionResponse, LlmError> {
+        self.inner.stream_with_tools(request, deltas).await
+    }
+
+    async fn list_models(&self) -> Result<Vec<String>, LlmError> {
+        match self.flavor {
+            LocalFlavor::Ollama => {
+                let body = self
+                    .fetch_json(self.http.get(format!("{}/api/tags", self.base_url)))
+                    .await?;
+                Ok(parse_ollama_tags(&body))
+            }
+            LocalFlavor::OpenAiCompatible => {
+                Ok(self.openai_models().await?.into_iter().map(|(id, _)| id).collect())
+            }
+        }
+    }
+
+    async fn model_metadata(&self) -> Result<ModelMetadata, LlmError> {
+        let id = self.model_name().to_string();
+        let context_length = match self.flavor {
+            LocalFlavor::Ollama => {
+                let body = self
+                    .fetch_json(
+                        self.http
+                            .post(format!("{}/api/show", self.base_url))
+                            .json(&serde_json::json!({ "model": id })),
+                    )
+                    .await?;
+                parse_ollama_context_length(&body)
+            }
+            LocalFlavor::OpenAiCompatible => self
+                .openai_models()
+                .await?
+                .into_iter()
+                .find(|(model, _)| *model == id)
+                .and_then(|(_, ctx)| ctx),
+        };
+        Ok(ModelMetadata { id, context_length })
+    }
+
+    fn active_model_name(&self) -> String {
+        self.inner.active_model_name()
+    }
+
+    fn set_model(&self, model: &str) -> Result<(), LlmError> {
+        self.inner.set_model(model)
+    }
+}
+
+/// Model names from Ollama's `GET /api/tags`.
+fn parse_ollama_tags(body: &serde_json::Value) -> Vec<String> {
+    body["models"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|m| m["name"].as_str().or_else(|| m["model"].as_str()))
+        .map(String::from)
+        .collect()
+}
+
+/// Context length from Ollama's `POST /api/show`.
+///
+/// An explicit `num_ctx` parameter (what the server actually allocates) wins
+/// over the architecture's trained `<arch>.context_length`.
+fn parse_ollama_context_length(body: &serde_json::Value) -> Option<u32> {
+    let num_ctx = body["parameters"].as_str().and_then(|params| {
+        params.lines().find_map(|line| {
+            let mut parts = line.split_whitespace();
+            (parts.next() == Some("num_ctx"))
+                .then(|| parts.next()?.parse().ok())
+                .flatten()
+        })
+    });
+    num_ctx.or_else(|| {
+        body["model_info"].as_object()?.iter().find_map(|(key, value)| {
+            key.ends_with(".context_length")
+                .then(|| value.as_u64())
+                .flatten()
+                .map(|n| n.min(u32::MAX as u64) as u32)
+        })
+    })
+}
+
+/// Model IDs and context lengths from an OpenAI-compatible `GET /models`.
+///
+/// The context length is non-standard: vLLM reports `max_model_len`,
+/// llama.cpp `meta.n_ctx_train`, LM Studio / OpenRouter `context_length` or
+/// `max_context_length`.
+fn parse_openai_models(body: &serde_json::Value) -> Vec<(String, Option<u32>)> {
+    body["data"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|m| {
+            let id = m["id"].as_str()?.to_string();
+            let context = [
+                &m["max_model_len"],
+                &m["context_length"],
+                &m["max_context_length"],
+                &m["meta"]["n_ctx"],
+                &m["meta"]["n_ctx_train"],
+            ]
+            .into_iter()
+            .find_map(|v| v.as_u64())
+            .map(|n| n.min(u32::MAX as u64) as u32);
+            Some((id, context))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn ollama_tags_lists_model_names() {
+        let body = json!({"models": [{"name": "llama3.1:8b"}, {"model": "qwen2.5:7b"}]});
+        assert_eq!(parse_ollama_tags(&body), vec!["llama3.1:8b", "qwen2.5:7b"]);
+        assert!(parse_ollama_tags(&json!({})).is_empty());
+    }
+
+    #[test]
+    fn ollama_context_prefers_num_ctx() {
+        let body = json!({
+            "parameters": "stop \"<|eot_id|>\"\nnum_ctx 16384",
+            "model_info": {"general.architecture": "llama", "llama.context_length": 131072}
+        });
+        assert_eq!(parse_ollama_context_length(&body), Some(16384));
+
+        let body = json!({"model_info": {"qwen2.context_length": 32768}});
+        assert_eq!(parse_ollama_context_length(&body), Some(32768));
+        assert_eq!(parse_ollama_context_length(&json!({})), None);
+    }
+
+    #[test]
+    fn openai_models_read_server_specific_context_fields() {
+        let body = json!({"data": [
+            {"id": "Qwen/Qwen2.5-7B-Instruct", "max_model_len": 32768},
+            {"id": "llama.gguf", "meta": {"n_ctx_train": 8192}},
+            {"id": "lmstudio-model", "max_context_length": 4096},
+            {"id": "bare"}
+        ]});
+        let models = parse_openai_models(&body);
+        assert_eq!(models.len(), 4);
+        assert_eq!(models[0], ("Qwen/Qwen2.5-7B-Instruct".into(), Some(32768)));
+        assert_eq!(models[1].1, Some(8192));
+        assert_eq!(models[2].1, Some(4096));
+        assert_eq!(models[3].1, None);
+    }
+}
@@ -3,24 +3,34 @@
 //! Supports:
 //! - **Anthropic**: Direct API access via rig-core
 //! - **OpenAI**: Direct API access via rig-core
+//! - **Ollama**: Local server via rig-core, model discovery via `/api/tags`
+//! - **OpenAI-compatible**: Any Chat Completions server (llama.cpp, vLLM,
+//!   LM Studio) at a configurable base URL
 //!
 //! Uses the rig-core crate for HTTP transport and the `RigAdapter` to bridge
 //! rig's `CompletionModel` trait to our `LlmProvider` trait.
 
-mod costs;
+pub mod costs;
+pub mod budget;
+pub mod circuit;
 pub mod failover;
+pub mod local;
 pub mod provider;
 pub mod reasoning;
 pub(crate) mod retry;
 mod rig_adapter;
+pub mod router;
+pub mod routes;
 
 pub use failover::FailoverProvider;
+pub use local::{LocalFlavor, LocalProvider};
 pub use provider::*;
 pub use reasoning::{
-    ActionPlan, Reasoning, ReasoningContext, RespondOutput, RespondResult, TokenUsage,
-    ToolSelection,
+    ActionPlan, Reasoning, ReasoningContext, RespondOutput, RespondResult, StreamTagStripper,
+    TokenUsage, ToolSelection,
 };
 pub use rig_adapter::RigAdapter;
+pub use router::{LlmRole, LlmRouter};
 
 use std::sync::Arc;
 
@@ -34,14 +44,52 @@ use crate::error::LlmError;
 pub enum LlmBackend {
     Anthropic,
     OpenAi,
+    /// Local Ollama server.
+    Ollama,
+    /// Any OpenAI Chat Completions-compatible server.
+    OpenAiCompatible,
+}
+
+impl LlmBackend {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Anthropic => "anthropic",
+            Self::OpenAi => "openai",
+            Self::Ollama => "ollama",
+            Self::OpenAiCompatible => "openai_compatible",
+        }
+    }
+
+    /// Whether the backend needs an API key.
+    pub fn requires_api_key(&self) -> bool {
+        matches!(self, Self::Anthropic | Self::OpenAi)
+    }
+}
+
+impl std::str::FromStr for LlmBackend {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "anthropic" => Ok(Self::Anthropic),
+            "openai" => Ok(Self::OpenAi),
+            "ollama" => Ok(Self::Ollama),
+            "openai_compatible" | "openai-compatible" | "local" => Ok(Self::OpenAiCompatible),
+            _ => Err(format!(
+                "Unknown LLM backend: {s} (expected anthropic, openai, ollama or openai_compatible)"
+            )),
+        }
+    }
 }
 
 /// Configuration for creating an LLM provider.
 #[derive(Debug, Clone)]
 pub struct LlmConfig {
     pub backend: LlmBackend,
+    /// API key; may be empty for local backends.
     pub api_key: secrecy::SecretString,
     pub model: String,
+    /// Server URL for local backends (defaults to the usual localhost port).
+    pub base_url: Option<String>,
 }
 
 /// Create an LLM provider from configuration.
@@ -49,6 +97,8 @@ pub fn create_provider(config: &LlmConfig) -> Result<Arc<dyn LlmProvider>, LlmEr
     match config.backend {
         LlmBackend::Anthropic => create_anthropic_provider(config),
         LlmBackend::OpenAi => create_openai_provider(config),
+        LlmBackend::Ollama => create_ollama_provider(config),
+        LlmBackend::OpenAiCompatible => create_openai_compatible_provider(config),
     }
 }
 
@@ -65,7 +115,7 @@ fn create_anthropic_provider(config: &LlmConfig) -> Result<Arc<dyn LlmProvider>,
 
     let model = client.completion_model(&config.model);
     tracing::info!("Using Anthropic (model: {})", config.model);
-    Ok(Arc::new(RigAdapter::new(model, &config.model)))
+    Ok(Arc::new(RigAdapter::new(model, config.backend.as_str(), &config.model)))
 }
 
 fn create_openai_provider(config: &LlmConfig) -> Result<Arc<dyn LlmProvider>, LlmError> {
@@ -81,12 +131,64 @@ fn create_openai_provider(config: &LlmConfig) -> Result<Arc<dyn LlmProvider>, Ll
 
     let model = client.completion_model(&config.model);
     tracing::info!("Using OpenAI (model: {})", config.model);
-    Ok(Arc::new(RigAdapter::new(model, &config.model)))
+    Ok(Arc::new(RigAdapter::new(model, config.backend.as_str(), &config.model)))
+}
+
+fn create_ollama_provider(config: &LlmConfig) -> Result<Arc<dyn LlmProvider>, LlmError> {
+    use rig::providers::ollama;
+
+    let base_url = config
+        .base_url
+        .clone()
+        .unwrap_or_else(|| local::OLLAMA_DEFAULT_BASE_URL.to_string());
+    let client: ollama::Client = ollama::Client::builder()
+        .api_key(rig::client::Nothing)
+        .base_url(&base_url)
+        .build()
+        .map_err(|e| LlmError::RequestFailed {
+            provider: "ollama".to_string(),
+            reason: format!("Failed to create Ollama client: {}", e),
+        })?;
+
+    let model = client.completion_model(&config.model);
+    tracing::info!("Using Ollama at {} (model: {})", base_url, config.model);
+    let adapter: Arc<dyn LlmProvider> = Arc::new(RigAdapter::new(model, config.backend.as_str(), &config.model));
+    Ok(Arc::new(LocalProvider::new(adapter, LocalFlavor::Ollama, base_url, None)))
+}
+
+fn create_openai_compatible_provider(config: &LlmConfig) -> Result<Arc<dyn LlmProvider>, LlmError> {
+    use rig::providers::openai;
+
+    let base_url = config
+        .base_url
+        .clone()
+        .unwrap_or_else(|| local::OPENAI_COMPATIBLE_DEFAULT_BASE_URL.to_string());
+    let api_key = Some(config.api_key.expose_secret().to_string()).filter(|k| !k.is_empty());
+    // Most local servers ignore the key, but the client requires one.
+    let client: openai::CompletionsClient = openai::CompletionsClient::builder()
+        .api_key(api_key.as_deref().unwrap_or("local"))
+        .base_url(&base_url)
+        .build()
+        .map_err(|e| LlmError::RequestFailed {
+            provider: "openai_compatible".to_string(),
+            reason: format!("Failed to create OpenAI-compatible client: {}", e),
+        })?;
+
+    let model = client.completion_model(&config.model);
+    tracing::info!("Using OpenAI-compatible server at {} (model: {})", base_url, config.model);
+    let adapter: Arc<dyn LlmProvider> = Arc::new(RigAdapter::new(model, config.backend.as_str(), &config.model));
+    Ok(Arc::new(LocalProvider::new(
+        adapter,
+        LocalFlavor::OpenAiCompatible,
+        base_url,
+        api_key,
+    )))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rust_decimal::Decimal;
 
     #[test]
     fn test_create_provider_missing_key_still_constructs() {
@@ -96,6 +198,7 @@ mod tests {
             backend: LlmBackend::Anthropic,
             api_key: secrecy::SecretString::from("test-key"),
             model: "claude-3-5-sonnet-latest".to_string(),
+            base_url: None,
         };
         let provider = create_provider(&config);
         assert!(provider.is_ok());
@@ -108,9 +211,34 @@ mod tests {
             backend: LlmBackend::OpenAi,
             api_key: secrecy::SecretString::from("sk-test"),
             model: "gpt-4o".to_string(),
+            base_url: None,
         };
         let provider = create_provider(&config);
         assert!(provider.is_ok());
         assert_eq!(provider.unwrap().model_name(), "gpt-4o");
     }
+
+    #[test]
+    fn test_create_local_providers_are_free() {
+        for backend in [LlmBackend::Ollama, LlmBackend::OpenAiCompatible] {
+            let config = LlmConfig {
+                backend,
+                api_key: secrecy::SecretString::from(""),
+                model: "my-finetune-v2".to_string(),
+                base_url: Some("http://127.0.0.1:9".to_string()),
+            };
+            let provider = create_provider(&config).unwrap();
+            assert_eq!(provider.model_name(), "my-finetune-v2");
+            assert_eq!(provider.cost_per_token(), (Decimal::ZERO, Decimal::ZERO));
+        }
+    }
+
+    #[test]
+    fn test_backend_from_str() {
+        assert_eq!("ollama".parse::<LlmBackend>().unwrap(), LlmBackend::Ollama);
+        assert_eq!("OpenAI".parse::<LlmBackend>().unwrap(), LlmBackend::OpenAi);
+        assert_eq!("openai-compatible".parse::<LlmBackend>().unwrap(), LlmBackend::OpenAiCompatible);
+        assert!("bard".parse::<LlmBackend>().is_err());
+        assert!(!LlmBackend::Ollama.requires_api_key());
+    }
 }
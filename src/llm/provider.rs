@@ -5,6 +5,8 @@ use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 
 use crate::error::LlmError;
+use crate::llm::circuit::ProviderHealthReport;
+use crate::llm::costs::{ModelPricing, PriceQuote, PriceSource, UsageCounts};
 
 /// Role in a conversation.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -140,9 +142,31 @@ pub struct CompletionResponse {
     pub content: String,
     pub input_tokens: u32,
     pub output_tokens: u32,
+    /// Input tokens served from the prompt cache (included in `input_tokens`).
+    pub cache_read_tokens: u32,
+    /// Input tokens written to the prompt cache (included in `input_tokens`).
+    pub cache_write_tokens: u32,
     pub finish_reason: FinishReason,
     /// Provider-specific response ID.
     pub response_id: Option<String>,
+    /// Backend that served the call (`anthropic`, `ollama`, ...). Behind a
+    /// failover chain this is the link that answered.
+    pub provider: String,
+    /// Model that served the call.
+    pub model: String,
+}
+
+impl CompletionResponse {
+    /// Token counts for cost accounting.
+    pub fn usage(&self) -> UsageCounts {
+        UsageCounts {
+            input_tokens: self.input_tokens,
+            output_tokens: self.output_tokens,
+            cache_read_tokens: self.cache_read_tokens,
+            cache_write_tokens: self.cache_write_tokens,
+            batch: false,
+        }
+    }
 }
 
 /// Why the completion finished.
@@ -234,11 +258,50 @@ pub struct ToolCompletionResponse {
     pub tool_calls: Vec<ToolCall>,
     pub input_tokens: u32,
     pub output_tokens: u32,
+    /// Input tokens served from the prompt cache (included in `input_tokens`).
+    pub cache_read_tokens: u32,
+    /// Input tokens written to the prompt cache (included in `input_tokens`).
+    pub cache_write_tokens: u32,
     pub finish_reason: FinishReason,
     /// Provider-specific response ID.
     pub response_id: Option<String>,
+    /// Backend that served the call (`anthropic`, `ollama`, ...). Behind a
+    /// failover chain this is the link that answered.
+    pub provider: String,
+    /// Model that served the call.
+    pub model: String,
+}
+
+impl ToolCompletionResponse {
+    /// Token counts for cost accounting.
+    pub fn usage(&self) -> UsageCounts {
+        UsageCounts {
+            input_tokens: self.input_tokens,
+            output_tokens: self.output_tokens,
+            cache_read_tokens: self.cache_read_tokens,
+            cache_write_tokens: self.cache_write_tokens,
+            batch: false,
+        }
+    }
+}
+
+/// An incremental piece of a streamed completion.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StreamDelta {
+    /// Text tokens, in order.
+    Text(String),
+    /// A tool-call fragment. `name` arrives with the first fragment of a call;
+    /// `arguments` fragments concatenate to the call's JSON arguments.
+    ToolCall {
+        id: String,
+        name: Option<String>,
+        arguments: String,
+    },
 }
 
+/// Receives deltas as a completion streams in.
+pub type StreamSender = tokio::sync::mpsc::UnboundedSender<StreamDelta>;
+
 /// Metadata about a model returned by the provider's API.
 #[derive(Debug, Clone)]
 pub struct ModelMetadata {
@@ -265,6 +328,31 @@ pub trait LlmProvider: Send + Sync {
         request: ToolCompletionRequest,
     ) -> Result<ToolCompletionResponse, LlmError>;
 
+    /// Complete with tool use, sending text and tool-call deltas to `deltas`
+    /// as they arrive. Returns the same aggregated response as
+    /// `complete_with_tools`.
+    ///
+    /// The default implementation doesn't stream: it sends the finished
+    /// response as a single batch of deltas.
+    async fn stream_with_tools(
+        &self,
+        request: ToolCompletionRequest,
+        deltas: StreamSender,
+    ) -> Result<ToolCompletionResponse, LlmError> {
+        let response = self.complete_with_tools(request).await?;
+        if let Some(text) = response.content.as_ref().filter(|t| !t.is_empty()) {
+            let _ = deltas.send(StreamDelta::Text(text.clone()));
+        }
+        for call in &response.tool_calls {
+            let _ = deltas.send(StreamDelta::ToolCall {
+                id: call.id.clone(),
+                name: Some(call.name.clone()),
+                arguments: call.arguments.to_string(),
+            });
+        }
+        Ok(response)
+    }
+
     /// List available models from the provider.
     async fn list_models(&self) -> Result<Vec<String>, LlmError> {
         Ok(Vec::new())
@@ -299,9 +387,30 @@ pub trait LlmProvider: Send + Sync {
         None
     }
 
+    /// Full pricing for the current model (cache and batch rates) and where
+    /// it came from. Defaults to flat `cost_per_token` pricing.
+    fn pricing(&self) -> PriceQuote {
+        PriceQuote {
+            pricing: ModelPricing::flat(self.cost_per_token()),
+            source: PriceSource::Provider,
+        }
+    }
+
+    /// Pricing for `model` when it served a call through this provider.
+    /// Wrappers over several models pick the matching one; defaults to
+    /// [`pricing`](Self::pricing).
+    fn pricing_for(&self, _model: &str) -> PriceQuote {
+        self.pricing()
+    }
+
     /// Calculate cost for a completion.
-    fn calculate_cost(&self, input_tokens: u32, output_tokens: u32) -> Decimal {
-        let (input_cost, output_cost) = self.cost_per_token();
-        input_cost * Decimal::from(input_tokens) + output_cost * Decimal::from(output_tokens)
+    fn calculate_cost(&self, usage: &UsageCounts) -> Decimal {
+        self.pricing().pricing.cost(usage)
+    }
+
+    /// Circuit-breaker health of the providers behind this one; empty when
+    /// it isn't tracked.
+    fn health(&self) -> Vec<ProviderHealthReport> {
+        Vec::new()
     }
 }
@@ -7,7 +7,8 @@ use serde::{Deserialize, Serialize};
 use crate::error::LlmError;
 
 use crate::llm::{
-    ChatMessage, CompletionRequest, LlmProvider, ToolCall, ToolCompletionRequest, ToolDefinition,
+    ChatMessage, CompletionRequest, LlmProvider, StreamSender, ToolCall, ToolCompletionRequest,
+    ToolDefinition,
 };
 use crate::safety::SafetyLayer;
 
@@ -334,6 +335,28 @@ Respond in JSON format:
     pub async fn respond_with_tools(
         &self,
         context: &ReasoningContext,
+    ) -> Result<RespondOutput, LlmError> {
+        self.respond_inner(context, None).await
+    }
+
+    /// Like `respond_with_tools()`, but streams the model's raw text and
+    /// tool-call deltas to `deltas` while the response is generated.
+    ///
+    /// The returned output is post-processed exactly as in
+    /// `respond_with_tools()`, so it may differ from the concatenated deltas
+    /// (e.g. stripped reasoning tags). The sender is dropped on return.
+    pub async fn respond_with_tools_streaming(
+        &self,
+        context: &ReasoningContext,
+        deltas: StreamSender,
+    ) -> Result<RespondOutput, LlmError> {
+        self.respond_inner(context, Some(deltas)).await
+    }
+
+    async fn respond_inner(
+        &self,
+        context: &ReasoningContext,
+        deltas: Option<StreamSender>,
     ) -> Result<RespondOutput, LlmError> {
         let system_prompt = self.build_conversation_prompt(context);
 
@@ -348,7 +371,10 @@ Respond in JSON format:
                 .with_tool_choice("auto");
             request.metadata = context.metadata.clone();
 
-            let response = self.llm.complete_with_tools(request).await?;
+            let response = match deltas {
+                Some(deltas) => self.llm.stream_with_tools(request, deltas).await?,
+                None => self.llm.complete_with_tools(request).await?,
+            };
             let usage = TokenUsage {
                 input_tokens: response.input_tokens,
                 output_tokens: response.output_tokens,
@@ -658,6 +684,113 @@ fn strip_internal_tags(text: &str) -> String {
     result.trim().to_string()
 }
 
+/// Incremental [`strip_internal_tags`] for streamed text deltas.
+///
+/// Tags can arrive split across deltas (`"<thin"` + `"king>"`), so text
+/// that might be the start of a tag is held back until it is decided.
+/// Content of an unclosed block is never emitted.
+#[derive(Debug, Default)]
+pub struct StreamTagStripper {
+    /// Undecided text: a possible tag start, or the tail of a hidden block.
+    pending: String,
+    /// Closing tag of the block currently being hidden.
+    inside: Option<String>,
+}
+
+impl StreamTagStripper {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed one delta; returns the text that is safe to show.
+    pub fn push(&mut self, delta: &str) -> String {
+        self.pending.push_str(delta);
+        let mut out = String::new();
+        loop {
+            if let Some(close) = &self.inside {
+                match self.pending.find(close.as_str()) {
+                    Some(pos) => {
+                        self.pending.drain(..pos + close.len());
+                        self.inside = None;
+                    }
+                    None => {
+                        // Keep just enough to recognise a split closing tag
+                        let mut keep = self.pending.len().saturating_sub(close.len() - 1);
+                        while !self.pending.is_char_boundary(keep) {
+                            keep += 1;
+                        }
+                        self.pending.drain(..keep);
+                        return out;
+                    }
+                }
+                continue;
+            }
+
+            let Some(lt) = self.pending.find('<') else {
+                out.push_str(&self.pending);
+                self.pending.clear();
+                return out;
+            };
+            out.push_str(&self.pending[..lt]);
+            self.pending.drain(..lt);
+            match match_open_tag(&self.pending) {
+                TagMatch::Open { len, close } => {
+                    self.pending.drain(..len);
+                    self.inside = Some(close);
+                }
+                TagMatch::Partial => return out,
+                TagMatch::No => {
+                    out.push('<');
+                    self.pending.drain(..1);
+                }
+            }
+        }
+    }
+
+    /// End of stream: release held-back text that turned out not to be a tag.
+    pub fn finish(&mut self) -> String {
+        let rest = std::mem::take(&mut self.pending);
+        if self.inside.take().is_some() { String::new() } else { rest }
+    }
+}
+
+enum TagMatch {
+    /// A complete opening tag of `len` bytes, closed by `close`.
+    Open { len: usize, close: String },
+    /// Could still become an opening tag.
+    Partial,
+    No,
+}
+
+/// Classify `text` (starting at `<`) against the internal opening tags.
+fn match_open_tag(text: &str) -> TagMatch {
+    let mut partial = false;
+    for tag in INTERNAL_TAGS {
+        let forms = [
+            (format!("<{tag}>"), format!("</{tag}>")),
+            (format!("<|{tag}|>"), format!("<|/{tag}|>")),
+        ];
+        for (open, close) in forms {
+            if text.starts_with(&open) {
+                return TagMatch::Open { len: open.len(), close };
+            }
+            partial |= open.starts_with(text);
+        }
+        // `<tag attr="...">`
+        let prefix = format!("<{tag} ");
+        if text.starts_with(&prefix) {
+            return match text.find('>') {
+                Some(end) => TagMatch::Open {
+                    len: end + 1,
+                    close: format!("</{tag}>"),
+                },
+                None => TagMatch::Partial,
+            };
+        }
+    }
+    if partial { TagMatch::Partial } else { TagMatch::No }
+}
+
 /// Strip `<tag>...</tag>` and `<tag ...>...</tag>` blocks from text.
 fn strip_xml_tag(text: &str, tag: &str) -> String {
     let open_exact = format!("<{}>", tag);
@@ -838,6 +971,33 @@ Here is my response to your question."#;
         assert_eq!(output, "Hello");
     }
 
+    fn stream(chunks: &[&str]) -> String {
+        let mut stripper = StreamTagStripper::new();
+        let mut out: String = chunks.iter().map(|c| stripper.push(c)).collect();
+        out.push_str(&stripper.finish());
+        out
+    }
+
+    #[test]
+    fn test_stream_stripper_handles_split_tags() {
+        assert_eq!(
+            stream(&["Hi <thin", "king>secret plan</thi", "nking> there"]),
+            "Hi  there"
+        );
+        assert_eq!(stream(&["<", "thinking>", "x", "<", "/", "thinking>ok"]), "ok");
+        assert_eq!(stream(&["a <|thinking|>no<|/thinking|>b"]), "a b");
+        assert_eq!(stream(&["<thinking reason=\"x\">no</thinking>yes"]), "yes");
+    }
+
+    #[test]
+    fn test_stream_stripper_passes_other_angle_brackets() {
+        assert_eq!(stream(&["if a <", " b then <b>bold</b>"]), "if a < b then <b>bold</b>");
+        // Held back as a possible tag, released at the end
+        assert_eq!(stream(&["x <thi"]), "x <thi");
+        // Never shows an unclosed block, even multi-byte content
+        assert_eq!(stream(&["ok <thinking>é", "é"]), "ok ");
+    }
+
     #[test]
     fn test_strip_tool_call_tags() {
         // GLM-4.7 emits this garbage instead of using the tool_calls array
@@ -1,12 +1,20 @@
 //! Shared retry helpers for LLM providers.
 //!
 //! Provides exponential backoff with jitter and retryable status classification
-//! used by LLM provider implementations.
+//! used by LLM provider implementations, and the retry decision that keeps
+//! backoff from retrying into an open circuit breaker.
 
 use std::time::Duration;
 
 use rand::Rng;
 
+use crate::error::LlmError;
+
+/// Longest we'll sleep before a retry, unless a circuit breaker's cooldown
+/// is longer. Rate limits that last longer fail the call instead of
+/// stalling it.
+pub(crate) const MAX_RETRY_DELAY: Duration = Duration::from_secs(10);
+
 /// Returns `true` if the HTTP status code is transient and worth retrying.
 pub(crate) fn is_retryable_status(status: u16) -> bool {
     matches!(status, 429 | 500 | 502 | 503 | 504)
@@ -31,6 +39,36 @@ pub(crate) fn retry_backoff_delay(attempt: u32) -> Duration {
     Duration::from_millis(delay_ms)
 }
 
+/// How long to wait before retry `attempt` (0-based) of a failed call, or
+/// `None` to give up.
+///
+/// Honours a rate limit's `retry_after`, otherwise backs off exponentially.
+/// `breaker_wait` is how long until a circuit breaker admits calls again;
+/// the delay is stretched to cover it so the retry doesn't land on an open
+/// circuit. The call gives up if the delay would exceed `max_delay`, which
+/// callers with breakers set to at least the cooldown.
+pub(crate) fn next_retry_delay(
+    attempt: u32,
+    max_retries: u32,
+    err: &LlmError,
+    breaker_wait: Option<Duration>,
+    max_delay: Duration,
+) -> Option<Duration> {
+    if attempt >= max_retries {
+        return None;
+    }
+    let delay = match err {
+        LlmError::RateLimited {
+            retry_after: Some(after),
+            ..
+        } => *after,
+        LlmError::CircuitOpen { retry_after, .. } => *retry_after,
+        _ => retry_backoff_delay(attempt),
+    };
+    let delay = delay.max(breaker_wait.unwrap_or(Duration::ZERO));
+    (delay <= max_delay).then_some(delay)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -87,6 +125,44 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_next_retry_delay() {
+        let failed = LlmError::RequestFailed {
+            provider: "p".into(),
+            reason: "err".into(),
+        };
+        let cap = MAX_RETRY_DELAY;
+        // Out of retries
+        assert_eq!(next_retry_delay(2, 2, &failed, None, cap), None);
+
+        // Plain backoff
+        let delay = next_retry_delay(0, 2, &failed, None, cap).unwrap();
+        assert!(delay.as_millis() >= 750 && delay.as_millis() <= 1250);
+
+        // Rate limits use the server's hint
+        let limited = LlmError::RateLimited {
+            provider: "p".into(),
+            retry_after: Some(Duration::from_secs(3)),
+        };
+        assert_eq!(next_retry_delay(0, 2, &limited, None, cap), Some(Duration::from_secs(3)));
+
+        // Waits out a short open circuit, gives up on one past the cap
+        assert_eq!(
+            next_retry_delay(0, 2, &limited, Some(Duration::from_secs(5)), cap),
+            Some(Duration::from_secs(5))
+        );
+        assert_eq!(next_retry_delay(0, 2, &failed, Some(Duration::from_secs(30)), cap), None);
+        let open = LlmError::CircuitOpen {
+            provider: "p".into(),
+            retry_after: Duration::from_secs(20),
+        };
+        assert_eq!(next_retry_delay(0, 2, &open, None, cap), None);
+
+        // A cap raised to the breaker cooldown waits the circuit out
+        let cap = Duration::from_secs(30);
+        assert_eq!(next_retry_delay(0, 2, &open, None, cap), Some(Duration::from_secs(20)));
+    }
+
     #[test]
     fn test_retry_backoff_delay_no_overflow() {
         // Very high attempt numbers should not panic from overflow
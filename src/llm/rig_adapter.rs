@@ -3,47 +3,47 @@
 //! This lets us use any rig-core provider (OpenAI, Anthropic, Ollama, etc.) as an
 //! `Arc<dyn LlmProvider>` without changing any of the agent, reasoning, or tool code.
 
+use std::collections::HashSet;
+
 use async_trait::async_trait;
+use futures::StreamExt;
 use rig::OneOrMany;
 use rig::completion::{
-    AssistantContent, CompletionModel, CompletionRequest as RigRequest,
+    AssistantContent, CompletionModel, CompletionRequest as RigRequest, GetTokenUsage,
     ToolDefinition as RigToolDefinition, Usage as RigUsage,
 };
 use rig::message::{
     Message as RigMessage, ToolChoice as RigToolChoice, ToolFunction, ToolResult as RigToolResult,
     ToolResultContent, UserContent,
 };
+use rig::streaming::{StreamedAssistantContent, ToolCallDeltaContent};
 use rust_decimal::Decimal;
 use serde::Serialize;
 use serde::de::DeserializeOwned;
 
 use crate::error::LlmError;
-use crate::llm::costs;
+use crate::llm::costs::{self, PriceQuote};
 use crate::llm::provider::{
-    ChatMessage, CompletionRequest, CompletionResponse, FinishReason, LlmProvider,
-    ToolCall as IronToolCall, ToolCompletionRequest, ToolCompletionResponse,
+    ChatMessage, CompletionRequest, CompletionResponse, FinishReason, LlmProvider, StreamDelta,
+    StreamSender, ToolCall as IronToolCall, ToolCompletionRequest, ToolCompletionResponse,
     ToolDefinition as IronToolDefinition,
 };
 
 /// Adapter that wraps a rig-core `CompletionModel` and implements `LlmProvider`.
 pub struct RigAdapter<M: CompletionModel> {
     model: M,
+    /// Backend name reported on responses (`anthropic`, `ollama`, ...).
+    provider_name: String,
     model_name: String,
-    input_cost: Decimal,
-    output_cost: Decimal,
 }
 
 impl<M: CompletionModel> RigAdapter<M> {
     /// Create a new adapter wrapping the given rig-core model.
-    pub fn new(model: M, model_name: impl Into<String>) -> Self {
-        let name = model_name.into();
-        let (input_cost, output_cost) =
-            costs::model_cost(&name).unwrap_or_else(costs::default_cost);
+    pub fn new(model: M, provider_name: impl Into<String>, model_name: impl Into<String>) -> Self {
         Self {
             model,
-            model_name: name,
-            input_cost,
-            output_cost,
+            provider_name: provider_name.into(),
+            model_name: model_name.into(),
         }
     }
 }
@@ -185,6 +185,47 @@ fn saturate_u32(val: u64) -> u32 {
     val.min(u32::MAX as u64) as u32
 }
 
+/// Token counts with prompt-cache reads and writes included in `input`.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct TokenCounts {
+    input: u32,
+    output: u32,
+    cache_read: u32,
+    cache_write: u32,
+}
+
+/// Read token counts, including prompt-cache usage, from a rig response.
+///
+/// rig's `Usage` carries cache reads but not writes, and providers disagree
+/// on whether input counts include cached tokens: Anthropic reports cache
+/// reads and writes beside `input_tokens`, OpenAI counts cached tokens inside
+/// `prompt_tokens`. The serialized raw response tells them apart.
+fn token_counts(raw: &serde_json::Value, usage: &RigUsage) -> TokenCounts {
+    let raw_usage = raw.get("usage");
+    let field = |key: &str| {
+        raw_usage
+            .and_then(|u| u.get(key))
+            .and_then(serde_json::Value::as_u64)
+            .unwrap_or(0)
+    };
+    let reports_cache_beside_input = raw_usage.is_some_and(|u| {
+        u.get("cache_read_input_tokens").is_some() || u.get("cache_creation_input_tokens").is_some()
+    });
+    let (input, cache_read, cache_write) = if reports_cache_beside_input {
+        let read = field("cache_read_input_tokens");
+        let write = field("cache_creation_input_tokens");
+        (usage.input_tokens + read + write, read, write)
+    } else {
+        (usage.input_tokens, usage.cached_input_tokens, 0)
+    };
+    TokenCounts {
+        input: saturate_u32(input),
+        output: saturate_u32(usage.output_tokens),
+        cache_read: saturate_u32(cache_read),
+        cache_write: saturate_u32(cache_write),
+    }
+}
+
 /// Build a rig-core CompletionRequest from our internal types.
 fn build_rig_request(
     preamble: Option<String>,
@@ -216,6 +257,58 @@ fn build_rig_request(
     })
 }
 
+/// Build a rig-core request for a tool completion.
+fn build_tool_request(request: &ToolCompletionRequest) -> Result<RigRequest, LlmError> {
+    let (preamble, history) = convert_messages(&request.messages);
+    build_rig_request(
+        preamble,
+        history,
+        convert_tools(&request.tools),
+        convert_tool_choice(request.tool_choice.as_deref()),
+        request.temperature,
+        request.max_tokens,
+    )
+}
+
+/// Map a streamed rig-core item to a delta, if it carries one.
+///
+/// Providers that stream tool calls as fragments also emit the assembled
+/// call at the end; `fragmented` holds the IDs already sent piecewise so the
+/// whole call isn't sent twice.
+fn stream_delta<R>(
+    item: StreamedAssistantContent<R>,
+    fragmented: &mut HashSet<String>,
+) -> Option<StreamDelta> {
+    match item {
+        StreamedAssistantContent::Text(t) if !t.text.is_empty() => Some(StreamDelta::Text(t.text)),
+        StreamedAssistantContent::ToolCallDelta { id, content, .. } => {
+            fragmented.insert(id.clone());
+            Some(match content {
+                ToolCallDeltaContent::Name(name) => StreamDelta::ToolCall {
+                    id,
+                    name: Some(name),
+                    arguments: String::new(),
+                },
+                ToolCallDeltaContent::Delta(arguments) => StreamDelta::ToolCall {
+                    id,
+                    name: None,
+                    arguments,
+                },
+            })
+        }
+        StreamedAssistantContent::ToolCall { tool_call, .. }
+            if !fragmented.contains(&tool_call.id) =>
+        {
+            Some(StreamDelta::ToolCall {
+                id: tool_call.id,
+                name: Some(tool_call.function.name),
+                arguments: tool_call.function.arguments.to_string(),
+            })
+        }
+        _ => None,
+    }
+}
+
 #[async_trait]
 impl<M> LlmProvider for RigAdapter<M>
 where
@@ -227,7 +320,13 @@ where
     }
 
     fn cost_per_token(&self) -> (Decimal, Decimal) {
-        (self.input_cost, self.output_cost)
+        let pricing = self.pricing().pricing;
+        (pricing.input, pricing.output)
+    }
+
+    fn pricing(&self) -> PriceQuote {
+        // Looked up per call so settings overrides apply without a restart.
+        costs::lookup(&self.model_name)
     }
 
     async fn complete(&self, request: CompletionRequest) -> Result<CompletionResponse, LlmError> {
@@ -252,13 +351,19 @@ where
                 })?;
 
         let (text, _tool_calls, finish) = extract_response(&response.choice, &response.usage);
+        let raw = serde_json::to_value(&response.raw_response).unwrap_or_default();
+        let tokens = token_counts(&raw, &response.usage);
 
         Ok(CompletionResponse {
             content: text.unwrap_or_default(),
-            input_tokens: saturate_u32(response.usage.input_tokens),
-            output_tokens: saturate_u32(response.usage.output_tokens),
+            input_tokens: tokens.input,
+            output_tokens: tokens.output,
+            cache_read_tokens: tokens.cache_read,
+            cache_write_tokens: tokens.cache_write,
             finish_reason: finish,
             response_id: None,
+            provider: self.provider_name.clone(),
+            model: self.model_name.clone(),
         })
     }
 
@@ -266,18 +371,7 @@ where
         &self,
         request: ToolCompletionRequest,
     ) -> Result<ToolCompletionResponse, LlmError> {
-        let (preamble, history) = convert_messages(&request.messages);
-        let tools = convert_tools(&request.tools);
-        let tool_choice = convert_tool_choice(request.tool_choice.as_deref());
-
-        let rig_req = build_rig_request(
-            preamble,
-            history,
-            tools,
-            tool_choice,
-            request.temperature,
-            request.max_tokens,
-        )?;
+        let rig_req = build_tool_request(&request)?;
 
         let response =
             self.model
@@ -289,14 +383,67 @@ where
                 })?;
 
         let (text, tool_calls, finish) = extract_response(&response.choice, &response.usage);
+        let raw = serde_json::to_value(&response.raw_response).unwrap_or_default();
+        let tokens = token_counts(&raw, &response.usage);
 
         Ok(ToolCompletionResponse {
             content: text,
             tool_calls,
-            input_tokens: saturate_u32(response.usage.input_tokens),
-            output_tokens: saturate_u32(response.usage.output_tokens),
+            input_tokens: tokens.input,
+            output_tokens: tokens.output,
+            cache_read_tokens: tokens.cache_read,
+            cache_write_tokens: tokens.cache_write,
             finish_reason: finish,
             response_id: None,
+            provider: self.provider_name.clone(),
+            model: self.model_name.clone(),
+        })
+    }
+
+    async fn stream_with_tools(
+        &self,
+        request: ToolCompletionRequest,
+        deltas: StreamSender,
+    ) -> Result<ToolCompletionResponse, LlmError> {
+        let rig_req = build_tool_request(&request)?;
+        let request_failed = |e: rig::completion::CompletionError| LlmError::RequestFailed {
+            provider: self.model_name.clone(),
+            reason: e.to_string(),
+        };
+
+        let mut stream = self.model.stream(rig_req).await.map_err(request_failed)?;
+        let mut usage = RigUsage::new();
+        let mut raw = serde_json::Value::Null;
+        let mut fragmented = HashSet::new();
+        while let Some(item) = stream.next().await {
+            let item = item.map_err(request_failed)?;
+            if let StreamedAssistantContent::Final(ref response) = item
+                && let Some(final_usage) = response.token_usage()
+            {
+                usage = final_usage;
+                raw = serde_json::to_value(response).unwrap_or_default();
+            }
+            if let Some(delta) = stream_delta(item, &mut fragmented) {
+                // A dropped receiver only means nobody is watching the stream.
+                let _ = deltas.send(delta);
+            }
+        }
+
+        // The stream aggregates text and tool calls into `choice` once exhausted.
+        let (text, tool_calls, finish) = extract_response(&stream.choice, &usage);
+        let tokens = token_counts(&raw, &usage);
+
+        Ok(ToolCompletionResponse {
+            content: text,
+            tool_calls,
+            input_tokens: tokens.input,
+            output_tokens: tokens.output,
+            cache_read_tokens: tokens.cache_read,
+            cache_write_tokens: tokens.cache_write,
+            finish_reason: finish,
+            response_id: None,
+            provider: self.provider_name.clone(),
+            model: self.model_name.clone(),
         })
     }
 
@@ -442,10 +589,97 @@ mod tests {
         assert_eq!(finish, FinishReason::ToolUse);
     }
 
+    #[test]
+    fn test_stream_delta_skips_reassembled_tool_calls() {
+        let mut fragmented = HashSet::new();
+        let text: StreamedAssistantContent<()> = StreamedAssistantContent::text("Hel");
+        assert_eq!(
+            stream_delta(text, &mut fragmented),
+            Some(StreamDelta::Text("Hel".into()))
+        );
+
+        let name: StreamedAssistantContent<()> = StreamedAssistantContent::ToolCallDelta {
+            id: "call_1".into(),
+            internal_call_id: "i1".into(),
+            content: ToolCallDeltaContent::Name("search".into()),
+        };
+        assert_eq!(
+            stream_delta(name, &mut fragmented),
+            Some(StreamDelta::ToolCall {
+                id: "call_1".into(),
+                name: Some("search".into()),
+                arguments: String::new(),
+            })
+        );
+
+        let whole = |id: &str| -> StreamedAssistantContent<()> {
+            StreamedAssistantContent::ToolCall {
+                tool_call: rig::message::ToolCall::new(
+                    id.into(),
+                    ToolFunction::new("search".into(), serde_json::json!({"q": "x"})),
+                ),
+                internal_call_id: "i".into(),
+            }
+        };
+        // Already streamed piecewise
+        assert_eq!(stream_delta(whole("call_1"), &mut fragmented), None);
+        // Sent whole by the provider
+        assert_eq!(
+            stream_delta(whole("call_2"), &mut fragmented),
+            Some(StreamDelta::ToolCall {
+                id: "call_2".into(),
+                name: Some("search".into()),
+                arguments: r#"{"q":"x"}"#.into(),
+            })
+        );
+    }
+
     #[test]
     fn test_saturate_u32() {
         assert_eq!(saturate_u32(100), 100);
         assert_eq!(saturate_u32(u64::MAX), u32::MAX);
         assert_eq!(saturate_u32(u32::MAX as u64), u32::MAX);
     }
+
+    #[test]
+    fn test_token_counts_normalise_cache_usage() {
+        let usage = RigUsage {
+            input_tokens: 100,
+            output_tokens: 20,
+            total_tokens: 120,
+            cached_input_tokens: 0,
+        };
+        // Anthropic: cache reads/writes sit beside input_tokens
+        let anthropic = serde_json::json!({"usage": {
+            "input_tokens": 100,
+            "cache_read_input_tokens": 800,
+            "cache_creation_input_tokens": 50,
+            "output_tokens": 20
+        }});
+        assert_eq!(
+            token_counts(&anthropic, &usage),
+            TokenCounts {
+                input: 950,
+                output: 20,
+                cache_read: 800,
+                cache_write: 50
+            }
+        );
+
+        // OpenAI: cached tokens are already inside prompt_tokens
+        let openai_usage = RigUsage {
+            cached_input_tokens: 60,
+            ..usage
+        };
+        let openai = serde_json::json!({"usage": {"prompt_tokens": 100, "total_tokens": 120}});
+        assert_eq!(
+            token_counts(&openai, &openai_usage),
+            TokenCounts {
+                input: 100,
+                output: 20,
+                cache_read: 60,
+                cache_write: 0
+            }
+        );
+    }
 }
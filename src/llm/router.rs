@@ -0,0 +1,663 @@
+//! Per-task model routing.
+//!
+//! Each subsystem asks the [`LlmRouter`] for the provider of its role —
+//! a cheap local model for triage, a strong model for agents — instead of
+//! sharing one `Arc<dyn LlmProvider>`. A role is configured with a
+//! comma-separated failover chain in `AI_ASSIST_MODEL_<ROLE>`:
+//!
+//! ```text
+//! AI_ASSIST_MODEL_TRIAGE="ollama:llama3.1:8b, anthropic:claude-3-5-haiku-latest"
+//! AI_ASSIST_MODEL_AGENT="anthropic:claude-sonnet-4-20250514"
+//! AI_ASSIST_MODEL_SUMMARY="openai_compatible:qwen2.5-7b@http://gpu-box:8000/v1"
+//! ```
+//!
+//! Roles without a chain use the default provider. Every call made through a
+//! routed provider is recorded in `llm_calls` with its role, so spend can be
+//! broken down per subsystem. With a [`BudgetGuard`] attached, each call is
+//! checked against the spend budgets first: over a soft limit it goes to the
+//! triage model instead, and background work over a hard limit is refused.
+//!
+//! Each chain — and the default provider, as a chain of one — runs behind a
+//! [`FailoverProvider`], so its entries get circuit breakers and
+//! [`LlmRouter::health`] can report them per role.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::error::LlmError;
+use crate::llm::budget::{BudgetCheck, BudgetGuard, CallScope};
+use crate::llm::circuit::ProviderHealthReport;
+use crate::llm::costs::{PriceQuote, PriceSource, UsageCounts};
+use crate::llm::provider::{
+    CompletionRequest, CompletionResponse, LlmProvider, ModelMetadata, StreamSender,
+    ToolCompletionRequest, ToolCompletionResponse,
+};
+use crate::llm::{FailoverProvider, LlmBackend, LlmConfig, create_provider};
+use crate::store::Database;
+use crate::store::traits::LlmCallRecord;
+
+/// Request metadata keys the router reads when recording a call.
+pub const META_CONVERSATION_ID: &str = "thread_id";
+pub const META_ROUTINE_RUN_ID: &str = "routine_run_id";
+pub const META_PURPOSE: &str = "purpose";
+pub const META_ROUTINE_ID: &str = "routine_id";
+pub const META_TODO_ID: &str = "todo_id";
+/// `"true"` for calls submitted through a provider batch API (discounted).
+pub const META_BATCH: &str = "batch";
+
+/// A subsystem that makes LLM calls.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LlmRole {
+    /// Inbound message triage (`MessageProcessor`).
+    Triage,
+    /// Reply drafts (`ReplyDrafter`).
+    Draft,
+    /// Context compaction summaries.
+    Compaction,
+    /// Lightweight routines.
+    Routine,
+    /// The chat agent and todo agents.
+    Agent,
+    /// Digest summaries.
+    Summary,
+}
+
+impl LlmRole {
+    pub const ALL: [LlmRole; 6] = [
+        Self::Triage,
+        Self::Draft,
+        Self::Compaction,
+        Self::Routine,
+        Self::Agent,
+        Self::Summary,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Triage => "triage",
+            Self::Draft => "draft",
+            Self::Compaction => "compaction",
+            Self::Routine => "routine",
+            Self::Agent => "agent",
+            Self::Summary => "summary",
+        }
+    }
+
+    /// Environment variable holding this role's failover chain.
+    pub fn env_var(&self) -> String {
+        format!("AI_ASSIST_MODEL_{}", self.as_str().to_uppercase())
+    }
+}
+
+impl std::str::FromStr for LlmRole {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|r| r.as_str() == s)
+            .ok_or_else(|| format!("Unknown LLM role: {s}"))
+    }
+}
+
+/// One link of a role's failover chain: `backend:model[@base_url]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChainEntry {
+    pub backend: LlmBackend,
+    pub model: String,
+    pub base_url: Option<String>,
+}
+
+/// Parse a comma-separated chain such as
+/// `ollama:llama3.1:8b@http://localhost:11434, anthropic:claude-3-5-haiku-latest`.
+pub fn parse_chain(spec: &str) -> Result<Vec<ChainEntry>, String> {
+    let entries = spec
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|entry| {
+            let (backend, rest) = entry
+                .split_once(':')
+                .ok_or_else(|| format!("Expected backend:model, got '{entry}'"))?;
+            let (model, base_url) = match rest.split_once('@') {
+                Some((model, url)) => (model, Some(url.trim().to_string())),
+                None => (rest, None),
+            };
+            if model.trim().is_empty() {
+                return Err(format!("Missing model in '{entry}'"));
+            }
+            Ok(ChainEntry {
+                backend: backend.trim().parse()?,
+                model: model.trim().to_string(),
+                base_url,
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    if entries.is_empty() {
+        return Err("Empty model chain".to_string());
+    }
+    Ok(entries)
+}
+
+/// Build a provider for one chain entry, reading the backend's API key from
+/// the environment.
+fn create_entry_provider(entry: &ChainEntry) -> Result<Arc<dyn LlmProvider>, LlmError> {
+    let key_var = match entry.backend {
+        LlmBackend::Anthropic => "ANTHROPIC_API_KEY",
+        LlmBackend::OpenAi => "OPENAI_API_KEY",
+        LlmBackend::Ollama | LlmBackend::OpenAiCompatible => "AI_ASSIST_LLM_API_KEY",
+    };
+    let api_key = std::env::var(key_var).unwrap_or_default();
+    if api_key.is_empty() && entry.backend.requires_api_key() {
+        return Err(LlmError::AuthFailed {
+            provider: format!("{key_var} not set"),
+        });
+    }
+    create_provider(&LlmConfig {
+        backend: entry.backend,
+        api_key: secrecy::SecretString::from(api_key),
+        model: entry.model.clone(),
+        base_url: entry.base_url.clone(),
+    })
+}
+
+/// Times a role's chain is retried (with backoff) after every entry failed.
+const CHAIN_RETRIES: u32 = 2;
+
+/// Build a provider for a whole chain, wrapped in `FailoverProvider` so each
+/// entry gets a circuit breaker and the chain is retried with backoff.
+pub fn create_chain_provider(chain: &[ChainEntry]) -> Result<Arc<dyn LlmProvider>, LlmError> {
+    let providers = chain
+        .iter()
+        .map(create_entry_provider)
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Arc::new(
+        FailoverProvider::new(providers)?.with_retries(CHAIN_RETRIES),
+    ))
+}
+
+/// Health of the providers serving one role.
+#[derive(Debug, Clone, Serialize)]
+pub struct RoleHealth {
+    pub role: &'static str,
+    /// Model of the provider that last served the role.
+    pub model: String,
+    pub providers: Vec<ProviderHealthReport>,
+}
+
+/// Maps roles to providers.
+pub struct LlmRouter {
+    default: Arc<dyn LlmProvider>,
+    roles: HashMap<LlmRole, Arc<dyn LlmProvider>>,
+    store: Option<Arc<dyn Database>>,
+    budgets: Option<Arc<BudgetGuard>>,
+}
+
+impl LlmRouter {
+    /// Every role uses `default` until overridden.
+    pub fn new(default: Arc<dyn LlmProvider>) -> Self {
+        Self {
+            default,
+            roles: HashMap::new(),
+            store: None,
+            budgets: None,
+        }
+    }
+
+    /// Record every routed call in `llm_calls`.
+    pub fn with_store(mut self, store: Arc<dyn Database>) -> Self {
+        self.store = Some(store);
+        self
+    }
+
+    /// Check every routed call against spend budgets.
+    pub fn with_budgets(mut self, budgets: Arc<BudgetGuard>) -> Self {
+        self.budgets = Some(budgets);
+        self
+    }
+
+    /// Route a role to a specific provider.
+    pub fn with_role(mut self, role: LlmRole, provider: Arc<dyn LlmProvider>) -> Self {
+        self.roles.insert(role, provider);
+        self
+    }
+
+    /// Read `AI_ASSIST_MODEL_<ROLE>` chains on top of the default provider,
+    /// which gets a breaker and retries like a one-entry chain.
+    pub fn from_env(default: Arc<dyn LlmProvider>) -> Result<Self, LlmError> {
+        let default = Arc::new(FailoverProvider::new(vec![default])?.with_retries(CHAIN_RETRIES));
+        let mut router = Self::new(default);
+        for role in LlmRole::ALL {
+            let var = role.env_var();
+            let Ok(spec) = std::env::var(&var) else {
+                continue;
+            };
+            let chain = parse_chain(&spec).map_err(|reason| LlmError::RequestFailed {
+                provider: var.clone(),
+                reason,
+            })?;
+            router = router.with_role(role, create_chain_provider(&chain)?);
+        }
+        Ok(router)
+    }
+
+    /// The provider for a role, tagged so its calls are recorded with the role.
+    pub fn for_role(&self, role: LlmRole) -> Arc<dyn LlmProvider> {
+        let inner = self.roles.get(&role).unwrap_or(&self.default);
+        // Over a soft limit, calls fall back to the triage model (if one is configured).
+        let downgrade = match role {
+            LlmRole::Triage => None,
+            _ => self.roles.get(&LlmRole::Triage).cloned(),
+        };
+        Arc::new(RoleProvider {
+            inner: Arc::clone(inner),
+            downgrade,
+            role,
+            store: self.store.clone(),
+            budgets: self.budgets.clone(),
+        })
+    }
+
+    /// Circuit-breaker health of each role's providers, for `/api/llm/health`.
+    /// Roles on an untracked provider (one passed to `new` or `with_role`
+    /// without a `FailoverProvider`) report none.
+    pub fn health(&self) -> Vec<RoleHealth> {
+        LlmRole::ALL
+            .into_iter()
+            .map(|role| {
+                let provider = self.roles.get(&role).unwrap_or(&self.default);
+                RoleHealth {
+                    role: role.as_str(),
+                    model: provider.model_name().to_string(),
+                    providers: provider.health(),
+                }
+            })
+            .collect()
+    }
+
+    /// `(role, model)` pairs for startup logging.
+    pub fn describe(&self) -> Vec<(LlmRole, String)> {
+        LlmRole::ALL
+            .into_iter()
+            .map(|role| {
+                let provider = self.roles.get(&role).unwrap_or(&self.default);
+                (role, provider.model_name().to_string())
+            })
+            .collect()
+    }
+}
+
+/// A provider bound to a role; checks budgets and records each successful call.
+struct RoleProvider {
+    inner: Arc<dyn LlmProvider>,
+    /// Cheaper provider used while a soft budget limit is exceeded.
+    downgrade: Option<Arc<dyn LlmProvider>>,
+    role: LlmRole,
+    store: Option<Arc<dyn Database>>,
+    budgets: Option<Arc<BudgetGuard>>,
+}
+
+impl RoleProvider {
+    /// Pick the provider for a call after checking the budgets that cover it.
+    async fn select(
+        &self,
+        metadata: &HashMap<String, String>,
+    ) -> Result<&Arc<dyn LlmProvider>, LlmError> {
+        let Some(budgets) = &self.budgets else {
+            return Ok(&self.inner);
+        };
+        match budgets
+            .check(&CallScope::from_metadata(metadata, self.role))
+            .await
+        {
+            BudgetCheck::Within => Ok(&self.inner),
+            BudgetCheck::Downgrade => {
+                if let Some(cheap) = &self.downgrade {
+                    tracing::debug!(
+                        role = self.role.as_str(),
+                        model = cheap.model_name(),
+                        "Soft budget exceeded, downgrading model"
+                    );
+                    return Ok(cheap);
+                }
+                Ok(&self.inner)
+            }
+            BudgetCheck::Paused(reason) => Err(LlmError::BudgetExceeded { reason }),
+        }
+    }
+
+    /// Record a call served by `served_provider`/`model`, as reported on the
+    /// response (not read back from `provider`, which other calls share).
+    async fn record(
+        &self,
+        provider: &Arc<dyn LlmProvider>,
+        metadata: &HashMap<String, String>,
+        mut usage: UsageCounts,
+        served_provider: &str,
+        model: &str,
+    ) {
+        let Some(store) = &self.store else {
+            return;
+        };
+        let quote = provider.pricing_for(model);
+        if quote.source == PriceSource::Fallback {
+            tracing::warn!(model = %model, "No price for model, cost recorded at the fallback rate");
+        }
+        usage.batch = metadata.get(META_BATCH).is_some_and(|v| v == "true");
+        let uuid = |key: &str| metadata.get(key).and_then(|v| Uuid::parse_str(v).ok());
+        let record = LlmCallRecord {
+            conversation_id: uuid(META_CONVERSATION_ID),
+            routine_run_id: uuid(META_ROUTINE_RUN_ID),
+            todo_id: uuid(META_TODO_ID),
+            provider: served_provider,
+            model,
+            input_tokens: usage.input_tokens,
+            output_tokens: usage.output_tokens,
+            cost: quote.pricing.cost(&usage),
+            purpose: metadata.get(META_PURPOSE).map(String::as_str),
+            role: Some(self.role.as_str()),
+            price_source: Some(quote.source.as_str()),
+        };
+        if let Err(e) = store.record_llm_call(&record).await {
+            tracing::warn!(role = self.role.as_str(), "Failed to record LLM call cost: {}", e);
+        }
+    }
+}
+
+#[async_trait]
+impl LlmProvider for RoleProvider {
+    fn model_name(&self) -> &str {
+        self.inner.model_name()
+    }
+
+    fn cost_per_token(&self) -> (Decimal, Decimal) {
+        self.inner.cost_per_token()
+    }
+
+    fn pricing(&self) -> PriceQuote {
+        self.inner.pricing()
+    }
+
+    fn pricing_for(&self, model: &str) -> PriceQuote {
+        self.inner.pricing_for(model)
+    }
+
+    async fn complete(&self, request: CompletionRequest) -> Result<CompletionResponse, LlmError> {
+        let metadata = request.metadata.clone();
+        let provider = self.select(&metadata).await?;
+        let response = provider.complete(request).await?;
+        self.record(provider, &metadata, response.usage(), &response.provider, &response.model)
+            .await;
+        Ok(response)
+    }
+
+    async fn complete_with_tools(
+        &self,
+        request: ToolCompletionRequest,
+    ) -> Result<ToolCompletionResponse, LlmError> {
+        let metadata = request.metadata.clone();
+        let provider = self.select(&metadata).await?;
+        let response = provider.complete_with_tools(request).await?;
+        self.record(provider, &metadata, response.usage(), &response.provider, &response.model)
+            .await;
+        Ok(response)
+    }
+
+    async fn stream_with_tools(
+        &self,
+        request: ToolCompletionRequest,
+        deltas: StreamSender,
+    ) -> Result<ToolCompletionResponse, LlmError> {
+        let metadata = request.metadata.clone();
+        let provider = self.select(&metadata).await?;
+        let response = provider.stream_with_tools(request, deltas).await?;
+        self.record(provider, &metadata, response.usage(), &response.provider, &response.model)
+            .await;
+        Ok(response)
+    }
+
+    async fn list_models(&self) -> Result<Vec<String>, LlmError> {
+        self.inner.list_models().await
+    }
+
+    async fn model_metadata(&self) -> Result<ModelMetadata, LlmError> {
+        self.inner.model_metadata().await
+    }
+
+    fn active_model_name(&self) -> String {
+        self.inner.active_model_name()
+    }
+
+    fn set_model(&self, model: &str) -> Result<(), LlmError> {
+        self.inner.set_model(model)
+    }
+
+    fn seed_response_chain(&self, thread_id: &str, response_id: String) {
+        self.inner.seed_response_chain(thread_id, response_id)
+    }
+
+    fn get_response_chain_id(&self, thread_id: &str) -> Option<String> {
+        self.inner.get_response_chain_id(thread_id)
+    }
+
+    fn health(&self) -> Vec<ProviderHealthReport> {
+        self.inner.health()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::provider::FinishReason;
+    use crate::store::LibSqlBackend;
+
+    struct FixedLlm(&'static str);
+
+    #[async_trait]
+    impl LlmProvider for FixedLlm {
+        fn model_name(&self) -> &str {
+            self.0
+        }
+        fn cost_per_token(&self) -> (Decimal, Decimal) {
+            (Decimal::new(1, 6), Decimal::new(2, 6))
+        }
+        async fn complete(&self, _req: CompletionRequest) -> Result<CompletionResponse, LlmError> {
+            Ok(CompletionResponse {
+                content: "ok".into(),
+                input_tokens: 100,
+                output_tokens: 10,
+                cache_read_tokens: 0,
+                cache_write_tokens: 0,
+                finish_reason: FinishReason::Stop,
+                response_id: None,
+                provider: "mock".into(),
+                model: self.0.into(),
+            })
+        }
+        async fn complete_with_tools(
+            &self,
+            _req: ToolCompletionRequest,
+        ) -> Result<ToolCompletionResponse, LlmError> {
+            unimplemented!()
+        }
+    }
+
+    /// Answers with a model other than its configured name, as a failover
+    /// chain does when a fallback serves the call; priced at the fallback
+    /// rate so the recorded model shows up in `get_fallback_priced_models`.
+    struct ServedElsewhere;
+
+    #[async_trait]
+    impl LlmProvider for ServedElsewhere {
+        fn model_name(&self) -> &str {
+            "configured"
+        }
+        fn cost_per_token(&self) -> (Decimal, Decimal) {
+            (Decimal::ZERO, Decimal::ZERO)
+        }
+        fn pricing(&self) -> PriceQuote {
+            PriceQuote {
+                pricing: crate::llm::costs::ModelPricing::flat(self.cost_per_token()),
+                source: PriceSource::Fallback,
+            }
+        }
+        async fn complete(&self, req: CompletionRequest) -> Result<CompletionResponse, LlmError> {
+            let mut response = FixedLlm("served").complete(req).await?;
+            response.provider = "ollama".into();
+            Ok(response)
+        }
+        async fn complete_with_tools(
+            &self,
+            _req: ToolCompletionRequest,
+        ) -> Result<ToolCompletionResponse, LlmError> {
+            unimplemented!()
+        }
+    }
+
+    #[test]
+    fn parse_chain_handles_tags_and_base_urls() {
+        let chain = parse_chain(
+            "ollama:llama3.1:8b@http://localhost:11434, anthropic:claude-3-5-haiku-latest",
+        )
+        .unwrap();
+        assert_eq!(chain.len(), 2);
+        assert_eq!(chain[0].backend, LlmBackend::Ollama);
+        assert_eq!(chain[0].model, "llama3.1:8b");
+        assert_eq!(chain[0].base_url.as_deref(), Some("http://localhost:11434"));
+        assert_eq!(chain[1].backend, LlmBackend::Anthropic);
+        assert!(chain[1].base_url.is_none());
+
+        assert!(parse_chain("").is_err());
+        assert!(parse_chain("llama3").is_err());
+        assert!(parse_chain("bard:gemini").is_err());
+        assert!(parse_chain("ollama:").is_err());
+    }
+
+    #[test]
+    fn chain_of_local_models_builds_failover() {
+        let chain = parse_chain(
+            "ollama:qwen2.5@http://127.0.0.1:9, openai_compatible:phi4@http://127.0.0.1:9/v1",
+        )
+        .unwrap();
+        let provider = create_chain_provider(&chain).unwrap();
+        assert_eq!(provider.model_name(), "qwen2.5");
+    }
+
+    #[test]
+    fn roles_fall_back_to_default() {
+        let router = LlmRouter::new(Arc::new(FixedLlm("strong")))
+            .with_role(LlmRole::Triage, Arc::new(FixedLlm("cheap")));
+        assert_eq!(router.for_role(LlmRole::Triage).model_name(), "cheap");
+        assert_eq!(router.for_role(LlmRole::Agent).model_name(), "strong");
+        let described: HashMap<_, _> = router.describe().into_iter().collect();
+        assert_eq!(described[&LlmRole::Draft], "strong");
+    }
+
+    #[test]
+    fn default_provider_reports_health() {
+        let router = LlmRouter::from_env(Arc::new(FixedLlm("strong"))).unwrap();
+        let health = router.health();
+        assert_eq!(health.len(), LlmRole::ALL.len());
+        for role in &health {
+            assert_eq!(role.model, "strong");
+            assert_eq!(role.providers.len(), 1);
+        }
+    }
+
+    #[tokio::test]
+    async fn routed_calls_are_recorded_with_role() {
+        let db: Arc<dyn Database> = Arc::new(LibSqlBackend::new_memory().await.unwrap());
+        let router = LlmRouter::new(Arc::new(FixedLlm("strong")))
+            .with_role(LlmRole::Triage, Arc::new(FixedLlm("cheap")))
+            .with_store(Arc::clone(&db));
+
+        let mut request = CompletionRequest::new(vec![]);
+        request
+            .metadata
+            .insert(META_PURPOSE.to_string(), "triage".to_string());
+        router.for_role(LlmRole::Triage).complete(request.clone()).await.unwrap();
+        router.for_role(LlmRole::Draft).complete(request).await.unwrap();
+
+        let now = chrono::Utc::now();
+        let by_role = db
+            .get_costs_by_role(now - chrono::Duration::hours(1), now + chrono::Duration::hours(1))
+            .await
+            .unwrap();
+        let roles: Vec<&str> = by_role.iter().map(|(r, _)| r.as_str()).collect();
+        assert_eq!(roles, vec!["draft", "triage"]);
+        assert_eq!(by_role[1].1.total_input_tokens, 100);
+        assert!(by_role[1].1.total_cost > Decimal::ZERO);
+    }
+
+    #[tokio::test]
+    async fn calls_are_recorded_under_the_model_that_served_them() {
+        let db: Arc<dyn Database> = Arc::new(LibSqlBackend::new_memory().await.unwrap());
+        let router = LlmRouter::new(Arc::new(ServedElsewhere)).with_store(Arc::clone(&db));
+        let response = router
+            .for_role(LlmRole::Agent)
+            .complete(CompletionRequest::new(vec![]))
+            .await
+            .unwrap();
+        assert_eq!((response.provider.as_str(), response.model.as_str()), ("ollama", "served"));
+
+        let now = chrono::Utc::now();
+        let models = db
+            .get_fallback_priced_models(now - chrono::Duration::hours(1), now + chrono::Duration::hours(1))
+            .await
+            .unwrap();
+        assert_eq!(models, vec![("served".to_string(), 1)]);
+    }
+
+    #[tokio::test]
+    async fn budgets_downgrade_then_pause_background_calls() {
+        use crate::llm::budget::{Budget, BudgetPeriod, BudgetScope};
+
+        let db: Arc<dyn Database> = Arc::new(LibSqlBackend::new_memory().await.unwrap());
+        // FixedLlm costs 0.00012 per call
+        let budget = Budget::new(
+            BudgetScope::Global,
+            None,
+            BudgetPeriod::Daily,
+            Some(Decimal::new(1, 4)),
+            Some(Decimal::new(2, 4)),
+        );
+        db.create_budget(&budget).await.unwrap();
+        let guard = Arc::new(BudgetGuard::load(Arc::clone(&db), None).await.unwrap());
+        let router = LlmRouter::new(Arc::new(FixedLlm("strong")))
+            .with_role(LlmRole::Triage, Arc::new(FixedLlm("cheap")))
+            .with_store(Arc::clone(&db))
+            .with_budgets(guard);
+        let routine = router.for_role(LlmRole::Routine);
+        let chat = router.for_role(LlmRole::Agent);
+
+        routine.complete(CompletionRequest::new(vec![])).await.unwrap();
+        routine.complete(CompletionRequest::new(vec![])).await.unwrap();
+        let err = routine
+            .complete(CompletionRequest::new(vec![]))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, LlmError::BudgetExceeded { .. }));
+        // Interactive calls keep going on the cheap model
+        chat.complete(CompletionRequest::new(vec![])).await.unwrap();
+
+        let now = chrono::Utc::now();
+        let by_role = db
+            .get_costs_by_role(now - chrono::Duration::hours(1), now + chrono::Duration::hours(1))
+            .await
+            .unwrap();
+        let calls: u64 = by_role.iter().map(|(_, s)| s.call_count).sum();
+        assert_eq!(calls, 3);
+    }
+
+    #[test]
+    fn role_round_trips() {
+        for role in LlmRole::ALL {
+            assert_eq!(role.as_str().parse::<LlmRole>().unwrap(), role);
+        }
+        assert_eq!(LlmRole::Compaction.env_var(), "AI_ASSIST_MODEL_COMPACTION");
+    }
+}
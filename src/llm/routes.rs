@@ -0,0 +1,337 @@
+//! REST API routes for LLM spend, provider health and budgets.
+//!
+//! Endpoints:
+//! - `GET    /api/llm/costs?days=N` — total spend over the last N days (default 30),
+//!   broken down by model-router role, with a warning when some calls were
+//!   priced at the fallback rate
+//! - `GET    /api/llm/pricing`      — effective pricing table version and overrides
+//! - `PUT    /api/llm/pricing`      — replace per-model price overrides (stored in settings)
+//! - `DELETE /api/llm/pricing`      — clear overrides
+//! - `GET    /api/llm/health`       — circuit state, error rate and p95 latency
+//!   of each role's providers
+//! - `GET    /api/budgets`          — budgets with current-period spend and state
+//! - `POST   /api/budgets`          — create a budget
+//! - `GET    /api/budgets/:id`      — one budget with its spend
+//! - `PUT    /api/budgets/:id`      — update limits, period or enabled
+//! - `DELETE /api/budgets/:id`      — delete a budget
+//!
+//! Every successful budget mutation reloads the live [`BudgetGuard`].
+
+use std::sync::Arc;
+
+use axum::{
+    Json, Router,
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::get,
+};
+use chrono::{Duration, Utc};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::llm::budget::{Budget, BudgetGuard, BudgetPeriod, BudgetScope};
+use crate::llm::costs::{self, PricingFile, PricingTable};
+use crate::llm::router::LlmRouter;
+use crate::store::Database;
+
+/// Shared state for LLM routes.
+#[derive(Clone)]
+pub struct LlmRoutesState {
+    pub db: Arc<dyn Database>,
+    /// The budgets checked before each routed LLM call.
+    pub budgets: Arc<BudgetGuard>,
+    /// The model router, for provider health.
+    pub router: Arc<LlmRouter>,
+}
+
+/// Query parameters for `/api/llm/costs`.
+#[derive(Debug, Deserialize)]
+pub struct CostsQuery {
+    #[serde(default = "default_days")]
+    pub days: i64,
+}
+
+fn default_days() -> i64 {
+    30
+}
+
+/// Request body for creating a budget.
+#[derive(Debug, Deserialize)]
+pub struct CreateBudgetRequest {
+    pub scope: BudgetScope,
+    #[serde(default)]
+    pub scope_id: Option<Uuid>,
+    pub period: BudgetPeriod,
+    #[serde(default)]
+    pub soft_limit: Option<Decimal>,
+    #[serde(default)]
+    pub hard_limit: Option<Decimal>,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// Request body for updating a budget. Omitted fields are kept.
+#[derive(Debug, Deserialize)]
+pub struct UpdateBudgetRequest {
+    pub period: Option<BudgetPeriod>,
+    pub soft_limit: Option<Decimal>,
+    pub hard_limit: Option<Decimal>,
+    pub enabled: Option<bool>,
+}
+
+/// Build the Axum router for `/api/llm` and `/api/budgets`.
+pub fn llm_routes(state: LlmRoutesState) -> Router {
+    Router::new()
+        .route("/api/llm/costs", get(costs))
+        .route(
+            "/api/llm/pricing",
+            get(get_pricing).put(put_pricing).delete(delete_pricing),
+        )
+        .route("/api/llm/health", get(health))
+        .route("/api/budgets", get(list_budgets).post(create_budget))
+        .route(
+            "/api/budgets/{id}",
+            get(get_budget).put(update_budget).delete(delete_budget),
+        )
+        .with_state(state)
+}
+
+fn error_response(status: StatusCode, message: impl Into<String>) -> Response {
+    (status, Json(serde_json::json!({"error": message.into()}))).into_response()
+}
+
+/// Reload the live budgets; failures are logged — the DB write already succeeded.
+async fn reload(state: &LlmRoutesState) {
+    if let Err(e) = state.budgets.reload().await {
+        warn!(error = %e, "Failed to reload budgets");
+    }
+}
+
+/// GET /api/llm/costs
+async fn costs(State(state): State<LlmRoutesState>, Query(query): Query<CostsQuery>) -> Response {
+    if query.days <= 0 {
+        return error_response(StatusCode::BAD_REQUEST, "days must be positive");
+    }
+    let end = Utc::now();
+    let start = end - Duration::days(query.days);
+
+    let total = match state.db.get_costs_by_period(start, end).await {
+        Ok(total) => total,
+        Err(e) => return error_response(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    };
+    let fallback = match state.db.get_fallback_priced_models(start, end).await {
+        Ok(models) => models,
+        Err(e) => return error_response(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    };
+    let warnings: Vec<String> = fallback
+        .iter()
+        .map(|(model, calls)| {
+            format!(
+                "{calls} call(s) to {model} were priced at the fallback rate; add it to the pricing table for accurate costs"
+            )
+        })
+        .collect();
+    match state.db.get_costs_by_role(start, end).await {
+        Ok(by_role) => {
+            let by_role: serde_json::Map<String, serde_json::Value> = by_role
+                .into_iter()
+                .map(|(role, summary)| (role, serde_json::json!(summary)))
+                .collect();
+            let fallback_priced: Vec<serde_json::Value> = fallback
+                .into_iter()
+                .map(|(model, calls)| serde_json::json!({"model": model, "calls": calls}))
+                .collect();
+            Json(serde_json::json!({
+                "days": query.days,
+                "total": total,
+                "by_role": by_role,
+                "pricing_version": costs::pricing_table().version(),
+                "fallback_priced": fallback_priced,
+                "warnings": warnings,
+            }))
+            .into_response()
+        }
+        Err(e) => error_response(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+/// GET /api/llm/health
+async fn health(State(state): State<LlmRoutesState>) -> Response {
+    Json(serde_json::json!({ "roles": state.router.health() })).into_response()
+}
+
+/// GET /api/llm/pricing
+async fn get_pricing(State(state): State<LlmRoutesState>) -> Response {
+    let overrides = match state.db.get_setting("default", costs::PRICING_SETTING).await {
+        Ok(overrides) => overrides,
+        Err(e) => return error_response(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    };
+    let table = costs::pricing_table();
+    Json(serde_json::json!({
+        "version": table.version(),
+        "override_count": table.override_count(),
+        "overrides": overrides,
+    }))
+    .into_response()
+}
+
+/// PUT /api/llm/pricing
+async fn put_pricing(
+    State(state): State<LlmRoutesState>,
+    Json(overrides): Json<PricingFile>,
+) -> Response {
+    if let Err(e) = PricingTable::bundled().with_overrides(&overrides) {
+        return error_response(StatusCode::BAD_REQUEST, e);
+    }
+    let value = match serde_json::to_value(&overrides) {
+        Ok(value) => value,
+        Err(e) => return error_response(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    };
+    match state
+        .db
+        .set_setting("default", costs::PRICING_SETTING, &value)
+        .await
+    {
+        Ok(()) => {
+            if let Err(e) = costs::install_overrides(Some(&overrides)) {
+                warn!(error = %e, "Failed to install pricing overrides");
+            }
+            Json(serde_json::json!({
+                "version": costs::pricing_table().version(),
+                "override_count": overrides.models.len(),
+            }))
+            .into_response()
+        }
+        Err(e) => error_response(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+/// DELETE /api/llm/pricing
+async fn delete_pricing(State(state): State<LlmRoutesState>) -> Response {
+    match state.db.delete_setting("default", costs::PRICING_SETTING).await {
+        Ok(deleted) => {
+            if let Err(e) = costs::install_overrides(None) {
+                warn!(error = %e, "Failed to reset pricing table");
+            }
+            Json(serde_json::json!({"deleted": deleted})).into_response()
+        }
+        Err(e) => error_response(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+/// GET /api/budgets
+async fn list_budgets(State(state): State<LlmRoutesState>) -> Response {
+    match state.budgets.status().await {
+        Ok(budgets) => Json(serde_json::json!({"budgets": budgets})).into_response(),
+        Err(e) => error_response(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+/// GET /api/budgets/:id
+async fn get_budget(State(state): State<LlmRoutesState>, Path(id): Path<String>) -> Response {
+    let Ok(budget_id) = Uuid::parse_str(&id) else {
+        return error_response(StatusCode::BAD_REQUEST, "Invalid budget ID");
+    };
+
+    match state.budgets.status().await {
+        Ok(budgets) => match budgets.into_iter().find(|b| b.budget.id == budget_id) {
+            Some(status) => Json(status).into_response(),
+            None => error_response(StatusCode::NOT_FOUND, "Budget not found"),
+        },
+        Err(e) => error_response(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+/// POST /api/budgets
+async fn create_budget(
+    State(state): State<LlmRoutesState>,
+    Json(req): Json<CreateBudgetRequest>,
+) -> Response {
+    let mut budget = Budget::new(
+        req.scope,
+        req.scope_id,
+        req.period,
+        req.soft_limit,
+        req.hard_limit,
+    );
+    budget.enabled = req.enabled;
+    if let Err(e) = budget.validate() {
+        return error_response(StatusCode::BAD_REQUEST, e);
+    }
+
+    match state.db.create_budget(&budget).await {
+        Ok(()) => {
+            reload(&state).await;
+            (StatusCode::CREATED, Json(serde_json::json!({"budget": budget}))).into_response()
+        }
+        Err(e) => error_response(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+/// PUT /api/budgets/:id
+async fn update_budget(
+    State(state): State<LlmRoutesState>,
+    Path(id): Path<String>,
+    Json(req): Json<UpdateBudgetRequest>,
+) -> Response {
+    let Ok(budget_id) = Uuid::parse_str(&id) else {
+        return error_response(StatusCode::BAD_REQUEST, "Invalid budget ID");
+    };
+
+    let existing = match state.db.get_budget(budget_id).await {
+        Ok(Some(budget)) => budget,
+        Ok(None) => return error_response(StatusCode::NOT_FOUND, "Budget not found"),
+        Err(e) => return error_response(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    };
+
+    // A new hard limit answers any outstanding raise request.
+    let hard_changed = req.hard_limit.is_some_and(|hard| Some(hard) != existing.hard_limit);
+    let updated = Budget {
+        period: req.period.unwrap_or(existing.period),
+        soft_limit: req.soft_limit.or(existing.soft_limit),
+        hard_limit: req.hard_limit.or(existing.hard_limit),
+        enabled: req.enabled.unwrap_or(existing.enabled),
+        raise_card_id: if hard_changed { None } else { existing.raise_card_id },
+        raise_requested_at: if hard_changed {
+            None
+        } else {
+            existing.raise_requested_at
+        },
+        updated_at: Utc::now(),
+        ..existing
+    };
+    if let Err(e) = updated.validate() {
+        return error_response(StatusCode::BAD_REQUEST, e);
+    }
+
+    match state.db.update_budget(&updated).await {
+        Ok(()) => {
+            reload(&state).await;
+            Json(serde_json::json!({"budget": updated})).into_response()
+        }
+        Err(e) => error_response(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+/// DELETE /api/budgets/:id
+async fn delete_budget(State(state): State<LlmRoutesState>, Path(id): Path<String>) -> Response {
+    let Ok(budget_id) = Uuid::parse_str(&id) else {
+        return error_response(StatusCode::BAD_REQUEST, "Invalid budget ID");
+    };
+
+    match state.db.delete_budget(budget_id).await {
+        Ok(true) => {
+            reload(&state).await;
+            Json(serde_json::json!({"deleted": true})).into_response()
+        }
+        Ok(false) => error_response(StatusCode::NOT_FOUND, "Budget not found"),
+        Err(e) => error_response(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
@@ -1,21 +1,25 @@
 use std::sync::Arc;
 
 use ai_assist::agent::routine_engine::{self, RoutineEngine};
+use ai_assist::agent::webhook::{WebhookState, webhook_routes};
 use ai_assist::agent::{Agent, AgentDeps};
 use ai_assist::cards::reply_drafter::{GeneratorConfig, ReplyDrafter};
 use ai_assist::cards::queue::{self, CardQueue};
 use ai_assist::cards::ws::card_routes;
-use ai_assist::channels::email::EmailConfig;
+use ai_assist::channels::email::EmailAccounts;
 use ai_assist::channels::{ChannelManager, CliChannel, IosChannel, TelegramChannel};
 use ai_assist::documents::routes::{DocumentState, document_routes};
-use ai_assist::config::{AgentConfig, RoutineConfig};
-use ai_assist::llm::{LlmBackend, LlmConfig, create_provider};
-use ai_assist::safety::SafetyLayer;
+use ai_assist::config::{AgentConfig, RoutineConfig, SafetyConfig, SandboxConfig};
+use ai_assist::llm::budget::BudgetGuard;
+use ai_assist::llm::{LlmBackend, LlmConfig, LlmRole, LlmRouter, create_provider};
+use ai_assist::safety::{LeakDetector, SafetyLayer};
 use ai_assist::store::{Database, LibSqlBackend};
 use ai_assist::todos::activity::{ActivityState, TodoActivityMessage, activity_routes};
 use ai_assist::todos::approval_registry::TodoApprovalRegistry;
 use ai_assist::todos::ws::{TodoState, todo_routes};
 use ai_assist::tools::ToolRegistry;
+use ai_assist::tools::sandbox::Sandbox;
+use ai_assist::tools::shell_policy::ShellPolicy;
 use ai_assist::worker::{ContextManager, Scheduler};
 use ai_assist::workspace::Workspace;
 
@@ -50,15 +54,42 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .with(file_layer)
         .init();
 
+    // LLM backend (anthropic by default; ollama / openai_compatible run fully local)
+    let backend: LlmBackend = std::env::var("AI_ASSIST_LLM_BACKEND")
+        .unwrap_or_else(|_| "anthropic".to_string())
+        .parse()
+        .unwrap_or_else(|e| {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        });
+
     // Read API key from environment
-    let api_key = std::env::var("ANTHROPIC_API_KEY").unwrap_or_else(|_| {
-        eprintln!("Error: ANTHROPIC_API_KEY not set");
-        eprintln!("  export ANTHROPIC_API_KEY=sk-ant-...");
-        std::process::exit(1);
+    let api_key_var = match backend {
+        LlmBackend::OpenAi => "OPENAI_API_KEY",
+        LlmBackend::Anthropic => "ANTHROPIC_API_KEY",
+        LlmBackend::Ollama | LlmBackend::OpenAiCompatible => "AI_ASSIST_LLM_API_KEY",
+    };
+    let api_key = std::env::var(api_key_var).unwrap_or_else(|_| {
+        if backend.requires_api_key() {
+            eprintln!("Error: {} not set", api_key_var);
+            eprintln!("  export {}=...", api_key_var);
+            std::process::exit(1);
+        }
+        String::new()
     });
 
-    let model =
-        std::env::var("AI_ASSIST_MODEL").unwrap_or_else(|_| "claude-sonnet-4-20250514".to_string());
+    let model = std::env::var("AI_ASSIST_MODEL").unwrap_or_else(|_| {
+        match backend {
+            LlmBackend::Anthropic => "claude-sonnet-4-20250514",
+            LlmBackend::OpenAi => "gpt-4o",
+            LlmBackend::Ollama => "llama3.1",
+            LlmBackend::OpenAiCompatible => {
+                eprintln!("Error: AI_ASSIST_MODEL must be set for the openai_compatible backend");
+                std::process::exit(1);
+            }
+        }
+        .to_string()
+    });
 
     let ws_port: u16 = std::env::var("AI_ASSIST_WS_PORT")
         .unwrap_or_else(|_| "8080".to_string())
@@ -76,13 +107,15 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     eprintln!("   Chat WS: ws://0.0.0.0:{}/ws/chat", ws_port);
     eprintln!("   Chat API: http://0.0.0.0:{}/api/chat/history", ws_port);
     eprintln!("   Card API: http://0.0.0.0:{}/api/cards", ws_port);
+    eprintln!("   Webhooks: http://0.0.0.0:{}/hooks/<path>", ws_port);
     eprintln!("   Type a message and press Enter. /quit to exit.\n");
 
     // Create LLM provider
     let llm_config = LlmConfig {
-        backend: LlmBackend::Anthropic,
+        backend,
         api_key: secrecy::SecretString::from(api_key),
         model,
+        base_url: std::env::var("AI_ASSIST_LLM_BASE_URL").ok(),
     };
     let llm = create_provider(&llm_config)?;
 
@@ -102,15 +135,66 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     eprintln!("   Database: {}", db_path);
 
+    // ── Model pricing (bundled table + llm_pricing setting overrides) ──
+    match ai_assist::llm::costs::load_overrides(db.as_ref()).await {
+        Ok(0) => {}
+        Ok(count) => eprintln!("   Pricing: {} model override(s)", count),
+        Err(e) => eprintln!("   Warning: Ignoring invalid pricing overrides: {}", e),
+    }
+
+    // ── Email accounts (EMAIL_ACCOUNTS_FILE or single-account EMAIL_* vars) ──
+    let email_accounts = EmailAccounts::from_env().unwrap_or_else(|e| {
+        eprintln!("Error: invalid email accounts: {e}");
+        std::process::exit(1);
+    });
+
+    // ── Secrets that must never leave the server (redacted from cards and tool output) ──
+    let known_secrets: Vec<String> = ["ANTHROPIC_API_KEY", "OPENAI_API_KEY", "TELEGRAM_BOT_TOKEN"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .chain(email_accounts.passwords().map(str::to_string))
+        .collect();
+
     // ── Card System ─────────────────────────────────────────────────────
-    let card_queue = CardQueue::with_db(Arc::clone(&db)).await;
+    let card_queue = CardQueue::with_db_and_leak_detector(
+        Arc::clone(&db),
+        LeakDetector::new().with_known_secrets(known_secrets.clone()),
+    )
+    .await;
+
+    // ── LLM budgets (soft → cheaper model, hard → pause background work) ──
+    let budget_guard = Arc::new(
+        BudgetGuard::load(Arc::clone(&db), Some(Arc::clone(&card_queue)))
+            .await
+            .unwrap_or_else(|e| {
+                eprintln!("Error: failed to load budgets: {e}");
+                std::process::exit(1);
+            }),
+    );
+
+    // ── Model routing (AI_ASSIST_MODEL_<ROLE> chains; default model otherwise) ──
+    let llm_router = Arc::new(
+        LlmRouter::from_env(llm)
+            .unwrap_or_else(|e| {
+                eprintln!("Error: invalid model routing: {e}");
+                std::process::exit(1);
+            })
+            .with_store(Arc::clone(&db))
+            .with_budgets(Arc::clone(&budget_guard)),
+    );
+    for (role, role_model) in llm_router.describe() {
+        if role_model != llm_config.model {
+            eprintln!("   Model ({}): {}", role.as_str(), role_model);
+        }
+    }
+    let llm = llm_router.for_role(LlmRole::Agent);
 
     let generator_config = GeneratorConfig {
         expire_minutes: card_expire_min,
         ..Default::default()
     };
     let reply_drafter = Arc::new(ReplyDrafter::new(
-        llm.clone(),
+        llm_router.for_role(LlmRole::Draft),
         generator_config,
     ));
 
@@ -152,14 +236,67 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     }
 
+    // ── Triage rules (DB-backed, hot-reloaded via /api/triage-rules) ───
+    let triage_rules: ai_assist::pipeline::rules::SharedRules = Arc::new(tokio::sync::RwLock::new(
+        ai_assist::pipeline::rules::load_rules(db.as_ref())
+            .await
+            .unwrap_or_else(|e| {
+                tracing::warn!(error = %e, "Failed to load triage rules, using defaults");
+                ai_assist::pipeline::rules::RulesEngine::default_rules()
+            }),
+    ));
+
+    // ── Approval rules (DB-backed, hot-reloaded via /api/approval-rules) ──
+    let approval_policies: ai_assist::agent::approval_policy::SharedApprovalPolicies =
+        Arc::new(tokio::sync::RwLock::new(
+            ai_assist::agent::approval_policy::load_approval_policies(db.as_ref())
+                .await
+                .unwrap_or_else(|e| {
+                    tracing::warn!(error = %e, "Failed to load approval rules, using tool defaults");
+                    ai_assist::agent::approval_policy::ApprovalPolicies::empty()
+                }),
+        ));
+
+    // ── Triage feedback (learned ignore rules from dismissals) ───────
+    let feedback_config = ai_assist::config::FeedbackConfig::from_env();
+    let triage_feedback = if feedback_config.enabled {
+        let feedback = Arc::new(ai_assist::pipeline::feedback::TriageFeedback::new(
+            Arc::clone(&db),
+            card_queue.clone(),
+            Arc::clone(&triage_rules),
+            feedback_config.min_dismissals,
+        ));
+        let (_feedback_handle, _feedback_shutdown) =
+            ai_assist::pipeline::feedback::spawn_feedback_loop(
+                Arc::clone(&feedback),
+                feedback_config.interval_secs,
+            );
+        eprintln!(
+            "   Triage feedback: enabled (suggest after {} dismissals)",
+            feedback_config.min_dismissals
+        );
+        Some(feedback)
+    } else {
+        None
+    };
+
     // Spawn card expiry sweep task (runs every 60s)
     let _expiry_handle = queue::spawn_expiry_task(card_queue.clone());
 
-    // Build EmailConfig for the card server (so approve/edit can send replies)
-    let email_config_for_cards = EmailConfig::from_env();
-
     // ── Agent Config (created early — Scheduler needs it) ──────────────
-    let agent_config = AgentConfig::from_env();
+    let mut agent_config = AgentConfig::from_env();
+
+    // Size the context window to the served model unless configured explicitly
+    if std::env::var("AI_ASSIST_MAX_CONTEXT_TOKENS").is_err() {
+        match llm.model_metadata().await {
+            Ok(meta) => {
+                if let Some(context_length) = meta.context_length {
+                    agent_config.max_context_tokens = context_length as usize;
+                }
+            }
+            Err(e) => tracing::warn!(error = %e, "Failed to fetch model metadata"),
+        }
+    }
 
     tracing::info!(
         max_workers = agent_config.max_parallel_jobs,
@@ -170,7 +307,21 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     );
 
     // ── Safety (shared between Scheduler and Agent) ──────────────────
-    let safety = Arc::new(SafetyLayer::new());
+    let safety_config = SafetyConfig::from_env();
+    let shell_policy = match ShellPolicy::from_file(safety_config.shell_policy_file.as_deref()) {
+        Ok(p) => Arc::new(p),
+        Err(e) => {
+            eprintln!("Error: invalid shell policy: {e}");
+            std::process::exit(1);
+        }
+    };
+    let safety = match SafetyLayer::from_config(safety_config) {
+        Ok(s) => Arc::new(s.with_known_secrets(known_secrets)),
+        Err(e) => {
+            eprintln!("Error: invalid safety policy: {e}");
+            std::process::exit(1);
+        }
+    };
 
     // ── Workspace ─────────────────────────────────────────────────────────
     let workspace_path = std::env::var("AI_ASSIST_WORKSPACE")
@@ -185,9 +336,27 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     }
     eprintln!("   Workspace: {}", workspace_path.display());
 
+    // ── Sandbox (Container-domain tools) ─────────────────────────────────
+    let sandbox = match Sandbox::from_config(SandboxConfig::from_env()) {
+        Ok(s) => s.map(Arc::new),
+        Err(e) => {
+            eprintln!("Error: {e}");
+            std::process::exit(1);
+        }
+    };
+    match &sandbox {
+        Some(s) => eprintln!(
+            "   Sandbox: {} (scratch {}, network {})",
+            s.backend().name(),
+            s.config().scratch_root.display(),
+            if s.config().network { "on" } else { "off" }
+        ),
+        None => eprintln!("   Sandbox: disabled — shell commands run on the host"),
+    }
+
     // ── Tools ────────────────────────────────────────────────────────────
     let tools = Arc::new(ToolRegistry::new());
-    tools.register_file_tools();
+    tools.register_file_tools(sandbox, shell_policy);
     tools.register_memory_tools(Arc::clone(&workspace));
     tools.register_document_tools(Arc::clone(&db));
 
@@ -216,11 +385,12 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         let engine = Arc::new(RoutineEngine::new(
             routine_config.clone(),
             Arc::clone(&db),
-            llm.clone(),
+            llm_router.for_role(LlmRole::Routine),
             None, // Workspace not yet implemented
             notify_tx,
             Some(Arc::clone(&scheduler)),
-        ));
+        )
+        .with_budgets(Arc::clone(&budget_guard)));
 
         // Refresh event cache on startup
         engine.refresh_event_cache().await;
@@ -263,6 +433,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let todo_agent_deps = ai_assist::agent::todo_agent::TodoAgentDeps {
         db: Arc::clone(&db),
         llm: llm.clone(),
+        compaction_llm: llm_router.for_role(LlmRole::Compaction),
         safety: Arc::clone(&safety),
         tools: Arc::clone(&tools),
         workspace: Arc::clone(&workspace),
@@ -270,6 +441,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         todo_tx: todo_state.tx.clone(),
         card_queue: card_queue.clone(),
         approval_registry: approval_registry.clone(),
+        approval_policies: Arc::clone(&approval_policies),
+        budgets: Some(Arc::clone(&budget_guard)),
     };
 
     let agent_queue = ai_assist::agent::agent_queue::AgentQueue::new(
@@ -286,6 +459,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let choice_registry = ai_assist::cards::choice_registry::ChoiceRegistry::new();
     tools.register_ask_user_tool(card_queue.clone(), choice_registry.clone());
     tools.register_message_tools(card_queue.clone());
+    let calendar_config = ai_assist::config::CalendarConfig::from_env();
+    let caldav = ai_assist::calendar::caldav::CalDavClient::from_config(&calendar_config).map(Arc::new);
+    tools.register_calendar_tools(Arc::clone(&db), card_queue.clone(), calendar_config.owner_email.clone());
     let activity_state = ActivityState::new(
         Arc::clone(&db),
         activity_tx.clone(),
@@ -306,44 +482,6 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let ios_channel = IosChannel::new(Some(Arc::clone(&db)));
     let ios_router = ios_channel.router();
 
-    // Spawn Axum WS/REST server — cards + iOS chat + todos + activity
-    let app = card_routes(
-        card_queue.clone(),
-        email_config_for_cards,
-        reply_drafter.clone(),
-        approval_registry,
-        activity_tx.clone(),
-        choice_registry,
-        Arc::clone(&db),
-        todo_state.tx.clone(),
-        Some(Arc::clone(&agent_queue)),
-    )
-    .merge(ios_router)
-    .merge(todo_routes(todo_state))
-    .merge(activity_routes(activity_state))
-    .merge(document_routes(DocumentState { db: Arc::clone(&db) }));
-    tokio::spawn(async move {
-        let listener = tokio::net::TcpListener::bind(format!("0.0.0.0:{}", ws_port))
-            .await
-            .expect("Failed to bind card server port");
-        tracing::info!(port = ws_port, "Card WebSocket server started");
-        axum::serve(listener, app).await.ok();
-    });
-
-    // ── Agent ───────────────────────────────────────────────────────────
-    let llm_for_pipeline = llm.clone();
-    let deps = AgentDeps {
-        store: Some(Arc::clone(&db)),
-        llm,
-        safety,
-        tools,
-        workspace: Some(Arc::clone(&workspace)),
-        extension_manager: None,
-        reply_drafter: Some(reply_drafter),
-        card_queue: Some(card_queue.clone()),
-        routine_engine,
-    };
-
     // Set up channels
     let mut channels = ChannelManager::new();
     let mut active_channels = vec!["cli", "ios"];
@@ -388,38 +526,163 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         active_channels.push("telegram");
     }
 
-    // Conditionally add Email pipeline if IMAP host is set
-    // Email no longer goes through the agent loop — it uses the standalone pipeline:
-    //   IMAP poller → messages DB → email processor → pipeline → cards
-    if let Some(email_config) = EmailConfig::from_env() {
-        let senders = &email_config.allowed_senders;
+    let channels = Arc::new(channels);
+
+    // Outbox: holds approved Reply/Compose/Meeting cards (and NewEvent cards when
+    // CalDAV is configured) for the undo window, then delivers them with retries
+    let outbox_config = ai_assist::config::OutboxConfig::from_env();
+    let mut delivery =
+        ai_assist::cards::outbox::ChannelDelivery::new(email_accounts.clone(), Some(Arc::clone(&channels)));
+    if let Some(ref client) = caldav {
+        delivery = delivery.with_calendar(Arc::clone(client), Arc::clone(&db));
+    }
+    let outbox = Arc::new(
+        ai_assist::cards::outbox::Outbox::new(Arc::clone(&db), card_queue.clone(), Arc::new(delivery))
+        .with_undo_window(std::time::Duration::from_secs(outbox_config.undo_window_secs)),
+    );
+    let (_outbox_handle, _outbox_shutdown) = ai_assist::cards::outbox::spawn_outbox_worker(
+        Arc::clone(&outbox),
+        ai_assist::cards::outbox::POLL_INTERVAL_SECS,
+    );
+
+    // Calendar: mirror the CalDAV calendar for conflict checks and the calendar tools
+    if let Some(ref client) = caldav {
         eprintln!(
-            "   Email: enabled (IMAP: {}, SMTP: {}, allowed: {})",
-            email_config.imap_host,
-            email_config.smtp_host,
-            if senders.iter().any(|s| s == "*") {
-                "everyone".to_string()
-            } else if senders.is_empty() {
-                "none (deny all)".to_string()
-            } else {
-                senders.join(", ")
-            }
+            "   Calendar: CalDAV sync every {}s ({} days ahead)",
+            calendar_config.sync_interval_secs, calendar_config.sync_days
         );
+        let sync = Arc::new(ai_assist::calendar::sync::CalendarSync::new(
+            Arc::clone(&db),
+            Arc::clone(client),
+            calendar_config.sync_days,
+        ));
+        let (_calendar_handle, _calendar_shutdown) =
+            ai_assist::calendar::sync::spawn_calendar_sync(sync, calendar_config.sync_interval_secs);
+    }
 
-        // Spawn IMAP poller (persists to DB, marks \Seen)
-        let (_poller_handle, _poller_shutdown) =
-            ai_assist::channels::email_poller::spawn_email_poller(
-                email_config.clone(),
-                Arc::clone(&db),
+    // Spawn Axum WS/REST server — cards + iOS chat + todos + activity
+    let app = card_routes(
+        card_queue.clone(),
+        email_accounts.clone(),
+        reply_drafter.clone(),
+        approval_registry,
+        activity_tx.clone(),
+        choice_registry,
+        Arc::clone(&db),
+        todo_state.tx.clone(),
+        Some(Arc::clone(&agent_queue)),
+        triage_feedback,
+        Some(Arc::clone(&budget_guard)),
+        Some(Arc::clone(&outbox)),
+        caldav.is_some(),
+    )
+    .merge(ios_router)
+    .merge(todo_routes(todo_state))
+    .merge(activity_routes(activity_state))
+    .merge(document_routes(DocumentState { db: Arc::clone(&db) }))
+    .merge(ai_assist::pipeline::routes::triage_rule_routes(
+        ai_assist::pipeline::routes::TriageRulesState {
+            db: Arc::clone(&db),
+            rules: Arc::clone(&triage_rules),
+        },
+    ))
+    .merge(ai_assist::agent::approval_routes::approval_rule_routes(
+        ai_assist::agent::approval_routes::ApprovalRulesState {
+            db: Arc::clone(&db),
+            policies: Arc::clone(&approval_policies),
+        },
+    ))
+    .merge(ai_assist::llm::routes::llm_routes(
+        ai_assist::llm::routes::LlmRoutesState {
+            db: Arc::clone(&db),
+            budgets: Arc::clone(&budget_guard),
+            router: Arc::clone(&llm_router),
+        },
+    ));
+    let app = match &routine_engine {
+        Some(engine) => app.merge(webhook_routes(WebhookState {
+            engine: Arc::clone(engine),
+            db: Arc::clone(&db),
+        })),
+        None => app,
+    };
+    tokio::spawn(async move {
+        let listener = tokio::net::TcpListener::bind(format!("0.0.0.0:{}", ws_port))
+            .await
+            .expect("Failed to bind card server port");
+        tracing::info!(port = ws_port, "Card WebSocket server started");
+        axum::serve(listener, app).await.ok();
+    });
+
+    // ── Agent ───────────────────────────────────────────────────────────
+    let deps = AgentDeps {
+        store: Some(Arc::clone(&db)),
+        llm,
+        compaction_llm: Some(llm_router.for_role(LlmRole::Compaction)),
+        safety,
+        tools,
+        workspace: Some(Arc::clone(&workspace)),
+        extension_manager: None,
+        reply_drafter: Some(reply_drafter),
+        card_queue: Some(card_queue.clone()),
+        routine_engine,
+        approval_policies,
+    };
+
+    // Conditionally add Email pipeline if any email account is configured
+    // Email no longer goes through the agent loop — it uses the standalone pipeline:
+    //   IMAP poller → messages DB → email processor → pipeline → cards
+    if !email_accounts.is_empty() {
+        for email_config in email_accounts.iter() {
+            let senders = &email_config.allowed_senders;
+            eprintln!(
+                "   Email [{}]: enabled (IMAP: {}, SMTP: {}, allowed: {})",
+                email_config.account_id,
+                email_config.imap_host,
+                email_config.smtp_host,
+                if senders.iter().any(|s| s == "*") {
+                    "everyone".to_string()
+                } else if senders.is_empty() {
+                    "none (deny all)".to_string()
+                } else {
+                    senders.join(", ")
+                }
             );
 
+            // Spawn one IMAP poller per account (persists to DB; leaves flags alone)
+            let (_poller_handle, _poller_shutdown) =
+                ai_assist::channels::email_poller::spawn_email_poller(
+                    email_config.clone(),
+                    Arc::clone(&db),
+                );
+        }
+
         // Create pipeline processor for emails
-        let rules = ai_assist::pipeline::rules::RulesEngine::default_rules();
-        let email_pipeline = Arc::new(ai_assist::pipeline::processor::MessageProcessor::new(
-            llm_for_pipeline.clone(),
+        let email_pipeline = Arc::new(ai_assist::pipeline::processor::MessageProcessor::with_shared_rules(
+            llm_router.for_role(LlmRole::Triage),
             card_queue.clone(),
-            rules,
-        ));
+            Arc::clone(&triage_rules),
+        )
+        .with_digest_store(Arc::clone(&db))
+        .with_calendar_store(Arc::clone(&db)));
+
+        // Spawn digest scheduler (rolls low-priority items into one card at DIGEST_TIMES)
+        let digest_config = ai_assist::config::DigestConfig::from_env();
+        if digest_config.enabled {
+            let aggregator = Arc::new(ai_assist::pipeline::digest::DigestAggregator::new(
+                llm_router.for_role(LlmRole::Summary),
+                Arc::clone(&db),
+                card_queue.clone(),
+            ));
+            let times: Vec<String> = digest_config
+                .times
+                .iter()
+                .map(|t| t.format("%H:%M").to_string())
+                .collect();
+            eprintln!("   Digest: {}", times.join(", "));
+            let (_digest_handle, _digest_shutdown) =
+                ai_assist::pipeline::digest::spawn_digest_scheduler(aggregator, digest_config);
+        }
 
         // Spawn background email processor (timer-based)
         let (_processor_handle, _processor_shutdown) =
@@ -434,7 +697,11 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     eprintln!("   Channels: {}\n", active_channels.join(", "));
 
-    let agent = Agent::new(agent_config, deps, channels, None);
+    // Sessions and threads survive restarts (pending approvals, undo history)
+    let session_manager = Arc::new(
+        ai_assist::agent::session_manager::SessionManager::new().with_store(Arc::clone(&db)),
+    );
+    let agent = Agent::new(agent_config, deps, channels, Some(session_manager));
     agent.run().await?;
 
     Ok(())
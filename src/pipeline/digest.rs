@@ -0,0 +1,598 @@
+//! Digest aggregation — batches low-priority messages into periodic summary cards.
+//!
+//! Flow:
+//! 1. Triage returns `TriageAction::Digest` → `MessageProcessor` stores a `DigestItem`
+//! 2. At each configured time (`DIGEST_TIMES`), `DigestAggregator::roll_up()` loads
+//!    every pending item, groups them per channel/sender, asks the LLM for a short
+//!    overview, and pushes one `CardPayload::Digest` card
+//! 3. From the expanded card, the user can promote any item to its own Reply card
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::task::JoinHandle;
+use tracing::{debug, error, info, warn};
+use uuid::Uuid;
+
+use crate::cards::model::{ApprovalCard, CardPayload, CardSilo, DigestEntry, DigestGroup};
+use crate::cards::queue::CardQueue;
+use crate::cards::reply_drafter::ReplyDrafter;
+use crate::config::DigestConfig;
+use crate::error::PipelineError;
+use crate::llm::provider::{ChatMessage, CompletionRequest, LlmProvider};
+use crate::pipeline::types::InboundMessage;
+use crate::store::Database;
+
+/// Digest cards stay around until roughly the next roll-up.
+const DIGEST_CARD_EXPIRE_MINUTES: u32 = 12 * 60;
+
+/// Max tokens for the digest summary LLM call.
+const SUMMARY_MAX_TOKENS: u32 = 300;
+
+/// Temperature for the digest summary.
+const SUMMARY_TEMPERATURE: f32 = 0.3;
+
+/// Items beyond this count are left out of the summary prompt (still shown on the card).
+const SUMMARY_MAX_ITEMS: usize = 50;
+
+/// A low-priority message parked in the `digest_items` table.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DigestItem {
+    pub id: String,
+    /// Pipeline message ID (`messages.id` for email).
+    pub message_id: String,
+    pub channel: String,
+    pub sender: String,
+    pub subject: Option<String>,
+    /// One-line summary from triage.
+    pub summary: String,
+    /// Full message body — needed when the item is promoted to a reply.
+    pub content: String,
+    pub reply_metadata: Option<serde_json::Value>,
+    pub received_at: DateTime<Utc>,
+    /// Digest card this item was rolled into (`None` = still pending).
+    pub digest_card_id: Option<Uuid>,
+    /// Reply card created when the user promoted this item.
+    pub promoted_card_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl DigestItem {
+    /// Build a pending digest item from a triaged inbound message.
+    pub fn from_inbound(message: &InboundMessage, summary: &str) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            message_id: message.id.clone(),
+            channel: message.channel.clone(),
+            sender: message.sender.clone(),
+            subject: message.subject.clone(),
+            summary: summary.to_string(),
+            content: message.content.clone(),
+            reply_metadata: if message.reply_metadata.is_null() {
+                None
+            } else {
+                Some(message.reply_metadata.clone())
+            },
+            received_at: message.received_at,
+            digest_card_id: None,
+            promoted_card_id: None,
+            created_at: Utc::now(),
+        }
+    }
+}
+
+/// Group items per channel/sender, keeping the order in which each sender first appears.
+pub fn group_items(items: &[DigestItem]) -> Vec<DigestGroup> {
+    let mut groups: Vec<DigestGroup> = Vec::new();
+    for item in items {
+        let entry = DigestEntry {
+            item_id: item.id.clone(),
+            subject: item.subject.clone(),
+            summary: item.summary.clone(),
+            received_at: item.received_at,
+        };
+        match groups
+            .iter_mut()
+            .find(|g| g.channel == item.channel && g.sender == item.sender)
+        {
+            Some(group) => group.items.push(entry),
+            None => groups.push(DigestGroup {
+                channel: item.channel.clone(),
+                sender: item.sender.clone(),
+                items: vec![entry],
+            }),
+        }
+    }
+    groups
+}
+
+/// Rolls pending digest items into a single summary card.
+pub struct DigestAggregator {
+    llm: Arc<dyn LlmProvider>,
+    db: Arc<dyn Database>,
+    card_queue: Arc<CardQueue>,
+}
+
+impl DigestAggregator {
+    pub fn new(llm: Arc<dyn LlmProvider>, db: Arc<dyn Database>, card_queue: Arc<CardQueue>) -> Self {
+        Self {
+            llm,
+            db,
+            card_queue,
+        }
+    }
+
+    /// Build and push a digest card from all pending items.
+    ///
+    /// Returns `None` when there is nothing to digest. Items are claimed for the
+    /// card before it is built, so a failed write or an overlapping roll-up
+    /// never produces duplicate digests.
+    pub async fn roll_up(&self) -> Result<Option<ApprovalCard>, PipelineError> {
+        let items = self
+            .db
+            .list_pending_digest_items()
+            .await
+            .map_err(|e| PipelineError::CardCreation(format!("load digest items: {e}")))?;
+        if items.is_empty() {
+            return Ok(None);
+        }
+
+        let card_id = Uuid::new_v4();
+        let ids: Vec<String> = items.iter().map(|i| i.id.clone()).collect();
+        let assigned = self
+            .db
+            .assign_digest_items(&ids, card_id)
+            .await
+            .map_err(|e| PipelineError::CardCreation(format!("assign digest items: {e}")))?;
+        let items: Vec<DigestItem> = items
+            .into_iter()
+            .filter(|item| assigned.contains(&item.id))
+            .collect();
+        if items.is_empty() {
+            return Ok(None);
+        }
+
+        let groups = group_items(&items);
+        let summary = match self.summarize(&groups).await {
+            Ok(summary) => summary,
+            Err(e) => {
+                warn!(error = %e, "Digest summary failed, using plain overview");
+                fallback_summary(&groups)
+            }
+        };
+
+        let mut card = ApprovalCard::new(
+            CardPayload::Digest { summary, groups },
+            CardSilo::Messages,
+            DIGEST_CARD_EXPIRE_MINUTES,
+        );
+        card.id = card_id;
+
+        self.card_queue.push(card.clone()).await;
+        info!(card_id = %card.id, items = items.len(), "Created digest card");
+        Ok(Some(card))
+    }
+
+    /// Ask the LLM for a short overview of the grouped items.
+    async fn summarize(&self, groups: &[DigestGroup]) -> Result<String, PipelineError> {
+        let request = CompletionRequest::new(vec![
+            ChatMessage::system(build_summary_system_prompt()),
+            ChatMessage::user(build_summary_user_prompt(groups)),
+        ])
+        .with_temperature(SUMMARY_TEMPERATURE)
+        .with_max_tokens(SUMMARY_MAX_TOKENS);
+
+        let response = self.llm.complete(request).await?;
+        let summary = response.content.trim();
+        if summary.is_empty() {
+            return Err(PipelineError::CardCreation("empty digest summary".into()));
+        }
+        Ok(summary.to_string())
+    }
+}
+
+/// Promote one item of a digest card to its own Reply card.
+///
+/// Claims the item for the new card first, so concurrent promotes of the same
+/// item yield one card. Then drafts a reply via `ReplyDrafter` (an empty draft
+/// when it has nothing to suggest); if drafting fails the claim is released so
+/// the item can be promoted again.
+pub async fn promote_item(
+    db: &Arc<dyn Database>,
+    queue: &Arc<CardQueue>,
+    drafter: &ReplyDrafter,
+    digest_card_id: Uuid,
+    item_id: &str,
+) -> Result<ApprovalCard, String> {
+    let item = db
+        .get_digest_item(item_id)
+        .await
+        .map_err(|e| format!("Failed to load digest item: {e}"))?
+        .ok_or_else(|| "Digest item not found".to_string())?;
+
+    if item.digest_card_id != Some(digest_card_id) {
+        return Err("Digest item does not belong to this card".into());
+    }
+
+    let card_id = Uuid::new_v4();
+    let claimed = db
+        .mark_digest_item_promoted(item_id, card_id)
+        .await
+        .map_err(|e| format!("Failed to record promotion: {e}"))?;
+    if !claimed {
+        return Err("Digest item was already promoted".into());
+    }
+
+    let (suggested_reply, confidence) =
+        match drafter.draft(&item.content, &item.sender, &item.message_id).await {
+            Ok(Some(draft)) => (draft.text, draft.confidence),
+            Ok(None) => (String::new(), 0.0),
+            Err(e) => {
+                warn!(item_id, error = %e, "Draft for promoted digest item failed");
+                if let Err(e) = db.clear_digest_item_promotion(item_id, card_id).await {
+                    error!(item_id, error = %e, "Failed to release digest item claim");
+                }
+                return Err(format!("Failed to draft a reply: {e}"));
+            }
+        };
+
+    let mut card = ApprovalCard::new(
+        CardPayload::Reply {
+            channel: item.channel.clone(),
+            source_sender: item.sender.clone(),
+            source_message: item.content.clone(),
+            suggested_reply,
+            confidence: confidence.clamp(0.0, 1.0),
+            conversation_id: item.message_id.clone(),
+            thread: Vec::new(),
+            email_thread: Vec::new(),
+            reply_metadata: item.reply_metadata.clone(),
+            message_id: Some(item.message_id.clone()),
+        },
+        CardSilo::Messages,
+        drafter.expire_minutes(),
+    );
+    card.id = card_id;
+
+    queue.push(card.clone()).await;
+    info!(item_id, card_id = %card.id, "Promoted digest item to reply card");
+    Ok(card)
+}
+
+/// Spawn a background task that rolls up digest items at the configured times.
+///
+/// Returns a `JoinHandle` and shutdown flag.
+pub fn spawn_digest_scheduler(
+    aggregator: Arc<DigestAggregator>,
+    config: DigestConfig,
+) -> (JoinHandle<()>, Arc<AtomicBool>) {
+    let shutdown = Arc::new(AtomicBool::new(false));
+    let shutdown_flag = Arc::clone(&shutdown);
+
+    let handle = tokio::spawn(async move {
+        info!(times = ?config.times, "Digest scheduler started");
+
+        loop {
+            let now = Local::now().naive_local();
+            let Some(next) = config.next_fire_after(now) else {
+                warn!("No digest times configured — scheduler exiting");
+                return;
+            };
+            let wait = (next - now).to_std().unwrap_or(Duration::from_secs(60));
+            debug!(next = %next, "Next digest roll-up scheduled");
+            tokio::time::sleep(wait).await;
+
+            if shutdown.load(Ordering::Relaxed) {
+                info!("Digest scheduler shutting down");
+                return;
+            }
+
+            match aggregator.roll_up().await {
+                Ok(Some(_)) => {}
+                Ok(None) => debug!("No pending digest items"),
+                Err(e) => error!(error = %e, "Digest roll-up failed"),
+            }
+        }
+    });
+
+    (handle, shutdown_flag)
+}
+
+// ── Prompt construction ─────────────────────────────────────────────
+
+fn build_summary_system_prompt() -> String {
+    "You write a short digest of low-priority messages for a busy person.\n\n\
+     Rules:\n\
+     - 2-4 sentences of plain text, no lists or markdown\n\
+     - Mention who wrote and what it's about; group related items\n\
+     - Call out anything that might deserve a reply after all\n\
+     - Don't invent details that aren't in the summaries"
+        .to_string()
+}
+
+fn build_summary_user_prompt(groups: &[DigestGroup]) -> String {
+    let mut prompt = String::with_capacity(1024);
+    let mut remaining = SUMMARY_MAX_ITEMS;
+    for group in groups {
+        if remaining == 0 {
+            break;
+        }
+        prompt.push_str(&format!(
+            "{} — {} ({} message(s)):\n",
+            group.channel,
+            group.sender,
+            group.items.len()
+        ));
+        for entry in group.items.iter().take(remaining) {
+            match &entry.subject {
+                Some(subject) => prompt.push_str(&format!("  - {}: {}\n", subject, entry.summary)),
+                None => prompt.push_str(&format!("  - {}\n", entry.summary)),
+            }
+            remaining -= 1;
+        }
+    }
+    prompt
+}
+
+/// Plain overview used when the LLM summary is unavailable.
+fn fallback_summary(groups: &[DigestGroup]) -> String {
+    let count: usize = groups.iter().map(|g| g.items.len()).sum();
+    format!(
+        "{} low-priority message(s) from {} sender(s).",
+        count,
+        groups.len()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cards::reply_drafter::GeneratorConfig;
+    use crate::llm::provider::{CompletionResponse, FinishReason};
+    use crate::store::LibSqlBackend;
+
+    struct MockLlm {
+        response: Result<String, ()>,
+    }
+
+    #[async_trait::async_trait]
+    impl LlmProvider for MockLlm {
+        fn model_name(&self) -> &str {
+            "mock-digest"
+        }
+
+        fn cost_per_token(&self) -> (rust_decimal::Decimal, rust_decimal::Decimal) {
+            (rust_decimal::Decimal::ZERO, rust_decimal::Decimal::ZERO)
+        }
+
+        async fn complete(
+            &self,
+            _request: CompletionRequest,
+        ) -> Result<CompletionResponse, crate::error::LlmError> {
+            match &self.response {
+                Ok(content) => Ok(CompletionResponse {
+                    content: content.clone(),
+                    input_tokens: 10,
+                    output_tokens: 10,
+                    cache_read_tokens: 0,
+                    cache_write_tokens: 0,
+                    finish_reason: FinishReason::Stop,
+                    response_id: None,
+                    provider: "mock".into(),
+                    model: self.model_name().into(),
+                }),
+                Err(()) => Err(crate::error::LlmError::RequestFailed {
+                    provider: "mock".into(),
+                    reason: "down".into(),
+                }),
+            }
+        }
+
+        async fn complete_with_tools(
+            &self,
+            _request: crate::llm::provider::ToolCompletionRequest,
+        ) -> Result<crate::llm::provider::ToolCompletionResponse, crate::error::LlmError> {
+            unimplemented!("mock does not support tool completion")
+        }
+    }
+
+    fn inbound(id: &str, channel: &str, sender: &str, subject: Option<&str>) -> InboundMessage {
+        InboundMessage {
+            id: id.into(),
+            channel: channel.into(),
+            sender: sender.into(),
+            sender_name: None,
+            content: format!("Body of {id}"),
+            subject: subject.map(String::from),
+            thread_context: vec![],
+            reply_metadata: serde_json::json!({"reply_to": sender}),
+            received_at: Utc::now(),
+            priority_hints: Default::default(),
+        }
+    }
+
+    async fn setup(llm_response: Result<String, ()>) -> (Arc<dyn Database>, Arc<CardQueue>, DigestAggregator) {
+        let db: Arc<dyn Database> = Arc::new(LibSqlBackend::new_memory().await.unwrap());
+        let queue = CardQueue::new();
+        let llm: Arc<dyn LlmProvider> = Arc::new(MockLlm { response: llm_response });
+        let aggregator = DigestAggregator::new(llm, Arc::clone(&db), queue.clone());
+        (db, queue, aggregator)
+    }
+
+    #[test]
+    fn group_items_per_channel_and_sender() {
+        let items = vec![
+            DigestItem::from_inbound(&inbound("1", "email", "a@x.com", Some("One")), "first"),
+            DigestItem::from_inbound(&inbound("2", "email", "b@x.com", None), "second"),
+            DigestItem::from_inbound(&inbound("3", "email", "a@x.com", Some("Three")), "third"),
+            DigestItem::from_inbound(&inbound("4", "telegram", "a@x.com", None), "fourth"),
+        ];
+        let groups = group_items(&items);
+
+        assert_eq!(groups.len(), 3);
+        assert_eq!(groups[0].sender, "a@x.com");
+        assert_eq!(groups[0].channel, "email");
+        assert_eq!(groups[0].items.len(), 2);
+        assert_eq!(groups[0].items[1].summary, "third");
+        assert_eq!(groups[1].sender, "b@x.com");
+        assert_eq!(groups[2].channel, "telegram");
+    }
+
+    #[test]
+    fn summary_prompt_lists_groups() {
+        let items = vec![
+            DigestItem::from_inbound(&inbound("1", "email", "a@x.com", Some("Invoice")), "March invoice"),
+            DigestItem::from_inbound(&inbound("2", "email", "a@x.com", None), "Reminder"),
+        ];
+        let prompt = build_summary_user_prompt(&group_items(&items));
+        assert!(prompt.contains("email — a@x.com (2 message(s))"));
+        assert!(prompt.contains("Invoice: March invoice"));
+        assert!(prompt.contains("- Reminder"));
+    }
+
+    #[tokio::test]
+    async fn roll_up_with_no_items_creates_nothing() {
+        let (_db, queue, aggregator) = setup(Ok("unused".into())).await;
+        assert!(aggregator.roll_up().await.unwrap().is_none());
+        assert!(queue.pending().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn roll_up_creates_single_digest_card() {
+        let (db, queue, aggregator) = setup(Ok("Two newsletters and a receipt.".into())).await;
+        for (id, sender) in [("m1", "news@x.com"), ("m2", "news@x.com"), ("m3", "shop@y.com")] {
+            db.insert_digest_item(&DigestItem::from_inbound(&inbound(id, "email", sender, None), id))
+                .await
+                .unwrap();
+        }
+
+        let card = aggregator.roll_up().await.unwrap().unwrap();
+        match &card.payload {
+            CardPayload::Digest { summary, groups } => {
+                assert_eq!(summary, "Two newsletters and a receipt.");
+                assert_eq!(groups.len(), 2);
+                assert_eq!(groups[0].items.len(), 2);
+            }
+            other => panic!("expected Digest, got {other:?}"),
+        }
+        assert_eq!(queue.pending().await.len(), 1);
+
+        // Items are consumed — the next roll-up has nothing to do.
+        assert!(db.list_pending_digest_items().await.unwrap().is_empty());
+        assert!(aggregator.roll_up().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn roll_up_falls_back_when_llm_fails() {
+        let (db, _queue, aggregator) = setup(Err(())).await;
+        db.insert_digest_item(&DigestItem::from_inbound(&inbound("m1", "email", "a@x.com", None), "hi"))
+            .await
+            .unwrap();
+
+        let card = aggregator.roll_up().await.unwrap().unwrap();
+        match &card.payload {
+            CardPayload::Digest { summary, .. } => {
+                assert_eq!(summary, "1 low-priority message(s) from 1 sender(s).");
+            }
+            other => panic!("expected Digest, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn promote_item_creates_reply_card_once() {
+        let (db, queue, aggregator) = setup(Ok("Summary".into())).await;
+        let item = DigestItem::from_inbound(&inbound("m1", "email", "a@x.com", Some("Lunch?")), "lunch");
+        db.insert_digest_item(&item).await.unwrap();
+        let digest = aggregator.roll_up().await.unwrap().unwrap();
+
+        let drafter_llm: Arc<dyn LlmProvider> = Arc::new(MockLlm {
+            response: Ok(r#"[{"text": "Sure, noon works", "confidence": 0.8}]"#.into()),
+        });
+        let drafter = ReplyDrafter::new(drafter_llm, GeneratorConfig::default());
+
+        let wrong_card = promote_item(&db, &queue, &drafter, Uuid::new_v4(), &item.id).await;
+        assert!(wrong_card.is_err());
+
+        let reply = promote_item(&db, &queue, &drafter, digest.id, &item.id).await.unwrap();
+        assert_eq!(reply.payload.suggested_reply(), Some("Sure, noon works"));
+        assert_eq!(reply.payload.message_id(), Some("m1"));
+        assert_eq!(
+            reply.payload.reply_metadata().unwrap()["reply_to"].as_str(),
+            Some("a@x.com")
+        );
+        assert_eq!(queue.pending().await.len(), 2);
+
+        let stored = db.get_digest_item(&item.id).await.unwrap().unwrap();
+        assert_eq!(stored.promoted_card_id, Some(reply.id));
+
+        let again = promote_item(&db, &queue, &drafter, digest.id, &item.id).await;
+        assert!(again.unwrap_err().contains("already promoted"));
+    }
+
+    /// Answers after a pause, so concurrent callers interleave.
+    struct SlowLlm(MockLlm);
+
+    #[async_trait::async_trait]
+    impl LlmProvider for SlowLlm {
+        fn model_name(&self) -> &str {
+            self.0.model_name()
+        }
+
+        fn cost_per_token(&self) -> (rust_decimal::Decimal, rust_decimal::Decimal) {
+            self.0.cost_per_token()
+        }
+
+        async fn complete(
+            &self,
+            request: CompletionRequest,
+        ) -> Result<CompletionResponse, crate::error::LlmError> {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            self.0.complete(request).await
+        }
+
+        async fn complete_with_tools(
+            &self,
+            request: crate::llm::provider::ToolCompletionRequest,
+        ) -> Result<crate::llm::provider::ToolCompletionResponse, crate::error::LlmError> {
+            self.0.complete_with_tools(request).await
+        }
+    }
+
+    #[tokio::test]
+    async fn concurrent_promotes_create_one_card() {
+        let (db, queue, aggregator) = setup(Ok("Summary".into())).await;
+        let item = DigestItem::from_inbound(&inbound("m1", "email", "a@x.com", None), "lunch");
+        db.insert_digest_item(&item).await.unwrap();
+        let digest = aggregator.roll_up().await.unwrap().unwrap();
+
+        let drafter = ReplyDrafter::new(
+            Arc::new(SlowLlm(MockLlm {
+                response: Ok(r#"[{"text": "Sure", "confidence": 0.8}]"#.into()),
+            })),
+            GeneratorConfig::default(),
+        );
+        let (a, b) = tokio::join!(
+            promote_item(&db, &queue, &drafter, digest.id, &item.id),
+            promote_item(&db, &queue, &drafter, digest.id, &item.id),
+        );
+        assert_eq!(a.is_ok() as u8 + b.is_ok() as u8, 1);
+        // The digest card plus exactly one reply
+        assert_eq!(queue.pending().await.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn failed_draft_releases_the_claim() {
+        let (db, queue, aggregator) = setup(Ok("Summary".into())).await;
+        let item = DigestItem::from_inbound(&inbound("m1", "email", "a@x.com", None), "lunch");
+        db.insert_digest_item(&item).await.unwrap();
+        let digest = aggregator.roll_up().await.unwrap().unwrap();
+
+        let down = ReplyDrafter::new(Arc::new(MockLlm { response: Err(()) }), GeneratorConfig::default());
+        assert!(promote_item(&db, &queue, &down, digest.id, &item.id).await.is_err());
+        assert!(db.get_digest_item(&item.id).await.unwrap().unwrap().promoted_card_id.is_none());
+        assert_eq!(queue.pending().await.len(), 1);
+    }
+}
@@ -126,11 +126,16 @@ pub fn stored_to_inbound(stored: &StoredMessage) -> InboundMessage {
         .and_then(|s| serde_json::from_str(s).ok())
         .unwrap_or(serde_json::Value::Null);
 
-    let reply_metadata = metadata
+    let mut reply_metadata = metadata
         .get("reply_metadata")
         .cloned()
         .unwrap_or(serde_json::Value::Null);
 
+    // Tag the account so the approved reply goes out through the same mailbox.
+    if let (Some(account_id), Some(meta)) = (&stored.account_id, reply_metadata.as_object_mut()) {
+        meta.insert("account_id".into(), serde_json::Value::String(account_id.clone()));
+    }
+
     let priority_hints = PriorityHints::analyze(
         &stored.content,
         &stored.sender,
@@ -171,6 +176,7 @@ mod tests {
             status: MessageStatus::Pending,
             replied_at: None,
             metadata: Some(r#"{"reply_metadata":{"reply_to":"alice@example.com","subject":"Re: Hello there"}}"#.to_string()),
+            account_id: Some("personal".to_string()),
             created_at: Utc::now(),
             updated_at: Utc::now(),
         }
@@ -201,6 +207,10 @@ mod tests {
             inbound.reply_metadata["subject"].as_str(),
             Some("Re: Hello there")
         );
+        assert_eq!(
+            inbound.reply_metadata["account_id"].as_str(),
+            Some("personal")
+        );
     }
 
     #[test]
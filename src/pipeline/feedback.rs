@@ -0,0 +1,540 @@
+//! Triage feedback — learns ignore rules from how the user handles cards.
+//!
+//! Flow:
+//! 1. `DB::card_outcomes_by_sender()` aggregates approved/dismissed Reply cards per sender
+//! 2. Senders (and whole domains) dismissed `min_dismissals` times with no approvals
+//!    become `RuleSuggestion`s, each surfaced as a `Decision` card
+//! 3. Approving the card stores an `Ignore` triage rule and hot-reloads the engine;
+//!    dismissing it records the rejection so the same suggestion is never raised again
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::task::JoinHandle;
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::cards::model::{ApprovalCard, CardSilo};
+use crate::cards::queue::CardQueue;
+use crate::error::DatabaseError;
+use crate::pipeline::rules::{
+    PatternKind, RuleAction, RuleDecision, RuleField, SharedRules, TriageRule, reload_rules,
+};
+use crate::pipeline::types::{InboundMessage, PriorityHints, TriageAction};
+use crate::store::Database;
+
+/// Suggestion cards stay up for a week.
+const SUGGESTION_CARD_EXPIRE_MINUTES: u32 = 7 * 24 * 60;
+
+/// Shared mailbox providers — never proposed as whole-domain ignore rules.
+const PERSONAL_DOMAINS: &[&str] = &[
+    "gmail.com",
+    "googlemail.com",
+    "outlook.com",
+    "hotmail.com",
+    "live.com",
+    "yahoo.com",
+    "icloud.com",
+    "me.com",
+    "proton.me",
+    "protonmail.com",
+];
+
+/// Approve/dismiss counts for Reply cards from one sender.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SenderOutcome {
+    pub channel: String,
+    pub sender: String,
+    pub dismissed: u32,
+    /// Approved, whether or not delivery succeeded.
+    pub approved: u32,
+}
+
+/// What a suggested rule matches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SuggestionScope {
+    Sender,
+    Domain,
+}
+
+impl SuggestionScope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Sender => "sender",
+            Self::Domain => "domain",
+        }
+    }
+}
+
+impl std::str::FromStr for SuggestionScope {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "sender" => Ok(Self::Sender),
+            "domain" => Ok(Self::Domain),
+            _ => Err(format!("Unknown suggestion scope: {s}")),
+        }
+    }
+}
+
+/// Lifecycle of a suggestion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SuggestionStatus {
+    Pending,
+    Accepted,
+    Rejected,
+}
+
+impl SuggestionStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Accepted => "accepted",
+            Self::Rejected => "rejected",
+        }
+    }
+}
+
+impl std::str::FromStr for SuggestionStatus {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pending" => Ok(Self::Pending),
+            "accepted" => Ok(Self::Accepted),
+            "rejected" => Ok(Self::Rejected),
+            _ => Err(format!("Unknown suggestion status: {s}")),
+        }
+    }
+}
+
+/// A proposed ignore rule, persisted in `rule_suggestions`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleSuggestion {
+    pub id: Uuid,
+    pub scope: SuggestionScope,
+    /// Sender address or domain.
+    pub value: String,
+    /// Decision card that asks the user.
+    pub card_id: Uuid,
+    pub status: SuggestionStatus,
+    /// Dismissals observed when the suggestion was made.
+    pub dismissed: u32,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A sender or domain that crossed the dismissal threshold.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuleCandidate {
+    pub scope: SuggestionScope,
+    pub value: String,
+    pub dismissed: u32,
+}
+
+impl RuleCandidate {
+    /// The triage rule this candidate turns into when accepted.
+    pub fn to_rule(&self) -> TriageRule {
+        let pattern = match self.scope {
+            SuggestionScope::Sender => self.value.clone(),
+            SuggestionScope::Domain => format!("*@{}", self.value),
+        };
+        TriageRule::new(
+            format!("Learned: ignore {pattern}"),
+            RuleField::Sender,
+            PatternKind::Glob,
+            pattern,
+            RuleAction::Ignore,
+            format!("learned from {} dismissed cards", self.dismissed),
+        )
+    }
+}
+
+/// Domain part of an email-style sender, lowercased.
+fn sender_domain(sender: &str) -> Option<String> {
+    let (_, domain) = sender.rsplit_once('@')?;
+    let domain = domain.trim().trim_end_matches('>').to_lowercase();
+    if domain.is_empty() { None } else { Some(domain) }
+}
+
+/// Pick senders/domains dismissed at least `min_dismissals` times and never approved.
+///
+/// A domain qualifies only when two or more of its senders were dismissed and it
+/// isn't a shared mailbox provider; senders covered by a domain candidate are
+/// not proposed separately.
+pub fn find_candidates(outcomes: &[SenderOutcome], min_dismissals: u32) -> Vec<RuleCandidate> {
+    // domain → (dismissed, approved, distinct dismissed senders)
+    let mut domains: BTreeMap<String, (u32, u32, u32)> = BTreeMap::new();
+    for o in outcomes {
+        if let Some(domain) = sender_domain(&o.sender) {
+            let entry = domains.entry(domain).or_default();
+            entry.0 += o.dismissed;
+            entry.1 += o.approved;
+            if o.dismissed > 0 {
+                entry.2 += 1;
+            }
+        }
+    }
+
+    let mut candidates: Vec<RuleCandidate> = domains
+        .into_iter()
+        .filter(|(domain, (dismissed, approved, senders))| {
+            *dismissed >= min_dismissals
+                && *approved == 0
+                && *senders >= 2
+                && !PERSONAL_DOMAINS.contains(&domain.as_str())
+        })
+        .map(|(domain, (dismissed, _, _))| RuleCandidate {
+            scope: SuggestionScope::Domain,
+            value: domain,
+            dismissed,
+        })
+        .collect();
+
+    for o in outcomes {
+        if o.dismissed < min_dismissals || o.approved > 0 {
+            continue;
+        }
+        let covered = sender_domain(&o.sender).is_some_and(|d| {
+            candidates
+                .iter()
+                .any(|c| c.scope == SuggestionScope::Domain && c.value == d)
+        });
+        if !covered {
+            candidates.push(RuleCandidate {
+                scope: SuggestionScope::Sender,
+                value: o.sender.clone(),
+                dismissed: o.dismissed,
+            });
+        }
+    }
+    candidates
+}
+
+/// Turns card history into ignore-rule suggestions and applies accepted ones.
+pub struct TriageFeedback {
+    db: Arc<dyn Database>,
+    card_queue: Arc<CardQueue>,
+    rules: SharedRules,
+    min_dismissals: u32,
+}
+
+impl TriageFeedback {
+    pub fn new(
+        db: Arc<dyn Database>,
+        card_queue: Arc<CardQueue>,
+        rules: SharedRules,
+        min_dismissals: u32,
+    ) -> Self {
+        Self {
+            db,
+            card_queue,
+            rules,
+            min_dismissals,
+        }
+    }
+
+    /// Scan card history and push a Decision card for each new candidate.
+    ///
+    /// Candidates already suggested (in any state) or already ignored by the
+    /// live rules are skipped.
+    pub async fn propose_rules(&self) -> Result<Vec<ApprovalCard>, DatabaseError> {
+        let outcomes = self.db.card_outcomes_by_sender().await?;
+        let mut cards = Vec::new();
+
+        for candidate in find_candidates(&outcomes, self.min_dismissals) {
+            if self
+                .db
+                .has_rule_suggestion(candidate.scope, &candidate.value)
+                .await?
+                || self.already_ignored(&candidate).await
+            {
+                continue;
+            }
+
+            let card = suggestion_card(&candidate);
+            let suggestion = RuleSuggestion {
+                id: Uuid::new_v4(),
+                scope: candidate.scope,
+                value: candidate.value.clone(),
+                card_id: card.id,
+                status: SuggestionStatus::Pending,
+                dismissed: candidate.dismissed,
+                created_at: Utc::now(),
+            };
+            self.db.insert_rule_suggestion(&suggestion).await?;
+            self.card_queue.push(card.clone()).await;
+            info!(
+                scope = candidate.scope.as_str(),
+                value = %candidate.value,
+                dismissed = candidate.dismissed,
+                "Proposed learned ignore rule"
+            );
+            cards.push(card);
+        }
+        Ok(cards)
+    }
+
+    /// Resolve the suggestion behind a Decision card, if there is one.
+    ///
+    /// Accepting stores the ignore rule and reloads the live engine. Returns
+    /// `false` when the card isn't a rule suggestion.
+    pub async fn resolve(&self, card_id: Uuid, accepted: bool) -> Result<bool, DatabaseError> {
+        let Some(suggestion) = self.db.get_rule_suggestion_by_card(card_id).await? else {
+            return Ok(false);
+        };
+        if suggestion.status != SuggestionStatus::Pending {
+            return Ok(true);
+        }
+
+        if accepted {
+            let candidate = RuleCandidate {
+                scope: suggestion.scope,
+                value: suggestion.value.clone(),
+                dismissed: suggestion.dismissed,
+            };
+            self.db.create_triage_rule(&candidate.to_rule()).await?;
+            reload_rules(self.db.as_ref(), &self.rules).await?;
+            info!(value = %suggestion.value, "Learned ignore rule accepted");
+        }
+
+        let status = if accepted {
+            SuggestionStatus::Accepted
+        } else {
+            SuggestionStatus::Rejected
+        };
+        self.db
+            .update_rule_suggestion_status(suggestion.id, status)
+            .await?;
+        Ok(true)
+    }
+
+    /// Would the live rules already drop mail from this sender/domain?
+    async fn already_ignored(&self, candidate: &RuleCandidate) -> bool {
+        let sender = match candidate.scope {
+            SuggestionScope::Sender => candidate.value.clone(),
+            SuggestionScope::Domain => format!("probe@{}", candidate.value),
+        };
+        let probe = InboundMessage {
+            id: String::new(),
+            channel: String::new(),
+            sender,
+            sender_name: None,
+            content: String::new(),
+            subject: None,
+            thread_context: Vec::new(),
+            reply_metadata: serde_json::Value::Null,
+            received_at: Utc::now(),
+            priority_hints: PriorityHints::default(),
+        };
+        matches!(
+            self.rules.read().await.decide(&probe),
+            RuleDecision::Action(TriageAction::Ignore { .. })
+        )
+    }
+}
+
+/// Build the Decision card asking the user to accept a learned rule.
+fn suggestion_card(candidate: &RuleCandidate) -> ApprovalCard {
+    let (question, subject) = match candidate.scope {
+        SuggestionScope::Sender => (
+            format!("Always ignore messages from {}?", candidate.value),
+            "this sender".to_string(),
+        ),
+        SuggestionScope::Domain => (
+            format!("Always ignore messages from anyone @{}?", candidate.value),
+            format!("senders at {}", candidate.value),
+        ),
+    };
+    ApprovalCard::new_decision(
+        question,
+        format!(
+            "You dismissed {} cards from {} and approved none. Approve to add an ignore rule \
+             (editable under triage rules); dismiss to keep seeing them.",
+            candidate.dismissed, subject
+        ),
+        Vec::new(),
+        CardSilo::Messages,
+        SUGGESTION_CARD_EXPIRE_MINUTES,
+    )
+}
+
+/// Spawn a background task that periodically proposes learned rules.
+///
+/// Returns a `JoinHandle` and shutdown flag.
+pub fn spawn_feedback_loop(
+    feedback: Arc<TriageFeedback>,
+    interval_secs: u64,
+) -> (JoinHandle<()>, Arc<AtomicBool>) {
+    let shutdown = Arc::new(AtomicBool::new(false));
+    let shutdown_flag = Arc::clone(&shutdown);
+
+    let handle = tokio::spawn(async move {
+        info!("Triage feedback started — scanning every {interval_secs}s");
+        let mut tick = tokio::time::interval(Duration::from_secs(interval_secs.max(1)));
+
+        loop {
+            tick.tick().await;
+
+            if shutdown.load(Ordering::Relaxed) {
+                info!("Triage feedback shutting down");
+                return;
+            }
+
+            if let Err(e) = feedback.propose_rules().await {
+                error!(error = %e, "Triage feedback scan failed");
+            }
+        }
+    });
+
+    (handle, shutdown_flag)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cards::model::CardStatus;
+    use crate::pipeline::rules::RulesEngine;
+    use crate::store::LibSqlBackend;
+
+    fn outcome(sender: &str, dismissed: u32, approved: u32) -> SenderOutcome {
+        SenderOutcome {
+            channel: "email".into(),
+            sender: sender.into(),
+            dismissed,
+            approved,
+        }
+    }
+
+    #[test]
+    fn sender_needs_threshold_and_no_approvals() {
+        let outcomes = vec![
+            outcome("jobs@linkedin.com", 5, 0),
+            outcome("alice@corp.com", 9, 1),
+            outcome("news@site.io", 4, 0),
+        ];
+        let candidates = find_candidates(&outcomes, 5);
+        assert_eq!(
+            candidates,
+            vec![RuleCandidate {
+                scope: SuggestionScope::Sender,
+                value: "jobs@linkedin.com".into(),
+                dismissed: 5,
+            }]
+        );
+    }
+
+    #[test]
+    fn domain_candidate_covers_its_senders() {
+        let outcomes = vec![
+            outcome("jobs@linkedin.com", 5, 0),
+            outcome("news@linkedin.com", 2, 0),
+        ];
+        let candidates = find_candidates(&outcomes, 5);
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(candidates[0].scope, SuggestionScope::Domain);
+        assert_eq!(candidates[0].value, "linkedin.com");
+        assert_eq!(candidates[0].dismissed, 7);
+    }
+
+    #[test]
+    fn personal_domains_are_never_proposed() {
+        let outcomes = vec![outcome("a@gmail.com", 3, 0), outcome("b@gmail.com", 3, 0)];
+        assert!(find_candidates(&outcomes, 5).is_empty());
+    }
+
+    #[test]
+    fn candidate_rule_is_glob_ignore() {
+        let rule = RuleCandidate {
+            scope: SuggestionScope::Domain,
+            value: "linkedin.com".into(),
+            dismissed: 7,
+        }
+        .to_rule();
+        assert_eq!(rule.pattern, "*@linkedin.com");
+        assert_eq!(rule.kind, PatternKind::Glob);
+        assert_eq!(rule.action, RuleAction::Ignore);
+    }
+
+    async fn setup() -> (Arc<dyn Database>, Arc<CardQueue>, SharedRules, TriageFeedback) {
+        let db: Arc<dyn Database> = Arc::new(LibSqlBackend::new_memory().await.unwrap());
+        let queue = CardQueue::new();
+        let rules: SharedRules = Arc::new(tokio::sync::RwLock::new(RulesEngine::empty()));
+        let feedback = TriageFeedback::new(Arc::clone(&db), queue.clone(), Arc::clone(&rules), 3);
+        (db, queue, rules, feedback)
+    }
+
+    async fn dismissed_reply(db: &Arc<dyn Database>, sender: &str) {
+        let card = ApprovalCard::new_reply("email", sender, "hi", "", 0.0, "c", 60);
+        db.insert_card(&card).await.unwrap();
+        db.update_card_status(card.id, CardStatus::Dismissed).await.unwrap();
+    }
+
+    fn probe(sender: &str) -> InboundMessage {
+        InboundMessage {
+            id: "p".into(),
+            channel: "email".into(),
+            sender: sender.into(),
+            sender_name: None,
+            content: "hello".into(),
+            subject: None,
+            thread_context: Vec::new(),
+            reply_metadata: serde_json::Value::Null,
+            received_at: Utc::now(),
+            priority_hints: PriorityHints::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn accepted_suggestion_becomes_live_rule() {
+        let (db, queue, rules, feedback) = setup().await;
+        for _ in 0..3 {
+            dismissed_reply(&db, "promo@shop.com").await;
+        }
+
+        let cards = feedback.propose_rules().await.unwrap();
+        assert_eq!(cards.len(), 1);
+        assert_eq!(cards[0].payload.card_type_str(), "decision");
+        assert_eq!(queue.pending().await.len(), 1);
+
+        // Not proposed twice.
+        assert!(feedback.propose_rules().await.unwrap().is_empty());
+
+        assert!(feedback.resolve(cards[0].id, true).await.unwrap());
+        assert_eq!(
+            rules.read().await.decide(&probe("promo@shop.com")).label(),
+            "ignore"
+        );
+        let stored = db.list_triage_rules().await.unwrap();
+        assert_eq!(stored.len(), 1);
+        assert_eq!(stored[0].pattern, "promo@shop.com");
+    }
+
+    #[tokio::test]
+    async fn rejected_suggestion_is_not_raised_again() {
+        let (db, _queue, rules, feedback) = setup().await;
+        for _ in 0..3 {
+            dismissed_reply(&db, "promo@shop.com").await;
+        }
+        let cards = feedback.propose_rules().await.unwrap();
+        assert!(feedback.resolve(cards[0].id, false).await.unwrap());
+
+        dismissed_reply(&db, "promo@shop.com").await;
+        assert!(feedback.propose_rules().await.unwrap().is_empty());
+        assert!(db.list_triage_rules().await.unwrap().is_empty());
+        assert_eq!(
+            rules.read().await.decide(&probe("promo@shop.com")).label(),
+            "llm_triage"
+        );
+    }
+
+    #[tokio::test]
+    async fn unrelated_decision_cards_are_ignored() {
+        let (_db, _queue, _rules, feedback) = setup().await;
+        assert!(!feedback.resolve(Uuid::new_v4(), true).await.unwrap());
+    }
+}
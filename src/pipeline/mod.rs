@@ -5,10 +5,14 @@
 //! 2. `RulesEngine::evaluate()` — fast pattern matching (no LLM)
 //! 3. `MessageProcessor::triage()` — LLM-powered triage
 //! 4. Card routing — all outbound goes through human-approved cards
+//!    (low-priority messages are batched into scheduled digest cards)
 //!
 //! **No auto-reply path exists.** Every outbound message requires card approval.
 
+pub mod digest;
+pub mod feedback;
 pub mod email_processor;
 pub mod processor;
+pub mod routes;
 pub mod rules;
 pub mod types;
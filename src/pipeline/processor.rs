@@ -5,20 +5,25 @@
 //!
 //! Flow:
 //! 1. Rules engine (fast, no LLM) → may short-circuit
-//! 2. LLM triage → structured JSON decision
-//! 3. Card routing → creates appropriate card type
+//! 2. Meeting invitations skip triage and become Calendar cards
+//! 3. LLM triage → structured JSON decision
+//! 4. Card routing → creates appropriate card type
 
 use std::sync::Arc;
 
 use chrono::Utc;
 use tracing::{debug, error, info, warn};
 
+use crate::calendar::invite::{self, Invitation};
+use crate::calendar::{CalendarEvent, EventSource, StoredEvent};
 use crate::cards::model::{ApprovalCard, CardPayload, CardSilo};
 use crate::cards::queue::CardQueue;
 use crate::error::PipelineError;
 use crate::llm::provider::{ChatMessage, CompletionRequest, LlmProvider};
-use crate::pipeline::rules::RulesEngine;
+use crate::pipeline::digest::DigestItem;
+use crate::pipeline::rules::{RuleDecision, RulesEngine, SharedRules};
 use crate::pipeline::types::{InboundMessage, ProcessedMessage, TriageAction};
+use crate::store::Database;
 
 /// Default card expiry in minutes.
 const CARD_EXPIRE_MINUTES: u32 = 60;
@@ -36,7 +41,11 @@ const TRIAGE_TEMPERATURE: f32 = 0.1;
 pub struct MessageProcessor {
     llm: Arc<dyn LlmProvider>,
     card_queue: Arc<CardQueue>,
-    rules: RulesEngine,
+    rules: SharedRules,
+    /// Where `Digest` items are parked until the next scheduled roll-up.
+    digest_store: Option<Arc<dyn Database>>,
+    /// Calendar used to flag invitation conflicts and record invited events.
+    calendar_store: Option<Arc<dyn Database>>,
 }
 
 impl MessageProcessor {
@@ -45,19 +54,45 @@ impl MessageProcessor {
         llm: Arc<dyn LlmProvider>,
         card_queue: Arc<CardQueue>,
         rules: RulesEngine,
+    ) -> Self {
+        Self::with_shared_rules(llm, card_queue, Arc::new(tokio::sync::RwLock::new(rules)))
+    }
+
+    /// Create a processor whose rules can be swapped at runtime (see `rules::reload_rules`).
+    pub fn with_shared_rules(
+        llm: Arc<dyn LlmProvider>,
+        card_queue: Arc<CardQueue>,
+        rules: SharedRules,
     ) -> Self {
         Self {
             llm,
             card_queue,
             rules,
+            digest_store: None,
+            calendar_store: None,
         }
     }
 
+    /// Persist `Digest` items for the scheduled digest instead of creating
+    /// a card per message.
+    pub fn with_digest_store(mut self, db: Arc<dyn Database>) -> Self {
+        self.digest_store = Some(db);
+        self
+    }
+
+    /// Check invitations against the stored calendar and keep invited
+    /// events there until the CalDAV sync picks them up.
+    pub fn with_calendar_store(mut self, db: Arc<dyn Database>) -> Self {
+        self.calendar_store = Some(db);
+        self
+    }
+
     /// Process a single inbound message through the full pipeline.
     ///
     /// 1. Rules engine (fast path)
-    /// 2. LLM triage (slow path, only if rules don't match)
-    /// 3. Route to card
+    /// 2. Meeting invitations (unless a rule ignores them)
+    /// 3. LLM triage (slow path, only if rules don't match)
+    /// 4. Route to card
     pub async fn process(
         &self,
         message: InboundMessage,
@@ -70,19 +105,36 @@ impl MessageProcessor {
         );
 
         // Step 1: Rules engine (fast, no LLM)
-        let action = if let Some(action) = self.rules.evaluate(&message) {
-            debug!(
-                id = %message.id,
-                action = action.label(),
-                "Rules engine matched — skipping LLM triage"
-            );
-            action
-        } else {
-            // Step 2: LLM triage
-            self.triage(&message).await?
+        let decision = self.rules.read().await.decide(&message);
+        let invitation = Invitation::from_message(&message);
+        let action = match (decision, invitation) {
+            (RuleDecision::Action(action @ TriageAction::Ignore { .. }), _) => action,
+            // Step 2: invitations always get a meeting card
+            (_, Some(invitation)) => {
+                debug!(
+                    id = %message.id,
+                    uid = %invitation.event.uid,
+                    "Meeting invitation — skipping LLM triage"
+                );
+                TriageAction::Meeting {
+                    summary: invite::describe(&invitation.event),
+                }
+            }
+            (RuleDecision::Action(action), None) => {
+                debug!(
+                    id = %message.id,
+                    action = action.label(),
+                    "Rules engine matched — skipping LLM triage"
+                );
+                action
+            }
+            // Step 3: LLM triage
+            (RuleDecision::NoMatch, None) => self.triage(&message, false).await?,
+            (RuleDecision::AlwaysCard, None) => ensure_card(self.triage(&message, false).await?),
+            (RuleDecision::ForceDraft, None) => ensure_card(self.triage(&message, true).await?),
         };
 
-        // Step 3: Route to card
+        // Step 4: Route to card
         self.route_to_card(&message, &action).await?;
 
         let processed = ProcessedMessage {
@@ -126,10 +178,20 @@ impl MessageProcessor {
     /// Call LLM for triage decision.
     ///
     /// Sends a tight prompt with message content, sender, subject, thread context,
-    /// and priority hints. Returns structured TriageAction.
-    async fn triage(&self, message: &InboundMessage) -> Result<TriageAction, PipelineError> {
+    /// and priority hints. Returns structured TriageAction. `force_draft` asks the
+    /// model for a `draft_reply` (set by force-draft rules).
+    async fn triage(
+        &self,
+        message: &InboundMessage,
+        force_draft: bool,
+    ) -> Result<TriageAction, PipelineError> {
         let system_prompt = build_triage_system_prompt();
-        let user_prompt = build_triage_user_prompt(message);
+        let mut user_prompt = build_triage_user_prompt(message);
+        if force_draft {
+            user_prompt.push_str(
+                "\n\nThe user always wants a reply drafted for messages like this. Choose \"draft_reply\".",
+            );
+        }
 
         let request = CompletionRequest::new(vec![
             ChatMessage::system(system_prompt),
@@ -157,7 +219,9 @@ impl MessageProcessor {
     /// - `Ignore` → log only, no card created
     /// - `Notify` → notification card (summary, no draft reply)
     /// - `DraftReply` → reply card (summary + draft for approval)
-    /// - `Digest` → stored for later aggregation (TODO: digest table)
+    /// - `Digest` → stored in `digest_items` for the next scheduled digest card
+    ///   (falls back to a low-priority card when no digest store is configured)
+    /// - `Meeting` → Calendar card to answer the invitation, with conflicts
     async fn route_to_card(
         &self,
         message: &InboundMessage,
@@ -242,12 +306,23 @@ impl MessageProcessor {
                 Ok(())
             }
             TriageAction::Digest { summary } => {
-                // For now, create a low-priority notification card.
-                // Phase 4 will batch these into periodic digest cards.
+                if let Some(db) = &self.digest_store {
+                    let item = DigestItem::from_inbound(message, summary);
+                    db.insert_digest_item(&item).await.map_err(|e| {
+                        PipelineError::CardCreation(format!("store digest item: {e}"))
+                    })?;
+                    debug!(
+                        id = %message.id,
+                        summary = %summary,
+                        "Digest item stored for next roll-up"
+                    );
+                    return Ok(());
+                }
+
                 debug!(
                     id = %message.id,
                     summary = %summary,
-                    "Digest item — creating low-priority notification for now"
+                    "Digest item — no digest store, creating low-priority card"
                 );
                 let card = ApprovalCard::new(
                     CardPayload::Reply {
@@ -269,7 +344,63 @@ impl MessageProcessor {
                 self.card_queue.push(card).await;
                 Ok(())
             }
+            TriageAction::Meeting { summary } => {
+                let Some(invitation) = Invitation::from_message(message) else {
+                    return Err(PipelineError::CardCreation(format!(
+                        "message {} has no invitation",
+                        message.id
+                    )));
+                };
+                let conflicts = match &self.calendar_store {
+                    Some(db) => {
+                        let conflicts = invitation.conflicts(db.as_ref()).await;
+                        store_invited_event(db.as_ref(), &invitation.event).await;
+                        conflicts
+                    }
+                    None => Vec::new(),
+                };
+
+                let card = ApprovalCard::new_meeting(
+                    message.channel.clone(),
+                    invitation.attendee,
+                    invitation.event,
+                    conflicts,
+                )
+                .with_reply_metadata(message.reply_metadata.clone());
+                info!(
+                    id = %message.id,
+                    summary = %summary,
+                    "Created meeting card"
+                );
+                self.card_queue.push(card).await;
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Record an invited event so it shows up in conflict checks, without
+/// overwriting the server's copy if the sync already has it.
+async fn store_invited_event(db: &dyn Database, event: &CalendarEvent) {
+    match db.get_calendar_event(&event.key()).await {
+        Ok(Some(existing)) if existing.source == EventSource::Caldav => {}
+        Ok(_) => {
+            let stored = StoredEvent::new(event.clone(), EventSource::Invite);
+            if let Err(e) = db.upsert_calendar_event(&stored).await {
+                warn!(uid = %event.uid, error = %e, "Failed to store invited event");
+            }
         }
+        Err(e) => warn!(uid = %event.uid, error = %e, "Failed to look up invited event"),
+    }
+}
+
+/// Downgrade-proof a triage result for always-card / force-draft rules:
+/// `Ignore` and `Digest` become a notification card.
+fn ensure_card(action: TriageAction) -> TriageAction {
+    match action {
+        TriageAction::Ignore { reason } => TriageAction::Notify { summary: reason },
+        TriageAction::Digest { summary } => TriageAction::Notify { summary },
+        other => other,
     }
 }
 
@@ -762,8 +893,12 @@ mod tests {
                 content: self.response.clone(),
                 input_tokens: 100,
                 output_tokens: 50,
+                cache_read_tokens: 0,
+                cache_write_tokens: 0,
                 finish_reason: crate::llm::provider::FinishReason::Stop,
                 response_id: None,
+                provider: "mock".into(),
+                model: self.model_name().into(),
             })
         }
 
@@ -1000,4 +1135,162 @@ mod tests {
         assert_eq!(pending.len(), 1);
         assert!(pending[0].payload.suggested_reply().unwrap().contains("Digest"));
     }
+
+    #[tokio::test]
+    async fn processor_always_card_rule_overrides_llm_ignore() {
+        use crate::pipeline::rules::{PatternKind, RuleAction, RuleField, TriageRule};
+
+        let llm: Arc<dyn LlmProvider> = Arc::new(MockTriageLlm {
+            response: r#"{"action": "ignore", "reason": "looks automated"}"#.into(),
+        });
+        let queue = CardQueue::new();
+        let rules = RulesEngine::from_rules(&[TriageRule::new(
+            "vip",
+            RuleField::Sender,
+            PatternKind::Glob,
+            "*@vip.com",
+            RuleAction::AlwaysCard,
+            "",
+        )]);
+        let processor = MessageProcessor::new(llm, queue.clone(), rules);
+
+        let msg = InboundMessage {
+            id: "test-7".into(),
+            channel: "email".into(),
+            sender: "bot@vip.com".into(),
+            sender_name: None,
+            content: "Automated status".into(),
+            subject: None,
+            thread_context: vec![],
+            reply_metadata: serde_json::json!({}),
+            received_at: Utc::now(),
+            priority_hints: crate::pipeline::types::PriorityHints::default(),
+        };
+
+        let result = processor.process(msg).await.unwrap();
+        assert!(matches!(result.action, TriageAction::Notify { .. }));
+        assert_eq!(queue.pending().await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn processor_picks_up_reloaded_rules() {
+        let llm: Arc<dyn LlmProvider> = Arc::new(MockTriageLlm {
+            response: r#"{"action": "notify", "summary": "hi"}"#.into(),
+        });
+        let queue = CardQueue::new();
+        let shared: SharedRules = Arc::new(tokio::sync::RwLock::new(RulesEngine::empty()));
+        let processor =
+            MessageProcessor::with_shared_rules(llm, queue.clone(), Arc::clone(&shared));
+
+        let msg = InboundMessage {
+            id: "test-8".into(),
+            channel: "email".into(),
+            sender: "noreply@company.com".into(),
+            sender_name: None,
+            content: "Welcome".into(),
+            subject: None,
+            thread_context: vec![],
+            reply_metadata: serde_json::json!({}),
+            received_at: Utc::now(),
+            priority_hints: crate::pipeline::types::PriorityHints::default(),
+        };
+
+        let before = processor.process(msg.clone()).await.unwrap();
+        assert!(matches!(before.action, TriageAction::Notify { .. }));
+
+        *shared.write().await = RulesEngine::default_rules();
+        let after = processor.process(msg).await.unwrap();
+        assert!(matches!(after.action, TriageAction::Ignore { .. }));
+    }
+
+    #[tokio::test]
+    async fn processor_digest_with_store_defers_card() {
+        let llm: Arc<dyn LlmProvider> = Arc::new(MockTriageLlm {
+            response: r#"{"action": "digest", "summary": "Weekly metrics report"}"#.into(),
+        });
+        let db: Arc<dyn Database> =
+            Arc::new(crate::store::LibSqlBackend::new_memory().await.unwrap());
+        let queue = CardQueue::new();
+        let processor = MessageProcessor::new(llm, queue.clone(), RulesEngine::empty())
+            .with_digest_store(Arc::clone(&db));
+
+        let msg = InboundMessage {
+            id: "test-6".into(),
+            channel: "email".into(),
+            sender: "metrics@company.com".into(),
+            sender_name: None,
+            content: "Here are this week's metrics...".into(),
+            subject: Some("Weekly metrics".into()),
+            thread_context: vec![],
+            reply_metadata: serde_json::json!({"reply_to": "metrics@company.com"}),
+            received_at: Utc::now(),
+            priority_hints: crate::pipeline::types::PriorityHints::default(),
+        };
+
+        processor.process(msg).await.unwrap();
+
+        assert!(queue.pending().await.is_empty());
+        let items = db.list_pending_digest_items().await.unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].message_id, "test-6");
+        assert_eq!(items[0].summary, "Weekly metrics report");
+        assert_eq!(items[0].subject.as_deref(), Some("Weekly metrics"));
+    }
+
+    #[tokio::test]
+    async fn processor_invitation_creates_meeting_card_without_llm() {
+        use crate::calendar::{Attendee, ics};
+        // Triage would fail to parse — invitations must not reach it.
+        let llm: Arc<dyn LlmProvider> = Arc::new(MockTriageLlm {
+            response: "not json".into(),
+        });
+        let db: Arc<dyn Database> =
+            Arc::new(crate::store::LibSqlBackend::new_memory().await.unwrap());
+        let queue = CardQueue::new();
+        let processor = MessageProcessor::new(llm, queue.clone(), RulesEngine::empty())
+            .with_calendar_store(Arc::clone(&db));
+
+        let start = Utc::now() + chrono::Duration::days(3);
+        let end = start + chrono::Duration::hours(1);
+        let existing = CalendarEvent::new("busy", "Dentist", start, end);
+        db.upsert_calendar_event(&StoredEvent::new(existing, EventSource::Caldav))
+            .await
+            .unwrap();
+
+        let mut event = CalendarEvent::new("inv-1", "Roadmap sync", start, end);
+        event.organizer = Some(Attendee::new("alice@x.com"));
+        event.attendees.push(Attendee::new("me@x.com"));
+        let msg = InboundMessage {
+            id: "invite-1".into(),
+            channel: "email".into(),
+            sender: "alice@x.com".into(),
+            sender_name: None,
+            content: "Invitation: Roadmap sync".into(),
+            subject: Some("Invitation: Roadmap sync".into()),
+            thread_context: vec![],
+            reply_metadata: serde_json::json!({
+                "reply_to": "alice@x.com",
+                "calendar": { "ics": ics::write(Some("REQUEST"), &[event]), "attendee": "me@x.com" },
+            }),
+            received_at: Utc::now(),
+            priority_hints: crate::pipeline::types::PriorityHints::default(),
+        };
+
+        let result = processor.process(msg).await.unwrap();
+        assert!(matches!(result.action, TriageAction::Meeting { .. }));
+
+        let pending = queue.pending().await;
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].silo, CardSilo::Calendar);
+        let CardPayload::Meeting { ref attendee, ref conflicts, ref reply_metadata, .. } = pending[0].payload else {
+            panic!("Expected Meeting card");
+        };
+        assert_eq!(attendee, "me@x.com");
+        assert_eq!(conflicts.len(), 1);
+        assert!(conflicts[0].starts_with("Dentist"));
+        assert_eq!(reply_metadata.as_ref().unwrap()["reply_to"], "alice@x.com");
+
+        let stored = db.get_calendar_event("inv-1").await.unwrap().unwrap();
+        assert_eq!(stored.source, EventSource::Invite);
+    }
 }
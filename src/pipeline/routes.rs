@@ -0,0 +1,367 @@
+//! REST API routes for user-editable triage rules.
+//!
+//! Endpoints:
+//! - `GET    /api/triage-rules`          — list rules
+//! - `POST   /api/triage-rules`          — create a rule
+//! - `GET    /api/triage-rules/:id`      — get single rule
+//! - `PUT    /api/triage-rules/:id`      — update a rule
+//! - `DELETE /api/triage-rules/:id`      — delete a rule
+//! - `POST   /api/triage-rules/dry-run`  — evaluate a rule set against recent messages
+//!
+//! Every successful mutation reloads the rules into the running pipeline.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use axum::{
+    Json, Router,
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+use uuid::Uuid;
+
+use super::email_processor::stored_to_inbound;
+use super::rules::{
+    PatternKind, RuleAction, RuleField, RulesEngine, SharedRules, TriageRule, reload_rules,
+};
+use crate::store::Database;
+
+/// Default number of stored messages a dry run evaluates.
+const DRY_RUN_DEFAULT_LIMIT: usize = 50;
+
+/// Upper bound on messages a dry run evaluates.
+const DRY_RUN_MAX_LIMIT: usize = 500;
+
+/// Shared state for triage rule routes.
+#[derive(Clone)]
+pub struct TriageRulesState {
+    pub db: Arc<dyn Database>,
+    /// The rules engine used by the running `MessageProcessor`.
+    pub rules: SharedRules,
+}
+
+/// Request body for creating a rule (also used for dry-run rule sets).
+#[derive(Debug, Deserialize)]
+pub struct CreateTriageRuleRequest {
+    pub name: String,
+    pub field: RuleField,
+    #[serde(default = "default_kind")]
+    pub kind: PatternKind,
+    pub pattern: String,
+    pub action: RuleAction,
+    #[serde(default)]
+    pub note: String,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_kind() -> PatternKind {
+    PatternKind::Glob
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl CreateTriageRuleRequest {
+    fn into_rule(self) -> TriageRule {
+        let mut rule = TriageRule::new(
+            self.name,
+            self.field,
+            self.kind,
+            self.pattern,
+            self.action,
+            self.note,
+        );
+        rule.enabled = self.enabled;
+        rule
+    }
+}
+
+/// Request body for updating a rule.
+#[derive(Debug, Deserialize)]
+pub struct UpdateTriageRuleRequest {
+    pub name: Option<String>,
+    pub field: Option<RuleField>,
+    pub kind: Option<PatternKind>,
+    pub pattern: Option<String>,
+    pub action: Option<RuleAction>,
+    pub note: Option<String>,
+    pub enabled: Option<bool>,
+}
+
+/// Request body for a dry run.
+#[derive(Debug, Deserialize)]
+pub struct DryRunRequest {
+    /// Candidate rule set that would replace the current rules.
+    pub rules: Vec<CreateTriageRuleRequest>,
+    /// How many recent stored messages to evaluate.
+    pub limit: Option<usize>,
+}
+
+/// One message whose rule outcome would change.
+#[derive(Debug, Serialize)]
+pub struct DryRunChange {
+    pub message_id: String,
+    pub channel: String,
+    pub sender: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subject: Option<String>,
+    pub current: &'static str,
+    pub proposed: &'static str,
+}
+
+/// Dry-run report.
+#[derive(Debug, Serialize)]
+pub struct DryRunReport {
+    pub evaluated: usize,
+    pub changed: Vec<DryRunChange>,
+    /// Count of proposed outcomes by label (`ignore`, `notify`, `llm_triage`, ...).
+    pub proposed_totals: BTreeMap<&'static str, usize>,
+}
+
+/// Build the Axum router for `/api/triage-rules`.
+pub fn triage_rule_routes(state: TriageRulesState) -> Router {
+    Router::new()
+        .route("/api/triage-rules", get(list_rules).post(create_rule))
+        .route("/api/triage-rules/dry-run", post(dry_run))
+        .route(
+            "/api/triage-rules/{id}",
+            get(get_rule).put(update_rule).delete(delete_rule),
+        )
+        .with_state(state)
+}
+
+fn error_response(status: StatusCode, message: impl Into<String>) -> Response {
+    (status, Json(serde_json::json!({"error": message.into()}))).into_response()
+}
+
+/// Reload the shared engine; failures are logged — the DB write already succeeded.
+async fn reload(state: &TriageRulesState) {
+    if let Err(e) = reload_rules(state.db.as_ref(), &state.rules).await {
+        warn!(error = %e, "Failed to reload triage rules");
+    }
+}
+
+/// GET /api/triage-rules
+async fn list_rules(State(state): State<TriageRulesState>) -> Response {
+    match state.db.list_triage_rules().await {
+        Ok(rules) => Json(serde_json::json!({"rules": rules})).into_response(),
+        Err(e) => error_response(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+/// GET /api/triage-rules/:id
+async fn get_rule(State(state): State<TriageRulesState>, Path(id): Path<String>) -> Response {
+    let Ok(rule_id) = Uuid::parse_str(&id) else {
+        return error_response(StatusCode::BAD_REQUEST, "Invalid rule ID");
+    };
+
+    match state.db.get_triage_rule(rule_id).await {
+        Ok(Some(rule)) => Json(rule).into_response(),
+        Ok(None) => error_response(StatusCode::NOT_FOUND, "Rule not found"),
+        Err(e) => error_response(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+/// POST /api/triage-rules
+async fn create_rule(
+    State(state): State<TriageRulesState>,
+    Json(req): Json<CreateTriageRuleRequest>,
+) -> Response {
+    let rule = req.into_rule();
+    if let Err(e) = rule.compile() {
+        return error_response(StatusCode::BAD_REQUEST, e);
+    }
+
+    match state.db.create_triage_rule(&rule).await {
+        Ok(()) => {
+            reload(&state).await;
+            (StatusCode::CREATED, Json(serde_json::json!({"rule": rule}))).into_response()
+        }
+        Err(e) => error_response(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+/// PUT /api/triage-rules/:id
+async fn update_rule(
+    State(state): State<TriageRulesState>,
+    Path(id): Path<String>,
+    Json(req): Json<UpdateTriageRuleRequest>,
+) -> Response {
+    let Ok(rule_id) = Uuid::parse_str(&id) else {
+        return error_response(StatusCode::BAD_REQUEST, "Invalid rule ID");
+    };
+
+    let existing = match state.db.get_triage_rule(rule_id).await {
+        Ok(Some(rule)) => rule,
+        Ok(None) => return error_response(StatusCode::NOT_FOUND, "Rule not found"),
+        Err(e) => return error_response(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    };
+
+    // Merge updates
+    let updated = TriageRule {
+        name: req.name.unwrap_or(existing.name),
+        field: req.field.unwrap_or(existing.field),
+        kind: req.kind.unwrap_or(existing.kind),
+        pattern: req.pattern.unwrap_or(existing.pattern),
+        action: req.action.unwrap_or(existing.action),
+        note: req.note.unwrap_or(existing.note),
+        enabled: req.enabled.unwrap_or(existing.enabled),
+        updated_at: chrono::Utc::now(),
+        ..existing
+    };
+    if let Err(e) = updated.compile() {
+        return error_response(StatusCode::BAD_REQUEST, e);
+    }
+
+    match state.db.update_triage_rule(&updated).await {
+        Ok(()) => {
+            reload(&state).await;
+            Json(serde_json::json!({"rule": updated})).into_response()
+        }
+        Err(e) => error_response(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+/// DELETE /api/triage-rules/:id
+async fn delete_rule(State(state): State<TriageRulesState>, Path(id): Path<String>) -> Response {
+    let Ok(rule_id) = Uuid::parse_str(&id) else {
+        return error_response(StatusCode::BAD_REQUEST, "Invalid rule ID");
+    };
+
+    match state.db.delete_triage_rule(rule_id).await {
+        Ok(true) => {
+            reload(&state).await;
+            Json(serde_json::json!({"deleted": true})).into_response()
+        }
+        Ok(false) => error_response(StatusCode::NOT_FOUND, "Rule not found"),
+        Err(e) => error_response(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+/// POST /api/triage-rules/dry-run
+///
+/// Evaluates the candidate rule set against the last N stored messages and
+/// reports every message whose rule outcome differs from the live rules.
+/// Nothing is persisted.
+async fn dry_run(State(state): State<TriageRulesState>, Json(req): Json<DryRunRequest>) -> Response {
+    let candidate: Vec<TriageRule> = req.rules.into_iter().map(|r| r.into_rule()).collect();
+    if let Some(e) = candidate.iter().find_map(|r| r.compile().err()) {
+        return error_response(StatusCode::BAD_REQUEST, e);
+    }
+    let proposed_engine = RulesEngine::from_rules(&candidate);
+
+    let limit = req
+        .limit
+        .unwrap_or(DRY_RUN_DEFAULT_LIMIT)
+        .clamp(1, DRY_RUN_MAX_LIMIT);
+    let messages = match state.db.list_recent_messages(limit).await {
+        Ok(msgs) => msgs,
+        Err(e) => return error_response(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    };
+
+    let current_engine = state.rules.read().await;
+    let report = compare_engines(&current_engine, &proposed_engine, &messages);
+    Json(report).into_response()
+}
+
+/// Compare two engines over the given messages.
+fn compare_engines(
+    current: &RulesEngine,
+    proposed: &RulesEngine,
+    messages: &[crate::store::StoredMessage],
+) -> DryRunReport {
+    let mut changed = Vec::new();
+    let mut proposed_totals = BTreeMap::new();
+
+    for stored in messages {
+        let inbound = stored_to_inbound(stored);
+        let before = current.decide(&inbound).label();
+        let after = proposed.decide(&inbound).label();
+        *proposed_totals.entry(after).or_insert(0) += 1;
+        if before != after {
+            changed.push(DryRunChange {
+                message_id: stored.id.clone(),
+                channel: stored.channel.clone(),
+                sender: stored.sender.clone(),
+                subject: stored.subject.clone(),
+                current: before,
+                proposed: after,
+            });
+        }
+    }
+
+    DryRunReport {
+        evaluated: messages.len(),
+        changed,
+        proposed_totals,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::{MessageStatus, StoredMessage};
+    use chrono::Utc;
+
+    fn stored(id: &str, sender: &str, subject: &str) -> StoredMessage {
+        StoredMessage {
+            id: id.into(),
+            external_id: format!("ext-{id}"),
+            channel: "email".into(),
+            sender: sender.into(),
+            subject: Some(subject.into()),
+            content: "Hello".into(),
+            received_at: Utc::now(),
+            status: MessageStatus::Pending,
+            replied_at: None,
+            metadata: None,
+            account_id: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn compare_reports_only_changed_messages() {
+        let current = RulesEngine::empty();
+        let proposed = RulesEngine::from_rules(&[TriageRule::new(
+            "LinkedIn",
+            RuleField::Sender,
+            PatternKind::Glob,
+            "*@linkedin.com",
+            RuleAction::Ignore,
+            "",
+        )]);
+        let messages = vec![
+            stored("1", "jobs@linkedin.com", "New jobs"),
+            stored("2", "alice@example.com", "Lunch?"),
+        ];
+
+        let report = compare_engines(&current, &proposed, &messages);
+        assert_eq!(report.evaluated, 2);
+        assert_eq!(report.changed.len(), 1);
+        assert_eq!(report.changed[0].message_id, "1");
+        assert_eq!(report.changed[0].current, "llm_triage");
+        assert_eq!(report.changed[0].proposed, "ignore");
+        assert_eq!(report.proposed_totals.get("ignore"), Some(&1));
+        assert_eq!(report.proposed_totals.get("llm_triage"), Some(&1));
+    }
+
+    #[test]
+    fn create_request_defaults_to_enabled_glob() {
+        let req: CreateTriageRuleRequest = serde_json::from_str(
+            r#"{"name": "LinkedIn", "field": "sender", "pattern": "*@linkedin.com", "action": "ignore"}"#,
+        )
+        .unwrap();
+        let rule = req.into_rule();
+        assert_eq!(rule.kind, PatternKind::Glob);
+        assert!(rule.enabled);
+        assert!(rule.compile().is_ok());
+    }
+}
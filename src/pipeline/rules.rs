@@ -7,20 +7,276 @@
 //! - Transactional (shipping, receipts) → Notify (not ignore)
 //!
 //! If the rules engine returns a `TriageAction`, the LLM call is skipped entirely.
+//!
+//! Rules are user-editable: they live in the `triage_rules` table (seeded with
+//! `default_triage_rules()` on first start), are managed over REST, and are
+//! hot-swapped into the running `MessageProcessor` through a `SharedRules` handle.
+
+use std::sync::Arc;
 
+use chrono::{DateTime, Utc};
 use regex::Regex;
-use tracing::debug;
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
 
+use crate::error::DatabaseError;
 use crate::pipeline::types::{InboundMessage, TriageAction};
+use crate::store::Database;
+
+/// Settings key recording that the default rules were copied into the DB.
+const RULES_SEEDED_SETTING: &str = "triage_rules_seeded";
+
+/// Rules engine shared between the pipeline and the REST API (hot reload).
+pub type SharedRules = Arc<RwLock<RulesEngine>>;
 
 /// Which field a rule matches against.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum RuleField {
     Sender,
     Subject,
     Content,
 }
 
+impl RuleField {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Sender => "sender",
+            Self::Subject => "subject",
+            Self::Content => "content",
+        }
+    }
+}
+
+impl std::str::FromStr for RuleField {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "sender" => Ok(Self::Sender),
+            "subject" => Ok(Self::Subject),
+            "content" => Ok(Self::Content),
+            _ => Err(format!("Unknown rule field: {s}")),
+        }
+    }
+}
+
+/// How a rule's pattern is interpreted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PatternKind {
+    /// Rust regex syntax, unanchored.
+    Regex,
+    /// Case-insensitive glob (`*`, `?`) matched against the whole field.
+    Glob,
+}
+
+impl PatternKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Regex => "regex",
+            Self::Glob => "glob",
+        }
+    }
+}
+
+impl std::str::FromStr for PatternKind {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "regex" => Ok(Self::Regex),
+            "glob" => Ok(Self::Glob),
+            _ => Err(format!("Unknown pattern kind: {s}")),
+        }
+    }
+}
+
+/// What happens when a rule matches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleAction {
+    /// Drop the message without a card.
+    Ignore,
+    /// Notification card, no LLM call.
+    Notify,
+    /// Bypass ignore rules; LLM triage runs but can't ignore or digest.
+    AlwaysCard,
+    /// Bypass ignore rules and ask LLM triage for a draft reply.
+    ForceDraft,
+}
+
+impl RuleAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Ignore => "ignore",
+            Self::Notify => "notify",
+            Self::AlwaysCard => "always_card",
+            Self::ForceDraft => "force_draft",
+        }
+    }
+}
+
+impl std::str::FromStr for RuleAction {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ignore" => Ok(Self::Ignore),
+            "notify" => Ok(Self::Notify),
+            "always_card" => Ok(Self::AlwaysCard),
+            "force_draft" => Ok(Self::ForceDraft),
+            _ => Err(format!("Unknown rule action: {s}")),
+        }
+    }
+}
+
+/// A user-editable triage rule, persisted in the `triage_rules` table.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TriageRule {
+    pub id: Uuid,
+    /// Human-readable name.
+    pub name: String,
+    pub field: RuleField,
+    pub kind: PatternKind,
+    pub pattern: String,
+    pub action: RuleAction,
+    /// Ignore reason or notify summary prefix (defaults to the name).
+    #[serde(default)]
+    pub note: String,
+    pub enabled: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl TriageRule {
+    /// Create a new enabled rule.
+    pub fn new(
+        name: impl Into<String>,
+        field: RuleField,
+        kind: PatternKind,
+        pattern: impl Into<String>,
+        action: RuleAction,
+        note: impl Into<String>,
+    ) -> Self {
+        let now = Utc::now();
+        Self {
+            id: Uuid::new_v4(),
+            name: name.into(),
+            field,
+            kind,
+            pattern: pattern.into(),
+            action,
+            note: note.into(),
+            enabled: true,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    /// Compile the pattern into a regex.
+    pub fn compile(&self) -> Result<Regex, String> {
+        let source = match self.kind {
+            PatternKind::Regex => self.pattern.clone(),
+            PatternKind::Glob => glob_to_regex(&self.pattern),
+        };
+        Regex::new(&source).map_err(|e| format!("Invalid pattern for rule '{}': {e}", self.name))
+    }
+
+    fn note_or_name(&self) -> String {
+        if self.note.is_empty() {
+            self.name.clone()
+        } else {
+            self.note.clone()
+        }
+    }
+}
+
+/// Translate a glob into an anchored, case-insensitive regex.
+pub fn glob_to_regex(glob: &str) -> String {
+    let mut out = String::with_capacity(glob.len() + 8);
+    out.push_str("(?i)^");
+    for c in glob.chars() {
+        match c {
+            '*' => out.push_str(".*"),
+            '?' => out.push('.'),
+            c => out.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    out.push('$');
+    out
+}
+
+/// The built-in rules, in the form they are seeded into the database.
+pub fn default_triage_rules() -> Vec<TriageRule> {
+    use PatternKind::Regex as Re;
+    vec![
+        TriageRule::new(
+            "noreply@*",
+            RuleField::Sender,
+            Re,
+            r"(?i)^no[\-_.]?reply@",
+            RuleAction::Ignore,
+            "noreply sender",
+        ),
+        TriageRule::new(
+            "*@marketing.*",
+            RuleField::Sender,
+            Re,
+            r"(?i)@(marketing|newsletter|promo|campaign)\b",
+            RuleAction::Ignore,
+            "marketing/newsletter sender",
+        ),
+        TriageRule::new(
+            "mailer-daemon",
+            RuleField::Sender,
+            Re,
+            r"(?i)^(mailer[\-_]?daemon|postmaster)@",
+            RuleAction::Ignore,
+            "automated mail system",
+        ),
+        TriageRule::new(
+            "unsubscribe in subject",
+            RuleField::Subject,
+            Re,
+            r"(?i)\bunsubscribe\b",
+            RuleAction::Ignore,
+            "newsletter/marketing (unsubscribe in subject)",
+        ),
+        TriageRule::new(
+            "unsubscribe footer",
+            RuleField::Content,
+            Re,
+            r"(?i)(click here to unsubscribe|manage your subscription|email preferences|opt[- ]?out)",
+            RuleAction::Ignore,
+            "bulk/marketing email (unsubscribe footer)",
+        ),
+        TriageRule::new(
+            "notifications@github.com",
+            RuleField::Sender,
+            Re,
+            r"(?i)^notifications@github\.com$",
+            RuleAction::Ignore,
+            "GitHub notification",
+        ),
+        TriageRule::new(
+            "shipping updates",
+            RuleField::Content,
+            Re,
+            r"(?i)(your (order|package|shipment)|tracking (number|update)|has (shipped|been delivered)|out for delivery)",
+            RuleAction::Notify,
+            "Shipping/delivery update",
+        ),
+        TriageRule::new(
+            "payment receipts",
+            RuleField::Content,
+            Re,
+            r"(?i)(payment (received|confirmed)|receipt for|invoice #|your (receipt|transaction))",
+            RuleAction::Notify,
+            "Payment/receipt",
+        ),
+    ]
+}
+
 /// A single ignore/action rule with a compiled regex.
 #[derive(Debug, Clone)]
 pub struct IgnoreRule {
@@ -45,91 +301,52 @@ pub struct NotifyRule {
     pub summary_prefix: String,
 }
 
+/// A regex bound to a message field (always-card / force-draft rules).
+#[derive(Debug, Clone)]
+struct FieldPattern {
+    regex: Regex,
+    field: RuleField,
+}
+
+/// Outcome of evaluating the rules for one message.
+#[derive(Debug, Clone)]
+pub enum RuleDecision {
+    /// A rule decided the action — LLM triage is skipped.
+    Action(TriageAction),
+    /// LLM triage runs, but the message must end up as a card.
+    AlwaysCard,
+    /// LLM triage runs and is asked for a draft reply.
+    ForceDraft,
+    /// No rule matched — LLM triage decides.
+    NoMatch,
+}
+
+impl RuleDecision {
+    /// Short label for logging and dry-run reports.
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Action(action) => action.label(),
+            Self::AlwaysCard => "always_card",
+            Self::ForceDraft => "force_draft",
+            Self::NoMatch => "llm_triage",
+        }
+    }
+}
+
 /// Pre-LLM rules engine for fast triage.
 pub struct RulesEngine {
     ignore_rules: Vec<IgnoreRule>,
     notify_rules: Vec<NotifyRule>,
-    /// Senders/domains that always get cards (bypass ignore rules).
-    always_card_patterns: Vec<Regex>,
+    /// Patterns that always get cards (bypass ignore rules).
+    always_card_patterns: Vec<FieldPattern>,
+    /// Patterns that always get a drafted reply (bypass ignore rules).
+    force_draft_patterns: Vec<FieldPattern>,
 }
 
 impl RulesEngine {
     /// Create a rules engine with default ignore patterns.
     pub fn default_rules() -> Self {
-        let ignore_rules = vec![
-            // noreply senders
-            IgnoreRule {
-                pattern: "noreply@*".into(),
-                regex: Regex::new(r"(?i)^no[\-_.]?reply@").unwrap(),
-                field: RuleField::Sender,
-                reason: "noreply sender".into(),
-            },
-            // Marketing/newsletter domains
-            IgnoreRule {
-                pattern: "*@marketing.*".into(),
-                regex: Regex::new(r"(?i)@(marketing|newsletter|promo|campaign)\b").unwrap(),
-                field: RuleField::Sender,
-                reason: "marketing/newsletter sender".into(),
-            },
-            // Bulk sender patterns
-            IgnoreRule {
-                pattern: "mailer-daemon".into(),
-                regex: Regex::new(r"(?i)^(mailer[\-_]?daemon|postmaster)@").unwrap(),
-                field: RuleField::Sender,
-                reason: "automated mail system".into(),
-            },
-            // Subject: unsubscribe prominence
-            IgnoreRule {
-                pattern: "unsubscribe in subject".into(),
-                regex: Regex::new(r"(?i)\bunsubscribe\b").unwrap(),
-                field: RuleField::Subject,
-                reason: "newsletter/marketing (unsubscribe in subject)".into(),
-            },
-            // Content: bulk unsubscribe footer pattern
-            IgnoreRule {
-                pattern: "unsubscribe footer".into(),
-                regex: Regex::new(
-                    r"(?i)(click here to unsubscribe|manage your subscription|email preferences|opt[- ]?out)",
-                )
-                .unwrap(),
-                field: RuleField::Content,
-                reason: "bulk/marketing email (unsubscribe footer)".into(),
-            },
-            // GitHub notification bot
-            IgnoreRule {
-                pattern: "notifications@github.com".into(),
-                regex: Regex::new(r"(?i)^notifications@github\.com$").unwrap(),
-                field: RuleField::Sender,
-                reason: "GitHub notification".into(),
-            },
-        ];
-
-        let notify_rules = vec![
-            // Shipping/delivery notifications → Notify (user may want to see)
-            NotifyRule {
-                regex: Regex::new(
-                    r"(?i)(your (order|package|shipment)|tracking (number|update)|has (shipped|been delivered)|out for delivery)",
-                )
-                .unwrap(),
-                field: RuleField::Content,
-                summary_prefix: "Shipping/delivery update".into(),
-            },
-            // Payment/receipt notifications
-            NotifyRule {
-                regex: Regex::new(
-                    r"(?i)(payment (received|confirmed)|receipt for|invoice #|your (receipt|transaction))",
-                )
-                .unwrap(),
-                field: RuleField::Content,
-                summary_prefix: "Payment/receipt".into(),
-            },
-        ];
-
-        Self {
-            ignore_rules,
-            notify_rules,
-            always_card_patterns: Vec::new(),
-        }
+        Self::from_rules(&default_triage_rules())
     }
 
     /// Create an empty rules engine (for testing).
@@ -138,12 +355,53 @@ impl RulesEngine {
             ignore_rules: Vec::new(),
             notify_rules: Vec::new(),
             always_card_patterns: Vec::new(),
+            force_draft_patterns: Vec::new(),
+        }
+    }
+
+    /// Build an engine from stored rules. Disabled rules are skipped, as are
+    /// rules whose pattern no longer compiles (logged).
+    pub fn from_rules(rules: &[TriageRule]) -> Self {
+        let mut engine = Self::empty();
+        for rule in rules.iter().filter(|r| r.enabled) {
+            let regex = match rule.compile() {
+                Ok(regex) => regex,
+                Err(e) => {
+                    warn!(rule_id = %rule.id, error = %e, "Skipping triage rule");
+                    continue;
+                }
+            };
+            match rule.action {
+                RuleAction::Ignore => engine.ignore_rules.push(IgnoreRule {
+                    pattern: rule.name.clone(),
+                    regex,
+                    field: rule.field,
+                    reason: rule.note_or_name(),
+                }),
+                RuleAction::Notify => engine.notify_rules.push(NotifyRule {
+                    regex,
+                    field: rule.field,
+                    summary_prefix: rule.note_or_name(),
+                }),
+                RuleAction::AlwaysCard => engine.always_card_patterns.push(FieldPattern {
+                    regex,
+                    field: rule.field,
+                }),
+                RuleAction::ForceDraft => engine.force_draft_patterns.push(FieldPattern {
+                    regex,
+                    field: rule.field,
+                }),
+            }
         }
+        engine
     }
 
     /// Add a sender/domain pattern that always gets a card (bypasses ignore rules).
     pub fn add_always_card(&mut self, pattern: &str) -> Result<(), regex::Error> {
-        self.always_card_patterns.push(Regex::new(pattern)?);
+        self.always_card_patterns.push(FieldPattern {
+            regex: Regex::new(pattern)?,
+            field: RuleField::Sender,
+        });
         Ok(())
     }
 
@@ -168,31 +426,42 @@ impl RulesEngine {
     /// Returns `Some(TriageAction)` if a rule matches (short-circuits LLM).
     /// Returns `None` if no rules match (fall through to LLM triage).
     pub fn evaluate(&self, message: &InboundMessage) -> Option<TriageAction> {
+        match self.decide(message) {
+            RuleDecision::Action(action) => Some(action),
+            _ => None,
+        }
+    }
+
+    /// Evaluate a message and report how the pipeline should proceed.
+    ///
+    /// Precedence: force-draft, always-card, ignore, notify.
+    pub fn decide(&self, message: &InboundMessage) -> RuleDecision {
+        if self
+            .force_draft_patterns
+            .iter()
+            .any(|p| field_value(message, p.field).is_some_and(|v| p.regex.is_match(v)))
+        {
+            debug!(sender = %message.sender, "Message matches force-draft rule");
+            return RuleDecision::ForceDraft;
+        }
+
         // Always-card senders bypass ignore rules entirely
         if self
             .always_card_patterns
             .iter()
-            .any(|r| r.is_match(&message.sender))
+            .any(|p| field_value(message, p.field).is_some_and(|v| p.regex.is_match(v)))
         {
             debug!(
                 sender = %message.sender,
                 "Sender matches always-card pattern, bypassing rules"
             );
-            return None;
+            return RuleDecision::AlwaysCard;
         }
 
         // Check ignore rules
         for rule in &self.ignore_rules {
-            let field_value = match rule.field {
-                RuleField::Sender => &message.sender,
-                RuleField::Subject => {
-                    if let Some(ref subj) = message.subject {
-                        subj
-                    } else {
-                        continue;
-                    }
-                }
-                RuleField::Content => &message.content,
+            let Some(field_value) = field_value(message, rule.field) else {
+                continue;
             };
 
             if rule.regex.is_match(field_value) {
@@ -202,7 +471,7 @@ impl RulesEngine {
                     reason = %rule.reason,
                     "Message matched ignore rule"
                 );
-                return Some(TriageAction::Ignore {
+                return RuleDecision::Action(TriageAction::Ignore {
                     reason: rule.reason.clone(),
                 });
             }
@@ -210,16 +479,8 @@ impl RulesEngine {
 
         // Check notify rules (transactional patterns that shouldn't be ignored)
         for rule in &self.notify_rules {
-            let field_value = match rule.field {
-                RuleField::Sender => &message.sender,
-                RuleField::Subject => {
-                    if let Some(ref subj) = message.subject {
-                        subj
-                    } else {
-                        continue;
-                    }
-                }
-                RuleField::Content => &message.content,
+            let Some(field_value) = field_value(message, rule.field) else {
+                continue;
             };
 
             if rule.regex.is_match(field_value) {
@@ -233,15 +494,46 @@ impl RulesEngine {
                     rule.summary_prefix,
                     message.sender_name.as_deref().unwrap_or(&message.sender),
                 );
-                return Some(TriageAction::Notify { summary });
+                return RuleDecision::Action(TriageAction::Notify { summary });
             }
         }
 
         // No rules matched — fall through to LLM triage
-        None
+        RuleDecision::NoMatch
     }
 }
 
+/// The value of `field` on a message (`None` when the message has no subject).
+fn field_value(message: &InboundMessage, field: RuleField) -> Option<&str> {
+    match field {
+        RuleField::Sender => Some(&message.sender),
+        RuleField::Subject => message.subject.as_deref(),
+        RuleField::Content => Some(&message.content),
+    }
+}
+
+/// Load rules from the database, seeding the built-in defaults on first start.
+pub async fn load_rules(db: &dyn Database) -> Result<RulesEngine, DatabaseError> {
+    let seeded = db.get_setting("default", RULES_SEEDED_SETTING).await?.is_some();
+    if !seeded {
+        for rule in default_triage_rules() {
+            db.create_triage_rule(&rule).await?;
+        }
+        db.set_setting("default", RULES_SEEDED_SETTING, &serde_json::json!(true))
+            .await?;
+        info!("Seeded default triage rules");
+    }
+    Ok(RulesEngine::from_rules(&db.list_triage_rules().await?))
+}
+
+/// Rebuild the engine from the database and swap it into the running pipeline.
+pub async fn reload_rules(db: &dyn Database, shared: &SharedRules) -> Result<(), DatabaseError> {
+    let engine = RulesEngine::from_rules(&db.list_triage_rules().await?);
+    *shared.write().await = engine;
+    debug!("Triage rules reloaded");
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -431,4 +723,122 @@ mod tests {
         // noreply sender should trigger ignore before content triggers notify
         assert!(matches!(engine.evaluate(&msg), Some(TriageAction::Ignore { .. })));
     }
+
+    #[test]
+    fn glob_matches_whole_field_case_insensitively() {
+        let re = Regex::new(&glob_to_regex("*@LinkedIn.com")).unwrap();
+        assert!(re.is_match("jobs@linkedin.com"));
+        assert!(!re.is_match("jobs@linkedin.com.evil.io"));
+        let re = Regex::new(&glob_to_regex("invoice-?.pdf")).unwrap();
+        assert!(re.is_match("Invoice-7.pdf"));
+        assert!(!re.is_match("invoice-7xpdf"));
+    }
+
+    #[test]
+    fn from_rules_skips_disabled_and_invalid() {
+        let mut disabled = TriageRule::new(
+            "off",
+            RuleField::Sender,
+            PatternKind::Glob,
+            "*",
+            RuleAction::Ignore,
+            "",
+        );
+        disabled.enabled = false;
+        let invalid = TriageRule::new(
+            "broken",
+            RuleField::Sender,
+            PatternKind::Regex,
+            "(",
+            RuleAction::Ignore,
+            "",
+        );
+        let engine = RulesEngine::from_rules(&[disabled, invalid]);
+        let msg = make_message("anyone@x.com", None, "Hi");
+        assert!(matches!(engine.decide(&msg), RuleDecision::NoMatch));
+    }
+
+    #[test]
+    fn force_draft_and_always_card_beat_ignore() {
+        let rules = vec![
+            TriageRule::new(
+                "ignore all",
+                RuleField::Sender,
+                PatternKind::Glob,
+                "*",
+                RuleAction::Ignore,
+                "",
+            ),
+            TriageRule::new(
+                "boss",
+                RuleField::Sender,
+                PatternKind::Glob,
+                "boss@*",
+                RuleAction::ForceDraft,
+                "",
+            ),
+            TriageRule::new(
+                "invoices",
+                RuleField::Subject,
+                PatternKind::Glob,
+                "*invoice*",
+                RuleAction::AlwaysCard,
+                "",
+            ),
+        ];
+        let engine = RulesEngine::from_rules(&rules);
+
+        let boss = make_message("boss@corp.com", Some("Invoice"), "Hi");
+        assert!(matches!(engine.decide(&boss), RuleDecision::ForceDraft));
+        let invoice = make_message("billing@x.com", Some("Your invoice"), "Hi");
+        assert!(matches!(engine.decide(&invoice), RuleDecision::AlwaysCard));
+        let other = make_message("someone@x.com", Some("Hello"), "Hi");
+        assert_eq!(engine.decide(&other).label(), "ignore");
+        // Legacy API still reports "no short-circuit" for card-forcing rules.
+        assert!(engine.evaluate(&boss).is_none());
+    }
+
+    #[test]
+    fn default_rules_use_notes_as_reason() {
+        let engine = RulesEngine::default_rules();
+        let msg = make_message("noreply@company.com", None, "Welcome!");
+        match engine.evaluate(&msg) {
+            Some(TriageAction::Ignore { reason }) => assert_eq!(reason, "noreply sender"),
+            other => panic!("Expected Ignore, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn load_rules_seeds_defaults_once_and_reload_swaps() {
+        let db = crate::store::LibSqlBackend::new_memory().await.unwrap();
+        let engine = load_rules(&db).await.unwrap();
+        let seeded = db.list_triage_rules().await.unwrap();
+        assert_eq!(seeded.len(), default_triage_rules().len());
+
+        let noreply = make_message("noreply@company.com", None, "Welcome!");
+        assert_eq!(engine.decide(&noreply).label(), "ignore");
+
+        // Deleting everything sticks — defaults are not re-seeded.
+        for rule in &seeded {
+            db.delete_triage_rule(rule.id).await.unwrap();
+        }
+        let engine = load_rules(&db).await.unwrap();
+        assert!(db.list_triage_rules().await.unwrap().is_empty());
+        assert_eq!(engine.decide(&noreply).label(), "llm_triage");
+
+        // Hot reload picks up new rules.
+        let shared: SharedRules = Arc::new(RwLock::new(engine));
+        db.create_triage_rule(&TriageRule::new(
+            "noreply",
+            RuleField::Sender,
+            PatternKind::Glob,
+            "noreply@*",
+            RuleAction::Notify,
+            "Automated",
+        ))
+        .await
+        .unwrap();
+        reload_rules(&db, &shared).await.unwrap();
+        assert_eq!(shared.read().await.decide(&noreply).label(), "notify");
+    }
 }
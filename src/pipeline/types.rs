@@ -124,6 +124,10 @@ pub enum TriageAction {
     },
     /// Low priority — batch into a periodic digest.
     Digest { summary: String },
+    /// Meeting invitation — create a Calendar card to accept, decline or
+    /// propose a new time. Chosen without the LLM when the message carries
+    /// an iCalendar `REQUEST`.
+    Meeting { summary: String },
 }
 
 impl TriageAction {
@@ -134,6 +138,7 @@ impl TriageAction {
             Self::Notify { .. } => "notify",
             Self::DraftReply { .. } => "draft_reply",
             Self::Digest { .. } => "digest",
+            Self::Meeting { .. } => "meeting",
         }
     }
 }
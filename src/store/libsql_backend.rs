@@ -9,14 +9,26 @@ use std::sync::Arc;
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use libsql::{Connection, Database as LibSqlDatabase, params};
+use rust_decimal::Decimal;
 use tracing::{debug, info};
 use uuid::Uuid;
 
+use crate::agent::approval_policy::ApprovalRule;
+use crate::agent::session::{Session, Thread};
+use crate::agent::undo::UndoManager;
+use crate::calendar::model::{EventSource, StoredEvent};
 use crate::cards::model::{ApprovalCard, CardPayload, CardSilo, CardStatus, SiloCounts};
+use crate::cards::outbox::OutboxJob;
 use crate::documents::model::{Document, DocumentType};
 use crate::error::DatabaseError;
+use crate::llm::budget::{Budget, BudgetScope};
+use crate::pipeline::digest::DigestItem;
+use crate::pipeline::feedback::{RuleSuggestion, SenderOutcome, SuggestionScope, SuggestionStatus};
+use crate::pipeline::rules::TriageRule;
 use crate::store::migrations;
-use crate::store::traits::{ConversationMessage, Database, MessageStatus, StoredMessage};
+use crate::store::traits::{
+    ConversationMessage, Database, MessageStatus, StoredMessage, StoredThread, ThreadBinding,
+};
 use crate::todos::model::{TodoBucket, TodoItem, TodoStatus, TodoType};
 
 /// libSQL database backend.
@@ -217,6 +229,9 @@ fn status_to_str(status: &CardStatus) -> &'static str {
         CardStatus::Dismissed => "dismissed",
         CardStatus::Expired => "expired",
         CardStatus::Sent => "sent",
+        CardStatus::Scheduled => "scheduled",
+        CardStatus::Sending => "sending",
+        CardStatus::Failed => "failed",
     }
 }
 
@@ -227,6 +242,9 @@ fn str_to_status(s: &str) -> CardStatus {
         "dismissed" => CardStatus::Dismissed,
         "expired" => CardStatus::Expired,
         "sent" => CardStatus::Sent,
+        "scheduled" => CardStatus::Scheduled,
+        "sending" => CardStatus::Sending,
+        "failed" => CardStatus::Failed,
         _ => CardStatus::Pending,
     }
 }
@@ -250,7 +268,7 @@ fn str_to_msg_status(s: &str) -> MessageStatus {
 /// Map a libsql Row to an ApprovalCard.
 ///
 /// Column order matches CARD_COLUMNS:
-/// 0:id, 1:card_type, 2:silo, 3:payload, 4:status, 5:created_at, 6:expires_at, 7:updated_at, 8:todo_id
+/// 0:id, 1:card_type, 2:silo, 3:payload, 4:status, 5:created_at, 6:expires_at, 7:updated_at, 8:todo_id, 9:warnings
 ///
 /// For legacy rows (before V6), card_type/silo/payload may be NULL.
 /// We fall back to reading the old flat columns in that case.
@@ -264,6 +282,11 @@ fn row_to_card(row: &libsql::Row) -> Result<ApprovalCard, libsql::Error> {
     let expires_str: Option<String> = row.get(6).ok();
     let updated_str: String = row.get(7)?;
     let todo_id_str: Option<String> = row.get(8).ok().and_then(|s: String| if s.is_empty() { None } else { Some(s) });
+    let warnings: Vec<String> = row
+        .get::<String>(9)
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default();
 
     let silo: CardSilo = silo_str.parse().unwrap_or_default();
 
@@ -299,12 +322,38 @@ fn row_to_card(row: &libsql::Row) -> Result<ApprovalCard, libsql::Error> {
                 "action" => serde_json::from_str(pstr).unwrap_or_else(|_| CardPayload::Action {
                     description: "Unknown".into(),
                     action_detail: None,
+                    always_allow: None,
                 }),
                 "decision" => serde_json::from_str(pstr).unwrap_or_else(|_| CardPayload::Decision {
                     question: "Unknown".into(),
                     context: String::new(),
                     options: Vec::new(),
                 }),
+                "meeting" => serde_json::from_str::<MeetingPayloadRaw>(pstr)
+                    .map(|m| CardPayload::Meeting {
+                        channel: m.channel,
+                        attendee: m.attendee,
+                        event: m.event,
+                        conflicts: m.conflicts,
+                        response: m.response,
+                        reply_metadata: m.reply_metadata,
+                    })
+                    .unwrap_or_else(|_| fallback_reply_payload()),
+                "new_event" => serde_json::from_str::<NewEventPayloadRaw>(pstr)
+                    .map(|e| CardPayload::NewEvent {
+                        event: e.event,
+                        conflicts: e.conflicts,
+                    })
+                    .unwrap_or_else(|_| fallback_reply_payload()),
+                "digest" => serde_json::from_str::<DigestPayloadRaw>(pstr)
+                    .map(|d| CardPayload::Digest {
+                        summary: d.summary,
+                        groups: d.groups,
+                    })
+                    .unwrap_or_else(|_| CardPayload::Digest {
+                        summary: "Unknown".into(),
+                        groups: Vec::new(),
+                    }),
                 _ => fallback_reply_payload(),
             }
         })
@@ -322,6 +371,7 @@ fn row_to_card(row: &libsql::Row) -> Result<ApprovalCard, libsql::Error> {
         expires_at: expires_str.as_deref().map(parse_datetime),
         updated_at: parse_datetime(&updated_str),
         todo_id: todo_id_str.and_then(|s| Uuid::parse_str(&s).ok()),
+        warnings,
     })
 }
 
@@ -354,6 +404,36 @@ struct ComposePayloadRaw {
     confidence: f32,
 }
 
+/// Helper struct for deserializing the inner Digest payload from the JSON column.
+#[derive(serde::Deserialize)]
+struct DigestPayloadRaw {
+    summary: String,
+    #[serde(default)]
+    groups: Vec<crate::cards::model::DigestGroup>,
+}
+
+/// Helper struct for deserializing the inner Meeting payload from the JSON column.
+#[derive(serde::Deserialize)]
+struct MeetingPayloadRaw {
+    channel: String,
+    attendee: String,
+    event: crate::calendar::CalendarEvent,
+    #[serde(default)]
+    conflicts: Vec<String>,
+    #[serde(default)]
+    response: crate::calendar::MeetingResponse,
+    #[serde(default)]
+    reply_metadata: Option<serde_json::Value>,
+}
+
+/// Helper struct for deserializing the inner NewEvent payload from the JSON column.
+#[derive(serde::Deserialize)]
+struct NewEventPayloadRaw {
+    event: crate::calendar::CalendarEvent,
+    #[serde(default)]
+    conflicts: Vec<String>,
+}
+
 /// Serialize a CardPayload's inner data as a flat JSON object (not adjacently tagged).
 /// This is what we store in the `payload` column — the `card_type` is a separate column.
 fn serialize_payload_inner(payload: &CardPayload) -> String {
@@ -400,10 +480,11 @@ fn serialize_payload_inner(payload: &CardPayload) -> String {
                 "confidence": confidence,
             }).to_string()
         }
-        CardPayload::Action { description, action_detail } => {
+        CardPayload::Action { description, action_detail, always_allow } => {
             serde_json::json!({
                 "description": description,
                 "action_detail": action_detail,
+                "always_allow": always_allow,
             }).to_string()
         }
         CardPayload::Decision { question, context, options } => {
@@ -419,6 +500,28 @@ fn serialize_payload_inner(payload: &CardPayload) -> String {
                 "options": options,
             }).to_string()
         }
+        CardPayload::Digest { summary, groups } => {
+            serde_json::json!({
+                "summary": summary,
+                "groups": groups,
+            }).to_string()
+        }
+        CardPayload::Meeting { channel, attendee, event, conflicts, response, reply_metadata } => {
+            serde_json::json!({
+                "channel": channel,
+                "attendee": attendee,
+                "event": event,
+                "conflicts": conflicts,
+                "response": response,
+                "reply_metadata": reply_metadata,
+            }).to_string()
+        }
+        CardPayload::NewEvent { event, conflicts } => {
+            serde_json::json!({
+                "event": event,
+                "conflicts": conflicts,
+            }).to_string()
+        }
     }
 }
 
@@ -456,11 +559,263 @@ fn row_to_message(row: &libsql::Row) -> Result<StoredMessage, libsql::Error> {
         status: str_to_msg_status(&status_str),
         replied_at: parse_optional_datetime(&replied_at_str),
         metadata: row.get(9).ok(),
+        account_id: row.get(12).ok(),
         created_at: parse_datetime(&created_str),
         updated_at: parse_datetime(&updated_str),
     })
 }
 
+/// Map a libsql Row to a DigestItem.
+///
+/// Column order matches DIGEST_ITEM_COLUMNS.
+fn row_to_digest_item(row: &libsql::Row) -> Result<DigestItem, libsql::Error> {
+    let received_str: String = row.get(8)?;
+    let digest_card_str: Option<String> = row.get(9).ok();
+    let promoted_card_str: Option<String> = row.get(10).ok();
+    let created_str: String = row.get(11)?;
+
+    Ok(DigestItem {
+        id: row.get(0)?,
+        message_id: row.get(1)?,
+        channel: row.get(2)?,
+        sender: row.get(3)?,
+        subject: row.get(4).ok(),
+        summary: row.get(5)?,
+        content: row.get(6)?,
+        reply_metadata: row
+            .get::<String>(7)
+            .ok()
+            .and_then(|s| serde_json::from_str(&s).ok()),
+        received_at: parse_datetime(&received_str),
+        digest_card_id: digest_card_str.and_then(|s| Uuid::parse_str(&s).ok()),
+        promoted_card_id: promoted_card_str.and_then(|s| Uuid::parse_str(&s).ok()),
+        created_at: parse_datetime(&created_str),
+    })
+}
+
+/// Map a libsql Row to a TriageRule.
+///
+/// Column order matches TRIAGE_RULE_COLUMNS.
+fn row_to_triage_rule(row: &libsql::Row) -> Result<TriageRule, DatabaseError> {
+    let parse = |idx: i32| -> Result<String, DatabaseError> {
+        row.get::<String>(idx)
+            .map_err(|e| DatabaseError::Query(format!("triage rule column {idx}: {e}")))
+    };
+    let id_str = parse(0)?;
+    let created_str = parse(8)?;
+    let updated_str = parse(9)?;
+    let enabled: i64 = row.get(7).unwrap_or(1);
+
+    Ok(TriageRule {
+        id: Uuid::parse_str(&id_str)
+            .map_err(|e| DatabaseError::Serialization(format!("triage rule id: {e}")))?,
+        name: parse(1)?,
+        field: parse(2)?.parse().map_err(DatabaseError::Serialization)?,
+        kind: parse(3)?.parse().map_err(DatabaseError::Serialization)?,
+        pattern: parse(4)?,
+        action: parse(5)?.parse().map_err(DatabaseError::Serialization)?,
+        note: row.get(6).unwrap_or_default(),
+        enabled: enabled != 0,
+        created_at: parse_datetime(&created_str),
+        updated_at: parse_datetime(&updated_str),
+    })
+}
+
+/// Map a libsql Row to an ApprovalRule.
+///
+/// Column order matches APPROVAL_RULE_COLUMNS.
+fn row_to_approval_rule(row: &libsql::Row) -> Result<ApprovalRule, DatabaseError> {
+    let parse = |idx: i32| -> Result<String, DatabaseError> {
+        row.get::<String>(idx)
+            .map_err(|e| DatabaseError::Query(format!("approval rule column {idx}: {e}")))
+    };
+    let id_str = parse(0)?;
+    let negate: i64 = row.get(6).unwrap_or(0);
+    let enabled: i64 = row.get(9).unwrap_or(1);
+
+    Ok(ApprovalRule {
+        id: Uuid::parse_str(&id_str)
+            .map_err(|e| DatabaseError::Serialization(format!("approval rule id: {e}")))?,
+        name: parse(1)?,
+        tool: parse(2)?,
+        argument: row.get::<String>(3).ok(),
+        kind: parse(4)?.parse().map_err(DatabaseError::Serialization)?,
+        pattern: row.get(5).unwrap_or_default(),
+        negate: negate != 0,
+        decision: parse(7)?.parse().map_err(DatabaseError::Serialization)?,
+        note: row.get(8).unwrap_or_default(),
+        enabled: enabled != 0,
+        created_at: parse_datetime(&parse(10)?),
+        updated_at: parse_datetime(&parse(11)?),
+    })
+}
+
+/// Map a libsql Row to a Budget.
+///
+/// Column order matches BUDGET_COLUMNS.
+fn row_to_budget(row: &libsql::Row) -> Result<Budget, DatabaseError> {
+    let parse = |idx: i32| -> Result<String, DatabaseError> {
+        row.get::<String>(idx)
+            .map_err(|e| DatabaseError::Query(format!("budget column {idx}: {e}")))
+    };
+    let opt_uuid = |idx: i32| row.get::<String>(idx).ok().and_then(|s| Uuid::parse_str(&s).ok());
+    let opt_decimal = |idx: i32| row.get::<String>(idx).ok().and_then(|s| s.parse::<Decimal>().ok());
+    let enabled: i64 = row.get(6).unwrap_or(1);
+
+    Ok(Budget {
+        id: Uuid::parse_str(&parse(0)?)
+            .map_err(|e| DatabaseError::Serialization(format!("budget id: {e}")))?,
+        scope: parse(1)?.parse().map_err(DatabaseError::Serialization)?,
+        scope_id: opt_uuid(2),
+        period: parse(3)?.parse().map_err(DatabaseError::Serialization)?,
+        soft_limit: opt_decimal(4),
+        hard_limit: opt_decimal(5),
+        enabled: enabled != 0,
+        raise_card_id: opt_uuid(7),
+        raise_requested_at: row.get::<String>(8).ok().map(|s| parse_datetime(&s)),
+        created_at: parse_datetime(&parse(9)?),
+        updated_at: parse_datetime(&parse(10)?),
+    })
+}
+
+/// Map a libsql Row to a Session (without threads).
+///
+/// Column order matches AGENT_SESSION_COLUMNS.
+fn row_to_agent_session(row: &libsql::Row) -> Result<Session, DatabaseError> {
+    let parse = |idx: i32| -> Result<String, DatabaseError> {
+        row.get::<String>(idx)
+            .map_err(|e| DatabaseError::Query(format!("agent session column {idx}: {e}")))
+    };
+    let tools: String = row.get(4).unwrap_or_else(|_| "[]".to_string());
+
+    Ok(Session {
+        user_id: parse(0)?,
+        id: Uuid::parse_str(&parse(1)?)
+            .map_err(|e| DatabaseError::Serialization(format!("agent session id: {e}")))?,
+        active_thread: row.get::<String>(2).ok().and_then(|s| Uuid::parse_str(&s).ok()),
+        threads: Default::default(),
+        metadata: row
+            .get::<String>(3)
+            .ok()
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or(serde_json::Value::Null),
+        auto_approved_tools: serde_json::from_str(&tools)
+            .map_err(|e| DatabaseError::Serialization(format!("auto_approved_tools: {e}")))?,
+        created_at: parse_datetime(&parse(5)?),
+        last_active_at: parse_datetime(&parse(6)?),
+    })
+}
+
+/// Map a libsql Row to a StoredThread.
+///
+/// Column order matches AGENT_THREAD_COLUMNS.
+fn row_to_stored_thread(row: &libsql::Row) -> Result<StoredThread, DatabaseError> {
+    let parse = |idx: i32| -> Result<String, DatabaseError> {
+        row.get::<String>(idx)
+            .map_err(|e| DatabaseError::Query(format!("agent thread column {idx}: {e}")))
+    };
+    let thread: Thread = serde_json::from_str(&parse(3)?)
+        .map_err(|e| DatabaseError::Serialization(format!("agent thread data: {e}")))?;
+    let undo = match row.get::<String>(4).ok() {
+        Some(json) => Some(
+            serde_json::from_str::<UndoManager>(&json)
+                .map_err(|e| DatabaseError::Serialization(format!("agent thread undo: {e}")))?,
+        ),
+        None => None,
+    };
+
+    Ok(StoredThread {
+        binding: ThreadBinding {
+            user_id: parse(0)?,
+            channel: parse(1)?,
+            external_thread_id: row.get::<String>(2).ok(),
+        },
+        thread,
+        undo,
+    })
+}
+
+/// Column list for outbox queries.
+const OUTBOX_COLUMNS: &str = "id, card_id, card, status, attempts, next_attempt_at, last_error, decision_card_id, created_at, updated_at";
+
+/// Map a libsql Row to an OutboxJob.
+///
+/// Column order matches OUTBOX_COLUMNS.
+fn row_to_outbox_job(row: &libsql::Row) -> Result<OutboxJob, DatabaseError> {
+    let parse = |idx: i32| -> Result<String, DatabaseError> {
+        row.get::<String>(idx)
+            .map_err(|e| DatabaseError::Query(format!("outbox column {idx}: {e}")))
+    };
+    let uuid = |idx: i32| -> Result<Uuid, DatabaseError> {
+        Uuid::parse_str(&parse(idx)?)
+            .map_err(|e| DatabaseError::Serialization(format!("outbox uuid: {e}")))
+    };
+    let attempts: i64 = row.get(4).unwrap_or(0);
+
+    Ok(OutboxJob {
+        id: uuid(0)?,
+        card_id: uuid(1)?,
+        card: serde_json::from_str(&parse(2)?)
+            .map_err(|e| DatabaseError::Serialization(format!("outbox card: {e}")))?,
+        status: parse(3)?.parse().map_err(DatabaseError::Serialization)?,
+        attempts: attempts as u32,
+        next_attempt_at: parse_datetime(&parse(5)?),
+        last_error: row.get::<String>(6).ok(),
+        decision_card_id: row.get::<String>(7).ok().and_then(|s| Uuid::parse_str(&s).ok()),
+        created_at: parse_datetime(&parse(8)?),
+        updated_at: parse_datetime(&parse(9)?),
+    })
+}
+
+/// Column list for calendar_events queries.
+const CALENDAR_EVENT_COLUMNS: &str = "id, uid, source, href, etag, summary, start_at, end_at, all_day, data, updated_at";
+
+/// Map a libsql Row to a StoredEvent. The event itself lives in `data`;
+/// the other columns exist for querying.
+///
+/// Column order matches CALENDAR_EVENT_COLUMNS.
+fn row_to_calendar_event(row: &libsql::Row) -> Result<StoredEvent, DatabaseError> {
+    let parse = |idx: i32| -> Result<String, DatabaseError> {
+        row.get::<String>(idx)
+            .map_err(|e| DatabaseError::Query(format!("calendar event column {idx}: {e}")))
+    };
+
+    Ok(StoredEvent {
+        event: serde_json::from_str(&parse(9)?)
+            .map_err(|e| DatabaseError::Serialization(format!("calendar event: {e}")))?,
+        source: parse(2)?.parse().map_err(DatabaseError::Serialization)?,
+        href: row.get::<String>(3).ok(),
+        etag: row.get::<String>(4).ok(),
+        updated_at: parse_datetime(&parse(10)?),
+    })
+}
+
+/// Column list for rule_suggestions queries.
+const RULE_SUGGESTION_COLUMNS: &str = "id, scope, value, card_id, status, dismissed, created_at";
+
+/// Convert a libsql row into a RuleSuggestion.
+fn row_to_rule_suggestion(row: &libsql::Row) -> Result<RuleSuggestion, DatabaseError> {
+    let parse = |idx: i32| -> Result<String, DatabaseError> {
+        row.get::<String>(idx)
+            .map_err(|e| DatabaseError::Query(format!("rule suggestion column {idx}: {e}")))
+    };
+    let uuid = |idx: i32| -> Result<Uuid, DatabaseError> {
+        Uuid::parse_str(&parse(idx)?)
+            .map_err(|e| DatabaseError::Serialization(format!("rule suggestion uuid: {e}")))
+    };
+    let dismissed: i64 = row.get(5).unwrap_or(0);
+
+    Ok(RuleSuggestion {
+        id: uuid(0)?,
+        scope: parse(1)?.parse().map_err(DatabaseError::Serialization)?,
+        value: parse(2)?,
+        card_id: uuid(3)?,
+        status: parse(4)?.parse().map_err(DatabaseError::Serialization)?,
+        dismissed: dismissed as u32,
+        created_at: parse_datetime(&parse(6)?),
+    })
+}
+
 /// Convert `Option<&str>` to libsql Value.
 fn opt_text(s: Option<&str>) -> libsql::Value {
     match s {
@@ -479,9 +834,21 @@ fn opt_text_owned(s: Option<String>) -> libsql::Value {
 
 // ── Trait implementation ────────────────────────────────────────────
 
-const CARD_COLUMNS: &str = "id, card_type, silo, payload, status, created_at, expires_at, updated_at, todo_id";
+const CARD_COLUMNS: &str = "id, card_type, silo, payload, status, created_at, expires_at, updated_at, todo_id, warnings";
+
+const DIGEST_ITEM_COLUMNS: &str = "id, message_id, channel, sender, subject, summary, content, reply_metadata, received_at, digest_card_id, promoted_card_id, created_at";
 
-const MESSAGE_COLUMNS: &str = "id, external_id, channel, sender, subject, content, received_at, status, replied_at, metadata, created_at, updated_at";
+const TRIAGE_RULE_COLUMNS: &str = "id, name, field, kind, pattern, action, note, enabled, created_at, updated_at";
+
+const APPROVAL_RULE_COLUMNS: &str = "id, name, tool, argument, kind, pattern, negate, decision, note, enabled, created_at, updated_at";
+
+const BUDGET_COLUMNS: &str = "id, scope, scope_id, period, soft_limit, hard_limit, enabled, raise_card_id, raise_requested_at, created_at, updated_at";
+
+const AGENT_SESSION_COLUMNS: &str = "user_id, id, active_thread, metadata, auto_approved_tools, created_at, last_active_at";
+
+const AGENT_THREAD_COLUMNS: &str = "user_id, channel, external_thread_id, data, undo";
+
+const MESSAGE_COLUMNS: &str = "id, external_id, channel, sender, subject, content, received_at, status, replied_at, metadata, created_at, updated_at, account_id";
 
 #[async_trait]
 impl Database for LibSqlBackend {
@@ -534,10 +901,19 @@ impl Database for LibSqlBackend {
                 CardPayload::MultipleChoice { question, .. } => (
                     String::new(), String::new(), String::new(), question.clone(), 0.0, String::new(), None, None, None,
                 ),
+                CardPayload::Digest { summary, .. } => (
+                    String::new(), String::new(), String::new(), summary.clone(), 0.0, String::new(), None, None, None,
+                ),
+                CardPayload::Meeting { channel, event, .. } => (
+                    String::new(), String::new(), String::new(), event.summary.clone(), 0.0, channel.clone(), None, None, None,
+                ),
+                CardPayload::NewEvent { event, .. } => (
+                    String::new(), String::new(), String::new(), event.summary.clone(), 0.0, String::new(), None, None, None,
+                ),
             };
 
         conn.execute(
-            "INSERT INTO cards (id, conversation_id, source_message, source_sender, suggested_reply, confidence, status, channel, created_at, expires_at, updated_at, message_id, reply_metadata, email_thread, card_type, silo, payload, todo_id) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18)",
+            "INSERT INTO cards (id, conversation_id, source_message, source_sender, suggested_reply, confidence, status, channel, created_at, expires_at, updated_at, message_id, reply_metadata, email_thread, card_type, silo, payload, todo_id, warnings) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19)",
             params![
                 card.id.to_string(),
                 conversation_id,
@@ -557,6 +933,7 @@ impl Database for LibSqlBackend {
                 card.silo.to_string(),
                 payload_json,
                 card.todo_id.map(|id| id.to_string()),
+                if card.warnings.is_empty() { None } else { serde_json::to_string(&card.warnings).ok() },
             ],
         )
         .await
@@ -621,6 +998,56 @@ impl Database for LibSqlBackend {
         Ok(())
     }
 
+    async fn update_card_warnings(&self, id: Uuid, warnings: &[String]) -> Result<(), DatabaseError> {
+        let conn = self.conn();
+        let value = if warnings.is_empty() {
+            None
+        } else {
+            serde_json::to_string(warnings).ok()
+        };
+        conn.execute(
+            "UPDATE cards SET warnings = ?1, updated_at = ?2 WHERE id = ?3",
+            params![value, Utc::now().to_rfc3339(), id.to_string()],
+        )
+        .await
+        .map_err(|e| DatabaseError::Query(format!("update_card_warnings: {e}")))?;
+        Ok(())
+    }
+
+    async fn update_card_expiry(
+        &self,
+        id: Uuid,
+        expires_at: Option<DateTime<Utc>>,
+    ) -> Result<(), DatabaseError> {
+        let conn = self.conn();
+        conn.execute(
+            "UPDATE cards SET expires_at = ?1, updated_at = ?2 WHERE id = ?3",
+            params![
+                opt_text_owned(expires_at.map(|t| t.to_rfc3339())),
+                Utc::now().to_rfc3339(),
+                id.to_string()
+            ],
+        )
+        .await
+        .map_err(|e| DatabaseError::Query(format!("update_card_expiry: {e}")))?;
+        Ok(())
+    }
+
+    async fn update_card_payload(&self, id: Uuid, payload: &CardPayload) -> Result<(), DatabaseError> {
+        let conn = self.conn();
+        conn.execute(
+            "UPDATE cards SET payload = ?1, updated_at = ?2 WHERE id = ?3",
+            params![
+                serialize_payload_inner(payload),
+                Utc::now().to_rfc3339(),
+                id.to_string()
+            ],
+        )
+        .await
+        .map_err(|e| DatabaseError::Query(format!("update_card_payload: {e}")))?;
+        Ok(())
+    }
+
     async fn get_pending_cards(&self) -> Result<Vec<ApprovalCard>, DatabaseError> {
         let conn = self.conn();
         let now = Utc::now().to_rfc3339();
@@ -819,14 +1246,15 @@ impl Database for LibSqlBackend {
         content: &str,
         received_at: DateTime<Utc>,
         metadata: Option<&str>,
+        account_id: Option<&str>,
     ) -> Result<String, DatabaseError> {
         let id = Uuid::new_v4().to_string();
         let now = Utc::now().to_rfc3339();
         let conn = self.conn();
         conn.execute(
             "INSERT INTO messages (id, external_id, channel, sender, subject, content,
-                received_at, status, metadata, created_at, updated_at)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, 'pending', ?8, ?9, ?9)",
+                received_at, status, metadata, created_at, updated_at, account_id)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, 'pending', ?8, ?9, ?9, ?10)",
             params![
                 id.clone(),
                 external_id,
@@ -837,6 +1265,7 @@ impl Database for LibSqlBackend {
                 received_at.to_rfc3339(),
                 opt_text(metadata),
                 now,
+                opt_text(account_id),
             ],
         )
         .await
@@ -924,6 +1353,28 @@ impl Database for LibSqlBackend {
         Ok(())
     }
 
+    async fn list_recent_messages(&self, limit: usize) -> Result<Vec<StoredMessage>, DatabaseError> {
+        let conn = self.conn();
+        let mut rows = conn
+            .query(
+                &format!("SELECT {MESSAGE_COLUMNS} FROM messages ORDER BY received_at DESC LIMIT ?1"),
+                params![limit as i64],
+            )
+            .await
+            .map_err(|e| DatabaseError::Query(format!("list_recent_messages: {e}")))?;
+
+        let mut messages = Vec::new();
+        while let Ok(Some(row)) = rows.next().await {
+            match row_to_message(&row) {
+                Ok(msg) => messages.push(msg),
+                Err(e) => {
+                    tracing::warn!("Skipping message row: {e}");
+                }
+            }
+        }
+        Ok(messages)
+    }
+
     async fn get_messages_by_channel(
         &self,
         channel: &str,
@@ -952,244 +1403,1245 @@ impl Database for LibSqlBackend {
         Ok(messages)
     }
 
-    // ── Conversations ───────────────────────────────────────────────
-
-    async fn ensure_conversation(
-        &self,
-        thread_id: Uuid,
-        channel: &str,
-        user_id: &str,
-        _title: Option<&str>,
-    ) -> Result<(), DatabaseError> {
-        let conn = self.conn();
-        let now = Utc::now().to_rfc3339();
-        conn.execute(
-            "INSERT INTO conversations (id, channel, user_id)
-             VALUES (?1, ?2, ?3)
-             ON CONFLICT (id) DO UPDATE SET last_activity = ?4",
-            params![thread_id.to_string(), channel, user_id, now],
-        )
-        .await
-        .map_err(|e| DatabaseError::Query(format!("ensure_conversation: {e}")))?;
-
-        Ok(())
-    }
+    // ── Digest Items ────────────────────────────────────────────────
 
-    async fn add_conversation_message(
-        &self,
-        thread_id: Uuid,
-        role: &str,
-        content: &str,
-    ) -> Result<(), DatabaseError> {
+    async fn insert_digest_item(&self, item: &DigestItem) -> Result<(), DatabaseError> {
         let conn = self.conn();
-        let id = Uuid::new_v4();
         conn.execute(
-            "INSERT INTO conversation_messages (id, conversation_id, role, content)
-             VALUES (?1, ?2, ?3, ?4)",
-            params![id.to_string(), thread_id.to_string(), role, content],
+            &format!(
+                "INSERT OR IGNORE INTO digest_items ({DIGEST_ITEM_COLUMNS}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)"
+            ),
+            params![
+                item.id.as_str(),
+                item.message_id.as_str(),
+                item.channel.as_str(),
+                item.sender.as_str(),
+                opt_text(item.subject.as_deref()),
+                item.summary.as_str(),
+                item.content.as_str(),
+                opt_text_owned(item.reply_metadata.as_ref().map(|v| v.to_string())),
+                item.received_at.to_rfc3339(),
+                opt_text_owned(item.digest_card_id.map(|id| id.to_string())),
+                opt_text_owned(item.promoted_card_id.map(|id| id.to_string())),
+                item.created_at.to_rfc3339(),
+            ],
         )
         .await
-        .map_err(|e| DatabaseError::Query(format!("add_conversation_message: {e}")))?;
-
-        // Touch last_activity
-        let now = Utc::now().to_rfc3339();
-        let _ = conn
-            .execute(
-                "UPDATE conversations SET last_activity = ?2 WHERE id = ?1",
-                params![thread_id.to_string(), now],
-            )
-            .await;
+        .map_err(|e| DatabaseError::Query(format!("insert_digest_item: {e}")))?;
 
+        debug!(id = %item.id, message_id = %item.message_id, "Digest item stored");
         Ok(())
     }
 
-    async fn list_conversation_messages(
-        &self,
-        thread_id: Uuid,
-    ) -> Result<Vec<ConversationMessage>, DatabaseError> {
+    async fn list_pending_digest_items(&self) -> Result<Vec<DigestItem>, DatabaseError> {
         let conn = self.conn();
         let mut rows = conn
             .query(
-                "SELECT id, role, content, created_at FROM conversation_messages
-                 WHERE conversation_id = ?1 ORDER BY created_at ASC",
-                params![thread_id.to_string()],
+                &format!(
+                    "SELECT {DIGEST_ITEM_COLUMNS} FROM digest_items WHERE digest_card_id IS NULL ORDER BY received_at ASC"
+                ),
+                (),
             )
             .await
-            .map_err(|e| DatabaseError::Query(format!("list_conversation_messages: {e}")))?;
+            .map_err(|e| DatabaseError::Query(format!("list_pending_digest_items: {e}")))?;
 
-        let mut messages = Vec::new();
+        let mut items = Vec::new();
         while let Ok(Some(row)) = rows.next().await {
-            let id_str: String = row.get(0).unwrap_or_default();
-            let role: String = row.get(1).unwrap_or_default();
-            let content: String = row.get(2).unwrap_or_default();
-            let created_str: String = row.get(3).unwrap_or_default();
-            messages.push(ConversationMessage {
-                id: Uuid::parse_str(&id_str).unwrap_or_else(|_| Uuid::nil()),
-                role,
-                content,
-                created_at: parse_datetime(&created_str),
-            });
+            match row_to_digest_item(&row) {
+                Ok(item) => items.push(item),
+                Err(e) => {
+                    tracing::warn!("Skipping digest item row: {e}");
+                }
+            }
         }
-        Ok(messages)
+        Ok(items)
     }
 
-    async fn get_conversation_metadata(
-        &self,
-        thread_id: Uuid,
-    ) -> Result<Option<serde_json::Value>, DatabaseError> {
+    async fn assign_digest_items(&self, ids: &[String], card_id: Uuid) -> Result<Vec<String>, DatabaseError> {
+        let conn = self.conn();
+        let mut assigned = Vec::with_capacity(ids.len());
+        for id in ids {
+            let affected = conn
+                .execute(
+                    "UPDATE digest_items SET digest_card_id = ?1 WHERE id = ?2 AND digest_card_id IS NULL",
+                    params![card_id.to_string(), id.as_str()],
+                )
+                .await
+                .map_err(|e| DatabaseError::Query(format!("assign_digest_items: {e}")))?;
+            if affected > 0 {
+                assigned.push(id.clone());
+            }
+        }
+        Ok(assigned)
+    }
+
+    async fn get_digest_item(&self, id: &str) -> Result<Option<DigestItem>, DatabaseError> {
         let conn = self.conn();
         let mut rows = conn
             .query(
-                "SELECT metadata FROM conversations WHERE id = ?1",
-                params![thread_id.to_string()],
+                &format!("SELECT {DIGEST_ITEM_COLUMNS} FROM digest_items WHERE id = ?1"),
+                params![id],
             )
             .await
-            .map_err(|e| DatabaseError::Query(format!("get_conversation_metadata: {e}")))?;
+            .map_err(|e| DatabaseError::Query(format!("get_digest_item: {e}")))?;
 
         match rows.next().await {
-            Ok(Some(row)) => {
-                let meta_str: String = row.get(0).unwrap_or_else(|_| "{}".to_string());
-                let value: serde_json::Value =
-                    serde_json::from_str(&meta_str).unwrap_or(serde_json::json!({}));
-                Ok(Some(value))
-            }
+            Ok(Some(row)) => row_to_digest_item(&row)
+                .map(Some)
+                .map_err(|e| DatabaseError::Query(format!("get_digest_item row parse: {e}"))),
             Ok(None) => Ok(None),
-            Err(e) => Err(DatabaseError::Query(format!(
-                "get_conversation_metadata: {e}"
-            ))),
+            Err(e) => Err(DatabaseError::Query(format!("get_digest_item: {e}"))),
         }
     }
 
-    async fn update_conversation_metadata_field(
-        &self,
-        thread_id: Uuid,
-        key: &str,
-        value: &serde_json::Value,
-    ) -> Result<(), DatabaseError> {
+    async fn mark_digest_item_promoted(&self, id: &str, card_id: Uuid) -> Result<bool, DatabaseError> {
         let conn = self.conn();
-
-        // Read current metadata
-        let mut rows = conn
-            .query(
+        let affected = conn
+            .execute(
+                "UPDATE digest_items SET promoted_card_id = ?1 WHERE id = ?2 AND promoted_card_id IS NULL",
+                params![card_id.to_string(), id],
+            )
+            .await
+            .map_err(|e| DatabaseError::Query(format!("mark_digest_item_promoted: {e}")))?;
+        Ok(affected > 0)
+    }
+
+    async fn clear_digest_item_promotion(&self, id: &str, card_id: Uuid) -> Result<(), DatabaseError> {
+        let conn = self.conn();
+        conn.execute(
+            "UPDATE digest_items SET promoted_card_id = NULL WHERE id = ?1 AND promoted_card_id = ?2",
+            params![id, card_id.to_string()],
+        )
+        .await
+        .map_err(|e| DatabaseError::Query(format!("clear_digest_item_promotion: {e}")))?;
+        Ok(())
+    }
+
+    // ── Triage Rules ────────────────────────────────────────────────
+
+    async fn list_triage_rules(&self) -> Result<Vec<TriageRule>, DatabaseError> {
+        let conn = self.conn();
+        let mut rows = conn
+            .query(
+                &format!("SELECT {TRIAGE_RULE_COLUMNS} FROM triage_rules ORDER BY created_at ASC, rowid ASC"),
+                (),
+            )
+            .await
+            .map_err(|e| DatabaseError::Query(format!("list_triage_rules: {e}")))?;
+
+        let mut rules = Vec::new();
+        while let Some(row) = rows
+            .next()
+            .await
+            .map_err(|e| DatabaseError::Query(format!("list_triage_rules next: {e}")))?
+        {
+            match row_to_triage_rule(&row) {
+                Ok(rule) => rules.push(rule),
+                Err(e) => tracing::warn!("Skipping triage rule row: {e}"),
+            }
+        }
+        Ok(rules)
+    }
+
+    async fn get_triage_rule(&self, id: Uuid) -> Result<Option<TriageRule>, DatabaseError> {
+        let conn = self.conn();
+        let mut rows = conn
+            .query(
+                &format!("SELECT {TRIAGE_RULE_COLUMNS} FROM triage_rules WHERE id = ?1"),
+                params![id.to_string()],
+            )
+            .await
+            .map_err(|e| DatabaseError::Query(format!("get_triage_rule: {e}")))?;
+
+        match rows.next().await {
+            Ok(Some(row)) => row_to_triage_rule(&row).map(Some),
+            Ok(None) => Ok(None),
+            Err(e) => Err(DatabaseError::Query(format!("get_triage_rule: {e}"))),
+        }
+    }
+
+    async fn create_triage_rule(&self, rule: &TriageRule) -> Result<(), DatabaseError> {
+        let conn = self.conn();
+        conn.execute(
+            &format!(
+                "INSERT INTO triage_rules ({TRIAGE_RULE_COLUMNS}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)"
+            ),
+            params![
+                rule.id.to_string(),
+                rule.name.as_str(),
+                rule.field.as_str(),
+                rule.kind.as_str(),
+                rule.pattern.as_str(),
+                rule.action.as_str(),
+                rule.note.as_str(),
+                rule.enabled as i64,
+                rule.created_at.to_rfc3339(),
+                rule.updated_at.to_rfc3339(),
+            ],
+        )
+        .await
+        .map_err(|e| DatabaseError::Query(format!("create_triage_rule: {e}")))?;
+        Ok(())
+    }
+
+    async fn update_triage_rule(&self, rule: &TriageRule) -> Result<(), DatabaseError> {
+        let conn = self.conn();
+        conn.execute(
+            "UPDATE triage_rules SET name = ?1, field = ?2, kind = ?3, pattern = ?4, action = ?5, note = ?6, enabled = ?7, updated_at = ?8 WHERE id = ?9",
+            params![
+                rule.name.as_str(),
+                rule.field.as_str(),
+                rule.kind.as_str(),
+                rule.pattern.as_str(),
+                rule.action.as_str(),
+                rule.note.as_str(),
+                rule.enabled as i64,
+                rule.updated_at.to_rfc3339(),
+                rule.id.to_string(),
+            ],
+        )
+        .await
+        .map_err(|e| DatabaseError::Query(format!("update_triage_rule: {e}")))?;
+        Ok(())
+    }
+
+    async fn delete_triage_rule(&self, id: Uuid) -> Result<bool, DatabaseError> {
+        let conn = self.conn();
+        let affected = conn
+            .execute(
+                "DELETE FROM triage_rules WHERE id = ?1",
+                params![id.to_string()],
+            )
+            .await
+            .map_err(|e| DatabaseError::Query(format!("delete_triage_rule: {e}")))?;
+        Ok(affected > 0)
+    }
+
+    // ── Approval Rules ──────────────────────────────────────────────
+
+    async fn list_approval_rules(&self) -> Result<Vec<ApprovalRule>, DatabaseError> {
+        let conn = self.conn();
+        let mut rows = conn
+            .query(
+                &format!("SELECT {APPROVAL_RULE_COLUMNS} FROM approval_rules ORDER BY created_at ASC, rowid ASC"),
+                (),
+            )
+            .await
+            .map_err(|e| DatabaseError::Query(format!("list_approval_rules: {e}")))?;
+
+        let mut rules = Vec::new();
+        while let Some(row) = rows
+            .next()
+            .await
+            .map_err(|e| DatabaseError::Query(format!("list_approval_rules next: {e}")))?
+        {
+            match row_to_approval_rule(&row) {
+                Ok(rule) => rules.push(rule),
+                Err(e) => tracing::warn!("Skipping approval rule row: {e}"),
+            }
+        }
+        Ok(rules)
+    }
+
+    async fn get_approval_rule(&self, id: Uuid) -> Result<Option<ApprovalRule>, DatabaseError> {
+        let conn = self.conn();
+        let mut rows = conn
+            .query(
+                &format!("SELECT {APPROVAL_RULE_COLUMNS} FROM approval_rules WHERE id = ?1"),
+                params![id.to_string()],
+            )
+            .await
+            .map_err(|e| DatabaseError::Query(format!("get_approval_rule: {e}")))?;
+
+        match rows.next().await {
+            Ok(Some(row)) => row_to_approval_rule(&row).map(Some),
+            Ok(None) => Ok(None),
+            Err(e) => Err(DatabaseError::Query(format!("get_approval_rule: {e}"))),
+        }
+    }
+
+    async fn create_approval_rule(&self, rule: &ApprovalRule) -> Result<(), DatabaseError> {
+        let conn = self.conn();
+        conn.execute(
+            &format!(
+                "INSERT INTO approval_rules ({APPROVAL_RULE_COLUMNS}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)"
+            ),
+            params![
+                rule.id.to_string(),
+                rule.name.as_str(),
+                rule.tool.as_str(),
+                opt_text(rule.argument.as_deref()),
+                rule.kind.as_str(),
+                rule.pattern.as_str(),
+                rule.negate as i64,
+                rule.decision.as_str(),
+                rule.note.as_str(),
+                rule.enabled as i64,
+                rule.created_at.to_rfc3339(),
+                rule.updated_at.to_rfc3339(),
+            ],
+        )
+        .await
+        .map_err(|e| DatabaseError::Query(format!("create_approval_rule: {e}")))?;
+        Ok(())
+    }
+
+    async fn update_approval_rule(&self, rule: &ApprovalRule) -> Result<(), DatabaseError> {
+        let conn = self.conn();
+        conn.execute(
+            "UPDATE approval_rules SET name = ?1, tool = ?2, argument = ?3, kind = ?4, pattern = ?5, negate = ?6, decision = ?7, note = ?8, enabled = ?9, updated_at = ?10 WHERE id = ?11",
+            params![
+                rule.name.as_str(),
+                rule.tool.as_str(),
+                opt_text(rule.argument.as_deref()),
+                rule.kind.as_str(),
+                rule.pattern.as_str(),
+                rule.negate as i64,
+                rule.decision.as_str(),
+                rule.note.as_str(),
+                rule.enabled as i64,
+                rule.updated_at.to_rfc3339(),
+                rule.id.to_string(),
+            ],
+        )
+        .await
+        .map_err(|e| DatabaseError::Query(format!("update_approval_rule: {e}")))?;
+        Ok(())
+    }
+
+    async fn delete_approval_rule(&self, id: Uuid) -> Result<bool, DatabaseError> {
+        let conn = self.conn();
+        let affected = conn
+            .execute(
+                "DELETE FROM approval_rules WHERE id = ?1",
+                params![id.to_string()],
+            )
+            .await
+            .map_err(|e| DatabaseError::Query(format!("delete_approval_rule: {e}")))?;
+        Ok(affected > 0)
+    }
+
+    // ── Triage Feedback ─────────────────────────────────────────────
+
+    async fn card_outcomes_by_sender(&self) -> Result<Vec<SenderOutcome>, DatabaseError> {
+        let conn = self.conn();
+        let mut rows = conn
+            .query(
+                "SELECT channel, LOWER(source_sender),
+                        SUM(CASE WHEN status = 'dismissed' THEN 1 ELSE 0 END),
+                        SUM(CASE WHEN status IN ('approved', 'scheduled', 'sending', 'sent', 'failed') THEN 1 ELSE 0 END)
+                 FROM cards
+                 WHERE card_type = 'reply' AND source_sender != ''
+                 GROUP BY channel, LOWER(source_sender)",
+                (),
+            )
+            .await
+            .map_err(|e| DatabaseError::Query(format!("card_outcomes_by_sender: {e}")))?;
+
+        let mut outcomes = Vec::new();
+        while let Some(row) = rows
+            .next()
+            .await
+            .map_err(|e| DatabaseError::Query(format!("card_outcomes_by_sender next: {e}")))?
+        {
+            let dismissed: i64 = row.get(2).unwrap_or(0);
+            let approved: i64 = row.get(3).unwrap_or(0);
+            outcomes.push(SenderOutcome {
+                channel: row.get(0).unwrap_or_default(),
+                sender: row.get(1).unwrap_or_default(),
+                dismissed: dismissed as u32,
+                approved: approved as u32,
+            });
+        }
+        Ok(outcomes)
+    }
+
+    async fn insert_rule_suggestion(&self, suggestion: &RuleSuggestion) -> Result<(), DatabaseError> {
+        let conn = self.conn();
+        conn.execute(
+            &format!(
+                "INSERT OR IGNORE INTO rule_suggestions ({RULE_SUGGESTION_COLUMNS}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)"
+            ),
+            params![
+                suggestion.id.to_string(),
+                suggestion.scope.as_str(),
+                suggestion.value.as_str(),
+                suggestion.card_id.to_string(),
+                suggestion.status.as_str(),
+                suggestion.dismissed as i64,
+                suggestion.created_at.to_rfc3339(),
+            ],
+        )
+        .await
+        .map_err(|e| DatabaseError::Query(format!("insert_rule_suggestion: {e}")))?;
+        Ok(())
+    }
+
+    async fn has_rule_suggestion(
+        &self,
+        scope: SuggestionScope,
+        value: &str,
+    ) -> Result<bool, DatabaseError> {
+        let conn = self.conn();
+        let mut rows = conn
+            .query(
+                "SELECT 1 FROM rule_suggestions WHERE scope = ?1 AND value = ?2",
+                params![scope.as_str(), value],
+            )
+            .await
+            .map_err(|e| DatabaseError::Query(format!("has_rule_suggestion: {e}")))?;
+
+        match rows.next().await {
+            Ok(row) => Ok(row.is_some()),
+            Err(e) => Err(DatabaseError::Query(format!("has_rule_suggestion: {e}"))),
+        }
+    }
+
+    async fn get_rule_suggestion_by_card(
+        &self,
+        card_id: Uuid,
+    ) -> Result<Option<RuleSuggestion>, DatabaseError> {
+        let conn = self.conn();
+        let mut rows = conn
+            .query(
+                &format!("SELECT {RULE_SUGGESTION_COLUMNS} FROM rule_suggestions WHERE card_id = ?1"),
+                params![card_id.to_string()],
+            )
+            .await
+            .map_err(|e| DatabaseError::Query(format!("get_rule_suggestion_by_card: {e}")))?;
+
+        match rows.next().await {
+            Ok(Some(row)) => row_to_rule_suggestion(&row).map(Some),
+            Ok(None) => Ok(None),
+            Err(e) => Err(DatabaseError::Query(format!("get_rule_suggestion_by_card: {e}"))),
+        }
+    }
+
+    async fn update_rule_suggestion_status(
+        &self,
+        id: Uuid,
+        status: SuggestionStatus,
+    ) -> Result<(), DatabaseError> {
+        let conn = self.conn();
+        conn.execute(
+            "UPDATE rule_suggestions SET status = ?1, resolved_at = ?2 WHERE id = ?3",
+            params![status.as_str(), Utc::now().to_rfc3339(), id.to_string()],
+        )
+        .await
+        .map_err(|e| DatabaseError::Query(format!("update_rule_suggestion_status: {e}")))?;
+        Ok(())
+    }
+
+    // ── Conversations ───────────────────────────────────────────────
+
+    async fn ensure_conversation(
+        &self,
+        thread_id: Uuid,
+        channel: &str,
+        user_id: &str,
+        _title: Option<&str>,
+    ) -> Result<(), DatabaseError> {
+        let conn = self.conn();
+        let now = Utc::now().to_rfc3339();
+        conn.execute(
+            "INSERT INTO conversations (id, channel, user_id)
+             VALUES (?1, ?2, ?3)
+             ON CONFLICT (id) DO UPDATE SET last_activity = ?4",
+            params![thread_id.to_string(), channel, user_id, now],
+        )
+        .await
+        .map_err(|e| DatabaseError::Query(format!("ensure_conversation: {e}")))?;
+
+        Ok(())
+    }
+
+    async fn add_conversation_message(
+        &self,
+        thread_id: Uuid,
+        role: &str,
+        content: &str,
+    ) -> Result<(), DatabaseError> {
+        let conn = self.conn();
+        let id = Uuid::new_v4();
+        conn.execute(
+            "INSERT INTO conversation_messages (id, conversation_id, role, content)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![id.to_string(), thread_id.to_string(), role, content],
+        )
+        .await
+        .map_err(|e| DatabaseError::Query(format!("add_conversation_message: {e}")))?;
+
+        // Touch last_activity
+        let now = Utc::now().to_rfc3339();
+        let _ = conn
+            .execute(
+                "UPDATE conversations SET last_activity = ?2 WHERE id = ?1",
+                params![thread_id.to_string(), now],
+            )
+            .await;
+
+        Ok(())
+    }
+
+    async fn list_conversation_messages(
+        &self,
+        thread_id: Uuid,
+    ) -> Result<Vec<ConversationMessage>, DatabaseError> {
+        let conn = self.conn();
+        let mut rows = conn
+            .query(
+                "SELECT id, role, content, created_at FROM conversation_messages
+                 WHERE conversation_id = ?1 ORDER BY created_at ASC",
+                params![thread_id.to_string()],
+            )
+            .await
+            .map_err(|e| DatabaseError::Query(format!("list_conversation_messages: {e}")))?;
+
+        let mut messages = Vec::new();
+        while let Ok(Some(row)) = rows.next().await {
+            let id_str: String = row.get(0).unwrap_or_default();
+            let role: String = row.get(1).unwrap_or_default();
+            let content: String = row.get(2).unwrap_or_default();
+            let created_str: String = row.get(3).unwrap_or_default();
+            messages.push(ConversationMessage {
+                id: Uuid::parse_str(&id_str).unwrap_or_else(|_| Uuid::nil()),
+                role,
+                content,
+                created_at: parse_datetime(&created_str),
+            });
+        }
+        Ok(messages)
+    }
+
+    async fn get_conversation_metadata(
+        &self,
+        thread_id: Uuid,
+    ) -> Result<Option<serde_json::Value>, DatabaseError> {
+        let conn = self.conn();
+        let mut rows = conn
+            .query(
+                "SELECT metadata FROM conversations WHERE id = ?1",
+                params![thread_id.to_string()],
+            )
+            .await
+            .map_err(|e| DatabaseError::Query(format!("get_conversation_metadata: {e}")))?;
+
+        match rows.next().await {
+            Ok(Some(row)) => {
+                let meta_str: String = row.get(0).unwrap_or_else(|_| "{}".to_string());
+                let value: serde_json::Value =
+                    serde_json::from_str(&meta_str).unwrap_or(serde_json::json!({}));
+                Ok(Some(value))
+            }
+            Ok(None) => Ok(None),
+            Err(e) => Err(DatabaseError::Query(format!(
+                "get_conversation_metadata: {e}"
+            ))),
+        }
+    }
+
+    async fn update_conversation_metadata_field(
+        &self,
+        thread_id: Uuid,
+        key: &str,
+        value: &serde_json::Value,
+    ) -> Result<(), DatabaseError> {
+        let conn = self.conn();
+
+        // Read current metadata
+        let mut rows = conn
+            .query(
                 "SELECT metadata FROM conversations WHERE id = ?1",
                 params![thread_id.to_string()],
             )
             .await
-            .map_err(|e| DatabaseError::Query(format!("read metadata: {e}")))?;
+            .map_err(|e| DatabaseError::Query(format!("read metadata: {e}")))?;
+
+        let mut metadata: serde_json::Value = match rows.next().await {
+            Ok(Some(row)) => {
+                let meta_str: String = row.get(0).unwrap_or_else(|_| "{}".to_string());
+                serde_json::from_str(&meta_str).unwrap_or(serde_json::json!({}))
+            }
+            _ => serde_json::json!({}),
+        };
+
+        // Update the field
+        if let serde_json::Value::Object(ref mut map) = metadata {
+            map.insert(key.to_string(), value.clone());
+        }
+
+        let meta_str = serde_json::to_string(&metadata)
+            .map_err(|e| DatabaseError::Serialization(e.to_string()))?;
+
+        conn.execute(
+            "UPDATE conversations SET metadata = ?1 WHERE id = ?2",
+            params![meta_str, thread_id.to_string()],
+        )
+        .await
+        .map_err(|e| DatabaseError::Query(format!("update metadata: {e}")))?;
+
+        Ok(())
+    }
+
+    // ── LLM Call Tracking ────────────────────────────────────────────
+
+    async fn record_llm_call(
+        &self,
+        record: &crate::store::traits::LlmCallRecord<'_>,
+    ) -> Result<Uuid, DatabaseError> {
+        let conn = self.conn();
+        let id = Uuid::new_v4();
+        let conv_id: libsql::Value = match record.conversation_id {
+            Some(cid) => libsql::Value::Text(cid.to_string()),
+            None => libsql::Value::Null,
+        };
+        let run_id: libsql::Value = match record.routine_run_id {
+            Some(rid) => libsql::Value::Text(rid.to_string()),
+            None => libsql::Value::Null,
+        };
+        let purpose: libsql::Value = match record.purpose {
+            Some(p) => libsql::Value::Text(p.to_string()),
+            None => libsql::Value::Null,
+        };
+
+        let now = Utc::now().to_rfc3339();
+        conn.execute(
+            "INSERT INTO llm_calls (id, conversation_id, routine_run_id, provider, model, input_tokens, output_tokens, cost, purpose, created_at, role, todo_id, price_source) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+            params![
+                id.to_string(),
+                conv_id,
+                run_id,
+                record.provider,
+                record.model,
+                record.input_tokens as i64,
+                record.output_tokens as i64,
+                record.cost.to_string(),
+                purpose,
+                now,
+                opt_text(record.role),
+                opt_text_owned(record.todo_id.map(|t| t.to_string())),
+                opt_text(record.price_source),
+            ],
+        )
+        .await
+        .map_err(|e| DatabaseError::Query(format!("record_llm_call: {e}")))?;
+
+        Ok(id)
+    }
+
+    async fn get_conversation_cost(
+        &self,
+        conversation_id: Uuid,
+    ) -> Result<crate::store::traits::LlmCostSummary, DatabaseError> {
+        let conn = self.conn();
+        let mut rows = conn
+            .query(
+                "SELECT TOTAL(CAST(cost AS REAL)), TOTAL(input_tokens), TOTAL(output_tokens), COUNT(*) FROM llm_calls WHERE conversation_id = ?1",
+                params![conversation_id.to_string()],
+            )
+            .await
+            .map_err(|e| DatabaseError::Query(format!("get_conversation_cost: {e}")))?;
 
-        let mut metadata: serde_json::Value = match rows.next().await {
-            Ok(Some(row)) => {
-                let meta_str: String = row.get(0).unwrap_or_else(|_| "{}".to_string());
-                serde_json::from_str(&meta_str).unwrap_or(serde_json::json!({}))
-            }
-            _ => serde_json::json!({}),
-        };
+        parse_cost_summary_row(&mut rows).await
+    }
 
-        // Update the field
-        if let serde_json::Value::Object(ref mut map) = metadata {
-            map.insert(key.to_string(), value.clone());
+    async fn get_costs_by_period(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<crate::store::traits::LlmCostSummary, DatabaseError> {
+        let conn = self.conn();
+        let mut rows = conn
+            .query(
+                "SELECT TOTAL(CAST(cost AS REAL)), TOTAL(input_tokens), TOTAL(output_tokens), COUNT(*) FROM llm_calls WHERE created_at >= ?1 AND created_at < ?2",
+                params![start.to_rfc3339(), end.to_rfc3339()],
+            )
+            .await
+            .map_err(|e| DatabaseError::Query(format!("get_costs_by_period: {e}")))?;
+
+        parse_cost_summary_row(&mut rows).await
+    }
+
+    async fn get_total_spend(&self) -> Result<crate::store::traits::LlmCostSummary, DatabaseError> {
+        let conn = self.conn();
+        let mut rows = conn
+            .query(
+                "SELECT TOTAL(CAST(cost AS REAL)), TOTAL(input_tokens), TOTAL(output_tokens), COUNT(*) FROM llm_calls",
+                (),
+            )
+            .await
+            .map_err(|e| DatabaseError::Query(format!("get_total_spend: {e}")))?;
+
+        parse_cost_summary_row(&mut rows).await
+    }
+
+    async fn get_costs_by_role(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<(String, crate::store::traits::LlmCostSummary)>, DatabaseError> {
+        use std::str::FromStr;
+
+        let conn = self.conn();
+        let mut rows = conn
+            .query(
+                "SELECT COALESCE(role, 'unknown') AS r, TOTAL(CAST(cost AS REAL)), TOTAL(input_tokens), TOTAL(output_tokens), COUNT(*) FROM llm_calls WHERE created_at >= ?1 AND created_at < ?2 GROUP BY r ORDER BY r",
+                params![start.to_rfc3339(), end.to_rfc3339()],
+            )
+            .await
+            .map_err(|e| DatabaseError::Query(format!("get_costs_by_role: {e}")))?;
+
+        let mut results = Vec::new();
+        while let Some(row) = rows
+            .next()
+            .await
+            .map_err(|e| DatabaseError::Query(format!("get_costs_by_role next: {e}")))?
+        {
+            let role: String = row.get(0).unwrap_or_else(|_| "unknown".to_string());
+            let cost_f64: f64 = row.get(1).unwrap_or(0.0);
+            let input_tokens: f64 = row.get(2).unwrap_or(0.0);
+            let output_tokens: f64 = row.get(3).unwrap_or(0.0);
+            let call_count = row.get::<i64>(4).unwrap_or(0);
+            results.push((
+                role,
+                crate::store::traits::LlmCostSummary {
+                    total_cost: Decimal::from_str(&format!("{cost_f64:.10}")).unwrap_or(Decimal::ZERO),
+                    total_input_tokens: input_tokens as u64,
+                    total_output_tokens: output_tokens as u64,
+                    call_count: call_count as u64,
+                },
+            ));
         }
+        Ok(results)
+    }
 
-        let meta_str = serde_json::to_string(&metadata)
-            .map_err(|e| DatabaseError::Serialization(e.to_string()))?;
+    async fn get_fallback_priced_models(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<(String, u64)>, DatabaseError> {
+        let conn = self.conn();
+        let mut rows = conn
+            .query(
+                "SELECT model, COUNT(*) FROM llm_calls WHERE price_source = 'fallback' AND created_at >= ?1 AND created_at < ?2 GROUP BY model ORDER BY model",
+                params![start.to_rfc3339(), end.to_rfc3339()],
+            )
+            .await
+            .map_err(|e| DatabaseError::Query(format!("get_fallback_priced_models: {e}")))?;
+
+        let mut models = Vec::new();
+        while let Some(row) = rows
+            .next()
+            .await
+            .map_err(|e| DatabaseError::Query(format!("get_fallback_priced_models next: {e}")))?
+        {
+            let model: String = row.get(0).unwrap_or_default();
+            let calls: i64 = row.get(1).unwrap_or(0);
+            models.push((model, calls as u64));
+        }
+        Ok(models)
+    }
+
+    // ── Budgets ─────────────────────────────────────────────────────
+
+    async fn list_budgets(&self) -> Result<Vec<Budget>, DatabaseError> {
+        let conn = self.conn();
+        let mut rows = conn
+            .query(
+                &format!("SELECT {BUDGET_COLUMNS} FROM budgets ORDER BY created_at ASC, rowid ASC"),
+                (),
+            )
+            .await
+            .map_err(|e| DatabaseError::Query(format!("list_budgets: {e}")))?;
+
+        let mut budgets = Vec::new();
+        while let Some(row) = rows
+            .next()
+            .await
+            .map_err(|e| DatabaseError::Query(format!("list_budgets next: {e}")))?
+        {
+            match row_to_budget(&row) {
+                Ok(budget) => budgets.push(budget),
+                Err(e) => tracing::warn!("Skipping budget row: {e}"),
+            }
+        }
+        Ok(budgets)
+    }
+
+    async fn get_budget(&self, id: Uuid) -> Result<Option<Budget>, DatabaseError> {
+        let conn = self.conn();
+        let mut rows = conn
+            .query(
+                &format!("SELECT {BUDGET_COLUMNS} FROM budgets WHERE id = ?1"),
+                params![id.to_string()],
+            )
+            .await
+            .map_err(|e| DatabaseError::Query(format!("get_budget: {e}")))?;
 
+        match rows.next().await {
+            Ok(Some(row)) => row_to_budget(&row).map(Some),
+            Ok(None) => Ok(None),
+            Err(e) => Err(DatabaseError::Query(format!("get_budget: {e}"))),
+        }
+    }
+
+    async fn create_budget(&self, budget: &Budget) -> Result<(), DatabaseError> {
+        let conn = self.conn();
         conn.execute(
-            "UPDATE conversations SET metadata = ?1 WHERE id = ?2",
-            params![meta_str, thread_id.to_string()],
+            &format!(
+                "INSERT INTO budgets ({BUDGET_COLUMNS}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)"
+            ),
+            params![
+                budget.id.to_string(),
+                budget.scope.as_str(),
+                opt_text_owned(budget.scope_id.map(|id| id.to_string())),
+                budget.period.as_str(),
+                opt_text_owned(budget.soft_limit.map(|d| d.to_string())),
+                opt_text_owned(budget.hard_limit.map(|d| d.to_string())),
+                budget.enabled as i64,
+                opt_text_owned(budget.raise_card_id.map(|id| id.to_string())),
+                opt_text_owned(budget.raise_requested_at.map(|t| t.to_rfc3339())),
+                budget.created_at.to_rfc3339(),
+                budget.updated_at.to_rfc3339(),
+            ],
         )
         .await
-        .map_err(|e| DatabaseError::Query(format!("update metadata: {e}")))?;
+        .map_err(|e| DatabaseError::Query(format!("create_budget: {e}")))?;
+        Ok(())
+    }
 
+    async fn update_budget(&self, budget: &Budget) -> Result<(), DatabaseError> {
+        let conn = self.conn();
+        conn.execute(
+            "UPDATE budgets SET scope = ?1, scope_id = ?2, period = ?3, soft_limit = ?4, hard_limit = ?5, enabled = ?6, raise_card_id = ?7, raise_requested_at = ?8, updated_at = ?9 WHERE id = ?10",
+            params![
+                budget.scope.as_str(),
+                opt_text_owned(budget.scope_id.map(|id| id.to_string())),
+                budget.period.as_str(),
+                opt_text_owned(budget.soft_limit.map(|d| d.to_string())),
+                opt_text_owned(budget.hard_limit.map(|d| d.to_string())),
+                budget.enabled as i64,
+                opt_text_owned(budget.raise_card_id.map(|id| id.to_string())),
+                opt_text_owned(budget.raise_requested_at.map(|t| t.to_rfc3339())),
+                budget.updated_at.to_rfc3339(),
+                budget.id.to_string(),
+            ],
+        )
+        .await
+        .map_err(|e| DatabaseError::Query(format!("update_budget: {e}")))?;
         Ok(())
     }
 
-    // ── LLM Call Tracking ────────────────────────────────────────────
+    async fn delete_budget(&self, id: Uuid) -> Result<bool, DatabaseError> {
+        let conn = self.conn();
+        let affected = conn
+            .execute("DELETE FROM budgets WHERE id = ?1", params![id.to_string()])
+            .await
+            .map_err(|e| DatabaseError::Query(format!("delete_budget: {e}")))?;
+        Ok(affected > 0)
+    }
 
-    async fn record_llm_call(
+    async fn get_scope_spend(
         &self,
-        record: &crate::store::traits::LlmCallRecord<'_>,
-    ) -> Result<Uuid, DatabaseError> {
+        scope: BudgetScope,
+        scope_id: Option<Uuid>,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Decimal, DatabaseError> {
+        use std::str::FromStr;
+
+        let window = (start.to_rfc3339(), end.to_rfc3339());
         let conn = self.conn();
-        let id = Uuid::new_v4();
-        let conv_id: libsql::Value = match record.conversation_id {
-            Some(cid) => libsql::Value::Text(cid.to_string()),
-            None => libsql::Value::Null,
-        };
-        let run_id: libsql::Value = match record.routine_run_id {
-            Some(rid) => libsql::Value::Text(rid.to_string()),
-            None => libsql::Value::Null,
+        let result = match (scope, scope_id) {
+            (BudgetScope::Global, _) => {
+                conn.query(
+                    "SELECT TOTAL(CAST(cost AS REAL)) FROM llm_calls WHERE created_at >= ?1 AND created_at < ?2",
+                    params![window.0, window.1],
+                )
+                .await
+            }
+            (BudgetScope::Routine, Some(id)) => {
+                conn.query(
+                    "SELECT TOTAL(CAST(c.cost AS REAL)) FROM llm_calls c JOIN routine_runs r ON r.id = c.routine_run_id WHERE c.created_at >= ?1 AND c.created_at < ?2 AND r.routine_id = ?3",
+                    params![window.0, window.1, id.to_string()],
+                )
+                .await
+            }
+            (BudgetScope::Todo, Some(id)) => {
+                conn.query(
+                    "SELECT TOTAL(CAST(cost AS REAL)) FROM llm_calls WHERE created_at >= ?1 AND created_at < ?2 AND todo_id = ?3",
+                    params![window.0, window.1, id.to_string()],
+                )
+                .await
+            }
+            (_, None) => return Ok(Decimal::ZERO),
         };
-        let purpose: libsql::Value = match record.purpose {
-            Some(p) => libsql::Value::Text(p.to_string()),
-            None => libsql::Value::Null,
+        let mut rows = result.map_err(|e| DatabaseError::Query(format!("get_scope_spend: {e}")))?;
+
+        let cost: f64 = match rows.next().await {
+            Ok(Some(row)) => row.get(0).unwrap_or(0.0),
+            Ok(None) => 0.0,
+            Err(e) => return Err(DatabaseError::Query(format!("get_scope_spend: {e}"))),
         };
+        Ok(Decimal::from_str(&format!("{cost:.10}"))
+            .unwrap_or(Decimal::ZERO)
+            .normalize())
+    }
+
+    // ── Agent Sessions ──────────────────────────────────────────────
+
+    async fn upsert_agent_session(&self, session: &Session) -> Result<(), DatabaseError> {
+        let tools = serde_json::to_string(&session.auto_approved_tools)
+            .map_err(|e| DatabaseError::Serialization(format!("auto_approved_tools: {e}")))?;
+        let metadata = (!session.metadata.is_null()).then(|| session.metadata.to_string());
+        let conn = self.conn();
+        conn.execute(
+            &format!(
+                "INSERT INTO agent_sessions ({AGENT_SESSION_COLUMNS}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+                 ON CONFLICT(user_id) DO UPDATE SET
+                    id = excluded.id,
+                    active_thread = excluded.active_thread,
+                    metadata = excluded.metadata,
+                    auto_approved_tools = excluded.auto_approved_tools,
+                    last_active_at = excluded.last_active_at"
+            ),
+            params![
+                session.user_id.as_str(),
+                session.id.to_string(),
+                opt_text_owned(session.active_thread.map(|id| id.to_string())),
+                opt_text_owned(metadata),
+                tools,
+                session.created_at.to_rfc3339(),
+                session.last_active_at.to_rfc3339(),
+            ],
+        )
+        .await
+        .map_err(|e| DatabaseError::Query(format!("upsert_agent_session: {e}")))?;
+        Ok(())
+    }
+
+    async fn get_agent_session(&self, user_id: &str) -> Result<Option<Session>, DatabaseError> {
+        let conn = self.conn();
+        let mut rows = conn
+            .query(
+                &format!("SELECT {AGENT_SESSION_COLUMNS} FROM agent_sessions WHERE user_id = ?1"),
+                params![user_id],
+            )
+            .await
+            .map_err(|e| DatabaseError::Query(format!("get_agent_session: {e}")))?;
+
+        match rows.next().await {
+            Ok(Some(row)) => row_to_agent_session(&row).map(Some),
+            Ok(None) => Ok(None),
+            Err(e) => Err(DatabaseError::Query(format!("get_agent_session: {e}"))),
+        }
+    }
+
+    async fn upsert_agent_thread(
+        &self,
+        binding: &ThreadBinding,
+        thread: &Thread,
+        undo: Option<&UndoManager>,
+    ) -> Result<(), DatabaseError> {
+        let data = serde_json::to_string(thread)
+            .map_err(|e| DatabaseError::Serialization(format!("agent thread data: {e}")))?;
+        let undo = undo
+            .map(serde_json::to_string)
+            .transpose()
+            .map_err(|e| DatabaseError::Serialization(format!("agent thread undo: {e}")))?;
+        let conn = self.conn();
+        conn.execute(
+            "INSERT INTO agent_threads (id, session_id, user_id, channel, external_thread_id, data, undo, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+             ON CONFLICT(id) DO UPDATE SET
+                session_id = excluded.session_id,
+                user_id = excluded.user_id,
+                channel = excluded.channel,
+                external_thread_id = excluded.external_thread_id,
+                data = excluded.data,
+                undo = excluded.undo,
+                updated_at = excluded.updated_at",
+            params![
+                thread.id.to_string(),
+                thread.session_id.to_string(),
+                binding.user_id.as_str(),
+                binding.channel.as_str(),
+                opt_text(binding.external_thread_id.as_deref()),
+                data,
+                opt_text_owned(undo),
+                Utc::now().to_rfc3339(),
+            ],
+        )
+        .await
+        .map_err(|e| DatabaseError::Query(format!("upsert_agent_thread: {e}")))?;
+        Ok(())
+    }
+
+    async fn find_agent_thread(
+        &self,
+        binding: &ThreadBinding,
+    ) -> Result<Option<StoredThread>, DatabaseError> {
+        let conn = self.conn();
+        let mut rows = conn
+            .query(
+                &format!(
+                    "SELECT {AGENT_THREAD_COLUMNS} FROM agent_threads
+                     WHERE user_id = ?1 AND channel = ?2 AND external_thread_id IS ?3
+                     ORDER BY updated_at DESC, rowid DESC LIMIT 1"
+                ),
+                params![
+                    binding.user_id.as_str(),
+                    binding.channel.as_str(),
+                    opt_text(binding.external_thread_id.as_deref()),
+                ],
+            )
+            .await
+            .map_err(|e| DatabaseError::Query(format!("find_agent_thread: {e}")))?;
+
+        match rows.next().await {
+            Ok(Some(row)) => row_to_stored_thread(&row).map(Some),
+            Ok(None) => Ok(None),
+            Err(e) => Err(DatabaseError::Query(format!("find_agent_thread: {e}"))),
+        }
+    }
+
+    // ── Outbox ──────────────────────────────────────────────────────
+
+    async fn insert_outbox_job(&self, job: &OutboxJob) -> Result<(), DatabaseError> {
+        let card = serde_json::to_string(&job.card)
+            .map_err(|e| DatabaseError::Serialization(format!("outbox card: {e}")))?;
+        let conn = self.conn();
+        conn.execute(
+            &format!(
+                "INSERT INTO outbox ({OUTBOX_COLUMNS}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)"
+            ),
+            params![
+                job.id.to_string(),
+                job.card_id.to_string(),
+                card,
+                job.status.as_str(),
+                job.attempts as i64,
+                job.next_attempt_at.to_rfc3339(),
+                opt_text(job.last_error.as_deref()),
+                opt_text_owned(job.decision_card_id.map(|id| id.to_string())),
+                job.created_at.to_rfc3339(),
+                job.updated_at.to_rfc3339(),
+            ],
+        )
+        .await
+        .map_err(|e| DatabaseError::Query(format!("insert_outbox_job: {e}")))?;
+        Ok(())
+    }
+
+    async fn update_outbox_job(&self, job: &OutboxJob) -> Result<(), DatabaseError> {
+        let card = serde_json::to_string(&job.card)
+            .map_err(|e| DatabaseError::Serialization(format!("outbox card: {e}")))?;
+        let conn = self.conn();
+        conn.execute(
+            "UPDATE outbox SET card = ?1, status = ?2, attempts = ?3, next_attempt_at = ?4,
+                    last_error = ?5, decision_card_id = ?6, updated_at = ?7
+             WHERE id = ?8",
+            params![
+                card,
+                job.status.as_str(),
+                job.attempts as i64,
+                job.next_attempt_at.to_rfc3339(),
+                opt_text(job.last_error.as_deref()),
+                opt_text_owned(job.decision_card_id.map(|id| id.to_string())),
+                job.updated_at.to_rfc3339(),
+                job.id.to_string(),
+            ],
+        )
+        .await
+        .map_err(|e| DatabaseError::Query(format!("update_outbox_job: {e}")))?;
+        Ok(())
+    }
+
+    async fn due_outbox_jobs(
+        &self,
+        now: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<Vec<OutboxJob>, DatabaseError> {
+        let conn = self.conn();
+        let mut rows = conn
+            .query(
+                &format!(
+                    "SELECT {OUTBOX_COLUMNS} FROM outbox
+                     WHERE status IN ('queued', 'scheduled') AND next_attempt_at <= ?1
+                     ORDER BY next_attempt_at ASC, created_at ASC
+                     LIMIT ?2"
+                ),
+                params![now.to_rfc3339(), limit],
+            )
+            .await
+            .map_err(|e| DatabaseError::Query(format!("due_outbox_jobs: {e}")))?;
+
+        let mut jobs = Vec::new();
+        while let Some(row) = rows
+            .next()
+            .await
+            .map_err(|e| DatabaseError::Query(format!("due_outbox_jobs next: {e}")))?
+        {
+            jobs.push(row_to_outbox_job(&row)?);
+        }
+        Ok(jobs)
+    }
+
+    async fn claim_outbox_job(&self, job: &OutboxJob) -> Result<bool, DatabaseError> {
+        let conn = self.conn();
+        let affected = conn
+            .execute(
+                "UPDATE outbox SET status = 'sending', attempts = ?1, updated_at = ?2
+                 WHERE id = ?3 AND status IN ('queued', 'scheduled')",
+                params![
+                    job.attempts as i64,
+                    Utc::now().to_rfc3339(),
+                    job.id.to_string()
+                ],
+            )
+            .await
+            .map_err(|e| DatabaseError::Query(format!("claim_outbox_job: {e}")))?;
+        Ok(affected > 0)
+    }
+
+    async fn cancel_scheduled_outbox_job(&self, card_id: Uuid) -> Result<bool, DatabaseError> {
+        let conn = self.conn();
+        let affected = conn
+            .execute(
+                "UPDATE outbox SET status = 'discarded', updated_at = ?1
+                 WHERE card_id = ?2 AND status = 'scheduled'",
+                params![Utc::now().to_rfc3339(), card_id.to_string()],
+            )
+            .await
+            .map_err(|e| DatabaseError::Query(format!("cancel_scheduled_outbox_job: {e}")))?;
+        Ok(affected > 0)
+    }
+
+    async fn get_outbox_job_by_decision_card(
+        &self,
+        card_id: Uuid,
+    ) -> Result<Option<OutboxJob>, DatabaseError> {
+        let conn = self.conn();
+        let mut rows = conn
+            .query(
+                &format!("SELECT {OUTBOX_COLUMNS} FROM outbox WHERE decision_card_id = ?1"),
+                params![card_id.to_string()],
+            )
+            .await
+            .map_err(|e| DatabaseError::Query(format!("get_outbox_job_by_decision_card: {e}")))?;
+
+        match rows.next().await {
+            Ok(Some(row)) => row_to_outbox_job(&row).map(Some),
+            Ok(None) => Ok(None),
+            Err(e) => Err(DatabaseError::Query(format!(
+                "get_outbox_job_by_decision_card: {e}"
+            ))),
+        }
+    }
 
-        let now = Utc::now().to_rfc3339();
+    async fn requeue_sending_outbox_jobs(&self) -> Result<usize, DatabaseError> {
+        let conn = self.conn();
+        let affected = conn
+            .execute(
+                "UPDATE outbox SET status = 'queued', updated_at = ?1 WHERE status = 'sending'",
+                params![Utc::now().to_rfc3339()],
+            )
+            .await
+            .map_err(|e| DatabaseError::Query(format!("requeue_sending_outbox_jobs: {e}")))?;
+        Ok(affected as usize)
+    }
+
+    // ── Calendar ────────────────────────────────────────────────────
+
+    async fn upsert_calendar_event(&self, stored: &StoredEvent) -> Result<(), DatabaseError> {
+        let data = serde_json::to_string(&stored.event)
+            .map_err(|e| DatabaseError::Serialization(format!("calendar event: {e}")))?;
+        let event = &stored.event;
+        let conn = self.conn();
         conn.execute(
-            "INSERT INTO llm_calls (id, conversation_id, routine_run_id, provider, model, input_tokens, output_tokens, cost, purpose, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            &format!(
+                "INSERT OR REPLACE INTO calendar_events ({CALENDAR_EVENT_COLUMNS}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)"
+            ),
             params![
-                id.to_string(),
-                conv_id,
-                run_id,
-                record.provider,
-                record.model,
-                record.input_tokens as i64,
-                record.output_tokens as i64,
-                record.cost.to_string(),
-                purpose,
-                now,
+                event.key(),
+                event.uid.clone(),
+                stored.source.as_str(),
+                opt_text(stored.href.as_deref()),
+                opt_text(stored.etag.as_deref()),
+                event.summary.clone(),
+                event.start.to_rfc3339(),
+                event.end.to_rfc3339(),
+                event.all_day as i64,
+                data,
+                stored.updated_at.to_rfc3339(),
             ],
         )
         .await
-        .map_err(|e| DatabaseError::Query(format!("record_llm_call: {e}")))?;
-
-        Ok(id)
+        .map_err(|e| DatabaseError::Query(format!("upsert_calendar_event: {e}")))?;
+        Ok(())
     }
 
-    async fn get_conversation_cost(
-        &self,
-        conversation_id: Uuid,
-    ) -> Result<crate::store::traits::LlmCostSummary, DatabaseError> {
+    async fn get_calendar_event(&self, key: &str) -> Result<Option<StoredEvent>, DatabaseError> {
         let conn = self.conn();
         let mut rows = conn
             .query(
-                "SELECT TOTAL(CAST(cost AS REAL)), TOTAL(input_tokens), TOTAL(output_tokens), COUNT(*) FROM llm_calls WHERE conversation_id = ?1",
-                params![conversation_id.to_string()],
+                &format!("SELECT {CALENDAR_EVENT_COLUMNS} FROM calendar_events WHERE id = ?1"),
+                params![key],
             )
             .await
-            .map_err(|e| DatabaseError::Query(format!("get_conversation_cost: {e}")))?;
-
-        parse_cost_summary_row(&mut rows).await
+            .map_err(|e| DatabaseError::Query(format!("get_calendar_event: {e}")))?;
+        match rows.next().await {
+            Ok(Some(row)) => Ok(Some(row_to_calendar_event(&row)?)),
+            Ok(None) => Ok(None),
+            Err(e) => Err(DatabaseError::Query(format!("get_calendar_event: {e}"))),
+        }
     }
 
-    async fn get_costs_by_period(
+    async fn list_calendar_events(
         &self,
         start: DateTime<Utc>,
         end: DateTime<Utc>,
-    ) -> Result<crate::store::traits::LlmCostSummary, DatabaseError> {
+    ) -> Result<Vec<StoredEvent>, DatabaseError> {
         let conn = self.conn();
         let mut rows = conn
             .query(
-                "SELECT TOTAL(CAST(cost AS REAL)), TOTAL(input_tokens), TOTAL(output_tokens), COUNT(*) FROM llm_calls WHERE created_at >= ?1 AND created_at < ?2",
+                &format!(
+                    "SELECT {CALENDAR_EVENT_COLUMNS} FROM calendar_events
+                     WHERE start_at < ?2 AND end_at > ?1
+                     ORDER BY start_at ASC"
+                ),
                 params![start.to_rfc3339(), end.to_rfc3339()],
             )
             .await
-            .map_err(|e| DatabaseError::Query(format!("get_costs_by_period: {e}")))?;
+            .map_err(|e| DatabaseError::Query(format!("list_calendar_events: {e}")))?;
 
-        parse_cost_summary_row(&mut rows).await
+        let mut events = Vec::new();
+        while let Ok(Some(row)) = rows.next().await {
+            events.push(row_to_calendar_event(&row)?);
+        }
+        Ok(events)
     }
 
-    async fn get_total_spend(&self) -> Result<crate::store::traits::LlmCostSummary, DatabaseError> {
+    async fn prune_calendar_events(
+        &self,
+        source: EventSource,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        keep: &[String],
+    ) -> Result<usize, DatabaseError> {
+        let keep = serde_json::to_string(keep)
+            .map_err(|e| DatabaseError::Serialization(format!("calendar keep list: {e}")))?;
         let conn = self.conn();
-        let mut rows = conn
-            .query(
-                "SELECT TOTAL(CAST(cost AS REAL)), TOTAL(input_tokens), TOTAL(output_tokens), COUNT(*) FROM llm_calls",
-                (),
+        let affected = conn
+            .execute(
+                "DELETE FROM calendar_events
+                 WHERE source = ?1 AND start_at >= ?2 AND start_at < ?3
+                   AND id NOT IN (SELECT value FROM json_each(?4))",
+                params![source.as_str(), start.to_rfc3339(), end.to_rfc3339(), keep],
             )
             .await
-            .map_err(|e| DatabaseError::Query(format!("get_total_spend: {e}")))?;
-
-        parse_cost_summary_row(&mut rows).await
+            .map_err(|e| DatabaseError::Query(format!("prune_calendar_events: {e}")))?;
+        Ok(affected as usize)
     }
 
     // ── Conversation Listing ────────────────────────────────────────
@@ -1450,6 +2902,30 @@ impl Database for LibSqlBackend {
         Ok(routines)
     }
 
+    async fn list_webhook_routines(
+        &self,
+    ) -> Result<Vec<crate::agent::routine::Routine>, DatabaseError> {
+        let conn = self.conn();
+        let mut rows = conn
+            .query(
+                &format!(
+                    "SELECT {ROUTINE_COLUMNS} FROM routines WHERE enabled = 1 AND trigger_type = 'webhook'"
+                ),
+                (),
+            )
+            .await
+            .map_err(|e| DatabaseError::Query(format!("list_webhook_routines: {e}")))?;
+
+        let mut routines = Vec::new();
+        while let Ok(Some(row)) = rows.next().await {
+            match row_to_routine(&row) {
+                Ok(r) => routines.push(r),
+                Err(e) => tracing::warn!("Skipping webhook routine row: {e}"),
+            }
+        }
+        Ok(routines)
+    }
+
     async fn list_due_cron_routines(
         &self,
     ) -> Result<Vec<crate::agent::routine::Routine>, DatabaseError> {
@@ -1641,6 +3117,26 @@ impl Database for LibSqlBackend {
         Ok(())
     }
 
+    async fn get_routine_run(
+        &self,
+        id: Uuid,
+    ) -> Result<Option<crate::agent::routine::RoutineRun>, DatabaseError> {
+        let conn = self.conn();
+        let mut rows = conn
+            .query(
+                &format!("SELECT {ROUTINE_RUN_COLUMNS} FROM routine_runs WHERE id = ?1"),
+                params![id.to_string()],
+            )
+            .await
+            .map_err(|e| DatabaseError::Query(format!("get_routine_run: {e}")))?;
+
+        match rows.next().await {
+            Ok(Some(row)) => Ok(Some(row_to_routine_run(&row)?)),
+            Ok(None) => Ok(None),
+            Err(e) => Err(DatabaseError::Query(format!("get_routine_run: {e}"))),
+        }
+    }
+
     async fn list_routine_runs(
         &self,
         routine_id: Uuid,
@@ -2511,6 +4007,166 @@ mod tests {
         assert_eq!(pending[0].id, fresh_card.id);
     }
 
+    #[tokio::test]
+    async fn card_warnings_roundtrip() {
+        let db = test_db().await;
+
+        let mut card = make_card("email");
+        card.warnings = vec!["Secrets were redacted".into()];
+        db.insert_card(&card).await.unwrap();
+        let loaded = db.get_card(card.id).await.unwrap().unwrap();
+        assert_eq!(loaded.warnings, card.warnings);
+
+        db.update_card_warnings(card.id, &[]).await.unwrap();
+        let loaded = db.get_card(card.id).await.unwrap().unwrap();
+        assert!(loaded.warnings.is_empty());
+    }
+
+    #[tokio::test]
+    async fn digest_card_roundtrip() {
+        use crate::cards::model::{DigestEntry, DigestGroup};
+
+        let db = test_db().await;
+        let card = ApprovalCard::new(
+            CardPayload::Digest {
+                summary: "Two newsletters".into(),
+                groups: vec![DigestGroup {
+                    channel: "email".into(),
+                    sender: "news@x.com".into(),
+                    items: vec![DigestEntry {
+                        item_id: "item-1".into(),
+                        subject: Some("Weekly".into()),
+                        summary: "Weekly roundup".into(),
+                        received_at: Utc::now(),
+                    }],
+                }],
+            },
+            CardSilo::Messages,
+            60,
+        );
+        db.insert_card(&card).await.unwrap();
+
+        let loaded = db.get_card(card.id).await.unwrap().unwrap();
+        assert_eq!(loaded.payload.card_type_str(), "digest");
+        match loaded.payload {
+            CardPayload::Digest { summary, groups } => {
+                assert_eq!(summary, "Two newsletters");
+                assert_eq!(groups[0].items[0].item_id, "item-1");
+            }
+            other => panic!("expected Digest, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn digest_items_lifecycle() {
+        let db = test_db().await;
+        let now = Utc::now();
+        let item = DigestItem {
+            id: "item-1".into(),
+            message_id: "msg-1".into(),
+            channel: "email".into(),
+            sender: "news@x.com".into(),
+            subject: Some("Weekly".into()),
+            summary: "Weekly roundup".into(),
+            content: "Full body".into(),
+            reply_metadata: Some(serde_json::json!({"reply_to": "news@x.com"})),
+            received_at: now,
+            digest_card_id: None,
+            promoted_card_id: None,
+            created_at: now,
+        };
+        db.insert_digest_item(&item).await.unwrap();
+        // Same message again is ignored.
+        let dup = DigestItem { id: "item-2".into(), ..item.clone() };
+        db.insert_digest_item(&dup).await.unwrap();
+
+        let pending = db.list_pending_digest_items().await.unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].reply_metadata, item.reply_metadata);
+
+        let card_id = Uuid::new_v4();
+        db.assign_digest_items(&["item-1".into()], card_id).await.unwrap();
+        assert!(db.list_pending_digest_items().await.unwrap().is_empty());
+
+        let reply_id = Uuid::new_v4();
+        db.mark_digest_item_promoted("item-1", reply_id).await.unwrap();
+        let loaded = db.get_digest_item("item-1").await.unwrap().unwrap();
+        assert_eq!(loaded.digest_card_id, Some(card_id));
+        assert_eq!(loaded.promoted_card_id, Some(reply_id));
+        assert!(db.get_digest_item("missing").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn triage_rule_crud() {
+        use crate::pipeline::rules::{PatternKind, RuleAction, RuleField};
+
+        let db = test_db().await;
+        let mut rule = TriageRule::new(
+            "LinkedIn",
+            RuleField::Sender,
+            PatternKind::Glob,
+            "*@linkedin.com",
+            RuleAction::Ignore,
+            "",
+        );
+        db.create_triage_rule(&rule).await.unwrap();
+
+        let loaded = db.get_triage_rule(rule.id).await.unwrap().unwrap();
+        assert_eq!(loaded.pattern, "*@linkedin.com");
+        assert_eq!(loaded.kind, PatternKind::Glob);
+        assert_eq!(loaded.action, RuleAction::Ignore);
+        assert!(loaded.enabled);
+
+        rule.enabled = false;
+        rule.action = RuleAction::Notify;
+        db.update_triage_rule(&rule).await.unwrap();
+        let all = db.list_triage_rules().await.unwrap();
+        assert_eq!(all.len(), 1);
+        assert!(!all[0].enabled);
+        assert_eq!(all[0].action, RuleAction::Notify);
+
+        assert!(db.delete_triage_rule(rule.id).await.unwrap());
+        assert!(!db.delete_triage_rule(rule.id).await.unwrap());
+        assert!(db.get_triage_rule(rule.id).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn approval_rule_crud() {
+        use crate::agent::approval_policy::ApprovalDecision;
+        use crate::pipeline::rules::PatternKind;
+
+        let db = test_db().await;
+        let mut rule = ApprovalRule::new(
+            "git read-only",
+            "shell",
+            Some("command".into()),
+            PatternKind::Regex,
+            r"^git (status|diff|log)\b",
+            ApprovalDecision::AutoApprove,
+        );
+        db.create_approval_rule(&rule).await.unwrap();
+
+        let loaded = db.get_approval_rule(rule.id).await.unwrap().unwrap();
+        assert_eq!(loaded.argument.as_deref(), Some("command"));
+        assert_eq!(loaded.pattern, r"^git (status|diff|log)\b");
+        assert_eq!(loaded.decision, ApprovalDecision::AutoApprove);
+        assert!(!loaded.negate);
+
+        rule.argument = None;
+        rule.negate = true;
+        rule.decision = ApprovalDecision::AlwaysAsk;
+        db.update_approval_rule(&rule).await.unwrap();
+        let all = db.list_approval_rules().await.unwrap();
+        assert_eq!(all.len(), 1);
+        assert!(all[0].argument.is_none());
+        assert!(all[0].negate);
+        assert_eq!(all[0].decision, ApprovalDecision::AlwaysAsk);
+
+        assert!(db.delete_approval_rule(rule.id).await.unwrap());
+        assert!(!db.delete_approval_rule(rule.id).await.unwrap());
+        assert!(db.get_approval_rule(rule.id).await.unwrap().is_none());
+    }
+
     #[tokio::test]
     async fn prune_old_cards() {
         let db = test_db().await;
@@ -2775,6 +4431,7 @@ mod tests {
                 "Hello world",
                 Utc::now(),
                 None,
+                Some("work"),
             )
             .await
             .unwrap();
@@ -2792,6 +4449,7 @@ mod tests {
         assert_eq!(loaded.content, "Hello world");
         assert_eq!(loaded.status, MessageStatus::Pending);
         assert!(loaded.replied_at.is_none());
+        assert_eq!(loaded.account_id.as_deref(), Some("work"));
     }
 
     #[tokio::test]
@@ -2812,6 +4470,7 @@ mod tests {
             "first",
             Utc::now(),
             None,
+            None,
         )
         .await
         .unwrap();
@@ -2825,6 +4484,7 @@ mod tests {
                 "second",
                 Utc::now(),
                 None,
+                None,
             )
             .await;
         assert!(result.is_err());
@@ -2833,11 +4493,11 @@ mod tests {
     #[tokio::test]
     async fn get_pending_messages() {
         let db = test_db().await;
-        db.insert_message("m1", "email", "a@x.com", None, "msg1", Utc::now(), None)
+        db.insert_message("m1", "email", "a@x.com", None, "msg1", Utc::now(), None, None)
             .await
             .unwrap();
         let id2 = db
-            .insert_message("m2", "email", "b@x.com", None, "msg2", Utc::now(), None)
+            .insert_message("m2", "email", "b@x.com", None, "msg2", Utc::now(), None, None)
             .await
             .unwrap();
 
@@ -2854,7 +4514,7 @@ mod tests {
     async fn update_message_status_to_replied() {
         let db = test_db().await;
         let id = db
-            .insert_message("m1", "email", "a@x.com", None, "msg", Utc::now(), None)
+            .insert_message("m1", "email", "a@x.com", None, "msg", Utc::now(), None, None)
             .await
             .unwrap();
 
@@ -2871,7 +4531,7 @@ mod tests {
     async fn update_message_status_to_dismissed() {
         let db = test_db().await;
         let id = db
-            .insert_message("m1", "email", "a@x.com", None, "msg", Utc::now(), None)
+            .insert_message("m1", "email", "a@x.com", None, "msg", Utc::now(), None, None)
             .await
             .unwrap();
 
@@ -2887,13 +4547,13 @@ mod tests {
     #[tokio::test]
     async fn get_messages_by_channel() {
         let db = test_db().await;
-        db.insert_message("m1", "email", "a@x.com", None, "msg1", Utc::now(), None)
+        db.insert_message("m1", "email", "a@x.com", None, "msg1", Utc::now(), None, None)
             .await
             .unwrap();
-        db.insert_message("m2", "email", "b@x.com", None, "msg2", Utc::now(), None)
+        db.insert_message("m2", "email", "b@x.com", None, "msg2", Utc::now(), None, None)
             .await
             .unwrap();
-        db.insert_message("m3", "telegram", "c@x.com", None, "msg3", Utc::now(), None)
+        db.insert_message("m3", "telegram", "c@x.com", None, "msg3", Utc::now(), None, None)
             .await
             .unwrap();
 
@@ -3152,6 +4812,35 @@ mod tests {
         assert_eq!(events[0].name, "event-one");
     }
 
+    #[tokio::test]
+    async fn routine_list_webhook_routines() {
+        use crate::agent::routine::*;
+        let db = test_db().await;
+
+        let mut hook = make_test_routine("hook-one");
+        hook.trigger = Trigger::Webhook {
+            path: Some("deploy".to_string()),
+            secret: Some("s3cret".to_string()),
+        };
+        db.create_routine(&hook).await.unwrap();
+
+        db.create_routine(&make_test_routine("cron-one"))
+            .await
+            .unwrap();
+
+        let mut disabled = make_test_routine("hook-disabled");
+        disabled.trigger = Trigger::Webhook {
+            path: Some("other".to_string()),
+            secret: None,
+        };
+        disabled.enabled = false;
+        db.create_routine(&disabled).await.unwrap();
+
+        let hooks = db.list_webhook_routines().await.unwrap();
+        assert_eq!(hooks.len(), 1);
+        assert_eq!(hooks[0].name, "hook-one");
+    }
+
     #[tokio::test]
     async fn routine_list_due_cron() {
         let db = test_db().await;
@@ -3350,6 +5039,12 @@ mod tests {
         assert_eq!(runs[0].result_summary.as_deref(), Some("All clear"));
         assert_eq!(runs[0].tokens_used, Some(150));
         assert!(runs[0].completed_at.is_some());
+
+        // Single-run lookup
+        let fetched = db.get_routine_run(run.id).await.unwrap().unwrap();
+        assert_eq!(fetched.routine_id, routine.id);
+        assert_eq!(fetched.status, RunStatus::Ok);
+        assert!(db.get_routine_run(Uuid::new_v4()).await.unwrap().is_none());
     }
 
     #[tokio::test]
@@ -3460,12 +5155,15 @@ mod tests {
         crate::store::traits::LlmCallRecord {
             conversation_id: conv_id,
             routine_run_id: run_id,
+            todo_id: None,
             provider: "anthropic",
             model: "claude-3-5-sonnet",
             input_tokens: 1000,
             output_tokens: 500,
             cost: rust_decimal_macros::dec!(0.0045),
             purpose: Some("chat"),
+            role: Some("agent"),
+            price_source: Some("table"),
         }
     }
 
@@ -3556,6 +5254,31 @@ mod tests {
         assert_eq!(old_cost.call_count, 0);
     }
 
+    #[tokio::test]
+    async fn get_costs_by_role_groups_calls() {
+        let db = test_db().await;
+
+        db.record_llm_call(&make_test_llm_record(None, None)).await.unwrap();
+        db.record_llm_call(&make_test_llm_record(None, None)).await.unwrap();
+        let mut triage = make_test_llm_record(None, None);
+        triage.role = Some("triage");
+        triage.input_tokens = 10;
+        db.record_llm_call(&triage).await.unwrap();
+        let mut untagged = make_test_llm_record(None, None);
+        untagged.role = None;
+        db.record_llm_call(&untagged).await.unwrap();
+
+        let now = Utc::now();
+        let by_role = db
+            .get_costs_by_role(now - chrono::Duration::hours(1), now + chrono::Duration::hours(1))
+            .await
+            .unwrap();
+        let roles: Vec<&str> = by_role.iter().map(|(r, _)| r.as_str()).collect();
+        assert_eq!(roles, vec!["agent", "triage", "unknown"]);
+        assert_eq!(by_role[0].1.call_count, 2);
+        assert_eq!(by_role[1].1.total_input_tokens, 10);
+    }
+
     #[tokio::test]
     async fn record_llm_call_with_routine_run() {
         use crate::agent::routine::*;
@@ -3586,6 +5309,290 @@ mod tests {
         assert_eq!(total.call_count, 1);
     }
 
+    #[tokio::test]
+    async fn fallback_priced_models_are_listed() {
+        let db = test_db().await;
+        db.record_llm_call(&make_test_llm_record(None, None)).await.unwrap();
+        let mut unknown = make_test_llm_record(None, None);
+        unknown.model = "mystery-model";
+        unknown.price_source = Some("fallback");
+        db.record_llm_call(&unknown).await.unwrap();
+        db.record_llm_call(&unknown).await.unwrap();
+
+        let now = Utc::now();
+        let models = db
+            .get_fallback_priced_models(now - chrono::Duration::hours(1), now + chrono::Duration::hours(1))
+            .await
+            .unwrap();
+        assert_eq!(models, vec![("mystery-model".to_string(), 2)]);
+    }
+
+    // ── Agent session tests ─────────────────────────────────────────
+
+    #[tokio::test]
+    async fn agent_session_and_thread_roundtrip() {
+        use crate::agent::session::{PendingApproval, ThreadState};
+        let db = test_db().await;
+
+        let mut session = Session::new("user-1");
+        session.auto_approve_tool("echo");
+        let thread_id = session.create_thread().id;
+        let thread = session.threads.get_mut(&thread_id).unwrap();
+        thread.start_turn("delete the file");
+        thread.await_approval(PendingApproval {
+            request_id: Uuid::new_v4(),
+            tool_name: "shell".into(),
+            parameters: serde_json::json!({"command": "rm notes.txt"}),
+            description: "Run rm notes.txt".into(),
+            tool_call_id: "call_1".into(),
+            context_messages: vec![crate::llm::ChatMessage::user("delete the file")],
+            summary: None,
+        });
+        let mut undo = UndoManager::new();
+        undo.checkpoint(0, vec![], "Before turn 1");
+
+        let binding = ThreadBinding {
+            user_id: "user-1".into(),
+            channel: "telegram".into(),
+            external_thread_id: None,
+        };
+        db.upsert_agent_session(&session).await.unwrap();
+        db.upsert_agent_thread(&binding, &session.threads[&thread_id], Some(&undo))
+            .await
+            .unwrap();
+
+        let loaded = db.get_agent_session("user-1").await.unwrap().unwrap();
+        assert_eq!(loaded.id, session.id);
+        assert_eq!(loaded.active_thread, Some(thread_id));
+        assert!(loaded.is_tool_auto_approved("echo"));
+        assert!(loaded.threads.is_empty());
+
+        let stored = db.find_agent_thread(&binding).await.unwrap().unwrap();
+        assert_eq!(stored.binding, binding);
+        assert_eq!(stored.thread.id, thread_id);
+        assert_eq!(stored.thread.state, ThreadState::AwaitingApproval);
+        let pending = stored.thread.pending_approval.unwrap();
+        assert_eq!(pending.tool_name, "shell");
+        assert_eq!(pending.context_messages.len(), 1);
+        assert_eq!(stored.undo.unwrap().undo_count(), 1);
+
+        // The binding includes the external thread ID
+        let other = ThreadBinding {
+            external_thread_id: Some("ext-1".into()),
+            ..binding.clone()
+        };
+        assert!(db.find_agent_thread(&other).await.unwrap().is_none());
+        assert!(db.get_agent_session("user-2").await.unwrap().is_none());
+    }
+
+    // ── Calendar tests ──────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn calendar_events_overlap_query_and_prune() {
+        use crate::calendar::CalendarEvent;
+        use chrono::TimeZone;
+        let db = test_db().await;
+        let at = |h: u32| Utc.with_ymd_and_hms(2026, 3, 2, h, 0, 0).unwrap();
+
+        let mut standup = StoredEvent::new(CalendarEvent::new("s1", "Standup", at(9), at(10)), EventSource::Caldav);
+        standup.href = Some("/cal/s1.ics".into());
+        standup.etag = Some("\"1\"".into());
+        let lunch = StoredEvent::new(CalendarEvent::new("l1", "Lunch", at(12), at(13)), EventSource::Caldav);
+        let invite = StoredEvent::new(CalendarEvent::new("i1", "Invite", at(9), at(11)), EventSource::Invite);
+        for e in [&standup, &lunch, &invite] {
+            db.upsert_calendar_event(e).await.unwrap();
+        }
+
+        let found = db.get_calendar_event("s1").await.unwrap().unwrap();
+        assert_eq!(found.event, standup.event);
+        assert_eq!(found.etag.as_deref(), Some("\"1\""));
+
+        let morning = db.list_calendar_events(at(9), at(12)).await.unwrap();
+        let keys: Vec<_> = morning.iter().map(|e| e.event.uid.as_str()).collect();
+        assert_eq!(keys, ["s1", "i1"]);
+
+        // Only CalDAV events in range that weren't seen are removed
+        let removed = db
+            .prune_calendar_events(EventSource::Caldav, at(0), at(23), &["s1".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(removed, 1);
+        assert!(db.get_calendar_event("l1").await.unwrap().is_none());
+        assert!(db.get_calendar_event("i1").await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn meeting_card_payload_roundtrip() {
+        use crate::calendar::{CalendarEvent, MeetingResponse};
+        let db = test_db().await;
+        let start = Utc::now() + chrono::Duration::days(1);
+        let event = CalendarEvent::new("m1", "Planning", start, start + chrono::Duration::hours(1));
+        let card = ApprovalCard::new_meeting("email", "me@x.com", event, vec!["Standup".into()])
+            .with_reply_metadata(serde_json::json!({"in_reply_to": "<a@x>"}));
+        db.insert_card(&card).await.unwrap();
+
+        let mut payload = card.payload.clone();
+        if let CardPayload::Meeting { ref mut response, .. } = payload {
+            *response = MeetingResponse::Decline;
+        }
+        db.update_card_payload(card.id, &payload).await.unwrap();
+
+        let loaded = db.get_card(card.id).await.unwrap().unwrap();
+        assert_eq!(loaded.silo, CardSilo::Calendar);
+        let CardPayload::Meeting { event, conflicts, response, reply_metadata, .. } = loaded.payload else {
+            panic!("Expected Meeting payload");
+        };
+        assert_eq!(event.uid, "m1");
+        assert_eq!(conflicts, ["Standup"]);
+        assert_eq!(response, MeetingResponse::Decline);
+        assert_eq!(reply_metadata.unwrap()["in_reply_to"], "<a@x>");
+    }
+
+    // ── Outbox tests ────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn outbox_job_lifecycle() {
+        use crate::cards::outbox::OutboxStatus;
+        let db = test_db().await;
+
+        let card = ApprovalCard::new_reply("email", "alice@x.com", "hi", "hello", 0.9, "c", 15);
+        let mut job = OutboxJob::new(card.clone());
+        db.insert_outbox_job(&job).await.unwrap();
+
+        let due = db.due_outbox_jobs(Utc::now(), 10).await.unwrap();
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].card_id, card.id);
+        assert_eq!(due[0].card.payload.suggested_reply(), Some("hello"));
+
+        // Not due until next_attempt_at
+        job.attempts = 1;
+        job.next_attempt_at = Utc::now() + chrono::Duration::minutes(5);
+        job.last_error = Some("timeout".into());
+        db.update_outbox_job(&job).await.unwrap();
+        assert!(db.due_outbox_jobs(Utc::now(), 10).await.unwrap().is_empty());
+
+        let decision = Uuid::new_v4();
+        job.status = OutboxStatus::Failed;
+        job.decision_card_id = Some(decision);
+        db.update_outbox_job(&job).await.unwrap();
+        let found = db.get_outbox_job_by_decision_card(decision).await.unwrap().unwrap();
+        assert_eq!(found.id, job.id);
+        assert_eq!(found.status, OutboxStatus::Failed);
+        assert_eq!(found.attempts, 1);
+        assert_eq!(found.last_error.as_deref(), Some("timeout"));
+
+        // Interrupted sends are requeued
+        job.status = OutboxStatus::Sending;
+        job.next_attempt_at = Utc::now();
+        db.update_outbox_job(&job).await.unwrap();
+        assert_eq!(db.requeue_sending_outbox_jobs().await.unwrap(), 1);
+        assert_eq!(db.due_outbox_jobs(Utc::now(), 10).await.unwrap().len(), 1);
+
+        // Only one claim wins
+        job.attempts = 2;
+        assert!(db.claim_outbox_job(&job).await.unwrap());
+        assert!(!db.claim_outbox_job(&job).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn scheduled_outbox_job_can_be_cancelled_until_claimed() {
+        use crate::cards::outbox::OutboxStatus;
+        let db = test_db().await;
+
+        let card = ApprovalCard::new_reply("email", "alice@x.com", "hi", "hello", 0.9, "c", 15);
+        let mut job = OutboxJob::new(card.clone());
+        job.status = OutboxStatus::Scheduled;
+        job.next_attempt_at = Utc::now() + chrono::Duration::hours(1);
+        db.insert_outbox_job(&job).await.unwrap();
+
+        assert!(db.due_outbox_jobs(Utc::now(), 10).await.unwrap().is_empty());
+        let later = Utc::now() + chrono::Duration::hours(2);
+        assert_eq!(db.due_outbox_jobs(later, 10).await.unwrap().len(), 1);
+
+        assert!(db.cancel_scheduled_outbox_job(card.id).await.unwrap());
+        assert!(!db.cancel_scheduled_outbox_job(card.id).await.unwrap());
+        assert!(db.due_outbox_jobs(later, 10).await.unwrap().is_empty());
+        assert!(!db.claim_outbox_job(&job).await.unwrap());
+    }
+
+    // ── Budget tests ────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn budget_crud() {
+        use crate::llm::budget::BudgetPeriod;
+        let db = test_db().await;
+
+        let budget = Budget::new(
+            BudgetScope::Routine,
+            Some(Uuid::new_v4()),
+            BudgetPeriod::Monthly,
+            Some(rust_decimal_macros::dec!(5)),
+            Some(rust_decimal_macros::dec!(10.50)),
+        );
+        db.create_budget(&budget).await.unwrap();
+
+        let mut loaded = db.get_budget(budget.id).await.unwrap().unwrap();
+        assert_eq!(loaded.scope, BudgetScope::Routine);
+        assert_eq!(loaded.scope_id, budget.scope_id);
+        assert_eq!(loaded.hard_limit, Some(rust_decimal_macros::dec!(10.50)));
+        assert!(loaded.raise_card_id.is_none());
+
+        loaded.raise_card_id = Some(Uuid::new_v4());
+        loaded.raise_requested_at = Some(Utc::now());
+        loaded.enabled = false;
+        db.update_budget(&loaded).await.unwrap();
+        let updated = db.get_budget(budget.id).await.unwrap().unwrap();
+        assert_eq!(updated.raise_card_id, loaded.raise_card_id);
+        assert!(!updated.enabled);
+
+        assert_eq!(db.list_budgets().await.unwrap().len(), 1);
+        assert!(db.delete_budget(budget.id).await.unwrap());
+        assert!(!db.delete_budget(budget.id).await.unwrap());
+        assert!(db.list_budgets().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn scope_spend_filters_by_routine_and_todo() {
+        use crate::agent::routine::*;
+        let db = test_db().await;
+
+        let routine = make_test_routine("budgeted");
+        db.create_routine(&routine).await.unwrap();
+        let run = RoutineRun {
+            id: Uuid::new_v4(),
+            routine_id: routine.id,
+            trigger_type: "manual".to_string(),
+            trigger_detail: None,
+            started_at: Utc::now(),
+            completed_at: None,
+            status: RunStatus::Running,
+            result_summary: None,
+            tokens_used: None,
+            job_id: None,
+            created_at: Utc::now(),
+        };
+        db.create_routine_run(&run).await.unwrap();
+
+        let todo_id = Uuid::new_v4();
+        db.record_llm_call(&make_test_llm_record(None, Some(run.id))).await.unwrap();
+        let mut todo_call = make_test_llm_record(None, None);
+        todo_call.todo_id = Some(todo_id);
+        db.record_llm_call(&todo_call).await.unwrap();
+        db.record_llm_call(&make_test_llm_record(None, None)).await.unwrap();
+
+        let start = Utc::now() - chrono::Duration::hours(1);
+        let end = Utc::now() + chrono::Duration::hours(1);
+        let spend = |scope, id| db.get_scope_spend(scope, id, start, end);
+        let per_call = rust_decimal_macros::dec!(0.0045);
+        assert_eq!(spend(BudgetScope::Global, None).await.unwrap(), per_call * Decimal::from(3));
+        assert_eq!(spend(BudgetScope::Routine, Some(routine.id)).await.unwrap(), per_call);
+        assert_eq!(spend(BudgetScope::Todo, Some(todo_id)).await.unwrap(), per_call);
+        assert_eq!(
+            spend(BudgetScope::Todo, Some(Uuid::new_v4())).await.unwrap(),
+            Decimal::ZERO
+        );
+    }
+
     // ── Conversation Listing tests ──────────────────────────────────
 
     #[tokio::test]
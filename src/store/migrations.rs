@@ -1,14 +1,42 @@
-//! Database schema initialization.
+//! Database schema migrations.
 //!
-//! Single `init_schema()` function creates all tables idempotently using
-//! `CREATE TABLE IF NOT EXISTS`. No migration tracking, no version table.
+//! Migrations are numbered and applied in order by `init_schema()`. Each one
+//! runs inside a transaction and is recorded in the `schema_migrations`
+//! table, so it is applied exactly once per database. A database whose
+//! recorded version is newer than this binary knows about is refused.
+//!
+//! Migration 1 is the baseline schema as it existed before version
+//! tracking; it uses `IF NOT EXISTS` / column checks so it also adopts
+//! databases created by older builds. New schema changes go at the end of
+//! `MIGRATIONS` — never edit a migration that has shipped.
 
 use libsql::Connection;
 
 use crate::error::DatabaseError;
 
-/// Complete schema — all 9 tables with current columns and indexes.
-const SCHEMA: &str = r#"
+/// One step of a migration.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Step {
+    /// Arbitrary SQL (may contain several statements).
+    Sql(&'static str),
+    /// `ALTER TABLE ... ADD COLUMN`, skipped if the column already exists.
+    AddColumn {
+        table: &'static str,
+        column: &'static str,
+        definition: &'static str,
+    },
+}
+
+/// A numbered schema migration.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Migration {
+    pub version: i64,
+    pub name: &'static str,
+    pub steps: &'static [Step],
+}
+
+/// Baseline schema — all tables as they existed before versioned migrations.
+const BASELINE_SCHEMA: &str = r#"
     CREATE TABLE IF NOT EXISTS cards (
         id TEXT PRIMARY KEY,
         conversation_id TEXT NOT NULL,
@@ -183,7 +211,6 @@ const SCHEMA: &str = r#"
         created_at TEXT NOT NULL DEFAULT (datetime('now'))
     );
     CREATE INDEX IF NOT EXISTS idx_job_actions_job_id ON job_actions(job_id);
-    CREATE INDEX IF NOT EXISTS idx_job_actions_todo_id ON job_actions(todo_id);
     CREATE INDEX IF NOT EXISTS idx_job_actions_created ON job_actions(created_at);
 
     CREATE TABLE IF NOT EXISTS documents (
@@ -200,34 +227,377 @@ const SCHEMA: &str = r#"
     CREATE INDEX IF NOT EXISTS idx_documents_doc_type ON documents(doc_type);
 "#;
 
-/// Create all tables and indexes idempotently.
+/// All migrations, in order. Append only.
+pub(crate) const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "baseline",
+        steps: &[
+            Step::Sql(BASELINE_SCHEMA),
+            // Older databases predate job_actions.todo_id.
+            Step::AddColumn {
+                table: "job_actions",
+                column: "todo_id",
+                definition: "TEXT",
+            },
+            Step::Sql(
+                "CREATE INDEX IF NOT EXISTS idx_job_actions_todo_id ON job_actions(todo_id);",
+            ),
+        ],
+    },
+    Migration {
+        version: 2,
+        name: "card_warnings",
+        steps: &[Step::AddColumn {
+            table: "cards",
+            column: "warnings",
+            definition: "TEXT",
+        }],
+    },
+    Migration {
+        version: 3,
+        name: "digest_items",
+        steps: &[Step::Sql(
+            r#"
+            CREATE TABLE IF NOT EXISTS digest_items (
+                id TEXT PRIMARY KEY,
+                message_id TEXT NOT NULL UNIQUE,
+                channel TEXT NOT NULL,
+                sender TEXT NOT NULL,
+                subject TEXT,
+                summary TEXT NOT NULL,
+                content TEXT NOT NULL,
+                reply_metadata TEXT,
+                received_at TEXT NOT NULL,
+                digest_card_id TEXT,
+                promoted_card_id TEXT,
+                created_at TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_digest_items_card ON digest_items(digest_card_id);
+            CREATE INDEX IF NOT EXISTS idx_digest_items_channel_sender ON digest_items(channel, sender);
+            "#,
+        )],
+    },
+    Migration {
+        version: 4,
+        name: "triage_rules",
+        steps: &[Step::Sql(
+            r#"
+            CREATE TABLE IF NOT EXISTS triage_rules (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                field TEXT NOT NULL,
+                kind TEXT NOT NULL,
+                pattern TEXT NOT NULL,
+                action TEXT NOT NULL,
+                note TEXT NOT NULL DEFAULT '',
+                enabled INTEGER NOT NULL DEFAULT 1,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            );
+            "#,
+        )],
+    },
+    Migration {
+        version: 5,
+        name: "rule_suggestions",
+        steps: &[Step::Sql(
+            r#"
+            CREATE TABLE IF NOT EXISTS rule_suggestions (
+                id TEXT PRIMARY KEY,
+                scope TEXT NOT NULL,
+                value TEXT NOT NULL,
+                card_id TEXT NOT NULL,
+                status TEXT NOT NULL DEFAULT 'pending',
+                dismissed INTEGER NOT NULL DEFAULT 0,
+                created_at TEXT NOT NULL,
+                resolved_at TEXT,
+                UNIQUE (scope, value)
+            );
+            CREATE INDEX IF NOT EXISTS idx_rule_suggestions_card ON rule_suggestions(card_id);
+            "#,
+        )],
+    },
+    Migration {
+        version: 6,
+        name: "message_account_id",
+        steps: &[Step::AddColumn {
+            table: "messages",
+            column: "account_id",
+            definition: "TEXT",
+        }],
+    },
+    Migration {
+        version: 7,
+        name: "approval_rules",
+        steps: &[Step::Sql(
+            r#"
+            CREATE TABLE IF NOT EXISTS approval_rules (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                tool TEXT NOT NULL,
+                argument TEXT,
+                kind TEXT NOT NULL,
+                pattern TEXT NOT NULL DEFAULT '',
+                negate INTEGER NOT NULL DEFAULT 0,
+                decision TEXT NOT NULL,
+                note TEXT NOT NULL DEFAULT '',
+                enabled INTEGER NOT NULL DEFAULT 1,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            );
+            "#,
+        )],
+    },
+    Migration {
+        version: 8,
+        name: "llm_call_role",
+        steps: &[
+            Step::AddColumn {
+                table: "llm_calls",
+                column: "role",
+                definition: "TEXT",
+            },
+            Step::Sql("CREATE INDEX IF NOT EXISTS idx_llm_calls_role ON llm_calls(role);"),
+        ],
+    },
+    Migration {
+        version: 9,
+        name: "budgets",
+        steps: &[
+            Step::Sql(
+                r#"
+                CREATE TABLE IF NOT EXISTS budgets (
+                    id TEXT PRIMARY KEY,
+                    scope TEXT NOT NULL,
+                    scope_id TEXT,
+                    period TEXT NOT NULL,
+                    soft_limit TEXT,
+                    hard_limit TEXT,
+                    enabled INTEGER NOT NULL DEFAULT 1,
+                    raise_card_id TEXT,
+                    raise_requested_at TEXT,
+                    created_at TEXT NOT NULL,
+                    updated_at TEXT NOT NULL
+                );
+                "#,
+            ),
+            Step::AddColumn {
+                table: "llm_calls",
+                column: "todo_id",
+                definition: "TEXT",
+            },
+            Step::Sql("CREATE INDEX IF NOT EXISTS idx_llm_calls_todo ON llm_calls(todo_id);"),
+        ],
+    },
+    Migration {
+        version: 10,
+        name: "llm_call_price_source",
+        steps: &[Step::AddColumn {
+            table: "llm_calls",
+            column: "price_source",
+            definition: "TEXT",
+        }],
+    },
+    Migration {
+        version: 11,
+        name: "agent_sessions",
+        steps: &[Step::Sql(
+            r#"
+            CREATE TABLE IF NOT EXISTS agent_sessions (
+                user_id TEXT PRIMARY KEY,
+                id TEXT NOT NULL,
+                active_thread TEXT,
+                metadata TEXT,
+                auto_approved_tools TEXT NOT NULL DEFAULT '[]',
+                created_at TEXT NOT NULL,
+                last_active_at TEXT NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS agent_threads (
+                id TEXT PRIMARY KEY,
+                session_id TEXT NOT NULL,
+                user_id TEXT NOT NULL,
+                channel TEXT NOT NULL,
+                external_thread_id TEXT,
+                data TEXT NOT NULL,
+                undo TEXT,
+                updated_at TEXT NOT NULL
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_agent_threads_binding
+                ON agent_threads(user_id, channel, external_thread_id);
+            "#,
+        )],
+    },
+    Migration {
+        version: 12,
+        name: "outbox",
+        steps: &[Step::Sql(
+            r#"
+            CREATE TABLE IF NOT EXISTS outbox (
+                id TEXT PRIMARY KEY,
+                card_id TEXT NOT NULL,
+                card TEXT NOT NULL,
+                status TEXT NOT NULL DEFAULT 'queued',
+                attempts INTEGER NOT NULL DEFAULT 0,
+                next_attempt_at TEXT NOT NULL,
+                last_error TEXT,
+                decision_card_id TEXT,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_outbox_due ON outbox(status, next_attempt_at);
+            CREATE INDEX IF NOT EXISTS idx_outbox_decision_card ON outbox(decision_card_id);
+            "#,
+        )],
+    },
+    Migration {
+        version: 13,
+        name: "calendar_events",
+        steps: &[Step::Sql(
+            r#"
+            CREATE TABLE IF NOT EXISTS calendar_events (
+                id TEXT PRIMARY KEY,
+                uid TEXT NOT NULL,
+                source TEXT NOT NULL,
+                href TEXT,
+                etag TEXT,
+                summary TEXT NOT NULL,
+                start_at TEXT NOT NULL,
+                end_at TEXT NOT NULL,
+                all_day INTEGER NOT NULL DEFAULT 0,
+                data TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_calendar_events_start ON calendar_events(start_at);
+            CREATE INDEX IF NOT EXISTS idx_calendar_events_uid ON calendar_events(uid);
+            "#,
+        )],
+    },
+];
+
+/// Latest schema version this binary knows about.
+pub fn latest_version() -> i64 {
+    MIGRATIONS.last().map(|m| m.version).unwrap_or(0)
+}
+
+/// Bring the database schema up to date.
 ///
-/// Uses `CREATE TABLE IF NOT EXISTS` — safe to call on every startup.
-/// No migration tracking needed.
+/// Safe to call on every startup: already-applied migrations are skipped.
+/// Fails if the database was written by a newer binary.
 pub async fn init_schema(conn: &Connection) -> Result<(), DatabaseError> {
-    conn.execute_batch(SCHEMA)
+    apply_migrations(conn, MIGRATIONS).await
+}
+
+/// Apply every migration in `migrations` that is newer than the recorded version.
+pub(crate) async fn apply_migrations(
+    conn: &Connection,
+    migrations: &[Migration],
+) -> Result<(), DatabaseError> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS schema_migrations (
+            version INTEGER PRIMARY KEY,
+            name TEXT NOT NULL,
+            applied_at TEXT NOT NULL DEFAULT (datetime('now'))
+        )",
+        (),
+    )
+    .await
+    .map_err(|e| DatabaseError::Migration(format!("create schema_migrations: {e}")))?;
+
+    let current = current_version(conn).await?;
+    let latest = migrations.last().map(|m| m.version).unwrap_or(0);
+    if current > latest {
+        return Err(DatabaseError::Migration(format!(
+            "Database schema version {current} is newer than this binary supports ({latest}); \
+             upgrade ai-assist before opening this database"
+        )));
+    }
+
+    for migration in migrations.iter().filter(|m| m.version > current) {
+        conn.execute("BEGIN", ())
+            .await
+            .map_err(|e| DatabaseError::Migration(format!("begin: {e}")))?;
+
+        if let Err(e) = apply_one(conn, migration).await {
+            let _ = conn.execute("ROLLBACK", ()).await;
+            return Err(DatabaseError::Migration(format!(
+                "Migration {} ({}) failed: {e}",
+                migration.version, migration.name
+            )));
+        }
+
+        conn.execute("COMMIT", ())
+            .await
+            .map_err(|e| DatabaseError::Migration(format!("commit: {e}")))?;
+
+        tracing::info!(
+            version = migration.version,
+            name = migration.name,
+            "Applied schema migration"
+        );
+    }
+
+    tracing::info!(version = latest.max(current), "Database schema initialized");
+    Ok(())
+}
+
+/// Highest applied migration version (0 for a fresh database).
+pub(crate) async fn current_version(conn: &Connection) -> Result<i64, DatabaseError> {
+    let mut rows = conn
+        .query("SELECT COALESCE(MAX(version), 0) FROM schema_migrations", ())
         .await
-        .map_err(|e| DatabaseError::Migration(format!("Schema initialization failed: {e}")))?;
-
-    // Additive column migration: add todo_id to job_actions if missing.
-    // ALTER TABLE ADD COLUMN is idempotent-safe: SQLite errors on duplicate columns,
-    // which we silently ignore.
-    let _ = conn
-        .execute("ALTER TABLE job_actions ADD COLUMN todo_id TEXT", ())
-        .await;
-
-    // Ensure index exists (idempotent via IF NOT EXISTS in SCHEMA, but also here for safety)
-    let _ = conn
-        .execute(
-            "CREATE INDEX IF NOT EXISTS idx_job_actions_todo_id ON job_actions(todo_id)",
-            (),
-        )
-        .await;
+        .map_err(|e| DatabaseError::Migration(format!("current_version: {e}")))?;
+    match rows.next().await {
+        Ok(Some(row)) => row
+            .get::<i64>(0)
+            .map_err(|e| DatabaseError::Migration(format!("current_version: {e}"))),
+        Ok(None) => Ok(0),
+        Err(e) => Err(DatabaseError::Migration(format!("current_version: {e}"))),
+    }
+}
 
-    tracing::info!("Database schema initialized");
+async fn apply_one(conn: &Connection, migration: &Migration) -> Result<(), libsql::Error> {
+    for step in migration.steps {
+        match *step {
+            Step::Sql(sql) => conn.execute_batch(sql).await.map(|_| ())?,
+            Step::AddColumn {
+                table,
+                column,
+                definition,
+            } => {
+                if !column_exists(conn, table, column).await? {
+                    conn.execute(
+                        &format!("ALTER TABLE {table} ADD COLUMN {column} {definition}"),
+                        (),
+                    )
+                    .await?;
+                }
+            }
+        }
+    }
+    conn.execute(
+        "INSERT INTO schema_migrations (version, name) VALUES (?1, ?2)",
+        libsql::params![migration.version, migration.name],
+    )
+    .await?;
     Ok(())
 }
 
+async fn column_exists(conn: &Connection, table: &str, column: &str) -> Result<bool, libsql::Error> {
+    let mut rows = conn.query(&format!("PRAGMA table_info({table})"), ()).await?;
+    while let Some(row) = rows.next().await? {
+        let name: String = row.get(1)?;
+        if name == column {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -257,6 +627,7 @@ mod tests {
             "todos",
             "job_actions",
             "documents",
+            "schema_migrations",
         ];
 
         for table in &expected_tables {
@@ -316,6 +687,7 @@ mod tests {
             "suggested_reply", "confidence", "status", "channel",
             "created_at", "expires_at", "updated_at", "message_id",
             "reply_metadata", "email_thread", "card_type", "silo", "payload",
+            "todo_id", "warnings",
         ] {
             assert!(card_cols.contains(&col.to_string()), "cards.{col} missing");
         }
@@ -352,6 +724,139 @@ mod tests {
         }
     }
 
+    async fn count(conn: &Connection, sql: &str) -> i64 {
+        let mut rows = conn.query(sql, ()).await.unwrap();
+        rows.next().await.unwrap().unwrap().get(0).unwrap()
+    }
+
+    async fn table_exists(conn: &Connection, table: &str) -> bool {
+        count(
+            conn,
+            &format!("SELECT COUNT(*) FROM sqlite_master WHERE type='table' AND name='{table}'"),
+        )
+        .await
+            == 1
+    }
+
+    #[tokio::test]
+    async fn records_applied_versions() {
+        let conn = test_conn().await;
+        init_schema(&conn).await.unwrap();
+
+        assert_eq!(current_version(&conn).await.unwrap(), latest_version());
+        assert_eq!(
+            count(&conn, "SELECT COUNT(*) FROM schema_migrations").await,
+            MIGRATIONS.len() as i64
+        );
+
+        // Re-running applies nothing new.
+        init_schema(&conn).await.unwrap();
+        assert_eq!(
+            count(&conn, "SELECT COUNT(*) FROM schema_migrations").await,
+            MIGRATIONS.len() as i64
+        );
+    }
+
+    #[test]
+    fn migration_versions_are_strictly_increasing() {
+        for pair in MIGRATIONS.windows(2) {
+            assert!(pair[0].version < pair[1].version);
+        }
+        assert_eq!(MIGRATIONS[0].version, 1);
+    }
+
+    /// A database created by a build from before version tracking: baseline
+    /// tables, no `schema_migrations`, existing rows.
+    async fn baseline_fixture() -> Connection {
+        let conn = test_conn().await;
+        conn.execute_batch(BASELINE_SCHEMA).await.unwrap();
+        conn.execute_batch(
+            "CREATE INDEX IF NOT EXISTS idx_job_actions_todo_id ON job_actions(todo_id);
+             INSERT INTO cards (id, conversation_id, source_message, source_sender, suggested_reply,
+                                confidence, channel, created_at, updated_at, card_type, silo, payload)
+             VALUES ('card-1', 'c', 'hi', 'alice', 'hello', 0.9, 'email',
+                     '2026-01-01T00:00:00Z', '2026-01-01T00:00:00Z', 'reply', 'messages', '{}');
+             INSERT INTO todos (id, user_id, title, todo_type) VALUES ('todo-1', 'u', 'Pay rent', 'errand');",
+        )
+        .await
+        .unwrap();
+        conn
+    }
+
+    #[tokio::test]
+    async fn upgrades_baseline_fixture() {
+        let conn = baseline_fixture().await;
+        assert!(!table_exists(&conn, "schema_migrations").await);
+
+        init_schema(&conn).await.unwrap();
+
+        assert_eq!(current_version(&conn).await.unwrap(), latest_version());
+        assert!(get_column_names(&conn, "cards").await.contains(&"warnings".to_string()));
+        // Existing data survives the upgrade.
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM cards WHERE id = 'card-1'").await, 1);
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM todos WHERE id = 'todo-1'").await, 1);
+    }
+
+    #[tokio::test]
+    async fn upgrades_legacy_job_actions_without_todo_id() {
+        let conn = test_conn().await;
+        conn.execute_batch(
+            "CREATE TABLE job_actions (
+                id TEXT PRIMARY KEY,
+                job_id TEXT NOT NULL,
+                action_type TEXT NOT NULL,
+                action_data TEXT NOT NULL,
+                created_at TEXT NOT NULL DEFAULT (datetime('now'))
+            );",
+        )
+        .await
+        .unwrap();
+
+        init_schema(&conn).await.unwrap();
+        assert!(get_column_names(&conn, "job_actions").await.contains(&"todo_id".to_string()));
+    }
+
+    #[tokio::test]
+    async fn refuses_newer_database() {
+        let conn = test_conn().await;
+        init_schema(&conn).await.unwrap();
+        conn.execute(
+            "INSERT INTO schema_migrations (version, name) VALUES (?1, 'from_the_future')",
+            libsql::params![latest_version() + 1],
+        )
+        .await
+        .unwrap();
+
+        let err = init_schema(&conn).await.unwrap_err();
+        assert!(err.to_string().contains("newer"), "unexpected error: {err}");
+    }
+
+    #[tokio::test]
+    async fn failed_migration_rolls_back() {
+        const BROKEN: &[Migration] = &[
+            MIGRATIONS[0],
+            Migration {
+                version: 2,
+                name: "broken",
+                steps: &[
+                    Step::Sql("CREATE TABLE half_done (id TEXT PRIMARY KEY);"),
+                    Step::Sql("THIS IS NOT SQL;"),
+                ],
+            },
+        ];
+        let conn = test_conn().await;
+
+        let err = apply_migrations(&conn, BROKEN).await.unwrap_err();
+        assert!(err.to_string().contains("broken"));
+        // Migration 1 committed; migration 2 left no trace.
+        assert_eq!(current_version(&conn).await.unwrap(), 1);
+        assert!(!table_exists(&conn, "half_done").await);
+
+        // The real migration list still applies cleanly afterwards.
+        init_schema(&conn).await.unwrap();
+        assert_eq!(current_version(&conn).await.unwrap(), latest_version());
+    }
+
     async fn get_column_names(conn: &Connection, table: &str) -> Vec<String> {
         let mut rows = conn
             .query(&format!("PRAGMA table_info({table})"), ())
@@ -8,9 +8,18 @@ use chrono::{DateTime, Utc};
 use rust_decimal::Decimal;
 use uuid::Uuid;
 
-use crate::cards::model::{ApprovalCard, CardSilo, CardStatus, SiloCounts};
+use crate::agent::approval_policy::ApprovalRule;
+use crate::agent::session::{Session, Thread};
+use crate::agent::undo::UndoManager;
+use crate::calendar::model::{EventSource, StoredEvent};
+use crate::cards::model::{ApprovalCard, CardPayload, CardSilo, CardStatus, SiloCounts};
+use crate::cards::outbox::OutboxJob;
 use crate::documents::model::{Document, DocumentType};
 use crate::error::DatabaseError;
+use crate::llm::budget::{Budget, BudgetScope};
+use crate::pipeline::digest::DigestItem;
+use crate::pipeline::feedback::{RuleSuggestion, SenderOutcome, SuggestionScope, SuggestionStatus};
+use crate::pipeline::rules::TriageRule;
 use crate::todos::model::{TodoItem, TodoStatus};
 
 /// A conversation message from the database.
@@ -26,12 +35,34 @@ pub struct ConversationMessage {
 pub struct LlmCallRecord<'a> {
     pub conversation_id: Option<Uuid>,
     pub routine_run_id: Option<Uuid>,
+    /// Todo whose agent made the call, for per-todo budgets.
+    pub todo_id: Option<Uuid>,
     pub provider: &'a str,
     pub model: &'a str,
     pub input_tokens: u32,
     pub output_tokens: u32,
     pub cost: Decimal,
     pub purpose: Option<&'a str>,
+    /// Model-router role that made the call (`triage`, `draft`, `agent`, ...).
+    pub role: Option<&'a str>,
+    /// Where the price came from (`table`, `override`, `local`, `fallback`, ...).
+    pub price_source: Option<&'a str>,
+}
+
+/// The channel-side address an agent thread is resolved by.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ThreadBinding {
+    pub user_id: String,
+    pub channel: String,
+    pub external_thread_id: Option<String>,
+}
+
+/// An agent thread loaded from the database, with its undo history.
+#[derive(Debug)]
+pub struct StoredThread {
+    pub binding: ThreadBinding,
+    pub thread: Thread,
+    pub undo: Option<UndoManager>,
 }
 
 /// Summary of a conversation for listing views.
@@ -48,7 +79,7 @@ pub struct ConversationSummary {
 }
 
 /// Aggregated LLM cost summary.
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, serde::Serialize)]
 pub struct LlmCostSummary {
     pub total_cost: Decimal,
     pub total_input_tokens: u64,
@@ -80,6 +111,8 @@ pub struct StoredMessage {
     pub status: MessageStatus,
     pub replied_at: Option<DateTime<Utc>>,
     pub metadata: Option<String>,
+    /// Email account the message arrived on (`None` for non-email channels).
+    pub account_id: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -109,6 +142,19 @@ pub trait Database: Send + Sync {
         status: CardStatus,
     ) -> Result<(), DatabaseError>;
 
+    /// Replace a card's user-facing warnings.
+    async fn update_card_warnings(&self, id: Uuid, warnings: &[String]) -> Result<(), DatabaseError>;
+
+    /// Move a card's expiry (`None` = never expires).
+    async fn update_card_expiry(
+        &self,
+        id: Uuid,
+        expires_at: Option<DateTime<Utc>>,
+    ) -> Result<(), DatabaseError>;
+
+    /// Replace a card's whole payload (e.g. a Meeting card's chosen response).
+    async fn update_card_payload(&self, id: Uuid, payload: &CardPayload) -> Result<(), DatabaseError>;
+
     /// Get all pending (non-expired) cards.
     async fn get_pending_cards(&self) -> Result<Vec<ApprovalCard>, DatabaseError>;
 
@@ -151,6 +197,7 @@ pub trait Database: Send + Sync {
         content: &str,
         received_at: DateTime<Utc>,
         metadata: Option<&str>,
+        account_id: Option<&str>,
     ) -> Result<String, DatabaseError>;
 
     /// Look up a message by its external (channel-native) ID.
@@ -169,6 +216,9 @@ pub trait Database: Send + Sync {
         status: MessageStatus,
     ) -> Result<(), DatabaseError>;
 
+    /// Get the most recently received messages across all channels.
+    async fn list_recent_messages(&self, limit: usize) -> Result<Vec<StoredMessage>, DatabaseError>;
+
     /// Get messages by channel, most recent first.
     async fn get_messages_by_channel(
         &self,
@@ -176,6 +226,91 @@ pub trait Database: Send + Sync {
         limit: usize,
     ) -> Result<Vec<StoredMessage>, DatabaseError>;
 
+    // ── Digest Items ────────────────────────────────────────────────
+
+    /// Store a low-priority message for the next digest. Re-inserting the
+    /// same `message_id` is a no-op.
+    async fn insert_digest_item(&self, item: &DigestItem) -> Result<(), DatabaseError>;
+
+    /// Get all items not yet rolled into a digest card, oldest first.
+    async fn list_pending_digest_items(&self) -> Result<Vec<DigestItem>, DatabaseError>;
+
+    /// Attach items to the digest card they were rolled into. Items already
+    /// on another card are left alone; returns the IDs actually assigned.
+    async fn assign_digest_items(&self, ids: &[String], card_id: Uuid) -> Result<Vec<String>, DatabaseError>;
+
+    /// Get a digest item by ID.
+    async fn get_digest_item(&self, id: &str) -> Result<Option<DigestItem>, DatabaseError>;
+
+    /// Claim an item for the Reply card it is being promoted to. Returns
+    /// `false` if it was already promoted (or claimed) elsewhere.
+    async fn mark_digest_item_promoted(&self, id: &str, card_id: Uuid) -> Result<bool, DatabaseError>;
+
+    /// Undo a promotion claim that never produced its card.
+    async fn clear_digest_item_promotion(&self, id: &str, card_id: Uuid) -> Result<(), DatabaseError>;
+
+    // ── Triage Rules ────────────────────────────────────────────────
+
+    /// List all triage rules in creation order.
+    async fn list_triage_rules(&self) -> Result<Vec<TriageRule>, DatabaseError>;
+
+    /// Get a triage rule by ID.
+    async fn get_triage_rule(&self, id: Uuid) -> Result<Option<TriageRule>, DatabaseError>;
+
+    /// Create a new triage rule.
+    async fn create_triage_rule(&self, rule: &TriageRule) -> Result<(), DatabaseError>;
+
+    /// Update a triage rule (full replace of mutable fields).
+    async fn update_triage_rule(&self, rule: &TriageRule) -> Result<(), DatabaseError>;
+
+    /// Delete a triage rule. Returns true if a row was deleted.
+    async fn delete_triage_rule(&self, id: Uuid) -> Result<bool, DatabaseError>;
+
+    // ── Approval Rules ──────────────────────────────────────────────
+
+    /// List all approval rules in creation order.
+    async fn list_approval_rules(&self) -> Result<Vec<ApprovalRule>, DatabaseError>;
+
+    /// Get an approval rule by ID.
+    async fn get_approval_rule(&self, id: Uuid) -> Result<Option<ApprovalRule>, DatabaseError>;
+
+    /// Create a new approval rule.
+    async fn create_approval_rule(&self, rule: &ApprovalRule) -> Result<(), DatabaseError>;
+
+    /// Update an approval rule (full replace of mutable fields).
+    async fn update_approval_rule(&self, rule: &ApprovalRule) -> Result<(), DatabaseError>;
+
+    /// Delete an approval rule. Returns true if a row was deleted.
+    async fn delete_approval_rule(&self, id: Uuid) -> Result<bool, DatabaseError>;
+
+    // ── Triage Feedback ─────────────────────────────────────────────
+
+    /// Approved/dismissed counts of Reply cards, grouped by channel and sender.
+    async fn card_outcomes_by_sender(&self) -> Result<Vec<SenderOutcome>, DatabaseError>;
+
+    /// Record a proposed ignore rule.
+    async fn insert_rule_suggestion(&self, suggestion: &RuleSuggestion) -> Result<(), DatabaseError>;
+
+    /// Whether a suggestion (in any state) already exists for this scope/value.
+    async fn has_rule_suggestion(
+        &self,
+        scope: SuggestionScope,
+        value: &str,
+    ) -> Result<bool, DatabaseError>;
+
+    /// Find the suggestion behind a Decision card.
+    async fn get_rule_suggestion_by_card(
+        &self,
+        card_id: Uuid,
+    ) -> Result<Option<RuleSuggestion>, DatabaseError>;
+
+    /// Mark a suggestion accepted or rejected.
+    async fn update_rule_suggestion_status(
+        &self,
+        id: Uuid,
+        status: SuggestionStatus,
+    ) -> Result<(), DatabaseError>;
+
     // ── Conversations ───────────────────────────────────────────────
 
     /// Ensure a conversation exists, creating it if needed.
@@ -242,6 +377,129 @@ pub trait Database: Send + Sync {
     /// Get total spend across all time.
     async fn get_total_spend(&self) -> Result<LlmCostSummary, DatabaseError>;
 
+    /// Get aggregated cost per model-router role for a time period.
+    /// Calls recorded without a role are grouped under `"unknown"`.
+    async fn get_costs_by_role(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<(String, LlmCostSummary)>, DatabaseError>;
+
+    /// Models whose calls in a time period were priced at the fallback rate,
+    /// with call counts.
+    async fn get_fallback_priced_models(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<(String, u64)>, DatabaseError>;
+
+    // ── Budgets ─────────────────────────────────────────────────────
+
+    /// List all budgets in creation order.
+    async fn list_budgets(&self) -> Result<Vec<Budget>, DatabaseError>;
+
+    /// Get a budget by ID.
+    async fn get_budget(&self, id: Uuid) -> Result<Option<Budget>, DatabaseError>;
+
+    /// Create a new budget.
+    async fn create_budget(&self, budget: &Budget) -> Result<(), DatabaseError>;
+
+    /// Update a budget (full replace of mutable fields).
+    async fn update_budget(&self, budget: &Budget) -> Result<(), DatabaseError>;
+
+    /// Delete a budget. Returns true if a row was deleted.
+    async fn delete_budget(&self, id: Uuid) -> Result<bool, DatabaseError>;
+
+    /// Total LLM spend in `[start, end)` for a budget scope. Routine spend
+    /// is joined through `routine_runs`; todo spend uses `llm_calls.todo_id`.
+    async fn get_scope_spend(
+        &self,
+        scope: BudgetScope,
+        scope_id: Option<Uuid>,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Decimal, DatabaseError>;
+
+    // ── Agent Sessions ──────────────────────────────────────────────
+
+    /// Save a session's own fields (keyed by user). Its threads are saved
+    /// separately with `upsert_agent_thread`.
+    async fn upsert_agent_session(&self, session: &Session) -> Result<(), DatabaseError>;
+
+    /// Load a user's session, without its threads.
+    async fn get_agent_session(&self, user_id: &str) -> Result<Option<Session>, DatabaseError>;
+
+    /// Save a thread (turns, state, pending approval), its binding and undo history.
+    async fn upsert_agent_thread(
+        &self,
+        binding: &ThreadBinding,
+        thread: &Thread,
+        undo: Option<&UndoManager>,
+    ) -> Result<(), DatabaseError>;
+
+    /// The most recently updated thread with this binding.
+    async fn find_agent_thread(
+        &self,
+        binding: &ThreadBinding,
+    ) -> Result<Option<StoredThread>, DatabaseError>;
+
+    // ── Outbox ──────────────────────────────────────────────────────
+
+    /// Queue an outbound message for delivery.
+    async fn insert_outbox_job(&self, job: &OutboxJob) -> Result<(), DatabaseError>;
+
+    /// Save a job's status, attempts, schedule, error and card snapshot.
+    async fn update_outbox_job(&self, job: &OutboxJob) -> Result<(), DatabaseError>;
+
+    /// Queued or scheduled jobs whose next attempt is due, oldest first.
+    async fn due_outbox_jobs(
+        &self,
+        now: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<Vec<OutboxJob>, DatabaseError>;
+
+    /// Mark a due job `sending` with its new attempt count. Returns `false`
+    /// if it is no longer queued or scheduled (e.g. undone meanwhile).
+    async fn claim_outbox_job(&self, job: &OutboxJob) -> Result<bool, DatabaseError>;
+
+    /// Discard a card's job if it is still `scheduled`. Returns whether
+    /// one was cancelled.
+    async fn cancel_scheduled_outbox_job(&self, card_id: Uuid) -> Result<bool, DatabaseError>;
+
+    /// The job a failed-delivery Decision card was raised for.
+    async fn get_outbox_job_by_decision_card(
+        &self,
+        card_id: Uuid,
+    ) -> Result<Option<OutboxJob>, DatabaseError>;
+
+    /// Requeue jobs left `sending` (interrupted mid-send). Returns how many.
+    async fn requeue_sending_outbox_jobs(&self) -> Result<usize, DatabaseError>;
+
+    // ── Calendar ────────────────────────────────────────────────────
+
+    /// Insert or replace an event, keyed by `event.key()`.
+    async fn upsert_calendar_event(&self, event: &StoredEvent) -> Result<(), DatabaseError>;
+
+    /// Get an event by key (UID, plus instance for recurrences).
+    async fn get_calendar_event(&self, key: &str) -> Result<Option<StoredEvent>, DatabaseError>;
+
+    /// Events overlapping `[start, end)`, earliest first.
+    async fn list_calendar_events(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<StoredEvent>, DatabaseError>;
+
+    /// Delete events from `source` starting in `[start, end)` whose key is
+    /// not in `keep`. Returns how many were removed.
+    async fn prune_calendar_events(
+        &self,
+        source: EventSource,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        keep: &[String],
+    ) -> Result<usize, DatabaseError>;
+
     // ── Conversation Listing ────────────────────────────────────────
 
     /// List conversations with preview (title from first user message).
@@ -293,6 +551,11 @@ pub trait Database: Send + Sync {
         &self,
     ) -> Result<Vec<crate::agent::routine::Routine>, DatabaseError>;
 
+    /// List all enabled webhook-triggered routines.
+    async fn list_webhook_routines(
+        &self,
+    ) -> Result<Vec<crate::agent::routine::Routine>, DatabaseError>;
+
     /// List all enabled cron routines whose next_fire_at <= now.
     async fn list_due_cron_routines(
         &self,
@@ -335,6 +598,12 @@ pub trait Database: Send + Sync {
         tokens: Option<i32>,
     ) -> Result<(), DatabaseError>;
 
+    /// Get a routine run by ID.
+    async fn get_routine_run(
+        &self,
+        id: Uuid,
+    ) -> Result<Option<crate::agent::routine::RoutineRun>, DatabaseError>;
+
     /// List recent runs for a routine.
     async fn list_routine_runs(
         &self,
@@ -19,6 +19,7 @@ use uuid::Uuid;
 
 use crate::agent::agent_queue::AgentQueue;
 use crate::agent::todo_agent::TodoAgentDeps;
+use crate::safety::PolicyAction;
 use crate::store::Database;
 use crate::todos::model::{TodoStatus, TodoWsMessage};
 
@@ -84,6 +85,11 @@ pub enum TodoActivityMessage {
         job_id: Uuid,
         error: String,
     },
+    /// Job stopped by a hard spend budget; the todo is requeued later.
+    Paused {
+        job_id: Uuid,
+        reason: String,
+    },
     /// Full agent transcript dump (for debugging).
     /// Contains the raw conversation thread: system prompt, user message,
     /// assistant responses, tool calls, and tool results.
@@ -104,6 +110,15 @@ pub enum TodoActivityMessage {
         card_id: Uuid,
         approved: bool,
     },
+    /// The safety layer warned about or blocked content during the job.
+    SafetyEvent {
+        job_id: Uuid,
+        /// Tool whose output was flagged; absent for the todo input itself.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        tool_name: Option<String>,
+        action: PolicyAction,
+        reasons: Vec<String>,
+    },
     /// A follow-up message from the user sent via the activity WebSocket.
     UserMessage {
         todo_id: Uuid,
@@ -122,9 +137,11 @@ impl TodoActivityMessage {
             | Self::AgentResponse { job_id, .. }
             | Self::Completed { job_id, .. }
             | Self::Failed { job_id, .. }
+            | Self::Paused { job_id, .. }
             | Self::Transcript { job_id, .. }
             | Self::ApprovalNeeded { job_id, .. }
-            | Self::ApprovalResolved { job_id, .. } => *job_id,
+            | Self::ApprovalResolved { job_id, .. }
+            | Self::SafetyEvent { job_id, .. } => *job_id,
             Self::UserMessage { .. } => Uuid::nil(),
         }
     }
@@ -138,9 +155,12 @@ impl TodoActivityMessage {
         }
     }
 
-    /// Whether this is a terminal event (completed or failed).
+    /// Whether this is a terminal event (completed, failed or paused).
     pub fn is_terminal(&self) -> bool {
-        matches!(self, Self::Completed { .. } | Self::Failed { .. } | Self::Transcript { .. })
+        matches!(
+            self,
+            Self::Completed { .. } | Self::Failed { .. } | Self::Paused { .. } | Self::Transcript { .. }
+        )
     }
 
     /// Get the action type name (matches serde tag: "started", "thinking", etc.).
@@ -153,9 +173,11 @@ impl TodoActivityMessage {
             Self::AgentResponse { .. } => "agent_response".to_string(),
             Self::Completed { .. } => "completed".to_string(),
             Self::Failed { .. } => "failed".to_string(),
+            Self::Paused { .. } => "paused".to_string(),
             Self::Transcript { .. } => "transcript".to_string(),
             Self::ApprovalNeeded { .. } => "approval_needed".to_string(),
             Self::ApprovalResolved { .. } => "approval_resolved".to_string(),
+            Self::SafetyEvent { .. } => "safety_event".to_string(),
             Self::UserMessage { .. } => "user_message".to_string(),
         }
     }
@@ -433,6 +455,24 @@ mod tests {
         assert!(msg.is_terminal());
     }
 
+    #[test]
+    fn activity_message_serde_safety_event() {
+        let msg = TodoActivityMessage::SafetyEvent {
+            job_id: Uuid::new_v4(),
+            tool_name: Some("shell".to_string()),
+            action: PolicyAction::Block,
+            reasons: vec!["Policy 'secret_exfiltration'".to_string()],
+        };
+        let json = serde_json::to_string(&msg).unwrap();
+        assert!(json.contains("\"type\":\"safety_event\""));
+        assert!(json.contains("\"action\":\"block\""));
+        assert_eq!(msg.action_type(), "safety_event");
+        assert!(!msg.is_terminal());
+
+        let parsed: TodoActivityMessage = serde_json::from_str(&json).unwrap();
+        assert!(matches!(parsed, TodoActivityMessage::SafetyEvent { .. }));
+    }
+
     #[test]
     fn activity_message_not_terminal() {
         let msg = TodoActivityMessage::Thinking {
@@ -32,6 +32,8 @@ pub enum TodoStatus {
     Created,
     AgentQueued,
     AgentWorking,
+    /// Agent work held by a hard spend budget until it's raised or resets.
+    BudgetPaused,
     AwaitingApproval,
     ReadyForReview,
     WaitingOnYou,
@@ -1,6 +1,7 @@
 //! Todo pickup loop — dual-interval background loop for agent todos.
 //!
-//! - Lightweight `scan_startable()` every 30s picks up newly-seeded AgentStartable todos.
+//! - Lightweight `scan_startable()` every 30s picks up newly-seeded AgentStartable todos,
+//!   and `resume_paused()` requeues budget-paused todos once their period rolls over.
 //! - Full `recover()` on startup + every 15 min as a crash-recovery safety net.
 
 use std::sync::Arc;
@@ -41,6 +42,7 @@ pub fn spawn_todo_pickup_loop(
         loop {
             scan_tick.tick().await;
             queue.scan_startable().await;
+            queue.resume_paused().await;
 
             if last_recovery.elapsed() >= recovery_interval {
                 queue.recover().await;
@@ -0,0 +1,671 @@
+//! Calendar tools backed by the local event store (mirrored from CalDAV and
+//! emailed invitations).
+//!
+//! - `calendar_list_events` / `calendar_find_free_slots` only read the store.
+//! - `calendar_create_event` never writes to the calendar itself: it raises a
+//!   Calendar-silo approval card, and the event is created once approved.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use uuid::Uuid;
+
+use crate::calendar::availability::{self, WorkingHours};
+use crate::calendar::invite;
+use crate::calendar::{Attendee, CalendarEvent};
+use crate::cards::model::ApprovalCard;
+use crate::cards::queue::CardQueue;
+use crate::context::JobContext;
+use crate::store::Database;
+use crate::tools::params::Params;
+use crate::tools::tool::{Tool, ToolError, ToolOutput};
+
+/// Default look-ahead when no range end is given.
+const DEFAULT_RANGE_DAYS: i64 = 7;
+
+/// Longest range a single call may cover.
+const MAX_RANGE_DAYS: i64 = 62;
+
+/// Longest event or free slot a call may ask for: one day.
+const MAX_DURATION_MINUTES: u64 = 24 * 60;
+
+// ── calendar_list_events ────────────────────────────────────────────
+
+/// Tool for listing calendar events in a time range.
+pub struct CalendarListEventsTool {
+    db: Arc<dyn Database>,
+    owner_email: Option<String>,
+}
+
+impl CalendarListEventsTool {
+    pub fn new(db: Arc<dyn Database>, owner_email: Option<String>) -> Self {
+        Self { db, owner_email }
+    }
+}
+
+#[async_trait]
+impl Tool for CalendarListEventsTool {
+    fn name(&self) -> &str {
+        "calendar_list_events"
+    }
+
+    fn description(&self) -> &str {
+        "List the user's calendar events between two times (default: the next 7 days). \
+         Each event says whether it makes the user busy — declined, cancelled and \
+         free-time events don't."
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "start": {
+                    "type": "string",
+                    "description": "Range start, RFC 3339 or local 'YYYY-MM-DD[THH:MM]' (default: now)"
+                },
+                "end": {
+                    "type": "string",
+                    "description": "Range end, same formats (default: 7 days after start)"
+                },
+                "limit": {
+                    "type": "integer",
+                    "description": "Max results (default: 50, max: 200)",
+                    "default": 50
+                }
+            },
+            "required": []
+        })
+    }
+
+    fn summarize(&self, params: &serde_json::Value) -> crate::tools::summary::ToolSummary {
+        let raw = serde_json::to_string_pretty(params).unwrap_or_default();
+        crate::tools::summary::ToolSummary::new("List", "calendar", "List calendar events", raw)
+    }
+
+    async fn execute(
+        &self,
+        params: serde_json::Value,
+        _ctx: &JobContext,
+    ) -> Result<ToolOutput, ToolError> {
+        let started = std::time::Instant::now();
+        let p = Params::new(&params);
+        let (start, end) = time_range(&p)?;
+        let limit = p.u64_or("limit", 50).min(200) as usize;
+
+        let stored = self
+            .db
+            .list_calendar_events(start, end)
+            .await
+            .map_err(|e| ToolError::exec("List calendar events", e))?;
+        let me = self.owner_email.as_deref();
+        let events: Vec<serde_json::Value> = stored
+            .iter()
+            .take(limit)
+            .map(|s| {
+                let e = &s.event;
+                let mut summary = serde_json::json!({
+                    "uid": e.uid,
+                    "summary": e.summary,
+                    "start": e.start.to_rfc3339(),
+                    "end": e.end.to_rfc3339(),
+                    "when": invite::when(e),
+                    "all_day": e.all_day,
+                    "status": e.status,
+                    "busy": e.blocks_time(me),
+                });
+                if let Some(ref location) = e.location {
+                    summary["location"] = serde_json::Value::String(location.clone());
+                }
+                if let Some(ref organizer) = e.organizer {
+                    summary["organizer"] = serde_json::Value::String(organizer.email.clone());
+                }
+                if !e.attendees.is_empty() {
+                    summary["attendees"] =
+                        serde_json::json!(e.attendees.iter().map(|a| &a.email).collect::<Vec<_>>());
+                }
+                summary
+            })
+            .collect();
+
+        Ok(ToolOutput::success(
+            serde_json::json!({
+                "start": start.to_rfc3339(),
+                "end": end.to_rfc3339(),
+                "count": events.len(),
+                "events": events,
+            }),
+            started.elapsed(),
+        ))
+    }
+}
+
+// ── calendar_find_free_slots ────────────────────────────────────────
+
+/// Tool for finding open time in working hours.
+pub struct CalendarFindFreeSlotsTool {
+    db: Arc<dyn Database>,
+    owner_email: Option<String>,
+}
+
+impl CalendarFindFreeSlotsTool {
+    pub fn new(db: Arc<dyn Database>, owner_email: Option<String>) -> Self {
+        Self { db, owner_email }
+    }
+}
+
+#[async_trait]
+impl Tool for CalendarFindFreeSlotsTool {
+    fn name(&self) -> &str {
+        "calendar_find_free_slots"
+    }
+
+    fn description(&self) -> &str {
+        "Find free time on the user's calendar: gaps of at least duration_minutes \
+         within working hours (local time, weekdays 09:00–17:00 unless overridden) \
+         between start and end. Use this before proposing or scheduling a meeting."
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "duration_minutes": {
+                    "type": "integer",
+                    "description": "Minimum length of a slot in minutes"
+                },
+                "start": {
+                    "type": "string",
+                    "description": "Range start, RFC 3339 or local 'YYYY-MM-DD[THH:MM]' (default: now)"
+                },
+                "end": {
+                    "type": "string",
+                    "description": "Range end, same formats (default: 7 days after start)"
+                },
+                "day_start": {
+                    "type": "string",
+                    "description": "Start of the working day, local 'HH:MM' (default: 09:00)"
+                },
+                "day_end": {
+                    "type": "string",
+                    "description": "End of the working day, local 'HH:MM' (default: 17:00)"
+                },
+                "include_weekends": {
+                    "type": "boolean",
+                    "description": "Also offer Saturdays and Sundays (default: false)"
+                },
+                "limit": {
+                    "type": "integer",
+                    "description": "Max slots returned (default: 10, max: 50)",
+                    "default": 10
+                }
+            },
+            "required": ["duration_minutes"]
+        })
+    }
+
+    fn summarize(&self, params: &serde_json::Value) -> crate::tools::summary::ToolSummary {
+        let raw = serde_json::to_string_pretty(params).unwrap_or_default();
+        let headline = match params.get("duration_minutes").and_then(|v| v.as_u64()) {
+            Some(minutes) => format!("Find free {minutes}-minute slots"),
+            None => "Find free calendar slots".to_string(),
+        };
+        crate::tools::summary::ToolSummary::new("Find", "calendar", headline, raw)
+    }
+
+    async fn execute(
+        &self,
+        params: serde_json::Value,
+        _ctx: &JobContext,
+    ) -> Result<ToolOutput, ToolError> {
+        let started = std::time::Instant::now();
+        let p = Params::new(&params);
+        let min = minutes_param(&p, "duration_minutes")?
+            .ok_or_else(|| ToolError::InvalidParameters("missing 'duration_minutes' parameter".into()))?;
+        let (start, end) = time_range(&p)?;
+        let defaults = WorkingHours::default();
+        let hours = WorkingHours {
+            start: clock_param(&p, "day_start")?.unwrap_or(defaults.start),
+            end: clock_param(&p, "day_end")?.unwrap_or(defaults.end),
+            weekends: p
+                .optional_json("include_weekends")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(defaults.weekends),
+        };
+        if hours.end <= hours.start {
+            return Err(ToolError::InvalidParameters("day_end must be after day_start".into()));
+        }
+        let limit = p.u64_or("limit", 10).min(50) as usize;
+
+        let stored = self
+            .db
+            .list_calendar_events(start, end)
+            .await
+            .map_err(|e| ToolError::exec("Find free slots", e))?;
+        let busy = availability::busy(&Local, &stored, self.owner_email.as_deref(), start, end);
+        let slots: Vec<serde_json::Value> = availability::free_slots(
+            &Local,
+            &busy,
+            start,
+            end,
+            &hours,
+            min,
+        )
+        .into_iter()
+        .take(limit)
+        .map(|(from, to)| {
+            serde_json::json!({
+                "start": from.to_rfc3339(),
+                "end": to.to_rfc3339(),
+                "when": invite::span(from, to),
+            })
+        })
+        .collect();
+
+        Ok(ToolOutput::success(
+            serde_json::json!({
+                "duration_minutes": min.num_minutes(),
+                "count": slots.len(),
+                "slots": slots,
+            }),
+            started.elapsed(),
+        ))
+    }
+}
+
+// ── calendar_create_event ───────────────────────────────────────────
+
+/// Tool for proposing a new calendar event via an approval card.
+pub struct CalendarCreateEventTool {
+    db: Arc<dyn Database>,
+    card_queue: Arc<CardQueue>,
+    owner_email: Option<String>,
+}
+
+impl CalendarCreateEventTool {
+    pub fn new(db: Arc<dyn Database>, card_queue: Arc<CardQueue>, owner_email: Option<String>) -> Self {
+        Self {
+            db,
+            card_queue,
+            owner_email,
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for CalendarCreateEventTool {
+    fn name(&self) -> &str {
+        "calendar_create_event"
+    }
+
+    fn description(&self) -> &str {
+        "Propose a new event for the user's calendar. The event appears as an approval \
+         card in the Calendar tab and is only created once the user approves it — it is \
+         NOT on the calendar when this tool returns. Check calendar_find_free_slots first. \
+         Include todo_id when scheduling during todo execution."
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "summary": {
+                    "type": "string",
+                    "description": "Event title"
+                },
+                "start": {
+                    "type": "string",
+                    "description": "Start time, RFC 3339 or local 'YYYY-MM-DDTHH:MM'"
+                },
+                "end": {
+                    "type": "string",
+                    "description": "End time, same formats (or give duration_minutes)"
+                },
+                "duration_minutes": {
+                    "type": "integer",
+                    "description": "Length in minutes when end is omitted (default: 30)"
+                },
+                "description": {
+                    "type": "string",
+                    "description": "Event notes (optional)"
+                },
+                "location": {
+                    "type": "string",
+                    "description": "Location or call link (optional)"
+                },
+                "attendees": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Email addresses to invite (optional)"
+                },
+                "todo_id": {
+                    "type": "string",
+                    "description": "UUID of the todo this event belongs to (optional)"
+                }
+            },
+            "required": ["summary", "start"]
+        })
+    }
+
+    fn summarize(&self, params: &serde_json::Value) -> crate::tools::summary::ToolSummary {
+        let raw = serde_json::to_string_pretty(params).unwrap_or_default();
+        let summary = params.get("summary").and_then(|v| v.as_str()).unwrap_or("untitled");
+        crate::tools::summary::ToolSummary::new("Schedule", summary, format!("Schedule event: {summary}"), raw)
+    }
+
+    async fn execute(
+        &self,
+        params: serde_json::Value,
+        _ctx: &JobContext,
+    ) -> Result<ToolOutput, ToolError> {
+        let started = std::time::Instant::now();
+        let p = Params::new(&params);
+
+        let summary = p.require_str("summary")?.trim();
+        if summary.is_empty() {
+            return Err(ToolError::InvalidParameters("summary must not be empty".into()));
+        }
+        let start = time_param(&p, "start")?
+            .ok_or_else(|| ToolError::InvalidParameters("missing 'start' parameter".into()))?;
+        let end = match time_param(&p, "end")? {
+            Some(end) => end,
+            None => {
+                let length = minutes_param(&p, "duration_minutes")?.unwrap_or(Duration::minutes(30));
+                start
+                    .checked_add_signed(length)
+                    .ok_or_else(|| ToolError::InvalidParameters("start is out of range".into()))?
+            }
+        };
+        if end <= start {
+            return Err(ToolError::InvalidParameters("end must be after start".into()));
+        }
+        if start <= Utc::now() {
+            return Err(ToolError::InvalidParameters("start must be in the future".into()));
+        }
+        let todo_id = p.optional_uuid("todo_id")?;
+
+        let mut event = CalendarEvent::new(format!("{}@ai-assist", Uuid::new_v4()), summary, start, end);
+        event.description = p.optional_str("description").map(String::from);
+        event.location = p.optional_str("location").map(String::from);
+        event.organizer = self.owner_email.as_deref().map(Attendee::new);
+        event.attendees = p
+            .optional_json("attendees")
+            .and_then(|v| v.as_array())
+            .map(|list| {
+                list.iter()
+                    .filter_map(|a| a.as_str())
+                    .map(str::trim)
+                    .filter(|a| a.contains('@'))
+                    .map(Attendee::new)
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let conflicts = availability::conflicts(self.db.as_ref(), &event, self.owner_email.as_deref()).await;
+        let mut card = ApprovalCard::new_calendar_event(event.clone(), conflicts.clone());
+        if let Some(todo_id) = todo_id {
+            card = card.with_todo_id(todo_id);
+        }
+        let card_id = card.id;
+        self.card_queue.push(card).await;
+
+        Ok(ToolOutput::success(
+            serde_json::json!({
+                "card_id": card_id.to_string(),
+                "uid": event.uid,
+                "when": invite::when(&event),
+                "conflicts": conflicts,
+                "message": "Event proposed as an approval card. It will be added to the calendar once the user approves."
+            }),
+            started.elapsed(),
+        ))
+    }
+}
+
+// ── parameter helpers ───────────────────────────────────────────────
+
+/// `start` / `end` parameters, defaulting to the next week and capped at
+/// `MAX_RANGE_DAYS`.
+fn time_range(p: &Params<'_>) -> Result<(DateTime<Utc>, DateTime<Utc>), ToolError> {
+    let start = time_param(p, "start")?.unwrap_or_else(Utc::now);
+    let after = |days| start.checked_add_signed(Duration::days(days)).unwrap_or(DateTime::<Utc>::MAX_UTC);
+    let end = time_param(p, "end")?.unwrap_or_else(|| after(DEFAULT_RANGE_DAYS));
+    if end <= start {
+        return Err(ToolError::InvalidParameters("end must be after start".into()));
+    }
+    Ok((start, end.min(after(MAX_RANGE_DAYS))))
+}
+
+/// An optional length in minutes, between 1 and `MAX_DURATION_MINUTES`.
+fn minutes_param(p: &Params<'_>, name: &str) -> Result<Option<Duration>, ToolError> {
+    let Some(value) = p.optional_json(name) else {
+        return Ok(None);
+    };
+    value
+        .as_u64()
+        .filter(|m| (1..=MAX_DURATION_MINUTES).contains(m))
+        .map(|m| Some(Duration::minutes(m as i64)))
+        .ok_or_else(|| {
+            ToolError::InvalidParameters(format!("'{name}' must be between 1 and {MAX_DURATION_MINUTES} minutes"))
+        })
+}
+
+/// An optional time parameter: RFC 3339, or a local date / date-time.
+fn time_param(p: &Params<'_>, name: &str) -> Result<Option<DateTime<Utc>>, ToolError> {
+    let Some(value) = p.optional_str(name).map(str::trim) else {
+        return Ok(None);
+    };
+    parse_time(value)
+        .map(Some)
+        .ok_or_else(|| ToolError::InvalidParameters(format!("invalid time for '{name}': {value}")))
+}
+
+fn parse_time(value: &str) -> Option<DateTime<Utc>> {
+    if let Ok(t) = DateTime::parse_from_rfc3339(value) {
+        return Some(t.with_timezone(&Utc));
+    }
+    let naive = ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M"]
+        .iter()
+        .find_map(|fmt| NaiveDateTime::parse_from_str(value, fmt).ok())
+        .or_else(|| {
+            NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .ok()
+                .map(|d| d.and_time(NaiveTime::MIN))
+        })?;
+    Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .map(|t| t.with_timezone(&Utc))
+}
+
+/// An optional `HH:MM` parameter.
+fn clock_param(p: &Params<'_>, name: &str) -> Result<Option<NaiveTime>, ToolError> {
+    p.optional_str(name)
+        .map(|v| {
+            NaiveTime::parse_from_str(v.trim(), "%H:%M")
+                .map_err(|_| ToolError::InvalidParameters(format!("invalid time of day for '{name}': {v}")))
+        })
+        .transpose()
+}
+
+// ── tests ───────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calendar::model::{EventSource, PartStat, StoredEvent};
+    use crate::cards::model::{CardPayload, CardSilo};
+    use crate::store::LibSqlBackend;
+
+    async fn store_with(events: Vec<CalendarEvent>) -> Arc<dyn Database> {
+        let db: Arc<dyn Database> = Arc::new(LibSqlBackend::new_memory().await.unwrap());
+        for event in events {
+            db.upsert_calendar_event(&StoredEvent::new(event, EventSource::Caldav))
+                .await
+                .unwrap();
+        }
+        db
+    }
+
+    fn tomorrow_at(hour: i64) -> DateTime<Utc> {
+        let midnight = (Utc::now() + Duration::days(1))
+            .date_naive()
+            .and_time(NaiveTime::MIN)
+            .and_utc();
+        midnight + Duration::hours(hour)
+    }
+
+    #[test]
+    fn parses_rfc3339_and_local_times() {
+        assert_eq!(
+            parse_time("2026-03-02T14:00:00+02:00"),
+            Some(Utc.with_ymd_and_hms(2026, 3, 2, 12, 0, 0).unwrap())
+        );
+        let local = Local.with_ymd_and_hms(2026, 3, 2, 14, 30, 0).unwrap();
+        assert_eq!(parse_time("2026-03-02T14:30"), Some(local.with_timezone(&Utc)));
+        assert!(parse_time("2026-03-02").is_some());
+        assert!(parse_time("next tuesday").is_none());
+    }
+
+    #[tokio::test]
+    async fn list_events_marks_declined_as_free() {
+        let mut declined = CalendarEvent::new("d", "All hands", tomorrow_at(10), tomorrow_at(11));
+        declined.attendees.push(Attendee {
+            partstat: PartStat::Declined,
+            ..Attendee::new("me@x.com")
+        });
+        let db = store_with(vec![
+            CalendarEvent::new("a", "Design review", tomorrow_at(9), tomorrow_at(10)),
+            declined,
+        ])
+        .await;
+        let tool = CalendarListEventsTool::new(db, Some("me@x.com".into()));
+
+        let out = tool
+            .execute(
+                serde_json::json!({
+                    "start": tomorrow_at(0).to_rfc3339(),
+                    "end": tomorrow_at(24).to_rfc3339(),
+                }),
+                &JobContext::default(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(out.result["count"], 2);
+        assert_eq!(out.result["events"][0]["summary"], "Design review");
+        assert_eq!(out.result["events"][0]["busy"], true);
+        assert_eq!(out.result["events"][1]["busy"], false);
+    }
+
+    #[tokio::test]
+    async fn free_slots_avoid_busy_time() {
+        let busy = (tomorrow_at(12), tomorrow_at(13));
+        let db = store_with(vec![CalendarEvent::new("a", "Lunch", busy.0, busy.1)]).await;
+        let tool = CalendarFindFreeSlotsTool::new(db, None);
+
+        let out = tool
+            .execute(
+                serde_json::json!({
+                    "duration_minutes": 45,
+                    "start": tomorrow_at(10).to_rfc3339(),
+                    "end": tomorrow_at(15).to_rfc3339(),
+                    "day_start": "00:00",
+                    "day_end": "23:59",
+                    "include_weekends": true,
+                }),
+                &JobContext::default(),
+            )
+            .await
+            .unwrap();
+        // Local midnight can split the range, so check the shape, not exact slots
+        let slots = out.result["slots"].as_array().unwrap();
+        assert!(!slots.is_empty());
+        for slot in slots {
+            let from = parse_time(slot["start"].as_str().unwrap()).unwrap();
+            let to = parse_time(slot["end"].as_str().unwrap()).unwrap();
+            assert!(to - from >= Duration::minutes(45));
+            assert!(from >= tomorrow_at(10) && to <= tomorrow_at(15));
+            assert!(to <= busy.0 || from >= busy.1, "slot overlaps lunch");
+        }
+
+        let bad = tool
+            .execute(
+                serde_json::json!({"duration_minutes": 30, "day_start": "17:00", "day_end": "09:00"}),
+                &JobContext::default(),
+            )
+            .await;
+        assert!(matches!(bad, Err(ToolError::InvalidParameters(_))));
+        let huge = tool
+            .execute(serde_json::json!({"duration_minutes": u64::MAX}), &JobContext::default())
+            .await;
+        assert!(matches!(huge, Err(ToolError::InvalidParameters(_))));
+    }
+
+    #[tokio::test]
+    async fn create_event_raises_calendar_card_with_conflicts() {
+        let db = store_with(vec![CalendarEvent::new("a", "Dentist", tomorrow_at(9), tomorrow_at(10))]).await;
+        let queue = CardQueue::new();
+        let tool = CalendarCreateEventTool::new(Arc::clone(&db), queue.clone(), Some("me@x.com".into()));
+
+        let todo_id = Uuid::new_v4();
+        let out = tool
+            .execute(
+                serde_json::json!({
+                    "summary": "Call with Alice",
+                    "start": (tomorrow_at(9) + Duration::minutes(30)).to_rfc3339(),
+                    "duration_minutes": 60,
+                    "attendees": ["alice@example.com", "not an address"],
+                    "todo_id": todo_id.to_string(),
+                }),
+                &JobContext::default(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(out.result["conflicts"].as_array().unwrap().len(), 1);
+
+        // Nothing is on the calendar until the card is approved
+        assert_eq!(
+            db.list_calendar_events(tomorrow_at(0), tomorrow_at(24)).await.unwrap().len(),
+            1
+        );
+        let pending = queue.pending().await;
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].silo, CardSilo::Calendar);
+        assert_eq!(pending[0].todo_id, Some(todo_id));
+        let CardPayload::NewEvent { event, conflicts } = &pending[0].payload else {
+            panic!("Expected NewEvent payload");
+        };
+        assert_eq!(event.end - event.start, Duration::hours(1));
+        assert_eq!(event.organizer.as_ref().map(|o| o.email.as_str()), Some("me@x.com"));
+        assert_eq!(event.attendees, vec![Attendee::new("alice@example.com")]);
+        assert!(conflicts[0].starts_with("Dentist ("));
+    }
+
+    #[tokio::test]
+    async fn create_event_rejects_bad_times() {
+        let db = store_with(vec![]).await;
+        let queue = CardQueue::new();
+        let tool = CalendarCreateEventTool::new(db, queue.clone(), None);
+        let ctx = JobContext::default();
+
+        let backwards = serde_json::json!({
+            "summary": "Oops",
+            "start": tomorrow_at(10).to_rfc3339(),
+            "end": tomorrow_at(9).to_rfc3339(),
+        });
+        let past = serde_json::json!({"summary": "Oops", "start": "2020-01-01T10:00:00Z"});
+        assert!(tool.execute(backwards, &ctx).await.is_err());
+        assert!(tool.execute(past, &ctx).await.is_err());
+        let lengths = [serde_json::json!(0), serde_json::json!(1441), serde_json::json!(u64::MAX), serde_json::json!(-5)];
+        for minutes in lengths {
+            let params = serde_json::json!({
+                "summary": "Oops",
+                "start": tomorrow_at(10).to_rfc3339(),
+                "duration_minutes": minutes,
+            });
+            let result = tool.execute(params, &ctx).await;
+            assert!(matches!(result, Err(ToolError::InvalidParameters(_))), "{minutes}");
+        }
+        assert!(queue.is_empty().await);
+    }
+}
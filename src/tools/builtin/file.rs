@@ -7,12 +7,14 @@
 //! - Search/replace patching
 
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 use async_trait::async_trait;
 use tokio::fs;
 
 use crate::context::JobContext;
 use crate::tools::params::Params;
+use crate::tools::sandbox::Sandbox;
 use crate::tools::tool::{Tool, ToolDomain, ToolError, ToolOutput};
 
 /// Maximum file size for reading (1MB).
@@ -115,6 +117,19 @@ fn validate_path(path_str: &str, base_dir: Option<&Path>) -> Result<PathBuf, Too
     Ok(resolved)
 }
 
+/// The directory a write is confined to: the job's own sandbox scratch dir
+/// (per todo, else per user) when sandboxed, otherwise the fixed base dir.
+async fn write_base(
+    base_dir: Option<&Path>,
+    sandbox: Option<&Sandbox>,
+    ctx: &JobContext,
+) -> Result<Option<PathBuf>, ToolError> {
+    match sandbox {
+        Some(sandbox) => sandbox.prepare_scratch(ctx).await.map(Some),
+        None => Ok(base_dir.map(Path::to_path_buf)),
+    }
+}
+
 // ── ReadFileTool ────────────────────────────────────────────────────
 
 /// Read file contents tool.
@@ -250,6 +265,7 @@ impl Tool for ReadFileTool {
 #[derive(Debug, Default)]
 pub struct WriteFileTool {
     base_dir: Option<PathBuf>,
+    sandbox: Option<Arc<Sandbox>>,
 }
 
 impl WriteFileTool {
@@ -261,6 +277,12 @@ impl WriteFileTool {
         self.base_dir = Some(dir);
         self
     }
+
+    /// Confine writes to each job's sandbox scratch dir.
+    pub fn with_sandbox(mut self, sandbox: Arc<Sandbox>) -> Self {
+        self.sandbox = Some(sandbox);
+        self
+    }
 }
 
 #[async_trait]
@@ -294,7 +316,7 @@ impl Tool for WriteFileTool {
     async fn execute(
         &self,
         params: serde_json::Value,
-        _ctx: &JobContext,
+        ctx: &JobContext,
     ) -> Result<ToolOutput, ToolError> {
         let p = Params::new(&params);
         let path_str = p.require_str("path")?;
@@ -310,7 +332,8 @@ impl Tool for WriteFileTool {
             )));
         }
 
-        let path = validate_path(path_str, self.base_dir.as_deref())?;
+        let base = write_base(self.base_dir.as_deref(), self.sandbox.as_deref(), ctx).await?;
+        let path = validate_path(path_str, base.as_deref())?;
 
         if let Some(parent) = path.parent() {
             fs::create_dir_all(parent)
@@ -550,6 +573,7 @@ fn format_size(bytes: u64) -> String {
 #[derive(Debug, Default)]
 pub struct ApplyPatchTool {
     base_dir: Option<PathBuf>,
+    sandbox: Option<Arc<Sandbox>>,
 }
 
 impl ApplyPatchTool {
@@ -561,6 +585,12 @@ impl ApplyPatchTool {
         self.base_dir = Some(dir);
         self
     }
+
+    /// Confine writes to each job's sandbox scratch dir.
+    pub fn with_sandbox(mut self, sandbox: Arc<Sandbox>) -> Self {
+        self.sandbox = Some(sandbox);
+        self
+    }
 }
 
 #[async_trait]
@@ -603,7 +633,7 @@ impl Tool for ApplyPatchTool {
     async fn execute(
         &self,
         params: serde_json::Value,
-        _ctx: &JobContext,
+        ctx: &JobContext,
     ) -> Result<ToolOutput, ToolError> {
         let p = Params::new(&params);
         let path_str = p.require_str("path")?;
@@ -612,7 +642,8 @@ impl Tool for ApplyPatchTool {
         let replace_all = params.get("replace_all").and_then(|v| v.as_bool()).unwrap_or(false);
 
         let start = std::time::Instant::now();
-        let path = validate_path(path_str, self.base_dir.as_deref())?;
+        let base = write_base(self.base_dir.as_deref(), self.sandbox.as_deref(), ctx).await?;
+        let path = validate_path(path_str, base.as_deref())?;
 
         let content = fs::read_to_string(&path)
             .await
@@ -771,6 +802,49 @@ mod tests {
         assert_eq!(std::fs::read_to_string(&file_path).unwrap(), "deep");
     }
 
+    #[tokio::test]
+    async fn test_sandboxed_writes_stay_in_the_todo_scratch_dir() {
+        use crate::config::{SandboxConfig, SandboxMode};
+        use crate::tools::sandbox::SandboxBackend;
+
+        let dir = TempDir::new().unwrap();
+        let config = SandboxConfig {
+            mode: SandboxMode::Required,
+            scratch_root: dir.path().to_path_buf(),
+            ..SandboxConfig::default()
+        };
+        let sandbox = Arc::new(Sandbox::new(config, SandboxBackend::Unshare("unshare".into())));
+        let write = WriteFileTool::new().with_sandbox(Arc::clone(&sandbox));
+        let patch = ApplyPatchTool::new().with_sandbox(sandbox);
+        let (todo_a, todo_b) = (uuid::Uuid::new_v4(), uuid::Uuid::new_v4());
+        let ctx = |todo_id: uuid::Uuid| JobContext {
+            metadata: serde_json::json!({ "todo_id": todo_id }),
+            ..JobContext::with_user("tester", "todo", "")
+        };
+
+        write
+            .execute(serde_json::json!({"path": "notes.md", "content": "a"}), &ctx(todo_a))
+            .await
+            .unwrap();
+        let own = dir.path().join(format!("todo-{todo_a}/notes.md"));
+        assert_eq!(std::fs::read_to_string(&own).unwrap(), "a");
+
+        // Another todo can't reach into it, by absolute or relative path
+        let absolute = own.to_str().unwrap();
+        let relative = format!("../todo-{todo_a}/notes.md");
+        for path in [absolute, relative.as_str()] {
+            let write_err = write
+                .execute(serde_json::json!({"path": path, "content": "b"}), &ctx(todo_b))
+                .await;
+            assert!(matches!(write_err, Err(ToolError::NotAuthorized(_))), "{path}");
+            let patch_err = patch
+                .execute(serde_json::json!({"path": path, "old_string": "a", "new_string": "b"}), &ctx(todo_b))
+                .await;
+            assert!(matches!(patch_err, Err(ToolError::NotAuthorized(_))), "{path}");
+        }
+        assert_eq!(std::fs::read_to_string(&own).unwrap(), "a");
+    }
+
     #[tokio::test]
     async fn test_apply_patch() {
         let dir = TempDir::new().unwrap();
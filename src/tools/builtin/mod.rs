@@ -1,6 +1,7 @@
-//! Built-in tools for shell execution, file operations, routine management, memory, and documents.
+//! Built-in tools for shell execution, file operations, routine management, memory, documents, and the calendar.
 
 pub mod ask_user;
+pub mod calendar;
 pub mod document;
 pub mod file;
 pub mod memory;
@@ -17,7 +17,7 @@ use uuid::Uuid;
 use crate::agent::routine::{
     next_cron_fire, NotifyConfig, Routine, RoutineAction, RoutineGuardrails, Trigger,
 };
-use crate::agent::routine_engine::RoutineEngine;
+use crate::agent::routine_engine::{RoutineEngine, normalize_webhook_path};
 use crate::context::JobContext;
 use crate::store::Database;
 use crate::tools::params::Params;
@@ -76,6 +76,14 @@ impl Tool for RoutineCreateTool {
                     "type": "string",
                     "description": "Optional channel filter for event trigger"
                 },
+                "webhook_path": {
+                    "type": "string",
+                    "description": "URL path under /hooks/ for webhook trigger (default: routine name)"
+                },
+                "webhook_secret": {
+                    "type": "string",
+                    "description": "HMAC-SHA256 secret for webhook signatures (generated if omitted)"
+                },
                 "prompt": {
                     "type": "string",
                     "description": "The prompt/instructions for the routine"
@@ -129,7 +137,17 @@ impl Tool for RoutineCreateTool {
                 let channel = p.optional_str("event_channel").map(String::from);
                 Trigger::Event { channel, pattern: pattern.to_string() }
             }
-            "webhook" => Trigger::Webhook { path: None, secret: None },
+            "webhook" => {
+                let path = p.optional_str("webhook_path").unwrap_or(name);
+                let secret = p
+                    .optional_str("webhook_secret")
+                    .map(String::from)
+                    .unwrap_or_else(generate_webhook_secret);
+                Trigger::Webhook {
+                    path: Some(normalize_webhook_path(path).to_string()),
+                    secret: Some(secret),
+                }
+            }
             "manual" => Trigger::Manual,
             other => return Err(ToolError::InvalidParameters(format!("unknown trigger_type: {other}"))),
         };
@@ -195,19 +213,29 @@ impl Tool for RoutineCreateTool {
             self.engine.refresh_event_cache().await;
         }
 
-        Ok(ToolOutput::success(
-            serde_json::json!({
-                "id": routine.id.to_string(),
-                "name": routine.name,
-                "trigger_type": routine.trigger.type_tag(),
-                "next_fire_at": routine.next_fire_at.map(|t| t.to_rfc3339()),
-                "status": "created",
-            }),
-            start.elapsed(),
-        ))
+        let mut result = serde_json::json!({
+            "id": routine.id.to_string(),
+            "name": routine.name,
+            "trigger_type": routine.trigger.type_tag(),
+            "next_fire_at": routine.next_fire_at.map(|t| t.to_rfc3339()),
+            "status": "created",
+        });
+        if let Trigger::Webhook { path: Some(path), secret: Some(secret) } = &routine.trigger {
+            result["webhook_url_path"] = serde_json::json!(format!("/hooks/{path}"));
+            result["webhook_secret"] = serde_json::json!(secret);
+        }
+
+        Ok(ToolOutput::success(result, start.elapsed()))
     }
 }
 
+/// Random 32-byte hex secret for signing webhook requests.
+fn generate_webhook_secret() -> String {
+    use rand::Rng;
+    let bytes: [u8; 32] = rand::thread_rng().r#gen();
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
 // ── routine_list ────────────────────────────────────────────────────
 
 pub struct RoutineListTool {
@@ -512,6 +540,15 @@ mod tests {
         assert!(required.contains(&"prompt".into()));
     }
 
+    #[test]
+    fn webhook_secret_is_random_hex() {
+        let a = generate_webhook_secret();
+        let b = generate_webhook_secret();
+        assert_eq!(a.len(), 64);
+        assert!(a.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_ne!(a, b);
+    }
+
     #[tokio::test]
     async fn routine_list_empty() {
         let db = test_db().await;
@@ -4,12 +4,12 @@
 //! - Working directory isolation
 //! - Timeout enforcement
 //! - Output capture and truncation
-//! - Blocked command patterns for safety
+//! - Per-command classification against a [`ShellPolicy`]
+//! - Namespace sandboxing when a [`Sandbox`] is attached
 
-use std::collections::HashSet;
 use std::path::PathBuf;
 use std::process::Stdio;
-use std::sync::LazyLock;
+use std::sync::Arc;
 use std::time::Duration;
 
 use async_trait::async_trait;
@@ -17,6 +17,9 @@ use tokio::io::AsyncReadExt;
 use tokio::process::Command;
 
 use crate::context::JobContext;
+use crate::tools::sandbox::Sandbox;
+use crate::tools::shell_parse::SimpleCommand;
+use crate::tools::shell_policy::{ShellAnalysis, ShellPolicy};
 use crate::tools::tool::{Tool, ToolDomain, ToolError, ToolOutput, require_str};
 
 /// Maximum output size before truncation (64KB).
@@ -25,92 +28,27 @@ const MAX_OUTPUT_SIZE: usize = 64 * 1024;
 /// Default command timeout.
 const DEFAULT_TIMEOUT: Duration = Duration::from_secs(120);
 
-/// Commands that are always blocked for safety.
-static BLOCKED_COMMANDS: LazyLock<HashSet<&'static str>> = LazyLock::new(|| {
-    HashSet::from([
-        "rm -rf /",
-        "rm -rf /*",
-        ":(){ :|:& };:", // Fork bomb
-        "dd if=/dev/zero",
-        "mkfs",
-        "chmod -R 777 /",
-        "> /dev/sda",
-        "curl | sh",
-        "wget | sh",
-        "curl | bash",
-        "wget | bash",
-    ])
-});
-
-/// Patterns that indicate potentially dangerous commands.
-static DANGEROUS_PATTERNS: LazyLock<Vec<&'static str>> = LazyLock::new(|| {
-    vec![
-        "sudo ",
-        "doas ",
-        " | sh",
-        " | bash",
-        " | zsh",
-        "eval ",
-        "$(curl",
-        "$(wget",
-        "/etc/passwd",
-        "/etc/shadow",
-        "~/.ssh",
-        ".bash_history",
-        "id_rsa",
-    ]
-});
-
-/// Patterns that should NEVER be auto-approved, even if the user chose "always approve"
-/// for the shell tool. These require explicit per-invocation approval because they are
-/// destructive or security-sensitive.
-static NEVER_AUTO_APPROVE_PATTERNS: LazyLock<Vec<&'static str>> = LazyLock::new(|| {
-    vec![
-        "rm -rf",
-        "rm -fr",
-        "chmod -r 777",
-        "chmod 777",
-        "chown -r",
-        "shutdown",
-        "reboot",
-        "poweroff",
-        "init 0",
-        "init 6",
-        "iptables",
-        "nft ",
-        "useradd",
-        "userdel",
-        "passwd",
-        "visudo",
-        "crontab",
-        "systemctl disable",
-        "launchctl unload",
-        "kill -9",
-        "killall",
-        "pkill",
-        "docker rm",
-        "docker rmi",
-        "docker system prune",
-        "git push --force",
-        "git push -f",
-        "git reset --hard",
-        "git clean -f",
-        "DROP TABLE",
-        "DROP DATABASE",
-        "TRUNCATE",
-        "DELETE FROM",
-    ]
-});
-
-/// Check whether a shell command contains patterns that must never be auto-approved.
+/// Check whether a shell command must never be auto-approved under the
+/// built-in policy.
 ///
 /// Even when the user has chosen "always approve" for the shell tool, these commands
 /// require explicit per-invocation approval because they are destructive.
 pub fn requires_explicit_approval(command: &str) -> bool {
-    let lower = command.to_lowercase();
-    NEVER_AUTO_APPROVE_PATTERNS
-        .iter()
-        .any(|p| lower.contains(&p.to_lowercase()))
+    ShellPolicy::default().analyze(command).requires_approval()
+}
+
+/// Pull the command out of shell tool arguments, which some providers send as
+/// a JSON-encoded string rather than an object.
+fn command_from_params(params: &serde_json::Value) -> Option<String> {
+    params
+        .get("command")
+        .and_then(|c| c.as_str().map(String::from))
+        .or_else(|| {
+            params
+                .as_str()
+                .and_then(|s| serde_json::from_str::<serde_json::Value>(s).ok())
+                .and_then(|v| v.get("command").and_then(|c| c.as_str().map(String::from)))
+        })
 }
 
 /// Shell command execution tool.
@@ -120,8 +58,10 @@ pub struct ShellTool {
     working_dir: Option<PathBuf>,
     /// Command timeout.
     timeout: Duration,
-    /// Whether to allow potentially dangerous commands (requires explicit approval).
-    allow_dangerous: bool,
+    /// Decides which commands are allowed, need approval, or are blocked.
+    policy: Arc<ShellPolicy>,
+    /// When set, commands run inside the sandbox instead of on the host.
+    sandbox: Option<Arc<Sandbox>>,
 }
 
 impl ShellTool {
@@ -130,7 +70,8 @@ impl ShellTool {
         Self {
             working_dir: None,
             timeout: DEFAULT_TIMEOUT,
-            allow_dangerous: false,
+            policy: Arc::new(ShellPolicy::default()),
+            sandbox: None,
         }
     }
 
@@ -146,34 +87,25 @@ impl ShellTool {
         self
     }
 
-    /// Check if a command is blocked.
-    fn is_blocked(&self, cmd: &str) -> Option<&'static str> {
-        let normalized = cmd.to_lowercase();
-
-        for blocked in BLOCKED_COMMANDS.iter() {
-            if normalized.contains(blocked) {
-                return Some("Command contains blocked pattern");
-            }
-        }
+    /// Run commands inside `sandbox`, each job in its own scratch directory.
+    pub fn with_sandbox(mut self, sandbox: Arc<Sandbox>) -> Self {
+        self.sandbox = Some(sandbox);
+        self
+    }
 
-        if !self.allow_dangerous {
-            for pattern in DANGEROUS_PATTERNS.iter() {
-                if normalized.contains(pattern) {
-                    return Some("Command contains potentially dangerous pattern");
-                }
-            }
-        }
+    /// Classify commands against `policy` instead of the built-in rules.
+    pub fn with_policy(mut self, policy: Arc<ShellPolicy>) -> Self {
+        self.policy = policy;
+        self
+    }
 
-        None
+    /// Check if a command is blocked, returning the reason.
+    fn is_blocked(&self, cmd: &str) -> Option<String> {
+        self.policy.analyze(cmd).blocked_reason()
     }
 
-    /// Execute a command directly, capturing stdout and stderr.
-    async fn execute_direct(
-        &self,
-        cmd: &str,
-        workdir: &PathBuf,
-        timeout: Duration,
-    ) -> Result<(String, i32), ToolError> {
+    /// Build a command that runs directly on the host.
+    fn host_command(cmd: &str, workdir: &PathBuf) -> Command {
         let mut command = if cfg!(target_os = "windows") {
             let mut c = Command::new("cmd");
             c.args(["/C", cmd]);
@@ -183,9 +115,17 @@ impl ShellTool {
             c.args(["-c", cmd]);
             c
         };
+        command.current_dir(workdir);
+        command
+    }
 
+    /// Run a prepared command, capturing stdout and stderr.
+    async fn execute_direct(
+        &self,
+        mut command: Command,
+        timeout: Duration,
+    ) -> Result<(String, i32), ToolError> {
         command
-            .current_dir(workdir)
             .stdin(Stdio::null())
             .stdout(Stdio::piped())
             .stderr(Stdio::piped());
@@ -242,23 +182,38 @@ impl ShellTool {
         cmd: &str,
         workdir: Option<&str>,
         timeout: Option<u64>,
+        ctx: &JobContext,
     ) -> Result<(String, i64), ToolError> {
         if let Some(reason) = self.is_blocked(cmd) {
             return Err(ToolError::NotAuthorized(format!(
-                "{}: {}",
+                "Command blocked ({}): {}",
                 reason,
                 truncate_for_error(cmd)
             )));
         }
 
-        let cwd = workdir
-            .map(PathBuf::from)
-            .or_else(|| self.working_dir.clone())
-            .unwrap_or_else(|| std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")));
-
         let timeout_duration = timeout.map(Duration::from_secs).unwrap_or(self.timeout);
 
-        let (output, code) = self.execute_direct(cmd, &cwd, timeout_duration).await?;
+        let command = match &self.sandbox {
+            Some(sandbox) => {
+                // Inside the sandbox only the scratch dir is writable, so it's
+                // the default working directory.
+                let scratch = sandbox.prepare_scratch(ctx).await?;
+                let cwd = workdir.map(PathBuf::from).unwrap_or_else(|| scratch.clone());
+                sandbox.command(cmd, &scratch, &cwd)
+            }
+            None => {
+                let cwd = workdir
+                    .map(PathBuf::from)
+                    .or_else(|| self.working_dir.clone())
+                    .unwrap_or_else(|| {
+                        std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."))
+                    });
+                Self::host_command(cmd, &cwd)
+            }
+        };
+
+        let (output, code) = self.execute_direct(command, timeout_duration).await?;
         Ok((output, code as i64))
     }
 }
@@ -304,14 +259,14 @@ impl Tool for ShellTool {
     async fn execute(
         &self,
         params: serde_json::Value,
-        _ctx: &JobContext,
+        ctx: &JobContext,
     ) -> Result<ToolOutput, ToolError> {
         let command = require_str(&params, "command")?;
         let workdir = params.get("workdir").and_then(|v| v.as_str());
         let timeout = params.get("timeout").and_then(|v| v.as_u64());
 
         let start = std::time::Instant::now();
-        let (output, exit_code) = self.execute_command(command, workdir, timeout).await?;
+        let (output, exit_code) = self.execute_command(command, workdir, timeout, ctx).await?;
         let duration = start.elapsed();
 
         let result = serde_json::json!({
@@ -331,6 +286,10 @@ impl Tool for ShellTool {
         true
     }
 
+    fn requires_explicit_approval(&self, params: &serde_json::Value) -> bool {
+        command_from_params(params).is_some_and(|cmd| self.policy.analyze(&cmd).requires_approval())
+    }
+
     fn summarize(&self, params: &serde_json::Value) -> crate::tools::summary::ToolSummary {
         let raw = serde_json::to_string_pretty(params).unwrap_or_default();
         let cmd = params
@@ -338,16 +297,24 @@ impl Tool for ShellTool {
             .and_then(|v| v.as_str())
             .unwrap_or("(unknown)");
 
-        // Split into effective words, stripping leading env assignments (FOO=bar)
-        let words: Vec<&str> = cmd.split_whitespace().collect();
-        let skip = words.iter().take_while(|w| w.contains('=')).count();
-        let effective = &words[skip..]; // words starting from the actual command
+        // Headline from the command that matters most; fall back to plain
+        // word splitting when the line doesn't parse.
+        let analysis = self.policy.analyze(cmd);
+        let words: Vec<String> = match primary_command(&analysis) {
+            Some(primary) => primary.words.iter().map(|w| w.text.clone()).collect(),
+            None => cmd
+                .split_whitespace()
+                .skip_while(|w| w.contains('='))
+                .map(String::from)
+                .collect(),
+        };
+        let effective: Vec<&str> = words.iter().map(String::as_str).collect();
         let base = effective.first().copied().unwrap_or("(unknown)");
         let arg1 = effective.get(1).copied();
 
         let (verb, target, headline) = match base {
             "curl" | "wget" => {
-                let url = words
+                let url = effective
                     .iter()
                     .map(|w| w.trim_matches(|c: char| c == '"' || c == '\''))
                     .find(|w| w.starts_with("http"))
@@ -403,6 +370,7 @@ impl Tool for ShellTool {
         };
 
         crate::tools::summary::ToolSummary::new(verb, target, headline, raw)
+            .with_details(breakdown(&analysis))
     }
 
     fn domain(&self) -> ToolDomain {
@@ -410,6 +378,41 @@ impl Tool for ShellTool {
     }
 }
 
+/// Commands that set up the real work rather than being it.
+const TRIVIAL_COMMANDS: &[&str] = &["cd", "echo", "printf", "true", ":", "export", "set", "pwd", "sleep"];
+
+/// The command a card headline should describe: the most restrictive one,
+/// preferring real work over `cd`/`echo` on ties.
+fn primary_command(analysis: &ShellAnalysis) -> Option<&SimpleCommand> {
+    let commands = analysis.script.as_ref()?.simple_commands();
+    let rank = |i: usize| {
+        let verdict = analysis.commands.get(i).map(|c| c.verdict);
+        let trivial = commands[i]
+            .program()
+            .is_none_or(|p| TRIVIAL_COMMANDS.contains(&p));
+        (verdict, !trivial, std::cmp::Reverse(i))
+    };
+    (0..commands.len())
+        .filter(|&i| !commands[i].words.is_empty())
+        .max_by_key(|&i| rank(i))
+        .map(|i| commands[i])
+}
+
+/// Per-command lines for the approval card, when there's more to say than
+/// the headline.
+fn breakdown(analysis: &ShellAnalysis) -> Vec<String> {
+    if let Some(reason) = analysis.reason()
+        && analysis.parse_error.is_some()
+    {
+        return vec![reason];
+    }
+    let interesting = analysis.commands.len() > 1 || analysis.commands.iter().any(|c| c.reason.is_some());
+    if !interesting {
+        return Vec::new();
+    }
+    analysis.commands.iter().map(|c| c.describe()).collect()
+}
+
 /// Truncate output to fit within limits (UTF-8 safe).
 fn truncate_output(s: &str) -> String {
     if s.len() <= MAX_OUTPUT_SIZE {
@@ -558,6 +561,34 @@ mod tests {
         assert!(output.contains("tmp"));
     }
 
+    #[tokio::test]
+    async fn test_sandboxed_command_runs_in_scratch_dir() {
+        use crate::config::{SandboxConfig, SandboxMode};
+        use crate::tools::sandbox::SandboxBackend;
+
+        // Only where unprivileged namespaces are available.
+        let Some(backend @ SandboxBackend::Unshare(_)) = SandboxBackend::detect() else {
+            return;
+        };
+        let dir = tempfile::tempdir().unwrap();
+        let config = SandboxConfig {
+            mode: SandboxMode::Required,
+            scratch_root: dir.path().to_path_buf(),
+            ..SandboxConfig::default()
+        };
+        let tool = ShellTool::new().with_sandbox(Arc::new(Sandbox::new(config, backend)));
+        let ctx = JobContext::with_user("tester", "chat", "");
+
+        let result = tool
+            .execute(serde_json::json!({"command": "pwd; echo $ANTHROPIC_API_KEY"}), &ctx)
+            .await
+            .unwrap();
+
+        let output = result.result.get("output").unwrap().as_str().unwrap();
+        assert!(output.trim().ends_with("user-tester"), "{output}");
+        assert_eq!(result.result.get("exit_code").unwrap().as_i64().unwrap(), 0);
+    }
+
     #[test]
     fn test_truncate_output_short() {
         let s = "short output";
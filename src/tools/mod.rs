@@ -3,6 +3,9 @@
 pub mod builtin;
 pub mod params;
 pub mod registry;
+pub mod sandbox;
+pub mod shell_parse;
+pub mod shell_policy;
 pub mod summary;
 pub mod tool;
 
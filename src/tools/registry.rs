@@ -7,6 +7,8 @@ use tokio::sync::RwLock;
 
 use crate::llm::ToolDefinition;
 use crate::store::Database;
+use crate::tools::sandbox::Sandbox;
+use crate::tools::shell_policy::ShellPolicy;
 use crate::tools::tool::{Tool, ToolDomain};
 use crate::workspace::Workspace;
 
@@ -40,6 +42,9 @@ const PROTECTED_TOOL_NAMES: &[&str] = &[
     "list_todos",
     "ask_user",
     "create_message",
+    "calendar_list_events",
+    "calendar_find_free_slots",
+    "calendar_create_event",
 ];
 
 /// Registry of available tools.
@@ -174,14 +179,28 @@ impl ToolRegistry {
     }
 
     /// Register all file and shell tools.
-    pub fn register_file_tools(&self) {
+    ///
+    /// Shell commands are classified against `shell_policy`. With a sandbox,
+    /// they run inside it and file writes are confined to the job's scratch
+    /// dir — the same one its shell commands see; reads stay unrestricted.
+    pub fn register_file_tools(&self, sandbox: Option<Arc<Sandbox>>, shell_policy: Arc<ShellPolicy>) {
         use crate::tools::builtin::file::*;
         use crate::tools::builtin::shell::ShellTool;
-        self.register_sync(Arc::new(ShellTool::new()));
+        let shell = ShellTool::new().with_policy(shell_policy);
+        match sandbox {
+            Some(sandbox) => {
+                self.register_sync(Arc::new(WriteFileTool::new().with_sandbox(Arc::clone(&sandbox))));
+                self.register_sync(Arc::new(ApplyPatchTool::new().with_sandbox(Arc::clone(&sandbox))));
+                self.register_sync(Arc::new(shell.with_sandbox(sandbox)));
+            }
+            None => {
+                self.register_sync(Arc::new(shell));
+                self.register_sync(Arc::new(WriteFileTool::new()));
+                self.register_sync(Arc::new(ApplyPatchTool::new()));
+            }
+        }
         self.register_sync(Arc::new(ReadFileTool::new()));
-        self.register_sync(Arc::new(WriteFileTool::new()));
         self.register_sync(Arc::new(ListDirTool::new()));
-        self.register_sync(Arc::new(ApplyPatchTool::new()));
     }
 
     /// Register all document tools.
@@ -222,6 +241,19 @@ impl ToolRegistry {
         self.register_sync(Arc::new(CreateMessageTool::new(card_queue)));
     }
 
+    /// Register calendar tools (free/busy lookup, event approval cards).
+    pub fn register_calendar_tools(
+        &self,
+        db: Arc<dyn Database>,
+        card_queue: Arc<crate::cards::queue::CardQueue>,
+        owner_email: Option<String>,
+    ) {
+        use crate::tools::builtin::calendar::*;
+        self.register_sync(Arc::new(CalendarListEventsTool::new(db.clone(), owner_email.clone())));
+        self.register_sync(Arc::new(CalendarFindFreeSlotsTool::new(db.clone(), owner_email.clone())));
+        self.register_sync(Arc::new(CalendarCreateEventTool::new(db, card_queue, owner_email)));
+    }
+
     /// Register all memory/workspace tools.
     pub fn register_memory_tools(&self, workspace: Arc<Workspace>) {
         use crate::tools::builtin::memory::*;
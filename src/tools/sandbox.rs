@@ -0,0 +1,388 @@
+//! Sandboxed process execution for `ToolDomain::Container` tools.
+//!
+//! Each command runs in fresh Linux user, mount, PID and (unless
+//! `SANDBOX_NETWORK=true`) network namespaces:
+//! - the configured host paths are mounted read-only
+//! - the per-todo scratch directory and a private `/tmp` are the only
+//!   writable locations
+//! - CPU time and address space are capped with rlimits; wall-clock time is
+//!   enforced by the calling tool's timeout
+//! - the host environment (API keys included) is not inherited
+//!
+//! Two wrappers are supported: bubblewrap (`bwrap`) when installed, otherwise
+//! util-linux `unshare` driving a small mount + `pivot_root` script.
+
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+
+use tokio::process::Command;
+
+use crate::config::{SandboxConfig, SandboxMode};
+use crate::context::JobContext;
+use crate::error::ConfigError;
+use crate::tools::tool::ToolError;
+
+/// Directory under the scratch root that `unshare` mounts the new root on.
+/// Mounts are per-namespace, so concurrent commands can share it.
+const NEW_ROOT_DIR: &str = ".root";
+
+/// `PATH` inside the sandbox (also used to find `mount`/`pivot_root`).
+const SANDBOX_PATH: &str = "/usr/local/sbin:/usr/local/bin:/usr/sbin:/usr/bin:/sbin:/bin";
+
+/// The namespace wrapper used to launch commands.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SandboxBackend {
+    /// bubblewrap.
+    Bubblewrap(PathBuf),
+    /// util-linux `unshare`.
+    Unshare(PathBuf),
+}
+
+impl SandboxBackend {
+    /// Find a usable wrapper on `PATH`, preferring bubblewrap.
+    ///
+    /// `unshare` is only accepted after a probe confirms unprivileged user
+    /// namespaces are allowed on this host.
+    pub fn detect() -> Option<Self> {
+        if let Some(bwrap) = find_on_path("bwrap") {
+            return Some(Self::Bubblewrap(bwrap));
+        }
+        let unshare = find_on_path("unshare")?;
+        let works = std::process::Command::new(&unshare)
+            .args(["--user", "--map-root-user", "--mount", "true"])
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .is_ok_and(|s| s.success());
+        works.then_some(Self::Unshare(unshare))
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Bubblewrap(_) => "bubblewrap",
+            Self::Unshare(_) => "unshare",
+        }
+    }
+}
+
+/// Launches Container-domain commands inside the sandbox.
+#[derive(Debug)]
+pub struct Sandbox {
+    config: SandboxConfig,
+    backend: SandboxBackend,
+}
+
+impl Sandbox {
+    pub fn new(config: SandboxConfig, backend: SandboxBackend) -> Self {
+        Self { config, backend }
+    }
+
+    /// Build the sandbox described by `config`.
+    ///
+    /// Returns `None` when sandboxing is off, or in `auto` mode when no
+    /// backend works here. `required` mode without a backend is an error.
+    pub fn from_config(config: SandboxConfig) -> Result<Option<Self>, ConfigError> {
+        if config.mode == SandboxMode::Off || !cfg!(target_os = "linux") {
+            if config.mode == SandboxMode::Required {
+                return Err(ConfigError::InvalidValue {
+                    key: "SANDBOX_MODE".into(),
+                    message: "sandboxing is only supported on Linux".into(),
+                });
+            }
+            return Ok(None);
+        }
+        match SandboxBackend::detect() {
+            Some(backend) => Ok(Some(Self::new(config, backend))),
+            None if config.mode == SandboxMode::Required => Err(ConfigError::InvalidValue {
+                key: "SANDBOX_MODE".into(),
+                message: "no sandbox backend found (install bubblewrap or enable user namespaces)"
+                    .into(),
+            }),
+            None => {
+                tracing::warn!("No sandbox backend available — Container tools run on the host");
+                Ok(None)
+            }
+        }
+    }
+
+    pub fn backend(&self) -> &SandboxBackend {
+        &self.backend
+    }
+
+    pub fn config(&self) -> &SandboxConfig {
+        &self.config
+    }
+
+    /// Scratch directory for a job: one per todo, one per user otherwise.
+    pub fn scratch_dir(&self, ctx: &JobContext) -> PathBuf {
+        let scope = match ctx.todo_id() {
+            Some(todo_id) => format!("todo-{todo_id}"),
+            None => format!("user-{}", sanitize_scope(&ctx.user_id)),
+        };
+        self.config.scratch_root.join(scope)
+    }
+
+    /// Create (if needed) and return the scratch directory for a job.
+    pub async fn prepare_scratch(&self, ctx: &JobContext) -> Result<PathBuf, ToolError> {
+        let scratch = self.scratch_dir(ctx);
+        tokio::fs::create_dir_all(&scratch)
+            .await
+            .map_err(|e| ToolError::exec("Create sandbox scratch dir", e))?;
+        if matches!(self.backend, SandboxBackend::Unshare(_)) {
+            tokio::fs::create_dir_all(self.config.scratch_root.join(NEW_ROOT_DIR))
+                .await
+                .map_err(|e| ToolError::exec("Create sandbox root", e))?;
+        }
+        // Absolute, symlink-free paths: they're used as mount targets.
+        tokio::fs::canonicalize(&scratch)
+            .await
+            .map_err(|e| ToolError::exec("Resolve sandbox scratch dir", e))
+    }
+
+    /// Build the command that runs `cmd` with `sh -c` inside the sandbox,
+    /// starting in `workdir` (the scratch dir or a read-only path).
+    pub fn command(&self, cmd: &str, scratch: &Path, workdir: &Path) -> Command {
+        let program = match &self.backend {
+            SandboxBackend::Bubblewrap(path) | SandboxBackend::Unshare(path) => path,
+        };
+        let mut command = Command::new(program);
+        command.args(self.wrapper_args(scratch, workdir)).arg(cmd);
+        command
+            .env_clear()
+            .env("PATH", SANDBOX_PATH)
+            .env("HOME", scratch)
+            .env("TMPDIR", "/tmp")
+            .env("LANG", "C.UTF-8")
+            .kill_on_drop(true);
+        command
+    }
+
+    /// Wrapper arguments up to (not including) the user command, which is
+    /// passed last and becomes `$0` of the innermost script.
+    fn wrapper_args(&self, scratch: &Path, workdir: &Path) -> Vec<String> {
+        let limits = self.limits_script(workdir);
+        match &self.backend {
+            SandboxBackend::Bubblewrap(_) => {
+                let mut args: Vec<String> = ["--die-with-parent", "--new-session", "--unshare-all"]
+                    .into_iter()
+                    .map(String::from)
+                    .collect();
+                if self.config.network {
+                    args.push("--share-net".into());
+                }
+                for path in &self.config.read_only_paths {
+                    let path = path.display().to_string();
+                    args.extend(["--ro-bind-try".into(), path.clone(), path]);
+                }
+                let scratch = scratch.display().to_string();
+                args.extend(
+                    ["--dev", "/dev", "--proc", "/proc", "--tmpfs", "/tmp", "--bind"]
+                        .into_iter()
+                        .map(String::from),
+                );
+                args.extend([scratch.clone(), scratch]);
+                args.extend(["--".into(), "sh".into(), "-c".into(), limits]);
+                args
+            }
+            SandboxBackend::Unshare(_) => {
+                let mut args: Vec<String> = [
+                    "--user",
+                    "--map-root-user",
+                    "--mount",
+                    "--pid",
+                    "--fork",
+                    "--kill-child",
+                ]
+                .into_iter()
+                .map(String::from)
+                .collect();
+                if !self.config.network {
+                    args.push("--net".into());
+                }
+                let setup = format!("{}\n{limits}", self.mount_script(scratch));
+                args.extend(["sh".into(), "-c".into(), setup]);
+                args
+            }
+        }
+    }
+
+    /// Build a minimal root on tmpfs and `pivot_root` into it (unshare only).
+    fn mount_script(&self, scratch: &Path) -> String {
+        let root = self.config.scratch_root.join(NEW_ROOT_DIR);
+        let mut script = format!(
+            "set -e\nmount --make-rprivate /\nR={}\nmount -t tmpfs sandbox \"$R\"\n",
+            shell_quote(&root.display().to_string())
+        );
+        for path in &self.config.read_only_paths {
+            let p = shell_quote(&path.display().to_string());
+            // Merged-/usr systems have /bin -> usr/bin etc.: recreate the link.
+            script.push_str(&format!(
+                "if [ -L {p} ]; then mkdir -p \"$R\"$(dirname {p}); ln -s \"$(readlink {p})\" \"$R\"{p}; \
+                 elif [ -d {p} ]; then mkdir -p \"$R\"{p}; mount --rbind {p} \"$R\"{p}; \
+                 mount -o remount,bind,ro \"$R\"{p}; fi\n"
+            ));
+        }
+        let s = shell_quote(&scratch.display().to_string());
+        script.push_str(&format!(
+            "mkdir -p \"$R/tmp\" \"$R/dev\" \"$R/proc\"\n\
+             mount -t tmpfs tmp \"$R/tmp\"\n\
+             mkdir -p \"$R\"{s}\n\
+             mount --bind {s} \"$R\"{s}\n\
+             mount --rbind /dev \"$R/dev\"\n\
+             mount -t proc proc \"$R/proc\"\n\
+             cd \"$R\"\nmkdir .old\npivot_root . .old\numount -l /.old\nrmdir /.old\nset +e"
+        ));
+        script
+    }
+
+    /// Enter `workdir`, apply rlimits, then exec the user command (`$0`).
+    fn limits_script(&self, workdir: &Path) -> String {
+        format!(
+            "cd {} && ulimit -t {} && ulimit -v {} && exec sh -c \"$0\"",
+            shell_quote(&workdir.display().to_string()),
+            self.config.cpu_secs,
+            self.config.memory_mb * 1024,
+        )
+    }
+}
+
+/// Single-quote `s` for POSIX sh.
+fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+/// Keep scope names to a safe single path component.
+fn sanitize_scope(raw: &str) -> String {
+    let cleaned: String = raw
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .take(64)
+        .collect();
+    if cleaned.is_empty() { "default".to_string() } else { cleaned }
+}
+
+fn find_on_path(binary: &str) -> Option<PathBuf> {
+    let path = std::env::var_os("PATH")?;
+    std::env::split_paths(&path)
+        .chain(["/usr/sbin", "/sbin"].map(PathBuf::from))
+        .map(|dir| dir.join(binary))
+        .find(|candidate| candidate.is_file())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(root: &Path) -> SandboxConfig {
+        SandboxConfig {
+            mode: SandboxMode::Required,
+            scratch_root: root.to_path_buf(),
+            read_only_paths: vec!["/usr".into(), "/bin".into(), "/lib".into(), "/lib64".into(), "/etc".into()],
+            network: false,
+            cpu_secs: 5,
+            memory_mb: 512,
+        }
+    }
+
+    fn args(sandbox: &Sandbox) -> Vec<String> {
+        sandbox
+            .command("echo hi", Path::new("/scratch/todo-1"), Path::new("/scratch/todo-1"))
+            .as_std()
+            .get_args()
+            .map(|a| a.to_string_lossy().into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn scratch_dir_is_scoped_per_todo_or_user() {
+        let sandbox = Sandbox::new(config(Path::new("/sbx")), SandboxBackend::Unshare("unshare".into()));
+        let todo_id = uuid::Uuid::new_v4();
+        let mut ctx = JobContext::with_user("alice", "chat", "");
+        assert_eq!(sandbox.scratch_dir(&ctx), PathBuf::from("/sbx/user-alice"));
+
+        ctx.user_id = "../../etc".into();
+        assert_eq!(sandbox.scratch_dir(&ctx), PathBuf::from("/sbx/user-______etc"));
+
+        ctx.metadata = serde_json::json!({ "todo_id": todo_id });
+        assert_eq!(sandbox.scratch_dir(&ctx), PathBuf::from(format!("/sbx/todo-{todo_id}")));
+    }
+
+    #[test]
+    fn bubblewrap_isolates_network_by_default() {
+        let mut cfg = config(Path::new("/sbx"));
+        let sandbox = Sandbox::new(cfg.clone(), SandboxBackend::Bubblewrap("bwrap".into()));
+        let args = args(&sandbox);
+        assert!(args.contains(&"--unshare-all".to_string()));
+        assert!(!args.contains(&"--share-net".to_string()));
+        assert!(args.windows(3).any(|w| w == ["--ro-bind-try", "/etc", "/etc"]));
+        assert!(args.windows(3).any(|w| w == ["--bind", "/scratch/todo-1", "/scratch/todo-1"]));
+        assert_eq!(args.last().map(String::as_str), Some("echo hi"));
+        let script = &args[args.len() - 2];
+        assert!(script.contains("ulimit -t 5"));
+        assert!(script.contains("ulimit -v 524288"));
+
+        cfg.network = true;
+        let sandbox = Sandbox::new(cfg, SandboxBackend::Bubblewrap("bwrap".into()));
+        assert!(self::args(&sandbox).contains(&"--share-net".to_string()));
+    }
+
+    #[test]
+    fn unshare_uses_new_namespaces_and_read_only_mounts() {
+        let sandbox = Sandbox::new(config(Path::new("/sbx")), SandboxBackend::Unshare("unshare".into()));
+        let args = args(&sandbox);
+        for flag in ["--user", "--mount", "--pid", "--net", "--kill-child"] {
+            assert!(args.contains(&flag.to_string()), "missing {flag}");
+        }
+        let script = &args[args.len() - 2];
+        assert!(script.contains("R='/sbx/.root'"));
+        assert!(script.contains("mount -o remount,bind,ro \"$R\"'/etc'"));
+        assert!(script.contains("mount --bind '/scratch/todo-1'"));
+        assert!(script.contains("pivot_root"));
+    }
+
+    #[test]
+    fn command_does_not_inherit_host_environment() {
+        let sandbox = Sandbox::new(config(Path::new("/sbx")), SandboxBackend::Unshare("unshare".into()));
+        let command = sandbox.command("env", Path::new("/sbx/user-a"), Path::new("/sbx/user-a"));
+        let envs: Vec<_> = command.as_std().get_envs().collect();
+        assert!(envs.iter().any(|(k, v)| *k == "HOME" && *v == Some("/sbx/user-a".as_ref())));
+        assert!(!envs.iter().any(|(k, _)| *k == "ANTHROPIC_API_KEY"));
+    }
+
+    #[test]
+    fn shell_quote_escapes_single_quotes() {
+        assert_eq!(shell_quote("/tmp/it's"), r"'/tmp/it'\''s'");
+    }
+
+    /// Runs for real only where `unshare` with user namespaces works.
+    #[tokio::test]
+    async fn unshare_sandbox_confines_writes_to_scratch() {
+        let Some(backend @ SandboxBackend::Unshare(_)) = SandboxBackend::detect() else {
+            return;
+        };
+        let dir = tempfile::tempdir().unwrap();
+        let sandbox = Sandbox::new(config(dir.path()), backend);
+        let ctx = JobContext::with_user("tester", "chat", "");
+        let scratch = sandbox.prepare_scratch(&ctx).await.unwrap();
+
+        let output = sandbox
+            .command(
+                "echo hi > note.txt; touch /etc/escape 2>/dev/null || echo ro; \
+                 test -e /root && echo host-root; wc -l < /proc/net/dev",
+                &scratch,
+                &scratch,
+            )
+            .stdin(Stdio::null())
+            .output()
+            .await
+            .unwrap();
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+        assert_eq!(std::fs::read_to_string(scratch.join("note.txt")).unwrap(), "hi\n");
+        assert!(stdout.contains("ro"));
+        assert!(!stdout.contains("host-root"));
+        // Header lines plus loopback only: no host interfaces.
+        assert_eq!(stdout.lines().last().map(str::trim), Some("3"));
+    }
+}
@@ -0,0 +1,898 @@
+//! Shell command tokenizer and parser.
+//!
+//! Understands enough POSIX sh / bash syntax to find every simple command a
+//! command line would run: pipelines, `;`/`&&`/`||`/`&` lists, `( … )`
+//! subshells, `{ … }` groups, function definitions, redirections (including
+//! here-documents), and commands nested in `$( … )`, backticks and `<( … )`.
+//! Control keywords (`if`, `while`, `for`, …) are skipped so the commands
+//! inside them are still seen. Nothing is expanded: words keep `$VAR` and
+//! `$( … )` verbatim and are flagged as dynamic.
+
+use thiserror::Error;
+
+/// Why a command line could not be parsed.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ParseError {
+    #[error("unterminated {0}")]
+    Unterminated(&'static str),
+    #[error("unexpected '{0}'")]
+    Unexpected(String),
+    #[error("unsupported shell construct: {0}")]
+    Unsupported(&'static str),
+}
+
+/// One shell word with quotes removed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Word {
+    /// Text after quote removal; expansions are kept verbatim.
+    pub text: String,
+    /// Contains a parameter, command or arithmetic expansion.
+    pub dynamic: bool,
+}
+
+impl Word {
+    /// `<( … )` or `>( … )`.
+    pub fn is_process_substitution(&self) -> bool {
+        self.text.starts_with("<(") || self.text.starts_with(">(")
+    }
+}
+
+/// A redirection such as `2>&1`, `> out.txt` or `<<EOF`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Redirect {
+    pub fd: Option<u32>,
+    pub op: &'static str,
+    /// File, fd, or here-document delimiter.
+    pub target: Word,
+    /// Here-document body.
+    pub heredoc: Option<String>,
+}
+
+/// A command name with its arguments, prefix assignments and redirections.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SimpleCommand {
+    /// `NAME=value` prefixes.
+    pub assignments: Vec<String>,
+    /// Command name followed by its arguments.
+    pub words: Vec<Word>,
+    pub redirects: Vec<Redirect>,
+    /// Scripts nested in this command's words (`$( … )`, backticks, `<( … )`).
+    pub substitutions: Vec<Script>,
+    /// Stdin comes from the previous stage of a pipeline.
+    pub piped_input: bool,
+}
+
+impl SimpleCommand {
+    /// Command name without its directory (`/usr/bin/rm` → `rm`).
+    pub fn program(&self) -> Option<&str> {
+        let name = &self.words.first()?.text;
+        Some(name.rsplit('/').next().unwrap_or(name))
+    }
+
+    /// Arguments after the command name.
+    pub fn args(&self) -> &[Word] {
+        self.words.get(1..).unwrap_or_default()
+    }
+
+    /// Re-render as a single line for display (heredoc bodies omitted).
+    pub fn render(&self) -> String {
+        let mut parts: Vec<String> = self.assignments.clone();
+        parts.extend(self.words.iter().map(|w| render_word(&w.text)));
+        for r in &self.redirects {
+            let fd = r.fd.map(|fd| fd.to_string()).unwrap_or_default();
+            parts.push(format!("{fd}{}{}", r.op, render_word(&r.target.text)));
+        }
+        parts.join(" ")
+    }
+}
+
+fn render_word(text: &str) -> String {
+    if text.is_empty() || text.contains(char::is_whitespace) {
+        format!("'{}'", text.replace('\'', r"'\''"))
+    } else {
+        text.to_string()
+    }
+}
+
+/// One element of a pipeline.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Simple(SimpleCommand),
+    /// `( … )` or `{ … }`.
+    Group(Script),
+    /// `name() { … }`.
+    Function { name: String, body: Script },
+}
+
+/// Commands joined by `|`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Pipeline {
+    pub commands: Vec<Command>,
+}
+
+/// A list of pipelines (separated by `;`, `&&`, `||`, `&` or newlines).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Script {
+    pub pipelines: Vec<Pipeline>,
+}
+
+impl Script {
+    /// Every simple command in execution-text order, including those nested
+    /// in groups, function bodies and substitutions.
+    pub fn simple_commands(&self) -> Vec<&SimpleCommand> {
+        let mut out = Vec::new();
+        self.collect_commands(&mut out);
+        out
+    }
+
+    fn collect_commands<'a>(&'a self, out: &mut Vec<&'a SimpleCommand>) {
+        for pipeline in &self.pipelines {
+            for command in &pipeline.commands {
+                match command {
+                    Command::Simple(simple) => {
+                        out.push(simple);
+                        for nested in &simple.substitutions {
+                            nested.collect_commands(out);
+                        }
+                    }
+                    Command::Group(script) | Command::Function { body: script, .. } => {
+                        script.collect_commands(out);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Function definitions (`name() { … }`) anywhere in the script.
+    pub fn functions(&self) -> Vec<(&str, &Script)> {
+        let mut out = Vec::new();
+        for pipeline in &self.pipelines {
+            for command in &pipeline.commands {
+                match command {
+                    Command::Function { name, body } => {
+                        out.push((name.as_str(), body));
+                        out.extend(body.functions());
+                    }
+                    Command::Group(script) => out.extend(script.functions()),
+                    Command::Simple(_) => {}
+                }
+            }
+        }
+        out
+    }
+}
+
+/// Parse a command line.
+pub fn parse(input: &str) -> Result<Script, ParseError> {
+    let tokens = Lexer::new(input).tokenize()?;
+    let mut parser = Parser { tokens, pos: 0 };
+    let script = parser.script(None)?;
+    match parser.peek() {
+        None => Ok(script),
+        Some(tok) => Err(ParseError::Unexpected(tok.describe())),
+    }
+}
+
+// ── Lexer ───────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Tok {
+    Word(Word, Vec<Script>),
+    Op(&'static str),
+    Redir(Redirect, Vec<Script>),
+    Newline,
+}
+
+impl Tok {
+    fn describe(&self) -> String {
+        match self {
+            Tok::Word(w, _) => w.text.clone(),
+            Tok::Op(op) => op.to_string(),
+            Tok::Redir(r, _) => r.op.to_string(),
+            Tok::Newline => "newline".to_string(),
+        }
+    }
+}
+
+/// Control operators, longest first.
+const OPERATORS: &[&str] = &["&&", "||", ";;", "|&", "|", ";", "&", "(", ")"];
+
+/// Redirection operators, longest first.
+const REDIRECTS: &[&str] = &[
+    "&>>", "<<<", "<<-", "&>", ">>", ">&", "<&", "<<", "<>", ">|", ">", "<",
+];
+
+struct Lexer {
+    chars: Vec<char>,
+    pos: usize,
+    tokens: Vec<Tok>,
+    /// Token indexes of here-documents waiting for their body.
+    pending_heredocs: Vec<usize>,
+}
+
+impl Lexer {
+    fn new(input: &str) -> Self {
+        Self {
+            chars: input.chars().collect(),
+            pos: 0,
+            tokens: Vec::new(),
+            pending_heredocs: Vec::new(),
+        }
+    }
+
+    fn peek_char(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn starts_with(&self, s: &str) -> bool {
+        s.chars()
+            .enumerate()
+            .all(|(i, c)| self.chars.get(self.pos + i) == Some(&c))
+    }
+
+    fn tokenize(mut self) -> Result<Vec<Tok>, ParseError> {
+        loop {
+            self.skip_blanks();
+            let Some(c) = self.peek_char() else { break };
+            if c == '#' {
+                while self.peek_char().is_some_and(|c| c != '\n') {
+                    self.pos += 1;
+                }
+                continue;
+            }
+            if c == '\n' {
+                self.pos += 1;
+                self.tokens.push(Tok::Newline);
+                self.read_heredoc_bodies()?;
+                continue;
+            }
+            if (c == '<' || c == '>') && self.chars.get(self.pos + 1) == Some(&'(') {
+                let (word, nested) = self.word()?;
+                self.tokens.push(Tok::Word(word, nested));
+                continue;
+            }
+            if let Some(op) = REDIRECTS.iter().find(|op| self.starts_with(op)) {
+                self.pos += op.len();
+                self.redirect(None, op)?;
+                continue;
+            }
+            if let Some(op) = OPERATORS.iter().find(|op| self.starts_with(op)) {
+                self.pos += op.len();
+                self.tokens.push(Tok::Op(op));
+                continue;
+            }
+            // `2>file`, `2>&1`: a leading fd number glued to a redirection.
+            let digits = self.chars[self.pos..]
+                .iter()
+                .take_while(|c| c.is_ascii_digit())
+                .count();
+            if digits > 0 {
+                let after = self.pos + digits;
+                let saved = self.pos;
+                self.pos = after;
+                if let Some(op) = REDIRECTS.iter().find(|op| self.starts_with(op)) {
+                    let fd = self.chars[saved..after].iter().collect::<String>().parse().ok();
+                    self.pos += op.len();
+                    self.redirect(fd, op)?;
+                    continue;
+                }
+                self.pos = saved;
+            }
+            let (word, nested) = self.word()?;
+            self.tokens.push(Tok::Word(word, nested));
+        }
+        if !self.pending_heredocs.is_empty() {
+            return Err(ParseError::Unterminated("here-document"));
+        }
+        Ok(self.tokens)
+    }
+
+    fn skip_blanks(&mut self) {
+        loop {
+            match self.peek_char() {
+                Some(' ' | '\t' | '\r') => self.pos += 1,
+                Some('\\') if self.chars.get(self.pos + 1) == Some(&'\n') => self.pos += 2,
+                _ => break,
+            }
+        }
+    }
+
+    fn redirect(&mut self, fd: Option<u32>, op: &'static str) -> Result<(), ParseError> {
+        self.skip_blanks();
+        if self.peek_char().is_none_or(|c| c == '\n' || is_operator_start(c)) {
+            return Err(ParseError::Unterminated("redirection"));
+        }
+        let (target, nested) = self.word()?;
+        let is_heredoc = op == "<<" || op == "<<-";
+        self.tokens.push(Tok::Redir(
+            Redirect {
+                fd,
+                op,
+                target,
+                heredoc: None,
+            },
+            nested,
+        ));
+        if is_heredoc {
+            self.pending_heredocs.push(self.tokens.len() - 1);
+        }
+        Ok(())
+    }
+
+    /// Consume here-document bodies that start after a newline.
+    fn read_heredoc_bodies(&mut self) -> Result<(), ParseError> {
+        for index in std::mem::take(&mut self.pending_heredocs) {
+            let Tok::Redir(redirect, _) = &self.tokens[index] else {
+                continue;
+            };
+            let strip_tabs = redirect.op == "<<-";
+            let delimiter = redirect.target.text.clone();
+            let mut body = String::new();
+            loop {
+                if self.pos >= self.chars.len() {
+                    return Err(ParseError::Unterminated("here-document"));
+                }
+                let start = self.pos;
+                while self.peek_char().is_some_and(|c| c != '\n') {
+                    self.pos += 1;
+                }
+                let line: String = self.chars[start..self.pos].iter().collect();
+                if self.peek_char() == Some('\n') {
+                    self.pos += 1;
+                }
+                let line = if strip_tabs { line.trim_start_matches('\t') } else { &line };
+                if line == delimiter {
+                    break;
+                }
+                body.push_str(line);
+                body.push('\n');
+            }
+            if let Tok::Redir(redirect, _) = &mut self.tokens[index] {
+                redirect.heredoc = Some(body);
+            }
+        }
+        Ok(())
+    }
+
+    /// Lex one word, removing quotes and parsing nested command substitutions.
+    fn word(&mut self) -> Result<(Word, Vec<Script>), ParseError> {
+        let mut text = String::new();
+        let mut dynamic = false;
+        let mut nested = Vec::new();
+
+        // Process substitution only at the start of a word.
+        if let Some(open @ ('<' | '>')) = self.peek_char()
+            && self.chars.get(self.pos + 1) == Some(&'(')
+        {
+            self.pos += 2;
+            let inner = self.balanced_parens()?;
+            nested.push(parse(&inner)?);
+            text.push(open);
+            text.push('(');
+            text.push_str(&inner);
+            text.push(')');
+            dynamic = true;
+        }
+
+        while let Some(c) = self.peek_char() {
+            match c {
+                ' ' | '\t' | '\r' | '\n' => break,
+                c if is_operator_start(c) => break,
+                '\\' => {
+                    self.pos += 1;
+                    match self.peek_char() {
+                        Some('\n') => self.pos += 1,
+                        Some(escaped) => {
+                            text.push(escaped);
+                            self.pos += 1;
+                        }
+                        None => text.push('\\'),
+                    }
+                }
+                '\'' => {
+                    self.pos += 1;
+                    let start = self.pos;
+                    while self.peek_char().is_some_and(|c| c != '\'') {
+                        self.pos += 1;
+                    }
+                    if self.peek_char().is_none() {
+                        return Err(ParseError::Unterminated("single quote"));
+                    }
+                    text.extend(&self.chars[start..self.pos]);
+                    self.pos += 1;
+                }
+                '"' => {
+                    self.pos += 1;
+                    loop {
+                        match self.peek_char() {
+                            None => return Err(ParseError::Unterminated("double quote")),
+                            Some('"') => {
+                                self.pos += 1;
+                                break;
+                            }
+                            Some('\\') => {
+                                self.pos += 1;
+                                match self.peek_char() {
+                                    Some(e @ ('$' | '`' | '"' | '\\')) => {
+                                        text.push(e);
+                                        self.pos += 1;
+                                    }
+                                    Some('\n') => self.pos += 1,
+                                    _ => text.push('\\'),
+                                }
+                            }
+                            Some('$' | '`') => {
+                                dynamic |= self.expansion(&mut text, &mut nested)?;
+                            }
+                            Some(other) => {
+                                text.push(other);
+                                self.pos += 1;
+                            }
+                        }
+                    }
+                }
+                '$' | '`' => {
+                    dynamic |= self.expansion(&mut text, &mut nested)?;
+                }
+                other => {
+                    text.push(other);
+                    self.pos += 1;
+                }
+            }
+        }
+        Ok((Word { text, dynamic }, nested))
+    }
+
+    /// Lex an expansion starting at `$` or a backtick, appending its raw text.
+    /// Returns whether it is dynamic (a lone `$` is literal).
+    fn expansion(&mut self, text: &mut String, nested: &mut Vec<Script>) -> Result<bool, ParseError> {
+        if self.peek_char() == Some('`') {
+            self.pos += 1;
+            let start = self.pos;
+            while let Some(c) = self.peek_char() {
+                match c {
+                    '\\' => self.pos += 2,
+                    '`' => break,
+                    _ => self.pos += 1,
+                }
+            }
+            if self.peek_char() != Some('`') {
+                return Err(ParseError::Unterminated("backtick"));
+            }
+            let inner: String = self.chars[start..self.pos].iter().collect();
+            self.pos += 1;
+            nested.push(parse(&inner)?);
+            text.push('`');
+            text.push_str(&inner);
+            text.push('`');
+            return Ok(true);
+        }
+
+        // At '$'.
+        self.pos += 1;
+        if self.starts_with("((") {
+            self.pos += 1;
+            let inner = self.balanced_parens()?;
+            text.push_str(&format!("$({inner})"));
+            return Ok(true);
+        }
+        match self.peek_char() {
+            Some('(') => {
+                self.pos += 1;
+                let inner = self.balanced_parens()?;
+                nested.push(parse(&inner)?);
+                text.push_str(&format!("$({inner})"));
+                Ok(true)
+            }
+            Some('{') => {
+                let start = self.pos;
+                while self.peek_char().is_some_and(|c| c != '}') {
+                    self.pos += 1;
+                }
+                if self.peek_char().is_none() {
+                    return Err(ParseError::Unterminated("${"));
+                }
+                self.pos += 1;
+                text.push('$');
+                text.extend(&self.chars[start..self.pos]);
+                Ok(true)
+            }
+            Some('\'') => {
+                // ANSI-C quoting: literal for our purposes.
+                self.pos += 1;
+                let start = self.pos;
+                while let Some(c) = self.peek_char() {
+                    match c {
+                        '\\' => self.pos += 2,
+                        '\'' => break,
+                        _ => self.pos += 1,
+                    }
+                }
+                if self.peek_char() != Some('\'') {
+                    return Err(ParseError::Unterminated("$'"));
+                }
+                text.extend(&self.chars[start..self.pos]);
+                self.pos += 1;
+                Ok(false)
+            }
+            Some(c) if c.is_ascii_alphanumeric() || "_@*#?$!-".contains(c) => {
+                let start = self.pos;
+                if c.is_ascii_alphabetic() || c == '_' {
+                    while self
+                        .peek_char()
+                        .is_some_and(|c| c.is_ascii_alphanumeric() || c == '_')
+                    {
+                        self.pos += 1;
+                    }
+                } else {
+                    self.pos += 1;
+                }
+                text.push('$');
+                text.extend(&self.chars[start..self.pos]);
+                Ok(true)
+            }
+            _ => {
+                text.push('$');
+                Ok(false)
+            }
+        }
+    }
+
+    /// Consume up to the `)` matching an already-consumed `(`, returning the
+    /// text in between. Quotes are respected.
+    fn balanced_parens(&mut self) -> Result<String, ParseError> {
+        let start = self.pos;
+        let mut depth = 1;
+        while let Some(c) = self.peek_char() {
+            match c {
+                '\\' => self.pos += 1,
+                '\'' => {
+                    self.pos += 1;
+                    while self.peek_char().is_some_and(|c| c != '\'') {
+                        self.pos += 1;
+                    }
+                }
+                '"' => {
+                    self.pos += 1;
+                    while let Some(c) = self.peek_char() {
+                        match c {
+                            '\\' => self.pos += 1,
+                            '"' => break,
+                            _ => {}
+                        }
+                        self.pos += 1;
+                    }
+                }
+                '(' => depth += 1,
+                ')' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        let inner = self.chars[start..self.pos].iter().collect();
+                        self.pos += 1;
+                        return Ok(inner);
+                    }
+                }
+                _ => {}
+            }
+            self.pos += 1;
+        }
+        Err(ParseError::Unterminated("parenthesis"))
+    }
+}
+
+fn is_operator_start(c: char) -> bool {
+    matches!(c, '|' | '&' | ';' | '(' | ')' | '<' | '>')
+}
+
+// ── Parser ──────────────────────────────────────────────────────────
+
+/// Reserved words skipped at command position.
+const KEYWORDS: &[&str] = &[
+    "if", "then", "elif", "else", "fi", "do", "done", "while", "until", "!", "time", "{", "}",
+];
+
+struct Parser {
+    tokens: Vec<Tok>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Tok> {
+        self.tokens.get(self.pos)
+    }
+
+    fn at_word(&self, text: &str) -> bool {
+        matches!(self.peek(), Some(Tok::Word(w, _)) if w.text == text && !w.dynamic)
+    }
+
+    fn skip_newlines(&mut self) {
+        while matches!(self.peek(), Some(Tok::Newline)) {
+            self.pos += 1;
+        }
+    }
+
+    /// Parse pipelines until end of input or the closing `)` / `}`.
+    fn script(&mut self, close: Option<&str>) -> Result<Script, ParseError> {
+        let mut script = Script::default();
+        loop {
+            while matches!(self.peek(), Some(Tok::Newline | Tok::Op(";" | "&" | ";;"))) {
+                self.pos += 1;
+            }
+            match (self.peek(), close) {
+                (None, _) => break,
+                (Some(Tok::Op(")")), Some(")")) => break,
+                (Some(Tok::Word(w, _)), Some("}")) if w.text == "}" => break,
+                _ => {}
+            }
+            let pipeline = self.pipeline(close)?;
+            if !pipeline.commands.is_empty() {
+                script.pipelines.push(pipeline);
+            }
+            match self.peek() {
+                Some(Tok::Op("&&" | "||")) => {
+                    self.pos += 1;
+                    self.skip_newlines();
+                }
+                Some(Tok::Op(")")) if close != Some(")") => {
+                    return Err(ParseError::Unexpected(")".into()));
+                }
+                _ => {}
+            }
+        }
+        Ok(script)
+    }
+
+    fn pipeline(&mut self, close: Option<&str>) -> Result<Pipeline, ParseError> {
+        let mut pipeline = Pipeline::default();
+        let mut piped = false;
+        loop {
+            if let Some(command) = self.command(piped, close)? {
+                pipeline.commands.push(command);
+            }
+            if matches!(self.peek(), Some(Tok::Op("|" | "|&"))) {
+                self.pos += 1;
+                self.skip_newlines();
+                piped = true;
+            } else {
+                return Ok(pipeline);
+            }
+        }
+    }
+
+    fn command(&mut self, piped: bool, close: Option<&str>) -> Result<Option<Command>, ParseError> {
+        if matches!(self.peek(), Some(Tok::Op("("))) {
+            self.pos += 1;
+            let inner = self.script(Some(")"))?;
+            if !matches!(self.peek(), Some(Tok::Op(")"))) {
+                return Err(ParseError::Unterminated("subshell"));
+            }
+            self.pos += 1;
+            // Redirections on the group apply to the whole group.
+            while matches!(self.peek(), Some(Tok::Redir(..))) {
+                self.pos += 1;
+            }
+            return Ok(Some(Command::Group(inner)));
+        }
+
+        let mut simple = SimpleCommand {
+            piped_input: piped,
+            ..Default::default()
+        };
+        while let Some(tok) = self.peek().cloned() {
+            match tok {
+                Tok::Word(word, nested) => {
+                    if simple.words.is_empty() && !word.dynamic {
+                        if close == Some("}") && word.text == "}" {
+                            break;
+                        }
+                        if word.text == "case" {
+                            return Err(ParseError::Unsupported("case"));
+                        }
+                        if word.text == "for" || word.text == "select" {
+                            // Loop header: the word list isn't a command.
+                            while !matches!(self.peek(), None | Some(Tok::Newline | Tok::Op(";"))) {
+                                self.pos += 1;
+                            }
+                            continue;
+                        }
+                        if word.text == "function" {
+                            self.pos += 1;
+                            let Some(Tok::Word(name, _)) = self.peek().cloned() else {
+                                return Err(ParseError::Unexpected("function".into()));
+                            };
+                            self.pos += 1;
+                            if matches!(self.peek(), Some(Tok::Op("("))) {
+                                self.expect_op("(")?;
+                                self.expect_op(")")?;
+                            }
+                            return self.function_body(name.text).map(Some);
+                        }
+                        if matches!(self.tokens.get(self.pos + 1), Some(Tok::Op("(")))
+                            && matches!(self.tokens.get(self.pos + 2), Some(Tok::Op(")")))
+                        {
+                            self.pos += 3;
+                            return self.function_body(word.text).map(Some);
+                        }
+                        if simple.assignments.is_empty() && KEYWORDS.contains(&word.text.as_str()) {
+                            self.pos += 1;
+                            continue;
+                        }
+                        if is_assignment(&word.text) {
+                            simple.assignments.push(word.text);
+                            simple.substitutions.extend(nested);
+                            self.pos += 1;
+                            continue;
+                        }
+                    }
+                    simple.words.push(word);
+                    simple.substitutions.extend(nested);
+                    self.pos += 1;
+                }
+                Tok::Redir(redirect, nested) => {
+                    simple.redirects.push(redirect);
+                    simple.substitutions.extend(nested);
+                    self.pos += 1;
+                }
+                Tok::Op("(") => return Err(ParseError::Unexpected("(".into())),
+                Tok::Op(_) | Tok::Newline => break,
+            }
+        }
+
+        let empty = simple.words.is_empty()
+            && simple.assignments.is_empty()
+            && simple.redirects.is_empty();
+        Ok((!empty).then_some(Command::Simple(simple)))
+    }
+
+    fn function_body(&mut self, name: String) -> Result<Command, ParseError> {
+        self.skip_newlines();
+        let body = if matches!(self.peek(), Some(Tok::Op("("))) {
+            self.pos += 1;
+            let body = self.script(Some(")"))?;
+            self.expect_op(")")?;
+            body
+        } else if self.at_word("{") {
+            self.pos += 1;
+            let body = self.script(Some("}"))?;
+            if !self.at_word("}") {
+                return Err(ParseError::Unterminated("function body"));
+            }
+            self.pos += 1;
+            body
+        } else {
+            return Err(ParseError::Unexpected(name));
+        };
+        Ok(Command::Function { name, body })
+    }
+
+    fn expect_op(&mut self, op: &'static str) -> Result<(), ParseError> {
+        match self.peek() {
+            Some(Tok::Op(found)) if *found == op => {
+                self.pos += 1;
+                Ok(())
+            }
+            Some(tok) => Err(ParseError::Unexpected(tok.describe())),
+            None => Err(ParseError::Unterminated("command")),
+        }
+    }
+}
+
+fn is_assignment(text: &str) -> bool {
+    match text.split_once('=') {
+        Some((name, _)) => {
+            !name.is_empty()
+                && !name.starts_with(|c: char| c.is_ascii_digit())
+                && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        }
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn literal(text: &str) -> Word {
+        Word {
+            text: text.to_string(),
+            dynamic: false,
+        }
+    }
+
+    fn programs(input: &str) -> Vec<String> {
+        parse(input)
+            .unwrap()
+            .simple_commands()
+            .iter()
+            .filter_map(|c| c.program().map(String::from))
+            .collect()
+    }
+
+    #[test]
+    fn splits_lists_and_pipelines() {
+        assert_eq!(
+            programs("cd src && cargo build || echo failed; ls | wc -l & sleep 1"),
+            ["cd", "cargo", "echo", "ls", "wc", "sleep"]
+        );
+        let script = parse("curl -s x | sh").unwrap();
+        let commands = script.simple_commands();
+        assert!(!commands[0].piped_input);
+        assert!(commands[1].piped_input);
+    }
+
+    #[test]
+    fn removes_quotes_and_keeps_escapes() {
+        let script = parse(r#"echo 'a b' "c $HOME" d\ e"#).unwrap();
+        let words = &script.simple_commands()[0].words;
+        assert_eq!(words[1], literal("a b"));
+        assert_eq!(words[2].text, "c $HOME");
+        assert!(words[2].dynamic);
+        assert_eq!(words[3], literal("d e"));
+    }
+
+    #[test]
+    fn separates_assignments_and_redirections() {
+        let script = parse("FOO=bar RUST_LOG=debug cargo test 2>&1 > out.log").unwrap();
+        let cmd = script.simple_commands()[0];
+        assert_eq!(cmd.assignments, ["FOO=bar", "RUST_LOG=debug"]);
+        assert_eq!(cmd.program(), Some("cargo"));
+        assert_eq!(cmd.redirects.len(), 2);
+        assert_eq!(cmd.redirects[0].fd, Some(2));
+        assert_eq!(cmd.redirects[0].op, ">&");
+        assert_eq!(cmd.redirects[1].target.text, "out.log");
+        assert_eq!(cmd.render(), "FOO=bar RUST_LOG=debug cargo test 2>&1 >out.log");
+    }
+
+    #[test]
+    fn finds_nested_commands() {
+        assert_eq!(programs("echo $(whoami) `date`"), ["echo", "whoami", "date"]);
+        assert_eq!(programs("bash <(curl -s https://x.sh)"), ["bash", "curl"]);
+        assert_eq!(programs("(cd /tmp && rm -r build)"), ["cd", "rm"]);
+        assert_eq!(programs("{ make; make install; }"), ["make", "make"]);
+        let script = parse("bash <(curl -s https://x.sh)").unwrap();
+        assert!(script.simple_commands()[0].args()[0].is_process_substitution());
+    }
+
+    #[test]
+    fn skips_control_keywords() {
+        assert_eq!(
+            programs("if test -f x; then rm x; else touch x; fi"),
+            ["test", "rm", "touch"]
+        );
+        assert_eq!(
+            programs("for f in *.log; do gzip \"$f\"; done"),
+            ["gzip"]
+        );
+        assert_eq!(programs("while read l; do echo $l; done < in.txt"), ["read", "echo"]);
+    }
+
+    #[test]
+    fn parses_function_definitions() {
+        let script = parse(":(){ :|:& };:").unwrap();
+        let functions = script.functions();
+        assert_eq!(functions.len(), 1);
+        assert_eq!(functions[0].0, ":");
+        assert_eq!(programs(":(){ :|:& };:"), [":", ":", ":"]);
+        assert_eq!(programs("function greet { echo hi; }; greet"), ["echo", "greet"]);
+    }
+
+    #[test]
+    fn reads_heredoc_bodies() {
+        let script = parse("psql <<SQL\nDROP TABLE users;\nSQL\necho done").unwrap();
+        let commands = script.simple_commands();
+        assert_eq!(commands[0].redirects[0].heredoc.as_deref(), Some("DROP TABLE users;\n"));
+        assert_eq!(commands[1].program(), Some("echo"));
+    }
+
+    #[test]
+    fn reports_malformed_input() {
+        assert_eq!(parse("echo 'oops"), Err(ParseError::Unterminated("single quote")));
+        assert_eq!(parse("echo $(date"), Err(ParseError::Unterminated("parenthesis")));
+        assert_eq!(parse("cat <<EOF\nno end"), Err(ParseError::Unterminated("here-document")));
+        assert!(matches!(parse("echo )"), Err(ParseError::Unexpected(_))));
+        assert_eq!(
+            parse("case $x in a) echo;; esac"),
+            Err(ParseError::Unsupported("case"))
+        );
+    }
+}
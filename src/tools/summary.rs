@@ -16,6 +16,9 @@ pub struct ToolSummary {
     pub headline: String,
     /// Raw parameters JSON string for `action_detail` on cards.
     pub raw_params: String,
+    /// Per-step breakdown (e.g. one line per command in a shell pipeline).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub details: Vec<String>,
 }
 
 impl ToolSummary {
@@ -31,9 +34,25 @@ impl ToolSummary {
             target: target.into(),
             headline: headline.into(),
             raw_params: raw_params.into(),
+            details: Vec::new(),
         }
     }
 
+    /// Attach a per-step breakdown.
+    pub fn with_details(mut self, details: Vec<String>) -> Self {
+        self.details = details;
+        self
+    }
+
+    /// Text for a card's `action_detail`: the breakdown, then the raw params.
+    pub fn action_detail(&self) -> String {
+        if self.details.is_empty() {
+            return self.raw_params.clone();
+        }
+        let lines: Vec<String> = self.details.iter().map(|d| format!("• {d}")).collect();
+        format!("{}\n\n{}", lines.join("\n"), self.raw_params)
+    }
+
     /// Fallback summary when a tool doesn't override `summarize()`.
     pub fn fallback(tool_name: &str, params: &serde_json::Value) -> Self {
         let raw = serde_json::to_string_pretty(params).unwrap_or_default();
@@ -42,6 +61,7 @@ impl ToolSummary {
             target: tool_name.into(),
             headline: format!("Execute tool: {}", tool_name),
             raw_params: raw,
+            details: Vec::new(),
         }
     }
 }
@@ -69,6 +89,14 @@ mod tests {
         assert!(s.raw_params.contains("key"));
     }
 
+    #[test]
+    fn action_detail_lists_breakdown_before_params() {
+        let s = ToolSummary::new("Run", "make", "Run: make", "{}");
+        assert_eq!(s.action_detail(), "{}");
+        let s = s.with_details(vec!["make".into(), "make install".into()]);
+        assert_eq!(s.action_detail(), "• make\n• make install\n\n{}");
+    }
+
     #[test]
     fn summary_serde_roundtrip() {
         let s = ToolSummary::new("Read", "file.txt", "Read file.txt", "{}");
@@ -166,6 +166,12 @@ pub trait Tool: Send + Sync {
         false
     }
 
+    /// Whether this particular invocation needs approval even when the user
+    /// has auto-approved the tool for the session (e.g. destructive commands).
+    fn requires_explicit_approval(&self, _params: &serde_json::Value) -> bool {
+        false
+    }
+
     /// Maximum time this tool is allowed to run.
     fn execution_timeout(&self) -> Duration {
         Duration::from_secs(60)
@@ -47,8 +47,12 @@ impl LlmProvider for StubLlm {
             content: r#"[{"text": "stub reply", "confidence": 0.9}]"#.to_string(),
             input_tokens: 0,
             output_tokens: 0,
+            cache_read_tokens: 0,
+            cache_write_tokens: 0,
             finish_reason: FinishReason::Stop,
             response_id: None,
+            provider: "mock".into(),
+            model: self.model_name().into(),
         })
     }
     async fn complete_with_tools(
@@ -76,7 +80,7 @@ async fn start_server() -> (u16, Arc<CardQueue>, TodoApprovalRegistry) {
     let (todo_tx, _todo_rx) = tokio::sync::broadcast::channel::<ai_assist::todos::model::TodoWsMessage>(16);
     let app = card_routes(
         Arc::clone(&queue),
-        None,
+        ai_assist::channels::email::EmailAccounts::default(),
         reply_drafter,
         registry.clone(),
         activity_tx,
@@ -84,6 +88,10 @@ async fn start_server() -> (u16, Arc<CardQueue>, TodoApprovalRegistry) {
         db,
         todo_tx,
         None,
+        None,
+        None,
+        None,
+        false,
     );
 
     let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();